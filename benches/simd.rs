@@ -0,0 +1,46 @@
+//! Criterion benchmarks for the SIMD kernels against the scalar loops, on layers of Hadamards,
+//! rotations and CNOT ladders of 12 to 20 qubits.
+//!
+//! Run them with `cargo bench --bench simd`; the two variants of each width are named after the
+//! instruction set they ran with, e.g. `avx2+fma/16` and `scalar/16`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use quantum_simulator::circuit::Circuit;
+use quantum_simulator::qubit::Qubit;
+use quantum_simulator::simd;
+use quantum_simulator::simulator::Simulator;
+
+/// Register widths the kernels are run at.
+const QUBIT_COUNTS: [usize; 3] = [12, 16, 20];
+
+/// Layers of the benchmarked circuits.
+const CIRCUIT_DEPTH: usize = 4;
+
+/// Runs layers of Hadamards and CNOT ladders with the vectorized kernels and with the scalar
+/// loops, to measure what the SIMD kernels gain on this machine.
+fn simd_kernels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simd_kernels");
+    for num_qubits in QUBIT_COUNTS {
+        group.sample_size(if num_qubits >= 16 { 10 } else { 100 });
+        let mut circuit = Circuit::new(num_qubits);
+        for _ in 0..CIRCUIT_DEPTH {
+            for qubit in 0..num_qubits {
+                circuit.h(qubit).ry(qubit, 0.3);
+            }
+            for qubit in 1..num_qubits {
+                circuit.cnot(qubit - 1, qubit);
+            }
+        }
+        let initial_state = Qubit::zero_state(num_qubits).state;
+        for enabled in [true, false] {
+            simd::set_enabled(enabled);
+            let id = format!("{}/{}", simd::active(), num_qubits);
+            group.bench_function(id, |b| b.iter(|| Simulator::run(&circuit, &initial_state)));
+        }
+        simd::set_enabled(true);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, simd_kernels);
+criterion_main!(benches);
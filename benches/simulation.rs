@@ -0,0 +1,102 @@
+//! Criterion benchmarks for gate application, full-circuit runs and sampling on 2 to 24 qubits.
+//! The SIMD kernels are benchmarked against the scalar loops in `simd.rs`.
+//!
+//! Run them with `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline main` before a backend change and
+//! `cargo bench -- --baseline main` after it.
+
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkGroup, Criterion};
+use quantum_simulator::backend::{Backend, CpuBackend};
+use quantum_simulator::benchmarking::random_clifford_circuit;
+use quantum_simulator::circuit::Circuit;
+use quantum_simulator::gates::hadamard;
+use quantum_simulator::precision::F32Backend;
+use quantum_simulator::qubit::Qubit;
+use quantum_simulator::simulator::Simulator;
+use quantum_simulator::stabilizer::StabilizerBackend;
+
+/// Register widths every benchmark is run at.
+const QUBIT_COUNTS: [usize; 7] = [2, 4, 8, 12, 16, 20, 24];
+
+/// Layers of the random Clifford circuits run end to end.
+const CIRCUIT_DEPTH: usize = 4;
+
+/// Shots drawn per sampling benchmark.
+const SHOTS: usize = 1024;
+
+/// Lowers the sample count for wide registers, where one iteration takes tens of milliseconds.
+fn configure(group: &mut BenchmarkGroup<'_, WallTime>, num_qubits: usize) {
+    group.sample_size(if num_qubits >= 16 { 10 } else { 100 });
+}
+
+fn bench_gate<B: Backend>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    num_qubits: usize,
+    mut backend: B,
+) {
+    let target = num_qubits / 2;
+    let gate = hadamard(1);
+    let id = format!("{}/{}", backend.name(), num_qubits);
+    group.bench_function(id, |b| {
+        b.iter(|| backend.apply_gate(&gate, &[target]).unwrap())
+    });
+}
+
+fn bench_run<B: Backend>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    circuit: &Circuit,
+    new_backend: impl Fn() -> B,
+) {
+    let id = format!("{}/{}", new_backend().name(), circuit.num_qubits());
+    group.bench_function(id, |b| {
+        b.iter_batched(
+            &new_backend,
+            |mut backend| backend.run(circuit).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn gate_application(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gate_application");
+    for num_qubits in QUBIT_COUNTS {
+        configure(&mut group, num_qubits);
+        bench_gate(&mut group, num_qubits, CpuBackend::new(num_qubits));
+        bench_gate(&mut group, num_qubits, F32Backend::new(num_qubits));
+        bench_gate(&mut group, num_qubits, StabilizerBackend::new(num_qubits));
+    }
+    group.finish();
+}
+
+fn circuit_run(c: &mut Criterion) {
+    let mut group = c.benchmark_group("circuit_run");
+    for num_qubits in QUBIT_COUNTS {
+        configure(&mut group, num_qubits);
+        let circuit = random_clifford_circuit(num_qubits, CIRCUIT_DEPTH, 7);
+        bench_run(&mut group, &circuit, || CpuBackend::new(num_qubits));
+        bench_run(&mut group, &circuit, || F32Backend::new(num_qubits));
+        bench_run(&mut group, &circuit, || StabilizerBackend::new(num_qubits));
+    }
+    group.finish();
+}
+
+fn sampling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sampling");
+    for num_qubits in QUBIT_COUNTS {
+        configure(&mut group, num_qubits);
+        let mut ghz = Circuit::new(num_qubits);
+        ghz.h(0);
+        for qubit in 1..num_qubits {
+            ghz.cnot(0, qubit);
+        }
+        let initial_state = Qubit::zero_state(num_qubits).state;
+        group.bench_function(num_qubits.to_string(), |b| {
+            b.iter(|| Simulator::sample(&ghz, &initial_state, SHOTS))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, gate_application, circuit_run, sampling);
+criterion_main!(benches);
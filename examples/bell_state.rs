@@ -0,0 +1,25 @@
+//! Prepares the Bell state (|00⟩ + |11⟩)/√2 and samples it.
+//!
+//! Run with `cargo run --example bell_state`.
+
+use quantum_simulator::analysis::entanglement_entropy;
+use quantum_simulator::prelude::*;
+
+fn main() {
+    let mut circuit = Circuit::new(2);
+    circuit.h(0).cnot(0, 1);
+
+    let initial_state = Qubit::zero_state(2).state;
+    let state = Simulator::run(&circuit, &initial_state);
+    println!("Amplitudes: {:?}", state.state);
+
+    let counts = state.sample(1000);
+    for (bitstring, count) in counts.iter() {
+        println!("{}: {}", bitstring, count);
+    }
+    assert_eq!(counts.get("00") + counts.get("11"), 1000);
+
+    let entropy = entanglement_entropy(&state, &[0]);
+    println!("Entanglement entropy of qubit 0: {:.3} bits", entropy);
+    assert!((entropy - 1.0).abs() < 1e-9);
+}
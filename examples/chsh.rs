@@ -0,0 +1,44 @@
+//! Violates the CHSH Bell inequality with a Bell pair, computing the correlations exactly and
+//! estimating them from shots.
+//!
+//! Run with `cargo run --example chsh`.
+
+use quantum_simulator::algorithms::{chsh, chsh_value, CHSH_ANGLES};
+use quantum_simulator::analysis::{correlation, correlation_from_counts};
+use quantum_simulator::noise::NoiseModel;
+use quantum_simulator::prelude::*;
+
+fn main() {
+    let mut bell = Circuit::new(2);
+    bell.h(0).cnot(0, 1);
+    let state = Simulator::run(&bell, &Qubit::zero_state(2).state);
+
+    let (a, a_prime, b, b_prime) = CHSH_ANGLES;
+    for (name_a, angle_a) in [("a", a), ("a'", a_prime)] {
+        for (name_b, angle_b) in [("b", b), ("b'", b_prime)] {
+            println!(
+                "E({}, {}) = {:+.4}",
+                name_a,
+                name_b,
+                correlation(&state, (0, 1), angle_a, angle_b)
+            );
+        }
+    }
+
+    let exact = chsh_value(|a, b| correlation(&state, (0, 1), a, b));
+    println!("S (exact):       {:.4}", exact);
+    assert!((exact - 2.0 * 2.0_f64.sqrt()).abs() < 1e-9);
+
+    let initial_state = Qubit::zero_state(2).state;
+    let sampled = chsh_value(|a, b| {
+        let counts =
+            Simulator::sample_trajectories(&chsh(a, b), &initial_state, &NoiseModel::new(), 4000);
+        correlation_from_counts(&counts, (0, 1))
+    });
+    println!("S (4000 shots):  {:.4}", sampled);
+    println!(
+        "Classical bound: 2, Tsirelson's bound: {:.4}",
+        2.0 * 2.0_f64.sqrt()
+    );
+    assert!(sampled > 2.0);
+}
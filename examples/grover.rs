@@ -0,0 +1,22 @@
+//! Searches four qubits for the marked bitstring `1011` with Grover's algorithm.
+//!
+//! Run with `cargo run --example grover`.
+
+use quantum_simulator::algorithms::{Grover, Oracle};
+use quantum_simulator::prelude::*;
+
+fn main() {
+    let grover = Grover::new(Oracle::from_bitstrings(4, &["1011"]));
+    println!("Optimal iterations: {}", grover.iterations());
+    for (iterations, probability) in grover.success_probabilities().iter().enumerate() {
+        println!("  after {} iterations: {:.4}", iterations, probability);
+    }
+
+    let circuit = grover.circuit();
+    let initial_state = Qubit::zero_state(4).state;
+    let counts = Simulator::run(&circuit, &initial_state).sample(500);
+
+    let (best, count) = counts.most_frequent().expect("at least one shot");
+    println!("Most frequent outcome: {} ({} of 500 shots)", best, count);
+    assert_eq!(best, "1011");
+}
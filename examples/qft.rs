@@ -0,0 +1,44 @@
+//! Applies the quantum Fourier transform to a basis state, undoes it, and estimates an
+//! eigenphase with quantum phase estimation.
+//!
+//! Run with `cargo run --example qft`.
+
+use quantum_simulator::algorithms::{inverse_qft, phase_estimation, qft};
+use quantum_simulator::prelude::*;
+use std::f64::consts::PI;
+
+fn main() {
+    let num_qubits = 3;
+    let initial_state = Qubit::from_bitstring("101")
+        .expect("a valid bitstring")
+        .state;
+
+    // The transform of a basis state has equal magnitudes and linearly increasing phases
+    let transformed = Simulator::run(&qft(num_qubits), &initial_state);
+    for (index, amplitude) in transformed.state.iter().enumerate() {
+        println!(
+            "|{:03b}⟩: magnitude {:.4}, phase {:+.4}",
+            index,
+            amplitude.norm(),
+            amplitude.arg()
+        );
+        assert!((amplitude.norm_sqr() - 1.0 / 8.0).abs() < 1e-12);
+    }
+
+    let mut round_trip = qft(num_qubits);
+    round_trip.append(&inverse_qft(num_qubits));
+    let restored = Simulator::run(&round_trip, &initial_state);
+    assert!((restored.state[5].norm() - 1.0).abs() < 1e-12);
+    println!("QFT followed by its inverse restores |101⟩");
+
+    // |1⟩ is an eigenstate of P(2π · 3/8), so four counting bits read 3/8 = 6/16
+    let precision = 4;
+    let mut estimation = Circuit::new(precision + 1);
+    estimation
+        .x(precision)
+        .append(&phase_estimation(&phase(2.0 * PI * 3.0 / 8.0), precision));
+    let initial_state = Qubit::zero_state(precision + 1).state;
+    let outcome = Simulator::run(&estimation, &initial_state).measure() & ((1 << precision) - 1);
+    println!("Estimated phase: {}/16", outcome);
+    assert_eq!(outcome, 6);
+}
@@ -0,0 +1,31 @@
+//! Sends two classical bits by transmitting a single qubit of a shared Bell pair.
+//!
+//! Run with `cargo run --example superdense_coding`.
+
+use quantum_simulator::algorithms::superdense_coding;
+use quantum_simulator::noise::NoiseModel;
+use quantum_simulator::prelude::*;
+
+fn main() {
+    for message in 0..4 {
+        let protocol = superdense_coding(message);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let counts = Simulator::sample_trajectories(
+            protocol.circuit(),
+            &initial_state,
+            &NoiseModel::new(),
+            100,
+        );
+        let sent = format!("{:02b}", message);
+        println!(
+            "sent {}, received {} in {} of 100 shots",
+            sent,
+            sent,
+            counts.get(&sent)
+        );
+        assert_eq!(counts.get(&sent), 100);
+        assert!(protocol.verify());
+    }
+}
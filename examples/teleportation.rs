@@ -0,0 +1,48 @@
+//! Teleports a qubit state from qubit 0 to qubit 2 using a Bell pair, two mid-circuit
+//! measurements and classically controlled corrections.
+//!
+//! Run with `cargo run --example teleportation`.
+
+use quantum_simulator::algorithms::teleportation;
+use quantum_simulator::analysis::bloch_vector;
+use quantum_simulator::prelude::*;
+
+fn main() {
+    let (theta, phi) = (1.1, 0.4);
+    let protocol = teleportation();
+
+    // The state to send: Rz(φ) Ry(θ) |0⟩
+    let mut prepare = Circuit::new(1);
+    prepare.ry(0, theta).rz(0, phi);
+    let mut input = Qubit::zero_state(1);
+    prepare.run(&mut input);
+
+    let mut circuit = Circuit::new(3);
+    circuit.append(&prepare).append(protocol.circuit());
+
+    let expected = (
+        theta.sin() * phi.cos(),
+        theta.sin() * phi.sin(),
+        theta.cos(),
+    );
+    for shot in 0..4 {
+        let mut qubit = Qubit::zero_state(3);
+        let clbits = circuit.run_with_rng(&mut qubit, &mut rand::thread_rng());
+
+        let (x, y, z) = bloch_vector(&qubit, 2);
+        println!(
+            "shot {}: measured {:?}, received Bloch vector ({:+.4}, {:+.4}, {:+.4})",
+            shot, clbits, x, y, z
+        );
+        assert!((x - expected.0).abs() < 1e-9);
+        assert!((y - expected.1).abs() < 1e-9);
+        assert!((z - expected.2).abs() < 1e-9);
+    }
+
+    let fidelity = protocol.verify(&input).expect("the input is one qubit");
+    println!(
+        "fidelity of the received state with the input: {:.6}",
+        fidelity
+    );
+    assert!((fidelity - 1.0).abs() < 1e-9);
+}
@@ -0,0 +1,108 @@
+//! Builds circuits from arbitrary instruction sequences and runs them, checking that invalid
+//! instructions are rejected with an error rather than a panic.
+
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use quantum_simulator::backend::{Backend, CpuBackend};
+use quantum_simulator::prelude::*;
+
+/// Registers stay small so each run is fast; qubit and classical bit indices are not limited.
+const MAX_QUBITS: u8 = 6;
+
+/// The largest `Unitary` matrix generated, deliberately not always a power of two.
+const MAX_UNITARY_DIMENSION: u8 = 9;
+
+#[derive(Arbitrary, Debug)]
+enum FuzzOperation {
+    H,
+    X,
+    Y,
+    Z,
+    S,
+    T,
+    Rx(f64),
+    Ry(f64),
+    Rz(f64),
+    Phase(f64),
+    Cnot,
+    Cz,
+    Swap,
+    ISwap,
+    Toffoli,
+    Unitary {
+        dimension: u8,
+        entries: Vec<(f64, f64)>,
+    },
+    Measure(usize),
+    Reset,
+    Conditional {
+        clbit: usize,
+        operation: Box<FuzzOperation>,
+    },
+}
+
+impl FuzzOperation {
+    fn to_operation(&self) -> Operation {
+        match self {
+            FuzzOperation::H => Operation::H,
+            FuzzOperation::X => Operation::X,
+            FuzzOperation::Y => Operation::Y,
+            FuzzOperation::Z => Operation::Z,
+            FuzzOperation::S => Operation::S,
+            FuzzOperation::T => Operation::T,
+            FuzzOperation::Rx(theta) => Operation::Rx(*theta),
+            FuzzOperation::Ry(theta) => Operation::Ry(*theta),
+            FuzzOperation::Rz(theta) => Operation::Rz(*theta),
+            FuzzOperation::Phase(theta) => Operation::Phase(*theta),
+            FuzzOperation::Cnot => Operation::Cnot,
+            FuzzOperation::Cz => Operation::Cz,
+            FuzzOperation::Swap => Operation::Swap,
+            FuzzOperation::ISwap => Operation::ISwap,
+            FuzzOperation::Toffoli => Operation::Toffoli,
+            FuzzOperation::Unitary { dimension, entries } => {
+                let dimension = (dimension % (MAX_UNITARY_DIMENSION + 1)) as usize;
+                let entry = |k: usize| {
+                    entries
+                        .get(k)
+                        .map_or(Complex::new(0.0, 0.0), |&(re, im)| Complex::new(re, im))
+                };
+                Operation::Unitary(Gate::new(
+                    (0..dimension)
+                        .map(|row| {
+                            (0..dimension)
+                                .map(|col| entry(row * dimension + col))
+                                .collect()
+                        })
+                        .collect(),
+                ))
+            }
+            FuzzOperation::Measure(clbit) => Operation::Measure(*clbit),
+            FuzzOperation::Reset => Operation::Reset,
+            FuzzOperation::Conditional { clbit, operation } => Operation::Conditional {
+                clbit: *clbit,
+                operation: Box::new(operation.to_operation()),
+            },
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    num_qubits: u8,
+    instructions: Vec<(FuzzOperation, Vec<u8>)>,
+}
+
+fuzz_target!(|input: Input| {
+    let num_qubits = (input.num_qubits % (MAX_QUBITS + 1)) as usize;
+    let mut circuit = Circuit::new(num_qubits);
+    for (operation, qubits) in &input.instructions {
+        let qubits = qubits.iter().map(|&qubit| qubit as usize).collect();
+        let _ = circuit.add_instruction(Instruction::new(operation.to_operation(), qubits));
+    }
+
+    let mut qubit = CpuBackend::new(num_qubits).state();
+    let _ = circuit.try_run(&mut qubit);
+    let _ = CpuBackend::new(num_qubits).run(&circuit);
+});
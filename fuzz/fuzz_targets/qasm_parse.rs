@@ -0,0 +1,20 @@
+//! Feeds arbitrary text to the QASM parser, which must reject malformed programs with an error
+//! rather than panicking, overflowing the stack or allocating without bound.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use quantum_simulator::qasm::{parse, to_qasm, QasmVersion};
+
+fuzz_target!(|source: &str| {
+    let Ok(circuit) = parse(source) else {
+        return;
+    };
+    // Whatever parses must survive a round trip through the serializer.
+    for version in [QasmVersion::V2, QasmVersion::V3] {
+        if let Ok(emitted) = to_qasm(&circuit, version) {
+            let reparsed = parse(&emitted).expect("emitted QASM parses");
+            assert_eq!(reparsed.num_qubits(), circuit.num_qubits());
+        }
+    }
+});
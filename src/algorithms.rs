@@ -0,0 +1,875 @@
+//! This module builds circuits for standard quantum algorithms out of the low-level gate API.
+//!
+//! Registers follow the rest of the crate: qubit `k` is bit `k` of the basis index, so a register
+//! holding the integer `y` is the basis state `|y⟩`.
+
+pub mod arithmetic;
+pub mod qaoa;
+pub mod vqe;
+
+use crate::analysis::reduced_density_matrix;
+use crate::circuit::{Circuit, Operation};
+use crate::error::QuantumError;
+use crate::gates::{diagonal_gate, phase, Gate};
+use crate::linalg;
+use crate::qubit::Qubit;
+use crate::rng;
+use crate::tolerance;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+/// Returns the quantum Fourier transform on `num_qubits` qubits.
+///
+/// The circuit maps `|x⟩` to `1/√N Σ_y e^{2πixy/N} |y⟩` with `N = 2^num_qubits`, including the
+/// final qubit reversal.
+///
+/// # Arguments
+///
+/// * `num_qubits` - The width of the register.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::qft;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// // The transform of |0⟩ is the uniform superposition
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+/// initial_state[0] = Complex::new(1.0, 0.0);
+/// let final_qubit = Simulator::run(&qft(3), &initial_state);
+/// assert!(final_qubit.state.iter().all(|a| (a.norm_sqr() - 0.125).abs() < 1e-12));
+/// ```
+pub fn qft(num_qubits: usize) -> Circuit {
+    let mut circuit = Circuit::new(num_qubits);
+    for target in (0..num_qubits).rev() {
+        circuit.h(target);
+        for control in (0..target).rev() {
+            let theta = PI / (1 << (target - control)) as f64;
+            circuit.add_controlled_gate(phase(theta), control, &[target]);
+        }
+    }
+    for low in 0..num_qubits / 2 {
+        circuit.swap(low, num_qubits - 1 - low);
+    }
+    circuit
+}
+
+/// Returns the inverse quantum Fourier transform on `num_qubits` qubits.
+///
+/// # Arguments
+///
+/// * `num_qubits` - The width of the register.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::{inverse_qft, qft};
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// let mut circuit = qft(3);
+/// circuit.append(&inverse_qft(3));
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+/// initial_state[5] = Complex::new(1.0, 0.0);
+/// let final_qubit = Simulator::run(&circuit, &initial_state);
+/// assert!((final_qubit.state[5].norm() - 1.0).abs() < 1e-12);
+/// ```
+pub fn inverse_qft(num_qubits: usize) -> Circuit {
+    qft(num_qubits).inverse()
+}
+
+/// Returns a quantum phase estimation circuit for `unitary`.
+///
+/// Qubits `0..precision` form the counting register and the following qubits hold the target
+/// register of `unitary`, which the caller prepares in an eigenstate `U|ψ⟩ = e^{2πiφ}|ψ⟩`. After
+/// the circuit runs, measuring the counting register gives `φ · 2^precision` rounded to an
+/// integer with high probability.
+///
+/// # Arguments
+///
+/// * `unitary` - The gate whose eigenphase is estimated.
+/// * `precision` - The number of counting qubits, i.e. bits of the estimate.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::phase_estimation;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::gates::phase;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+/// use std::f64::consts::PI;
+///
+/// // |1⟩ is an eigenstate of P(π/2) with phase φ = 1/4
+/// let mut circuit = Circuit::new(4);
+/// circuit.x(3).append(&phase_estimation(&phase(PI / 2.0), 3));
+///
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 16];
+/// initial_state[0] = Complex::new(1.0, 0.0);
+/// let final_qubit = Simulator::run(&circuit, &initial_state);
+/// assert!((final_qubit.state[0b1_010].norm() - 1.0).abs() < 1e-12); // 2/8 = 1/4
+/// ```
+pub fn phase_estimation(unitary: &Gate, precision: usize) -> Circuit {
+    let target_qubits = unitary.matrix.len().trailing_zeros() as usize;
+    let targets: Vec<usize> = (precision..precision + target_qubits).collect();
+    let mut circuit = Circuit::new(precision + target_qubits);
+
+    let mut power = unitary.clone();
+    for control in 0..precision {
+        circuit.h(control);
+        circuit.add_controlled_gate(power.clone(), control, &targets);
+        power = Gate::new(linalg::matmul(&power.matrix, &power.matrix));
+    }
+
+    let counting: Vec<usize> = (0..precision).collect();
+    circuit.compose(&inverse_qft(precision), &counting);
+    circuit
+}
+
+/// The measurement angles `(a, a', b, b')` in the X-Z plane at which the Bell state
+/// `(|00⟩ + |11⟩)/√2` violates the CHSH inequality maximally, reaching `S = 2√2`.
+pub const CHSH_ANGLES: (f64, f64, f64, f64) = (0.0, FRAC_PI_2, FRAC_PI_4, -FRAC_PI_4);
+
+/// Returns one setting of the CHSH Bell test: prepares the Bell state `(|00⟩ + |11⟩)/√2`,
+/// rotates qubit `0` to measure it at angle `a` and qubit `1` at angle `b` from the Z axis, and
+/// measures them into classical bits `0` and `1`.
+///
+/// The correlation of the two bits, see
+/// [`correlation_from_counts`](crate::analysis::correlation_from_counts), estimates
+/// `E(a, b) = cos(a - b)`.
+///
+/// # Arguments
+///
+/// * `a` - The measurement angle of qubit `0`.
+/// * `b` - The measurement angle of qubit `1`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::{chsh, chsh_value};
+/// use quantum_simulator::analysis::correlation_from_counts;
+/// use quantum_simulator::noise::NoiseModel;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// let zero = vec![
+///     Complex::new(1.0, 0.0),
+///     Complex::new(0.0, 0.0),
+///     Complex::new(0.0, 0.0),
+///     Complex::new(0.0, 0.0),
+/// ];
+/// let s = chsh_value(|a, b| {
+///     let counts = Simulator::sample_trajectories(&chsh(a, b), &zero, &NoiseModel::new(), 2000);
+///     correlation_from_counts(&counts, (0, 1))
+/// });
+/// // No local hidden-variable model exceeds 2
+/// assert!(s > 2.5);
+/// ```
+pub fn chsh(a: f64, b: f64) -> Circuit {
+    let mut circuit = Circuit::new(2);
+    circuit
+        .h(0)
+        .cnot(0, 1)
+        .ry(0, -a)
+        .ry(1, -b)
+        .measure(0, 0)
+        .measure(1, 1);
+    circuit
+}
+
+/// Returns the CHSH value `S = E(a, b) + E(a, b') + E(a', b) - E(a', b')` at [`CHSH_ANGLES`].
+///
+/// Local hidden-variable models satisfy `|S| ≤ 2`, while quantum mechanics reaches `2√2`.
+///
+/// # Arguments
+///
+/// * `correlation` - The correlation `E` of the two measurement angles, e.g. computed exactly
+///   with [`correlation`](crate::analysis::correlation) or from the shots of [`chsh`].
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::chsh_value;
+///
+/// // The Bell state's exact correlation reaches Tsirelson's bound
+/// let s = chsh_value(|a, b| (a - b).cos());
+/// assert!((s - 2.0 * 2.0_f64.sqrt()).abs() < 1e-12);
+/// ```
+pub fn chsh_value(mut correlation: impl FnMut(f64, f64) -> f64) -> f64 {
+    let (a, a_prime, b, b_prime) = CHSH_ANGLES;
+    correlation(a, b) + correlation(a, b_prime) + correlation(a_prime, b)
+        - correlation(a_prime, b_prime)
+}
+
+/// The quantum teleportation protocol, built by [`teleportation`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Teleportation {
+    circuit: Circuit,
+}
+
+impl Teleportation {
+    /// Returns the protocol on three qubits: qubit 0 holds the state to send, qubits 1 and 2
+    /// become a Bell pair, qubits 0 and 1 are measured into classical bits 0 and 1, and the
+    /// corrections conditioned on them leave the state on qubit 2.
+    pub fn circuit(&self) -> &Circuit {
+        &self.circuit
+    }
+
+    /// Teleports `input` once and returns the fidelity of the received qubit with it, `1` up to
+    /// rounding whatever the measurements gave.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The single-qubit state to send.
+    /// * `rng` - The random number generator the measurements draw from.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The fidelity `⟨ψ|ρ|ψ⟩` of the state `ρ` of qubit 2 with the input `|ψ⟩`.
+    /// * `Err(QuantumError::DimensionMismatch)` - If `input` is not a single qubit.
+    pub fn verify_with_rng<R: Rng + ?Sized>(
+        &self,
+        input: &Qubit,
+        rng: &mut R,
+    ) -> Result<f64, QuantumError> {
+        if input.state.len() != 2 {
+            return Err(QuantumError::DimensionMismatch {
+                expected: 2,
+                found: input.state.len(),
+            });
+        }
+        // The input on qubit 0, the other two qubits in |0⟩
+        let mut state = vec![Complex::new(0.0, 0.0); 8];
+        state[..2].copy_from_slice(&input.state);
+        let mut qubit = Qubit::from_state(state);
+        self.circuit.run_with_rng(&mut qubit, rng);
+        let received = reduced_density_matrix(&qubit, &[2]);
+        let mut fidelity = Complex::new(0.0, 0.0);
+        for (row, entries) in received.matrix.iter().enumerate() {
+            for (column, entry) in entries.iter().enumerate() {
+                fidelity += input.state[row].conj() * entry * input.state[column];
+            }
+        }
+        Ok(fidelity.re)
+    }
+
+    /// Teleports `input` once like [`Teleportation::verify_with_rng`], using the crate's random
+    /// number generator.
+    pub fn verify(&self, input: &Qubit) -> Result<f64, QuantumError> {
+        rng::with_rng(|rng| self.verify_with_rng(input, rng))
+    }
+}
+
+/// Returns the quantum teleportation protocol, which moves the state of qubit 0 onto qubit 2
+/// with a shared Bell pair, two mid-circuit measurements and corrections fed forward from them.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::teleportation;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::qubit::Qubit;
+///
+/// let protocol = teleportation();
+/// assert_eq!(protocol.circuit().num_clbits(), 2);
+///
+/// let mut prepare = Circuit::new(1);
+/// prepare.ry(0, 1.1).rz(0, 0.4);
+/// let mut input = Qubit::zero_state(1);
+/// prepare.run(&mut input);
+/// for _ in 0..4 {
+///     assert!((protocol.verify(&input).unwrap() - 1.0).abs() < 1e-9);
+/// }
+/// ```
+pub fn teleportation() -> Teleportation {
+    let mut circuit = Circuit::new(3);
+    // A Bell pair between the sender's qubit 1 and the receiver's qubit 2
+    circuit.h(1).cnot(1, 2);
+    // A Bell measurement of the input and the sender's half of the pair
+    circuit.cnot(0, 1).h(0).measure(0, 0).measure(1, 1);
+    circuit
+        .conditional(1, Operation::X, vec![2])
+        .conditional(0, Operation::Z, vec![2]);
+    Teleportation { circuit }
+}
+
+/// The superdense coding protocol for one two-bit message, built by [`superdense_coding`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuperdenseCoding {
+    message: usize,
+    circuit: Circuit,
+}
+
+impl SuperdenseCoding {
+    /// Returns the two-bit message the circuit sends.
+    pub fn message(&self) -> usize {
+        self.message
+    }
+
+    /// Returns the protocol on two qubits: a Bell pair is shared, the sender encodes the
+    /// message on qubit 0 with `Z` for bit 0 and `X` for bit 1, and the receiver decodes both
+    /// qubits back into classical bits 0 and 1.
+    pub fn circuit(&self) -> &Circuit {
+        &self.circuit
+    }
+
+    /// Runs the protocol once and returns `true` if the decoded bits equal the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The random number generator the measurements draw from.
+    pub fn verify_with_rng<R: Rng + ?Sized>(&self, rng: &mut R) -> bool {
+        let mut qubit = Qubit::zero_state(2);
+        let bits = self.circuit.run_with_rng(&mut qubit, rng);
+        let decoded = bits
+            .iter()
+            .enumerate()
+            .fold(0, |message, (bit, &set)| message | (set as usize) << bit);
+        decoded == self.message
+    }
+
+    /// Runs the protocol once like [`SuperdenseCoding::verify_with_rng`], using the crate's
+    /// random number generator.
+    pub fn verify(&self) -> bool {
+        rng::with_rng(|rng| self.verify_with_rng(rng))
+    }
+}
+
+/// Returns the superdense coding protocol, which sends the two classical bits of `message` by
+/// transmitting one qubit of a shared Bell pair.
+///
+/// # Arguments
+///
+/// * `message` - The message, from `0` to `3`; bit `k` is decoded into classical bit `k`.
+///
+/// # Panics
+///
+/// Panics if `message` is larger than `3`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::superdense_coding;
+///
+/// for message in 0..4 {
+///     let protocol = superdense_coding(message);
+///     assert!(protocol.verify());
+/// }
+/// ```
+pub fn superdense_coding(message: usize) -> SuperdenseCoding {
+    assert!(
+        message < 4,
+        "a superdense message has two bits, found {}",
+        message
+    );
+    let mut circuit = Circuit::new(2);
+    circuit.h(0).cnot(0, 1);
+    if message & 0b10 != 0 {
+        circuit.x(0);
+    }
+    if message & 0b01 != 0 {
+        circuit.z(0);
+    }
+    circuit.cnot(0, 1).h(0).measure(0, 0).measure(1, 1);
+    SuperdenseCoding { message, circuit }
+}
+
+/// An `Oracle` marks the solutions of a search problem by flipping their phase,
+/// `|x⟩ ↦ -|x⟩` for marked `x`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Oracle {
+    num_qubits: usize,
+    marked: Vec<usize>,
+}
+
+impl Oracle {
+    /// Creates an oracle from a phase-oracle gate on the whole register.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - A diagonal gate whose entries are `-1` on marked states and `1` elsewhere.
+    ///
+    /// # Returns
+    ///
+    /// * The oracle, or `QuantumError::Unsupported` if `gate` is not such a phase oracle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::Oracle;
+    /// use quantum_simulator::gates::cz;
+    ///
+    /// let oracle = Oracle::from_gate(&cz(0, 1, 2)).unwrap();
+    /// assert_eq!(oracle.marked(), &[3]);
+    /// ```
+    pub fn from_gate(gate: &Gate) -> Result<Self, QuantumError> {
+        let num_qubits = gate.matrix.len().trailing_zeros() as usize;
+        let mut marked = vec![];
+        for (i, row) in gate.matrix.iter().enumerate() {
+            for (j, &entry) in row.iter().enumerate() {
+                let expected = if i != j {
+                    Complex::new(0.0, 0.0)
+                } else if entry.re < 0.0 {
+                    marked.push(i);
+                    Complex::new(-1.0, 0.0)
+                } else {
+                    Complex::new(1.0, 0.0)
+                };
+                if (entry - expected).norm() > tolerance::current().equality {
+                    return Err(QuantumError::Unsupported {
+                        feature: "oracle gates other than diagonal ±1 phase oracles".to_string(),
+                    });
+                }
+            }
+        }
+        Ok(Oracle { num_qubits, marked })
+    }
+
+    /// Creates an oracle marking the given bitstrings, written with the highest qubit first.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the search register.
+    /// * `bitstrings` - The marked bitstrings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a bitstring is not `num_qubits` characters of `0` and `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::Oracle;
+    ///
+    /// assert_eq!(Oracle::from_bitstrings(3, &["101", "011"]).marked(), &[3, 5]);
+    /// ```
+    pub fn from_bitstrings(num_qubits: usize, bitstrings: &[&str]) -> Self {
+        let marked: Vec<usize> = bitstrings
+            .iter()
+            .map(|bits| {
+                assert!(
+                    bits.len() == num_qubits && bits.chars().all(|c| c == '0' || c == '1'),
+                    "{:?} is not a {}-bit bitstring",
+                    bits,
+                    num_qubits
+                );
+                usize::from_str_radix(bits, 2).unwrap_or(0)
+            })
+            .collect();
+        Self::from_fn(num_qubits, |index| marked.contains(&index))
+    }
+
+    /// Creates an oracle marking every basis index for which `is_marked` returns `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the search register.
+    /// * `is_marked` - The predicate over basis-state indices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::Oracle;
+    ///
+    /// let multiples_of_five = Oracle::from_fn(4, |index| index > 0 && index % 5 == 0);
+    /// assert_eq!(multiples_of_five.marked(), &[5, 10, 15]);
+    /// ```
+    pub fn from_fn(num_qubits: usize, is_marked: impl Fn(usize) -> bool) -> Self {
+        Oracle {
+            num_qubits,
+            marked: (0..1 << num_qubits)
+                .filter(|&index| is_marked(index))
+                .collect(),
+        }
+    }
+
+    /// Returns the width of the search register.
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Returns the marked basis-state indices in increasing order.
+    pub fn marked(&self) -> &[usize] {
+        &self.marked
+    }
+
+    /// Returns the oracle as a diagonal gate on the whole register.
+    pub fn gate(&self) -> Gate {
+        diagonal_gate(self.num_qubits, |index| {
+            if self.marked.binary_search(&index).is_ok() {
+                Complex::new(-1.0, 0.0)
+            } else {
+                Complex::new(1.0, 0.0)
+            }
+        })
+    }
+}
+
+/// A `Grover` search amplifies the marked states of an [`Oracle`] starting from the uniform
+/// superposition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grover {
+    oracle: Oracle,
+    iterations: usize,
+}
+
+impl Grover {
+    /// Creates a search running the optimal number of iterations for the oracle.
+    ///
+    /// # Arguments
+    ///
+    /// * `oracle` - The oracle marking the solutions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::{Grover, Oracle};
+    ///
+    /// let grover = Grover::new(Oracle::from_bitstrings(4, &["1011"]));
+    /// assert_eq!(grover.iterations(), 3);
+    /// assert!(grover.success_probability(3) > 0.96);
+    /// ```
+    pub fn new(oracle: Oracle) -> Self {
+        let theta = Self::rotation_angle(&oracle);
+        let iterations = if theta > 0.0 {
+            (PI / (4.0 * theta) - 0.5).round().max(0.0) as usize
+        } else {
+            0
+        };
+        Grover { oracle, iterations }
+    }
+
+    /// Overrides the number of Grover iterations.
+    ///
+    /// # Arguments
+    ///
+    /// * `iterations` - The number of oracle and diffusion rounds.
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Returns the number of Grover iterations the circuit runs.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns the oracle being searched.
+    pub fn oracle(&self) -> &Oracle {
+        &self.oracle
+    }
+
+    /// Returns the full amplitude-amplification circuit, starting from `|0…0⟩`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::{Grover, Oracle};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let grover = Grover::new(Oracle::from_bitstrings(3, &["110"]));
+    /// let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+    /// initial_state[0] = Complex::new(1.0, 0.0);
+    /// let final_qubit = Simulator::run(&grover.circuit(), &initial_state);
+    /// assert!(final_qubit.state[0b110].norm_sqr() > 0.9);
+    /// ```
+    pub fn circuit(&self) -> Circuit {
+        let num_qubits = self.oracle.num_qubits;
+        let mut superposition = Circuit::new(num_qubits);
+        for qubit in 0..num_qubits {
+            superposition.h(qubit);
+        }
+
+        // The diffusion operator 2|s⟩⟨s| - I, up to a global phase
+        let mut round = Circuit::new(num_qubits);
+        round.add_gate(self.oracle.gate());
+        round.append(&superposition);
+        round.add_gate(diagonal_gate(num_qubits, |index| {
+            if index == 0 {
+                Complex::new(-1.0, 0.0)
+            } else {
+                Complex::new(1.0, 0.0)
+            }
+        }));
+        round.append(&superposition);
+
+        let mut circuit = superposition.clone();
+        circuit.append(&round.repeat(self.iterations));
+        circuit
+    }
+
+    /// Returns the probability of measuring a marked state after `iterations` rounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `iterations` - The number of oracle and diffusion rounds.
+    pub fn success_probability(&self, iterations: usize) -> f64 {
+        let theta = Self::rotation_angle(&self.oracle);
+        ((2 * iterations + 1) as f64 * theta).sin().powi(2)
+    }
+
+    /// Returns the success probability after each of `0..=iterations()` rounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::{Grover, Oracle};
+    ///
+    /// let grover = Grover::new(Oracle::from_bitstrings(2, &["01"]));
+    /// let probabilities = grover.success_probabilities();
+    /// assert!((probabilities[0] - 0.25).abs() < 1e-12);
+    /// assert!((probabilities[1] - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn success_probabilities(&self) -> Vec<f64> {
+        (0..=self.iterations)
+            .map(|iterations| self.success_probability(iterations))
+            .collect()
+    }
+
+    /// Returns the angle `θ` with `sin²θ = M/N`, half the rotation of one Grover iteration.
+    fn rotation_angle(oracle: &Oracle) -> f64 {
+        let fraction = oracle.marked.len() as f64 / (1usize << oracle.num_qubits) as f64;
+        fraction.sqrt().asin()
+    }
+}
+
+/// The widest modulus [`shor`] factors, in bits; its order-finding circuit has `2n + 3` qubits.
+///
+/// Each bit adds two qubits and two controlled multipliers, so a run costs roughly four times as
+/// much per bit: one order-finding run on a 6-bit modulus takes a few seconds in a release build,
+/// one on a 7-bit modulus already takes about 15, and a factorization may need several runs.
+pub const MAX_SHOR_BITS: usize = 6;
+
+/// Number of random bases [`shor`] tries before giving up.
+const SHOR_ATTEMPTS: usize = 32;
+
+/// The result of [`shor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Factorization {
+    /// Two nontrivial factors whose product is the modulus, the smaller first.
+    pub factors: (u64, u64),
+    /// The base `a` of the attempt that found the factors, or `None` if the modulus is even or a
+    /// prime power, which are factored classically.
+    pub base: Option<u64>,
+    /// The order `r` of the base modulo `N`, the smallest `r > 0` with `aʳ = 1 mod N`, or `None`
+    /// if the base already shared a factor with `N`.
+    pub order: Option<u64>,
+    /// The number of order-finding circuits run.
+    pub runs: usize,
+}
+
+/// Returns the order-finding circuit of Shor's algorithm for `base` modulo `modulus`.
+///
+/// The circuit estimates the phase of multiplication by `a` with one control qubit that is
+/// measured and reset for each of the `2n` bits, where `n` is the bit length of `N`, instead of a
+/// full counting register: a semiclassical inverse QFT. Step `k` multiplies by `a^(2^(2n-1-k))`
+/// with [`arithmetic::controlled_modular_multiplier`], rotates the control by the phases of the
+/// bits measured so far, and measures it into classical bit `k`. The classical bits then hold an
+/// integer `y` with `y / 2^(2n)` close to `s / r` for a random `s`, where `r` is the order of
+/// `a`. Qubits `1..=n` start in `|1⟩`, so the circuit runs from `|0…0⟩` on `2n + 3` qubits.
+///
+/// # Arguments
+///
+/// * `base` - The base `a`, coprime to `modulus`.
+/// * `modulus` - The modulus `N`, at least 2.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit on `2n + 3` qubits with `2n` classical bits.
+/// * `Err(QuantumError::Unsupported)` - If the modulus is below 2 or above `2^31`, or `base`
+///   is not coprime to it.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::order_finding;
+///
+/// let circuit = order_finding(7, 15).unwrap();
+/// assert_eq!(circuit.num_qubits(), 11);
+/// assert_eq!(circuit.num_clbits(), 8);
+/// ```
+pub fn order_finding(base: u64, modulus: u64) -> Result<Circuit, QuantumError> {
+    let multiplier = arithmetic::controlled_modular_multiplier(base, modulus)?;
+    let num_qubits = multiplier.num_qubits();
+    let num_bits = (num_qubits - 3) / 2;
+    let precision = 2 * num_bits;
+    let qubits: Vec<usize> = (0..num_qubits).collect();
+
+    let mut circuit = Circuit::new(num_qubits);
+    circuit.x(1);
+    for step in 0..precision {
+        if step > 0 {
+            circuit.reset(0);
+        }
+        let power = pow_mod(base, 1 << (precision - 1 - step), modulus);
+        circuit.h(0);
+        circuit.compose(
+            &arithmetic::controlled_modular_multiplier(power, modulus)?,
+            &qubits,
+        );
+        // Remove the phase of the bits below this one, measured in earlier steps
+        for earlier in 0..step {
+            let theta = -PI / (1u64 << (step - earlier)) as f64;
+            circuit.conditional(earlier, Operation::Phase(theta), vec![0]);
+        }
+        circuit.h(0).measure(0, step);
+    }
+    Ok(circuit)
+}
+
+/// Factors `modulus` with Shor's algorithm, simulating the order-finding circuit.
+///
+/// Even moduli and prime powers are factored classically. Otherwise each attempt picks a random
+/// base `a`; if it shares a factor with `N` that factor is returned, and if not, one shot of
+/// [`order_finding`] is sampled and the continued-fraction expansion of its outcome gives a
+/// candidate order `r`. An even `r` with `a^(r/2) ≠ -1 mod N` yields the factors
+/// `gcd(a^(r/2) ± 1, N)`; any other outcome starts a new attempt.
+///
+/// # Arguments
+///
+/// * `modulus` - The number `N` to factor, composite and below `2^6`.
+/// * `seed` - The seed of the bases and measurement outcomes; equal seeds give equal results.
+///
+/// # Returns
+///
+/// * `Ok(Factorization)` - The factors and how they were found.
+/// * `Err(QuantumError::Unsupported)` - If `N` is below 4, prime, wider than
+///   [`MAX_SHOR_BITS`] bits, or no attempt of 32 found a factor.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::shor;
+///
+/// let factorization = shor(15, 1).unwrap();
+/// assert_eq!(factorization.factors, (3, 5));
+/// assert!(factorization.runs <= 32);
+///
+/// assert_eq!(shor(12, 0).unwrap().factors, (2, 6));
+/// assert!(shor(13, 0).is_err());
+/// ```
+pub fn shor(modulus: u64, seed: u64) -> Result<Factorization, QuantumError> {
+    let unsupported = |reason: &str| QuantumError::Unsupported {
+        feature: format!("factoring {}: {}", modulus, reason),
+    };
+    if modulus < 4 || modulus >> MAX_SHOR_BITS != 0 {
+        return Err(unsupported(&format!(
+            "only 4 to {} are supported",
+            (1u64 << MAX_SHOR_BITS) - 1
+        )));
+    }
+    if (2..modulus)
+        .take_while(|d| d * d <= modulus)
+        .all(|d| !modulus.is_multiple_of(d))
+    {
+        return Err(unsupported("it is prime"));
+    }
+    let factorization = |factor: u64, base, order, runs| {
+        let (small, large) = (factor, modulus / factor);
+        Factorization {
+            factors: (small.min(large), small.max(large)),
+            base,
+            order,
+            runs,
+        }
+    };
+    if modulus.is_multiple_of(2) {
+        return Ok(factorization(2, None, None, 0));
+    }
+    if let Some(root) = prime_power_root(modulus) {
+        return Ok(factorization(root, None, None, 0));
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for runs in 0..SHOR_ATTEMPTS {
+        let base = rng.gen_range(2..modulus - 1);
+        let shared = gcd(base, modulus);
+        if shared > 1 {
+            return Ok(factorization(shared, Some(base), None, runs));
+        }
+        let circuit = order_finding(base, modulus)?;
+        let mut qubit = Qubit::zero_state(circuit.num_qubits());
+        let bits = circuit.run_with_rng(&mut qubit, &mut rng);
+        let outcome = bits
+            .iter()
+            .enumerate()
+            .fold(0u64, |y, (bit, &set)| y | (set as u64) << bit);
+        let Some(order) = order_from_phase(outcome, bits.len(), base, modulus) else {
+            continue;
+        };
+        if !order.is_multiple_of(2) {
+            continue;
+        }
+        let half = pow_mod(base, order / 2, modulus);
+        if half == modulus - 1 {
+            continue;
+        }
+        let factor = gcd(half + 1, modulus);
+        let factor = if factor > 1 && factor < modulus {
+            factor
+        } else {
+            gcd(half + modulus - 1, modulus)
+        };
+        return Ok(factorization(factor, Some(base), Some(order), runs + 1));
+    }
+    Err(unsupported(&format!(
+        "no attempt of {} found a factor",
+        SHOR_ATTEMPTS
+    )))
+}
+
+/// Returns the smallest denominator `r` among the continued-fraction convergents of
+/// `outcome / 2^bits` with `baseʳ = 1 mod modulus`, if any.
+fn order_from_phase(outcome: u64, bits: usize, base: u64, modulus: u64) -> Option<u64> {
+    let (mut numerator, mut denominator) = (outcome, 1u64 << bits);
+    // Convergent denominators follow q_k = a_k q_(k-1) + q_(k-2)
+    let (mut previous, mut current) = (0u64, 1u64);
+    while numerator != 0 {
+        let term = denominator / numerator;
+        (denominator, numerator) = (numerator, denominator % numerator);
+        (previous, current) = (current, term * current + previous);
+        if current >= modulus {
+            break;
+        }
+        if pow_mod(base, current, modulus) == 1 {
+            return Some(current);
+        }
+    }
+    None
+}
+
+/// Returns `p` if `value` is `p^k` for a prime `p` and `k ≥ 2`.
+fn prime_power_root(value: u64) -> Option<u64> {
+    let prime = (2..value).find(|&d| value.is_multiple_of(d))?;
+    let mut rest = value;
+    while rest.is_multiple_of(prime) {
+        rest /= prime;
+    }
+    (rest == 1 && prime != value).then_some(prime)
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn pow_mod(base: u64, exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    let mut square = base % modulus;
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * square % modulus;
+        }
+        square = square * square % modulus;
+        exponent >>= 1;
+    }
+    result
+}
@@ -0,0 +1,352 @@
+//! This module builds reversible arithmetic circuits: adders, modular multiplication and modular
+//! exponentiation, the ingredients of Shor's algorithm.
+//!
+//! An `n`-bit register is a list of `n` qubits holding the integer whose bit `k` is the register's
+//! qubit `k`. The adders act on two registers, either with the ripple-carry circuit of Cuccaro et
+//! al. out of CNOT and Toffoli gates, or with Draper's adder as phase rotations between quantum
+//! Fourier transforms. The modular circuits follow Beauregard: a constant is added modulo `N` in
+//! the Fourier basis with one ancilla, doubly controlled additions of `a·2^i mod N` multiply, and
+//! a controlled multiplication by `a`, a swap and an uncomputed multiplication by `a⁻¹` make the
+//! in-place `|x⟩ → |a·x mod N⟩` that [`modular_exponentiation`] repeats for every exponent bit.
+
+use crate::algorithms::{inverse_qft, qft};
+use crate::circuit::Circuit;
+use crate::error::QuantumError;
+use std::f64::consts::PI;
+
+/// Returns a ripple-carry adder on two `num_bits`-bit registers, mapping `|a⟩|b⟩` to
+/// `|a⟩|a + b mod 2^n⟩`.
+///
+/// Qubits `0..n` hold `a`, `n..2n` hold `b`, qubit `2n` is an ancilla in `|0⟩` that stays there,
+/// and qubit `2n + 1` is flipped by the carry out of the top bit. The circuit is `2n` MAJ and UMA
+/// blocks of two CNOTs and a Toffoli each, plus a CNOT for the carry.
+///
+/// # Arguments
+///
+/// * `num_bits` - The width `n` of each register, at least 1.
+///
+/// # Panics
+///
+/// Panics if `num_bits` is 0.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::arithmetic::ripple_carry_adder;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// // 5 + 6 = 11 = 0b1011: the low three bits land in b and the carry in qubit 7
+/// let a = 5;
+/// let b = 6 << 3;
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << 8];
+/// initial_state[a | b] = Complex::new(1.0, 0.0);
+/// let final_qubit = Simulator::run(&ripple_carry_adder(3), &initial_state);
+/// let sum = 0b011 << 3;
+/// let carry = 1 << 7;
+/// assert!((final_qubit.state[a | sum | carry].norm() - 1.0).abs() < 1e-12);
+/// ```
+pub fn ripple_carry_adder(num_bits: usize) -> Circuit {
+    assert!(num_bits > 0, "an adder needs at least one bit");
+    let a: Vec<usize> = (0..num_bits).collect();
+    let b: Vec<usize> = (num_bits..2 * num_bits).collect();
+    let ancilla = 2 * num_bits;
+    let carry = 2 * num_bits + 1;
+
+    let mut circuit = Circuit::new(2 * num_bits + 2);
+    // Each MAJ leaves the carry into bit i + 1 in a[i]
+    let mut carry_in = ancilla;
+    for i in 0..num_bits {
+        circuit
+            .cnot(a[i], b[i])
+            .cnot(a[i], carry_in)
+            .toffoli(carry_in, b[i], a[i]);
+        carry_in = a[i];
+    }
+    circuit.cnot(a[num_bits - 1], carry);
+    // Each UMA restores a[i] and the carry into it and writes the sum bit into b[i]
+    for i in (0..num_bits).rev() {
+        let carry_in = if i == 0 { ancilla } else { a[i - 1] };
+        circuit
+            .toffoli(carry_in, b[i], a[i])
+            .cnot(a[i], carry_in)
+            .cnot(carry_in, b[i]);
+    }
+    circuit
+}
+
+/// Returns Draper's QFT adder on two `num_bits`-bit registers, mapping `|a⟩|b⟩` to
+/// `|a⟩|a + b mod 2^n⟩` without ancillas.
+///
+/// Qubits `0..n` hold `a` and `n..2n` hold `b`. `b` is taken to the Fourier basis, where adding
+/// `a` is a controlled phase rotation from every bit of `a` to every bit of `b`, and back.
+///
+/// # Arguments
+///
+/// * `num_bits` - The width `n` of each register.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::arithmetic::qft_adder;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// // 6 + 7 = 13 = 5 mod 8
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << 6];
+/// initial_state[6 | 7 << 3] = Complex::new(1.0, 0.0);
+/// let final_qubit = Simulator::run(&qft_adder(3), &initial_state);
+/// assert!((final_qubit.state[6 | 5 << 3].norm() - 1.0).abs() < 1e-9);
+/// ```
+pub fn qft_adder(num_bits: usize) -> Circuit {
+    let b: Vec<usize> = (num_bits..2 * num_bits).collect();
+    let mut circuit = Circuit::new(2 * num_bits);
+    circuit.compose(&qft(num_bits), &b);
+    for control in 0..num_bits {
+        // Bit `control` of a adds 2^control, a rotation of b's qubit j by 2π·2^(control+j)/2^n
+        for (j, &target) in b.iter().enumerate().take(num_bits - control) {
+            let theta = 2.0 * PI / (1u64 << (num_bits - control - j)) as f64;
+            circuit.mcp(theta, &[control], target);
+        }
+    }
+    circuit.compose(&inverse_qft(num_bits), &b);
+    circuit
+}
+
+/// Returns a controlled in-place modular multiplier, mapping `|c⟩|x⟩|0⟩` to
+/// `|c⟩|a·x mod N⟩|0⟩` if the control `c` is set and leaving it unchanged otherwise.
+///
+/// With `n` the bit length of `N`, qubit 0 is the control, qubits `1..=n` hold `x`, which must
+/// be below `N`, and the remaining `n + 2` qubits are work qubits in `|0⟩` that the circuit
+/// returns to `|0⟩`: an `n + 1`-bit register and the ancilla of the modular additions. That is
+/// `2n + 3` qubits in all.
+///
+/// # Arguments
+///
+/// * `multiplier` - The constant `a`, coprime to `modulus`.
+/// * `modulus` - The modulus `N`, at least 2.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The multiplier on `2n + 3` qubits.
+/// * `Err(QuantumError::Unsupported)` - If the modulus is below 2 or above `2^31`, or
+///   `multiplier` has no inverse modulo it.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::arithmetic::controlled_modular_multiplier;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// // 7·4 = 28 = 13 mod 15, on 11 qubits
+/// let circuit = controlled_modular_multiplier(7, 15).unwrap();
+/// assert_eq!(circuit.num_qubits(), 11);
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << 11];
+/// initial_state[1 | 4 << 1] = Complex::new(1.0, 0.0);
+/// let final_qubit = Simulator::run(&circuit, &initial_state);
+/// assert!((final_qubit.state[1 | 13 << 1].norm() - 1.0).abs() < 1e-6);
+/// ```
+pub fn controlled_modular_multiplier(
+    multiplier: u64,
+    modulus: u64,
+) -> Result<Circuit, QuantumError> {
+    let num_bits = modulus_bits(modulus)?;
+    let mut circuit = Circuit::new(2 * num_bits + 3);
+    let layout = Layout::after(1, num_bits);
+    layout.controlled_multiply(&mut circuit, 0, multiplier, modulus)?;
+    Ok(circuit)
+}
+
+/// Returns the modular exponentiation of Shor's algorithm, mapping `|e⟩|y⟩|0⟩` to
+/// `|e⟩|aᵉ·y mod N⟩|0⟩`.
+///
+/// With `n` the bit length of `N` and `t` the number of exponent bits, qubits `0..t` hold the
+/// exponent `e`, qubits `t..t + n` hold `y`, which must be below `N`, and the remaining `n + 2`
+/// qubits are work qubits in `|0⟩`, as in [`controlled_modular_multiplier`]. That is
+/// `t + 2n + 2` qubits; factoring 15 with a three-bit exponent takes 13. Exponent bit `k`
+/// controls a multiplication by `a^(2^k) mod N`.
+///
+/// For Shor's algorithm, put the exponent register in uniform superposition and `y = 1`, run
+/// this circuit, apply the inverse QFT to the exponent register and measure it: the outcomes
+/// cluster around multiples of `2^t / r`, where `r` is the order of `a` modulo `N`.
+///
+/// # Arguments
+///
+/// * `base` - The base `a`, coprime to `modulus`.
+/// * `modulus` - The modulus `N`, at least 2.
+/// * `exponent_bits` - The width `t` of the exponent register.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The circuit on `t + 2n + 2` qubits.
+/// * `Err(QuantumError::Unsupported)` - If the modulus is below 2 or above `2^31`, or `base`
+///   is not coprime to it.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::arithmetic::modular_exponentiation;
+/// use quantum_simulator::algorithms::inverse_qft;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::qubit::Qubit;
+///
+/// // Shor's algorithm for 15 with a = 7, whose order is 4
+/// let exponent = [0, 1, 2];
+/// let modexp = modular_exponentiation(7, 15, 3).unwrap();
+/// let mut circuit = Circuit::new(modexp.num_qubits());
+/// circuit.h(0).h(1).h(2).x(3);
+/// circuit.append(&modexp).compose(&inverse_qft(3), &exponent);
+///
+/// let mut qubit = Qubit::zero_state(13);
+/// circuit.run(&mut qubit);
+/// let mut probabilities = [0.0; 8];
+/// for (index, amplitude) in qubit.state.iter().enumerate() {
+///     probabilities[index & 0b111] += amplitude.norm_sqr();
+/// }
+/// // Outcomes 0, 2, 4 and 6 of 8, multiples of 8 / 4, each with probability 1/4
+/// for outcome in [0, 2, 4, 6] {
+///     assert!((probabilities[outcome] - 0.25).abs() < 1e-6);
+/// }
+/// ```
+pub fn modular_exponentiation(
+    base: u64,
+    modulus: u64,
+    exponent_bits: usize,
+) -> Result<Circuit, QuantumError> {
+    let num_bits = modulus_bits(modulus)?;
+    let mut circuit = Circuit::new(exponent_bits + 2 * num_bits + 2);
+    let layout = Layout::after(exponent_bits, num_bits);
+    let mut multiplier = base % modulus;
+    for control in 0..exponent_bits {
+        layout.controlled_multiply(&mut circuit, control, multiplier, modulus)?;
+        multiplier = multiply_mod(multiplier, multiplier, modulus);
+    }
+    Ok(circuit)
+}
+
+/// The registers of an in-place modular multiplication.
+struct Layout {
+    /// The `n`-bit register multiplied in place.
+    x: Vec<usize>,
+    /// The `n + 1`-bit work register the product is accumulated in, in the Fourier basis.
+    b: Vec<usize>,
+    /// The ancilla recording whether a modular addition overflowed.
+    ancilla: usize,
+}
+
+impl Layout {
+    /// Places `x`, `b` and the ancilla one after another, starting at qubit `first`.
+    fn after(first: usize, num_bits: usize) -> Self {
+        Layout {
+            x: (first..first + num_bits).collect(),
+            b: (first + num_bits..first + 2 * num_bits + 1).collect(),
+            ancilla: first + 2 * num_bits + 1,
+        }
+    }
+
+    /// Multiplies `x` by `multiplier` modulo `modulus` in place if `control` is set: adds
+    /// `multiplier·x` into `b`, swaps `x` and `b`, and subtracts `multiplier⁻¹·b` from `b`.
+    fn controlled_multiply(
+        &self,
+        circuit: &mut Circuit,
+        control: usize,
+        multiplier: u64,
+        modulus: u64,
+    ) -> Result<(), QuantumError> {
+        let inverse = inverse_mod(multiplier % modulus, modulus).ok_or_else(|| {
+            QuantumError::Unsupported {
+                feature: format!(
+                    "modular multiplication by {}, which has no inverse modulo {}",
+                    multiplier, modulus
+                ),
+            }
+        })?;
+        self.multiply_add(circuit, control, multiplier % modulus, modulus);
+        for (&x, &b) in self.x.iter().zip(&self.b) {
+            // A controlled swap
+            circuit.cnot(b, x).toffoli(control, x, b).cnot(b, x);
+        }
+        let mut uncompute = Circuit::new(circuit.num_qubits());
+        self.multiply_add(&mut uncompute, control, inverse, modulus);
+        circuit.append(&uncompute.inverse());
+        Ok(())
+    }
+
+    /// Adds `multiplier·x mod modulus` to `b` if `control` is set, one doubly controlled modular
+    /// addition of `multiplier·2^i mod modulus` per bit `i` of `x`.
+    fn multiply_add(&self, circuit: &mut Circuit, control: usize, multiplier: u64, modulus: u64) {
+        let width = self.b.len();
+        circuit.compose(&qft(width), &self.b);
+        let mut addend = multiplier;
+        for &x in &self.x {
+            self.add_mod(circuit, addend, modulus, &[control, x]);
+            addend = (addend << 1) % modulus;
+        }
+        circuit.compose(&inverse_qft(width), &self.b);
+    }
+
+    /// Adds `addend` to `b` modulo `modulus` if every qubit in `controls` is set, for `b` and
+    /// `addend` below `modulus` and `b` in the Fourier basis.
+    ///
+    /// The sum minus the modulus is negative, i.e. sets the top bit of `b`, exactly when no
+    /// reduction is needed; the ancilla records that to add the modulus back, and is cleared by
+    /// comparing the result with `addend` again.
+    fn add_mod(&self, circuit: &mut Circuit, addend: u64, modulus: u64, controls: &[usize]) {
+        let width = self.b.len();
+        let top = self.b[width - 1];
+        let wrap = 1u64 << width;
+        self.add(circuit, addend, controls);
+        self.add(circuit, wrap - modulus, &[]);
+        circuit.compose(&inverse_qft(width), &self.b);
+        circuit.cnot(top, self.ancilla);
+        circuit.compose(&qft(width), &self.b);
+        self.add(circuit, modulus, &[self.ancilla]);
+
+        self.add(circuit, wrap - addend, controls);
+        circuit.compose(&inverse_qft(width), &self.b);
+        circuit.x(top).cnot(top, self.ancilla).x(top);
+        circuit.compose(&qft(width), &self.b);
+        self.add(circuit, addend, controls);
+    }
+
+    /// Adds the constant `addend` to `b`, held in the Fourier basis, modulo `2^(n + 1)` if every
+    /// qubit in `controls` is set: qubit `j` of `b` is rotated by `2π·addend·2^j / 2^(n + 1)`.
+    fn add(&self, circuit: &mut Circuit, addend: u64, controls: &[usize]) {
+        let width = self.b.len();
+        let wrap = 1u64 << width;
+        for (j, &target) in self.b.iter().enumerate() {
+            let turns = (addend << j) % wrap;
+            if turns != 0 {
+                circuit.mcp(2.0 * PI * turns as f64 / wrap as f64, controls, target);
+            }
+        }
+    }
+}
+
+/// Returns the number of bits of `modulus`, checking that it is between 2 and `2^31`.
+fn modulus_bits(modulus: u64) -> Result<usize, QuantumError> {
+    if !(2..=1 << 31).contains(&modulus) {
+        return Err(QuantumError::Unsupported {
+            feature: format!("modular arithmetic modulo {}", modulus),
+        });
+    }
+    Ok(64 - modulus.leading_zeros() as usize)
+}
+
+fn multiply_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
+
+/// Returns the inverse of `value` modulo `modulus`, or `None` if they are not coprime.
+fn inverse_mod(value: u64, modulus: u64) -> Option<u64> {
+    // Extended Euclid on (modulus, value), tracking the coefficient of value
+    let (mut old_r, mut r) = (modulus as i128, value as i128);
+    let (mut old_t, mut t) = (0i128, 1i128);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_t, t) = (t, old_t - quotient * t);
+    }
+    (old_r == 1).then(|| old_t.rem_euclid(modulus as i128) as u64)
+}
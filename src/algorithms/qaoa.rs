@@ -0,0 +1,262 @@
+//! This module builds the quantum approximate optimization algorithm (QAOA) for weighted Max-Cut:
+//! split the vertices of a graph into two sides so that the total weight of the edges between
+//! the sides is as large as possible.
+//!
+//! Vertex `k` is qubit `k`, and a measured basis state assigns vertex `k` to the side given by bit
+//! `k`. The circuit alternates `p` cost layers `exp(-iγₗC)` with mixer layers `exp(-iβₗ Σ Xₖ)`,
+//! where `C` is the cut-value operator, and its angles are the parameters `gamma0`, `beta0`,
+//! `gamma1`, … to be bound with [`parameter_values`].
+
+use crate::circuit::Circuit;
+use crate::counts::Counts;
+use crate::error::QuantumError;
+use crate::parameter::Parameter;
+use crate::qubit::Qubit;
+use crate::variational::{Hamiltonian, PauliString};
+use std::collections::HashMap;
+
+/// A weighted undirected graph whose maximum cut QAOA approximates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaxCut {
+    num_vertices: usize,
+    edges: Vec<(usize, usize, f64)>,
+}
+
+impl MaxCut {
+    /// Creates the Max-Cut problem of a graph.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_vertices` - The number of vertices, one qubit each.
+    /// * `edges` - The edges as `(u, v, weight)`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MaxCut)` - The problem.
+    /// * `Err(QuantumError::QubitOutOfRange)` - If an edge names a vertex that does not exist.
+    /// * `Err(QuantumError::DuplicateQubit)` - If an edge is a self-loop.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::qaoa::MaxCut;
+    ///
+    /// let triangle = MaxCut::new(3, vec![(0, 1, 1.0), (1, 2, 1.0), (0, 2, 1.0)]).unwrap();
+    /// // Vertex 0 alone on one side cuts two edges
+    /// assert_eq!(triangle.cut_value(0b001), 2.0);
+    /// assert_eq!(triangle.max_cut(), (0b001, 2.0));
+    /// assert!(MaxCut::new(2, vec![(0, 2, 1.0)]).is_err());
+    /// ```
+    pub fn new(num_vertices: usize, edges: Vec<(usize, usize, f64)>) -> Result<Self, QuantumError> {
+        for &(u, v, _) in &edges {
+            for vertex in [u, v] {
+                if vertex >= num_vertices {
+                    return Err(QuantumError::QubitOutOfRange {
+                        qubit: vertex,
+                        num_qubits: num_vertices,
+                    });
+                }
+            }
+            if u == v {
+                return Err(QuantumError::DuplicateQubit { qubit: u });
+            }
+        }
+        Ok(MaxCut {
+            num_vertices,
+            edges,
+        })
+    }
+
+    /// Returns the number of vertices.
+    pub fn num_vertices(&self) -> usize {
+        self.num_vertices
+    }
+
+    /// Returns the edges as `(u, v, weight)`.
+    pub fn edges(&self) -> &[(usize, usize, f64)] {
+        &self.edges
+    }
+
+    /// Returns the total weight of the edges between the two sides of an assignment.
+    ///
+    /// # Arguments
+    ///
+    /// * `assignment` - The side of every vertex, bit `k` for vertex `k`.
+    pub fn cut_value(&self, assignment: usize) -> f64 {
+        self.edges
+            .iter()
+            .filter(|&&(u, v, _)| (assignment >> u ^ assignment >> v) & 1 == 1)
+            .map(|&(_, _, weight)| weight)
+            .sum()
+    }
+
+    /// Returns an assignment with the largest cut value and that value, found by trying every
+    /// assignment, as a reference for small graphs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph has more than 30 vertices.
+    pub fn max_cut(&self) -> (usize, f64) {
+        assert!(self.num_vertices <= 30, "too many vertices to enumerate");
+        (0..1usize << self.num_vertices)
+            .map(|assignment| (assignment, self.cut_value(assignment)))
+            .fold((0, f64::NEG_INFINITY), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// Returns the cut-value operator `C = Σ w (1 - Zᵤ Zᵥ) / 2`, whose expectation value is the
+    /// expected weight of the cut.
+    pub fn cost_hamiltonian(&self) -> Hamiltonian {
+        let mut terms = vec![];
+        for &(u, v, weight) in &self.edges {
+            let zz = PauliString::parse(&format!("Z{} Z{}", u, v))
+                .expect("an edge joins two distinct vertices");
+            terms.push((weight / 2.0, PauliString::identity()));
+            terms.push((-weight / 2.0, zz));
+        }
+        Hamiltonian::from_terms(terms)
+    }
+
+    /// Returns the parametric QAOA circuit with `layers` cost and mixer layers, starting from the
+    /// uniform superposition.
+    ///
+    /// Layer `l` applies `exp(-iγₗC)` as one `Rzz` rotation per edge, then `Rx(2βₗ)` on every
+    /// qubit, with the parameters named `gamma{l}` and `beta{l}`.
+    ///
+    /// # Arguments
+    ///
+    /// * `layers` - The depth `p`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::qaoa::MaxCut;
+    ///
+    /// let graph = MaxCut::new(2, vec![(0, 1, 1.0)]).unwrap();
+    /// let circuit = graph.circuit(2);
+    /// assert_eq!(circuit.parameters(), vec!["beta0", "beta1", "gamma0", "gamma1"]);
+    /// ```
+    pub fn circuit(&self, layers: usize) -> Circuit {
+        let mut circuit = Circuit::new(self.num_vertices);
+        for qubit in 0..self.num_vertices {
+            circuit.h(qubit);
+        }
+        for layer in 0..layers {
+            let gamma = Parameter::new(&format!("gamma{}", layer));
+            let beta = Parameter::new(&format!("beta{}", layer));
+            for &(u, v, weight) in &self.edges {
+                // exp(-iγw(1 - ZZ)/2) is exp(iγw ZZ/2) up to global phase, i.e. Rzz(-γw)
+                circuit.cnot(u, v).rz(v, gamma.clone() * -weight).cnot(u, v);
+            }
+            for qubit in 0..self.num_vertices {
+                circuit.rx(qubit, beta.clone() * 2.0);
+            }
+        }
+        circuit
+    }
+
+    /// Returns the QAOA state for the given angles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gammas` and `betas` have different lengths.
+    pub fn state(&self, gammas: &[f64], betas: &[f64]) -> Qubit {
+        let circuit = self
+            .circuit(gammas.len())
+            .bind_parameters(&parameter_values(gammas, betas))
+            .expect("every layer has a gamma and a beta");
+        let mut qubit = Qubit::zero_state(self.num_vertices);
+        circuit.run(&mut qubit);
+        qubit
+    }
+
+    /// Returns the expected cut value `⟨γ,β|C|γ,β⟩` of the QAOA state, the quantity to maximize
+    /// over the angles.
+    ///
+    /// # Arguments
+    ///
+    /// * `gammas` - The cost angles, one per layer.
+    /// * `betas` - The mixer angles, one per layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gammas` and `betas` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::algorithms::qaoa::MaxCut;
+    ///
+    /// // With all angles zero, every edge is cut with probability 1/2
+    /// let graph = MaxCut::new(3, vec![(0, 1, 2.0), (1, 2, 1.0)]).unwrap();
+    /// assert!((graph.expectation(&[0.0], &[0.0]) - 1.5).abs() < 1e-12);
+    /// ```
+    pub fn expectation(&self, gammas: &[f64], betas: &[f64]) -> f64 {
+        self.cost_hamiltonian()
+            .expectation(&self.state(gammas, betas))
+    }
+
+    /// Samples assignments from the QAOA state.
+    ///
+    /// # Arguments
+    ///
+    /// * `gammas` - The cost angles, one per layer.
+    /// * `betas` - The mixer angles, one per layer.
+    /// * `shots` - The number of samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gammas` and `betas` have different lengths.
+    pub fn sample(&self, gammas: &[f64], betas: &[f64], shots: usize) -> Counts {
+        self.state(gammas, betas).sample(shots)
+    }
+
+    /// Returns the sampled bitstring with the largest cut value and that value, or `None` if
+    /// `counts` is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `counts` - Samples over the graph's vertices, e.g. from [`MaxCut::sample`].
+    pub fn best_cut(&self, counts: &Counts) -> Option<(String, f64)> {
+        counts
+            .iter()
+            .filter_map(|(bitstring, _)| {
+                let assignment = usize::from_str_radix(bitstring, 2).ok()?;
+                Some((bitstring.to_string(), self.cut_value(assignment)))
+            })
+            .fold(None, |best: Option<(String, f64)>, candidate| match best {
+                Some(best) if best.1 >= candidate.1 => Some(best),
+                _ => Some(candidate),
+            })
+    }
+}
+
+/// Returns the values of the parameters of [`MaxCut::circuit`], ready for
+/// [`Circuit::bind_parameters`].
+///
+/// # Arguments
+///
+/// * `gammas` - The cost angles, one per layer.
+/// * `betas` - The mixer angles, one per layer.
+///
+/// # Panics
+///
+/// Panics if `gammas` and `betas` have different lengths.
+pub fn parameter_values(gammas: &[f64], betas: &[f64]) -> HashMap<String, f64> {
+    assert_eq!(
+        gammas.len(),
+        betas.len(),
+        "expected one gamma and one beta per layer"
+    );
+    let mut values = HashMap::new();
+    for (layer, (gamma, beta)) in gammas.iter().zip(betas).enumerate() {
+        values.insert(format!("gamma{}", layer), *gamma);
+        values.insert(format!("beta{}", layer), *beta);
+    }
+    values
+}
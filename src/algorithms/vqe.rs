@@ -0,0 +1,402 @@
+//! This module drives the variational quantum eigensolver (VQE): a classical optimizer tunes the
+//! parameters of an ansatz circuit to minimize the energy of a [`Hamiltonian`], which bounds its
+//! ground-state energy from above.
+//!
+//! The ansatz is any unitary [`Circuit`] with [`Parameter`](crate::parameter::Parameter) angles,
+//! and the optimizer is anything implementing [`Optimizer`]: [`GradientDescent`] with exact
+//! parameter-shift gradients, the gradient-free [`NelderMead`] simplex search or the stochastic
+//! [`Spsa`], which needs two energy evaluations per step whatever the number of parameters.
+
+use crate::circuit::Circuit;
+use crate::error::QuantumError;
+use crate::qubit::Qubit;
+use crate::simulator::Simulator;
+use crate::variational::{central_difference, GradientDescent, Hamiltonian, Iteration};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// A function of real parameters for an [`Optimizer`] to minimize.
+pub trait Objective {
+    /// Returns the value at `params`.
+    fn value(&self, params: &[f64]) -> f64;
+
+    /// Returns the gradient at `params`. The default estimates it with central differences.
+    fn gradient(&self, params: &[f64]) -> Vec<f64> {
+        central_difference(|params| self.value(params), params)
+    }
+}
+
+impl<F: Fn(&[f64]) -> f64> Objective for F {
+    fn value(&self, params: &[f64]) -> f64 {
+        self(params)
+    }
+}
+
+/// A classical optimizer minimizing an [`Objective`].
+pub trait Optimizer {
+    /// Minimizes `objective` starting from `initial`.
+    ///
+    /// # Arguments
+    ///
+    /// * `objective` - The function to minimize.
+    /// * `initial` - The starting parameters.
+    /// * `on_step` - Called with the starting point and the optimizer's point after every step;
+    ///   optimizers that do not compute gradients report a NaN gradient norm.
+    ///
+    /// # Returns
+    ///
+    /// * The iteration with the lowest objective.
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        initial: &[f64],
+        on_step: &mut dyn FnMut(&Iteration),
+    ) -> Iteration;
+}
+
+impl Optimizer for GradientDescent {
+    /// Follows the objective's gradient as [`GradientDescent::minimize`] does.
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        initial: &[f64],
+        on_step: &mut dyn FnMut(&Iteration),
+    ) -> Iteration {
+        let mut best: Option<Iteration> = None;
+        let last = self.descend(
+            |params| objective.value(params),
+            |params| objective.gradient(params),
+            initial,
+            |iteration| {
+                on_step(iteration);
+                if best
+                    .as_ref()
+                    .is_none_or(|best| iteration.energy < best.energy)
+                {
+                    best = Some(iteration.clone());
+                }
+            },
+        );
+        best.unwrap_or(last)
+    }
+}
+
+/// Simultaneous perturbation stochastic approximation: every step estimates the gradient from the
+/// objective at two points, shifted by a random `±1` vector in opposite directions, so its cost
+/// does not grow with the number of parameters and it tolerates noisy objectives.
+///
+/// The step size decays as `a / (k + 1 + A)^0.602` with `A = max_iterations / 10`, and the
+/// perturbation as `c / (k + 1)^0.101`, the gains recommended by Spall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spsa {
+    /// The maximum number of steps.
+    pub max_iterations: usize,
+    /// The step-size gain `a`.
+    pub learning_rate: f64,
+    /// The perturbation gain `c`.
+    pub perturbation: f64,
+    /// The seed of the random perturbations.
+    pub seed: u64,
+}
+
+impl Spsa {
+    /// Creates an optimizer with `a = 1`, `c = 0.1` and seed `0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_iterations` - The number of steps.
+    pub fn new(max_iterations: usize) -> Self {
+        Spsa {
+            max_iterations,
+            learning_rate: 1.0,
+            perturbation: 0.1,
+            seed: 0,
+        }
+    }
+
+    /// Returns a copy of the optimizer drawing its perturbations from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Optimizer for Spsa {
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        initial: &[f64],
+        on_step: &mut dyn FnMut(&Iteration),
+    ) -> Iteration {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let stability = self.max_iterations as f64 / 10.0;
+        let mut current = Iteration {
+            step: 0,
+            energy: objective.value(initial),
+            gradient_norm: f64::NAN,
+            params: initial.to_vec(),
+        };
+        on_step(&current);
+        let mut best = current.clone();
+        for k in 0..self.max_iterations {
+            let step_size = self.learning_rate / (k as f64 + 1.0 + stability).powf(0.602);
+            let shift = self.perturbation / (k as f64 + 1.0).powf(0.101);
+            let delta: Vec<f64> = (0..initial.len())
+                .map(|_| if rng.gen::<bool>() { 1.0 } else { -1.0 })
+                .collect();
+            let shifted = |sign: f64| -> Vec<f64> {
+                current
+                    .params
+                    .iter()
+                    .zip(&delta)
+                    .map(|(param, d)| param + sign * shift * d)
+                    .collect()
+            };
+            let difference = objective.value(&shifted(1.0)) - objective.value(&shifted(-1.0));
+            for (param, d) in current.params.iter_mut().zip(&delta) {
+                *param -= step_size * difference / (2.0 * shift * d);
+            }
+            current.energy = objective.value(&current.params);
+            current.step = k + 1;
+            on_step(&current);
+            if current.energy < best.energy {
+                best = current.clone();
+            }
+        }
+        best
+    }
+}
+
+/// The Nelder–Mead downhill simplex method, which needs no gradients: it moves a simplex of
+/// `n + 1` points through the parameter space by reflecting, expanding and contracting it away
+/// from its worst point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NelderMead {
+    /// The maximum number of steps.
+    pub max_iterations: usize,
+    /// The distance of the initial simplex's points from the starting point along each axis.
+    pub initial_step: f64,
+    /// The optimization stops once the objective differs by less than this across the simplex.
+    pub tolerance: f64,
+}
+
+impl NelderMead {
+    /// Creates an optimizer with an initial step of `0.5` and a tolerance of `1e-10`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_iterations` - The maximum number of steps.
+    pub fn new(max_iterations: usize) -> Self {
+        NelderMead {
+            max_iterations,
+            initial_step: 0.5,
+            tolerance: 1e-10,
+        }
+    }
+}
+
+impl Optimizer for NelderMead {
+    /// Reports the best point of the simplex after every step.
+    fn minimize(
+        &self,
+        objective: &dyn Objective,
+        initial: &[f64],
+        on_step: &mut dyn FnMut(&Iteration),
+    ) -> Iteration {
+        let n = initial.len();
+        let evaluate = |params: Vec<f64>| (objective.value(&params), params);
+        let mut simplex: Vec<(f64, Vec<f64>)> = (0..=n)
+            .map(|vertex| {
+                let mut params = initial.to_vec();
+                if vertex > 0 {
+                    params[vertex - 1] += self.initial_step;
+                }
+                evaluate(params)
+            })
+            .collect();
+        // Moves `from` by `factor` times its offset from `to`
+        let towards = |from: &[f64], to: &[f64], factor: f64| -> Vec<f64> {
+            from.iter()
+                .zip(to)
+                .map(|(a, b)| b + factor * (a - b))
+                .collect()
+        };
+
+        let mut step = 0;
+        loop {
+            simplex.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let best = Iteration {
+                step,
+                energy: simplex[0].0,
+                gradient_norm: f64::NAN,
+                params: simplex[0].1.clone(),
+            };
+            on_step(&best);
+            if step == self.max_iterations || simplex[n].0 - simplex[0].0 < self.tolerance {
+                return best;
+            }
+            step += 1;
+
+            let centroid: Vec<f64> = (0..n)
+                .map(|k| {
+                    simplex[..n]
+                        .iter()
+                        .map(|(_, params)| params[k])
+                        .sum::<f64>()
+                        / n as f64
+                })
+                .collect();
+            let (worst_value, worst) = simplex[n].clone();
+            let reflected = evaluate(towards(&worst, &centroid, -1.0));
+            if reflected.0 < simplex[0].0 {
+                let expanded = evaluate(towards(&worst, &centroid, -2.0));
+                simplex[n] = if expanded.0 < reflected.0 {
+                    expanded
+                } else {
+                    reflected
+                };
+            } else if reflected.0 < simplex[n - 1].0 {
+                simplex[n] = reflected;
+            } else {
+                // Contract outside if the reflection improved on the worst point, inside otherwise
+                let (anchor, threshold) = if reflected.0 < worst_value {
+                    (&reflected.1, reflected.0)
+                } else {
+                    (&worst, worst_value)
+                };
+                let contracted = evaluate(towards(anchor, &centroid, 0.5));
+                if contracted.0 < threshold {
+                    simplex[n] = contracted;
+                } else {
+                    let origin = simplex[0].1.clone();
+                    for vertex in simplex.iter_mut().skip(1) {
+                        *vertex = evaluate(towards(&vertex.1, &origin, 0.5));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The outcome of [`vqe`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct VqeResult {
+    /// The lowest energy found, an upper bound on the ground-state energy.
+    pub energy: f64,
+    /// The parameters reaching `energy`, ready for
+    /// [`Circuit::bind_parameters`](crate::circuit::Circuit::bind_parameters).
+    pub parameters: HashMap<String, f64>,
+    /// Every step the optimizer reported, with parameters in the order of
+    /// [`Circuit::parameters`](crate::circuit::Circuit::parameters).
+    pub history: Vec<Iteration>,
+}
+
+/// The energy of the ansatz state, differentiated with the parameter-shift rule.
+struct Energy<'a> {
+    hamiltonian: &'a Hamiltonian,
+    ansatz: &'a Circuit,
+    names: Vec<String>,
+}
+
+impl Energy<'_> {
+    fn bind(&self, params: &[f64]) -> HashMap<String, f64> {
+        self.names
+            .iter()
+            .cloned()
+            .zip(params.iter().copied())
+            .collect()
+    }
+}
+
+impl Objective for Energy<'_> {
+    fn value(&self, params: &[f64]) -> f64 {
+        let circuit = self
+            .ansatz
+            .bind_parameters(&self.bind(params))
+            .expect("every parameter of the ansatz is bound");
+        let mut qubit = Qubit::zero_state(circuit.num_qubits());
+        circuit.run(&mut qubit);
+        self.hamiltonian.expectation(&qubit)
+    }
+
+    fn gradient(&self, params: &[f64]) -> Vec<f64> {
+        Simulator::gradient(self.ansatz, self.hamiltonian, &self.bind(params))
+    }
+}
+
+/// Minimizes the energy `⟨ψ(θ)|H|ψ(θ)⟩` of the ansatz state `|ψ(θ)⟩`, prepared from `|0…0⟩`.
+///
+/// # Arguments
+///
+/// * `hamiltonian` - The Hamiltonian `H`, e.g. built from [`PauliString`](crate::variational::PauliString)s
+///   with [`Hamiltonian::from_terms`].
+/// * `ansatz` - A unitary circuit with parametric rotations.
+/// * `optimizer` - The classical optimizer.
+/// * `initial` - The starting parameters, in the order of
+///   [`Circuit::parameters`](crate::circuit::Circuit::parameters).
+///
+/// # Returns
+///
+/// * `Ok(VqeResult)` - The lowest energy, its parameters and the convergence history.
+/// * `Err(QuantumError::DimensionMismatch)` - If `initial` does not have one value per parameter.
+/// * `Err(QuantumError::QubitOutOfRange)` - If the Hamiltonian acts on more qubits than the
+///   ansatz has.
+/// * `Err(QuantumError::Unsupported)` - If the ansatz measures, resets or conditions on classical
+///   bits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::algorithms::vqe::{vqe, NelderMead};
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::parameter::Parameter;
+/// use quantum_simulator::variational::Hamiltonian;
+///
+/// // The ground state of X0 is |−⟩ with energy -1
+/// let mut ansatz = Circuit::new(1);
+/// ansatz.ry(0, Parameter::new("theta"));
+/// let hamiltonian = Hamiltonian::parse("X0").unwrap();
+///
+/// let result = vqe(&hamiltonian, &ansatz, &NelderMead::new(200), &[0.1]).unwrap();
+/// assert!((result.energy + 1.0).abs() < 1e-6);
+/// assert!(result.parameters["theta"].sin() < 0.0);
+/// ```
+pub fn vqe<O: Optimizer + ?Sized>(
+    hamiltonian: &Hamiltonian,
+    ansatz: &Circuit,
+    optimizer: &O,
+    initial: &[f64],
+) -> Result<VqeResult, QuantumError> {
+    let names = ansatz.parameters();
+    if initial.len() != names.len() {
+        return Err(QuantumError::DimensionMismatch {
+            expected: names.len(),
+            found: initial.len(),
+        });
+    }
+    if hamiltonian.num_qubits() > ansatz.num_qubits() {
+        return Err(QuantumError::QubitOutOfRange {
+            qubit: hamiltonian.num_qubits() - 1,
+            num_qubits: ansatz.num_qubits(),
+        });
+    }
+    if !ansatz.is_unitary() {
+        return Err(QuantumError::Unsupported {
+            feature: "VQE with a non-unitary ansatz".to_string(),
+        });
+    }
+
+    let energy = Energy {
+        hamiltonian,
+        ansatz,
+        names,
+    };
+    let mut history = vec![];
+    let best = optimizer.minimize(&energy, initial, &mut |step| history.push(step.clone()));
+    Ok(VqeResult {
+        energy: best.energy,
+        parameters: energy.bind(&best.params),
+        history,
+    })
+}
@@ -0,0 +1,1218 @@
+//! This module provides analyses of circuits and states: uncomputation checks run by simulating
+//! circuits, light-cone reduction of circuits before simulation, local expectation values on the
+//! reduced register, qubit lifetimes, per-qubit gate activity and register compaction,
+//! entanglement metrics computed from state vectors and density matrices, two-qubit correlations
+//! for Bell tests, classical shadows of circuit outputs, fidelities and distances between states
+//! and gates, and comparisons of states and circuits up to global phase.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::counts::Counts;
+use crate::density::DensityMatrix;
+use crate::error::QuantumError;
+use crate::gates::{ry, Gate};
+use crate::linalg;
+use crate::qubit::Qubit;
+use crate::rng;
+use crate::shadows::ClassicalShadow;
+use crate::simulator::Simulator;
+use crate::tolerance;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+
+/// An `UncomputationFailure` records an ancilla that was not returned to `|0⟩`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UncomputationFailure {
+    /// The number of instructions applied when the check failed.
+    pub checkpoint: usize,
+    /// The ancilla qubit left in a non-zero state.
+    pub ancilla: usize,
+    /// The index of the random input state that exposed the failure.
+    pub trial: usize,
+    /// The probability of measuring the ancilla as `1` at the checkpoint.
+    pub population: f64,
+}
+
+/// Checks that the ancilla qubits are back in `|0⟩` at each checkpoint for random inputs.
+///
+/// Each trial prepares a random state on the non-ancilla qubits with every ancilla in `|0⟩` and
+/// runs the circuit, checking after `checkpoints[k]` instructions and at the end of the circuit
+/// that each ancilla has a `1` population of at most `tolerance`. Because the check is on the
+/// ancilla alone, an ancilla that is left entangled with the data qubits also fails.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to check.
+/// * `ancillas` - The qubits that must be uncomputed.
+/// * `checkpoints` - Instruction counts after which the ancillas must already be clean.
+/// * `trials` - The number of random input states to try.
+/// * `tolerance` - The largest acceptable `1` population of an ancilla.
+///
+/// # Returns
+///
+/// * `Ok(())` if every check passed, otherwise the first failure found.
+///
+/// # Panics
+///
+/// Panics if the circuit is not unitary (see [`Circuit::is_unitary`]).
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::verify_uncomputation;
+/// use quantum_simulator::circuit::Circuit;
+///
+/// // Compute x0 AND x1 into ancilla 2, copy it to qubit 3, then uncompute.
+/// let mut circuit = Circuit::new(4);
+/// circuit.toffoli(0, 1, 2).cnot(2, 3).toffoli(0, 1, 2);
+/// assert!(verify_uncomputation(&circuit, &[2], &[], 10, 1e-10).is_ok());
+///
+/// // Forgetting the uncompute step leaves the ancilla dirty.
+/// let mut buggy = Circuit::new(4);
+/// buggy.toffoli(0, 1, 2).cnot(2, 3);
+/// let failure = verify_uncomputation(&buggy, &[2], &[], 10, 1e-10).unwrap_err();
+/// assert_eq!(failure.ancilla, 2);
+/// ```
+pub fn verify_uncomputation(
+    circuit: &Circuit,
+    ancillas: &[usize],
+    checkpoints: &[usize],
+    trials: usize,
+    tolerance: f64,
+) -> Result<(), UncomputationFailure> {
+    rng::with_rng(|rng| {
+        verify_uncomputation_with_rng(circuit, ancillas, checkpoints, trials, tolerance, rng)
+    })
+}
+
+/// Checks uncomputation like [`verify_uncomputation`], drawing the input states from `rng`.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to check.
+/// * `ancillas` - The qubits that must be uncomputed.
+/// * `checkpoints` - Instruction counts after which the ancillas must already be clean.
+/// * `trials` - The number of random input states to try.
+/// * `tolerance` - The largest acceptable `1` population of an ancilla.
+/// * `rng` - The random number generator to draw from.
+///
+/// # Panics
+///
+/// Panics if the circuit is not unitary (see [`Circuit::is_unitary`]).
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::verify_uncomputation_with_rng;
+/// use quantum_simulator::circuit::Circuit;
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+///
+/// let mut circuit = Circuit::new(3);
+/// circuit.cnot(0, 2).h(1).cnot(0, 2);
+/// let mut rng = StdRng::seed_from_u64(7);
+/// assert!(verify_uncomputation_with_rng(&circuit, &[2], &[1], 5, 1e-10, &mut rng).is_err());
+/// assert!(verify_uncomputation_with_rng(&circuit, &[2], &[], 5, 1e-10, &mut rng).is_ok());
+/// ```
+pub fn verify_uncomputation_with_rng<R: Rng + ?Sized>(
+    circuit: &Circuit,
+    ancillas: &[usize],
+    checkpoints: &[usize],
+    trials: usize,
+    tolerance: f64,
+    rng: &mut R,
+) -> Result<(), UncomputationFailure> {
+    let instructions = circuit.instructions();
+    let ancilla_mask: usize = ancillas.iter().map(|&ancilla| 1 << ancilla).sum();
+
+    for trial in 0..trials {
+        let mut qubit = random_state(circuit.num_qubits(), ancilla_mask, rng);
+        for (applied, instruction) in instructions.iter().enumerate() {
+            instruction.apply(&mut qubit);
+            let applied = applied + 1;
+            if applied == instructions.len() || checkpoints.contains(&applied) {
+                check_ancillas(&qubit, ancillas, applied, trial, tolerance)?;
+            }
+        }
+        if instructions.is_empty() {
+            check_ancillas(&qubit, ancillas, 0, trial, tolerance)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the first ancilla with a `1` population above `tolerance`.
+fn check_ancillas(
+    qubit: &Qubit,
+    ancillas: &[usize],
+    checkpoint: usize,
+    trial: usize,
+    tolerance: f64,
+) -> Result<(), UncomputationFailure> {
+    for &ancilla in ancillas {
+        let population: f64 = qubit
+            .state
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| (index >> ancilla) & 1 == 1)
+            .map(|(_, amplitude)| amplitude.norm_sqr())
+            .sum();
+        if population > tolerance {
+            return Err(UncomputationFailure {
+                checkpoint,
+                ancilla,
+                trial,
+                population,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Draws a Haar-random state on the qubits outside `zero_mask`, leaving those qubits in `|0⟩`.
+fn random_state<R: Rng + ?Sized>(num_qubits: usize, zero_mask: usize, rng: &mut R) -> Qubit {
+    let mut state: Vec<Complex<f64>> = (0..1usize << num_qubits)
+        .map(|index| {
+            if index & zero_mask != 0 {
+                return Complex::new(0.0, 0.0);
+            }
+            // Box-Muller transform for a complex Gaussian amplitude.
+            let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+            Complex::from_polar(radius, 2.0 * PI * rng.gen::<f64>())
+        })
+        .collect();
+    let norm = state.iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt();
+    for amplitude in state.iter_mut() {
+        *amplitude /= norm;
+    }
+    Qubit::from_state(state)
+}
+
+/// Returns the reduced density matrix of the given qubits, tracing out the rest of the register.
+///
+/// Bit `j` of the result's basis index corresponds to `qubits[j]`. Unlike tracing a full
+/// [`DensityMatrix`], this works directly on the state vector and never builds the `2ⁿ × 2ⁿ`
+/// matrix.
+///
+/// # Arguments
+///
+/// * `qubit` - The pure state of the whole register.
+/// * `qubits` - The qubits to keep.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::reduced_density_matrix;
+/// use quantum_simulator::qubit::Qubit;
+/// use num_complex::Complex;
+///
+/// // Either half of a Bell pair is maximally mixed
+/// let h = 1.0 / 2.0_f64.sqrt();
+/// let zero = Complex::new(0.0, 0.0);
+/// let bell = Qubit::from_state(vec![Complex::new(h, 0.0), zero, zero, Complex::new(h, 0.0)]);
+/// let rho = reduced_density_matrix(&bell, &[1]);
+/// assert!((rho.matrix[0][0].re - 0.5).abs() < 1e-12);
+/// assert!(rho.matrix[0][1].norm() < 1e-12);
+/// ```
+pub fn reduced_density_matrix(qubit: &Qubit, qubits: &[usize]) -> DensityMatrix {
+    let (offsets, keep_mask) = linalg::local_offsets(qubits);
+    let mut matrix = linalg::zeros(offsets.len(), offsets.len());
+    for base in (0..qubit.state.len()).filter(|index| index & keep_mask == 0) {
+        for (row, row_offset) in matrix.iter_mut().zip(&offsets) {
+            let amplitude = qubit.state[base + row_offset];
+            for (entry, col_offset) in row.iter_mut().zip(&offsets) {
+                *entry += amplitude * qubit.state[base + col_offset].conj();
+            }
+        }
+    }
+    DensityMatrix::from_matrix(matrix)
+}
+
+/// Returns the von Neumann entanglement entropy, in bits, across the cut between `qubits` and
+/// the rest of the register.
+///
+/// # Arguments
+///
+/// * `qubit` - The pure state of the whole register.
+/// * `qubits` - One side of the cut.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::entanglement_entropy;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// let mut circuit = Circuit::new(3);
+/// circuit.h(0).cnot(0, 1);
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+/// initial_state[0] = Complex::new(1.0, 0.0);
+/// let state = Simulator::run(&circuit, &initial_state);
+///
+/// assert!((entanglement_entropy(&state, &[0]) - 1.0).abs() < 1e-9);
+/// assert!(entanglement_entropy(&state, &[0, 1]).abs() < 1e-9);
+/// ```
+pub fn entanglement_entropy(qubit: &Qubit, qubits: &[usize]) -> f64 {
+    reduced_density_matrix(qubit, qubits).von_neumann_entropy()
+}
+
+/// Returns the Schmidt coefficients of the state across the cut between `qubits` and the rest of
+/// the register, in decreasing order.
+///
+/// The squares of the coefficients sum to one, and the state is a product across the cut exactly
+/// when only one of them is non-zero.
+///
+/// # Arguments
+///
+/// * `qubit` - The pure state of the whole register.
+/// * `qubits` - One side of the cut.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::schmidt_coefficients;
+/// use quantum_simulator::qubit::Qubit;
+/// use num_complex::Complex;
+///
+/// let zero = Complex::new(0.0, 0.0);
+/// let state = Qubit::from_state(vec![Complex::new(0.8, 0.0), zero, zero, Complex::new(0.6, 0.0)]);
+/// let coefficients = schmidt_coefficients(&state, &[0]);
+/// assert!((coefficients[0] - 0.8).abs() < 1e-9 && (coefficients[1] - 0.6).abs() < 1e-9);
+/// ```
+pub fn schmidt_coefficients(qubit: &Qubit, qubits: &[usize]) -> Vec<f64> {
+    let (eigenvalues, _) = linalg::eigh(&reduced_density_matrix(qubit, qubits).matrix);
+    eigenvalues
+        .iter()
+        .rev()
+        .map(|value| value.max(0.0).sqrt())
+        .collect()
+}
+
+/// Returns the Wootters concurrence of a two-qubit state, from `0` for separable states to `1`
+/// for maximally entangled ones.
+///
+/// # Arguments
+///
+/// * `rho` - The two-qubit density matrix, e.g. from [`reduced_density_matrix`].
+///
+/// # Panics
+///
+/// Panics if `rho` is not a two-qubit density matrix.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::{concurrence, reduced_density_matrix};
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::simulator::Simulator;
+/// use num_complex::Complex;
+///
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1);
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+/// initial_state[0] = Complex::new(1.0, 0.0);
+/// let bell = Simulator::run(&circuit, &initial_state);
+/// assert!((concurrence(&reduced_density_matrix(&bell, &[0, 1])) - 1.0).abs() < 1e-6);
+/// ```
+pub fn concurrence(rho: &DensityMatrix) -> f64 {
+    assert_eq!(rho.matrix.len(), 4, "concurrence needs a two-qubit state");
+
+    // ρ̃ = (Y ⊗ Y) ρ* (Y ⊗ Y); Y ⊗ Y is real and flips both bits with sign -1 when they agree
+    let flip = |index: usize| if index == 0 || index == 3 { -1.0 } else { 1.0 };
+    let spin_flipped: Vec<Vec<Complex<f64>>> = (0..4)
+        .map(|i| {
+            (0..4)
+                .map(|j| rho.matrix[3 - i][3 - j].conj() * flip(i) * flip(j))
+                .collect()
+        })
+        .collect();
+
+    let root = linalg::positive_sqrt(&rho.matrix);
+    let product = linalg::matmul(&linalg::matmul(&root, &spin_flipped), &root);
+    let (eigenvalues, _) = linalg::eigh(&product);
+    let lambdas: Vec<f64> = eigenvalues
+        .iter()
+        .rev()
+        .map(|value| value.max(0.0).sqrt())
+        .collect();
+    (lambdas[0] - lambdas[1] - lambdas[2] - lambdas[3]).max(0.0)
+}
+
+/// Returns the Bloch vector `(⟨X⟩, ⟨Y⟩, ⟨Z⟩)` of one qubit of a register.
+///
+/// The vector is read off the qubit's reduced density matrix, so it has unit length when the
+/// qubit is unentangled and shrinks towards the origin as it becomes entangled with the rest of
+/// the register.
+///
+/// # Arguments
+///
+/// * `qubit` - The pure state of the whole register.
+/// * `qubit_index` - The qubit whose Bloch vector is returned.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::bloch_vector;
+/// use quantum_simulator::qubit::Qubit;
+/// use num_complex::Complex;
+///
+/// assert_eq!(bloch_vector(&Qubit::new(), 0), (0.0, 0.0, 1.0));
+///
+/// // Each half of a Bell pair sits at the centre of the sphere
+/// let h = 1.0 / 2.0_f64.sqrt();
+/// let zero = Complex::new(0.0, 0.0);
+/// let bell = Qubit::from_state(vec![Complex::new(h, 0.0), zero, zero, Complex::new(h, 0.0)]);
+/// let (x, y, z) = bloch_vector(&bell, 1);
+/// assert!(x.abs() < 1e-12 && y.abs() < 1e-12 && z.abs() < 1e-12);
+/// ```
+pub fn bloch_vector(qubit: &Qubit, qubit_index: usize) -> (f64, f64, f64) {
+    let rho = reduced_density_matrix(qubit, &[qubit_index]).matrix;
+    (
+        2.0 * rho[0][1].re,
+        2.0 * rho[1][0].im,
+        rho[0][0].re - rho[1][1].re,
+    )
+}
+
+/// Returns the correlation `E(a, b)` of two qubits measured along directions in the X-Z plane of
+/// the Bloch sphere: the expectation of the product of their `±1` outcomes, where a qubit
+/// measured at angle `θ` from the Z axis is measured with `cos θ·Z + sin θ·X`.
+///
+/// For the Bell state `(|00⟩ + |11⟩)/√2`, `E(a, b) = cos(a - b)`.
+///
+/// # Arguments
+///
+/// * `qubit` - The state.
+/// * `qubits` - The two qubits to correlate.
+/// * `a` - The measurement angle of the first qubit.
+/// * `b` - The measurement angle of the second qubit.
+///
+/// # Panics
+///
+/// Panics if either qubit is not in the state.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::correlation;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::qubit::Qubit;
+/// use std::f64::consts::FRAC_PI_4;
+///
+/// let mut bell = Qubit::zero_state(2);
+/// Circuit::new(2).h(0).cnot(0, 1).run(&mut bell);
+/// assert!((correlation(&bell, (0, 1), 0.0, FRAC_PI_4) - FRAC_PI_4.cos()).abs() < 1e-12);
+/// ```
+pub fn correlation(qubit: &Qubit, qubits: (usize, usize), a: f64, b: f64) -> f64 {
+    let rotated = rotate_for_correlation(qubit, qubits, a, b);
+    rotated
+        .state
+        .iter()
+        .enumerate()
+        .map(|(index, amplitude)| {
+            let parity = ((index >> qubits.0) ^ (index >> qubits.1)) & 1;
+            if parity == 0 {
+                amplitude.norm_sqr()
+            } else {
+                -amplitude.norm_sqr()
+            }
+        })
+        .sum()
+}
+
+/// Estimates the correlation [`correlation`] from `shots` samples, like
+/// [`sample_correlation_with_rng`] with the thread-local generator.
+pub fn sample_correlation(
+    qubit: &Qubit,
+    qubits: (usize, usize),
+    a: f64,
+    b: f64,
+    shots: usize,
+) -> f64 {
+    rng::with_rng(|rng| sample_correlation_with_rng(qubit, qubits, a, b, shots, rng))
+}
+
+/// Estimates the correlation [`correlation`] from `shots` samples, as an experiment would: both
+/// qubits are rotated into their measurement directions and measured, and the outcomes are
+/// passed to [`correlation_from_counts`].
+///
+/// # Arguments
+///
+/// * `qubit` - The state, which is not collapsed.
+/// * `qubits` - The two qubits to correlate.
+/// * `a` - The measurement angle of the first qubit.
+/// * `b` - The measurement angle of the second qubit.
+/// * `shots` - The number of samples.
+/// * `rng` - The random number generator to draw from.
+///
+/// # Panics
+///
+/// Panics if either qubit is not in the state.
+pub fn sample_correlation_with_rng<R: Rng + ?Sized>(
+    qubit: &Qubit,
+    qubits: (usize, usize),
+    a: f64,
+    b: f64,
+    shots: usize,
+    rng: &mut R,
+) -> f64 {
+    let counts = rotate_for_correlation(qubit, qubits, a, b).sample_with_rng(shots, rng);
+    correlation_from_counts(&counts, qubits)
+}
+
+/// Returns the correlation of two bits in measured counts, the fraction of shots in which they
+/// agree minus the fraction in which they differ, or `0` for no shots.
+///
+/// # Arguments
+///
+/// * `counts` - The measured bitstrings.
+/// * `bits` - The two bits to correlate, where bit `k` is the `k`-th character from the right.
+///
+/// # Panics
+///
+/// Panics if a bitstring is too short to hold both bits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::correlation_from_counts;
+/// use quantum_simulator::counts::Counts;
+///
+/// let mut counts = Counts::new();
+/// counts.record_many(0b00, 2, 45);
+/// counts.record_many(0b11, 2, 45);
+/// counts.record_many(0b01, 2, 10);
+/// assert!((correlation_from_counts(&counts, (0, 1)) - 0.8).abs() < 1e-12);
+/// ```
+pub fn correlation_from_counts(counts: &Counts, bits: (usize, usize)) -> f64 {
+    if counts.shots() == 0 {
+        return 0.0;
+    }
+    let sum: i64 = counts
+        .iter()
+        .map(|(bitstring, count)| {
+            let bit = |k: usize| bitstring.as_bytes()[bitstring.len() - 1 - k];
+            if bit(bits.0) == bit(bits.1) {
+                count as i64
+            } else {
+                -(count as i64)
+            }
+        })
+        .sum();
+    sum as f64 / counts.shots() as f64
+}
+
+/// Rotates two qubits so that measuring them in the computational basis measures them at angles
+/// `a` and `b` in the X-Z plane.
+fn rotate_for_correlation(qubit: &Qubit, qubits: (usize, usize), a: f64, b: f64) -> Qubit {
+    let mut rotated = qubit.clone();
+    ry(-a).apply_to(&mut rotated, &[qubits.0]);
+    ry(-b).apply_to(&mut rotated, &[qubits.1]);
+    rotated
+}
+
+/// Takes a classical shadow of the state a circuit prepares from `|0…0⟩`: each of the
+/// `num_snapshots` shots measures every qubit in a random Pauli basis, i.e. after a random
+/// single-qubit Clifford, and keeps the bases with the outcome.
+///
+/// The shadow estimates the expectations of Pauli strings and weighted sums of them, with
+/// standard errors and confidence intervals, from a number of snapshots that grows with the
+/// weight of the observables rather than with `3ⁿ` as for full tomography. A circuit with
+/// mid-circuit measurements is run once and its outcomes fix the measured branch.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit preparing the state.
+/// * `num_snapshots` - The number of randomized measurements.
+/// * `seed` - The seed of the random bases, outcomes and mid-circuit measurements.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::classical_shadows;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::variational::{Hamiltonian, PauliString};
+///
+/// let mut ghz = Circuit::new(3);
+/// ghz.h(0).cnot(0, 1).cnot(1, 2);
+/// let shadow = classical_shadows(&ghz, 2000, 11);
+///
+/// let zz = shadow.expectation(&PauliString::parse("Z0 Z2").unwrap());
+/// let interval = zz.confidence_interval(4.0);
+/// assert!(interval.lo <= 1.0 && 1.0 <= interval.hi);
+///
+/// let energy = shadow.observable(&Hamiltonian::parse("-Z0 Z1 - Z1 Z2").unwrap());
+/// assert!((energy.value + 2.0).abs() < 4.0 * energy.standard_error);
+/// ```
+pub fn classical_shadows(circuit: &Circuit, num_snapshots: usize, seed: u64) -> ClassicalShadow {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut state = Qubit::zero_state(circuit.num_qubits());
+    circuit.run_with_rng(&mut state, &mut rng);
+    ClassicalShadow::collect_with_rng(&state, num_snapshots, 1, &mut rng)
+}
+
+/// Returns the fidelity `|⟨a|b⟩|²` of two pure states, `1` for the same state up to global phase
+/// and `0` for orthogonal ones.
+///
+/// For mixed states, see [`DensityMatrix::fidelity`].
+///
+/// # Arguments
+///
+/// * `a` - One state.
+/// * `b` - The other state, on the same number of qubits.
+///
+/// # Panics
+///
+/// Panics if the states have different dimensions.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::state_fidelity;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::qubit::Qubit;
+///
+/// let mut plus = Qubit::new();
+/// Circuit::new(1).h(0).run(&mut plus);
+/// assert!((state_fidelity(&Qubit::new(), &plus) - 0.5).abs() < 1e-12);
+/// assert!((state_fidelity(&plus, &plus) - 1.0).abs() < 1e-12);
+/// ```
+pub fn state_fidelity(a: &Qubit, b: &Qubit) -> f64 {
+    assert_eq!(
+        a.state.len(),
+        b.state.len(),
+        "states have different dimensions"
+    );
+    a.state
+        .iter()
+        .zip(&b.state)
+        .map(|(x, y)| x.conj() * y)
+        .sum::<Complex<f64>>()
+        .norm_sqr()
+}
+
+/// Returns `true` if two states are equal up to global phase: after rotating `b` by the phase of
+/// `⟨b|a⟩`, no amplitude differs from `a` by more than `tolerance`.
+///
+/// Use it instead of comparing amplitudes directly in tests, where a circuit that differs only by
+/// a physically irrelevant phase, e.g. `Rz(θ)` in place of a phase gate, should still pass.
+///
+/// # Arguments
+///
+/// * `a` - One state.
+/// * `b` - The other state.
+/// * `tolerance` - The largest difference accepted in any amplitude.
+///
+/// # Returns
+///
+/// * `false` for states of different dimensions.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::approx_eq_up_to_global_phase;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::qubit::Qubit;
+/// use std::f64::consts::PI;
+///
+/// let mut with_s = Qubit::plus_state(1);
+/// Circuit::new(1).s(0).run(&mut with_s);
+/// let mut with_rz = Qubit::plus_state(1);
+/// Circuit::new(1).rz(0, PI / 2.0).run(&mut with_rz);
+///
+/// assert_ne!(with_s, with_rz);
+/// assert!(approx_eq_up_to_global_phase(&with_s, &with_rz, 1e-12));
+/// assert!(!approx_eq_up_to_global_phase(&with_s, &Qubit::plus_state(1), 1e-6));
+/// ```
+pub fn approx_eq_up_to_global_phase(a: &Qubit, b: &Qubit, tolerance: f64) -> bool {
+    a.state.len() == b.state.len() && equal_up_to_phase(&a.state, &b.state, tolerance)
+}
+
+/// Widest circuits [`circuit_equivalence`] compares; their unitaries have `4^n` entries.
+pub const MAX_EQUIVALENCE_QUBITS: usize = 12;
+
+/// Returns whether two circuits implement the same unitary up to global phase, so one can
+/// replace the other, e.g. a circuit and its optimized or transpiled version.
+///
+/// Both unitaries are built column by column by running each circuit on every basis state,
+/// which takes `2^n` runs, so this is meant for small registers.
+///
+/// # Arguments
+///
+/// * `a` - One circuit.
+/// * `b` - The other circuit.
+///
+/// # Returns
+///
+/// * `Ok(true)` if the unitaries agree up to global phase in every entry to within the
+///   [`equality`](crate::tolerance::Tolerances::equality) tolerance, and `Ok(false)` if they
+///   differ or act on different numbers of qubits.
+/// * `Err(QuantumError::Unsupported)` if a circuit measures, resets or conditions on classical
+///   bits, or has more than [`MAX_EQUIVALENCE_QUBITS`] qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::circuit_equivalence;
+/// use quantum_simulator::circuit::Circuit;
+///
+/// // H·Z·H = X, and CNOT in the Hadamard basis is CNOT with control and target swapped
+/// let mut hzh = Circuit::new(1);
+/// hzh.h(0).z(0).h(0);
+/// let mut x = Circuit::new(1);
+/// x.x(0);
+/// assert!(circuit_equivalence(&hzh, &x).unwrap());
+///
+/// let mut flipped = Circuit::new(2);
+/// flipped.h(0).h(1).cnot(0, 1).h(0).h(1);
+/// let mut cnot = Circuit::new(2);
+/// cnot.cnot(1, 0);
+/// assert!(circuit_equivalence(&flipped, &cnot).unwrap());
+/// assert!(!circuit_equivalence(&flipped, &Circuit::new(2)).unwrap());
+/// ```
+pub fn circuit_equivalence(a: &Circuit, b: &Circuit) -> Result<bool, QuantumError> {
+    for circuit in [a, b] {
+        if !circuit.is_unitary() {
+            return Err(QuantumError::Unsupported {
+                feature: "equivalence of circuits with measurements, resets or conditions"
+                    .to_string(),
+            });
+        }
+        if circuit.num_qubits() > MAX_EQUIVALENCE_QUBITS {
+            return Err(QuantumError::Unsupported {
+                feature: format!(
+                    "equivalence of {}-qubit circuits (at most {})",
+                    circuit.num_qubits(),
+                    MAX_EQUIVALENCE_QUBITS
+                ),
+            });
+        }
+    }
+    if a.num_qubits() != b.num_qubits() {
+        return Ok(false);
+    }
+    let num_qubits = a.num_qubits();
+    Ok(equal_up_to_phase(
+        &a.to_unitary(num_qubits).concat(),
+        &b.to_unitary(num_qubits).concat(),
+        tolerance::current().equality,
+    ))
+}
+
+/// Returns `true` if `b`, rotated by the phase of `⟨b|a⟩`, is within `tolerance` of `a` in every
+/// entry. The phase of the overlap is the one minimizing the distance, so no entry has to be
+/// picked as a reference.
+fn equal_up_to_phase(a: &[Complex<f64>], b: &[Complex<f64>], tolerance: f64) -> bool {
+    let overlap: Complex<f64> = b.iter().zip(a).map(|(y, x)| y.conj() * x).sum();
+    let phase = if overlap.norm() > 0.0 {
+        overlap / overlap.norm()
+    } else {
+        Complex::new(1.0, 0.0)
+    };
+    a.iter()
+        .zip(b)
+        .all(|(x, y)| (x - phase * y).norm() <= tolerance)
+}
+
+/// Returns the trace distance `½ Tr|ρ - σ|` of two density matrices, from `0` for equal states to
+/// `1` for perfectly distinguishable ones.
+///
+/// It bounds how differently the two states can behave under any measurement: no outcome's
+/// probability differs by more than the trace distance.
+///
+/// # Arguments
+///
+/// * `rho` - One state.
+/// * `sigma` - The other state, on the same number of qubits.
+///
+/// # Panics
+///
+/// Panics if the matrices have different dimensions.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::trace_distance;
+/// use quantum_simulator::density::DensityMatrix;
+/// use quantum_simulator::gates::pauli_x;
+///
+/// let zero = DensityMatrix::new(1);
+/// let mut one = DensityMatrix::new(1);
+/// one.apply_gate(&pauli_x(), &[0]);
+/// assert!((trace_distance(&zero, &one) - 1.0).abs() < 1e-12);
+/// assert!(trace_distance(&zero, &zero).abs() < 1e-12);
+/// ```
+pub fn trace_distance(rho: &DensityMatrix, sigma: &DensityMatrix) -> f64 {
+    assert_eq!(
+        rho.matrix.len(),
+        sigma.matrix.len(),
+        "density matrices have different dimensions"
+    );
+    let difference: Vec<Vec<Complex<f64>>> = rho
+        .matrix
+        .iter()
+        .zip(&sigma.matrix)
+        .map(|(a, b)| a.iter().zip(b).map(|(x, y)| x - y).collect())
+        .collect();
+    let (eigenvalues, _) = linalg::eigh(&difference);
+    eigenvalues.iter().map(|value| value.abs()).sum::<f64>() / 2.0
+}
+
+/// Returns the process fidelity `|Tr(U†V)|² / d²` of two gates on the same qubits, `1` exactly
+/// when they are equal up to global phase.
+///
+/// The average fidelity over all input states is `(d·F + 1) / (d + 1)` for process fidelity `F`
+/// and dimension `d`.
+///
+/// # Arguments
+///
+/// * `u` - One gate, e.g. the ideal one.
+/// * `v` - The other gate, e.g. an optimized or approximate implementation.
+///
+/// # Panics
+///
+/// Panics if the gates have different dimensions.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::gate_fidelity;
+/// use quantum_simulator::gates::{pauli_z, rz};
+/// use std::f64::consts::PI;
+///
+/// // Rz(π) is Z up to global phase
+/// assert!((gate_fidelity(&pauli_z(), &rz(PI)) - 1.0).abs() < 1e-12);
+/// assert!((gate_fidelity(&rz(0.0), &rz(0.2)) - 0.1f64.cos().powi(2)).abs() < 1e-12);
+/// ```
+pub fn gate_fidelity(u: &Gate, v: &Gate) -> f64 {
+    let dimension = u.matrix.len();
+    assert_eq!(dimension, v.matrix.len(), "gates have different dimensions");
+    let overlap: Complex<f64> = u
+        .matrix
+        .iter()
+        .zip(v.matrix.iter())
+        .flat_map(|(a, b)| a.iter().zip(b).map(|(x, y)| x.conj() * y))
+        .sum();
+    overlap.norm_sqr() / (dimension * dimension) as f64
+}
+
+/// The part of a circuit inside the causal cone of a set of measured qubits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightCone {
+    /// The reduced circuit, acting only on the qubits of the cone.
+    pub circuit: Circuit,
+    /// The original label of each qubit of `circuit`, in increasing order.
+    pub qubits: Vec<usize>,
+}
+
+impl LightCone {
+    /// Returns the qubit of the reduced circuit that stands for an original qubit, if it is in
+    /// the cone.
+    pub fn position(&self, qubit: usize) -> Option<usize> {
+        self.qubits.binary_search(&qubit).ok()
+    }
+}
+
+/// Removes every instruction outside the causal cone of the measured qubits.
+///
+/// Walking the circuit backwards, an instruction is kept if it touches a qubit already in the
+/// cone, and then pulls all of its qubits into the cone. Measurements whose classical bit feeds
+/// a kept conditional operation are kept too. Everything else acts on qubits that never
+/// influence the measured ones, so dropping it leaves their joint distribution unchanged for
+/// any product initial state. The surviving qubits are relabeled compactly, so the reduced
+/// circuit can be simulated on `2^k` amplitudes instead of `2^n`.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to reduce.
+/// * `measured` - The qubits whose outcomes are of interest.
+///
+/// # Panics
+///
+/// Panics if a measured qubit is outside the circuit.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::light_cone;
+/// use quantum_simulator::circuit::Circuit;
+///
+/// let mut circuit = Circuit::new(6);
+/// circuit.h(0).cnot(0, 1).h(3).cnot(3, 4).cnot(4, 5).x(2);
+///
+/// let cone = light_cone(&circuit, &[1]);
+/// assert_eq!(cone.qubits, vec![0, 1]);
+/// assert_eq!(cone.circuit.instructions().len(), 2);
+/// assert_eq!(cone.position(1), Some(1));
+/// ```
+pub fn light_cone(circuit: &Circuit, measured: &[usize]) -> LightCone {
+    let mut in_cone = vec![false; circuit.num_qubits()];
+    for &qubit in measured {
+        assert!(
+            qubit < circuit.num_qubits(),
+            "measured qubit {} is outside the {}-qubit circuit",
+            qubit,
+            circuit.num_qubits()
+        );
+        in_cone[qubit] = true;
+    }
+    let mut needed_clbits = vec![false; circuit.num_clbits()];
+    let mut kept = vec![];
+    for instruction in circuit.instructions().iter().rev() {
+        let touches_cone = instruction.qubits.iter().any(|&qubit| in_cone[qubit]);
+        let feeds_cone = match instruction.operation {
+            Operation::Measure(clbit) => needed_clbits[clbit],
+            _ => false,
+        };
+        if !touches_cone && !feeds_cone {
+            continue;
+        }
+        if let Operation::Conditional { clbit, .. } = instruction.operation {
+            needed_clbits[clbit] = true;
+        }
+        for &qubit in &instruction.qubits {
+            in_cone[qubit] = true;
+        }
+        kept.push(instruction);
+    }
+
+    let qubits: Vec<usize> = (0..circuit.num_qubits())
+        .filter(|&qubit| in_cone[qubit])
+        .collect();
+    let mut position = vec![0; circuit.num_qubits()];
+    for (index, &qubit) in qubits.iter().enumerate() {
+        position[qubit] = index;
+    }
+    let mut reduced = Circuit::with_clbits(qubits.len(), circuit.num_clbits());
+    for instruction in kept.into_iter().rev() {
+        reduced
+            .add_instruction(Instruction::new(
+                instruction.operation.clone(),
+                instruction
+                    .qubits
+                    .iter()
+                    .map(|&qubit| position[qubit])
+                    .collect(),
+            ))
+            .expect("relabeled qubits stay inside the cone");
+    }
+    LightCone {
+        circuit: reduced,
+        qubits,
+    }
+}
+
+/// Returns the expectation value `⟨O⟩` of a local observable after running `circuit` on
+/// `|0…0⟩`.
+///
+/// Qubits outside the [`light_cone`] of the observable's support never interact with it, so
+/// they are traced out before simulating: only the reduced circuit is run, on `2^k` amplitudes
+/// for a cone of `k` qubits. Circuits with measurements, resets or classically conditioned
+/// operations are run exactly as a density matrix.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit preparing the state.
+/// * `observable` - A Hermitian matrix acting on `support`; bit `j` of its basis index
+///   corresponds to `support[j]`.
+/// * `support` - The qubits the observable acts on.
+///
+/// # Panics
+///
+/// Panics if the observable's dimension does not match `support`, or a support qubit is outside
+/// the circuit.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::expectation_value;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::gates::pauli_z;
+///
+/// // Only qubits 0 and 1 are simulated; the chain on qubits 2..30 is traced out
+/// let mut circuit = Circuit::new(30);
+/// circuit.ry(0, 0.7).cnot(0, 1);
+/// for qubit in 2..29 {
+///     circuit.h(qubit).cnot(qubit, qubit + 1);
+/// }
+/// assert!((expectation_value(&circuit, &pauli_z(), &[1]) - 0.7_f64.cos()).abs() < 1e-12);
+/// ```
+pub fn expectation_value(circuit: &Circuit, observable: &Gate, support: &[usize]) -> f64 {
+    assert_eq!(
+        observable.matrix.len(),
+        1 << support.len(),
+        "expected a {0}x{0} observable on {1} qubits",
+        1 << support.len(),
+        support.len()
+    );
+    let cone = light_cone(circuit, support);
+    let local: Vec<usize> = support
+        .iter()
+        .map(|&qubit| {
+            cone.position(qubit)
+                .expect("support qubits are in the cone")
+        })
+        .collect();
+    let rho = if cone.circuit.is_unitary() {
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << cone.qubits.len()];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        reduced_density_matrix(&Simulator::run(&cone.circuit, &initial_state), &local)
+    } else {
+        Simulator::run_density(&cone.circuit, &DensityMatrix::new(cone.qubits.len()))
+            .partial_trace(&local)
+    };
+    linalg::trace(&linalg::matmul(&rho.matrix, &observable.matrix)).re
+}
+
+/// The span of instructions during which a qubit holds information.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QubitLifetime {
+    /// The index of the first instruction that touches the qubit.
+    pub first: usize,
+    /// The index of the last instruction that touches the qubit.
+    pub last: usize,
+}
+
+/// Returns the first and last use of every qubit, or `None` for a qubit no instruction touches.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to analyse.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::{qubit_lifetimes, QubitLifetime};
+/// use quantum_simulator::circuit::Circuit;
+///
+/// let mut circuit = Circuit::new(3);
+/// circuit.h(0).cnot(0, 1).measure(0, 0).x(1);
+/// let lifetimes = qubit_lifetimes(&circuit);
+/// assert_eq!(lifetimes[0], Some(QubitLifetime { first: 0, last: 2 }));
+/// assert_eq!(lifetimes[1], Some(QubitLifetime { first: 1, last: 3 }));
+/// assert_eq!(lifetimes[2], None);
+/// ```
+pub fn qubit_lifetimes(circuit: &Circuit) -> Vec<Option<QubitLifetime>> {
+    let mut lifetimes: Vec<Option<QubitLifetime>> = vec![None; circuit.num_qubits()];
+    for (index, instruction) in circuit.instructions().iter().enumerate() {
+        for &qubit in &instruction.qubits {
+            let lifetime = lifetimes[qubit].get_or_insert(QubitLifetime {
+                first: index,
+                last: index,
+            });
+            lifetime.last = index;
+        }
+    }
+    lifetimes
+}
+
+/// How many gates act on each qubit and how many two-qubit interactions each pair of qubits
+/// takes part in, from [`gate_activity`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateActivity {
+    /// The number of gates acting on each qubit.
+    pub gates: Vec<usize>,
+    /// `interactions[a][b]` is the number of gates acting on both `a` and `b`; the matrix is
+    /// symmetric with a zero diagonal.
+    pub interactions: Vec<Vec<usize>>,
+}
+
+impl GateActivity {
+    /// Returns the heatmap matrix: gate counts on the diagonal and interaction counts off it.
+    pub fn matrix(&self) -> Vec<Vec<usize>> {
+        let mut matrix = self.interactions.clone();
+        for (qubit, &gates) in self.gates.iter().enumerate() {
+            matrix[qubit][qubit] = gates;
+        }
+        matrix
+    }
+
+    /// Returns the largest entry of [`GateActivity::matrix`], e.g. to scale a heatmap.
+    pub fn max(&self) -> usize {
+        self.matrix().into_iter().flatten().max().unwrap_or(0)
+    }
+
+    /// Returns [`GateActivity::matrix`] as CSV, with a header row and a label column of qubit
+    /// names.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::analysis::gate_activity;
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// assert_eq!(gate_activity(&circuit).to_csv(), "qubit,q0,q1\nq0,2,1\nq1,1,1\n");
+    /// ```
+    pub fn to_csv(&self) -> String {
+        let names: Vec<String> = (0..self.gates.len()).map(|q| format!("q{}", q)).collect();
+        let mut csv = format!("qubit,{}\n", names.join(","));
+        for (name, row) in names.iter().zip(self.matrix()) {
+            let row: Vec<String> = row.iter().map(usize::to_string).collect();
+            csv.push_str(&format!("{},{}\n", name, row.join(",")));
+        }
+        csv
+    }
+}
+
+/// Counts the gates on every qubit and the interactions between every pair, to spot qubits and
+/// couplings that carry most of a circuit's load before it is routed onto a device.
+///
+/// Unitary and classically conditioned instructions count as gates; measurements, resets,
+/// snapshots and assertions do not. A gate on three or more qubits counts as an interaction
+/// between each pair of them.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to analyse.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::gate_activity;
+/// use quantum_simulator::circuit::Circuit;
+///
+/// let mut circuit = Circuit::new(3);
+/// circuit.h(0).cnot(0, 1).cnot(0, 1).cnot(1, 2).measure(2, 0);
+/// let activity = gate_activity(&circuit);
+/// assert_eq!(activity.gates, vec![3, 3, 1]);
+/// assert_eq!(activity.interactions[0][1], 2);
+/// assert_eq!(activity.interactions[2][1], 1);
+/// assert_eq!(activity.interactions[0][2], 0);
+/// ```
+pub fn gate_activity(circuit: &Circuit) -> GateActivity {
+    let n = circuit.num_qubits();
+    let mut gates = vec![0; n];
+    let mut interactions = vec![vec![0; n]; n];
+    for instruction in circuit.instructions() {
+        let is_gate = instruction.operation.is_unitary()
+            || matches!(instruction.operation, Operation::Conditional { .. });
+        if !is_gate {
+            continue;
+        }
+        for (i, &a) in instruction.qubits.iter().enumerate() {
+            gates[a] += 1;
+            for &b in &instruction.qubits[i + 1..] {
+                interactions[a][b] += 1;
+                interactions[b][a] += 1;
+            }
+        }
+    }
+    GateActivity {
+        gates,
+        interactions,
+    }
+}
+
+/// A circuit rewritten onto fewer qubits by [`compact_qubits`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactedCircuit {
+    /// The rewritten circuit.
+    pub circuit: Circuit,
+    /// The qubit of `circuit` that holds each original qubit after the last instruction, or
+    /// `None` for a qubit the original circuit never touches.
+    pub qubits: Vec<Option<usize>>,
+}
+
+/// Reuses qubits that are no longer needed for qubits that are not needed yet, so the circuit
+/// runs on a narrower register.
+///
+/// Every qubit's history is split at each reset into independent segments, from its first use
+/// or a reset to its last use before the next reset. Segments that do not overlap in time share
+/// a qubit of the rewritten circuit, assigned greedily in order of their start, which needs as
+/// few qubits as any assignment. A segment that expects `|0⟩` from the initial state but lands
+/// on a qubit used before gets a reset inserted first.
+///
+/// A qubit whose segment has ended is abandoned in whatever state it was left, which is the
+/// same as tracing it out: the classical bits and the reduced state of the qubits still in use
+/// are unchanged, but the final state vector is not, and snapshots see the rewritten register.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to compact.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::analysis::compact_qubits;
+/// use quantum_simulator::circuit::Circuit;
+///
+/// // Three Bell pairs prepared and measured one after the other fit on two qubits
+/// let mut circuit = Circuit::new(6);
+/// for pair in 0..3 {
+///     let (a, b) = (2 * pair, 2 * pair + 1);
+///     circuit.h(a).cnot(a, b).measure(a, a).measure(b, b);
+/// }
+/// let compacted = compact_qubits(&circuit);
+/// assert_eq!(compacted.circuit.num_qubits(), 2);
+/// assert_eq!(compacted.circuit.num_clbits(), 6);
+/// assert_eq!(compacted.qubits[4], Some(0));
+/// ```
+pub fn compact_qubits(circuit: &Circuit) -> CompactedCircuit {
+    // Segment boundaries: a reset after a qubit's first use starts a new segment
+    let mut segment_of = vec![vec![]; circuit.instructions().len()];
+    let mut segments: Vec<(usize, usize, bool)> = vec![];
+    let mut current: Vec<Option<usize>> = vec![None; circuit.num_qubits()];
+    for (index, instruction) in circuit.instructions().iter().enumerate() {
+        for &qubit in &instruction.qubits {
+            let starts_segment =
+                current[qubit].is_none() || instruction.operation == Operation::Reset;
+            if starts_segment {
+                let fresh = instruction.operation == Operation::Reset;
+                segments.push((index, index, fresh));
+                current[qubit] = Some(segments.len() - 1);
+            }
+            let segment = current[qubit].expect("the qubit has a segment");
+            segments[segment].1 = index;
+            segment_of[index].push(segment);
+        }
+    }
+
+    // Segments are created in order of their start, so a greedy pass assigns them optimally
+    let mut wire_of = vec![0; segments.len()];
+    let mut reused = vec![false; segments.len()];
+    let mut released: Vec<usize> = vec![];
+    for (segment, &(first, last, _)) in segments.iter().enumerate() {
+        let wire = match released.iter().position(|&end| end < first) {
+            Some(wire) => {
+                reused[segment] = true;
+                wire
+            }
+            None => {
+                released.push(0);
+                released.len() - 1
+            }
+        };
+        released[wire] = last;
+        wire_of[segment] = wire;
+    }
+
+    let mut compacted = Circuit::with_clbits(released.len(), circuit.num_clbits());
+    for (index, instruction) in circuit.instructions().iter().enumerate() {
+        for &segment in &segment_of[index] {
+            let (first, _, fresh) = segments[segment];
+            if first == index && reused[segment] && !fresh {
+                compacted.reset(wire_of[segment]);
+            }
+        }
+        compacted
+            .add_instruction(Instruction::new(
+                instruction.operation.clone(),
+                segment_of[index]
+                    .iter()
+                    .map(|&segment| wire_of[segment])
+                    .collect(),
+            ))
+            .expect("segments that overlap in time use different qubits");
+    }
+    CompactedCircuit {
+        circuit: compacted,
+        qubits: current
+            .iter()
+            .map(|segment| segment.map(|segment| wire_of[segment]))
+            .collect(),
+    }
+}
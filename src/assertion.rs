@@ -0,0 +1,168 @@
+//! This module defines the assertions a circuit checks about its state while it runs.
+//!
+//! An [`Operation::Assert`](crate::circuit::Operation::Assert) instruction leaves the state
+//! unchanged and checks a property of the reduced state of its qubits: that it is a given state,
+//! that an outcome has a given probability, or that two qubits are entangled. Simulation stops
+//! with [`QuantumError::AssertionFailed`] or, where the run cannot fail, a panic, so a circuit
+//! with assertions is its own test. Exporters skip assertions.
+
+use crate::analysis::concurrence;
+use crate::density::DensityMatrix;
+use crate::error::QuantumError;
+use crate::qubit::Qubit;
+use crate::tolerance;
+use num_complex::Complex;
+use std::fmt;
+
+/// A property of the state of an assertion's qubits.
+///
+/// Bit `j` of a basis index of the assertion corresponds to the `j`-th qubit of its instruction,
+/// and bitstrings are written with the last qubit first.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Assertion {
+    /// The qubits are in `state`, up to global phase: their fidelity with it is at least
+    /// `1 - tolerance`.
+    State {
+        state: Vec<Complex<f64>>,
+        tolerance: f64,
+    },
+    /// The qubits are measured as `bitstring` with a probability within `tolerance` of
+    /// `probability`.
+    Probability {
+        bitstring: String,
+        probability: f64,
+        tolerance: f64,
+    },
+    /// The two qubits are entangled with each other, i.e. their reduced state has nonzero
+    /// concurrence.
+    Entangled,
+}
+
+impl Assertion {
+    /// Returns the number of qubits the assertion checks.
+    pub fn num_qubits(&self) -> usize {
+        match self {
+            Assertion::State { state, .. } => state.len().trailing_zeros() as usize,
+            Assertion::Probability { bitstring, .. } => bitstring.len(),
+            Assertion::Entangled => 2,
+        }
+    }
+
+    /// Checks that the assertion is well formed: an expected state has a power-of-two length of
+    /// at least two and unit norm, and a bitstring is nonempty and made of `0`s and `1`s.
+    pub fn validate(&self) -> Result<(), QuantumError> {
+        match self {
+            Assertion::State { state, .. } => {
+                if state.len() < 2 || !state.len().is_power_of_two() {
+                    return Err(QuantumError::InvalidDimension {
+                        dimension: state.len(),
+                    });
+                }
+                let norm = state.iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt();
+                if (norm - 1.0).abs() > tolerance::current().normalization {
+                    return Err(QuantumError::NotNormalized { norm });
+                }
+            }
+            Assertion::Probability { bitstring, .. } => {
+                if bitstring.is_empty() {
+                    return Err(QuantumError::InvalidDimension { dimension: 0 });
+                }
+                Qubit::from_bitstring(bitstring)?;
+            }
+            Assertion::Entangled => {}
+        }
+        Ok(())
+    }
+
+    /// Checks the assertion against the reduced state `rho` of its qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The index of the assertion in its circuit, for the error.
+    /// * `rho` - The reduced state of the assertion's qubits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`, or `QuantumError::AssertionFailed` describing the violation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::assertion::Assertion;
+    /// use quantum_simulator::density::DensityMatrix;
+    ///
+    /// let zero = DensityMatrix::new(1);
+    /// let certain = Assertion::Probability {
+    ///     bitstring: "0".to_string(),
+    ///     probability: 1.0,
+    ///     tolerance: 1e-9,
+    /// };
+    /// assert!(certain.check(0, &zero).is_ok());
+    /// assert!(Assertion::Entangled.check(0, &DensityMatrix::new(2)).is_err());
+    /// ```
+    pub fn check(&self, instruction: usize, rho: &DensityMatrix) -> Result<(), QuantumError> {
+        let failed = |message: String| {
+            Err(QuantumError::AssertionFailed {
+                instruction,
+                message,
+            })
+        };
+        match self {
+            Assertion::State { state, tolerance } => {
+                let fidelity: f64 = state
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, a)| {
+                        state
+                            .iter()
+                            .enumerate()
+                            .map(move |(j, b)| a.conj() * rho.matrix[i][j] * b)
+                    })
+                    .sum::<Complex<f64>>()
+                    .re;
+                if fidelity < 1.0 - tolerance {
+                    return failed(format!(
+                        "fidelity {:.6} with the expected state is below {:.6}",
+                        fidelity,
+                        1.0 - tolerance
+                    ));
+                }
+            }
+            Assertion::Probability {
+                bitstring,
+                probability,
+                tolerance,
+            } => {
+                let index = usize::from_str_radix(bitstring, 2).unwrap_or(0);
+                let actual = rho.matrix[index][index].re;
+                if (actual - probability).abs() > *tolerance {
+                    return failed(format!(
+                        "P(|{}⟩) is {:.6}, expected {} ± {}",
+                        bitstring, actual, probability, tolerance
+                    ));
+                }
+            }
+            Assertion::Entangled => {
+                if concurrence(rho) <= tolerance::current().equality {
+                    return failed("the qubits are not entangled".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assertion::State { tolerance, .. } => write!(f, "state within {}", tolerance),
+            Assertion::Probability {
+                bitstring,
+                probability,
+                tolerance,
+            } => write!(f, "P(|{}⟩) = {} ± {}", bitstring, probability, tolerance),
+            Assertion::Entangled => write!(f, "entangled"),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! This module defines the `Backend` trait that lets a circuit run on different simulation
+//! engines, chosen at runtime.
+//!
+//! A backend owns the simulated state and exposes the primitive operations a circuit needs:
+//! applying gates, measuring qubits, evaluating expectation values and reading the state back.
+//! [`Backend::run`] drives a whole [`Circuit`] through these primitives, so new engines only
+//! implement the primitives. [`CpuBackend`] is the dense state-vector engine and is always
+//! available; with the `gpu` feature, [`crate::gpu::GpuBackend`] runs gates in wgpu compute
+//! shaders.
+
+use crate::analysis::reduced_density_matrix;
+use crate::circuit::{Circuit, Operation};
+use crate::error::QuantumError;
+use crate::gates::{pauli_x, Gate};
+use crate::linalg;
+use crate::qubit::Qubit;
+use crate::rng;
+use num_complex::Complex;
+use rand::RngCore;
+
+/// A `Backend` holds a simulated register and applies operations to it.
+pub trait Backend {
+    /// The representation returned by [`Backend::state`], e.g. [`Qubit`] for state vectors.
+    type State;
+
+    /// Returns a short human-readable name for the backend, e.g. for logging.
+    fn name(&self) -> &str;
+
+    /// Returns the number of qubits in the register.
+    fn num_qubits(&self) -> usize;
+
+    /// Applies `gate` to the given qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate to apply; bit `j` of its basis index corresponds to `targets[j]`.
+    /// * `targets` - The register qubits the gate acts on.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`, or an error if the gate does not fit the targets or the backend cannot apply
+    ///   it.
+    fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError>;
+
+    /// Measures `qubit` in the computational basis, collapsing the state, and returns the
+    /// outcome, drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit to measure.
+    /// * `rng` - The random number generator to draw from.
+    fn measure_with_rng(&mut self, qubit: usize, rng: &mut dyn RngCore) -> usize;
+
+    /// Measures `qubit` like [`Backend::measure_with_rng`], returning the error instead of
+    /// panicking if the backend cannot reach its state. The default never fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit to measure.
+    /// * `rng` - The random number generator to draw from.
+    fn try_measure_with_rng(
+        &mut self,
+        qubit: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<usize, QuantumError> {
+        Ok(self.measure_with_rng(qubit, rng))
+    }
+
+    /// Returns the expectation value of a Hermitian observable acting on the given qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `observable` - The observable; bit `j` of its basis index corresponds to `targets[j]`.
+    /// * `targets` - The register qubits the observable acts on.
+    fn expectation(&self, observable: &Gate, targets: &[usize]) -> f64;
+
+    /// Returns the expectation value like [`Backend::expectation`], returning the error instead
+    /// of panicking if the backend cannot reach its state. The default never fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `observable` - The observable; bit `j` of its basis index corresponds to `targets[j]`.
+    /// * `targets` - The register qubits the observable acts on.
+    fn try_expectation(&self, observable: &Gate, targets: &[usize]) -> Result<f64, QuantumError> {
+        Ok(self.expectation(observable, targets))
+    }
+
+    /// Returns a copy of the current state.
+    fn state(&self) -> Self::State;
+
+    /// Returns the amplitudes of the current state, indexed by basis state, if the backend holds
+    /// a state vector, e.g. for [`conformance`](crate::conformance) checks. The default returns
+    /// `None`.
+    fn amplitudes(&self) -> Option<Vec<Complex<f64>>> {
+        None
+    }
+
+    /// Measures `qubit` like [`Backend::measure_with_rng`], using the crate's random number
+    /// generator.
+    fn measure(&mut self, qubit: usize) -> usize {
+        rng::with_rng(|rng| self.measure_with_rng(qubit, rng))
+    }
+
+    /// Runs every instruction of `circuit` on the register.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to run.
+    ///
+    /// # Returns
+    ///
+    /// * The final classical register, or an error if the circuit does not fit the register,
+    ///   the backend cannot apply one of its gates, or an assertion fails. Assertions are only
+    ///   checked on backends that expose their [`amplitudes`](Backend::amplitudes).
+    fn run(&mut self, circuit: &Circuit) -> Result<Vec<bool>, QuantumError> {
+        rng::with_rng(|rng| self.run_with_rng(circuit, rng))
+    }
+
+    /// Runs `circuit` like [`Backend::run`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to run.
+    /// * `rng` - The random number generator to draw from.
+    fn run_with_rng(
+        &mut self,
+        circuit: &Circuit,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<bool>, QuantumError> {
+        if circuit.num_qubits() != self.num_qubits() {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.num_qubits(),
+                found: circuit.num_qubits(),
+            });
+        }
+        let mut clbits = vec![false; circuit.num_clbits()];
+        for (index, instruction) in circuit.instructions().iter().enumerate() {
+            let qubits = &instruction.qubits;
+            match &instruction.operation {
+                Operation::Measure(clbit) => {
+                    clbits[*clbit] = self.try_measure_with_rng(qubits[0], rng)? == 1;
+                }
+                Operation::Reset => {
+                    if self.try_measure_with_rng(qubits[0], rng)? == 1 {
+                        self.apply_gate(&pauli_x(), qubits)?;
+                    }
+                }
+                Operation::Conditional { clbit, operation } => {
+                    if clbits[*clbit] {
+                        self.apply_gate(&operation.gate(), qubits)?;
+                    }
+                }
+                Operation::Snapshot(_) | Operation::Barrier(_) => {}
+                Operation::Assert(assertion) => {
+                    if let Some(amplitudes) = self.amplitudes() {
+                        let rho = reduced_density_matrix(&Qubit::from_state(amplitudes), qubits);
+                        assertion.check(index, &rho)?;
+                    }
+                }
+                operation => self.apply_gate(&operation.gate(), qubits)?,
+            }
+        }
+        Ok(clbits)
+    }
+}
+
+/// The `CpuBackend` is the crate's dense, double-precision state-vector engine.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::backend::{Backend, CpuBackend};
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::gates::pauli_z;
+///
+/// let mut circuit = Circuit::new(2);
+/// circuit.x(0).cnot(0, 1);
+///
+/// let mut backend = CpuBackend::new(2);
+/// backend.run(&circuit).unwrap();
+/// assert_eq!(backend.expectation(&pauli_z(), &[1]), -1.0);
+/// assert_eq!(backend.measure(1), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CpuBackend {
+    qubit: Qubit,
+}
+
+impl CpuBackend {
+    /// Creates a backend holding `num_qubits` qubits in `|0…0⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register.
+    pub fn new(num_qubits: usize) -> Self {
+        let mut state = vec![Default::default(); 1 << num_qubits];
+        state[0] = Complex::new(1.0, 0.0);
+        Self::from_state(Qubit::from_state(state))
+    }
+
+    /// Creates a backend holding the given state.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The initial state of the register.
+    pub fn from_state(qubit: Qubit) -> Self {
+        CpuBackend { qubit }
+    }
+}
+
+impl Backend for CpuBackend {
+    type State = Qubit;
+
+    fn name(&self) -> &str {
+        "cpu"
+    }
+
+    fn num_qubits(&self) -> usize {
+        self.qubit.num_qubits()
+    }
+
+    fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+        check_targets(gate, targets, self.num_qubits())?;
+        gate.apply_to(&mut self.qubit, targets);
+        Ok(())
+    }
+
+    fn measure_with_rng(&mut self, qubit: usize, rng: &mut dyn RngCore) -> usize {
+        self.qubit.measure_qubit_with_rng(qubit, rng)
+    }
+
+    fn expectation(&self, observable: &Gate, targets: &[usize]) -> f64 {
+        local_expectation(&self.qubit, observable, targets)
+    }
+
+    fn state(&self) -> Qubit {
+        self.qubit.clone()
+    }
+
+    fn amplitudes(&self) -> Option<Vec<Complex<f64>>> {
+        Some(self.qubit.state.clone())
+    }
+}
+
+/// Checks that `gate` has one qubit per target and that the targets are distinct qubits of the
+/// register.
+pub(crate) fn check_targets(
+    gate: &Gate,
+    targets: &[usize],
+    num_qubits: usize,
+) -> Result<(), QuantumError> {
+    let expected = 1 << targets.len();
+    if gate.matrix.len() != expected {
+        return Err(QuantumError::DimensionMismatch {
+            expected,
+            found: gate.matrix.len(),
+        });
+    }
+    for (position, &qubit) in targets.iter().enumerate() {
+        if qubit >= num_qubits {
+            return Err(QuantumError::QubitOutOfRange { qubit, num_qubits });
+        }
+        if targets[..position].contains(&qubit) {
+            return Err(QuantumError::DuplicateQubit { qubit });
+        }
+    }
+    Ok(())
+}
+
+/// Returns `Tr(ρ O)` for the reduced state `ρ` of the targets of a state vector.
+pub(crate) fn local_expectation(qubit: &Qubit, observable: &Gate, targets: &[usize]) -> f64 {
+    let rho = reduced_density_matrix(qubit, targets);
+    linalg::trace(&linalg::matmul(&rho.matrix, &observable.matrix)).re
+}
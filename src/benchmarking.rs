@@ -0,0 +1,633 @@
+//! This module generates random circuits and runs single-qubit randomized benchmarking (RB) and
+//! cycle benchmarking (CB) of multi-qubit layers.
+//!
+//! RB applies sequences of `m` Cliffords drawn uniformly from the 24-element single-qubit
+//! Clifford group, followed by the one Clifford that undoes them, and records how often the
+//! qubit returns to `|0⟩`. Averaged over sequences, gate errors turn into a decay
+//! `F(m) = A·pᵐ + B` whose rate gives the error per Clifford independently of state preparation
+//! and measurement errors.
+//!
+//! CB characterizes one Clifford layer, the cycle, as a whole: for every Pauli `P` it prepares
+//! an eigenstate of `P`, repeats the cycle `m` times with a random Pauli layer before each
+//! repetition, and measures the Pauli that `P` ideally evolves into. The random Paulis twirl the
+//! cycle's composite noise into a Pauli channel, so each expectation decays as `A·fᵐ` with one
+//! Pauli fidelity `f` per Pauli.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::density::DensityMatrix;
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::linalg;
+use crate::noise::NoiseModel;
+use crate::qubit::Qubit;
+use crate::simulator::Simulator;
+use crate::tolerance;
+use crate::variational::PauliString;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+/// The number of elements of the single-qubit Clifford group, up to global phase.
+const CLIFFORD_GROUP_SIZE: usize = 24;
+
+/// Number of grid points the decay rate is first searched over before refining.
+const DECAY_GRID: usize = 1000;
+
+/// The widest cycle [`cycle_benchmarking`] characterizes; it measures all `4^n - 1` Paulis.
+const MAX_CYCLE_QUBITS: usize = 3;
+
+/// Returns a random circuit of Clifford gates, which the stabilizer backend can run at any width.
+///
+/// Each of the `depth` layers applies a random gate from `H`, `S`, `X`, `Y` and `Z` to every
+/// qubit, then a `CNOT` or `CZ` with random orientation to each pair of a random pairing of the
+/// qubits.
+///
+/// # Arguments
+///
+/// * `num_qubits` - The width of the circuit.
+/// * `depth` - The number of layers.
+/// * `seed` - The seed of the random choices; equal seeds give equal circuits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::backend::Backend;
+/// use quantum_simulator::benchmarking::random_clifford_circuit;
+/// use quantum_simulator::stabilizer::StabilizerBackend;
+///
+/// let circuit = random_clifford_circuit(50, 20, 7);
+/// assert_eq!(circuit, random_clifford_circuit(50, 20, 7));
+/// assert!(StabilizerBackend::new(50).run(&circuit).is_ok());
+/// ```
+pub fn random_clifford_circuit(num_qubits: usize, depth: usize, seed: u64) -> Circuit {
+    let single = [
+        Operation::H,
+        Operation::S,
+        Operation::X,
+        Operation::Y,
+        Operation::Z,
+    ];
+    random_layers(num_qubits, depth, seed, |rng| {
+        single[rng.gen_range(0..single.len())].clone()
+    })
+}
+
+/// Returns a random circuit drawing from a universal gate set, e.g. as a benchmark workload.
+///
+/// Each of the `depth` layers applies a random gate from `H`, `T`, and `Rx`, `Ry` or `Rz` by a
+/// uniformly random angle to every qubit, then a `CNOT` or `CZ` with random orientation to each
+/// pair of a random pairing of the qubits.
+///
+/// # Arguments
+///
+/// * `num_qubits` - The width of the circuit.
+/// * `depth` - The number of layers.
+/// * `seed` - The seed of the random choices; equal seeds give equal circuits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::benchmarking::random_universal_circuit;
+///
+/// let circuit = random_universal_circuit(4, 10, 1);
+/// assert_eq!(circuit.num_qubits(), 4);
+/// assert!(circuit.instructions().len() >= 40);
+/// ```
+pub fn random_universal_circuit(num_qubits: usize, depth: usize, seed: u64) -> Circuit {
+    random_layers(num_qubits, depth, seed, |rng| {
+        let angle = rng.gen_range(-PI..PI);
+        match rng.gen_range(0..5) {
+            0 => Operation::H,
+            1 => Operation::T,
+            2 => Operation::Rx(angle),
+            3 => Operation::Ry(angle),
+            _ => Operation::Rz(angle),
+        }
+    })
+}
+
+/// Builds `depth` layers of random single-qubit gates followed by random two-qubit gates on a
+/// random pairing of the qubits.
+fn random_layers(
+    num_qubits: usize,
+    depth: usize,
+    seed: u64,
+    mut single: impl FnMut(&mut StdRng) -> Operation,
+) -> Circuit {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut circuit = Circuit::new(num_qubits);
+    for _ in 0..depth {
+        for qubit in 0..num_qubits {
+            circuit
+                .add_instruction(Instruction::new(single(&mut rng), vec![qubit]))
+                .expect("the qubit is in the circuit");
+        }
+        // Fisher-Yates shuffle, then pair neighbours
+        let mut order: Vec<usize> = (0..num_qubits).collect();
+        for i in (1..num_qubits).rev() {
+            order.swap(i, rng.gen_range(0..=i));
+        }
+        for pair in order.chunks_exact(2) {
+            let (a, b) = if rng.gen() {
+                (pair[0], pair[1])
+            } else {
+                (pair[1], pair[0])
+            };
+            if rng.gen() {
+                circuit.cnot(a, b);
+            } else {
+                circuit.cz(a, b);
+            }
+        }
+    }
+    circuit
+}
+
+/// The outcome of [`randomized_benchmarking`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomizedBenchmarking {
+    /// The sequence lengths, in the order given.
+    pub lengths: Vec<usize>,
+    /// The survival probability of `|0⟩` at each length, averaged over the random sequences.
+    pub survival: Vec<f64>,
+    /// The fitted `A` of `F(m) = A·pᵐ + B`.
+    pub amplitude: f64,
+    /// The fitted `B` of `F(m) = A·pᵐ + B`.
+    pub offset: f64,
+    /// The fitted decay `p` of `F(m) = A·pᵐ + B`.
+    pub decay: f64,
+    /// The average error per Clifford, `(1 - p) / 2`.
+    pub error_per_clifford: f64,
+    /// The average number of native gates (`H` and `S`) per Clifford, to convert the error per
+    /// Clifford into an error per gate.
+    pub gates_per_clifford: f64,
+}
+
+/// Runs single-qubit randomized benchmarking under a noise model and fits the decay curve.
+///
+/// Every Clifford is compiled into its shortest word in `H` and `S`, so the noise model's errors
+/// apply to those gates. Each sequence is simulated exactly as a density matrix, so the only
+/// randomness is the choice of sequences.
+///
+/// # Arguments
+///
+/// * `noise_model` - The errors to benchmark, applied on qubit `0` of a one-qubit register.
+/// * `lengths` - The numbers of random Cliffords per sequence, before the inverting Clifford.
+/// * `sequences` - The number of random sequences averaged per length.
+/// * `seed` - The seed of the random sequences.
+///
+/// # Panics
+///
+/// Panics if fewer than three lengths are given, since the fit has three parameters.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::benchmarking::randomized_benchmarking;
+/// use quantum_simulator::noise::{depolarizing, NoiseModel};
+///
+/// let mut model = NoiseModel::new();
+/// model.add_error(depolarizing(0.01));
+/// let rb = randomized_benchmarking(&model, &[1, 5, 10, 20, 40], 10, 1);
+/// assert!(rb.survival[0] > rb.survival[4]);
+/// assert!(rb.error_per_clifford > 0.0 && rb.error_per_clifford < 0.05);
+///
+/// let ideal = randomized_benchmarking(&NoiseModel::new(), &[1, 5, 10], 5, 1);
+/// assert!(ideal.error_per_clifford.abs() < 1e-9);
+/// ```
+pub fn randomized_benchmarking(
+    noise_model: &NoiseModel,
+    lengths: &[usize],
+    sequences: usize,
+    seed: u64,
+) -> RandomizedBenchmarking {
+    assert!(
+        lengths.len() >= 3,
+        "the decay fit needs at least three lengths"
+    );
+    let group = clifford_group();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut gates = 0;
+    let mut cliffords = 0;
+    let survival: Vec<f64> = lengths
+        .iter()
+        .map(|&length| {
+            let total: f64 = (0..sequences)
+                .map(|_| {
+                    let mut circuit = Circuit::new(1);
+                    let mut net = linalg::identity(2);
+                    for _ in 0..length {
+                        let (word, matrix) = &group[rng.gen_range(0..group.len())];
+                        append(&mut circuit, word);
+                        net = linalg::matmul(matrix, &net);
+                        gates += word.len();
+                        cliffords += 1;
+                    }
+                    let inverse = linalg::dagger(&net);
+                    let (word, _) = group
+                        .iter()
+                        .find(|(_, matrix)| equal_up_to_phase(matrix, &inverse))
+                        .expect("the Clifford group is closed under inversion");
+                    append(&mut circuit, word);
+                    let rho = Simulator::run_density_with_noise(
+                        &circuit,
+                        &DensityMatrix::new(1),
+                        noise_model,
+                    );
+                    rho.probabilities()[0]
+                })
+                .sum();
+            total / sequences.max(1) as f64
+        })
+        .collect();
+
+    let (amplitude, offset, decay) = fit_decay(lengths, &survival);
+    RandomizedBenchmarking {
+        lengths: lengths.to_vec(),
+        survival,
+        amplitude,
+        offset,
+        decay,
+        error_per_clifford: (1.0 - decay) / 2.0,
+        gates_per_clifford: gates as f64 / cliffords.max(1) as f64,
+    }
+}
+
+/// The decay of one Pauli in [`cycle_benchmarking`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PauliDecay {
+    /// The Pauli whose eigenstates were prepared.
+    pub pauli: PauliString,
+    /// The expectation of the ideally evolved Pauli at each length, averaged over sequences.
+    pub expectations: Vec<f64>,
+    /// The fitted `A` of `A·fᵐ`, which absorbs state preparation and measurement errors.
+    pub amplitude: f64,
+    /// The fitted Pauli fidelity `f` per cycle.
+    pub fidelity: f64,
+}
+
+/// The outcome of [`cycle_benchmarking`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CycleBenchmarking {
+    /// The numbers of cycles per sequence, in the order given.
+    pub lengths: Vec<usize>,
+    /// One decay per non-identity Pauli, in the order of their base-4 index with the lowest digit
+    /// on qubit 0 and `I`, `X`, `Y`, `Z` as digits.
+    pub decays: Vec<PauliDecay>,
+    /// The process fidelity of the cycle's composite noise, the mean Pauli fidelity with `1` for
+    /// the identity.
+    pub process_fidelity: f64,
+}
+
+impl CycleBenchmarking {
+    /// Returns the process infidelity `1 - F` of the cycle's composite noise.
+    pub fn process_infidelity(&self) -> f64 {
+        1.0 - self.process_fidelity
+    }
+
+    /// Returns the Pauli fidelity of `pauli`, or `None` if it was not measured, e.g. because it
+    /// is the identity or acts outside the cycle.
+    pub fn fidelity_of(&self, pauli: &PauliString) -> Option<f64> {
+        self.decays
+            .iter()
+            .find(|decay| decay.pauli == *pauli)
+            .map(|decay| decay.fidelity)
+    }
+}
+
+/// Runs cycle benchmarking of a multi-qubit Clifford layer under a noise model and fits one
+/// Pauli fidelity per Pauli.
+///
+/// Eigenstates are prepared and the random Pauli layers applied without noise, so the fitted
+/// fidelities describe the cycle alone. When the cycle maps a Pauli to a different one, its
+/// decay mixes the fidelities along that Pauli's orbit; the process fidelity, their mean, is
+/// accurate to first order in the error rates. Each sequence is simulated exactly as a density
+/// matrix, so the only randomness is the choice of eigenstates and Pauli layers.
+///
+/// # Arguments
+///
+/// * `cycle` - The layer to characterize, made of Clifford gates on at most three qubits.
+/// * `noise_model` - The errors applied to the cycle's gates.
+/// * `lengths` - The numbers of cycles per sequence.
+/// * `sequences` - The number of random sequences averaged per Pauli and length.
+/// * `seed` - The seed of the random sequences.
+///
+/// # Returns
+///
+/// * `Ok(CycleBenchmarking)` - The decays and the process fidelity.
+/// * `Err(QuantumError::Unsupported)` - If the cycle has more than three qubits, a non-unitary
+///   instruction or a gate that is not a Clifford.
+/// * `Err(QuantumError::UnboundParameter)` - If the cycle has an unbound parameter.
+///
+/// # Panics
+///
+/// Panics if fewer than two lengths are given, since the fit has two parameters.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::benchmarking::cycle_benchmarking;
+/// use quantum_simulator::circuit::{Circuit, Operation};
+/// use quantum_simulator::noise::{bit_flip, NoiseModel};
+/// use quantum_simulator::variational::PauliString;
+///
+/// // A CNOT layer whose target suffers bit flips
+/// let mut cycle = Circuit::new(2);
+/// cycle.cnot(0, 1);
+/// let mut model = NoiseModel::new();
+/// model.add_gate_error(Operation::Cnot, bit_flip(0.02));
+///
+/// let cb = cycle_benchmarking(&cycle, &model, &[2, 4, 8], 4, 3).unwrap();
+/// assert!(cb.process_infidelity() > 0.005 && cb.process_infidelity() < 0.05);
+/// // X on the target commutes with the flips, Z on the target does not
+/// let x1 = cb.fidelity_of(&PauliString::parse("X1").unwrap()).unwrap();
+/// let z1 = cb.fidelity_of(&PauliString::parse("Z1").unwrap()).unwrap();
+/// assert!(x1 > z1);
+/// ```
+pub fn cycle_benchmarking(
+    cycle: &Circuit,
+    noise_model: &NoiseModel,
+    lengths: &[usize],
+    sequences: usize,
+    seed: u64,
+) -> Result<CycleBenchmarking, QuantumError> {
+    assert!(
+        lengths.len() >= 2,
+        "the decay fit needs at least two lengths"
+    );
+    let num_qubits = cycle.num_qubits();
+    if num_qubits > MAX_CYCLE_QUBITS {
+        return Err(QuantumError::Unsupported {
+            feature: format!("cycle benchmarking on {} qubits", num_qubits),
+        });
+    }
+    for instruction in cycle.instructions() {
+        match &instruction.operation {
+            Operation::Parametric { parameter, .. } => {
+                return Err(QuantumError::UnboundParameter {
+                    name: parameter.name().to_string(),
+                })
+            }
+            Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_) => {}
+            operation if !operation.is_unitary() => {
+                return Err(QuantumError::Unsupported {
+                    feature: format!("cycle benchmarking a cycle with {}", operation.name()),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    let dimension = 1 << num_qubits;
+    let paulis = linalg::pauli_basis(num_qubits);
+    let unitary = cycle.to_unitary(num_qubits);
+    let conjugate = |u: &[Vec<Complex<f64>>], pauli: &[Vec<Complex<f64>>]| {
+        linalg::matmul(&linalg::matmul(u, pauli), &linalg::dagger(u))
+    };
+    if paulis
+        .iter()
+        .any(|pauli| !is_signed_pauli(&conjugate(&unitary, pauli), &paulis))
+    {
+        return Err(QuantumError::Unsupported {
+            feature: "cycle benchmarking a cycle that is not a Clifford".to_string(),
+        });
+    }
+
+    let single = linalg::paulis();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut decays = vec![];
+    for (index, pauli) in paulis.iter().enumerate().skip(1) {
+        let digits: Vec<usize> = (0..num_qubits)
+            .map(|qubit| (index >> (2 * qubit)) & 3)
+            .collect();
+        let expectations: Vec<f64> = lengths
+            .iter()
+            .map(|&length| {
+                let total: f64 = (0..sequences)
+                    .map(|_| {
+                        // A random eigenstate of P, tracking its eigenvalue
+                        let mut state = Qubit::zero_state(num_qubits);
+                        let mut eigenvalue = 1.0;
+                        for (qubit, &digit) in digits.iter().enumerate() {
+                            let negative = digit != 0 && rng.gen::<bool>();
+                            if negative {
+                                eigenvalue = -eigenvalue;
+                            }
+                            prepare_eigenstate(digit, negative).apply_to(&mut state, &[qubit]);
+                        }
+                        let mut rho = DensityMatrix::from_state(&state);
+
+                        let mut ideal = linalg::identity(dimension);
+                        for _ in 0..length {
+                            let twirl = rng.gen_range(0..paulis.len());
+                            for qubit in 0..num_qubits {
+                                let digit = (twirl >> (2 * qubit)) & 3;
+                                if digit != 0 {
+                                    rho.apply_gate(&Gate::new(single[digit].clone()), &[qubit]);
+                                }
+                            }
+                            rho = Simulator::run_density_with_noise(cycle, &rho, noise_model);
+                            ideal =
+                                linalg::matmul(&unitary, &linalg::matmul(&paulis[twirl], &ideal));
+                        }
+                        let evolved = conjugate(&ideal, pauli);
+                        eigenvalue * linalg::trace(&linalg::matmul(&rho.matrix, &evolved)).re
+                    })
+                    .sum();
+                total / sequences.max(1) as f64
+            })
+            .collect();
+        let (amplitude, fidelity) = fit_exponential(lengths, &expectations);
+        let label: Vec<String> = digits
+            .iter()
+            .enumerate()
+            .filter(|(_, &digit)| digit != 0)
+            .map(|(qubit, &digit)| format!("{}{}", ["I", "X", "Y", "Z"][digit], qubit))
+            .collect();
+        decays.push(PauliDecay {
+            pauli: PauliString::parse(&label.join(" ")).expect("labels are valid Pauli strings"),
+            expectations,
+            amplitude,
+            fidelity,
+        });
+    }
+
+    let process_fidelity =
+        (1.0 + decays.iter().map(|decay| decay.fidelity).sum::<f64>()) / paulis.len() as f64;
+    Ok(CycleBenchmarking {
+        lengths: lengths.to_vec(),
+        decays,
+        process_fidelity,
+    })
+}
+
+/// Returns the gate preparing the `+1` or, if `negative`, the `-1` eigenstate of the Pauli with
+/// the given digit (`I`, `X`, `Y`, `Z`) from `|0⟩`.
+fn prepare_eigenstate(digit: usize, negative: bool) -> Gate {
+    let mut circuit = Circuit::new(1);
+    if negative {
+        circuit.x(0);
+    }
+    match digit {
+        1 => {
+            circuit.h(0);
+        }
+        2 => {
+            circuit.h(0).s(0);
+        }
+        _ => {}
+    }
+    Gate::new(circuit.to_unitary(circuit.num_qubits()))
+}
+
+/// Returns `true` if a matrix is `±` one of the Pauli strings.
+fn is_signed_pauli(matrix: &[Vec<Complex<f64>>], paulis: &[Vec<Vec<Complex<f64>>>]) -> bool {
+    let dimension = matrix.len() as f64;
+    let equality = tolerance::current().equality;
+    paulis.iter().any(|pauli| {
+        let overlap = linalg::trace(&linalg::matmul(pauli, matrix)) / dimension;
+        (overlap.re.abs() - 1.0).abs() < equality && overlap.im.abs() < equality
+    })
+}
+
+/// Fits `E(m) = A·fᵐ` by least squares and returns `(A, f)`, with `f` in `[0, 1]`.
+fn fit_exponential(lengths: &[usize], values: &[f64]) -> (f64, f64) {
+    // Without noise every expectation is the same; report no decay at all
+    if values
+        .iter()
+        .all(|value| (value - values[0]).abs() < tolerance::current().truncation)
+    {
+        return (values[0], 1.0);
+    }
+    let solve = |f: f64| -> (f64, f64) {
+        let xs: Vec<f64> = lengths.iter().map(|&m| f.powi(m as i32)).collect();
+        let sxx: f64 = xs.iter().map(|x| x * x).sum();
+        let sxy: f64 = xs.iter().zip(values).map(|(x, y)| x * y).sum();
+        let amplitude = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+        let residual = xs
+            .iter()
+            .zip(values)
+            .map(|(x, y)| (amplitude * x - y).powi(2))
+            .sum();
+        (amplitude, residual)
+    };
+    let fidelity = minimize_decay(|f| solve(f).1);
+    (solve(fidelity).0, fidelity)
+}
+
+/// A single-qubit Clifford as a word of gates and its matrix.
+type Clifford = (Vec<Operation>, Vec<Vec<Complex<f64>>>);
+
+/// Returns the single-qubit Clifford group as shortest words in `H` and `S` with their matrices,
+/// found by breadth-first search from the identity.
+fn clifford_group() -> Vec<Clifford> {
+    let generators = [Operation::H, Operation::S];
+    let mut group: Vec<Clifford> = vec![];
+    let mut queue = VecDeque::from([(vec![], linalg::identity(2))]);
+    while let Some((word, matrix)) = queue.pop_front() {
+        if group
+            .iter()
+            .any(|(_, known)| equal_up_to_phase(known, &matrix))
+        {
+            continue;
+        }
+        for generator in &generators {
+            let mut longer = word.clone();
+            longer.push(generator.clone());
+            let product = linalg::matmul(&generator.gate().matrix, &matrix);
+            queue.push_back((longer, product));
+        }
+        group.push((word, matrix));
+        if group.len() == CLIFFORD_GROUP_SIZE {
+            break;
+        }
+    }
+    group
+}
+
+/// Appends a word of single-qubit gates to qubit `0`.
+fn append(circuit: &mut Circuit, word: &[Operation]) {
+    for operation in word {
+        circuit
+            .add_instruction(Instruction::new(operation.clone(), vec![0]))
+            .expect("qubit 0 is in the circuit");
+    }
+}
+
+/// Returns `true` if two matrices differ only by a global phase.
+fn equal_up_to_phase(a: &[Vec<Complex<f64>>], b: &[Vec<Complex<f64>>]) -> bool {
+    let overlap: Complex<f64> = a
+        .iter()
+        .zip(b)
+        .flat_map(|(x, y)| x.iter().zip(y).map(|(p, q)| p.conj() * q))
+        .sum();
+    let dimension = a.len() as f64;
+    (overlap.norm() - dimension).abs() < tolerance::current().equality
+}
+
+/// Fits `F(m) = A·pᵐ + B` by least squares and returns `(A, B, p)`.
+///
+/// For a fixed `p` the model is linear in `A` and `B`, so the residual is minimized over `p`
+/// alone, see [`minimize_decay`].
+fn fit_decay(lengths: &[usize], survival: &[f64]) -> (f64, f64, f64) {
+    // A flat curve, e.g. without noise, fits any decay; report no decay at all
+    let (min, max) = survival
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+    if max - min < tolerance::current().truncation {
+        return (0.0, (min + max) / 2.0, 1.0);
+    }
+
+    let solve = |p: f64| -> (f64, f64, f64) {
+        // Linear least squares for F ≈ A·x + B with x = pᵐ
+        let xs: Vec<f64> = lengths.iter().map(|&m| p.powi(m as i32)).collect();
+        let n = xs.len() as f64;
+        let mean_x = xs.iter().sum::<f64>() / n;
+        let mean_y = survival.iter().sum::<f64>() / n;
+        let sxx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+        let sxy: f64 = xs
+            .iter()
+            .zip(survival)
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let amplitude = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+        let offset = mean_y - amplitude * mean_x;
+        let residual = xs
+            .iter()
+            .zip(survival)
+            .map(|(x, y)| (amplitude * x + offset - y).powi(2))
+            .sum();
+        (amplitude, offset, residual)
+    };
+
+    let decay = minimize_decay(|p| solve(p).2);
+    let (amplitude, offset, _) = solve(decay);
+    (amplitude, offset, decay)
+}
+
+/// Returns the decay rate in `[0, 1]` minimizing a fit's residual: the best point of a grid,
+/// refined by golden-section search around it.
+fn minimize_decay(residual: impl Fn(f64) -> f64) -> f64 {
+    let step = 1.0 / DECAY_GRID as f64;
+    let best = (1..=DECAY_GRID)
+        .map(|k| k as f64 * step)
+        .min_by(|&a, &b| residual(a).total_cmp(&residual(b)))
+        .expect("the grid is not empty");
+
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = ((best - step).max(0.0), (best + step).min(1.0));
+    for _ in 0..60 {
+        let a = high - ratio * (high - low);
+        let b = low + ratio * (high - low);
+        if residual(a) <= residual(b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+    (low + high) / 2.0
+}
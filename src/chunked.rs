@@ -0,0 +1,539 @@
+//! This module stores a state vector on disk, for registers too large to hold in memory.
+//!
+//! A [`ChunkedStateVector`] keeps its amplitudes in a file split into chunks of `2^c`
+//! amplitudes, and maps only the chunks a gate touches into memory: one chunk for a gate on
+//! qubits below `c`, and the `2^g` chunks that differ in the `g` targets at or above `c`
+//! otherwise. The chunk size follows from a memory budget, set for the thread with
+//! [`Simulator::set_memory_budget`](crate::simulator::Simulator::set_memory_budget), so that a
+//! gate with up to three such targets stays within it; a gate with more fails rather than map more
+//! than the budget. Every gate streams the whole file through
+//! memory once, so a run is much slower than in memory, but it finishes where allocating the
+//! state would abort. [`ChunkedBackend`] runs whole circuits on it through the [`Backend`] trait.
+
+use crate::backend::{check_targets, Backend};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::linalg;
+use crate::qubit::Qubit;
+use crate::stats;
+use memmap2::{MmapMut, MmapOptions};
+use num_complex::Complex;
+use rand::{Rng, RngCore};
+use std::cell::Cell;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The memory budget used when none is set, 1 GiB.
+pub const DEFAULT_MEMORY_BUDGET: usize = 1 << 30;
+
+/// The smallest chunk, 64 KiB of amplitudes, a multiple of the page size and mapping
+/// granularity of every supported platform.
+const MIN_CHUNK_QUBITS: usize = 12;
+
+/// Number of chunks a gate may map at once within the budget, those of three targets above the
+/// chunk boundary.
+const MAX_MAPPED_CHUNKS: usize = 8;
+
+/// Bytes taken by one amplitude.
+const AMPLITUDE_BYTES: usize = std::mem::size_of::<Complex<f64>>();
+
+/// The smallest memory budget, 512 KiB, enough to map the smallest chunks of a gate with three
+/// targets above the chunk boundary.
+pub const MIN_MEMORY_BUDGET: usize = MAX_MAPPED_CHUNKS * (AMPLITUDE_BYTES << MIN_CHUNK_QUBITS);
+
+/// Counter that keeps the state files of one process apart.
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static MEMORY_BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Makes `bytes` the memory budget of out-of-core runs on this thread.
+pub(crate) fn set_budget(bytes: usize) {
+    MEMORY_BUDGET.with(|cell| cell.set(Some(bytes)));
+}
+
+/// Returns this thread to the default memory budget.
+pub(crate) fn clear_budget() {
+    MEMORY_BUDGET.with(|cell| cell.set(None));
+}
+
+/// Returns the memory budget set on this thread, if any.
+pub(crate) fn budget() -> Option<usize> {
+    MEMORY_BUDGET.with(|cell| cell.get())
+}
+
+/// A state vector of `2^n` amplitudes stored in a file and mapped into memory chunk by chunk,
+/// with qubit `k` as bit `k` of the index. The file is deleted when the state is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::chunked::ChunkedStateVector;
+/// use quantum_simulator::gates::{cnot, hadamard};
+///
+/// // 64 KiB chunks, so the 14 qubits span four of them
+/// let mut state = ChunkedStateVector::zero_state(14, 512 << 10).unwrap();
+/// assert_eq!(state.chunk_qubits(), 12);
+/// state.apply_gate(&hadamard(1), &[0]).unwrap();
+/// state.apply_gate(&cnot(0, 1, 2), &[0, 13]).unwrap();
+/// assert!((state.probability_of_one(13).unwrap() - 0.5).abs() < 1e-12);
+/// assert_eq!(state.disk_bytes(), 16 << 14);
+/// ```
+#[derive(Debug)]
+pub struct ChunkedStateVector {
+    file: File,
+    path: PathBuf,
+    num_qubits: usize,
+    chunk_qubits: usize,
+    memory_budget: usize,
+}
+
+impl ChunkedStateVector {
+    /// Creates `num_qubits` qubits in `|0…0⟩`, stored in the system's temporary directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register.
+    /// * `memory_budget` - The bytes of amplitudes that may be mapped into memory at once.
+    ///
+    /// # Returns
+    ///
+    /// * The state, `QuantumError::Unsupported` if the budget is below [`MIN_MEMORY_BUDGET`], or
+    ///   `QuantumError::BackendUnavailable` if the file cannot be created.
+    pub fn zero_state(num_qubits: usize, memory_budget: usize) -> Result<Self, QuantumError> {
+        Self::zero_state_in(num_qubits, memory_budget, &std::env::temp_dir())
+    }
+
+    /// Creates `num_qubits` qubits in `|0…0⟩`, stored in `directory`. Choose a directory on
+    /// disk: on some systems the temporary directory is itself kept in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register.
+    /// * `memory_budget` - The bytes of amplitudes that may be mapped into memory at once.
+    /// * `directory` - Where to create the state file.
+    ///
+    /// # Returns
+    ///
+    /// * The state, `QuantumError::Unsupported` if the budget is below [`MIN_MEMORY_BUDGET`], or
+    ///   `QuantumError::BackendUnavailable` if the file cannot be created.
+    pub fn zero_state_in(
+        num_qubits: usize,
+        memory_budget: usize,
+        directory: &Path,
+    ) -> Result<Self, QuantumError> {
+        if num_qubits >= usize::BITS as usize - AMPLITUDE_BYTES.trailing_zeros() as usize {
+            return Err(QuantumError::Unsupported {
+                feature: format!("a {}-qubit state file", num_qubits),
+            });
+        }
+        if memory_budget < MIN_MEMORY_BUDGET {
+            return Err(QuantumError::Unsupported {
+                feature: format!(
+                    "a memory budget of {} bytes, below the minimum of {}",
+                    memory_budget, MIN_MEMORY_BUDGET
+                ),
+            });
+        }
+        let path = directory.join(format!(
+            "quantum_simulator-{}-{}.state",
+            process::id(),
+            NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|error| unavailable(&path, error))?;
+        // Sized without writing, the file reads as zeros and takes disk space as it is filled
+        file.set_len((AMPLITUDE_BYTES << num_qubits) as u64)
+            .map_err(|error| unavailable(&path, error))?;
+
+        let chunk_amplitudes = memory_budget / MAX_MAPPED_CHUNKS / AMPLITUDE_BYTES;
+        let budget_qubits = chunk_amplitudes.ilog2() as usize;
+        let state = ChunkedStateVector {
+            file,
+            path,
+            num_qubits,
+            chunk_qubits: budget_qubits.min(num_qubits),
+            memory_budget,
+        };
+        amplitudes_mut(&mut state.map_chunk(0)?)[0] = Complex::new(1.0, 0.0);
+        Ok(state)
+    }
+
+    /// Copies an in-memory state into a new state file in the system's temporary directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The state to copy.
+    /// * `memory_budget` - The bytes of amplitudes that may be mapped into memory at once.
+    pub fn from_qubit(qubit: &Qubit, memory_budget: usize) -> Result<Self, QuantumError> {
+        let state = Self::zero_state(qubit.num_qubits(), memory_budget)?;
+        for chunk in 0..state.num_chunks() {
+            let start = chunk << state.chunk_qubits;
+            let mut map = state.map_chunk(chunk)?;
+            let amplitudes = amplitudes_mut(&mut map);
+            let end = start + amplitudes.len();
+            amplitudes.copy_from_slice(&qubit.state[start..end]);
+        }
+        Ok(state)
+    }
+
+    /// Reads the whole state into memory, which only works for registers that fit there.
+    pub fn to_qubit(&self) -> Result<Qubit, QuantumError> {
+        let mut state = Vec::with_capacity(1 << self.num_qubits);
+        for chunk in 0..self.num_chunks() {
+            state.extend_from_slice(amplitudes_mut(&mut self.map_chunk(chunk)?));
+        }
+        Ok(Qubit::from_state(state))
+    }
+
+    /// Returns the number of qubits.
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Returns the number of qubits addressed within a chunk; a chunk holds `2^c` amplitudes.
+    pub fn chunk_qubits(&self) -> usize {
+        self.chunk_qubits
+    }
+
+    /// Returns the number of chunks the file is split into.
+    pub fn num_chunks(&self) -> usize {
+        1 << (self.num_qubits - self.chunk_qubits)
+    }
+
+    /// Returns the bytes of amplitudes that may be mapped into memory at once.
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    /// Returns the size of the state file.
+    pub fn disk_bytes(&self) -> usize {
+        AMPLITUDE_BYTES << self.num_qubits
+    }
+
+    /// Returns the path of the state file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Applies `gate` to the given qubits, one group of chunks at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate to apply; bit `j` of its basis index corresponds to `targets[j]`.
+    /// * `targets` - The qubits the gate acts on.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`, an error if the gate does not fit the targets,
+    ///   `QuantumError::Unsupported` if its targets above the chunk boundary span more chunks
+    ///   than the memory budget holds, or `QuantumError::BackendUnavailable` if a chunk cannot
+    ///   be mapped.
+    pub fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+        check_targets(gate, targets, self.num_qubits)?;
+        stats::record_gate(self.disk_bytes(), || {
+            self.for_each_group(targets, |amplitudes, offsets, mask| {
+                let mut local = vec![Complex::new(0.0, 0.0); offsets.len()];
+                for base in (0..amplitudes.len()).filter(|index| index & mask == 0) {
+                    for (amplitude, offset) in local.iter_mut().zip(offsets) {
+                        *amplitude = amplitudes.get(base + offset);
+                    }
+                    for (row, offset) in gate.matrix.iter().zip(offsets) {
+                        let value = row.iter().zip(&local).map(|(m, a)| m * a).sum();
+                        amplitudes.set(base + offset, value);
+                    }
+                }
+            })
+        })
+    }
+
+    /// Returns the probability of measuring `qubit` as `1`.
+    pub fn probability_of_one(&self, qubit: usize) -> Result<f64, QuantumError> {
+        if qubit >= self.num_qubits {
+            return Err(QuantumError::QubitOutOfRange {
+                qubit,
+                num_qubits: self.num_qubits,
+            });
+        }
+        let mut probability = 0.0;
+        self.for_each_group(&[qubit], |amplitudes, offsets, mask| {
+            probability += (0..amplitudes.len())
+                .filter(|index| index & mask == 0)
+                .map(|base| amplitudes.get(base + offsets[1]).norm_sqr())
+                .sum::<f64>();
+        })?;
+        Ok(probability)
+    }
+
+    /// Measures `qubit` in the computational basis, collapsing the state, and returns the
+    /// outcome, drawing randomness from `rng`.
+    pub fn measure_qubit_with_rng<R: Rng + ?Sized>(
+        &mut self,
+        qubit: usize,
+        rng: &mut R,
+    ) -> Result<usize, QuantumError> {
+        let prob_1 = self.probability_of_one(qubit)?;
+        let outcome = usize::from(rng.gen::<f64>() < prob_1);
+        let kept = if outcome == 1 { prob_1 } else { 1.0 - prob_1 };
+        let scale = 1.0 / kept.sqrt();
+        self.for_each_group(&[qubit], |amplitudes, offsets, mask| {
+            for base in (0..amplitudes.len()).filter(|index| index & mask == 0) {
+                let (zeroed, scaled) = if outcome == 1 {
+                    (base, base + offsets[1])
+                } else {
+                    (base + offsets[1], base)
+                };
+                amplitudes.set(zeroed, Complex::new(0.0, 0.0));
+                amplitudes.set(scaled, amplitudes.get(scaled) * scale);
+            }
+        })?;
+        Ok(outcome)
+    }
+
+    /// Returns the expectation value `⟨ψ|O|ψ⟩` of a Hermitian observable on the given qubits.
+    pub fn expectation(&self, observable: &Gate, targets: &[usize]) -> Result<f64, QuantumError> {
+        check_targets(observable, targets, self.num_qubits)?;
+        let mut expectation = 0.0;
+        self.for_each_group(targets, |amplitudes, offsets, mask| {
+            for base in (0..amplitudes.len()).filter(|index| index & mask == 0) {
+                let local: Vec<Complex<f64>> = offsets
+                    .iter()
+                    .map(|offset| amplitudes.get(base + offset))
+                    .collect();
+                for (row, a) in observable.matrix.iter().zip(&local) {
+                    let product: Complex<f64> = row.iter().zip(&local).map(|(m, b)| m * b).sum();
+                    expectation += (a.conj() * product).re;
+                }
+            }
+        })?;
+        Ok(expectation)
+    }
+
+    /// Maps the chunks that differ only in the targets at or above the chunk boundary, group by
+    /// group, and calls `visit` with them joined into one register whose low `c` qubits are the
+    /// chunk's and whose next qubits are those targets, with the local offsets and target mask
+    /// of [`linalg::local_offsets`] in that register. Fails without visiting any group if a
+    /// group would map more than the memory budget.
+    fn for_each_group(
+        &self,
+        targets: &[usize],
+        mut visit: impl FnMut(&mut Group, &[usize], usize),
+    ) -> Result<(), QuantumError> {
+        let chunk_qubits = self.chunk_qubits;
+        let high: Vec<usize> = targets
+            .iter()
+            .filter(|&&target| target >= chunk_qubits)
+            .map(|&target| target - chunk_qubits)
+            .collect();
+        let mapped = (AMPLITUDE_BYTES << chunk_qubits) << high.len();
+        if mapped > self.memory_budget {
+            return Err(QuantumError::Unsupported {
+                feature: format!(
+                    "{} targets above the chunk boundary, which map {} bytes within a budget of {}",
+                    high.len(),
+                    mapped,
+                    self.memory_budget
+                ),
+            });
+        }
+        let joined: Vec<usize> = targets
+            .iter()
+            .map(
+                |&target| match high.iter().position(|&h| h + chunk_qubits == target) {
+                    Some(position) => chunk_qubits + position,
+                    None => target,
+                },
+            )
+            .collect();
+        let (offsets, mask) = linalg::local_offsets(&joined);
+        let high_mask: usize = high.iter().map(|&h| 1 << h).sum();
+
+        for first in (0..self.num_chunks()).filter(|chunk| chunk & high_mask == 0) {
+            let mut maps = (0..1usize << high.len())
+                .map(|member| {
+                    let chunk = high
+                        .iter()
+                        .enumerate()
+                        .filter(|(bit, _)| (member >> bit) & 1 == 1)
+                        .fold(first, |chunk, (_, &h)| chunk | 1 << h);
+                    self.map_chunk(chunk)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut group = Group {
+                chunks: maps.iter_mut().map(amplitudes_mut).collect(),
+                chunk_qubits,
+            };
+            visit(&mut group, &offsets, mask);
+        }
+        Ok(())
+    }
+
+    /// Maps chunk `chunk` of the file into memory.
+    fn map_chunk(&self, chunk: usize) -> Result<MmapMut, QuantumError> {
+        let len = AMPLITUDE_BYTES << self.chunk_qubits;
+        // SAFETY: the file was created by this state and is not resized while mapped; other
+        // processes modifying it behind our back is the only way to break the mapping
+        unsafe {
+            MmapOptions::new()
+                .offset((chunk * len) as u64)
+                .len(len)
+                .map_mut(&self.file)
+        }
+        .map_err(|error| unavailable(&self.path, error))
+    }
+}
+
+impl Drop for ChunkedStateVector {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The chunks of one group joined into a single register.
+struct Group<'a> {
+    chunks: Vec<&'a mut [Complex<f64>]>,
+    chunk_qubits: usize,
+}
+
+impl Group<'_> {
+    fn len(&self) -> usize {
+        self.chunks.len() << self.chunk_qubits
+    }
+
+    fn get(&self, index: usize) -> Complex<f64> {
+        self.chunks[index >> self.chunk_qubits][index & ((1 << self.chunk_qubits) - 1)]
+    }
+
+    fn set(&mut self, index: usize, value: Complex<f64>) {
+        self.chunks[index >> self.chunk_qubits][index & ((1 << self.chunk_qubits) - 1)] = value;
+    }
+}
+
+/// Views a mapped chunk as amplitudes.
+fn amplitudes_mut(map: &mut MmapMut) -> &mut [Complex<f64>] {
+    // SAFETY: mappings are page aligned, `Complex<f64>` is two `f64`s laid out as in the file
+    // and every bit pattern is a valid `f64`
+    let (prefix, amplitudes, suffix) = unsafe { map.align_to_mut::<Complex<f64>>() };
+    debug_assert!(prefix.is_empty() && suffix.is_empty());
+    amplitudes
+}
+
+fn unavailable(path: &Path, error: std::io::Error) -> QuantumError {
+    QuantumError::BackendUnavailable {
+        reason: format!("state file {}: {}", path.display(), error),
+    }
+}
+
+/// The `ChunkedBackend` is a state-vector engine that keeps the state on disk; see
+/// [`ChunkedStateVector`].
+///
+/// Runs, [`Backend::try_measure_with_rng`] and [`Backend::try_expectation`] return the error if a
+/// chunk of the state file cannot be mapped; the infallible [`Backend::measure_with_rng`],
+/// [`Backend::expectation`] and [`Backend::state`] panic with it instead.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::backend::Backend;
+/// use quantum_simulator::chunked::ChunkedBackend;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::gates::pauli_z;
+///
+/// let mut circuit = Circuit::new(13);
+/// circuit.x(0).cnot(0, 12);
+///
+/// let mut backend = ChunkedBackend::new(13, 512 << 10).unwrap();
+/// backend.run(&circuit).unwrap();
+/// assert_eq!(backend.expectation(&pauli_z(), &[12]), -1.0);
+/// assert_eq!(backend.measure(12), 1);
+/// ```
+#[derive(Debug)]
+pub struct ChunkedBackend {
+    state: ChunkedStateVector,
+}
+
+impl ChunkedBackend {
+    /// Creates a backend holding `num_qubits` qubits in `|0…0⟩`, stored in the system's
+    /// temporary directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register.
+    /// * `memory_budget` - The bytes of amplitudes that may be mapped into memory at once.
+    pub fn new(num_qubits: usize, memory_budget: usize) -> Result<Self, QuantumError> {
+        Ok(Self::from_state(ChunkedStateVector::zero_state(
+            num_qubits,
+            memory_budget,
+        )?))
+    }
+
+    /// Creates a backend holding the given state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The initial state of the register.
+    pub fn from_state(state: ChunkedStateVector) -> Self {
+        ChunkedBackend { state }
+    }
+
+    /// Returns the state without reading it into memory.
+    pub fn chunked_state(&self) -> &ChunkedStateVector {
+        &self.state
+    }
+}
+
+impl Backend for ChunkedBackend {
+    /// The whole state read into memory, which only works for registers that fit there.
+    type State = Qubit;
+
+    fn name(&self) -> &str {
+        "cpu-chunked"
+    }
+
+    fn num_qubits(&self) -> usize {
+        self.state.num_qubits()
+    }
+
+    fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+        self.state.apply_gate(gate, targets)
+    }
+
+    fn measure_with_rng(&mut self, qubit: usize, rng: &mut dyn RngCore) -> usize {
+        self.try_measure_with_rng(qubit, rng)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn try_measure_with_rng(
+        &mut self,
+        qubit: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<usize, QuantumError> {
+        self.state.measure_qubit_with_rng(qubit, rng)
+    }
+
+    fn expectation(&self, observable: &Gate, targets: &[usize]) -> f64 {
+        self.try_expectation(observable, targets)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn try_expectation(&self, observable: &Gate, targets: &[usize]) -> Result<f64, QuantumError> {
+        self.state.expectation(observable, targets)
+    }
+
+    fn state(&self) -> Qubit {
+        self.state
+            .to_qubit()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn amplitudes(&self) -> Option<Vec<Complex<f64>>> {
+        self.state.to_qubit().ok().map(|qubit| qubit.state)
+    }
+}
@@ -1,48 +1,1755 @@
 //! This module defines the `Circuit` struct and its associated methods for managing and running quantum circuits.
 
-use crate::gates::Gate;
-use crate::qubit::Qubit;
+use crate::analysis::reduced_density_matrix;
+use crate::assertion::Assertion;
+use crate::diagram;
+use crate::diff::CircuitDiff;
+use crate::error::QuantumError;
+use crate::execution::{self, Execution, ExecutionMode};
+use crate::gates::{
+    cnot, cz, fuse, hadamard, iswap, mcp, mcx, pauli_x, pauli_y, pauli_z, phase, rx, ry, rz, s,
+    swap, t, toffoli, Gate, GateKind,
+};
+use crate::library;
+use crate::optimize::{OptimizationReport, PassManager};
+use crate::parameter::{Angle, Parameter, ParametricGate};
+use crate::presets;
+use crate::qubit::{Basis, Qubit};
+use crate::rng;
+use crate::schedule::Schedule;
+use num_complex::Complex;
+use rand::Rng;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+/// Maximum number of classical bits a circuit may address, since running it allocates one
+/// entry per bit.
+const MAX_CLBITS: usize = 1 << 20;
+
+/// Appended to the name of a custom gate to name its inverse, as in OpenQASM's `sdg` and `tdg`.
+pub(crate) const INVERSE_SUFFIX: &str = "_dg";
+
+/// An `Operation` is the action performed by an [`Instruction`] on its qubits.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operation {
+    H,
+    X,
+    Y,
+    Z,
+    S,
+    T,
+    Rx(f64),
+    Ry(f64),
+    Rz(f64),
+    Phase(f64),
+    Cnot,
+    Cz,
+    Swap,
+    ISwap,
+    Toffoli,
+    /// An arbitrary gate whose matrix acts on the instruction's qubits.
+    Unitary(Gate),
+    /// A gate registered under `name` in a [`GateLibrary`](crate::library::GateLibrary), with
+    /// its matrix acting on the instruction's qubits.
+    Custom {
+        name: String,
+        gate: Gate,
+    },
+    /// A rotation by a symbolic angle, which must be bound with [`Circuit::bind_parameters`]
+    /// before the circuit runs.
+    Parametric {
+        gate: ParametricGate,
+        parameter: Parameter,
+    },
+    /// Measures the qubit in the computational basis and stores the outcome in classical bit
+    /// `clbit`.
+    Measure(usize),
+    /// Resets the qubit to `|0⟩`.
+    Reset,
+    /// Applies `operation` only if classical bit `clbit` is `1`.
+    Conditional {
+        clbit: usize,
+        operation: Box<Operation>,
+    },
+    /// Leaves the state unchanged and marks a point, named by the label, at which
+    /// [`Simulator::run_stepwise`](crate::simulator::Simulator::run_stepwise) records the state.
+    Snapshot(String),
+    /// Leaves the state unchanged and keeps instructions from being scheduled or optimized across
+    /// it on the instruction's qubits, of which it holds the number.
+    Barrier(usize),
+    /// Leaves the state unchanged and checks a property of the instruction's qubits.
+    Assert(Assertion),
+}
+
+impl Operation {
+    /// Returns the number of qubits the operation acts on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    ///
+    /// assert_eq!(Operation::H.num_qubits(), 1);
+    /// assert_eq!(Operation::Toffoli.num_qubits(), 3);
+    /// ```
+    pub fn num_qubits(&self) -> usize {
+        match self {
+            Operation::H
+            | Operation::X
+            | Operation::Y
+            | Operation::Z
+            | Operation::S
+            | Operation::T
+            | Operation::Rx(_)
+            | Operation::Ry(_)
+            | Operation::Rz(_)
+            | Operation::Phase(_)
+            | Operation::Parametric { .. }
+            | Operation::Measure(_)
+            | Operation::Reset => 1,
+            Operation::Cnot | Operation::Cz | Operation::Swap | Operation::ISwap => 2,
+            Operation::Toffoli => 3,
+            Operation::Unitary(gate) | Operation::Custom { gate, .. } => {
+                gate.matrix.len().trailing_zeros() as usize
+            }
+            Operation::Conditional { operation, .. } => operation.num_qubits(),
+            Operation::Snapshot(_) => 0,
+            Operation::Barrier(num_qubits) => *num_qubits,
+            Operation::Assert(assertion) => assertion.num_qubits(),
+        }
+    }
+
+    /// Returns a short lowercase label for the operation, e.g. for text diagrams.
+    ///
+    /// Conditional operations are labeled `if` followed by the label of the operation they
+    /// guard, e.g. `ifx`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    ///
+    /// assert_eq!(Operation::Cnot.name(), "cx");
+    /// assert_eq!(Operation::Measure(0).name(), "m");
+    /// ```
+    pub fn name(&self) -> String {
+        match self {
+            Operation::H => "h".to_string(),
+            Operation::X => "x".to_string(),
+            Operation::Y => "y".to_string(),
+            Operation::Z => "z".to_string(),
+            Operation::S => "s".to_string(),
+            Operation::T => "t".to_string(),
+            Operation::Rx(_) => "rx".to_string(),
+            Operation::Ry(_) => "ry".to_string(),
+            Operation::Rz(_) => "rz".to_string(),
+            Operation::Phase(_) => "p".to_string(),
+            Operation::Cnot => "cx".to_string(),
+            Operation::Cz => "cz".to_string(),
+            Operation::Swap => "swap".to_string(),
+            Operation::ISwap => "iswap".to_string(),
+            Operation::Toffoli => "ccx".to_string(),
+            Operation::Unitary(_) => "u".to_string(),
+            Operation::Custom { name, .. } => name.clone(),
+            Operation::Parametric { gate, .. } => gate.name().to_string(),
+            Operation::Measure(_) => "m".to_string(),
+            Operation::Reset => "reset".to_string(),
+            Operation::Conditional { operation, .. } => format!("if{}", operation.name()),
+            Operation::Snapshot(_) => "snapshot".to_string(),
+            Operation::Barrier(_) => "barrier".to_string(),
+            Operation::Assert(_) => "assert".to_string(),
+        }
+    }
+
+    /// Returns `true` if the operation is a fixed unitary gate, i.e. not a measurement, reset,
+    /// classically conditioned operation, snapshot, barrier or assertion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    ///
+    /// assert!(Operation::Cnot.is_unitary());
+    /// assert!(!Operation::Measure(0).is_unitary());
+    /// ```
+    pub fn is_unitary(&self) -> bool {
+        !matches!(
+            self,
+            Operation::Measure(_)
+                | Operation::Reset
+                | Operation::Conditional { .. }
+                | Operation::Snapshot(_)
+                | Operation::Barrier(_)
+                | Operation::Assert(_)
+        )
+    }
+
+    /// Returns `true` if the operation leaves the state unchanged: a barrier, a snapshot or an
+    /// assertion, which only checks the state. It is the identity wherever a circuit is treated
+    /// as a unitary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    ///
+    /// assert!(Operation::Barrier(2).is_transparent());
+    /// assert!(Operation::Snapshot("before".to_string()).is_transparent());
+    /// assert!(!Operation::Measure(0).is_transparent());
+    /// ```
+    pub fn is_transparent(&self) -> bool {
+        matches!(
+            self,
+            Operation::Barrier(_) | Operation::Snapshot(_) | Operation::Assert(_)
+        )
+    }
+
+    /// Returns the gate acting on the operation's own qubits.
+    ///
+    /// Bit `j` of the gate's basis index corresponds to the `j`-th qubit of the instruction, so a
+    /// `Cnot` on `[control, target]` uses control bit 0 and target bit 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation is not unitary (see [`Operation::is_unitary`]) or is a rotation by
+    /// an unbound parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    ///
+    /// assert_eq!(Operation::Cnot.gate().matrix.len(), 4);
+    /// ```
+    pub fn gate(&self) -> Gate {
+        match self {
+            Operation::H => hadamard(1),
+            Operation::X => pauli_x(),
+            Operation::Y => pauli_y(),
+            Operation::Z => pauli_z(),
+            Operation::S => s(),
+            Operation::T => t(),
+            Operation::Rx(theta) => rx(*theta),
+            Operation::Ry(theta) => ry(*theta),
+            Operation::Rz(theta) => rz(*theta),
+            Operation::Phase(theta) => phase(*theta),
+            Operation::Cnot => cnot(0, 1, 2),
+            Operation::Cz => cz(0, 1, 2),
+            Operation::Swap => swap(0, 1, 2),
+            Operation::ISwap => iswap(0, 1, 2),
+            Operation::Toffoli => toffoli(0, 1, 2, 3),
+            Operation::Unitary(gate) | Operation::Custom { gate, .. } => gate.clone(),
+            Operation::Parametric { parameter, .. } => panic!(
+                "parameter '{}' is unbound; bind it with Circuit::bind_parameters",
+                parameter.name()
+            ),
+            Operation::Measure(_)
+            | Operation::Reset
+            | Operation::Conditional { .. }
+            | Operation::Snapshot(_)
+            | Operation::Barrier(_)
+            | Operation::Assert(_) => {
+                panic!("{:?} is not a unitary gate", self)
+            }
+        }
+    }
+
+    /// Returns the operation that undoes this one.
+    ///
+    /// Self-inverse gates are returned unchanged, rotations and phases are negated, a custom gate
+    /// `name` becomes the custom gate `name_dg` of its adjoint (and `name_dg` becomes `name`
+    /// again), and other gates become the [`Operation::Unitary`] of their adjoint. Transparent
+    /// operations (see
+    /// [`Operation::is_transparent`]) are returned unchanged, so an inverted circuit checks its
+    /// assertions at their mirrored positions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation is neither unitary (see [`Operation::is_unitary`]) nor
+    /// transparent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    ///
+    /// assert_eq!(Operation::Rx(0.3).inverse(), Operation::Rx(-0.3));
+    /// assert_eq!(Operation::Cnot.inverse(), Operation::Cnot);
+    /// ```
+    pub fn inverse(&self) -> Operation {
+        match self {
+            Operation::H
+            | Operation::X
+            | Operation::Y
+            | Operation::Z
+            | Operation::Cnot
+            | Operation::Cz
+            | Operation::Swap
+            | Operation::Toffoli => self.clone(),
+            Operation::S => Operation::Phase(-FRAC_PI_2),
+            Operation::T => Operation::Phase(-FRAC_PI_4),
+            Operation::Rx(theta) => Operation::Rx(-theta),
+            Operation::Ry(theta) => Operation::Ry(-theta),
+            Operation::Rz(theta) => Operation::Rz(-theta),
+            Operation::Phase(theta) => Operation::Phase(-theta),
+            Operation::ISwap | Operation::Unitary(_) => Operation::Unitary(self.gate().dagger()),
+            Operation::Custom { name, gate } => Operation::Custom {
+                name: match name.strip_suffix(INVERSE_SUFFIX) {
+                    Some(base) => base.to_string(),
+                    None => format!("{}{}", name, INVERSE_SUFFIX),
+                },
+                gate: gate.dagger(),
+            },
+            Operation::Parametric { gate, parameter } => Operation::Parametric {
+                gate: *gate,
+                parameter: -parameter.clone(),
+            },
+            Operation::Barrier(_) | Operation::Snapshot(_) | Operation::Assert(_) => self.clone(),
+            Operation::Measure(_) | Operation::Reset | Operation::Conditional { .. } => {
+                panic!("{:?} cannot be inverted", self)
+            }
+        }
+    }
+}
+
+/// An `Instruction` applies an [`Operation`] to specific qubits of a register.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instruction {
+    pub operation: Operation,
+    pub qubits: Vec<usize>,
+}
+
+impl Instruction {
+    /// Creates a new `Instruction`.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The operation to perform.
+    /// * `qubits` - The qubits it acts on, in the operation's argument order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Instruction, Operation};
+    ///
+    /// let instruction = Instruction::new(Operation::Cnot, vec![0, 1]);
+    /// assert_eq!(instruction.qubits, vec![0, 1]);
+    /// ```
+    pub fn new(operation: Operation, qubits: Vec<usize>) -> Self {
+        Instruction { operation, qubits }
+    }
+
+    /// Lowers the instruction to a gate acting on a full `num_qubits`-qubit register.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The total number of qubits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation is not unitary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Instruction, Operation};
+    /// use quantum_simulator::gates::cnot;
+    ///
+    /// let lowered = Instruction::new(Operation::Cnot, vec![2, 0]).to_gate(3);
+    /// assert_eq!(lowered.matrix, cnot(2, 0, 3).matrix);
+    /// ```
+    pub fn to_gate(&self, num_qubits: usize) -> Gate {
+        let size = 2usize.pow(num_qubits as u32);
+        let mut qubit = Qubit::from_state(vec![Default::default(); size]);
+        let mut columns = Vec::with_capacity(size);
+        for basis in 0..size {
+            qubit.state.iter_mut().for_each(|a| *a = Default::default());
+            qubit.state[basis] = num_complex::Complex::new(1.0, 0.0);
+            self.apply(&mut qubit);
+            columns.push(qubit.state.clone());
+        }
+        Gate::new(
+            (0..size)
+                .map(|row| columns.iter().map(|column| column[row]).collect())
+                .collect(),
+        )
+    }
+
+    /// Applies the instruction to the given qubit register in place.
+    ///
+    /// Snapshots, barriers and assertions leave the register unchanged, and assertions are not
+    /// checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The register on which to apply the instruction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation is neither unitary nor a snapshot, barrier or assertion.
+    pub fn apply(&self, qubit: &mut Qubit) {
+        if let Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_) =
+            self.operation
+        {
+            return;
+        }
+        self.kind().apply(qubit);
+    }
+
+    /// Returns the instruction's gate bound to its qubits, in its cheapest representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation is not unitary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Instruction, Operation};
+    /// use quantum_simulator::gates::GateKind;
+    ///
+    /// let instruction = Instruction::new(Operation::Cz, vec![2, 0]);
+    /// assert!(matches!(instruction.kind(), GateKind::Diagonal { .. }));
+    /// assert_eq!(instruction.kind().targets(), &[2, 0]);
+    /// ```
+    pub fn kind(&self) -> GateKind {
+        GateKind::new(&self.operation.gate(), &self.qubits)
+    }
+}
 
 /// A `Circuit` represents a sequence of quantum gates to be applied to qubits.
+///
+/// Measurements write to a classical register of `num_clbits` bits, which starts out all `0` and
+/// grows to cover every classical bit an instruction refers to.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Circuit {
-    gates: Vec<Gate>,
+    num_qubits: usize,
+    num_clbits: usize,
+    instructions: Vec<Instruction>,
 }
 
-impl Circuit {
-    /// Creates a new, empty `Circuit`.
+impl Circuit {
+    /// Creates a new, empty `Circuit` on `num_qubits` qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register the circuit acts on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let circuit = Circuit::new(2);
+    /// assert_eq!(circuit.num_qubits(), 2);
+    /// ```
+    pub fn new(num_qubits: usize) -> Self {
+        Circuit {
+            num_qubits,
+            num_clbits: 0,
+            instructions: vec![],
+        }
+    }
+
+    /// Creates a new, empty `Circuit` with a classical register of `num_clbits` bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register the circuit acts on.
+    /// * `num_clbits` - The width of the classical register.
+    pub fn with_clbits(num_qubits: usize, num_clbits: usize) -> Self {
+        Circuit {
+            num_qubits,
+            num_clbits,
+            instructions: vec![],
+        }
+    }
+
+    /// Returns the number of qubits the circuit acts on.
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Returns the number of classical bits in the circuit's register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.measure(1, 3);
+    /// assert_eq!(circuit.num_clbits(), 4);
+    /// ```
+    pub fn num_clbits(&self) -> usize {
+        self.num_clbits
+    }
+
+    /// Returns the instructions of the circuit in application order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.h(0);
+    /// assert_eq!(circuit.instructions()[0].operation, Operation::H);
+    /// ```
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Returns the instructions grouped into moments, sets of instructions on disjoint qubits
+    /// that run at the same time, each as early as the instructions before it allow.
+    ///
+    /// Moments are the layers of the circuit's [`Schedule`]; barriers belong to no moment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    ///
+    /// let mut circuit = Circuit::new(3);
+    /// circuit.h(0).h(1).cnot(0, 1).x(2);
+    ///
+    /// let moments = circuit.moments();
+    /// assert_eq!(moments.len(), 2);
+    /// assert_eq!(moments[0].len(), 3);
+    /// assert_eq!(moments[1][0].operation, Operation::Cnot);
+    /// ```
+    pub fn moments(&self) -> Vec<Vec<&Instruction>> {
+        Schedule::new(self)
+            .layers()
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|&index| &self.instructions[index])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the depth of the circuit, its number of moments (see [`Circuit::moments`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(3);
+    /// circuit.h(0).cnot(0, 1).cnot(1, 2).x(0);
+    /// assert_eq!(circuit.depth(), 3);
+    /// ```
+    pub fn depth(&self) -> usize {
+        Schedule::new(self).depth()
+    }
+
+    /// Returns the number of unitary gates, not counting measurements, resets, classically
+    /// conditioned operations, snapshots, barriers or assertions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1).barrier(&[0, 1]).measure(0, 0);
+    /// assert_eq!(circuit.gate_count(), 2);
+    /// ```
+    pub fn gate_count(&self) -> usize {
+        self.instructions
+            .iter()
+            .filter(|instruction| instruction.operation.is_unitary())
+            .count()
+    }
+
+    /// Counts the instructions of each kind, keyed by [`Operation::name`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).h(1).cnot(0, 1).measure(0, 0).measure(1, 1);
+    ///
+    /// let counts = circuit.count_ops();
+    /// assert_eq!(counts["h"], 2);
+    /// assert_eq!(counts["cx"], 1);
+    /// assert_eq!(counts["m"], 2);
+    /// ```
+    pub fn count_ops(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for instruction in &self.instructions {
+            *counts.entry(instruction.operation.name()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Adds a gate acting on the whole register to the circuit.
+    ///
+    /// The gate's matrix spans qubits `0..k` for a `2^k`-dimensional gate; the circuit widens to
+    /// `k` qubits if it was narrower.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The quantum gate to add.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::gates::hadamard;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.add_gate(hadamard(1));
+    /// ```
+    pub fn add_gate(&mut self, gate: Gate) {
+        let width = gate.matrix.len().trailing_zeros() as usize;
+        self.num_qubits = self.num_qubits.max(width);
+        self.instructions.push(Instruction::new(
+            Operation::Unitary(gate),
+            (0..width).collect(),
+        ));
+    }
+
+    /// Adds a single-qubit gate acting on one qubit of the register.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The single-qubit gate to add.
+    /// * `target` - The index of the qubit the gate acts on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` is outside the circuit or the gate is not a single-qubit gate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::gates::pauli_x;
+    ///
+    /// let mut circuit = Circuit::new(4);
+    /// circuit.add_single_qubit_gate(pauli_x(), 2);
+    /// assert_eq!(circuit.instructions()[0].qubits, vec![2]);
+    /// ```
+    pub fn add_single_qubit_gate(&mut self, gate: Gate, target: usize) -> &mut Self {
+        self.push(Operation::Unitary(gate), vec![target])
+    }
+
+    /// Adds a gate on `targets` that only acts when `control` is `|1⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate to control; bit `j` of its basis index corresponds to `targets[j]`.
+    /// * `control` - The control qubit index.
+    /// * `targets` - The qubits the gate acts on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a qubit is outside the circuit or repeated, or the gate does not match the
+    /// number of targets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::gates::phase;
+    /// use std::f64::consts::PI;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.add_controlled_gate(phase(PI / 2.0), 0, &[1]);
+    /// assert_eq!(circuit.instructions()[0].qubits, vec![0, 1]);
+    /// ```
+    pub fn add_controlled_gate(
+        &mut self,
+        gate: Gate,
+        control: usize,
+        targets: &[usize],
+    ) -> &mut Self {
+        let qubits = std::iter::once(control)
+            .chain(targets.iter().copied())
+            .collect();
+        self.push(Operation::Unitary(gate.with_control()), qubits)
+    }
+
+    /// Adds a custom gate registered in the thread's
+    /// [`GateLibrary`](crate::library::GateLibrary), see
+    /// [`Simulator::set_gate_library`](crate::simulator::Simulator::set_gate_library).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the gate is registered under.
+    /// * `qubits` - The qubits it acts on, in the gate's argument order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no gate is registered under `name`, a qubit is outside the circuit or repeated,
+    /// or the gate does not match the number of qubits.
+    pub fn custom(&mut self, name: &str, qubits: &[usize]) -> &mut Self {
+        let operation = library::current()
+            .operation(name)
+            .unwrap_or_else(|| panic!("no gate '{}' is registered in the gate library", name));
+        self.push(operation, qubits.to_vec())
+    }
+
+    /// Adds an instruction after checking that its qubits exist, are distinct and match the
+    /// operation's arity, and that any classical bit is below 2^20.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The instruction to add.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Instruction, Operation};
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// assert!(circuit.add_instruction(Instruction::new(Operation::Cz, vec![0, 1])).is_ok());
+    /// assert!(circuit.add_instruction(Instruction::new(Operation::H, vec![5])).is_err());
+    /// ```
+    pub fn add_instruction(&mut self, instruction: Instruction) -> Result<(), QuantumError> {
+        self.check_qubits(&instruction)?;
+        match &instruction.operation {
+            Operation::Measure(clbit) | Operation::Conditional { clbit, .. }
+                if *clbit >= MAX_CLBITS =>
+            {
+                return Err(QuantumError::Unsupported {
+                    feature: format!("classical bit {} beyond {} bits", clbit, MAX_CLBITS),
+                });
+            }
+            Operation::Measure(clbit) => self.num_clbits = self.num_clbits.max(clbit + 1),
+            Operation::Conditional { clbit, operation } => {
+                if !operation.is_unitary() {
+                    return Err(QuantumError::Unsupported {
+                        feature: format!("conditional {:?}", operation),
+                    });
+                }
+                self.num_clbits = self.num_clbits.max(clbit + 1);
+            }
+            Operation::Assert(assertion) => assertion.validate()?,
+            _ => {}
+        }
+        self.instructions.push(instruction);
+        Ok(())
+    }
+
+    /// Applies a Hadamard gate to `qubit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// assert_eq!(circuit.instructions().len(), 2);
+    /// ```
+    pub fn h(&mut self, qubit: usize) -> &mut Self {
+        self.push(Operation::H, vec![qubit])
+    }
+
+    /// Applies a Pauli-X gate to `qubit`.
+    pub fn x(&mut self, qubit: usize) -> &mut Self {
+        self.push(Operation::X, vec![qubit])
+    }
+
+    /// Applies a Pauli-Y gate to `qubit`.
+    pub fn y(&mut self, qubit: usize) -> &mut Self {
+        self.push(Operation::Y, vec![qubit])
+    }
+
+    /// Applies a Pauli-Z gate to `qubit`.
+    pub fn z(&mut self, qubit: usize) -> &mut Self {
+        self.push(Operation::Z, vec![qubit])
+    }
+
+    /// Applies an S gate to `qubit`.
+    pub fn s(&mut self, qubit: usize) -> &mut Self {
+        self.push(Operation::S, vec![qubit])
+    }
+
+    /// Applies a T gate to `qubit`.
+    pub fn t(&mut self, qubit: usize) -> &mut Self {
+        self.push(Operation::T, vec![qubit])
+    }
+
+    /// Applies an X rotation by `theta`, a number or a [`Parameter`], to `qubit`.
+    pub fn rx(&mut self, qubit: usize, theta: impl Into<Angle>) -> &mut Self {
+        self.push(ParametricGate::Rx.with_angle(theta.into()), vec![qubit])
+    }
+
+    /// Applies a Y rotation by `theta`, a number or a [`Parameter`], to `qubit`.
+    pub fn ry(&mut self, qubit: usize, theta: impl Into<Angle>) -> &mut Self {
+        self.push(ParametricGate::Ry.with_angle(theta.into()), vec![qubit])
+    }
+
+    /// Applies a Z rotation by `theta`, a number or a [`Parameter`], to `qubit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    /// use quantum_simulator::parameter::Parameter;
+    ///
+    /// let mut circuit = Circuit::new(3);
+    /// circuit.rz(2, std::f64::consts::PI / 4.0);
+    /// circuit.rz(0, Parameter::new("theta"));
+    /// assert_eq!(circuit.instructions()[0].qubits, vec![2]);
+    /// assert_eq!(circuit.parameters(), vec!["theta"]);
+    /// ```
+    pub fn rz(&mut self, qubit: usize, theta: impl Into<Angle>) -> &mut Self {
+        self.push(ParametricGate::Rz.with_angle(theta.into()), vec![qubit])
+    }
+
+    /// Applies a phase gate with angle `theta`, a number or a [`Parameter`], to `qubit`.
+    pub fn phase(&mut self, qubit: usize, theta: impl Into<Angle>) -> &mut Self {
+        self.push(ParametricGate::Phase.with_angle(theta.into()), vec![qubit])
+    }
+
+    /// Applies a CNOT gate with the given control and target.
+    pub fn cnot(&mut self, control: usize, target: usize) -> &mut Self {
+        self.push(Operation::Cnot, vec![control, target])
+    }
+
+    /// Applies a controlled-Z gate with the given control and target.
+    pub fn cz(&mut self, control: usize, target: usize) -> &mut Self {
+        self.push(Operation::Cz, vec![control, target])
+    }
+
+    /// Swaps qubits `a` and `b`.
+    pub fn swap(&mut self, a: usize, b: usize) -> &mut Self {
+        self.push(Operation::Swap, vec![a, b])
+    }
+
+    /// Applies an iSWAP gate to qubits `a` and `b`.
+    pub fn iswap(&mut self, a: usize, b: usize) -> &mut Self {
+        self.push(Operation::ISwap, vec![a, b])
+    }
+
+    /// Applies a Toffoli gate with the given controls and target.
+    pub fn toffoli(&mut self, control1: usize, control2: usize, target: usize) -> &mut Self {
+        self.push(Operation::Toffoli, vec![control1, control2, target])
+    }
+
+    /// Applies an X gate to `target` controlled by every qubit in `controls`, the generalized
+    /// Toffoli. Up to two controls give an X, CNOT or Toffoli instruction; more give one
+    /// [`Operation::Unitary`] on the controls followed by the target.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a qubit is outside the circuit or repeated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut circuit = Circuit::new(5);
+    /// circuit.x(0).x(1).x(3).mcx(&[0, 1, 3], 4);
+    /// assert_eq!(circuit.instructions().len(), 4);
+    ///
+    /// let mut qubit = Qubit::zero_state(5);
+    /// circuit.run(&mut qubit);
+    /// assert!((qubit.state[0b11011].norm() - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn mcx(&mut self, controls: &[usize], target: usize) -> &mut Self {
+        match *controls {
+            [] => self.x(target),
+            [control] => self.cnot(control, target),
+            [control1, control2] => self.toffoli(control1, control2, target),
+            _ => {
+                // Bit j of the gate's basis index is qubit j of the instruction
+                let k = controls.len();
+                let gate = mcx(&(0..k).collect::<Vec<_>>(), k, k + 1);
+                let qubits = controls.iter().copied().chain([target]).collect();
+                self.push(Operation::Unitary(gate), qubits)
+            }
+        }
+    }
+
+    /// Multiplies the states with `target` and every qubit in `controls` set by `e^{iθ}`, e.g.
+    /// to mark a basis state in a Grover oracle. No controls give a phase instruction; otherwise
+    /// it is one [`Operation::Unitary`] on the controls followed by the target.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a qubit is outside the circuit or repeated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use std::f64::consts::PI;
+    ///
+    /// // Marks |111⟩ with a sign, as a Grover oracle does
+    /// let mut circuit = Circuit::new(3);
+    /// circuit.h(0).h(1).h(2).mcp(PI, &[0, 1], 2);
+    /// let mut qubit = Qubit::zero_state(3);
+    /// circuit.run(&mut qubit);
+    /// assert!(qubit.state[0b111].re < 0.0);
+    /// assert!(qubit.state[0b011].re > 0.0);
+    /// ```
+    pub fn mcp(&mut self, theta: f64, controls: &[usize], target: usize) -> &mut Self {
+        if controls.is_empty() {
+            return self.phase(target, theta);
+        }
+        let k = controls.len();
+        let gate = mcp(theta, &(0..k).collect::<Vec<_>>(), k, k + 1);
+        let qubits = controls.iter().copied().chain([target]).collect();
+        self.push(Operation::Unitary(gate), qubits)
+    }
+
+    /// Applies the generalized Toffoli of [`Circuit::mcx`] as 1- and 2-qubit gates only, e.g.
+    /// for hardware without multi-qubit gates.
+    ///
+    /// With more than two controls the controls are combined pairwise into a chain of ancillas by
+    /// Toffoli gates, the last ancilla and control flip the target, and the chain is undone.
+    /// Every Toffoli is then written as six CNOTs with Hadamard, T and T† gates, so `k`
+    /// controls cost `2k - 3` Toffolis and `k - 2` ancillas.
+    ///
+    /// # Arguments
+    ///
+    /// * `controls` - The control qubit indices.
+    /// * `target` - The target qubit index.
+    /// * `ancillas` - Work qubits, of which the first `controls.len() - 2` are used. They must be
+    ///   in `|0⟩` and are returned to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are too few ancillas, or a qubit is outside the circuit or repeated among
+    /// the controls, target and used ancillas.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// // Four controls, the target 4 and the ancillas 5 and 6
+    /// let mut circuit = Circuit::new(7);
+    /// circuit.x(0).x(1).x(2).x(3);
+    /// circuit.mcx_with_ancillas(&[0, 1, 2, 3], 4, &[5, 6]);
+    /// assert!(circuit.instructions().iter().all(|i| i.qubits.len() <= 2));
+    /// assert_eq!(circuit.count_ops()["cx"], 5 * 6);
+    ///
+    /// let mut qubit = Qubit::zero_state(7);
+    /// circuit.run(&mut qubit);
+    /// assert!((qubit.state[0b0011111].norm() - 1.0).abs() < 1e-9);
+    /// ```
+    pub fn mcx_with_ancillas(
+        &mut self,
+        controls: &[usize],
+        target: usize,
+        ancillas: &[usize],
+    ) -> &mut Self {
+        let needed = controls.len().saturating_sub(2);
+        assert!(
+            ancillas.len() >= needed,
+            "{} controls need {} ancillas, found {}",
+            controls.len(),
+            needed,
+            ancillas.len()
+        );
+        let ancillas = &ancillas[..needed];
+        let qubits: Vec<usize> = controls
+            .iter()
+            .chain([&target])
+            .chain(ancillas)
+            .copied()
+            .collect();
+        let distinct: BTreeSet<&usize> = qubits.iter().collect();
+        assert_eq!(
+            distinct.len(),
+            qubits.len(),
+            "the controls, target and ancillas must be distinct"
+        );
+
+        match *controls {
+            [] => self.x(target),
+            [control] => self.cnot(control, target),
+            [control1, control2] => self.decomposed_toffoli(control1, control2, target),
+            _ => {
+                let mut chain = vec![(controls[0], controls[1], ancillas[0])];
+                for k in 2..controls.len() - 1 {
+                    chain.push((controls[k], ancillas[k - 2], ancillas[k - 1]));
+                }
+                for &(a, b, c) in &chain {
+                    self.decomposed_toffoli(a, b, c);
+                }
+                self.decomposed_toffoli(controls[controls.len() - 1], ancillas[needed - 1], target);
+                for &(a, b, c) in chain.iter().rev() {
+                    self.decomposed_toffoli(a, b, c);
+                }
+                self
+            }
+        }
+    }
+
+    /// Applies a Toffoli gate as the standard circuit of six CNOTs, two Hadamards and seven T
+    /// and T† gates.
+    fn decomposed_toffoli(&mut self, control1: usize, control2: usize, target: usize) -> &mut Self {
+        self.h(target)
+            .cnot(control2, target)
+            .phase(target, -FRAC_PI_4)
+            .cnot(control1, target)
+            .t(target)
+            .cnot(control2, target)
+            .phase(target, -FRAC_PI_4)
+            .cnot(control1, target)
+            .t(control2)
+            .t(target)
+            .h(target)
+            .cnot(control1, control2)
+            .t(control1)
+            .phase(control2, -FRAC_PI_4)
+            .cnot(control1, control2)
+    }
+
+    /// Measures `qubit` into classical bit `clbit`, collapsing the state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0).measure(0, 0);
+    /// assert_eq!(circuit.run_with_rng(&mut Qubit::new(), &mut rand::thread_rng()), vec![true]);
+    /// ```
+    pub fn measure(&mut self, qubit: usize, clbit: usize) -> &mut Self {
+        self.push(Operation::Measure(clbit), vec![qubit])
+    }
+
+    /// Measures `qubit` in a Pauli basis into classical bit `clbit`, by rotating the basis onto
+    /// the computational one first (see [`Basis::rotation`]). The qubit is left in the rotated
+    /// frame, so a `0` means the `+1` eigenstate of the basis.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::{Basis, Qubit};
+    ///
+    /// // Both halves of a Bell pair agree in the X basis
+    /// let mut circuit = Circuit::new(2);
+    /// circuit
+    ///     .h(0)
+    ///     .cnot(0, 1)
+    ///     .measure_in_basis(0, 0, Basis::X)
+    ///     .measure_in_basis(1, 1, Basis::X);
+    /// let bits = circuit.run_with_rng(&mut Qubit::zero_state(2), &mut rand::thread_rng());
+    /// assert_eq!(bits[0], bits[1]);
+    /// ```
+    pub fn measure_in_basis(&mut self, qubit: usize, clbit: usize, basis: Basis) -> &mut Self {
+        match basis {
+            Basis::X => {
+                self.h(qubit);
+            }
+            Basis::Y => {
+                self.phase(qubit, -FRAC_PI_2).h(qubit);
+            }
+            Basis::Z => {}
+        }
+        self.measure(qubit, clbit)
+    }
+
+    /// Resets `qubit` to `|0⟩`.
+    pub fn reset(&mut self, qubit: usize) -> &mut Self {
+        self.push(Operation::Reset, vec![qubit])
+    }
+
+    /// Applies `operation` to `qubits` only if classical bit `clbit` is `1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the qubits are invalid for the operation, or the operation is itself a
+    /// measurement, reset or conditional operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    ///
+    /// // Measure qubit 0 and copy the outcome onto qubit 1.
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).measure(0, 0).conditional(0, Operation::X, vec![1]);
+    /// assert_eq!(circuit.num_clbits(), 1);
+    /// ```
+    pub fn conditional(
+        &mut self,
+        clbit: usize,
+        operation: Operation,
+        qubits: Vec<usize>,
+    ) -> &mut Self {
+        self.push(
+            Operation::Conditional {
+                clbit,
+                operation: Box::new(operation),
+            },
+            qubits,
+        )
+    }
+
+    /// Marks a point, named `label`, at which stepwise simulation records the state.
+    ///
+    /// The snapshot acts on no qubits and leaves the state unchanged; see
+    /// [`Simulator::run_stepwise`](crate::simulator::Simulator::run_stepwise).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.h(0).snapshot("superposed");
+    /// assert_eq!(
+    ///     circuit.instructions()[1].operation,
+    ///     Operation::Snapshot("superposed".to_string())
+    /// );
+    /// ```
+    pub fn snapshot(&mut self, label: &str) -> &mut Self {
+        self.push(Operation::Snapshot(label.to_string()), vec![])
+    }
+
+    /// Adds a barrier across `qubits`, or across every qubit if `qubits` is empty.
+    ///
+    /// A barrier leaves the state unchanged. Instructions after it on any of its qubits are
+    /// scheduled after every instruction before it on all of them, and the optimizer does not
+    /// merge or cancel gates across it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a qubit is out of range or listed twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).barrier(&[]).h(1);
+    /// assert_eq!(circuit.depth(), 2);
+    ///
+    /// // The barrier keeps the two X gates apart
+    /// circuit.x(0).barrier(&[0]).x(0);
+    /// assert_eq!(circuit.optimized().0.gate_count(), 4);
+    /// ```
+    pub fn barrier(&mut self, qubits: &[usize]) -> &mut Self {
+        let qubits: Vec<usize> = if qubits.is_empty() {
+            (0..self.num_qubits).collect()
+        } else {
+            qubits.to_vec()
+        };
+        self.push(Operation::Barrier(qubits.len()), qubits)
+    }
+
+    /// Asserts that the register is in `state`, up to global phase, when simulation reaches this
+    /// point.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The expected amplitudes of all qubits, indexed by basis state.
+    /// * `tolerance` - How far below one the fidelity with `state` may fall.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `state` does not have one amplitude per basis state or is not normalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use num_complex::Complex;
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let amplitude = Complex::new(std::f64::consts::FRAC_1_SQRT_2, 0.0);
+    /// let zero = Complex::new(0.0, 0.0);
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1).assert_state(&[amplitude, zero, zero, amplitude], 1e-9);
+    /// assert!(circuit.try_run(&mut Qubit::zero_state(2)).is_ok());
+    /// ```
+    pub fn assert_state(&mut self, state: &[Complex<f64>], tolerance: f64) -> &mut Self {
+        let assertion = Assertion::State {
+            state: state.to_vec(),
+            tolerance,
+        };
+        self.push(Operation::Assert(assertion), (0..self.num_qubits).collect())
+    }
+
+    /// Asserts that measuring every qubit gives `bitstring` with probability `probability`, to
+    /// within `tolerance`, when simulation reaches this point.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitstring` - The outcome, with the last qubit first.
+    /// * `probability` - The expected probability of the outcome.
+    /// * `tolerance` - The largest accepted deviation from `probability`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bitstring` is not one `0` or `1` per qubit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).assert_prob("01", 0.5, 1e-9).assert_prob("11", 0.5, 1e-9);
+    /// assert!(matches!(
+    ///     circuit.try_run(&mut Qubit::zero_state(2)),
+    ///     Err(QuantumError::AssertionFailed { instruction: 2, .. })
+    /// ));
+    /// ```
+    pub fn assert_prob(&mut self, bitstring: &str, probability: f64, tolerance: f64) -> &mut Self {
+        let assertion = Assertion::Probability {
+            bitstring: bitstring.to_string(),
+            probability,
+            tolerance,
+        };
+        self.push(Operation::Assert(assertion), (0..self.num_qubits).collect())
+    }
+
+    /// Asserts that qubits `a` and `b` are entangled with each other when simulation reaches this
+    /// point.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either qubit is out of range or they are the same qubit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1).assert_entangled(0, 1);
+    /// assert!(circuit.try_run(&mut Qubit::zero_state(2)).is_ok());
+    /// ```
+    pub fn assert_entangled(&mut self, a: usize, b: usize) -> &mut Self {
+        self.push(Operation::Assert(Assertion::Entangled), vec![a, b])
+    }
+
+    /// Returns the inverse circuit, which applies the inverse of every instruction in reverse
+    /// order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit is not unitary (see [`Circuit::is_unitary`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).s(0).cnot(0, 1);
+    /// let inverse = circuit.inverse();
+    /// assert_eq!(inverse.instructions()[0].operation, Operation::Cnot);
+    /// assert_eq!(inverse.instructions()[2].operation, Operation::H);
+    /// ```
+    pub fn inverse(&self) -> Circuit {
+        Circuit {
+            num_qubits: self.num_qubits,
+            num_clbits: self.num_clbits,
+            instructions: self
+                .instructions
+                .iter()
+                .rev()
+                .map(|instruction| {
+                    Instruction::new(instruction.operation.inverse(), instruction.qubits.clone())
+                })
+                .collect(),
+        }
+    }
+
+    /// Renders the circuit as a text diagram with one wire per qubit, drawn with Unicode
+    /// box-drawing characters.
+    ///
+    /// Gates are boxes on their wires, controls are dots joined to their target, and
+    /// measurements show the classical bit they write. Instructions on disjoint qubits share a
+    /// column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1).measure(1, 0);
+    ///
+    /// let expected = [
+    ///     "     ┌───┐",
+    ///     "q0: ─┤ H ├───●────────────",
+    ///     "     └───┘   │",
+    ///     "           ┌─┴─┐ ┌──────┐",
+    ///     "q1: ───────┤ X ├─┤ M→c0 ├─",
+    ///     "           └───┘ └──────┘",
+    /// ];
+    /// assert_eq!(circuit.draw(), expected.join("\n") + "\n");
+    /// ```
+    pub fn draw(&self) -> String {
+        diagram::render(self, &diagram::UNICODE)
+    }
+
+    /// Renders the circuit like [`Circuit::draw`], using only ASCII characters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.cz(0, 1).x(1);
+    /// let expected = ["q0: -*-------", "     |", "     | +---+", "q1: -*-| X |-", "       +---+"];
+    /// assert_eq!(circuit.draw_ascii(), expected.join("\n") + "\n");
+    /// ```
+    pub fn draw_ascii(&self) -> String {
+        diagram::render(self, &diagram::ASCII)
+    }
+
+    /// Returns the circuit simplified by the standard optimization passes (see
+    /// [`PassManager::standard`]), with the gate count and depth before and after.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.rz(0, 0.25).x(1).rz(0, 0.5).x(1).h(1).rx(0, 0.0);
+    ///
+    /// let (optimized, report) = circuit.optimized();
+    /// assert_eq!(optimized.instructions().len(), 2);
+    /// assert_eq!(optimized.instructions()[0].operation, Operation::Rz(0.75));
+    /// assert_eq!((report.gates_before, report.gates_after), (6, 2));
+    /// assert_eq!((report.depth_before, report.depth_after), (3, 1));
+    /// ```
+    pub fn optimized(&self) -> (Circuit, OptimizationReport) {
+        PassManager::standard().run(self)
+    }
+
+    /// Returns the instructions removed, inserted and changed going from this circuit to `other`,
+    /// e.g. to review what an optimizer did.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The new version of the circuit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    /// use quantum_simulator::diff::Change;
+    ///
+    /// let mut old = Circuit::new(2);
+    /// old.h(0).cnot(0, 1);
+    /// let mut new = Circuit::new(2);
+    /// new.h(0).x(1).cnot(0, 1);
+    ///
+    /// let diff = old.diff(&new);
+    /// assert!(matches!(
+    ///     &diff.changes[..],
+    ///     [Change::Inserted { index: 1, instruction }] if instruction.operation == Operation::X
+    /// ));
+    /// assert_eq!(diff.to_string(), "+ #1 X on [1]");
+    /// assert!(old.diff(&old).is_empty());
+    /// ```
+    pub fn diff(&self, other: &Circuit) -> CircuitDiff {
+        CircuitDiff::new(self, other)
+    }
+
+    /// Builds a named example circuit from the built-in library.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - `bell`, `teleportation`, `ghz-<n>` or `qft-<n>` for `n` from 2 to
+    ///   [`MAX_PRESET_QUBITS`](crate::presets::MAX_PRESET_QUBITS), or `grover-<n>` for `n` from 2
+    ///   to [`MAX_GROVER_PRESET_QUBITS`](crate::presets::MAX_GROVER_PRESET_QUBITS). The Grover
+    ///   search marks the all-ones state.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Circuit)` - The example circuit.
+    /// * `Err(QuantumError::Parse)` - If there is no preset of that name.
     ///
     /// # Examples
     ///
     /// ```
     /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let grover = Circuit::preset("grover-3").unwrap();
+    /// let mut register = Qubit::zero_state(3);
+    /// grover.run(&mut register);
+    /// assert!(register.state[0b111].norm_sqr() > 0.9);
     ///
-    /// let circuit = Circuit::new();
+    /// assert_eq!(Circuit::preset("ghz-5").unwrap().num_qubits(), 5);
+    /// assert!(Circuit::preset("shor-15").is_err());
+    /// assert!(Circuit::preset("grover-16").is_err());
     /// ```
-    pub fn new() -> Self {
-        Circuit { gates: vec![] }
+    pub fn preset(name: &str) -> Result<Circuit, QuantumError> {
+        presets::preset(name)
     }
 
-    /// Adds a gate to the circuit.
+    /// Returns the circuit with qubit `i` relabeled as `map[i]`.
+    ///
+    /// Labels may point beyond the current register, e.g. when placing a logical circuit on
+    /// physical qubits after routing; the result widens to fit them.
     ///
     /// # Arguments
     ///
-    /// * `gate` - The quantum gate to add.
+    /// * `map` - The new label of every qubit.
+    ///
+    /// # Returns
+    ///
+    /// * The relabeled circuit, or an error if `map` does not have one entry per qubit or
+    ///   repeats a label.
     ///
     /// # Examples
     ///
     /// ```
     /// use quantum_simulator::circuit::Circuit;
-    /// use quantum_simulator::gates::hadamard;
     ///
-    /// let mut circuit = Circuit::new();
-    /// circuit.add_gate(hadamard(1));
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// let placed = circuit.remap_qubits(&[3, 1]).unwrap();
+    /// assert_eq!(placed.num_qubits(), 4);
+    /// assert_eq!(placed.instructions()[1].qubits, vec![3, 1]);
     /// ```
-    pub fn add_gate(&mut self, gate: Gate) {
-        self.gates.push(gate);
+    pub fn remap_qubits(&self, map: &[usize]) -> Result<Circuit, QuantumError> {
+        if map.len() != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: map.len(),
+            });
+        }
+        for (position, &qubit) in map.iter().enumerate() {
+            if map[..position].contains(&qubit) {
+                return Err(QuantumError::DuplicateQubit { qubit });
+            }
+        }
+        let num_qubits = map
+            .iter()
+            .map(|&qubit| qubit + 1)
+            .fold(self.num_qubits, usize::max);
+        Ok(Circuit {
+            num_qubits,
+            num_clbits: self.num_clbits,
+            instructions: self
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    Instruction::new(
+                        instruction.operation.clone(),
+                        instruction.qubits.iter().map(|&qubit| map[qubit]).collect(),
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns a circuit that runs this one `k` times in a row.
+    ///
+    /// # Arguments
+    ///
+    /// * `k` - The number of repetitions; `0` gives an empty circuit of the same width.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.t(0);
+    /// assert_eq!(circuit.repeat(4).instructions().len(), 4);
+    /// ```
+    pub fn repeat(&self, k: usize) -> Circuit {
+        Circuit {
+            num_qubits: self.num_qubits,
+            num_clbits: self.num_clbits,
+            instructions: self
+                .instructions
+                .iter()
+                .cloned()
+                .cycle()
+                .take(k * self.instructions.len())
+                .collect(),
+        }
+    }
+
+    /// Appends the instructions of `other` acting on the same qubits and classical bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The circuit to append.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is wider than this circuit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut bell = Circuit::new(2);
+    /// bell.h(0).cnot(0, 1);
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.append(&bell).append(&bell.inverse());
+    /// assert_eq!(circuit.instructions().len(), 4);
+    /// ```
+    pub fn append(&mut self, other: &Circuit) -> &mut Self {
+        let qubits: Vec<usize> = (0..other.num_qubits).collect();
+        self.compose(other, &qubits)
+    }
+
+    /// Appends the instructions of `other`, mapping its qubit `i` onto qubit `qubits[i]` of this
+    /// circuit.
+    ///
+    /// Classical bits are shared, so `other`'s classical bit `k` is classical bit `k` here.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The sub-circuit to append.
+    /// * `qubits` - Where each of `other`'s qubits lands in this circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `qubits` does not have one entry per qubit of `other`, or maps a qubit outside
+    /// this circuit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut bell = Circuit::new(2);
+    /// bell.h(0).cnot(0, 1);
+    ///
+    /// let mut circuit = Circuit::new(4);
+    /// circuit.compose(&bell, &[0, 1]).compose(&bell, &[2, 3]);
+    /// assert_eq!(circuit.instructions()[3].qubits, vec![2, 3]);
+    /// ```
+    pub fn compose(&mut self, other: &Circuit, qubits: &[usize]) -> &mut Self {
+        assert_eq!(
+            qubits.len(),
+            other.num_qubits,
+            "expected one target qubit per qubit of the composed circuit"
+        );
+        self.num_clbits = self.num_clbits.max(other.num_clbits);
+        for instruction in &other.instructions {
+            let mapped = instruction
+                .qubits
+                .iter()
+                .map(|&qubit| qubits[qubit])
+                .collect();
+            self.push(instruction.operation.clone(), mapped);
+        }
+        self
+    }
+
+    /// Returns the names of the parameters the circuit's rotations depend on, sorted and without
+    /// duplicates.
+    pub fn parameters(&self) -> Vec<String> {
+        let names: BTreeSet<&str> = self
+            .instructions
+            .iter()
+            .filter_map(|instruction| match &instruction.operation {
+                Operation::Parametric { parameter, .. } => Some(parameter.name()),
+                Operation::Conditional { operation, .. } => match operation.as_ref() {
+                    Operation::Parametric { parameter, .. } => Some(parameter.name()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        names.into_iter().map(str::to_string).collect()
+    }
+
+    /// Returns a copy of the circuit with every parameter replaced by its value, ready to run.
+    ///
+    /// The circuit itself is left unchanged, so the same parameterized ansatz can be bound to
+    /// every point of a parameter sweep.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values by parameter name. Values for names the circuit does not use are
+    ///   ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Circuit)` - The circuit with numeric angles only.
+    /// * `Err(QuantumError::UnboundParameter)` - If `values` has no value for a parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::parameter::Parameter;
+    /// use std::collections::HashMap;
+    ///
+    /// let theta = Parameter::new("theta");
+    /// let mut ansatz = Circuit::new(2);
+    /// ansatz.ry(0, theta.clone()).cnot(0, 1).rz(1, theta * 2.0);
+    ///
+    /// for value in [0.1, 0.2] {
+    ///     let bound = ansatz.bind_parameters(&HashMap::from([("theta".to_string(), value)])).unwrap();
+    ///     assert_eq!(bound.instructions()[0].operation, Operation::Ry(value));
+    ///     assert_eq!(bound.instructions()[2].operation, Operation::Rz(2.0 * value));
+    /// }
+    /// assert!(matches!(
+    ///     ansatz.bind_parameters(&HashMap::new()),
+    ///     Err(QuantumError::UnboundParameter { .. })
+    /// ));
+    /// ```
+    pub fn bind_parameters(&self, values: &HashMap<String, f64>) -> Result<Circuit, QuantumError> {
+        let bind = |operation: &Operation| -> Result<Operation, QuantumError> {
+            match operation {
+                Operation::Parametric { gate, parameter } => {
+                    let value =
+                        parameter
+                            .value(values)
+                            .ok_or_else(|| QuantumError::UnboundParameter {
+                                name: parameter.name().to_string(),
+                            })?;
+                    Ok(gate.with_angle(Angle::Value(value)))
+                }
+                operation => Ok(operation.clone()),
+            }
+        };
+        let mut bound = self.clone();
+        for instruction in &mut bound.instructions {
+            instruction.operation = match &instruction.operation {
+                Operation::Conditional { clbit, operation } => Operation::Conditional {
+                    clbit: *clbit,
+                    operation: Box::new(bind(operation)?),
+                },
+                operation => bind(operation)?,
+            };
+        }
+        Ok(bound)
+    }
+
+    /// Returns `true` if every instruction is a unitary gate or transparent (see
+    /// [`Operation::is_transparent`]), so the circuit has no measurements, resets or classically
+    /// conditioned operations.
+    pub fn is_unitary(&self) -> bool {
+        self.instructions.iter().all(|instruction| {
+            instruction.operation.is_unitary() || instruction.operation.is_transparent()
+        })
+    }
+
+    /// Lowers every instruction to a gate acting on the full register.
+    ///
+    /// This is the bridge to code written against the original `Vec<Gate>` representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit is not unitary (see [`Circuit::is_unitary`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(1);
+    /// assert_eq!(circuit.to_gates()[0].matrix.len(), 4);
+    /// ```
+    pub fn to_gates(&self) -> Vec<Gate> {
+        self.instructions
+            .iter()
+            .map(|instruction| instruction.to_gate(self.num_qubits))
+            .collect()
+    }
+
+    /// Returns the full matrix of the circuit on a `num_qubits`-qubit register, the product of
+    /// all its gates with the first one rightmost, e.g. to check a decomposition against a known
+    /// matrix or to pass it to [`Gate::from_unitary`].
+    ///
+    /// Column `j` is the final state of a run on basis state `|j⟩`, with qubit `k` as bit `k` of
+    /// the index. The matrix has `4^num_qubits` entries, so this is meant for small registers.
+    /// Snapshots and assertions act as the identity.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register, at least the circuit's; extra qubits are left
+    ///   untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_qubits` is smaller than the circuit's, or if the circuit measures, resets,
+    /// conditions on classical bits or has unbound parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::gates::{cnot, Gate};
+    ///
+    /// // Three CNOTs alternating direction make a SWAP
+    /// let mut swap = Circuit::new(2);
+    /// swap.cnot(0, 1).cnot(1, 0).cnot(0, 1);
+    /// let matrix = swap.to_unitary(2);
+    /// assert_eq!(matrix[0b01][0b10].re, 1.0);
+    /// assert_eq!(matrix[0b10][0b01].re, 1.0);
+    ///
+    /// // H·CNOT·H on the target is a CZ, on a wider register as well
+    /// let mut cz = Circuit::new(2);
+    /// cz.h(1).cnot(0, 1).h(1);
+    /// let gate = Gate::from_unitary(cz.to_unitary(3)).unwrap();
+    /// assert!((gate.matrix[0b011][0b011].re + 1.0).abs() < 1e-12);
+    /// assert!((gate.matrix[0b111][0b111].re + 1.0).abs() < 1e-12);
+    /// assert_eq!(gate.matrix.len(), cnot(0, 1, 3).matrix.len());
+    /// ```
+    pub fn to_unitary(&self, num_qubits: usize) -> Vec<Vec<Complex<f64>>> {
+        assert!(
+            num_qubits >= self.num_qubits,
+            "a {}-qubit circuit does not fit a {}-qubit register",
+            self.num_qubits,
+            num_qubits
+        );
+        let dimension = 1usize << num_qubits;
+        let columns: Vec<Vec<Complex<f64>>> = (0..dimension)
+            .map(|basis| {
+                let mut column = Qubit::zero_state(num_qubits);
+                column.state.swap(0, basis);
+                for instruction in &self.instructions {
+                    instruction.apply(&mut column);
+                }
+                column.state
+            })
+            .collect();
+        (0..dimension)
+            .map(|row| columns.iter().map(|column| column[row]).collect())
+            .collect()
     }
 
     /// Runs the circuit on the given qubit.
     ///
+    /// Measurements and resets draw their outcomes from the thread-local generator, see
+    /// [`Circuit::run_with_rng`].
+    ///
+    /// Between measurements, runs of single-qubit gates on the same qubit are fused into one gate
+    /// (see [`crate::gates::fuse`]) and each gate is applied through its [`GateKind`].
+    ///
     /// # Arguments
     ///
     /// * `qubit` - The qubit on which to run the circuit.
@@ -54,21 +1761,259 @@ impl Circuit {
     /// use quantum_simulator::gates::hadamard;
     /// use quantum_simulator::qubit::Qubit;
     ///
-    /// let mut circuit = Circuit::new();
+    /// let mut circuit = Circuit::new(1);
     /// circuit.add_gate(hadamard(1));
     ///
     /// let mut qubit = Qubit::new();
     /// circuit.run(&mut qubit);
     /// ```
     pub fn run(&self, qubit: &mut Qubit) {
-        for gate in &self.gates {
-            gate.apply(qubit);
+        rng::with_rng(|rng| {
+            self.run_with_rng(qubit, rng);
+        });
+    }
+
+    /// Runs the circuit like [`Circuit::run`], drawing measurement outcomes from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit on which to run the circuit.
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Returns
+    ///
+    /// * The final classical register, where element `k` is classical bit `k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an [`Operation::Assert`] does not hold.
+    pub fn run_with_rng<R: Rng + ?Sized>(&self, qubit: &mut Qubit, rng: &mut R) -> Vec<bool> {
+        self.execute(qubit, rng)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Runs the circuit, stopping at the first assertion that does not hold.
+    fn execute<R: Rng + ?Sized>(
+        &self,
+        qubit: &mut Qubit,
+        rng: &mut R,
+    ) -> Result<Vec<bool>, QuantumError> {
+        let mut clbits = vec![false; self.num_clbits];
+        let mut pending = vec![];
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if let Operation::Snapshot(_) | Operation::Barrier(_) = instruction.operation {
+                continue;
+            }
+            if instruction.operation.is_unitary() {
+                pending.push(instruction.kind());
+                continue;
+            }
+            for kind in fuse(std::mem::take(&mut pending)) {
+                kind.apply(qubit);
+            }
+            match &instruction.operation {
+                Operation::Measure(clbit) => {
+                    clbits[*clbit] = qubit.measure_qubit_with_rng(instruction.qubits[0], rng) == 1;
+                }
+                Operation::Reset => {
+                    if qubit.measure_qubit_with_rng(instruction.qubits[0], rng) == 1 {
+                        pauli_x().apply_to(qubit, &instruction.qubits);
+                    }
+                }
+                Operation::Conditional { clbit, operation } => {
+                    if clbits[*clbit] {
+                        operation.gate().apply_to(qubit, &instruction.qubits);
+                    }
+                }
+                Operation::Assert(assertion) => {
+                    let rho = reduced_density_matrix(qubit, &instruction.qubits);
+                    assertion.check(index, &rho)?;
+                }
+                _ => unreachable!("unitary instructions are queued for fusion"),
+            }
         }
+        for kind in fuse(pending) {
+            kind.apply(qubit);
+        }
+        Ok(clbits)
+    }
+
+    /// Runs the circuit on the given qubit after validating the register and every instruction.
+    ///
+    /// The register must have `2^num_qubits` amplitudes and unit norm, and each instruction's gate
+    /// must match the number of qubits it acts on. Nothing is applied if validation fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit on which to run the circuit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.h(0);
+    /// assert!(circuit.try_run(&mut Qubit::new()).is_ok());
+    ///
+    /// let mut unnormalized = Qubit::from_state(vec![Complex::new(1.0, 0.0); 2]);
+    /// assert!(matches!(
+    ///     circuit.try_run(&mut unnormalized),
+    ///     Err(QuantumError::NotNormalized { .. })
+    /// ));
+    /// ```
+    pub fn try_run(&self, qubit: &mut Qubit) -> Result<(), QuantumError> {
+        self.validate(qubit, ExecutionMode::Strict, &mut vec![])?;
+        rng::with_rng(|rng| self.execute(qubit, rng))?;
+        Ok(())
+    }
+
+    /// Runs the circuit like [`Circuit::try_run`], treating recoverable problems according to
+    /// `mode`.
+    ///
+    /// Both modes reject registers of the wrong size and malformed instructions, e.g. those of a
+    /// deserialized circuit that were never checked by [`Circuit::add_instruction`]. A state
+    /// whose norm drifted, before or after the run, is an error in strict mode and is rescaled
+    /// with a [`execution::Warning::Renormalized`] in lenient mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit on which to run the circuit.
+    /// * `mode` - Whether recoverable problems abort the run or are reported as warnings.
+    ///
+    /// # Returns
+    ///
+    /// * The final classical register with any warnings, or the error that stopped the run. In
+    ///   strict mode, a norm drift caused by the circuit itself is reported after `qubit` has
+    ///   been updated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::execution::{ExecutionMode, Warning};
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0).measure(0, 0);
+    ///
+    /// let drifted = vec![Complex::new(1.0 + 1e-6, 0.0), Complex::new(0.0, 0.0)];
+    /// assert!(circuit
+    ///     .run_with_mode(&mut Qubit::from_state(drifted.clone()), ExecutionMode::Strict)
+    ///     .is_err());
+    ///
+    /// let execution = circuit
+    ///     .run_with_mode(&mut Qubit::from_state(drifted), ExecutionMode::Lenient)
+    ///     .unwrap();
+    /// assert_eq!(execution.value, vec![true]);
+    /// assert!(matches!(execution.warnings[..], [Warning::Renormalized { .. }]));
+    /// ```
+    pub fn run_with_mode(
+        &self,
+        qubit: &mut Qubit,
+        mode: ExecutionMode,
+    ) -> Result<Execution<Vec<bool>>, QuantumError> {
+        let mut warnings = vec![];
+        self.validate(qubit, mode, &mut warnings)?;
+        let clbits = rng::with_rng(|rng| self.execute(qubit, rng))?;
+        execution::check_norm(&mut qubit.state, mode, &mut warnings)?;
+        Ok(Execution {
+            value: clbits,
+            warnings,
+        })
+    }
+
+    /// Checks that the circuit can run on `qubit` without panicking, and that the state is
+    /// normalized according to `mode`.
+    fn validate(
+        &self,
+        qubit: &mut Qubit,
+        mode: ExecutionMode,
+        warnings: &mut Vec<execution::Warning>,
+    ) -> Result<(), QuantumError> {
+        let expected = u32::try_from(self.num_qubits)
+            .ok()
+            .and_then(|num_qubits| 1usize.checked_shl(num_qubits))
+            .ok_or_else(|| QuantumError::Unsupported {
+                feature: format!("{}-qubit state vectors", self.num_qubits),
+            })?;
+        if qubit.state.len() != expected {
+            return Err(QuantumError::DimensionMismatch {
+                expected,
+                found: qubit.state.len(),
+            });
+        }
+        for instruction in &self.instructions {
+            self.check_qubits(instruction)?;
+            let operation = match &instruction.operation {
+                Operation::Measure(clbit) | Operation::Conditional { clbit, .. }
+                    if *clbit >= self.num_clbits =>
+                {
+                    return Err(QuantumError::DimensionMismatch {
+                        expected: self.num_clbits,
+                        found: clbit.saturating_add(1),
+                    });
+                }
+                Operation::Measure(_)
+                | Operation::Reset
+                | Operation::Snapshot(_)
+                | Operation::Barrier(_) => continue,
+                Operation::Assert(assertion) => {
+                    assertion.validate()?;
+                    continue;
+                }
+                Operation::Conditional { operation, .. } if !operation.is_unitary() => {
+                    return Err(QuantumError::Unsupported {
+                        feature: format!("conditional {:?}", operation),
+                    });
+                }
+                Operation::Conditional { operation, .. } => operation,
+                operation => operation,
+            };
+            let dimension = operation.gate().matrix.len();
+            let expected = 1 << instruction.qubits.len();
+            if dimension != expected {
+                return Err(QuantumError::DimensionMismatch {
+                    expected,
+                    found: dimension,
+                });
+            }
+        }
+        execution::check_norm(&mut qubit.state, mode, warnings)
+    }
+
+    /// Checks that an instruction's qubits exist, are distinct and match its operation's arity.
+    fn check_qubits(&self, instruction: &Instruction) -> Result<(), QuantumError> {
+        let arity = instruction.operation.num_qubits();
+        if instruction.qubits.len() != arity {
+            return Err(QuantumError::DimensionMismatch {
+                expected: arity,
+                found: instruction.qubits.len(),
+            });
+        }
+        for (position, &qubit) in instruction.qubits.iter().enumerate() {
+            if qubit >= self.num_qubits {
+                return Err(QuantumError::QubitOutOfRange {
+                    qubit,
+                    num_qubits: self.num_qubits,
+                });
+            }
+            if instruction.qubits[..position].contains(&qubit) {
+                return Err(QuantumError::DuplicateQubit { qubit });
+            }
+        }
+        Ok(())
     }
-}
 
-impl Default for Circuit {
-    fn default() -> Self {
-        Self::new()
+    /// Adds an operation on the given qubits, panicking on invalid qubit indices.
+    fn push(&mut self, operation: Operation, qubits: Vec<usize>) -> &mut Self {
+        if let Err(error) = self.add_instruction(Instruction::new(operation, qubits)) {
+            panic!("invalid instruction: {}", error);
+        }
+        self
     }
 }
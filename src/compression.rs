@@ -0,0 +1,333 @@
+//! This module approximates the unitary of a small circuit with a shorter one, e.g. to turn a
+//! deep machine-generated circuit into a compact teaching example.
+//!
+//! The replacement is a layered template: `Rz·Ry·Rz` on every qubit, then `layers` rounds of a
+//! CNOT chain followed by another `Rz·Ry·Rz` on every qubit. Starting from no layers, the
+//! template's angles are fitted to the target by exact coordinate-wise minimization, and layers
+//! are added until the process fidelity reaches the target. The fitted circuit is then truncated:
+//! rotations are dropped, smallest first, for as long as the fidelity stays on target.
+
+use crate::analysis::gate_fidelity;
+use crate::circuit::{Circuit, Operation};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// The widest circuit whose unitary is fitted; the cost grows as `4^n`.
+const MAX_QUBITS: usize = 4;
+
+/// A sweep over all angles stops the fit once it improves the infidelity by less than this.
+const CONVERGENCE: f64 = 1e-12;
+
+/// A fit stops once its infidelity is this fraction of the allowed infidelity.
+const MARGIN: f64 = 0.1;
+
+/// The three rotations of each qubit in every round of the template.
+const ROTATIONS_PER_QUBIT: usize = 3;
+
+/// The result of [`Compressor::compress`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Compression {
+    /// The replacement circuit, or the original one if no shorter circuit reaches the target.
+    pub circuit: Circuit,
+    /// The process fidelity of `circuit` with the original circuit, see [`gate_fidelity`].
+    pub fidelity: f64,
+    /// The number of template layers fitted, or `None` if the original circuit was kept.
+    pub layers: Option<usize>,
+}
+
+/// Fits shorter circuits to the unitaries of small circuits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Compressor {
+    /// The smallest acceptable process fidelity with the original circuit.
+    pub target_fidelity: f64,
+    /// The most template layers tried.
+    pub max_layers: usize,
+    /// The most sweeps over all angles per fit.
+    pub sweeps: usize,
+    /// The number of random starting points fitted per layer count.
+    pub restarts: usize,
+    /// The seed of the random starting points.
+    pub seed: u64,
+}
+
+impl Compressor {
+    /// Creates a compressor trying up to 8 layers with 3 restarts of up to 200 sweeps each.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_fidelity` - The smallest acceptable process fidelity, e.g. `0.999`.
+    pub fn new(target_fidelity: f64) -> Self {
+        Compressor {
+            target_fidelity,
+            max_layers: 8,
+            sweeps: 200,
+            restarts: 3,
+            seed: 0,
+        }
+    }
+
+    /// Returns a copy of the compressor that tries at most `max_layers` template layers.
+    pub fn with_max_layers(mut self, max_layers: usize) -> Self {
+        self.max_layers = max_layers;
+        self
+    }
+
+    /// Returns a copy of the compressor that draws its starting points from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns a circuit with fewer instructions whose unitary matches `circuit`'s to the target
+    /// fidelity, or `circuit` itself if none is found.
+    ///
+    /// Layer counts are tried in increasing order and only while the template is shorter than
+    /// `circuit`, so the first fit that reaches the target is also the shortest found.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to compress.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Compression)` - The replacement and its fidelity.
+    /// * `Err(QuantumError::Unsupported)` - If the circuit measures, resets, conditions on a
+    ///   classical bit or has more than four qubits.
+    /// * `Err(QuantumError::UnboundParameter)` - If the circuit has an unbound parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::compression::Compressor;
+    ///
+    /// // Twenty small rotations on one qubit collapse into at most three
+    /// let mut circuit = Circuit::new(1);
+    /// for k in 0..10 {
+    ///     circuit.rx(0, 0.1 * k as f64).rz(0, 0.3);
+    /// }
+    /// let compression = Compressor::new(0.9999).compress(&circuit).unwrap();
+    /// assert!(compression.circuit.instructions().len() <= 3);
+    /// assert!(compression.fidelity >= 0.9999);
+    /// ```
+    pub fn compress(&self, circuit: &Circuit) -> Result<Compression, QuantumError> {
+        let num_qubits = circuit.num_qubits();
+        if num_qubits > MAX_QUBITS {
+            return Err(QuantumError::Unsupported {
+                feature: format!("compressing a circuit on {} qubits", num_qubits),
+            });
+        }
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                Operation::Parametric { parameter, .. } => {
+                    return Err(QuantumError::UnboundParameter {
+                        name: parameter.name().to_string(),
+                    })
+                }
+                Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_) => {}
+                operation if !operation.is_unitary() => {
+                    return Err(QuantumError::Unsupported {
+                        feature: format!("compressing a circuit with {}", operation.name()),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        let target = unitary(circuit);
+        let length = circuit.instructions().len();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        // Fits stop well inside the target, leaving room for truncation
+        let margin = (1.0 - self.target_fidelity) * MARGIN;
+        for layers in 0..=self.max_layers {
+            let template = Template { num_qubits, layers };
+            if template.length() >= length {
+                break;
+            }
+            let infidelity =
+                |angles: &[f64]| 1.0 - gate_fidelity(&target, &template.unitary(angles));
+            let mut best: Option<(f64, Vec<f64>)> = None;
+            for _ in 0..self.restarts.max(1) {
+                let initial: Vec<f64> = (0..template.num_angles())
+                    .map(|_| rng.gen_range(-PI..PI))
+                    .collect();
+                let fitted = rotosolve(&infidelity, initial, self.sweeps, margin);
+                if best.as_ref().is_none_or(|best| fitted.0 < best.0) {
+                    best = Some(fitted);
+                }
+                if best.as_ref().is_some_and(|best| best.0 <= margin) {
+                    break;
+                }
+            }
+            let (infidelity_value, mut angles) = best.expect("at least one restart runs");
+            if 1.0 - infidelity_value < self.target_fidelity {
+                continue;
+            }
+
+            // Truncate: drop the smallest rotations while the fit stays on target
+            let mut order: Vec<usize> = (0..angles.len()).collect();
+            order.sort_by(|&a, &b| angles[a].abs().total_cmp(&angles[b].abs()));
+            for index in order {
+                let kept = angles[index];
+                angles[index] = 0.0;
+                if 1.0 - infidelity(&angles) < self.target_fidelity {
+                    angles[index] = kept;
+                }
+            }
+            let (compressed, _) = template.circuit(&angles).optimized();
+            let fidelity = gate_fidelity(&target, &unitary(&compressed));
+            return Ok(Compression {
+                circuit: compressed,
+                fidelity,
+                layers: Some(layers),
+            });
+        }
+        Ok(Compression {
+            circuit: circuit.clone(),
+            fidelity: 1.0,
+            layers: None,
+        })
+    }
+}
+
+/// The layered template circuit on `num_qubits` qubits.
+struct Template {
+    num_qubits: usize,
+    layers: usize,
+}
+
+impl Template {
+    /// Returns the number of rotation angles.
+    fn num_angles(&self) -> usize {
+        ROTATIONS_PER_QUBIT * self.num_qubits * (self.layers + 1)
+    }
+
+    /// Returns the number of instructions before truncation.
+    fn length(&self) -> usize {
+        self.num_angles() + self.layers * self.num_qubits.saturating_sub(1)
+    }
+
+    /// Builds the template for a set of angles, leaving out rotations by exactly zero.
+    fn circuit(&self, angles: &[f64]) -> Circuit {
+        let mut circuit = Circuit::new(self.num_qubits);
+        let mut angles = angles.iter();
+        for layer in 0..=self.layers {
+            if layer > 0 {
+                for qubit in 1..self.num_qubits {
+                    circuit.cnot(qubit - 1, qubit);
+                }
+            }
+            for qubit in 0..self.num_qubits {
+                for rotation in 0..ROTATIONS_PER_QUBIT {
+                    let angle = *angles.next().expect("one angle per rotation");
+                    if angle == 0.0 {
+                        continue;
+                    }
+                    if rotation == 1 {
+                        circuit.ry(qubit, angle);
+                    } else {
+                        circuit.rz(qubit, angle);
+                    }
+                }
+            }
+        }
+        circuit
+    }
+
+    /// Returns the template's unitary for a set of angles.
+    ///
+    /// This is the inner loop of the fit, so the gates are applied directly to the columns of
+    /// the matrix instead of building and running the circuit.
+    fn unitary(&self, angles: &[f64]) -> Gate {
+        let dimension = 1 << self.num_qubits;
+        let mut columns: Vec<Vec<Complex<f64>>> = (0..dimension)
+            .map(|basis| {
+                let mut column = vec![Complex::new(0.0, 0.0); dimension];
+                column[basis] = Complex::new(1.0, 0.0);
+                column
+            })
+            .collect();
+        let mut angles = angles.iter();
+        for layer in 0..=self.layers {
+            if layer > 0 {
+                for qubit in 1..self.num_qubits {
+                    let (control, target) = (1 << (qubit - 1), 1 << qubit);
+                    for column in columns.iter_mut() {
+                        for index in (0..dimension).filter(|i| i & control != 0 && i & target == 0)
+                        {
+                            column.swap(index, index | target);
+                        }
+                    }
+                }
+            }
+            for qubit in 0..self.num_qubits {
+                let bit = 1 << qubit;
+                for rotation in 0..ROTATIONS_PER_QUBIT {
+                    let half = angles.next().expect("one angle per rotation") / 2.0;
+                    for column in columns.iter_mut() {
+                        for index in (0..dimension).filter(|i| i & bit == 0) {
+                            let (a, b) = (column[index], column[index | bit]);
+                            if rotation == 1 {
+                                let (cos, sin) = (half.cos(), half.sin());
+                                column[index] = a * cos - b * sin;
+                                column[index | bit] = a * sin + b * cos;
+                            } else {
+                                let phase = Complex::from_polar(1.0, half);
+                                column[index] = a * phase.conj();
+                                column[index | bit] = b * phase;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Gate::new(
+            (0..dimension)
+                .map(|row| columns.iter().map(|column| column[row]).collect())
+                .collect(),
+        )
+    }
+}
+
+/// Returns the unitary of a circuit as a gate.
+fn unitary(circuit: &Circuit) -> Gate {
+    Gate::new(circuit.to_unitary(circuit.num_qubits()))
+}
+
+/// Minimizes `cost` one angle at a time, until it drops to `good_enough` or stops improving,
+/// and returns the final cost and angles.
+///
+/// As a function of a single rotation angle, the infidelity is `a + b·cos(θ - φ)`, so three
+/// evaluations locate its exact minimum along that angle.
+fn rotosolve(
+    cost: &impl Fn(&[f64]) -> f64,
+    mut angles: Vec<f64>,
+    sweeps: usize,
+    good_enough: f64,
+) -> (f64, Vec<f64>) {
+    let mut value = cost(&angles);
+    for _ in 0..sweeps {
+        if value <= good_enough {
+            break;
+        }
+        let before = value;
+        for k in 0..angles.len() {
+            let theta = angles[k];
+            angles[k] = theta + FRAC_PI_2;
+            let plus = cost(&angles);
+            angles[k] = theta - FRAC_PI_2;
+            let minus = cost(&angles);
+            let offset = (minus - plus).atan2(2.0 * value - plus - minus);
+            angles[k] = (theta - offset).rem_euclid(2.0 * PI) - PI;
+            value = cost(&angles);
+        }
+        if before - value < CONVERGENCE {
+            break;
+        }
+    }
+    (value, angles)
+}
@@ -0,0 +1,346 @@
+//! This module is a conformance suite of small circuits with known results that any [`Backend`]
+//! can be checked against, including third-party backends implementing the trait.
+//!
+//! The golden results ship with the crate in `conformance/cases.json`: each case is an OpenQASM
+//! circuit with its expected final amplitudes and/or outcome probabilities. A backend that exposes
+//! its amplitudes through [`Backend::amplitudes`] is compared amplitude by amplitude, up to a
+//! global phase; any other backend, or a circuit that measures, is sampled and the frequencies
+//! are compared with the expected probabilities.
+
+use crate::backend::Backend;
+use crate::circuit::Circuit;
+use crate::error::QuantumError;
+use crate::qasm;
+use crate::tolerance;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// The built-in golden results.
+const CASES: &str = include_str!("../conformance/cases.json");
+
+/// Factor by which the [`equality`](crate::tolerance::Tolerances::equality) tolerance is widened
+/// for amplitudes, so that single-precision backends pass: `1e-5` by default.
+const AMPLITUDE_SLACK: f64 = 1e4;
+
+/// Number of shots sampled for a case that is checked by its outcome probabilities.
+const SHOTS: usize = 1000;
+
+/// Seed of the random number generator each case is sampled with.
+const SEED: u64 = 1286;
+
+/// Number of standard deviations a sampled frequency may deviate from its expected probability.
+const SIGMAS: f64 = 5.0;
+
+/// A circuit with its known results.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Case {
+    /// A short unique name, e.g. `bell`.
+    pub name: String,
+    /// The circuit, run from `|0…0⟩`.
+    pub circuit: Circuit,
+    /// The final amplitudes, indexed by basis state, if the circuit is deterministic.
+    pub amplitudes: Option<Vec<Complex<f64>>>,
+    /// The probability of each outcome: the classical bits if the circuit measures, otherwise
+    /// all qubits measured at the end, as bitstrings with the highest bit first.
+    pub probabilities: HashMap<String, f64>,
+}
+
+/// The result of checking one case.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Passed,
+    /// The backend produced a wrong result or an unexpected error, described by the message.
+    Failed(String),
+    /// The backend rejected the circuit with [`QuantumError::Unsupported`], e.g. a
+    /// non-Clifford gate on the stabilizer backend.
+    Unsupported(String),
+}
+
+/// The outcome of one case for a backend.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaseResult {
+    /// The name of the case.
+    pub name: String,
+    /// Whether the backend passed it.
+    pub outcome: Outcome,
+}
+
+/// The results of the conformance suite for one backend.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// The name of the backend, see [`Backend::name`].
+    pub backend: String,
+    /// One result per case, in suite order.
+    pub results: Vec<CaseResult>,
+}
+
+impl Report {
+    /// Returns `true` if no case failed. Unsupported cases do not count as failures.
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Returns the cases the backend failed.
+    pub fn failures(&self) -> impl Iterator<Item = &CaseResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.outcome, Outcome::Failed(_)))
+    }
+
+    /// Returns the number of cases with the given outcome kind.
+    fn count(&self, matches: impl Fn(&Outcome) -> bool) -> usize {
+        self.results
+            .iter()
+            .filter(|result| matches(&result.outcome))
+            .count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} passed, {} failed, {} unsupported",
+            self.backend,
+            self.count(|outcome| *outcome == Outcome::Passed),
+            self.count(|outcome| matches!(outcome, Outcome::Failed(_))),
+            self.count(|outcome| matches!(outcome, Outcome::Unsupported(_)))
+        )?;
+        for result in self.failures() {
+            if let Outcome::Failed(message) = &result.outcome {
+                writeln!(f, "  {}: {}", result.name, message)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the built-in cases.
+pub fn cases() -> Vec<Case> {
+    parse_cases(CASES).expect("the built-in conformance cases are valid")
+}
+
+/// Parses cases from JSON of the form `{"cases": [{"name", "qasm", "amplitudes",
+/// "probabilities"}, …]}`, where `amplitudes` is an optional array of `[re, im]` pairs and
+/// `probabilities` maps outcome bitstrings to probabilities.
+///
+/// # Arguments
+///
+/// * `json` - The cases.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Case>)` - The cases in order.
+/// * `Err(QuantumError::Parse)` - If the JSON is malformed, a field is missing or has the wrong
+///   type, or a circuit is not valid OpenQASM.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::conformance::parse_cases;
+///
+/// let cases = parse_cases(r#"{"cases": [{
+///     "name": "flip",
+///     "qasm": "OPENQASM 2.0; include \"qelib1.inc\"; qreg q[1]; x q[0];",
+///     "amplitudes": [[0, 0], [1, 0]],
+///     "probabilities": {"1": 1}
+/// }]}"#).unwrap();
+/// assert_eq!(cases[0].name, "flip");
+/// assert!(parse_cases(r#"{"cases": [{"name": "incomplete"}]}"#).is_err());
+/// ```
+pub fn parse_cases(json: &str) -> Result<Vec<Case>, QuantumError> {
+    let root: Value = serde_json::from_str(json).map_err(|error| QuantumError::Parse {
+        line: error.line(),
+        message: error.to_string(),
+    })?;
+    let invalid = |name: &str, message: &str| QuantumError::Parse {
+        line: 0,
+        message: format!("case '{}': {}", name, message),
+    };
+    let entries = root["cases"]
+        .as_array()
+        .ok_or_else(|| invalid("", "expected a 'cases' array"))?;
+
+    let mut cases = vec![];
+    for entry in entries {
+        let name = entry["name"]
+            .as_str()
+            .ok_or_else(|| invalid("", "expected a 'name' string"))?;
+        let source = entry["qasm"]
+            .as_str()
+            .ok_or_else(|| invalid(name, "expected a 'qasm' string"))?;
+        let circuit = qasm::parse(source).map_err(|error| invalid(name, &format!("{}", error)))?;
+
+        let amplitudes = match &entry["amplitudes"] {
+            Value::Null => None,
+            Value::Array(pairs) => Some(
+                pairs
+                    .iter()
+                    .map(|pair| match pair.as_array().map(Vec::as_slice) {
+                        Some([re, im]) => Some(Complex::new(re.as_f64()?, im.as_f64()?)),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid(name, "expected [re, im] amplitude pairs"))?,
+            ),
+            _ => return Err(invalid(name, "expected an 'amplitudes' array")),
+        };
+        let probabilities = entry["probabilities"]
+            .as_object()
+            .ok_or_else(|| invalid(name, "expected a 'probabilities' object"))?
+            .iter()
+            .map(|(outcome, probability)| {
+                probability
+                    .as_f64()
+                    .map(|probability| (outcome.clone(), probability))
+                    .ok_or_else(|| invalid(name, "expected numeric probabilities"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        cases.push(Case {
+            name: name.to_string(),
+            circuit,
+            amplitudes,
+            probabilities,
+        });
+    }
+    Ok(cases)
+}
+
+/// Runs the built-in conformance suite against a backend.
+///
+/// # Arguments
+///
+/// * `new_backend` - Creates a backend with the given number of qubits in `|0…0⟩`. It is called
+///   once per case, or once per shot for sampled cases.
+///
+/// # Returns
+///
+/// * A `Report` with one result per case.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::backend::CpuBackend;
+/// use quantum_simulator::conformance;
+///
+/// let report = conformance::run(CpuBackend::new);
+/// assert!(report.passed(), "{}", report);
+/// ```
+pub fn run<B: Backend>(new_backend: impl FnMut(usize) -> B) -> Report {
+    run_cases(&cases(), new_backend)
+}
+
+/// Runs the given cases against a backend, see [`run`].
+///
+/// # Arguments
+///
+/// * `cases` - The cases to check, e.g. from [`parse_cases`].
+/// * `new_backend` - Creates a backend with the given number of qubits in `|0…0⟩`.
+pub fn run_cases<B: Backend>(cases: &[Case], mut new_backend: impl FnMut(usize) -> B) -> Report {
+    let mut backend_name = None;
+    let results = cases
+        .iter()
+        .map(|case| {
+            let mut create = |num_qubits| {
+                let backend = new_backend(num_qubits);
+                backend_name.get_or_insert_with(|| backend.name().to_string());
+                backend
+            };
+            let outcome = match check(case, &mut create) {
+                Ok(outcome) => outcome,
+                Err(QuantumError::Unsupported { feature }) => Outcome::Unsupported(feature),
+                Err(error) => Outcome::Failed(error.to_string()),
+            };
+            CaseResult {
+                name: case.name.clone(),
+                outcome,
+            }
+        })
+        .collect();
+    Report {
+        backend: backend_name.unwrap_or_default(),
+        results,
+    }
+}
+
+/// Checks one case, comparing amplitudes if the case has them and the backend exposes its own,
+/// and sampled outcome frequencies otherwise.
+fn check<B: Backend>(
+    case: &Case,
+    new_backend: &mut impl FnMut(usize) -> B,
+) -> Result<Outcome, QuantumError> {
+    let num_qubits = case.circuit.num_qubits();
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    if let Some(expected) = &case.amplitudes {
+        let mut backend = new_backend(num_qubits);
+        backend.run_with_rng(&case.circuit, &mut rng)?;
+        if let Some(actual) = backend.amplitudes() {
+            return Ok(compare_amplitudes(expected, &actual));
+        }
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for _ in 0..SHOTS {
+        let mut backend = new_backend(num_qubits);
+        let clbits = backend.run_with_rng(&case.circuit, &mut rng)?;
+        let bits: Vec<bool> = if case.circuit.num_clbits() > 0 {
+            clbits
+        } else {
+            (0..num_qubits)
+                .map(|qubit| Ok(backend.try_measure_with_rng(qubit, &mut rng)? == 1))
+                .collect::<Result<_, QuantumError>>()?
+        };
+        let outcome = bits.iter().rev().map(|&bit| if bit { '1' } else { '0' });
+        *counts.entry(outcome.collect()).or_default() += 1;
+    }
+
+    let outcomes: BTreeSet<&String> = counts.keys().chain(case.probabilities.keys()).collect();
+    for outcome in outcomes {
+        let probability = case.probabilities.get(outcome).copied().unwrap_or(0.0);
+        let frequency = counts.get(outcome).copied().unwrap_or(0) as f64 / SHOTS as f64;
+        let bound = SIGMAS * (probability * (1.0 - probability) / SHOTS as f64).sqrt() + 1e-12;
+        if (frequency - probability).abs() > bound {
+            return Ok(Outcome::Failed(format!(
+                "outcome {} has frequency {:.3} over {} shots, expected {:.3}",
+                outcome, frequency, SHOTS, probability
+            )));
+        }
+    }
+    Ok(Outcome::Passed)
+}
+
+/// Compares amplitudes up to a global phase, fixed by the largest expected amplitude.
+fn compare_amplitudes(expected: &[Complex<f64>], actual: &[Complex<f64>]) -> Outcome {
+    if expected.len() != actual.len() {
+        return Outcome::Failed(format!(
+            "expected {} amplitudes, found {}",
+            expected.len(),
+            actual.len()
+        ));
+    }
+    let reference = (0..expected.len())
+        .max_by(|&a, &b| expected[a].norm().total_cmp(&expected[b].norm()))
+        .expect("a state has at least one amplitude");
+    let allowed = tolerance::current().equality * AMPLITUDE_SLACK;
+    let phase = actual[reference] / expected[reference];
+    let phase = if phase.norm() > 0.0 {
+        phase / phase.norm()
+    } else {
+        Complex::new(1.0, 0.0)
+    };
+    match (0..expected.len())
+        .find(|&index| (actual[index] - phase * expected[index]).norm() > allowed)
+    {
+        Some(index) => Outcome::Failed(format!(
+            "amplitude {} is {:.6}, expected {:.6} up to global phase",
+            index, actual[index], expected[index]
+        )),
+        None => Outcome::Passed,
+    }
+}
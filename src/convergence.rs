@@ -0,0 +1,154 @@
+//! This module defines convergence criteria that stop a shot campaign early.
+//!
+//! A [`Convergence`] criterion maps every measured outcome to a sample of some observable and
+//! stops sampling once the standard error of the running mean drops below a tolerance, so a
+//! campaign spends only as many shots as the estimate needs.
+
+/// The running estimate of an observable's expectation value over a shot campaign.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Estimate {
+    /// The sample mean of the observable.
+    pub mean: f64,
+    /// The standard error of the mean, `s / √n`.
+    pub standard_error: f64,
+    /// The number of shots the estimate is based on.
+    pub shots: usize,
+    /// The running sum of squared deviations from the mean (Welford's `M₂`).
+    m2: f64,
+}
+
+impl Estimate {
+    /// Adds one sample to the estimate.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The observable's value in the new shot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::convergence::Estimate;
+    ///
+    /// let mut estimate = Estimate::default();
+    /// for value in [1.0, -1.0, 1.0, 1.0] {
+    ///     estimate.push(value);
+    /// }
+    /// assert_eq!(estimate.shots, 4);
+    /// assert!((estimate.mean - 0.5).abs() < 1e-12);
+    /// assert!((estimate.standard_error - 0.5).abs() < 1e-12);
+    /// ```
+    pub fn push(&mut self, value: f64) {
+        self.shots += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.shots as f64;
+        self.m2 += delta * (value - self.mean);
+        self.standard_error = if self.shots > 1 {
+            (self.m2 / (self.shots - 1) as f64 / self.shots as f64).sqrt()
+        } else {
+            f64::INFINITY
+        };
+    }
+}
+
+/// A `Convergence` criterion estimates an observable from measured outcomes and reports when
+/// its standard error is small enough.
+pub struct Convergence<'a> {
+    observable: Box<dyn Fn(usize) -> f64 + 'a>,
+    tolerance: f64,
+    min_shots: usize,
+}
+
+impl<'a> Convergence<'a> {
+    /// The default number of shots taken before the standard error is trusted.
+    pub const DEFAULT_MIN_SHOTS: usize = 100;
+
+    /// Creates a criterion for an arbitrary observable.
+    ///
+    /// # Arguments
+    ///
+    /// * `observable` - Maps a measured outcome (the basis index, or the classical register if
+    ///   the circuit has classical bits) to the observable's value in that shot.
+    /// * `tolerance` - The standard error below which the estimate is considered converged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::convergence::Convergence;
+    ///
+    /// // The number of qubits found in |1⟩
+    /// let criterion = Convergence::new(|outcome| outcome.count_ones() as f64, 0.01);
+    /// assert_eq!(criterion.value(0b101), 2.0);
+    /// ```
+    pub fn new(observable: impl Fn(usize) -> f64 + 'a, tolerance: f64) -> Self {
+        Convergence {
+            observable: Box::new(observable),
+            tolerance,
+            min_shots: Self::DEFAULT_MIN_SHOTS,
+        }
+    }
+
+    /// Creates a criterion estimating the probability of measuring `outcome`.
+    ///
+    /// # Arguments
+    ///
+    /// * `outcome` - The basis index or classical register value to estimate.
+    /// * `tolerance` - The standard error below which the estimate is considered converged.
+    pub fn probability(outcome: usize, tolerance: f64) -> Self {
+        Self::new(
+            move |measured| (measured == outcome) as u8 as f64,
+            tolerance,
+        )
+    }
+
+    /// Creates a criterion estimating the parity `⟨Z⊗…⊗Z⟩` of the given qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubits` - The qubits (or classical bits) whose parity is estimated.
+    /// * `tolerance` - The standard error below which the estimate is considered converged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::convergence::Convergence;
+    ///
+    /// let criterion = Convergence::parity(&[0, 2], 0.01);
+    /// assert_eq!(criterion.value(0b001), -1.0);
+    /// assert_eq!(criterion.value(0b101), 1.0);
+    /// ```
+    pub fn parity(qubits: &[usize], tolerance: f64) -> Self {
+        let mask: usize = qubits.iter().map(|&qubit| 1 << qubit).sum();
+        Self::new(
+            move |measured| {
+                if (measured & mask).count_ones() & 1 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            },
+            tolerance,
+        )
+    }
+
+    /// Sets the number of shots taken before convergence is checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_shots` - The minimum number of shots.
+    pub fn with_min_shots(mut self, min_shots: usize) -> Self {
+        self.min_shots = min_shots;
+        self
+    }
+
+    /// Returns the observable's value for a measured outcome.
+    pub fn value(&self, outcome: usize) -> f64 {
+        (self.observable)(outcome)
+    }
+
+    /// Returns `true` once `estimate` has at least the minimum number of shots and a standard
+    /// error below the tolerance.
+    pub fn is_converged(&self, estimate: &Estimate) -> bool {
+        estimate.shots >= self.min_shots && estimate.standard_error < self.tolerance
+    }
+}
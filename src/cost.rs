@@ -0,0 +1,328 @@
+//! This module estimates what simulating a circuit will cost on each engine before running it,
+//! so programs targeting small devices or WebAssembly can decide feasibility up front.
+//!
+//! The model is deliberately coarse. Every unitary on `k` qubits of an `n`-qubit state vector
+//! is counted as a dense `2ᵏ × 2ᵏ` matrix applied to all `2ⁿ` amplitudes, which reads and writes
+//! the whole register once; a density matrix does the same twice over `4ⁿ` entries; a stabilizer
+//! tableau touches two bits per row for each target. The wall time is the larger of the compute
+//! time and the memory time on a [`DeviceProfile`], plus a fixed overhead per instruction, so
+//! estimates are good to within a small factor rather than exact.
+
+use crate::circuit::{Circuit, Operation};
+use crate::stabilizer;
+use std::fmt;
+
+/// Real floating-point operations of one complex multiply-add.
+const FLOPS_PER_MULTIPLY_ADD: f64 = 8.0;
+
+/// Bytes of one double-precision complex amplitude, and of the single-precision ones on the GPU.
+const AMPLITUDE_BYTES: f64 = 16.0;
+const GPU_AMPLITUDE_BYTES: f64 = 8.0;
+
+/// Bits per word of a stabilizer tableau row.
+const TABLEAU_WORD_BITS: usize = 64;
+
+/// A simulation engine the cost of a circuit can be estimated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Engine {
+    /// The dense state vector of [`CpuBackend`](crate::backend::CpuBackend) and
+    /// [`Simulator::run`](crate::simulator::Simulator::run).
+    StateVector,
+    /// The density matrix of
+    /// [`Simulator::run_density`](crate::simulator::Simulator::run_density), which also
+    /// simulates noise exactly.
+    DensityMatrix,
+    /// The tableau of [`StabilizerBackend`](crate::stabilizer::StabilizerBackend), for Clifford
+    /// circuits only.
+    Stabilizer,
+    /// The single-precision state vector of the GPU backend.
+    Gpu,
+}
+
+impl Engine {
+    /// Every engine, in the order [`estimate_all`] reports them.
+    pub const ALL: [Engine; 4] = [
+        Engine::StateVector,
+        Engine::DensityMatrix,
+        Engine::Stabilizer,
+        Engine::Gpu,
+    ];
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Engine::StateVector => "state vector",
+            Engine::DensityMatrix => "density matrix",
+            Engine::Stabilizer => "stabilizer",
+            Engine::Gpu => "gpu",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The throughput and limits of the device a circuit would run on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeviceProfile {
+    /// Sustained floating-point operations per second.
+    pub flops_per_second: f64,
+    /// Sustained memory bandwidth in bytes per second.
+    pub bytes_per_second: f64,
+    /// Memory available to the simulation, in bytes.
+    pub memory_bytes: f64,
+    /// Fixed cost of each instruction in seconds, e.g. dispatch or kernel-launch latency.
+    pub overhead_seconds: f64,
+    /// Power drawn while simulating, in watts.
+    pub watts: f64,
+}
+
+impl DeviceProfile {
+    /// A desktop CPU core: 20 GFLOP/s, 20 GB/s, 16 GiB and 65 W.
+    pub fn desktop() -> Self {
+        DeviceProfile {
+            flops_per_second: 20e9,
+            bytes_per_second: 20e9,
+            memory_bytes: 16.0 * (1u64 << 30) as f64,
+            overhead_seconds: 50e-9,
+            watts: 65.0,
+        }
+    }
+
+    /// A WebAssembly module in a browser: 5 GFLOP/s, 5 GB/s and the 4 GiB address space of
+    /// `wasm32`.
+    pub fn wasm() -> Self {
+        DeviceProfile {
+            flops_per_second: 5e9,
+            bytes_per_second: 5e9,
+            memory_bytes: 4.0 * (1u64 << 30) as f64,
+            overhead_seconds: 200e-9,
+            watts: 15.0,
+        }
+    }
+
+    /// A microcontroller with a floating-point unit: 100 MFLOP/s, 200 MB/s, 512 KiB and
+    /// 0.5 W.
+    pub fn embedded() -> Self {
+        DeviceProfile {
+            flops_per_second: 100e6,
+            bytes_per_second: 200e6,
+            memory_bytes: 512.0 * 1024.0,
+            overhead_seconds: 5e-6,
+            watts: 0.5,
+        }
+    }
+
+    /// An integrated GPU: 500 GFLOP/s, 50 GB/s, 2 GiB, 20 µs per kernel launch and 15 W.
+    pub fn integrated_gpu() -> Self {
+        DeviceProfile {
+            flops_per_second: 500e9,
+            bytes_per_second: 50e9,
+            memory_bytes: 2.0 * (1u64 << 30) as f64,
+            overhead_seconds: 20e-6,
+            watts: 15.0,
+        }
+    }
+}
+
+/// The estimated cost of running a circuit once on one engine and device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CostEstimate {
+    /// The engine the estimate is for.
+    pub engine: Engine,
+    /// The reason the engine cannot run the circuit at all, e.g. a non-Clifford gate on the
+    /// stabilizer engine, or `None` if it can.
+    pub unsupported: Option<String>,
+    /// Floating-point operations, or bit operations on a tableau.
+    pub flops: f64,
+    /// Bytes read from and written to memory.
+    pub bytes_moved: f64,
+    /// Bytes the simulated state occupies.
+    pub memory_bytes: f64,
+    /// Estimated wall time in seconds.
+    pub seconds: f64,
+    /// Estimated energy in joules.
+    pub joules: f64,
+    /// Whether the state fits in the device's memory.
+    pub fits_in_memory: bool,
+}
+
+impl CostEstimate {
+    /// Returns whether the engine can run the circuit, the state fits in memory and the run is
+    /// estimated to finish within `max_seconds`.
+    pub fn is_feasible(&self, max_seconds: f64) -> bool {
+        self.unsupported.is_none() && self.fits_in_memory && self.seconds <= max_seconds
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(reason) = &self.unsupported {
+            return write!(f, "{}: unsupported ({})", self.engine, reason);
+        }
+        write!(
+            f,
+            "{}: {:.3e} FLOPs, {:.3e} bytes moved, {:.3e} bytes of state{}, {:.3e} s, {:.3e} J",
+            self.engine,
+            self.flops,
+            self.bytes_moved,
+            self.memory_bytes,
+            if self.fits_in_memory {
+                ""
+            } else {
+                " (does not fit)"
+            },
+            self.seconds,
+            self.joules
+        )
+    }
+}
+
+/// Estimates the cost of running a circuit once on an engine, without running it.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to estimate.
+/// * `engine` - The engine that would run it.
+/// * `device` - The device the engine would run on.
+///
+/// # Returns
+///
+/// * A `CostEstimate`, with `unsupported` set if the engine cannot run the circuit.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::cost::{estimate_cost, DeviceProfile, Engine};
+///
+/// let mut circuit = Circuit::new(20);
+/// circuit.h(0);
+/// for qubit in 1..20 {
+///     circuit.cnot(qubit - 1, qubit);
+/// }
+///
+/// // 16 MiB of amplitudes do not fit on a microcontroller, but a tableau does
+/// let device = DeviceProfile::embedded();
+/// let dense = estimate_cost(&circuit, Engine::StateVector, &device);
+/// assert_eq!(dense.memory_bytes, 16.0 * (1 << 20) as f64);
+/// assert!(!dense.is_feasible(1.0));
+/// assert!(estimate_cost(&circuit, Engine::Stabilizer, &device).is_feasible(1.0));
+/// ```
+pub fn estimate_cost(circuit: &Circuit, engine: Engine, device: &DeviceProfile) -> CostEstimate {
+    let n = circuit.num_qubits();
+    let size = |base: f64| base.powi(n as i32);
+    let words = n.div_ceil(TABLEAU_WORD_BITS).max(1) as f64;
+    // A tableau has 2n rows of x and z words and a sign
+    let tableau_bytes = 2.0 * n as f64 * (2.0 * words * 8.0 + 1.0);
+
+    let mut unsupported = None;
+    let (mut flops, mut bytes_moved) = (0.0, 0.0);
+    let mut instructions = 0usize;
+    for instruction in circuit.instructions() {
+        let mut operation = &instruction.operation;
+        while let Operation::Conditional {
+            operation: inner, ..
+        } = operation
+        {
+            operation = inner;
+        }
+        let k = instruction.qubits.len() as i32;
+        let local = 2f64.powi(k);
+        let (gate_flops, gate_bytes) = match (engine, operation) {
+            (_, Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_)) => continue,
+            (Engine::StateVector, Operation::Measure(_) | Operation::Reset) => {
+                (3.0 * size(2.0), 2.0 * AMPLITUDE_BYTES * size(2.0))
+            }
+            (Engine::StateVector, _) => (
+                FLOPS_PER_MULTIPLY_ADD * local * size(2.0),
+                2.0 * AMPLITUDE_BYTES * size(2.0),
+            ),
+            (Engine::Gpu, Operation::Measure(_) | Operation::Reset) => {
+                (3.0 * size(2.0), 2.0 * GPU_AMPLITUDE_BYTES * size(2.0))
+            }
+            (Engine::Gpu, _) => (
+                FLOPS_PER_MULTIPLY_ADD * local * size(2.0),
+                2.0 * GPU_AMPLITUDE_BYTES * size(2.0),
+            ),
+            (Engine::DensityMatrix, Operation::Measure(_) | Operation::Reset) => {
+                (3.0 * size(4.0), 2.0 * AMPLITUDE_BYTES * size(4.0))
+            }
+            // U ρ U† is a pass over the rows and a pass over the columns
+            (Engine::DensityMatrix, _) => (
+                2.0 * FLOPS_PER_MULTIPLY_ADD * local * size(4.0),
+                4.0 * AMPLITUDE_BYTES * size(4.0),
+            ),
+            // A measurement may multiply every row into another, word by word
+            (Engine::Stabilizer, Operation::Measure(_) | Operation::Reset) => (
+                2.0 * n as f64 * 2.0 * words * TABLEAU_WORD_BITS as f64,
+                2.0 * tableau_bytes,
+            ),
+            (Engine::Stabilizer, operation) => {
+                let clifford = match operation {
+                    Operation::Parametric { parameter, .. } => Err(format!(
+                        "the unbound parameter {} may not be Clifford",
+                        parameter.name()
+                    )),
+                    operation if stabilizer::is_clifford(&operation.gate()) => Ok(()),
+                    operation => Err(format!("{} is not a Clifford gate", operation.name())),
+                };
+                if let Err(reason) = clifford {
+                    unsupported.get_or_insert(reason);
+                }
+                // Every row updates the x and z bits of each target
+                (
+                    2.0 * n as f64 * 4.0 * local,
+                    2.0 * n as f64 * 2.0 * 2.0 * k as f64 * 8.0,
+                )
+            }
+        };
+        flops += gate_flops;
+        bytes_moved += gate_bytes;
+        instructions += 1;
+    }
+
+    let memory_bytes = match engine {
+        Engine::StateVector => AMPLITUDE_BYTES * size(2.0),
+        Engine::Gpu => GPU_AMPLITUDE_BYTES * size(2.0),
+        Engine::DensityMatrix => AMPLITUDE_BYTES * size(4.0),
+        Engine::Stabilizer => tableau_bytes,
+    };
+    let seconds = (flops / device.flops_per_second).max(bytes_moved / device.bytes_per_second)
+        + instructions as f64 * device.overhead_seconds;
+    CostEstimate {
+        engine,
+        unsupported,
+        flops,
+        bytes_moved,
+        memory_bytes,
+        seconds,
+        joules: seconds * device.watts,
+        fits_in_memory: memory_bytes <= device.memory_bytes,
+    }
+}
+
+/// Estimates the cost of running a circuit once on every engine, in the order of
+/// [`Engine::ALL`].
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::cost::{estimate_all, DeviceProfile, Engine};
+///
+/// let mut circuit = Circuit::new(10);
+/// circuit.h(0).t(0).cnot(0, 1);
+/// let feasible: Vec<Engine> = estimate_all(&circuit, &DeviceProfile::wasm())
+///     .into_iter()
+///     .filter(|estimate| estimate.is_feasible(0.1))
+///     .map(|estimate| estimate.engine)
+///     .collect();
+/// // T is not a Clifford gate
+/// assert_eq!(feasible, vec![Engine::StateVector, Engine::DensityMatrix, Engine::Gpu]);
+/// ```
+pub fn estimate_all(circuit: &Circuit, device: &DeviceProfile) -> Vec<CostEstimate> {
+    Engine::ALL
+        .iter()
+        .map(|&engine| estimate_cost(circuit, engine, device))
+        .collect()
+}
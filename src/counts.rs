@@ -0,0 +1,236 @@
+//! This module defines the `Counts` histogram produced by shot-based sampling.
+
+use std::collections::BTreeMap;
+
+/// `Counts` maps measured bitstrings to how many shots produced them.
+///
+/// Bitstrings are written with the highest-index qubit first, so the string for basis state
+/// `index` is its binary representation (e.g. `"0101"` is index 5 on four qubits).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Counts {
+    counts: BTreeMap<String, usize>,
+    shots: usize,
+}
+
+impl Counts {
+    /// Creates an empty histogram.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::Counts;
+    ///
+    /// let counts = Counts::new();
+    /// assert_eq!(counts.shots(), 0);
+    /// ```
+    pub fn new() -> Self {
+        Counts {
+            counts: BTreeMap::new(),
+            shots: 0,
+        }
+    }
+
+    /// Records one shot that produced the given basis state.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The measured basis-state index.
+    /// * `num_qubits` - The register width, which sets the bitstring length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::Counts;
+    ///
+    /// let mut counts = Counts::new();
+    /// counts.record(5, 4);
+    /// assert_eq!(counts.get("0101"), 1);
+    /// ```
+    pub fn record(&mut self, index: usize, num_qubits: usize) {
+        *self.counts.entry(bitstring(index, num_qubits)).or_insert(0) += 1;
+        self.shots += 1;
+    }
+
+    /// Records `count` shots that produced the given basis state, e.g. from a histogram
+    /// returned by another simulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The measured basis-state index.
+    /// * `num_qubits` - The register width, which sets the bitstring length.
+    /// * `count` - The number of shots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::Counts;
+    ///
+    /// let mut counts = Counts::new();
+    /// counts.record_many(2, 2, 10);
+    /// assert_eq!(counts.get("10"), 10);
+    /// assert_eq!(counts.shots(), 10);
+    /// ```
+    pub fn record_many(&mut self, index: usize, num_qubits: usize, count: usize) {
+        if count > 0 {
+            *self.counts.entry(bitstring(index, num_qubits)).or_insert(0) += count;
+            self.shots += count;
+        }
+    }
+
+    /// Returns how many shots produced the given bitstring.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::Counts;
+    ///
+    /// let counts = Counts::new();
+    /// assert_eq!(counts.get("00"), 0);
+    /// ```
+    pub fn get(&self, bitstring: &str) -> usize {
+        self.counts.get(bitstring).copied().unwrap_or(0)
+    }
+
+    /// Returns the total number of recorded shots.
+    pub fn shots(&self) -> usize {
+        self.shots
+    }
+
+    /// Iterates over the observed bitstrings and their counts in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts
+            .iter()
+            .map(|(key, &count)| (key.as_str(), count))
+    }
+
+    /// Returns the observed relative frequency of every bitstring.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::Counts;
+    ///
+    /// let mut counts = Counts::new();
+    /// counts.record(0, 1);
+    /// counts.record(1, 1);
+    /// assert_eq!(counts.frequencies()["1"], 0.5);
+    /// ```
+    pub fn frequencies(&self) -> BTreeMap<String, f64> {
+        self.counts
+            .iter()
+            .map(|(key, &count)| (key.clone(), count as f64 / self.shots as f64))
+            .collect()
+    }
+
+    /// Returns the most frequently observed bitstring, if any shots were recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::Counts;
+    ///
+    /// let mut counts = Counts::new();
+    /// counts.record(3, 2);
+    /// counts.record(3, 2);
+    /// counts.record(0, 2);
+    /// assert_eq!(counts.most_frequent(), Some(("11", 2)));
+    /// ```
+    pub fn most_frequent(&self) -> Option<(&str, usize)> {
+        self.iter().max_by_key(|&(_, count)| count)
+    }
+
+    /// Returns the histogram as a map from bitstring to count.
+    pub fn as_map(&self) -> &BTreeMap<String, usize> {
+        &self.counts
+    }
+}
+
+/// `WeightedCounts` is a histogram of importance-sampled shots, where every shot carries the
+/// weight `p(x) / q(x)` that converts it from the sampling distribution `q` back to the state's
+/// true distribution `p`.
+///
+/// Outcomes that `q` never proposes are missing from the histogram, so estimates only cover the
+/// sampled subset of bitstrings.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightedCounts {
+    counts: Counts,
+    weights: BTreeMap<String, f64>,
+    squared_weight: f64,
+}
+
+impl WeightedCounts {
+    /// Creates an empty weighted histogram.
+    pub fn new() -> Self {
+        WeightedCounts::default()
+    }
+
+    /// Records one shot that produced the given basis state with importance weight `weight`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The measured basis-state index.
+    /// * `num_qubits` - The register width, which sets the bitstring length.
+    /// * `weight` - The ratio `p(x) / q(x)` of the true and sampling probabilities.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::WeightedCounts;
+    ///
+    /// let mut counts = WeightedCounts::new();
+    /// counts.record(1, 2, 0.1);
+    /// counts.record(1, 2, 0.1);
+    /// assert_eq!(counts.counts().get("01"), 2);
+    /// assert!((counts.estimate("01") - 0.1).abs() < 1e-12);
+    /// ```
+    pub fn record(&mut self, index: usize, num_qubits: usize, weight: f64) {
+        self.counts.record(index, num_qubits);
+        *self
+            .weights
+            .entry(bitstring(index, num_qubits))
+            .or_insert(0.0) += weight;
+        self.squared_weight += weight * weight;
+    }
+
+    /// Returns the unweighted histogram of sampled bitstrings.
+    pub fn counts(&self) -> &Counts {
+        &self.counts
+    }
+
+    /// Returns the total number of recorded shots.
+    pub fn shots(&self) -> usize {
+        self.counts.shots()
+    }
+
+    /// Returns the reweighted estimate of the true probability of the given bitstring.
+    pub fn estimate(&self, bitstring: &str) -> f64 {
+        self.weights.get(bitstring).copied().unwrap_or(0.0) / self.shots() as f64
+    }
+
+    /// Returns the reweighted probability estimate of every sampled bitstring.
+    pub fn estimates(&self) -> BTreeMap<String, f64> {
+        self.weights
+            .iter()
+            .map(|(key, &weight)| (key.clone(), weight / self.shots() as f64))
+            .collect()
+    }
+
+    /// Returns the estimated total probability of the sampled bitstrings.
+    pub fn total_estimate(&self) -> f64 {
+        self.weights.values().sum::<f64>() / self.shots() as f64
+    }
+
+    /// Returns the effective sample size `(Σw)² / Σw²`, i.e. how many unweighted shots the
+    /// weighted ones are worth.
+    pub fn effective_sample_size(&self) -> f64 {
+        let total: f64 = self.weights.values().sum();
+        total * total / self.squared_weight
+    }
+}
+
+/// Formats a basis-state index as a bitstring with the highest-index qubit first.
+pub(crate) fn bitstring(index: usize, num_qubits: usize) -> String {
+    format!("{:0width$b}", index, width = num_qubits)
+}
@@ -0,0 +1,394 @@
+//! This module provides a time-travel debugger for circuits.
+//!
+//! A [`Debugger`] records one trajectory up front, keeping the state after every instruction as a
+//! [`Trace`] of copy-on-write snapshots and the measurements, noise and branches as an
+//! [`EventLog`]. Moving through the run is then free in both directions: [`Debugger::step`] and
+//! [`Debugger::step_back`] move one instruction, and [`Debugger::resume`] and
+//! [`Debugger::reverse`] run to the next [`Breakpoint`] either way. Positions count executed
+//! instructions, so position `0` is the initial state and position `i` is the state after
+//! instruction `i - 1`, just before instruction `i`.
+
+use crate::circuit::{Circuit, Instruction};
+use crate::error::QuantumError;
+use crate::events::{Event, EventLog};
+use crate::hooks::Hooks;
+use crate::noise::NoiseModel;
+use crate::simulator::Simulator;
+use crate::snapshot::{Snapshot, Trace};
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fmt;
+use std::str::FromStr;
+
+/// A comparison in a [`Breakpoint::Probability`] condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Returns whether `value` compares to `threshold` this way.
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A condition the debugger stops at.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Breakpoint {
+    /// Stop just before the instruction with this index.
+    Instruction(usize),
+    /// Stop just before any instruction acting on this qubit.
+    Qubit(usize),
+    /// Stop once the probability of a basis state compares to a threshold, e.g. `P(|11⟩) > 0.4`.
+    Probability {
+        /// The basis state, written most significant qubit first.
+        bitstring: String,
+        comparison: Comparison,
+        threshold: f64,
+    },
+}
+
+impl Breakpoint {
+    /// Returns whether the breakpoint fires at `position` of a run of `circuit`, where `state` is
+    /// the state at that position.
+    fn is_hit(&self, circuit: &Circuit, position: usize, state: &Snapshot) -> bool {
+        let next = circuit.instructions().get(position);
+        match self {
+            Breakpoint::Instruction(index) => position == *index,
+            Breakpoint::Qubit(qubit) => next.is_some_and(|next| next.qubits.contains(qubit)),
+            Breakpoint::Probability {
+                bitstring,
+                comparison,
+                threshold,
+            } => {
+                let index = usize::from_str_radix(bitstring, 2).unwrap_or(usize::MAX);
+                bitstring.len() == circuit.num_qubits()
+                    && comparison.holds(state.get(index).norm_sqr(), *threshold)
+            }
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = QuantumError;
+
+    /// Parses `3` or `#3` as an instruction, `q1` as a qubit and conditions such as
+    /// `P(|11⟩) > 0.4` or `p(11) <= 0.1` as a probability breakpoint.
+    fn from_str(text: &str) -> Result<Self, QuantumError> {
+        let invalid = || QuantumError::Parse {
+            line: 0,
+            message: format!(
+                "expected a breakpoint like '3', 'q1' or 'P(|11>) > 0.4', found '{}'",
+                text
+            ),
+        };
+        let text = text.trim();
+        if let Some(qubit) = text.strip_prefix('q') {
+            return qubit.parse().map(Breakpoint::Qubit).map_err(|_| invalid());
+        }
+        if let Some(rest) = text.strip_prefix(['P', 'p']) {
+            let rest = rest.trim_start().strip_prefix('(').ok_or_else(invalid)?;
+            let (state, condition) = rest.split_once(')').ok_or_else(invalid)?;
+            let bitstring = state
+                .trim()
+                .trim_start_matches('|')
+                .trim_end_matches(['⟩', '>']);
+            if bitstring.is_empty() || !bitstring.chars().all(|c| c == '0' || c == '1') {
+                return Err(invalid());
+            }
+            let condition = condition.trim();
+            let (comparison, threshold) = [
+                ("<=", Comparison::LessOrEqual),
+                (">=", Comparison::GreaterOrEqual),
+                ("<", Comparison::Less),
+                (">", Comparison::Greater),
+            ]
+            .into_iter()
+            .find_map(|(symbol, comparison)| {
+                condition
+                    .strip_prefix(symbol)
+                    .map(|threshold| (comparison, threshold))
+            })
+            .ok_or_else(invalid)?;
+            let threshold = threshold.trim().parse().map_err(|_| invalid())?;
+            return Ok(Breakpoint::Probability {
+                bitstring: bitstring.to_string(),
+                comparison,
+                threshold,
+            });
+        }
+        text.trim_start_matches('#')
+            .parse()
+            .map(Breakpoint::Instruction)
+            .map_err(|_| invalid())
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Instruction(index) => write!(f, "#{}", index),
+            Breakpoint::Qubit(qubit) => write!(f, "q{}", qubit),
+            Breakpoint::Probability {
+                bitstring,
+                comparison,
+                threshold,
+            } => write!(f, "P(|{}⟩) {} {}", bitstring, comparison, threshold),
+        }
+    }
+}
+
+/// Where a [`Debugger::resume`] or [`Debugger::reverse`] stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// The breakpoint with this index fired.
+    Breakpoint(usize),
+    /// The run reached its start or end without hitting a breakpoint.
+    Boundary,
+}
+
+/// A `Debugger` steps forwards and backwards through one recorded trajectory of a circuit.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::debugger::{Debugger, Stop};
+/// use quantum_simulator::noise::NoiseModel;
+/// use quantum_simulator::qubit::Qubit;
+///
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1).h(1);
+///
+/// let initial_state = Qubit::zero_state(2).state;
+/// let mut debugger = Debugger::new(&circuit, &initial_state, &NoiseModel::new(), 1);
+/// debugger.add_breakpoint("P(|11>) > 0.4".parse().unwrap()).unwrap();
+/// assert!(debugger.add_breakpoint("P(|011>) > 0.4".parse().unwrap()).is_err());
+///
+/// // Runs to the state after the CNOT, then travels back to the start
+/// assert_eq!(debugger.resume(), Stop::Breakpoint(0));
+/// assert_eq!(debugger.position(), 2);
+/// assert!((debugger.state().get(0b11).norm_sqr() - 0.5).abs() < 1e-12);
+/// assert_eq!(debugger.reverse(), Stop::Boundary);
+/// assert_eq!(debugger.position(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Debugger {
+    circuit: Circuit,
+    trace: Trace,
+    events: EventLog,
+    position: usize,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    /// Records one trajectory of `circuit` and positions the debugger at its start.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to debug.
+    /// * `initial_state` - The initial state vector.
+    /// * `noise_model` - The errors to sample along the trajectory.
+    /// * `seed` - The seed of the measurement outcomes and errors.
+    pub fn new(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        seed: u64,
+    ) -> Self {
+        let mut trace = Trace::new(initial_state);
+        let mut hooks = Hooks::new();
+        hooks.on_gate_applied(|index, instruction, qubit| trace.record(index, instruction, qubit));
+        let result = Simulator::run_with_hooks_with_rng(
+            circuit,
+            initial_state,
+            noise_model,
+            &mut hooks,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        drop(hooks);
+        trace.set_clbits(result.clbits);
+        Debugger {
+            circuit: circuit.clone(),
+            trace,
+            events: result.events,
+            position: 0,
+            breakpoints: vec![],
+        }
+    }
+
+    /// Returns the circuit being debugged.
+    pub fn circuit(&self) -> &Circuit {
+        &self.circuit
+    }
+
+    /// Returns the number of instructions executed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the number of instructions in the run, the last position.
+    pub fn len(&self) -> usize {
+        self.trace.steps().len()
+    }
+
+    /// Returns whether the circuit has no instructions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the state at the current position.
+    pub fn state(&self) -> &Snapshot {
+        self.state_at(self.position)
+    }
+
+    /// Returns the state at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is past the end of the run.
+    pub fn state_at(&self, position: usize) -> &Snapshot {
+        if position == 0 {
+            self.trace.initial_state()
+        } else {
+            &self.trace.steps()[position - 1].state
+        }
+    }
+
+    /// Returns the instruction about to run, `None` at the end of the run.
+    pub fn next_instruction(&self) -> Option<&Instruction> {
+        self.circuit.instructions().get(self.position)
+    }
+
+    /// Returns the events of the instructions executed so far.
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        let position = self.position;
+        self.events
+            .iter()
+            .filter(move |event| event_instruction(event) < position)
+    }
+
+    /// Returns the states of the recorded run, e.g. to
+    /// [`explain`](crate::explain::explain) it.
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    /// Returns the full event log of the recorded run.
+    pub fn event_log(&self) -> &EventLog {
+        &self.events
+    }
+
+    /// Adds a breakpoint and returns its index.
+    ///
+    /// # Returns
+    ///
+    /// * The index of the breakpoint, or `QuantumError::DimensionMismatch` if it is a
+    ///   [`Breakpoint::Probability`] whose bitstring does not have one bit per qubit, which
+    ///   could never fire.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> Result<usize, QuantumError> {
+        if let Breakpoint::Probability { bitstring, .. } = &breakpoint {
+            if bitstring.len() != self.circuit.num_qubits() {
+                return Err(QuantumError::DimensionMismatch {
+                    expected: self.circuit.num_qubits(),
+                    found: bitstring.len(),
+                });
+            }
+        }
+        self.breakpoints.push(breakpoint);
+        Ok(self.breakpoints.len() - 1)
+    }
+
+    /// Removes the breakpoint with index `index`, shifting later indices down, and returns it.
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        (index < self.breakpoints.len()).then(|| self.breakpoints.remove(index))
+    }
+
+    /// Returns the breakpoints, in the order they were added.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Returns the index of the first breakpoint that fires at `position`.
+    pub fn breakpoint_at(&self, position: usize) -> Option<usize> {
+        let state = self.state_at(position);
+        self.breakpoints
+            .iter()
+            .position(|breakpoint| breakpoint.is_hit(&self.circuit, position, state))
+    }
+
+    /// Moves to `position`, clamped to the run.
+    pub fn goto(&mut self, position: usize) {
+        self.position = position.min(self.len());
+    }
+
+    /// Executes the next instruction, returning `false` at the end of the run.
+    pub fn step(&mut self) -> bool {
+        let moved = self.position < self.len();
+        self.goto(self.position + 1);
+        moved
+    }
+
+    /// Undoes the last instruction, returning `false` at the start of the run.
+    pub fn step_back(&mut self) -> bool {
+        let moved = self.position > 0;
+        self.position = self.position.saturating_sub(1);
+        moved
+    }
+
+    /// Steps forward at least once and on until a breakpoint fires or the run ends.
+    pub fn resume(&mut self) -> Stop {
+        while self.step() {
+            if let Some(index) = self.breakpoint_at(self.position) {
+                return Stop::Breakpoint(index);
+            }
+        }
+        Stop::Boundary
+    }
+
+    /// Steps backward at least once and on until a breakpoint fires or the run's start.
+    pub fn reverse(&mut self) -> Stop {
+        while self.step_back() {
+            if let Some(index) = self.breakpoint_at(self.position) {
+                return Stop::Breakpoint(index);
+            }
+        }
+        Stop::Boundary
+    }
+}
+
+/// Returns the index of the instruction an event belongs to.
+fn event_instruction(event: &Event) -> usize {
+    match event {
+        Event::Gate { instruction, .. }
+        | Event::Channel { instruction, .. }
+        | Event::Measurement { instruction, .. }
+        | Event::Branch { instruction, .. } => *instruction,
+    }
+}
@@ -0,0 +1,455 @@
+//! This module synthesizes circuits of CNOTs and single-qubit rotations from gate matrices.
+//!
+//! [`single_qubit`] writes a one-qubit unitary as the Euler rotations `Rz·Ry·Rz`, and
+//! [`two_qubit`] writes a two-qubit unitary with the KAK (Cartan) decomposition
+//! `U = (A₁ ⊗ B₁) · exp(i(a XX + b YY + c ZZ)) · (A₀ ⊗ B₀)`: the local factors become Euler
+//! rotations and the interaction needs at most three CNOTs, fewer when some of `a`, `b` and `c`
+//! vanish. Both hold up to a global phase. The
+//! [`LowerUnitaries`](crate::optimize::LowerUnitaries) pass applies them to every `Unitary`
+//! instruction of a circuit.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::error::QuantumError;
+use crate::gates::{hadamard, pauli_x, pauli_y, pauli_z, rx, ry, rz, Gate};
+use crate::linalg;
+use crate::tolerance;
+use num_complex::Complex;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4, PI};
+
+type Matrix = Vec<Vec<Complex<f64>>>;
+
+/// Returns Euler rotations `Rz(λ)`, `Ry(θ)`, `Rz(φ)` on qubit 0 implementing a single-qubit gate
+/// up to a global phase, with angles in `(-π, π]`. Rotations by a negligible angle are left out,
+/// and a diagonal gate becomes the single rotation `Rz(λ + φ)`.
+///
+/// # Arguments
+///
+/// * `gate` - The single-qubit unitary.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - A one-qubit circuit of at most three rotations.
+/// * `Err(QuantumError::DimensionMismatch)` - If the gate does not act on one qubit.
+/// * `Err(QuantumError::NotUnitary)` - If the matrix is not unitary.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Operation;
+/// use quantum_simulator::decompose::single_qubit;
+/// use quantum_simulator::gates::hadamard;
+/// use std::f64::consts::PI;
+///
+/// let circuit = single_qubit(&hadamard(1)).unwrap();
+/// let operations: Vec<_> = circuit.instructions().iter().map(|i| &i.operation).collect();
+/// assert!(matches!(operations[..], [Operation::Rz(lambda), Operation::Ry(theta)]
+///     if (lambda - PI).abs() < 1e-12 && (theta - PI / 2.0).abs() < 1e-12));
+///
+/// // A diagonal gate is a single Rz
+/// let circuit = single_qubit(&quantum_simulator::gates::t()).unwrap();
+/// let operations: Vec<_> = circuit.instructions().iter().map(|i| &i.operation).collect();
+/// assert!(matches!(operations[..], [Operation::Rz(angle)] if (angle - PI / 4.0).abs() < 1e-12));
+/// ```
+pub fn single_qubit(gate: &Gate) -> Result<Circuit, QuantumError> {
+    let matrix = checked(gate, 2)?;
+    let mut circuit = Circuit::new(1);
+    for operation in euler_rotations(&matrix) {
+        circuit.add_instruction(Instruction::new(operation, vec![0]))?;
+    }
+    Ok(circuit)
+}
+
+/// Returns a circuit of at most three CNOTs and single-qubit rotations implementing a two-qubit
+/// gate up to a global phase.
+///
+/// Bit `j` of the gate's basis index is qubit `j` of the circuit. Gates that are a product of
+/// single-qubit gates need no CNOT, gates locally equivalent to a CNOT one, and gates whose
+/// interaction lacks one of the `XX`, `YY` and `ZZ` terms two.
+///
+/// # Arguments
+///
+/// * `gate` - The two-qubit unitary.
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - A two-qubit circuit of `Cnot`, `Rz` and `Ry` instructions.
+/// * `Err(QuantumError::DimensionMismatch)` - If the gate does not act on two qubits.
+/// * `Err(QuantumError::NotUnitary)` - If the matrix is not unitary.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Operation;
+/// use quantum_simulator::decompose::two_qubit;
+/// use quantum_simulator::gates::{cz, swap, Gate};
+///
+/// let cnots = |gate: &Gate| {
+///     let circuit = two_qubit(gate).unwrap();
+///     circuit.instructions().iter().filter(|i| i.operation == Operation::Cnot).count()
+/// };
+/// assert_eq!(cnots(&cz(0, 1, 2)), 1);
+/// assert_eq!(cnots(&swap(0, 1, 2)), 3);
+///
+/// // The synthesized circuit reproduces the gate up to a global phase
+/// let circuit = two_qubit(&swap(0, 1, 2)).unwrap();
+/// let matrix = circuit.to_unitary(2);
+/// let phase = matrix[0][0];
+/// assert!((phase.norm() - 1.0).abs() < 1e-9);
+/// assert!((matrix[0b01][0b10] - phase).norm() < 1e-9);
+/// ```
+pub fn two_qubit(gate: &Gate) -> Result<Circuit, QuantumError> {
+    let matrix = checked(gate, 4)?;
+    // Scale into SU(4) and change to the magic basis, where local gates are real orthogonal
+    let scale = Complex::from_polar(1.0, -linalg::determinant(&matrix).arg() / 4.0);
+    let special: Matrix = matrix
+        .iter()
+        .map(|row| row.iter().map(|x| x * scale).collect())
+        .collect();
+    let magic = magic_basis();
+    let magic_dagger = linalg::dagger(&magic);
+    let u = linalg::matmul(&magic_dagger, &linalg::matmul(&special, &magic));
+
+    // UᵀU = K₂ᵀ D K₂ with K₂ real orthogonal: Re and Im of UᵀU commute, so one real
+    // eigenbasis of a generic mix of them diagonalizes both
+    let symmetric = linalg::matmul(&transpose(&u), &u);
+    let mixed: Matrix = symmetric
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|x| Complex::new(x.re + linalg::MIXING * x.im, 0.0))
+                .collect()
+        })
+        .collect();
+    let (_, vectors) = linalg::eigh(&mixed);
+    let mut p = real_eigenbasis(&vectors);
+    if linalg::determinant(&p).re < 0.0 {
+        p.iter_mut().for_each(|row| row[0] = -row[0]);
+    }
+    let diagonal = linalg::matmul(&transpose(&p), &linalg::matmul(&symmetric, &p));
+    let mut half: Vec<Complex<f64>> = (0..4)
+        .map(|k| Complex::from_polar(1.0, diagonal[k][k].arg() / 2.0))
+        .collect();
+    if half.iter().product::<Complex<f64>>().re < 0.0 {
+        half[0] = -half[0];
+    }
+    // U = K₁ A K₂ with A = diag(half), K₂ = Pᵀ and K₁ = U P A⁻¹
+    let mut k1 = linalg::matmul(&u, &p);
+    for row in k1.iter_mut() {
+        for (elem, h) in row.iter_mut().zip(&half) {
+            *elem /= h;
+        }
+    }
+    let before = linalg::matmul(&magic, &linalg::matmul(&transpose(&p), &magic_dagger));
+    let after = linalg::matmul(&magic, &linalg::matmul(&k1, &magic_dagger));
+
+    // A is exp(i(a XX + b YY + c ZZ)) up to phase, and XX, YY, ZZ are ±1 on each magic state
+    let signs: Vec<Vec<f64>> = [pauli_x(), pauli_y(), pauli_z()]
+        .iter()
+        .map(|pauli| {
+            let pp = linalg::kron(&pauli.matrix, &pauli.matrix);
+            let diagonal = linalg::matmul(&magic_dagger, &linalg::matmul(&pp, &magic));
+            (0..4).map(|k| diagonal[k][k].re).collect()
+        })
+        .collect();
+    let coefficients: Vec<f64> = signs
+        .iter()
+        .map(|sign| (0..4).map(|k| sign[k] * half[k].arg()).sum::<f64>() / 4.0)
+        .collect();
+
+    let mut synthesis = Synthesis::new();
+    let (high, low) = split_local(&before);
+    synthesis.local(0, &low);
+    synthesis.local(1, &high);
+    synthesis.interaction(coefficients[0], coefficients[1], coefficients[2]);
+    let (high, low) = split_local(&after);
+    synthesis.local(0, &low);
+    synthesis.local(1, &high);
+    Ok(synthesis.finish())
+}
+
+/// Returns the gate's matrix after checking that it is a unitary of the given dimension.
+fn checked(gate: &Gate, dimension: usize) -> Result<Matrix, QuantumError> {
+    if gate.matrix.len() != dimension {
+        return Err(QuantumError::DimensionMismatch {
+            expected: dimension,
+            found: gate.matrix.len(),
+        });
+    }
+    Ok(Gate::from_unitary(gate.matrix.as_ref().clone())?
+        .matrix
+        .as_ref()
+        .clone())
+}
+
+/// Returns whether an angle is negligible modulo `period`.
+fn negligible(angle: f64, period: f64) -> bool {
+    let remainder = angle.rem_euclid(period);
+    remainder.min(period - remainder) < tolerance::current().equality
+}
+
+/// Returns the Euler rotations `Rz(λ)`, `Ry(θ)`, `Rz(φ)`, in circuit order, of a single-qubit
+/// unitary, leaving out negligible ones.
+fn euler_rotations(matrix: &[Vec<Complex<f64>>]) -> Vec<Operation> {
+    // With det V = 1, V = [[e^{-i(φ+λ)/2} cos, -e^{-i(φ-λ)/2} sin],
+    //                      [e^{i(φ-λ)/2} sin,   e^{i(φ+λ)/2} cos]] of θ/2
+    let root = Complex::from_polar(1.0, -linalg::determinant(matrix).arg() / 2.0);
+    let (m00, m10, m11) = (
+        matrix[0][0] * root,
+        matrix[1][0] * root,
+        matrix[1][1] * root,
+    );
+    let theta = 2.0 * m10.norm().atan2(m00.norm());
+    let truncation = tolerance::current().truncation;
+    let sum = if m11.norm() > truncation {
+        2.0 * m11.arg()
+    } else {
+        0.0
+    };
+    let difference = if m10.norm() > truncation {
+        2.0 * m10.arg()
+    } else {
+        0.0
+    };
+    let (phi, lambda) = ((sum + difference) / 2.0, (sum - difference) / 2.0);
+    // Rz and Ry by 2π are -I, a global phase; without the Ry the two Rz are one rotation
+    let rotations = if negligible(theta, 2.0 * PI) {
+        vec![Operation::Rz(principal(lambda + phi))]
+    } else {
+        vec![
+            Operation::Rz(principal(lambda)),
+            Operation::Ry(principal(theta)),
+            Operation::Rz(principal(phi)),
+        ]
+    };
+    rotations
+        .into_iter()
+        .filter(|operation| match operation {
+            Operation::Ry(angle) | Operation::Rz(angle) => !negligible(*angle, 2.0 * PI),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Returns an angle equal to `angle` modulo `2π` in `(-π, π]`.
+fn principal(angle: f64) -> f64 {
+    let reduced = angle - 2.0 * PI * (angle / (2.0 * PI)).round();
+    if reduced <= -PI + tolerance::current().equality {
+        reduced + 2.0 * PI
+    } else {
+        reduced
+    }
+}
+
+/// Returns a real orthonormal basis, as columns, of the space spanned by the complex eigenvectors
+/// of a real symmetric matrix.
+///
+/// Each eigenspace is closed under conjugation, so the real and imaginary parts of the
+/// eigenvectors span it; the parts are orthonormalized greedily, largest remainder first.
+fn real_eigenbasis(vectors: &[Vec<Complex<f64>>]) -> Matrix {
+    let n = vectors.len();
+    let candidates: Vec<Vec<f64>> = (0..n)
+        .flat_map(|j| {
+            [
+                (0..n).map(|i| vectors[i][j].re).collect(),
+                (0..n).map(|i| vectors[i][j].im).collect(),
+            ]
+        })
+        .collect();
+    let mut basis: Vec<Vec<f64>> = Vec::with_capacity(n);
+    while basis.len() < n {
+        let remainder = |candidate: &Vec<f64>| {
+            let mut rest = candidate.clone();
+            for vector in &basis {
+                let overlap: f64 = rest.iter().zip(vector).map(|(x, y)| x * y).sum();
+                rest.iter_mut()
+                    .zip(vector)
+                    .for_each(|(x, y)| *x -= overlap * y);
+            }
+            rest
+        };
+        let norm = |v: &Vec<f64>| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        let best = candidates
+            .iter()
+            .map(remainder)
+            .max_by(|a, b| norm(a).total_cmp(&norm(b)))
+            .expect("there are candidates");
+        let length = norm(&best);
+        basis.push(best.iter().map(|x| x / length).collect());
+    }
+    (0..n)
+        .map(|i| basis.iter().map(|v| Complex::new(v[i], 0.0)).collect())
+        .collect()
+}
+
+/// Returns the magic basis as columns: `(|00⟩ + |11⟩)/√2`, `i(|00⟩ - |11⟩)/√2`,
+/// `i(|01⟩ + |10⟩)/√2` and `(|01⟩ - |10⟩)/√2`.
+fn magic_basis() -> Matrix {
+    let (r, i, z) = (
+        Complex::new(FRAC_1_SQRT_2, 0.0),
+        Complex::new(0.0, FRAC_1_SQRT_2),
+        Complex::new(0.0, 0.0),
+    );
+    vec![
+        vec![r, i, z, z],
+        vec![z, z, i, r],
+        vec![z, z, i, -r],
+        vec![r, -i, z, z],
+    ]
+}
+
+/// Returns the transpose of a matrix.
+fn transpose(a: &[Vec<Complex<f64>>]) -> Matrix {
+    (0..a.len())
+        .map(|j| a.iter().map(|row| row[j]).collect())
+        .collect()
+}
+
+/// Splits a two-qubit product gate into its factors on qubit 1 and qubit 0.
+fn split_local(matrix: &[Vec<Complex<f64>>]) -> (Matrix, Matrix) {
+    // The largest entry picks a row and column of each factor that is far from zero
+    let (row, col) = (0..16)
+        .map(|k| (k / 4, k % 4))
+        .max_by(|&(a, b), &(c, d)| matrix[a][b].norm().total_cmp(&matrix[c][d].norm()))
+        .expect("the matrix has entries");
+    let mut high: Matrix = (0..2)
+        .map(|i| {
+            (0..2)
+                .map(|j| matrix[2 * i + (row & 1)][2 * j + (col & 1)])
+                .collect()
+        })
+        .collect();
+    let norm = linalg::determinant(&high).norm().sqrt();
+    high.iter_mut().flatten().for_each(|elem| *elem /= norm);
+    let pivot = high[row >> 1][col >> 1];
+    let low = (0..2)
+        .map(|i| {
+            (0..2)
+                .map(|j| matrix[2 * (row >> 1) + i][2 * (col >> 1) + j] / pivot)
+                .collect()
+        })
+        .collect();
+    (high, low)
+}
+
+/// Builds a two-qubit circuit, multiplying up consecutive single-qubit gates on each qubit and
+/// writing them as Euler rotations only when a CNOT or the end of the circuit needs them.
+struct Synthesis {
+    circuit: Circuit,
+    pending: [Matrix; 2],
+}
+
+impl Synthesis {
+    fn new() -> Self {
+        Synthesis {
+            circuit: Circuit::new(2),
+            pending: [linalg::identity(2), linalg::identity(2)],
+        }
+    }
+
+    /// Applies a single-qubit gate to `qubit`.
+    fn local(&mut self, qubit: usize, matrix: &[Vec<Complex<f64>>]) {
+        self.pending[qubit] = linalg::matmul(matrix, &self.pending[qubit]);
+    }
+
+    /// Applies a CNOT.
+    fn cnot(&mut self, control: usize, target: usize) {
+        self.flush();
+        self.circuit.cnot(control, target);
+    }
+
+    /// Applies the same single-qubit gate to both qubits.
+    fn both(&mut self, gate: &Gate) {
+        self.local(0, &gate.matrix);
+        self.local(1, &gate.matrix);
+    }
+
+    /// Applies `exp(i(a XX + b YY + c ZZ))` with as few CNOTs as the coefficients allow.
+    fn interaction(&mut self, a: f64, b: f64, c: f64) {
+        // exp(iπ/2 PP) = i PP is local, so each coefficient moves into [-π/4, π/4]
+        let mut reduced = [a, b, c];
+        for (coefficient, pauli) in reduced.iter_mut().zip([pauli_x(), pauli_y(), pauli_z()]) {
+            let turns = (*coefficient / FRAC_PI_2).round();
+            *coefficient -= turns * FRAC_PI_2;
+            if turns.rem_euclid(2.0) == 1.0 {
+                self.both(&pauli);
+            }
+        }
+        let [a, b, c] = reduced;
+        let zero = |angle: f64| angle.abs() < tolerance::current().equality;
+        let quarter = |angle: f64| (angle.abs() - FRAC_PI_4).abs() < tolerance::current().equality;
+        match (zero(a), zero(b), zero(c)) {
+            (true, true, true) => {}
+            // A single term of a quarter turn is a CZ up to local gates: H⊗H maps XX to ZZ and
+            // Rx(π/2)⊗Rx(π/2) maps YY to ZZ
+            (false, true, true) if quarter(a) => {
+                self.both(&hadamard(1));
+                self.controlled_z(a);
+                self.both(&hadamard(1));
+            }
+            (true, false, true) if quarter(b) => {
+                self.both(&rx(FRAC_PI_2));
+                self.controlled_z(b);
+                self.both(&rx(-FRAC_PI_2));
+            }
+            (true, true, false) if quarter(c) => self.controlled_z(c),
+            (_, true, _) => self.two_cnots(a, c),
+            // Rx(π/2)⊗Rx(π/2) maps YY to ZZ and Rz(π/2)⊗Rz(π/2) maps YY to XX
+            (_, _, true) => {
+                self.both(&rx(FRAC_PI_2));
+                self.two_cnots(a, b);
+                self.both(&rx(-FRAC_PI_2));
+            }
+            (true, _, _) => {
+                self.both(&rz(FRAC_PI_2));
+                self.two_cnots(b, c);
+                self.both(&rz(-FRAC_PI_2));
+            }
+            _ => {
+                // Vatan and Williams, "Optimal quantum circuits for general two-qubit gates"
+                self.local(1, &rz(FRAC_PI_2).matrix);
+                self.cnot(1, 0);
+                self.local(0, &rz(FRAC_PI_2 - 2.0 * c).matrix);
+                self.local(1, &ry(FRAC_PI_2 - 2.0 * a).matrix);
+                self.cnot(0, 1);
+                self.local(1, &ry(2.0 * b - FRAC_PI_2).matrix);
+                self.cnot(1, 0);
+                self.local(0, &rz(-FRAC_PI_2).matrix);
+            }
+        }
+    }
+
+    /// Applies `exp(iθ ZZ)` for `θ = ±π/4` as a CZ, written with one CNOT, and `Rz(-2θ)` on
+    /// both qubits.
+    fn controlled_z(&mut self, theta: f64) {
+        self.local(1, &hadamard(1).matrix);
+        self.cnot(0, 1);
+        self.local(1, &hadamard(1).matrix);
+        self.both(&rz(-2.0 * theta));
+    }
+
+    /// Applies `exp(i(α XX + β ZZ))`, which a CNOT on each side turns into `exp(iα X)` on the
+    /// control and `exp(iβ Z)` on the target.
+    fn two_cnots(&mut self, alpha: f64, beta: f64) {
+        self.cnot(0, 1);
+        self.local(0, &rx(-2.0 * alpha).matrix);
+        self.local(1, &rz(-2.0 * beta).matrix);
+        self.cnot(0, 1);
+    }
+
+    /// Writes out the pending single-qubit gates.
+    fn flush(&mut self) {
+        for qubit in 0..2 {
+            for operation in euler_rotations(&self.pending[qubit]) {
+                self.circuit
+                    .add_instruction(Instruction::new(operation, vec![qubit]))
+                    .expect("rotations on qubit 0 or 1 fit a two-qubit circuit");
+            }
+            self.pending[qubit] = linalg::identity(2);
+        }
+    }
+
+    fn finish(mut self) -> Circuit {
+        self.flush();
+        self.circuit
+    }
+}
@@ -0,0 +1,273 @@
+//! This module defines the `DensityMatrix` type for simulating mixed states exactly.
+//!
+//! A density matrix `ρ` on `n` qubits is a `2ⁿ × 2ⁿ` matrix indexed like the state vector, so
+//! qubit `k` is bit `k` of both the row and column index.
+
+use crate::gates::Gate;
+use crate::linalg;
+use crate::noise::KrausChannel;
+use crate::qubit::Qubit;
+use crate::stats;
+use crate::tolerance;
+use num_complex::Complex;
+
+/// A `DensityMatrix` represents a possibly mixed state of a qubit register.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DensityMatrix {
+    pub matrix: Vec<Vec<Complex<f64>>>,
+}
+
+impl DensityMatrix {
+    /// Creates the pure state `|0…0⟩⟨0…0|` on `num_qubits` qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The number of qubits in the register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::density::DensityMatrix;
+    ///
+    /// let rho = DensityMatrix::new(2);
+    /// assert_eq!(rho.num_qubits(), 2);
+    /// assert_eq!(rho.probabilities(), vec![1.0, 0.0, 0.0, 0.0]);
+    /// ```
+    pub fn new(num_qubits: usize) -> Self {
+        let mut matrix = linalg::zeros(1 << num_qubits, 1 << num_qubits);
+        matrix[0][0] = Complex::new(1.0, 0.0);
+        DensityMatrix { matrix }
+    }
+
+    /// Creates a density matrix from an explicit matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `matrix` - The matrix elements `ρᵢⱼ`.
+    pub fn from_matrix(matrix: Vec<Vec<Complex<f64>>>) -> Self {
+        DensityMatrix { matrix }
+    }
+
+    /// Creates the pure state `|ψ⟩⟨ψ|`.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The state vector `|ψ⟩`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let rho = DensityMatrix::from_state(&Qubit::new());
+    /// assert!((rho.purity() - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn from_state(qubit: &Qubit) -> Self {
+        let matrix = qubit
+            .state
+            .iter()
+            .map(|a| qubit.state.iter().map(|b| a * b.conj()).collect())
+            .collect();
+        DensityMatrix { matrix }
+    }
+
+    /// Returns the number of qubits the matrix acts on.
+    pub fn num_qubits(&self) -> usize {
+        self.matrix.len().trailing_zeros() as usize
+    }
+
+    /// Returns the trace `Tr ρ`, which is `1` for a normalized state.
+    pub fn trace(&self) -> f64 {
+        linalg::trace(&self.matrix).re
+    }
+
+    /// Applies `U ρ U†` for a gate `U` acting on `targets`.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate to apply.
+    /// * `targets` - The register qubits the gate acts on; bit `j` of the gate's local index
+    ///   corresponds to `targets[j]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::gates::pauli_x;
+    ///
+    /// let mut rho = DensityMatrix::new(2);
+    /// rho.apply_gate(&pauli_x(), &[1]);
+    /// assert_eq!(rho.probabilities(), vec![0.0, 0.0, 1.0, 0.0]);
+    /// ```
+    pub fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) {
+        let bytes = self.matrix.len().pow(2) * std::mem::size_of::<Complex<f64>>();
+        stats::record_gate(bytes, || {
+            self.matrix = conjugate(&self.matrix, &gate.matrix, targets);
+        });
+    }
+
+    /// Applies the channel `ρ ↦ Σᵢ Kᵢ ρ Kᵢ†` on `targets`.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel to apply.
+    /// * `targets` - The register qubits the channel acts on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::noise::depolarizing;
+    ///
+    /// let mut rho = DensityMatrix::new(1);
+    /// rho.apply_channel(&depolarizing(1.0), &[0]);
+    /// assert!((rho.purity() - 0.5).abs() < 1e-12);
+    /// ```
+    pub fn apply_channel(&mut self, channel: &KrausChannel, targets: &[usize]) {
+        let size = self.matrix.len();
+        self.matrix =
+            channel
+                .operators
+                .iter()
+                .fold(linalg::zeros(size, size), |mut sum, operator| {
+                    linalg::add_assign(&mut sum, &conjugate(&self.matrix, operator, targets));
+                    sum
+                });
+    }
+
+    /// Traces out every qubit not in `keep`.
+    ///
+    /// # Arguments
+    ///
+    /// * `keep` - The qubits to keep; qubit `keep[j]` becomes qubit `j` of the result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::gates::pauli_x;
+    ///
+    /// let mut rho = DensityMatrix::new(2);
+    /// rho.apply_gate(&pauli_x(), &[1]);
+    /// assert_eq!(rho.partial_trace(&[1]).probabilities(), vec![0.0, 1.0]);
+    /// ```
+    pub fn partial_trace(&self, keep: &[usize]) -> DensityMatrix {
+        let (offsets, keep_mask) = linalg::local_offsets(keep);
+        let mut matrix = linalg::zeros(offsets.len(), offsets.len());
+        for base in (0..self.matrix.len()).filter(|index| index & keep_mask == 0) {
+            for (row, row_offset) in matrix.iter_mut().zip(&offsets) {
+                for (entry, col_offset) in row.iter_mut().zip(&offsets) {
+                    *entry += self.matrix[base + row_offset][base + col_offset];
+                }
+            }
+        }
+        DensityMatrix { matrix }
+    }
+
+    /// Returns the purity `Tr ρ²`, which is `1` for pure states and `1/2ⁿ` when maximally mixed.
+    pub fn purity(&self) -> f64 {
+        self.matrix
+            .iter()
+            .flatten()
+            .map(|entry| entry.norm_sqr())
+            .sum()
+    }
+
+    /// Returns the Uhlmann fidelity `(Tr √(√ρ σ √ρ))²` between this state and `other`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The state `σ` to compare against.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two matrices have different dimensions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::gates::hadamard;
+    ///
+    /// let zero = DensityMatrix::new(1);
+    /// let mut plus = DensityMatrix::new(1);
+    /// plus.apply_gate(&hadamard(1), &[0]);
+    /// assert!((zero.fidelity(&plus) - 0.5).abs() < 1e-9);
+    /// ```
+    pub fn fidelity(&self, other: &DensityMatrix) -> f64 {
+        assert_eq!(
+            self.matrix.len(),
+            other.matrix.len(),
+            "density matrices have different dimensions"
+        );
+        let root = linalg::positive_sqrt(&self.matrix);
+        let product = linalg::matmul(&linalg::matmul(&root, &other.matrix), &root);
+        let (eigenvalues, _) = linalg::eigh(&product);
+        let root_trace: f64 = eigenvalues.iter().map(|value| value.max(0.0).sqrt()).sum();
+        root_trace * root_trace
+    }
+
+    /// Returns the von Neumann entropy `S(ρ) = -Tr(ρ log₂ ρ)` in bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use num_complex::Complex;
+    ///
+    /// assert!(DensityMatrix::new(2).von_neumann_entropy().abs() < 1e-12);
+    ///
+    /// let half = Complex::new(0.5, 0.0);
+    /// let zero = Complex::new(0.0, 0.0);
+    /// let mixed = DensityMatrix::from_matrix(vec![vec![half, zero], vec![zero, half]]);
+    /// assert!((mixed.von_neumann_entropy() - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn von_neumann_entropy(&self) -> f64 {
+        let (eigenvalues, _) = linalg::eigh(&self.matrix);
+        eigenvalues
+            .iter()
+            .filter(|&&value| value > tolerance::current().truncation)
+            .map(|value| -value * value.log2())
+            .sum()
+    }
+
+    /// Returns the probability of measuring each basis state, `ρᵢᵢ`.
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.matrix
+            .iter()
+            .enumerate()
+            .map(|(i, row)| row[i].re)
+            .collect()
+    }
+}
+
+/// Returns `A ρ A†` for a local operator `A` acting on `targets`.
+fn conjugate(
+    rho: &[Vec<Complex<f64>>],
+    operator: &[Vec<Complex<f64>>],
+    targets: &[usize],
+) -> Vec<Vec<Complex<f64>>> {
+    // Apply A to every column, then conj(A) along every row: (A ρ A†)ᵢⱼ = Σ Aᵢₖ ρₖₗ conj(Aⱼₗ).
+    let mut columns = transpose(rho);
+    for column in columns.iter_mut() {
+        linalg::apply_local(operator, column, targets);
+    }
+    let conjugated: Vec<Vec<Complex<f64>>> = operator
+        .iter()
+        .map(|row| row.iter().map(|entry| entry.conj()).collect())
+        .collect();
+    let mut rows = transpose(&columns);
+    for row in rows.iter_mut() {
+        linalg::apply_local(&conjugated, row, targets);
+    }
+    rows
+}
+
+/// Returns the transpose of a square matrix.
+fn transpose(matrix: &[Vec<Complex<f64>>]) -> Vec<Vec<Complex<f64>>> {
+    (0..matrix.len())
+        .map(|j| matrix.iter().map(|row| row[j]).collect())
+        .collect()
+}
@@ -0,0 +1,286 @@
+//! This module renders circuits as text diagrams with one wire per qubit.
+//!
+//! Gates are drawn as boxes on their qubit's wire, controls as dots joined to their target by a
+//! vertical line, measurements as boxes naming the classical bit they write, and barriers as
+//! shaded marks across their qubits. Instructions are packed into columns as soon as every wire
+//! their vertical span crosses is free, so connectors never run through another gate.
+
+use crate::circuit::{Circuit, Operation};
+use crate::parameter::ParametricGate;
+
+/// The characters a diagram is drawn with.
+pub(crate) struct Charset {
+    wire: char,
+    vertical: char,
+    cross: char,
+    control: char,
+    swap: char,
+    barrier: char,
+    top_left: char,
+    top_right: char,
+    bottom_left: char,
+    bottom_right: char,
+    box_left: char,
+    box_right: char,
+    join_up: char,
+    join_down: char,
+    arrow: &'static str,
+    ket_zero: &'static str,
+}
+
+/// Box-drawing characters, for terminals and logs that render Unicode.
+pub(crate) const UNICODE: Charset = Charset {
+    wire: '─',
+    vertical: '│',
+    cross: '┼',
+    control: '●',
+    swap: '×',
+    barrier: '░',
+    top_left: '┌',
+    top_right: '┐',
+    bottom_left: '└',
+    bottom_right: '┘',
+    box_left: '┤',
+    box_right: '├',
+    join_up: '┴',
+    join_down: '┬',
+    arrow: "→",
+    ket_zero: "|0⟩",
+};
+
+/// Plain ASCII characters, for environments without Unicode support.
+pub(crate) const ASCII: Charset = Charset {
+    wire: '-',
+    vertical: '|',
+    cross: '+',
+    control: '*',
+    swap: 'x',
+    barrier: '#',
+    top_left: '+',
+    top_right: '+',
+    bottom_left: '+',
+    bottom_right: '+',
+    box_left: '|',
+    box_right: '|',
+    join_up: '+',
+    join_down: '+',
+    arrow: "->",
+    ket_zero: "|0>",
+};
+
+/// What one qubit shows in one column.
+#[derive(Clone)]
+enum Element {
+    Wire,
+    Symbol(char),
+    Box(String),
+}
+
+/// An element together with whether a vertical connector leaves it upwards or downwards.
+#[derive(Clone)]
+struct Cell {
+    element: Element,
+    up: bool,
+    down: bool,
+}
+
+impl Cell {
+    fn width(&self) -> usize {
+        match &self.element {
+            Element::Wire | Element::Symbol(_) => 1,
+            Element::Box(label) => label.chars().count() + 4,
+        }
+    }
+
+    /// Renders the top, wire and bottom rows of the cell, centered in `width` characters.
+    fn render(&self, width: usize, charset: &Charset) -> [String; 3] {
+        let center = (width - 1) / 2;
+        let line = |fill: char, middle: Option<char>| -> String {
+            (0..width)
+                .map(|k| match middle {
+                    Some(c) if k == center => c,
+                    _ => fill,
+                })
+                .collect()
+        };
+        let vertical = |linked: bool| linked.then_some(charset.vertical);
+        match &self.element {
+            Element::Wire => [
+                line(' ', vertical(self.up)),
+                line(
+                    charset.wire,
+                    (self.up || self.down).then_some(charset.cross),
+                ),
+                line(' ', vertical(self.down)),
+            ],
+            Element::Symbol(symbol) => [
+                line(' ', vertical(self.up)),
+                line(charset.wire, Some(*symbol)),
+                line(' ', vertical(self.down)),
+            ],
+            Element::Box(label) => {
+                let inner = label.chars().count() + 2;
+                let left = center - inner.div_ceil(2);
+                let right = width - inner - 2 - left;
+                let border = |open: char, close: char, join: Option<char>| -> String {
+                    let mut row: Vec<char> = std::iter::repeat_n(' ', left)
+                        .chain([open])
+                        .chain(std::iter::repeat_n(charset.wire, inner))
+                        .chain([close])
+                        .chain(std::iter::repeat_n(' ', right))
+                        .collect();
+                    if let Some(join) = join {
+                        row[center] = join;
+                    }
+                    row.into_iter().collect()
+                };
+                let wire = |count: usize| charset.wire.to_string().repeat(count);
+                [
+                    border(
+                        charset.top_left,
+                        charset.top_right,
+                        self.up.then_some(charset.join_up),
+                    ),
+                    format!(
+                        "{}{} {} {}{}",
+                        wire(left),
+                        charset.box_left,
+                        label,
+                        charset.box_right,
+                        wire(right)
+                    ),
+                    border(
+                        charset.bottom_left,
+                        charset.bottom_right,
+                        self.down.then_some(charset.join_down),
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+/// Returns the label of a gate box, e.g. `H` or `Rz(0.50)`.
+fn label(operation: &Operation) -> String {
+    match operation {
+        Operation::Rx(theta) => format!("Rx({:.2})", theta),
+        Operation::Ry(theta) => format!("Ry({:.2})", theta),
+        Operation::Rz(theta) => format!("Rz({:.2})", theta),
+        Operation::Phase(theta) => format!("P({:.2})", theta),
+        Operation::Parametric { gate, parameter } => {
+            let name = match gate {
+                ParametricGate::Rx => "Rx",
+                ParametricGate::Ry => "Ry",
+                ParametricGate::Rz => "Rz",
+                ParametricGate::Phase => "P",
+            };
+            format!("{}({})", name, parameter)
+        }
+        Operation::Custom { name, .. } => name.clone(),
+        Operation::ISwap => "iSwap".to_string(),
+        Operation::Swap => "Swap".to_string(),
+        operation => operation.name().to_uppercase(),
+    }
+}
+
+/// Returns the element drawn on each of an operation's qubits, in argument order.
+fn elements(operation: &Operation, charset: &Charset) -> Vec<Element> {
+    let gate_box = |name: &str| Element::Box(name.to_string());
+    match operation {
+        Operation::Cnot => vec![Element::Symbol(charset.control), gate_box("X")],
+        Operation::Toffoli => vec![
+            Element::Symbol(charset.control),
+            Element::Symbol(charset.control),
+            gate_box("X"),
+        ],
+        Operation::Cz => vec![Element::Symbol(charset.control); 2],
+        Operation::Swap => vec![Element::Symbol(charset.swap); 2],
+        Operation::Measure(clbit) => {
+            vec![Element::Box(format!("M{}c{}", charset.arrow, clbit))]
+        }
+        Operation::Reset => vec![gate_box(charset.ket_zero)],
+        Operation::Barrier(num_qubits) => vec![Element::Symbol(charset.barrier); *num_qubits],
+        Operation::Conditional { clbit, operation } => {
+            let label = format!("{} if c{}", label(operation), clbit);
+            vec![Element::Box(label); operation.num_qubits()]
+        }
+        operation => vec![Element::Box(label(operation)); operation.num_qubits()],
+    }
+}
+
+/// Renders `circuit` with the given characters.
+pub(crate) fn render(circuit: &Circuit, charset: &Charset) -> String {
+    let num_qubits = circuit.num_qubits();
+    let mut columns: Vec<Vec<Cell>> = vec![];
+    let mut next_free = vec![0; num_qubits];
+    for instruction in circuit.instructions() {
+        let (Some(&low), Some(&high)) = (
+            instruction.qubits.iter().min(),
+            instruction.qubits.iter().max(),
+        ) else {
+            continue;
+        };
+        let column = next_free[low..=high].iter().copied().max().unwrap_or(0);
+        next_free[low..=high].fill(column + 1);
+        if column == columns.len() {
+            columns.push(vec![
+                Cell {
+                    element: Element::Wire,
+                    up: false,
+                    down: false,
+                };
+                num_qubits
+            ]);
+        }
+        for (qubit, cell) in columns[column]
+            .iter_mut()
+            .enumerate()
+            .take(high + 1)
+            .skip(low)
+        {
+            cell.up = qubit > low;
+            cell.down = qubit < high;
+        }
+        for (&qubit, element) in instruction
+            .qubits
+            .iter()
+            .zip(elements(&instruction.operation, charset))
+        {
+            columns[column][qubit].element = element;
+        }
+    }
+
+    let label_width = format!("q{}: ", num_qubits.saturating_sub(1)).len();
+    let mut out = String::new();
+    for qubit in 0..num_qubits {
+        let mut rows = [
+            " ".repeat(label_width + 1),
+            format!(
+                "{:<width$}{}",
+                format!("q{}:", qubit),
+                charset.wire,
+                width = label_width
+            ),
+            " ".repeat(label_width + 1),
+        ];
+        for column in &columns {
+            let width = column.iter().map(Cell::width).max().unwrap_or(1);
+            let cell = column[qubit].render(width, charset);
+            for (row, (text, separator)) in
+                rows.iter_mut()
+                    .zip(cell.iter().zip([' ', charset.wire, ' ']))
+            {
+                row.push_str(text);
+                row.push(separator);
+            }
+        }
+        for row in rows {
+            let row = row.trim_end();
+            if !row.is_empty() {
+                out.push_str(row);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,230 @@
+//! This module compares two circuits instruction by instruction.
+//!
+//! [`Circuit::diff`] aligns the instructions of both circuits along their longest common
+//! subsequence. Instructions outside it are reported as removed from the old circuit or inserted
+//! into the new one, and a removed and an inserted instruction at the same place that share their
+//! operation name or their qubits are reported as one changed instruction, e.g. a rotation whose
+//! angle an optimizer merged. The result prints as a line-per-change listing and, with the `serde`
+//! feature, serializes for tools.
+
+use crate::circuit::{Circuit, Instruction};
+use std::fmt;
+
+/// One difference between two circuits. Indices are positions in the instruction lists.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Change {
+    /// The old circuit's instruction `index` has no counterpart in the new circuit.
+    Removed {
+        index: usize,
+        instruction: Instruction,
+    },
+    /// The new circuit's instruction `index` has no counterpart in the old circuit.
+    Inserted {
+        index: usize,
+        instruction: Instruction,
+    },
+    /// The old circuit's instruction `old_index` became the new circuit's `new_index`.
+    Changed {
+        old_index: usize,
+        new_index: usize,
+        old: Instruction,
+        new: Instruction,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |instruction: &Instruction| {
+            format!("{:?} on {:?}", instruction.operation, instruction.qubits)
+        };
+        match self {
+            Change::Removed { index, instruction } => {
+                write!(f, "- #{} {}", index, describe(instruction))
+            }
+            Change::Inserted { index, instruction } => {
+                write!(f, "+ #{} {}", index, describe(instruction))
+            }
+            Change::Changed {
+                old_index,
+                new_index,
+                old,
+                new,
+            } => write!(
+                f,
+                "~ #{} {} -> #{} {}",
+                old_index,
+                describe(old),
+                new_index,
+                describe(new)
+            ),
+        }
+    }
+}
+
+/// The differences between an old and a new circuit, in instruction order.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+///
+/// let mut old = Circuit::new(2);
+/// old.h(0).rz(1, 0.25).rz(1, 0.5).cnot(0, 1);
+/// let (optimized, _) = old.optimized();
+///
+/// let diff = old.diff(&optimized);
+/// assert_eq!(diff.changes.len(), 2);
+/// assert_eq!(
+///     diff.to_string(),
+///     "~ #1 Rz(0.25) on [1] -> #1 Rz(0.75) on [1]\n- #2 Rz(0.5) on [1]"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CircuitDiff {
+    /// The number of qubits of the old and the new circuit.
+    pub num_qubits: (usize, usize),
+    /// The number of classical bits of the old and the new circuit.
+    pub num_clbits: (usize, usize),
+    /// The instruction-level differences.
+    pub changes: Vec<Change>,
+}
+
+impl CircuitDiff {
+    /// Compares `old` with `new`; see [`Circuit::diff`].
+    ///
+    /// Common leading and trailing instructions are skipped first; the rest is aligned in time
+    /// and memory proportional to the product of the two remaining lengths.
+    pub fn new(old: &Circuit, new: &Circuit) -> Self {
+        let a = old.instructions();
+        let b = new.instructions();
+        let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+        let suffix = a[prefix..]
+            .iter()
+            .rev()
+            .zip(b[prefix..].iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count();
+        let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+        // lengths[i][j] is the longest common subsequence of a_mid[i..] and b_mid[j..]
+        let mut lengths = vec![vec![0usize; b_mid.len() + 1]; a_mid.len() + 1];
+        for i in (0..a_mid.len()).rev() {
+            for j in (0..b_mid.len()).rev() {
+                lengths[i][j] = if a_mid[i] == b_mid[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let mut changes = vec![];
+        let (mut removed, mut inserted) = (vec![], vec![]);
+        let (mut i, mut j) = (0, 0);
+        while i < a_mid.len() || j < b_mid.len() {
+            if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+                flush_gap(&mut changes, &mut removed, &mut inserted);
+                i += 1;
+                j += 1;
+            } else if j == b_mid.len()
+                || (i < a_mid.len() && lengths[i + 1][j] >= lengths[i][j + 1])
+            {
+                removed.push((prefix + i, a_mid[i].clone()));
+                i += 1;
+            } else {
+                inserted.push((prefix + j, b_mid[j].clone()));
+                j += 1;
+            }
+        }
+        flush_gap(&mut changes, &mut removed, &mut inserted);
+
+        CircuitDiff {
+            num_qubits: (old.num_qubits(), new.num_qubits()),
+            num_clbits: (old.num_clbits(), new.num_clbits()),
+            changes,
+        }
+    }
+
+    /// Returns `true` if the circuits have the same registers and instructions.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+            && self.num_qubits.0 == self.num_qubits.1
+            && self.num_clbits.0 == self.num_clbits.1
+    }
+}
+
+impl fmt::Display for CircuitDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no differences");
+        }
+        let mut lines = vec![];
+        if self.num_qubits.0 != self.num_qubits.1 {
+            lines.push(format!(
+                "qubits: {} -> {}",
+                self.num_qubits.0, self.num_qubits.1
+            ));
+        }
+        if self.num_clbits.0 != self.num_clbits.1 {
+            lines.push(format!(
+                "clbits: {} -> {}",
+                self.num_clbits.0, self.num_clbits.1
+            ));
+        }
+        lines.extend(self.changes.iter().map(Change::to_string));
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Reports the instructions removed and inserted between two common ones, pairing each removal,
+/// in order, with the next insertion related to it into a change.
+fn flush_gap(
+    changes: &mut Vec<Change>,
+    removed: &mut Vec<(usize, Instruction)>,
+    inserted: &mut Vec<(usize, Instruction)>,
+) {
+    let related = |old: &Instruction, new: &Instruction| {
+        old.operation.name() == new.operation.name() || old.qubits == new.qubits
+    };
+    let mut inserted = std::mem::take(inserted).into_iter();
+    let mut pending = vec![];
+    for (old_index, old) in std::mem::take(removed) {
+        let offset = pending
+            .iter()
+            .position(|(_, new)| related(&old, new))
+            .or_else(|| {
+                // Look further ahead, keeping what is skipped for later removals
+                loop {
+                    let next = inserted.next()?;
+                    let found = related(&old, &next.1);
+                    pending.push(next);
+                    if found {
+                        return Some(pending.len() - 1);
+                    }
+                }
+            });
+        match offset {
+            Some(offset) => {
+                for (index, instruction) in pending.drain(..offset) {
+                    changes.push(Change::Inserted { index, instruction });
+                }
+                let (new_index, new) = pending.remove(0);
+                changes.push(Change::Changed {
+                    old_index,
+                    new_index,
+                    old,
+                    new,
+                });
+            }
+            None => changes.push(Change::Removed {
+                index: old_index,
+                instruction: old,
+            }),
+        }
+    }
+    for (index, instruction) in pending.into_iter().chain(inserted) {
+        changes.push(Change::Inserted { index, instruction });
+    }
+}
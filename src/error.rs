@@ -0,0 +1,133 @@
+//! This module defines the `QuantumError` type returned by fallible operations across the crate.
+
+use std::fmt;
+
+/// A `QuantumError` describes why a quantum object or operation was rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuantumError {
+    /// A matrix or vector does not have the dimension the operation requires.
+    DimensionMismatch { expected: usize, found: usize },
+    /// A dimension is not a power of two and so cannot describe a qubit register.
+    InvalidDimension { dimension: usize },
+    /// A gate matrix is not unitary; `deviation` is the largest entry of `U†U - I`.
+    NotUnitary { deviation: f64 },
+    /// A state vector is not normalized; `norm` is its Euclidean norm.
+    NotNormalized { norm: f64 },
+    /// A channel is not completely positive; `min_eigenvalue` is the most negative Choi eigenvalue.
+    NotCompletelyPositive { min_eigenvalue: f64 },
+    /// A channel is not trace preserving; `deviation` is the largest entry of `Σ Kᵢ†Kᵢ - I`.
+    NotTracePreserving { deviation: f64 },
+    /// A qubit index does not exist in a register of `num_qubits` qubits.
+    QubitOutOfRange { qubit: usize, num_qubits: usize },
+    /// The same qubit was given more than once to a multi-qubit operation.
+    DuplicateQubit { qubit: usize },
+    /// Source text could not be parsed; `line` is 1-based.
+    Parse { line: usize, message: String },
+    /// An operation or feature is not supported by the requested conversion or backend.
+    Unsupported { feature: String },
+    /// A subsystem is entangled with the rest of the register; `purity` is `Tr(ρ²)` of its
+    /// reduced state.
+    EntangledSubsystem { purity: f64 },
+    /// A recorded event log does not fit the circuit and noise model it is replayed against;
+    /// `event` is the index of the first event that does not match.
+    ReplayMismatch { event: usize },
+    /// A simulation backend could not be initialized or failed while running, e.g. because no
+    /// GPU adapter is available.
+    BackendUnavailable { reason: String },
+    /// Postselecting `qubit` on `outcome` is impossible or, in strict mode, too unlikely;
+    /// `probability` is the probability of the outcome.
+    PostselectionFailed {
+        qubit: usize,
+        outcome: usize,
+        probability: f64,
+    },
+    /// Entry `index` of a probability distribution is negative or not finite.
+    InvalidProbability { index: usize, probability: f64 },
+    /// A circuit depends on a parameter that was given no value.
+    UnboundParameter { name: String },
+    /// The assertion at instruction `instruction` of a circuit does not hold.
+    AssertionFailed { instruction: usize, message: String },
+}
+
+impl fmt::Display for QuantumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantumError::DimensionMismatch { expected, found } => {
+                write!(
+                    f,
+                    "dimension mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            QuantumError::InvalidDimension { dimension } => {
+                write!(f, "dimension {} is not a power of two", dimension)
+            }
+            QuantumError::NotUnitary { deviation } => {
+                write!(f, "matrix is not unitary (deviation {})", deviation)
+            }
+            QuantumError::NotNormalized { norm } => {
+                write!(f, "state is not normalized (norm {})", norm)
+            }
+            QuantumError::NotCompletelyPositive { min_eigenvalue } => write!(
+                f,
+                "channel is not completely positive (Choi eigenvalue {})",
+                min_eigenvalue
+            ),
+            QuantumError::NotTracePreserving { deviation } => write!(
+                f,
+                "channel is not trace preserving (deviation {})",
+                deviation
+            ),
+            QuantumError::QubitOutOfRange { qubit, num_qubits } => write!(
+                f,
+                "qubit {} is out of range for a {}-qubit register",
+                qubit, num_qubits
+            ),
+            QuantumError::DuplicateQubit { qubit } => {
+                write!(f, "qubit {} is used more than once", qubit)
+            }
+            QuantumError::Parse { line, message } => {
+                write!(f, "parse error on line {}: {}", line, message)
+            }
+            QuantumError::Unsupported { feature } => write!(f, "unsupported: {}", feature),
+            QuantumError::EntangledSubsystem { purity } => write!(
+                f,
+                "subsystem is entangled with the rest of the register (purity {})",
+                purity
+            ),
+            QuantumError::ReplayMismatch { event } => {
+                write!(
+                    f,
+                    "event {} of the log does not match the replayed circuit",
+                    event
+                )
+            }
+            QuantumError::BackendUnavailable { reason } => {
+                write!(f, "backend unavailable: {}", reason)
+            }
+            QuantumError::PostselectionFailed {
+                qubit,
+                outcome,
+                probability,
+            } => write!(
+                f,
+                "cannot postselect qubit {} on {}: probability {}",
+                qubit, outcome, probability
+            ),
+            QuantumError::InvalidProbability { index, probability } => write!(
+                f,
+                "probability {} of basis state {} is not a valid probability",
+                probability, index
+            ),
+            QuantumError::UnboundParameter { name } => {
+                write!(f, "parameter '{}' has no value", name)
+            }
+            QuantumError::AssertionFailed {
+                instruction,
+                message,
+            } => write!(f, "assertion #{} failed: {}", instruction, message),
+        }
+    }
+}
+
+impl std::error::Error for QuantumError {}
@@ -0,0 +1,137 @@
+//! This module defines the ordered event log recorded while a circuit is simulated.
+//!
+//! An [`EventLog`] lists every gate application, measurement outcome, noise realization and
+//! conditional branch in the order they happened, so a single shot can be inspected step by step
+//! after the fact.
+
+use crate::circuit::Operation;
+use std::fmt;
+
+/// An `Event` is one step of a recorded simulation.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    /// The gate of instruction `instruction` was applied to `qubits`.
+    Gate {
+        instruction: usize,
+        operation: Operation,
+        qubits: Vec<usize>,
+    },
+    /// An error channel attached to instruction `instruction` applied its Kraus operator
+    /// `operator` to `qubits`.
+    Channel {
+        instruction: usize,
+        qubits: Vec<usize>,
+        operator: usize,
+    },
+    /// Instruction `instruction` measured or reset `qubit`, observing `outcome`.
+    Measurement {
+        instruction: usize,
+        qubit: usize,
+        outcome: usize,
+    },
+    /// The conditional instruction `instruction` read classical bit `clbit` and was applied if
+    /// `taken`.
+    Branch {
+        instruction: usize,
+        clbit: usize,
+        taken: bool,
+    },
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Gate {
+                instruction,
+                operation,
+                qubits,
+            } => write!(f, "#{} gate {:?} on {:?}", instruction, operation, qubits),
+            Event::Channel {
+                instruction,
+                qubits,
+                operator,
+            } => write!(f, "#{} noise K{} on {:?}", instruction, operator, qubits),
+            Event::Measurement {
+                instruction,
+                qubit,
+                outcome,
+            } => write!(f, "#{} measure q{} = {}", instruction, qubit, outcome),
+            Event::Branch {
+                instruction,
+                clbit,
+                taken,
+            } => {
+                let branch = if *taken { "taken" } else { "skipped" };
+                write!(f, "#{} if c{} == 1: {}", instruction, clbit, branch)
+            }
+        }
+    }
+}
+
+/// An `EventLog` is the ordered list of events recorded during one simulation.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventLog {
+    events: Vec<Event>,
+}
+
+impl EventLog {
+    /// Creates an empty event log.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::events::EventLog;
+    ///
+    /// assert!(EventLog::new().is_empty());
+    /// ```
+    pub fn new() -> Self {
+        EventLog { events: vec![] }
+    }
+
+    /// Appends an event to the end of the log.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    /// use quantum_simulator::events::{Event, EventLog};
+    ///
+    /// let mut log = EventLog::new();
+    /// log.push(Event::Gate {
+    ///     instruction: 0,
+    ///     operation: Operation::H,
+    ///     qubits: vec![0],
+    /// });
+    /// assert_eq!(log.len(), 1);
+    /// assert_eq!(log.events()[0].to_string(), "#0 gate H on [0]");
+    /// ```
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Returns the recorded events in order.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Iterates over the recorded events in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    /// Returns the number of recorded events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` if no events were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
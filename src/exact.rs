@@ -0,0 +1,348 @@
+//! This module provides exact simulation of Clifford+T circuits over the ring `ℤ[i, 1/√2]`.
+//!
+//! Amplitudes are stored as `(a + bω + cω² + dω³) / √2^k` with integer coefficients and
+//! `ω = e^{iπ/4}`, so states such as `(1+i)/2` are represented without rounding. The module is
+//! available with the `exact` feature.
+
+use crate::circuit::{Circuit, Operation};
+use crate::error::QuantumError;
+use crate::linalg;
+use crate::tolerance;
+use num_complex::Complex;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// An `ExactAmplitude` is an element of `ℤ[i, 1/√2]`, kept in lowest terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ExactAmplitude {
+    coefficients: [i128; 4],
+    sqrt2_power: u32,
+}
+
+impl ExactAmplitude {
+    /// Returns `0`.
+    pub fn zero() -> Self {
+        ExactAmplitude {
+            coefficients: [0; 4],
+            sqrt2_power: 0,
+        }
+    }
+
+    /// Returns `1`.
+    pub fn one() -> Self {
+        ExactAmplitude {
+            coefficients: [1, 0, 0, 0],
+            sqrt2_power: 0,
+        }
+    }
+
+    /// Returns `ω^k / √2^j`.
+    ///
+    /// # Arguments
+    ///
+    /// * `k` - The power of `ω = e^{iπ/4}`, taken modulo 8.
+    /// * `j` - The power of `1/√2`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::exact::ExactAmplitude;
+    ///
+    /// assert_eq!(ExactAmplitude::omega_power(1, 1).to_string(), "(1+i)/2");
+    /// assert_eq!(ExactAmplitude::omega_power(4, 0).to_string(), "-1");
+    /// ```
+    pub fn omega_power(k: u32, j: u32) -> Self {
+        let k = k % 8;
+        let mut coefficients = [0; 4];
+        coefficients[(k % 4) as usize] = if k < 4 { 1 } else { -1 };
+        ExactAmplitude {
+            coefficients,
+            sqrt2_power: j,
+        }
+        .reduced()
+    }
+
+    /// Recognizes `value` as `ω^k / √2^j` for small `j`, or zero.
+    ///
+    /// These are exactly the entries of Clifford+T gate matrices.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The floating-point amplitude to recognize.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::exact::ExactAmplitude;
+    /// use num_complex::Complex;
+    ///
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// assert!(ExactAmplitude::from_complex(Complex::new(0.0, -h)).is_some());
+    /// assert!(ExactAmplitude::from_complex(Complex::new(0.3, 0.0)).is_none());
+    /// ```
+    pub fn from_complex(value: Complex<f64>) -> Option<Self> {
+        let equality = tolerance::current().equality;
+        if value.norm() < equality {
+            return Some(Self::zero());
+        }
+        let j = (0..=8)
+            .find(|&j| (value.norm() * 2.0_f64.sqrt().powi(j as i32) - 1.0).abs() < equality)?;
+        let k = (value.arg() / FRAC_PI_4).round();
+        if (value.arg() - k * FRAC_PI_4).abs() > equality {
+            return None;
+        }
+        Some(Self::omega_power(k.rem_euclid(8.0) as u32, j))
+    }
+
+    /// Returns the nearest floating-point value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::exact::ExactAmplitude;
+    ///
+    /// let value = ExactAmplitude::omega_power(2, 0).to_complex();
+    /// assert!((value.im - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn to_complex(&self) -> Complex<f64> {
+        let [a, b, c, d] = self.coefficients.map(|x| x as f64);
+        let scale = FRAC_1_SQRT_2.powi(self.sqrt2_power as i32);
+        Complex::new(a + (b - d) * FRAC_1_SQRT_2, c + (b + d) * FRAC_1_SQRT_2) * scale
+    }
+
+    /// Returns the complex conjugate.
+    pub fn conj(&self) -> Self {
+        let [a, b, c, d] = self.coefficients;
+        // ω̄ = ω⁷ = -ω³, ω̄² = -ω², ω̄³ = -ω.
+        ExactAmplitude {
+            coefficients: [a, -d, -c, -b],
+            sqrt2_power: self.sqrt2_power,
+        }
+    }
+
+    /// Returns `|self|²`, which is again an exact ring element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::exact::ExactAmplitude;
+    ///
+    /// assert_eq!(ExactAmplitude::omega_power(3, 1).norm_sqr().to_string(), "1/2");
+    /// ```
+    pub fn norm_sqr(&self) -> Self {
+        *self * self.conj()
+    }
+
+    /// Returns `true` if the value is zero.
+    pub fn is_zero(&self) -> bool {
+        self.coefficients == [0; 4]
+    }
+
+    /// Multiplies the coefficients by `√2 = ω - ω³`.
+    fn times_sqrt2([a, b, c, d]: [i128; 4]) -> [i128; 4] {
+        [b - d, a + c, b + d, c - a]
+    }
+
+    /// Divides out common factors of `√2` from the numerator and denominator.
+    fn reduced(mut self) -> Self {
+        if self.is_zero() {
+            self.sqrt2_power = 0;
+            return self;
+        }
+        while self.sqrt2_power > 0 {
+            let [a, b, c, d] = self.coefficients;
+            if (a - c) % 2 != 0 || (b - d) % 2 != 0 {
+                break;
+            }
+            self.coefficients = Self::times_sqrt2(self.coefficients).map(|x| x / 2);
+            self.sqrt2_power -= 1;
+        }
+        self
+    }
+
+    /// Returns the coefficients rescaled to a denominator of `√2^power`.
+    fn scaled_to(&self, power: u32) -> [i128; 4] {
+        (self.sqrt2_power..power).fold(self.coefficients, |x, _| Self::times_sqrt2(x))
+    }
+}
+
+impl Add for ExactAmplitude {
+    type Output = ExactAmplitude;
+
+    fn add(self, rhs: ExactAmplitude) -> ExactAmplitude {
+        let power = self.sqrt2_power.max(rhs.sqrt2_power);
+        let (x, y) = (self.scaled_to(power), rhs.scaled_to(power));
+        ExactAmplitude {
+            coefficients: [x[0] + y[0], x[1] + y[1], x[2] + y[2], x[3] + y[3]],
+            sqrt2_power: power,
+        }
+        .reduced()
+    }
+}
+
+impl Sub for ExactAmplitude {
+    type Output = ExactAmplitude;
+
+    fn sub(self, rhs: ExactAmplitude) -> ExactAmplitude {
+        self + (-rhs)
+    }
+}
+
+impl Neg for ExactAmplitude {
+    type Output = ExactAmplitude;
+
+    fn neg(self) -> ExactAmplitude {
+        ExactAmplitude {
+            coefficients: self.coefficients.map(|x| -x),
+            sqrt2_power: self.sqrt2_power,
+        }
+    }
+}
+
+impl Mul for ExactAmplitude {
+    type Output = ExactAmplitude;
+
+    fn mul(self, rhs: ExactAmplitude) -> ExactAmplitude {
+        let mut coefficients = [0; 4];
+        for (i, x) in self.coefficients.iter().enumerate() {
+            for (j, y) in rhs.coefficients.iter().enumerate() {
+                // ω⁴ = -1
+                let sign = if i + j >= 4 { -1 } else { 1 };
+                coefficients[(i + j) % 4] += sign * x * y;
+            }
+        }
+        ExactAmplitude {
+            coefficients,
+            sqrt2_power: self.sqrt2_power + rhs.sqrt2_power,
+        }
+        .reduced()
+    }
+}
+
+impl fmt::Display for ExactAmplitude {
+    /// Formats the value as `(x + y√2 + (z + w√2)i) / 2^m`, omitting zero and unit terms.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Rewrite over a power-of-two denominator: value = (re + im·i) / 2^(power/2 + 1).
+        let power = self.sqrt2_power + self.sqrt2_power % 2;
+        let [a, b, c, d] = self.scaled_to(power);
+        let mut parts = [2 * a, b - d, 2 * c, b + d];
+        let mut denominator: i128 = 1 << (power / 2 + 1);
+        while denominator > 1 && parts.iter().all(|x| x % 2 == 0) {
+            parts = parts.map(|x| x / 2);
+            denominator /= 2;
+        }
+
+        let real = surd(parts[0], parts[1]);
+        let imag = surd(parts[2], parts[3]).map(|imag| match imag.as_str() {
+            "1" => "i".to_string(),
+            "-1" => "-i".to_string(),
+            _ if parts[2] != 0 && parts[3] != 0 => format!("({})i", imag),
+            _ if parts[2] == 0 => imag.replacen('√', "i√", 1),
+            _ => format!("{}i", imag),
+        });
+        let numerator = match (real, imag) {
+            (None, None) => return write!(f, "0"),
+            (Some(real), None) => real,
+            (None, Some(imag)) => imag,
+            (Some(real), Some(imag)) if imag.starts_with('-') => format!("{}{}", real, imag),
+            (Some(real), Some(imag)) => format!("{}+{}", real, imag),
+        };
+        if denominator == 1 {
+            write!(f, "{}", numerator)
+        } else if numerator.chars().skip(1).any(|c| c == '+' || c == '-') {
+            write!(f, "({})/{}", numerator, denominator)
+        } else {
+            write!(f, "{}/{}", numerator, denominator)
+        }
+    }
+}
+
+/// Formats `x + y√2`, returning `None` when it is zero.
+fn surd(x: i128, y: i128) -> Option<String> {
+    let root = match y {
+        0 => None,
+        1 => Some("√2".to_string()),
+        -1 => Some("-√2".to_string()),
+        _ => Some(format!("{}√2", y)),
+    };
+    match (x, root) {
+        (0, root) => root,
+        (x, None) => Some(x.to_string()),
+        (x, Some(root)) if root.starts_with('-') => Some(format!("{}{}", x, root)),
+        (x, Some(root)) => Some(format!("{}+{}", x, root)),
+    }
+}
+
+/// Runs a Clifford+T circuit exactly, starting from `|0…0⟩`.
+///
+/// Any gate whose matrix entries are all of the form `ω^k / √2^j` is accepted, which covers the
+/// Clifford+T set, `T†`, `√X` and rotations by multiples of `π/2`.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to run.
+///
+/// # Returns
+///
+/// * The exact final amplitudes, or `QuantumError::Unsupported` if a gate is not exactly
+///   representable or the circuit measures or resets a qubit.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::exact::run_exact;
+///
+/// let mut circuit = Circuit::new(1);
+/// circuit.h(0).t(0);
+/// let amplitudes = run_exact(&circuit).unwrap();
+/// assert_eq!(amplitudes[0].to_string(), "√2/2");
+/// assert_eq!(amplitudes[1].to_string(), "(1+i)/2");
+/// ```
+pub fn run_exact(circuit: &Circuit) -> Result<Vec<ExactAmplitude>, QuantumError> {
+    let mut state = vec![ExactAmplitude::zero(); 1 << circuit.num_qubits()];
+    state[0] = ExactAmplitude::one();
+
+    for instruction in circuit.instructions() {
+        if let Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_) =
+            instruction.operation
+        {
+            continue;
+        }
+        if !instruction.operation.is_unitary() {
+            return Err(QuantumError::Unsupported {
+                feature: format!("{:?} in exact simulation", instruction.operation),
+            });
+        }
+        let gate = instruction.operation.gate();
+        let matrix = gate
+            .matrix
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&value| ExactAmplitude::from_complex(value))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| QuantumError::Unsupported {
+                feature: format!(
+                    "{:?} is not exactly representable over ℤ[i, 1/√2]",
+                    instruction.operation
+                ),
+            })?;
+
+        let (offsets, target_mask) = linalg::local_offsets(&instruction.qubits);
+        for base in (0..state.len()).filter(|index| index & target_mask == 0) {
+            let local: Vec<ExactAmplitude> =
+                offsets.iter().map(|offset| state[base + offset]).collect();
+            for (row, offset) in matrix.iter().zip(&offsets) {
+                state[base + offset] = row
+                    .iter()
+                    .zip(&local)
+                    .fold(ExactAmplitude::zero(), |sum, (m, a)| sum + *m * *a);
+            }
+        }
+    }
+    Ok(state)
+}
@@ -0,0 +1,89 @@
+//! This module defines how checked simulation entry points treat recoverable problems.
+//!
+//! [`Circuit::run_with_mode`](crate::circuit::Circuit::run_with_mode),
+//! [`Qubit::postselect`](crate::qubit::Qubit::postselect) and
+//! [`Simulator::try_run`](crate::simulator::Simulator::try_run) never panic on bad input; they
+//! return a [`QuantumError`] instead. Some problems can be repaired, though: a state whose norm
+//! has drifted can be rescaled, and a postselection with a tiny but non-zero probability can
+//! still be renormalized. In [`ExecutionMode::Strict`] these abort with an error; in
+//! [`ExecutionMode::Lenient`] they are repaired and reported as a [`Warning`] alongside the
+//! result.
+
+use crate::error::QuantumError;
+use crate::tolerance;
+use num_complex::Complex;
+use std::fmt;
+
+/// How checked entry points react to recoverable problems.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Abort with an error on any problem.
+    #[default]
+    Strict,
+    /// Repair recoverable problems and report them as warnings.
+    Lenient,
+}
+
+/// A recoverable problem that lenient execution repaired.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// The state had Euclidean norm `norm` and was rescaled to unit norm.
+    Renormalized { norm: f64 },
+    /// Postselecting `qubit` on `outcome` succeeded with only `probability`, so the renormalized
+    /// state is dominated by rounding error.
+    UnlikelyPostselection {
+        qubit: usize,
+        outcome: usize,
+        probability: f64,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Renormalized { norm } => {
+                write!(f, "state with norm {} was renormalized", norm)
+            }
+            Warning::UnlikelyPostselection {
+                qubit,
+                outcome,
+                probability,
+            } => write!(
+                f,
+                "postselected qubit {} on {} with probability {}",
+                qubit, outcome, probability
+            ),
+        }
+    }
+}
+
+/// The result of a checked entry point together with the warnings raised while producing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Execution<T> {
+    /// The value the entry point computed.
+    pub value: T,
+    /// The recoverable problems repaired on the way, empty in strict mode.
+    pub warnings: Vec<Warning>,
+}
+
+/// Checks that `state` has unit norm, rescaling it in lenient mode.
+///
+/// Zero and non-finite norms cannot be repaired and are errors in either mode.
+pub(crate) fn check_norm(
+    state: &mut [Complex<f64>],
+    mode: ExecutionMode,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), QuantumError> {
+    let norm = state.iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt();
+    if (norm - 1.0).abs() <= tolerance::current().normalization {
+        return Ok(());
+    }
+    if mode == ExecutionMode::Strict || !norm.is_finite() || norm == 0.0 {
+        return Err(QuantumError::NotNormalized { norm });
+    }
+    for amplitude in state.iter_mut() {
+        *amplitude /= norm;
+    }
+    warnings.push(Warning::Renormalized { norm });
+    Ok(())
+}
@@ -0,0 +1,292 @@
+//! This module bundles common entangled-state experiments with their ideal outcomes.
+//!
+//! Each [`Experiment`] pairs a circuit that prepares the state from `|0…0⟩` with the ideal
+//! measurement distribution and an analysis that scores observed [`Counts`] against it.
+
+use crate::circuit::Circuit;
+use crate::counts::{bitstring, Counts};
+use crate::qubit::Qubit;
+use crate::rng;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The result of analyzing measured counts against an experiment's ideal distribution.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExperimentAnalysis {
+    /// Total variation distance between the observed frequencies and the ideal distribution.
+    pub total_variation_distance: f64,
+    /// The experiment-specific figure of merit, ideally `1.0`.
+    pub figure_of_merit: f64,
+    /// A one-line description of the figure of merit.
+    pub description: String,
+}
+
+/// An `Experiment` is a state-preparation circuit with its ideal distribution and analysis.
+pub struct Experiment {
+    pub name: String,
+    pub circuit: Circuit,
+    /// The ideal probability of each bitstring with non-zero probability.
+    pub expected_distribution: BTreeMap<String, f64>,
+    analysis: Box<dyn Fn(&Counts) -> ExperimentAnalysis + Send + Sync>,
+}
+
+impl Experiment {
+    /// Creates a new `Experiment`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A human-readable name.
+    /// * `circuit` - The circuit preparing the state from `|0…0⟩`.
+    /// * `expected_distribution` - The ideal measurement distribution.
+    /// * `analysis` - Scores observed counts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::experiments::{Experiment, ExperimentAnalysis};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0);
+    /// let expected = BTreeMap::from([("1".to_string(), 1.0)]);
+    /// let experiment = Experiment::new("flip", circuit, expected, |counts| ExperimentAnalysis {
+    ///     total_variation_distance: 0.0,
+    ///     figure_of_merit: counts.get("1") as f64 / counts.shots() as f64,
+    ///     description: "population of |1⟩".to_string(),
+    /// });
+    /// assert_eq!(experiment.run(10).1.figure_of_merit, 1.0);
+    /// ```
+    pub fn new(
+        name: &str,
+        circuit: Circuit,
+        expected_distribution: BTreeMap<String, f64>,
+        analysis: impl Fn(&Counts) -> ExperimentAnalysis + Send + Sync + 'static,
+    ) -> Self {
+        Experiment {
+            name: name.to_string(),
+            circuit,
+            expected_distribution,
+            analysis: Box::new(analysis),
+        }
+    }
+
+    /// Analyzes observed counts with the experiment's analysis.
+    ///
+    /// # Arguments
+    ///
+    /// * `counts` - The measured histogram, e.g. from hardware or a noisy simulation.
+    pub fn analyze(&self, counts: &Counts) -> ExperimentAnalysis {
+        (self.analysis)(counts)
+    }
+
+    /// Runs the experiment on the ideal simulator and analyzes the sampled counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `shots` - The number of measurement samples to draw.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::experiments::ghz;
+    ///
+    /// let (counts, analysis) = ghz(3).run(500);
+    /// assert_eq!(counts.get("000") + counts.get("111"), 500);
+    /// assert_eq!(analysis.figure_of_merit, 1.0);
+    /// ```
+    pub fn run(&self, shots: usize) -> (Counts, ExperimentAnalysis) {
+        rng::with_rng(|rng| self.run_with_rng(shots, rng))
+    }
+
+    /// Runs the experiment like [`Experiment::run`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `shots` - The number of measurement samples to draw.
+    /// * `rng` - The random number generator to draw from.
+    pub fn run_with_rng<R: Rng + ?Sized>(
+        &self,
+        shots: usize,
+        rng: &mut R,
+    ) -> (Counts, ExperimentAnalysis) {
+        let mut qubit = zero_state(self.circuit.num_qubits());
+        self.circuit.run_with_rng(&mut qubit, rng);
+        let counts = qubit.sample_with_rng(shots, rng);
+        let analysis = self.analyze(&counts);
+        (counts, analysis)
+    }
+}
+
+impl fmt::Debug for Experiment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Experiment")
+            .field("name", &self.name)
+            .field("circuit", &self.circuit)
+            .field("expected_distribution", &self.expected_distribution)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the Bell-pair experiment `(|00⟩ + |11⟩)/√2`.
+///
+/// The figure of merit is the parity correlation `⟨Z₀Z₁⟩`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::experiments::bell_pair;
+///
+/// let experiment = bell_pair();
+/// assert_eq!(experiment.expected_distribution["00"], 0.5);
+/// let (_, analysis) = experiment.run(1000);
+/// assert_eq!(analysis.figure_of_merit, 1.0);
+/// ```
+pub fn bell_pair() -> Experiment {
+    let mut circuit = Circuit::new(2);
+    circuit.h(0).cnot(0, 1);
+    let expected = BTreeMap::from([("00".to_string(), 0.5), ("11".to_string(), 0.5)]);
+    let reference = expected.clone();
+    Experiment::new("Bell pair", circuit, expected, move |counts| {
+        let frequencies = counts.frequencies();
+        let correlation: f64 = frequencies
+            .iter()
+            .map(|(bits, p)| {
+                if bits.matches('1').count() % 2 == 0 {
+                    *p
+                } else {
+                    -*p
+                }
+            })
+            .sum();
+        ExperimentAnalysis {
+            total_variation_distance: total_variation_distance(&frequencies, &reference),
+            figure_of_merit: correlation,
+            description: "parity correlation ⟨Z₀Z₁⟩".to_string(),
+        }
+    })
+}
+
+/// Returns the `n`-qubit GHZ experiment `(|0…0⟩ + |1…1⟩)/√2`.
+///
+/// The figure of merit is the combined population of `|0…0⟩` and `|1…1⟩`.
+///
+/// # Arguments
+///
+/// * `n` - The number of qubits.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::experiments::ghz;
+///
+/// let experiment = ghz(4);
+/// assert_eq!(experiment.expected_distribution.len(), 2);
+/// assert_eq!(experiment.expected_distribution["1111"], 0.5);
+/// ```
+pub fn ghz(n: usize) -> Experiment {
+    assert!(n > 0, "a GHZ state needs at least one qubit");
+    let mut circuit = Circuit::new(n);
+    circuit.h(0);
+    for target in 1..n {
+        circuit.cnot(target - 1, target);
+    }
+    let zeros = bitstring(0, n);
+    let ones = bitstring((1 << n) - 1, n);
+    let expected = BTreeMap::from([(zeros.clone(), 0.5), (ones.clone(), 0.5)]);
+    let reference = expected.clone();
+    Experiment::new(&format!("GHZ-{}", n), circuit, expected, move |counts| {
+        let frequencies = counts.frequencies();
+        let population =
+            frequencies.get(&zeros).unwrap_or(&0.0) + frequencies.get(&ones).unwrap_or(&0.0);
+        ExperimentAnalysis {
+            total_variation_distance: total_variation_distance(&frequencies, &reference),
+            figure_of_merit: population,
+            description: "population of |0…0⟩ and |1…1⟩".to_string(),
+        }
+    })
+}
+
+/// Returns the `n`-qubit W-state experiment, an equal superposition of all single excitations.
+///
+/// The excitation starts on qubit 0 and is passed along the chain with controlled-RY rotations
+/// followed by CNOTs. The figure of merit is the total single-excitation population.
+///
+/// # Arguments
+///
+/// * `n` - The number of qubits.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::experiments::w;
+///
+/// let experiment = w(3);
+/// for bits in ["001", "010", "100"] {
+///     assert!((experiment.expected_distribution[bits] - 1.0 / 3.0).abs() < 1e-12);
+/// }
+/// let (_, analysis) = experiment.run(600);
+/// assert!((analysis.figure_of_merit - 1.0).abs() < 1e-12);
+/// ```
+pub fn w(n: usize) -> Experiment {
+    assert!(n > 0, "a W state needs at least one qubit");
+    let mut circuit = Circuit::new(n);
+    circuit.x(0);
+    for k in 0..n - 1 {
+        // Keep amplitude 1/√n on qubit k and pass the rest to qubit k + 1.
+        let theta = 2.0 * (1.0 / ((n - k) as f64).sqrt()).acos();
+        circuit
+            .ry(k + 1, theta / 2.0)
+            .cnot(k, k + 1)
+            .ry(k + 1, -theta / 2.0)
+            .cnot(k, k + 1)
+            .cnot(k + 1, k);
+    }
+    let expected: BTreeMap<String, f64> = (0..n)
+        .map(|k| (bitstring(1 << k, n), 1.0 / n as f64))
+        .collect();
+    let reference = expected.clone();
+    Experiment::new(&format!("W-{}", n), circuit, expected, move |counts| {
+        let frequencies = counts.frequencies();
+        let population: f64 = frequencies
+            .iter()
+            .filter(|(bits, _)| bits.matches('1').count() == 1)
+            .map(|(_, p)| p)
+            .sum();
+        ExperimentAnalysis {
+            total_variation_distance: total_variation_distance(&frequencies, &reference),
+            figure_of_merit: population,
+            description: "single-excitation population".to_string(),
+        }
+    })
+}
+
+/// Returns `½ Σ |p(x) - q(x)|` over the union of both supports.
+fn total_variation_distance(p: &BTreeMap<String, f64>, q: &BTreeMap<String, f64>) -> f64 {
+    let only_p: f64 = p
+        .iter()
+        .filter(|(key, _)| !q.contains_key(*key))
+        .map(|(_, value)| value)
+        .sum();
+    let shared: f64 = q
+        .iter()
+        .map(|(key, value)| (p.get(key).unwrap_or(&0.0) - value).abs())
+        .sum();
+    0.5 * (only_p + shared)
+}
+
+/// Returns `|0…0⟩` on `num_qubits` qubits.
+fn zero_state(num_qubits: usize) -> Qubit {
+    let mut qubit = Qubit::from_state(vec![Default::default(); 1 << num_qubits]);
+    qubit.state[0] = num_complex::Complex::new(1.0, 0.0);
+    qubit
+}
@@ -0,0 +1,208 @@
+//! This module explains a traced simulation step by step, for teaching.
+//!
+//! [`explain`] pairs every step of a [`Trace`] with its instruction and the state before it, and
+//! describes it as an [`Explanation`]: the gate and the qubits it acts on, the resulting state in
+//! Dirac notation and the basis states whose probabilities the step changed. The command line's
+//! `run --explain` prints them, and the visualizer shows the explanation of the current step
+//! under its gate list.
+
+use crate::circuit::{Circuit, Operation};
+use crate::simulator::Simulator;
+use crate::snapshot::Trace;
+use crate::tolerance;
+use num_complex::Complex;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Number of probability changes listed when an explanation is printed.
+const MAX_LISTED_CHANGES: usize = 8;
+
+/// The probability of one basis state before and after a step.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbabilityChange {
+    /// The basis state, qubit 0 last.
+    pub bitstring: String,
+    /// The probability before the step.
+    pub before: f64,
+    /// The probability after the step.
+    pub after: f64,
+}
+
+/// What one step of a simulation did.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    /// The index of the instruction in the circuit.
+    pub instruction: usize,
+    /// The gate with its angle, e.g. `rz(0.500)`, or the measurement, reset or condition.
+    pub gate: String,
+    /// The qubits the instruction acts on.
+    pub qubits: Vec<usize>,
+    /// The state after the step in Dirac notation.
+    pub dirac: String,
+    /// The basis states whose probability changed, in basis order.
+    pub changes: Vec<ProbabilityChange>,
+}
+
+impl Explanation {
+    /// Returns `true` if the step left every probability unchanged, e.g. a phase gate.
+    pub fn only_phases_changed(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Describes the result of the step without the gate, e.g.
+    /// `0.7071|00⟩ + 0.7071|11⟩; P(|01⟩) 0.500 → 0.000`, listing at most eight changes.
+    pub fn effect(&self) -> String {
+        if self.changes.is_empty() {
+            return format!("{}; probabilities unchanged", self.dirac);
+        }
+        let listed: Vec<String> = self
+            .changes
+            .iter()
+            .take(MAX_LISTED_CHANGES)
+            .map(|change| {
+                format!(
+                    "P(|{}⟩) {:.3} → {:.3}",
+                    change.bitstring, change.before, change.after
+                )
+            })
+            .collect();
+        let mut effect = format!("{}; {}", self.dirac, listed.join(", "));
+        if self.changes.len() > MAX_LISTED_CHANGES {
+            effect.push_str(&format!(
+                " and {} more",
+                self.changes.len() - MAX_LISTED_CHANGES
+            ));
+        }
+        effect
+    }
+
+    /// Returns the explanation as a JSON object with the fields of the struct.
+    pub fn to_json_value(&self) -> Value {
+        let changes: Vec<Value> = self
+            .changes
+            .iter()
+            .map(|change| {
+                json!({
+                    "bitstring": change.bitstring,
+                    "before": change.before,
+                    "after": change.after,
+                })
+            })
+            .collect();
+        json!({
+            "instruction": self.instruction,
+            "gate": self.gate,
+            "qubits": self.qubits,
+            "dirac": self.dirac,
+            "changes": changes,
+        })
+    }
+}
+
+impl fmt::Display for Explanation {
+    /// Writes e.g. `#1 cx on q0, q1: 0.7071|00⟩ + 0.7071|11⟩; P(|01⟩) 0.500 → 0.000, …`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let qubits: Vec<String> = self.qubits.iter().map(|q| format!("q{}", q)).collect();
+        write!(
+            f,
+            "#{} {} on {}: {}",
+            self.instruction,
+            self.gate,
+            qubits.join(", "),
+            self.effect()
+        )
+    }
+}
+
+/// Explains every step of a traced run of `circuit`, reporting probability changes larger than
+/// the [`equality`](crate::tolerance::Tolerances::equality) tolerance.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit the trace was recorded from.
+/// * `trace` - The trace, e.g. from
+///   [`Simulator::run_stepwise`](crate::simulator::Simulator::run_stepwise).
+///
+/// # Returns
+///
+/// * One explanation per step of the trace, in order.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::explain::explain;
+/// use quantum_simulator::qubit::Qubit;
+/// use quantum_simulator::simulator::Simulator;
+///
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1).z(1);
+/// let trace = Simulator::run_stepwise(&circuit, &Qubit::zero_state(2).state);
+///
+/// let explanations = explain(&circuit, &trace);
+/// assert_eq!(explanations[1].gate, "cx");
+/// assert_eq!(explanations[1].dirac, "0.7071|00⟩ + 0.7071|11⟩");
+/// assert_eq!(explanations[1].changes[0].bitstring, "01");
+/// assert!(explanations[2].only_phases_changed());
+/// assert_eq!(
+///     explanations[1].to_string(),
+///     "#1 cx on q0, q1: 0.7071|00⟩ + 0.7071|11⟩; P(|01⟩) 0.500 → 0.000, P(|11⟩) 0.000 → 0.500"
+/// );
+/// ```
+pub fn explain(circuit: &Circuit, trace: &Trace) -> Vec<Explanation> {
+    let negligible = tolerance::current().equality;
+    let num_qubits = circuit.num_qubits();
+    let mut before: Vec<f64> = trace.initial_state().iter().map(|a| a.norm_sqr()).collect();
+    trace
+        .steps()
+        .iter()
+        .map(|step| {
+            let instruction = &circuit.instructions()[step.instruction];
+            let state = step.state.to_qubit();
+            let after: Vec<f64> = state.state.iter().map(|a| a.norm_sqr()).collect();
+            let changes = before
+                .iter()
+                .zip(&after)
+                .enumerate()
+                .filter(|(_, (b, a))| (*a - *b).abs() > negligible)
+                .map(|(index, (&before, &after))| ProbabilityChange {
+                    bitstring: format!("{:0width$b}", index, width = num_qubits),
+                    before,
+                    after,
+                })
+                .collect();
+            before = after;
+            Explanation {
+                instruction: step.instruction,
+                gate: gate_label(&instruction.operation),
+                qubits: instruction.qubits.clone(),
+                dirac: state.to_dirac(),
+                changes,
+            }
+        })
+        .collect()
+}
+
+/// Runs `circuit` stepwise from `initial_state` and explains every step; see [`explain`].
+pub fn explain_run(circuit: &Circuit, initial_state: &[Complex<f64>]) -> Vec<Explanation> {
+    explain(circuit, &Simulator::run_stepwise(circuit, initial_state))
+}
+
+/// Returns the name of an operation with its angle or classical bit, e.g. `rz(0.500)` or
+/// `m → c0`.
+fn gate_label(operation: &Operation) -> String {
+    match operation {
+        Operation::Rx(theta)
+        | Operation::Ry(theta)
+        | Operation::Rz(theta)
+        | Operation::Phase(theta) => format!("{}({:.3})", operation.name(), theta),
+        Operation::Parametric { gate, parameter } => format!("{}({})", gate.name(), parameter),
+        Operation::Measure(clbit) => format!("m → c{}", clbit),
+        Operation::Conditional { clbit, operation } => {
+            format!("{} if c{}", gate_label(operation), clbit)
+        }
+        operation => operation.name(),
+    }
+}
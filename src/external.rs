@@ -0,0 +1,796 @@
+//! This module lets circuits run on simulators and hardware outside the crate, so the crate can
+//! act as a uniform frontend to them.
+//!
+//! An [`ExternalBackend`] takes a whole circuit and a number of shots and returns measurement
+//! [`Counts`], unlike a [`Backend`](crate::backend::Backend), which exposes gate-by-gate
+//! primitives. Two adapters serialize the circuit to OpenQASM and hand it over:
+//! [`CommandBackend`] runs a program with the source on its standard input, and [`HttpBackend`]
+//! posts it to an HTTP endpoint. Both read back a JSON histogram and normalize it with
+//! [`parse_counts`].
+
+use crate::circuit::Circuit;
+use crate::counts::Counts;
+use crate::error::QuantumError;
+use crate::qasm::{self, QasmVersion};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The placeholder in a [`CommandBackend`] argument that is replaced with the number of shots.
+pub const SHOTS_PLACEHOLDER: &str = "{shots}";
+
+/// How long an [`HttpBackend`] waits for the provider by default.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An `ExternalBackend` runs whole circuits somewhere else, e.g. in another simulator or on a
+/// hardware provider, and reports the measured outcomes.
+pub trait ExternalBackend {
+    /// Returns a short human-readable name for the backend, e.g. for logging.
+    fn name(&self) -> &str;
+
+    /// Runs `circuit` `shots` times and returns the outcomes.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to run. If it has no classical register, every qubit is measured
+    ///   at the end, like [`Simulator::sample`](crate::simulator::Simulator::sample) does.
+    /// * `shots` - The number of repetitions.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Counts)` - The histogram of classical registers, as bitstrings with the highest bit
+    ///   first.
+    /// * `Err(QuantumError)` - If the circuit cannot be serialized, the backend cannot be
+    ///   reached or fails (`BackendUnavailable`), or its reply cannot be parsed (`Parse`).
+    fn submit(&self, circuit: &Circuit, shots: usize) -> Result<Counts, QuantumError>;
+
+    /// Starts running `circuit` without waiting for a queued job to finish, see
+    /// [`ExternalBackend::poll`]. The default runs it with [`ExternalBackend::submit`].
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to run, measured like in [`ExternalBackend::submit`].
+    /// * `shots` - The number of repetitions.
+    fn start(&self, circuit: &Circuit, shots: usize) -> Result<Job, QuantumError> {
+        self.submit(circuit, shots).map(Job::Done)
+    }
+
+    /// Checks on a job returned by [`ExternalBackend::start`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Job)` - The job, still pending or finished.
+    /// * `Err(QuantumError::BackendUnavailable)` - If the job failed or the provider cannot be
+    ///   reached.
+    /// * `Err(QuantumError::Unsupported)` - The default, for backends that never return pending
+    ///   jobs.
+    fn poll(&self, job: &PendingJob) -> Result<Job, QuantumError> {
+        Err(QuantumError::Unsupported {
+            feature: format!("polling job {} on {}", job.id, self.name()),
+        })
+    }
+}
+
+/// A job accepted by an external provider that has not finished yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingJob {
+    /// The provider's identifier of the job.
+    pub id: String,
+    /// The number of classical bits of the outcomes.
+    pub width: usize,
+}
+
+/// The state of a job started with [`ExternalBackend::start`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Job {
+    Pending(PendingJob),
+    Done(Counts),
+}
+
+/// How to treat a provider: how often to retry failures, how fast to submit and how long to wait
+/// for queued jobs.
+///
+/// Failures reported as [`QuantumError::BackendUnavailable`], e.g. network errors, HTTP errors or
+/// failed and timed-out jobs, are retried after an exponentially growing backoff; any other error
+/// is returned at once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProviderProfile {
+    /// The number of times a failed job is resubmitted.
+    pub max_retries: usize,
+    /// The wait before the first retry, doubled for every further one.
+    pub initial_backoff: Duration,
+    /// The longest wait between retries.
+    pub max_backoff: Duration,
+    /// The shortest time between two submissions, to respect the provider's rate limit.
+    pub min_interval: Duration,
+    /// The time between two checks on a pending job.
+    pub poll_interval: Duration,
+    /// How long one attempt may wait for a pending job before it counts as failed.
+    pub timeout: Duration,
+}
+
+impl Default for ProviderProfile {
+    /// Retries 3 times after 1, 2 and 4 seconds, submits without a rate limit and polls every
+    /// second for up to 5 minutes.
+    fn default() -> Self {
+        ProviderProfile {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            min_interval: Duration::ZERO,
+            poll_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl ProviderProfile {
+    /// Parses a TOML table of named profiles, where every key is optional and durations are in
+    /// seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The profiles, one `[name]` section per provider.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HashMap<String, ProviderProfile>)` - The profiles by name, with defaults for missing
+    ///   keys.
+    /// * `Err(QuantumError::Parse)` - If the TOML is malformed, a key is unknown, `max_retries` is
+    ///   not a non-negative integer or a duration is negative, not finite or too long for a
+    ///   [`Duration`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::external::ProviderProfile;
+    /// use std::time::Duration;
+    ///
+    /// let profiles = ProviderProfile::parse_profiles(
+    ///     "[cloud]\nmax_retries = 5\nmin_interval = 0.5\n\n[local]\ntimeout = 10",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(profiles["cloud"].max_retries, 5);
+    /// assert_eq!(profiles["cloud"].min_interval, Duration::from_millis(500));
+    /// assert_eq!(profiles["local"].timeout, Duration::from_secs(10));
+    /// assert!(ProviderProfile::parse_profiles("[cloud]\nretries = 5").is_err());
+    /// assert!(ProviderProfile::parse_profiles("[cloud]\nmax_retries = 2.5").is_err());
+    /// assert!(ProviderProfile::parse_profiles("[cloud]\ntimeout = 1e30").is_err());
+    /// ```
+    pub fn parse_profiles(source: &str) -> Result<HashMap<String, ProviderProfile>, QuantumError> {
+        let table: toml::Table =
+            source
+                .parse()
+                .map_err(|error: toml::de::Error| QuantumError::Parse {
+                    line: error
+                        .span()
+                        .map_or(0, |span| source[..span.start].lines().count().max(1)),
+                    message: error.message().to_string(),
+                })?;
+        let invalid = |name: &str, message: String| QuantumError::Parse {
+            line: 0,
+            message: format!("profile '{}': {}", name, message),
+        };
+
+        let mut profiles = HashMap::new();
+        for (name, entry) in &table {
+            let entry = entry
+                .as_table()
+                .ok_or_else(|| invalid(name, "expected a table".to_string()))?;
+            let mut profile = ProviderProfile::default();
+            for (key, value) in entry {
+                if key == "max_retries" {
+                    profile.max_retries = value
+                        .as_integer()
+                        .and_then(|retries| usize::try_from(retries).ok())
+                        .ok_or_else(|| {
+                            invalid(name, "'max_retries' must be a non-negative integer".into())
+                        })?;
+                    continue;
+                }
+                let seconds = value
+                    .as_float()
+                    .or_else(|| value.as_integer().map(|value| value as f64))
+                    .and_then(|number| Duration::try_from_secs_f64(number).ok())
+                    .ok_or_else(|| {
+                        invalid(name, format!("'{}' must be a non-negative duration", key))
+                    })?;
+                match key.as_str() {
+                    "initial_backoff" => profile.initial_backoff = seconds,
+                    "max_backoff" => profile.max_backoff = seconds,
+                    "min_interval" => profile.min_interval = seconds,
+                    "poll_interval" => profile.poll_interval = seconds,
+                    "timeout" => profile.timeout = seconds,
+                    _ => return Err(invalid(name, format!("unknown key '{}'", key))),
+                }
+            }
+            profiles.insert(name.clone(), profile);
+        }
+        Ok(profiles)
+    }
+
+    /// Returns the wait before retry number `retry`, counting from `0`.
+    fn backoff(&self, retry: usize) -> Duration {
+        let factor = 2u32.saturating_pow(retry.min(u32::MAX as usize) as u32);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Polls a pending job until it finishes, fails or exceeds the profile's timeout.
+fn wait<B: ExternalBackend + ?Sized>(
+    backend: &B,
+    mut job: PendingJob,
+    profile: &ProviderProfile,
+) -> Result<Counts, QuantumError> {
+    let deadline = Instant::now() + profile.timeout;
+    loop {
+        if Instant::now() >= deadline {
+            return Err(QuantumError::BackendUnavailable {
+                reason: format!(
+                    "{}: job {} did not finish within {:?}",
+                    backend.name(),
+                    job.id,
+                    profile.timeout
+                ),
+            });
+        }
+        thread::sleep(profile.poll_interval);
+        match backend.poll(&job)? {
+            Job::Done(counts) => return Ok(counts),
+            Job::Pending(pending) => job = pending,
+        }
+    }
+}
+
+/// A `JobQueue` submits circuits to an [`ExternalBackend`] one after another, following a
+/// [`ProviderProfile`]: it spaces submissions by the profile's minimum interval, polls queued jobs
+/// and retries failed ones with exponential backoff.
+pub struct JobQueue<B: ExternalBackend> {
+    backend: B,
+    profile: ProviderProfile,
+    jobs: VecDeque<(Circuit, usize)>,
+    last_submission: Option<Instant>,
+}
+
+impl<B: ExternalBackend> JobQueue<B> {
+    /// Creates an empty queue for `backend`.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The backend to submit to.
+    /// * `profile` - The provider's retry, rate-limit and polling settings.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::external::{HttpBackend, JobQueue, ProviderProfile};
+    /// use std::time::Duration;
+    ///
+    /// let profile = ProviderProfile {
+    ///     min_interval: Duration::from_secs(2),
+    ///     ..ProviderProfile::default()
+    /// };
+    /// let mut queue = JobQueue::new(HttpBackend::new("http://localhost:8080/jobs").unwrap(), profile);
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// queue.push(circuit.clone(), 1000).push(circuit, 4000);
+    /// for counts in queue.run() {
+    ///     println!("{:?}", counts.unwrap().most_frequent());
+    /// }
+    /// ```
+    pub fn new(backend: B, profile: ProviderProfile) -> Self {
+        JobQueue {
+            backend,
+            profile,
+            jobs: VecDeque::new(),
+            last_submission: None,
+        }
+    }
+
+    /// Returns the backend jobs are submitted to.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Appends a job to the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to run.
+    /// * `shots` - The number of repetitions.
+    pub fn push(&mut self, circuit: Circuit, shots: usize) -> &mut Self {
+        self.jobs.push_back((circuit, shots));
+        self
+    }
+
+    /// Returns the number of jobs waiting to be submitted.
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Returns `true` if no jobs are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Submits every queued job in order, waiting for each to finish, and empties the queue.
+    ///
+    /// # Returns
+    ///
+    /// * The outcome of every job, in queue order. A job that still fails after the profile's
+    ///   retries returns its last error.
+    pub fn run(&mut self) -> Vec<Result<Counts, QuantumError>> {
+        let mut results = vec![];
+        while let Some((circuit, shots)) = self.jobs.pop_front() {
+            results.push(self.run_job(&circuit, shots));
+        }
+        results
+    }
+
+    /// Runs one job, retrying transient failures.
+    fn run_job(&mut self, circuit: &Circuit, shots: usize) -> Result<Counts, QuantumError> {
+        let mut retry = 0;
+        loop {
+            if let Some(last) = self.last_submission {
+                thread::sleep(self.profile.min_interval.saturating_sub(last.elapsed()));
+            }
+            self.last_submission = Some(Instant::now());
+            let result = self
+                .backend
+                .start(circuit, shots)
+                .and_then(|job| match job {
+                    Job::Done(counts) => Ok(counts),
+                    Job::Pending(job) => wait(&self.backend, job, &self.profile),
+                });
+            match result {
+                Err(QuantumError::BackendUnavailable { .. })
+                    if retry < self.profile.max_retries =>
+                {
+                    thread::sleep(self.profile.backoff(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// An [`ExternalBackend`] that runs a program for every submission, writing the circuit's
+/// OpenQASM source to its standard input and reading a JSON histogram from its standard output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandBackend {
+    program: String,
+    args: Vec<String>,
+    version: QasmVersion,
+}
+
+impl CommandBackend {
+    /// Creates a backend that runs `program` with no arguments and sends it OpenQASM 2.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The program to run, looked up in `PATH` if it is not a path.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::external::{CommandBackend, ExternalBackend};
+    ///
+    /// let backend = CommandBackend::new("python3").arg("run_qiskit.py").arg("--shots={shots}");
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// let counts = backend.submit(&circuit, 1000).unwrap();
+    /// assert_eq!(counts.shots(), 1000);
+    /// ```
+    pub fn new(program: &str) -> Self {
+        CommandBackend {
+            program: program.to_string(),
+            args: vec![],
+            version: QasmVersion::V2,
+        }
+    }
+
+    /// Appends an argument, in which every [`SHOTS_PLACEHOLDER`] is replaced with the number of
+    /// shots of the submission.
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Sets the OpenQASM version the circuit is sent as.
+    pub fn qasm_version(mut self, version: QasmVersion) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+impl ExternalBackend for CommandBackend {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    fn submit(&self, circuit: &Circuit, shots: usize) -> Result<Counts, QuantumError> {
+        let (source, width) = prepare(circuit, self.version)?;
+        let unavailable = |reason: String| QuantumError::BackendUnavailable {
+            reason: format!("{}: {}", self.program, reason),
+        };
+
+        let mut child = Command::new(&self.program)
+            .args(
+                self.args
+                    .iter()
+                    .map(|arg| arg.replace(SHOTS_PLACEHOLDER, &shots.to_string())),
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| unavailable(error.to_string()))?;
+        // The source is written on its own thread while the output is read here, so a program
+        // that writes before it has read all of its input cannot fill both pipes and deadlock. A
+        // program that exits without reading its input closes the pipe, which surfaces below as
+        // its exit status
+        let writer = child.stdin.take().map(|mut stdin| {
+            thread::spawn(move || {
+                let _ = stdin.write_all(source.as_bytes());
+            })
+        });
+        let output = child
+            .wait_with_output()
+            .map_err(|error| unavailable(error.to_string()))?;
+        if let Some(writer) = writer {
+            let _ = writer.join();
+        }
+        if !output.status.success() {
+            return Err(unavailable(format!(
+                "{}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        parse_counts(&String::from_utf8_lossy(&output.stdout), width)
+    }
+}
+
+/// An [`ExternalBackend`] that posts every submission as `{"qasm": "…", "shots": n}` to an HTTP
+/// endpoint and reads a JSON histogram from the response body.
+///
+/// Only plain `http://` URLs are supported; reach TLS endpoints through a local proxy or a
+/// [`CommandBackend`] wrapping an HTTP client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpBackend {
+    authority: String,
+    host: String,
+    port: u16,
+    path: String,
+    headers: Vec<(String, String)>,
+    version: QasmVersion,
+    timeout: Duration,
+}
+
+impl HttpBackend {
+    /// Creates a backend posting to `url`, sending OpenQASM 2.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The endpoint, e.g. `http://localhost:8080/run`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HttpBackend)` - The backend.
+    /// * `Err(QuantumError::Unsupported)` - If the URL does not start with `http://`.
+    /// * `Err(QuantumError::Parse)` - If the host or port is missing or invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::external::{ExternalBackend, HttpBackend};
+    ///
+    /// let backend = HttpBackend::new("http://localhost:8080/run")
+    ///     .unwrap()
+    ///     .header("Authorization", "Bearer token");
+    /// assert_eq!(backend.name(), "localhost:8080");
+    /// assert!(HttpBackend::new("https://example.com").is_err());
+    /// ```
+    pub fn new(url: &str) -> Result<Self, QuantumError> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| QuantumError::Unsupported {
+                feature: format!("URL scheme of '{}', only http:// is supported", url),
+            })?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        let invalid = |message: &str| QuantumError::Parse {
+            line: 1,
+            message: format!("{} in URL '{}'", message, url),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid("invalid port"))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        Ok(HttpBackend {
+            authority: authority.to_string(),
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            headers: vec![],
+            version: QasmVersion::V2,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Adds a header sent with every request, e.g. an API token.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the OpenQASM version the circuit is sent as.
+    pub fn qasm_version(mut self, version: QasmVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets how long to wait for the connection and for each read or write, 60 seconds by
+    /// default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends a request with an optional JSON body to `path` and returns the body of a successful
+    /// response.
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+    ) -> Result<String, QuantumError> {
+        let unavailable = |reason: String| QuantumError::BackendUnavailable {
+            reason: format!("{}: {}", self.name(), reason),
+        };
+        let io_error = |error: std::io::Error| unavailable(error.to_string());
+
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(io_error)?
+            .next()
+            .ok_or_else(|| unavailable("host has no address".to_string()))?;
+        let mut stream = TcpStream::connect_timeout(&address, self.timeout).map_err(io_error)?;
+        stream
+            .set_read_timeout(Some(self.timeout))
+            .map_err(io_error)?;
+        stream
+            .set_write_timeout(Some(self.timeout))
+            .map_err(io_error)?;
+
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n",
+            method, path, self.authority
+        );
+        if let Some(body) = body {
+            request.push_str(&format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\n",
+                body.len()
+            ));
+        }
+        for (name, value) in &self.headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        request.push_str(body.unwrap_or_default());
+        stream.write_all(request.as_bytes()).map_err(io_error)?;
+
+        let mut response = vec![];
+        stream.read_to_end(&mut response).map_err(io_error)?;
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| unavailable("malformed HTTP response".to_string()))?;
+        let mut lines = head.lines();
+        let status = lines.next().unwrap_or_default();
+        if !status
+            .split_whitespace()
+            .nth(1)
+            .is_some_and(|code| code.starts_with('2'))
+        {
+            return Err(unavailable(format!("{}: {}", status, body.trim())));
+        }
+        let chunked = lines.any(|line| {
+            line.split_once(':').is_some_and(|(name, value)| {
+                name.eq_ignore_ascii_case("transfer-encoding")
+                    && value.trim().eq_ignore_ascii_case("chunked")
+            })
+        });
+        if chunked {
+            dechunk(body).ok_or_else(|| unavailable("malformed chunked body".to_string()))
+        } else {
+            Ok(body.to_string())
+        }
+    }
+}
+
+impl ExternalBackend for HttpBackend {
+    fn name(&self) -> &str {
+        &self.authority
+    }
+
+    /// Posts the circuit and, if the provider answers with a job ID instead of counts, polls it
+    /// with the default [`ProviderProfile`]'s interval and timeout.
+    fn submit(&self, circuit: &Circuit, shots: usize) -> Result<Counts, QuantumError> {
+        match self.start(circuit, shots)? {
+            Job::Done(counts) => Ok(counts),
+            Job::Pending(job) => wait(self, job, &ProviderProfile::default()),
+        }
+    }
+
+    fn start(&self, circuit: &Circuit, shots: usize) -> Result<Job, QuantumError> {
+        let (source, width) = prepare(circuit, self.version)?;
+        let body = serde_json::json!({ "qasm": source, "shots": shots }).to_string();
+        let response = self.request("POST", &self.path, Some(&body))?;
+        parse_job(self.name(), &response, None, width)
+    }
+
+    /// Fetches the job's status from `GET <path>/<id>`.
+    fn poll(&self, job: &PendingJob) -> Result<Job, QuantumError> {
+        let path = format!("{}/{}", self.path.trim_end_matches('/'), job.id);
+        let response = self.request("GET", &path, None)?;
+        parse_job(self.name(), &response, Some(&job.id), job.width)
+    }
+}
+
+/// Parses a provider's reply into a finished or pending job.
+///
+/// A reply with a `status` of `queued`, `pending`, `running` or `submitted`, or with a `job_id`
+/// or `id` but no counts, is pending; any other status except `done`, `completed` or `succeeded`
+/// is a failed job, reported with the reply's `message` or `error`.
+fn parse_job(
+    backend: &str,
+    response: &str,
+    id: Option<&str>,
+    width: usize,
+) -> Result<Job, QuantumError> {
+    let root: Value =
+        serde_json::from_str(response.trim()).map_err(|error| QuantumError::Parse {
+            line: error.line(),
+            message: error.to_string(),
+        })?;
+    let job_id = ["job_id", "id"]
+        .iter()
+        .find_map(|key| root[key].as_str())
+        .or(id)
+        .map(str::to_string);
+    let pending = |id: Option<String>| {
+        id.map(|id| Job::Pending(PendingJob { id, width }))
+            .ok_or_else(|| QuantumError::Parse {
+                line: 1,
+                message: "pending job without a 'job_id'".to_string(),
+            })
+    };
+    match root["status"]
+        .as_str()
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("queued" | "pending" | "running" | "submitted") => pending(job_id),
+        Some("done" | "completed" | "succeeded") | None if !root["counts"].is_null() => {
+            parse_counts(response, width).map(Job::Done)
+        }
+        None if job_id.is_some() => pending(job_id),
+        None => parse_counts(response, width).map(Job::Done),
+        Some(status) => Err(QuantumError::BackendUnavailable {
+            reason: format!(
+                "{}: job {} {}: {}",
+                backend,
+                job_id.unwrap_or_default(),
+                status,
+                root["message"]
+                    .as_str()
+                    .or(root["error"].as_str())
+                    .unwrap_or("no details")
+            ),
+        }),
+    }
+}
+
+/// Serializes the circuit, measuring every qubit first if it has no classical register, and
+/// returns the source with the width of the classical register.
+fn prepare(circuit: &Circuit, version: QasmVersion) -> Result<(String, usize), QuantumError> {
+    if circuit.num_clbits() > 0 {
+        return Ok((qasm::to_qasm(circuit, version)?, circuit.num_clbits()));
+    }
+    let mut measured = circuit.clone();
+    for qubit in 0..circuit.num_qubits() {
+        measured.measure(qubit, qubit);
+    }
+    Ok((qasm::to_qasm(&measured, version)?, circuit.num_qubits()))
+}
+
+/// Decodes a `Transfer-Encoding: chunked` body.
+fn dechunk(mut body: &str) -> Option<String> {
+    let mut decoded = String::new();
+    loop {
+        let (size, rest) = body.split_once("\r\n")?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.push_str(rest.get(..size)?);
+        body = rest.get(size..)?.strip_prefix("\r\n")?;
+    }
+}
+
+/// Parses a JSON histogram returned by an external backend into `Counts`.
+///
+/// The histogram is an object mapping outcomes to counts, either at the top level or under a
+/// `"counts"` key. Outcomes are bitstrings with the highest bit first, optionally split into
+/// registers by spaces as Qiskit prints them, or hexadecimal numbers prefixed with `0x`.
+///
+/// # Arguments
+///
+/// * `json` - The histogram.
+/// * `width` - The number of classical bits; shorter outcomes are padded with leading zeros.
+///
+/// # Returns
+///
+/// * `Ok(Counts)` - The normalized histogram.
+/// * `Err(QuantumError::Parse)` - If the JSON is malformed, a count is not a non-negative integer,
+///   or an outcome is not a number that fits in `width` bits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::external::parse_counts;
+///
+/// let counts = parse_counts(r#"{"counts": {"1 01": 3, "0x0": 5, "10": 2}}"#, 3).unwrap();
+/// assert_eq!(counts.get("101"), 3);
+/// assert_eq!(counts.get("000"), 5);
+/// assert_eq!(counts.get("010"), 2);
+/// assert_eq!(counts.shots(), 10);
+/// assert!(parse_counts(r#"{"1111": 1}"#, 3).is_err());
+/// ```
+pub fn parse_counts(json: &str, width: usize) -> Result<Counts, QuantumError> {
+    let invalid = |message: String| QuantumError::Parse { line: 1, message };
+    let root: Value = serde_json::from_str(json.trim()).map_err(|error| QuantumError::Parse {
+        line: error.line(),
+        message: error.to_string(),
+    })?;
+    let histogram = root
+        .get("counts")
+        .unwrap_or(&root)
+        .as_object()
+        .ok_or_else(|| invalid("expected an object of counts".to_string()))?;
+
+    let mut counts = Counts::new();
+    for (outcome, count) in histogram {
+        let count = count
+            .as_u64()
+            .ok_or_else(|| invalid(format!("count of '{}' is not an integer", outcome)))?;
+        let index = match outcome.strip_prefix("0x") {
+            Some(hex) => usize::from_str_radix(hex, 16).ok(),
+            None => {
+                let bits: String = outcome.split_whitespace().collect();
+                usize::from_str_radix(&bits, 2)
+                    .ok()
+                    .filter(|_| bits.len() <= width)
+            }
+        }
+        .filter(|&index| width >= usize::BITS as usize || index >> width == 0)
+        .ok_or_else(|| invalid(format!("outcome '{}' does not fit {} bits", outcome, width)))?;
+        counts.record_many(index, width, count as usize);
+    }
+    Ok(counts)
+}
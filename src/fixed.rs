@@ -0,0 +1,287 @@
+//! This module holds a fixed-size state vector simulator for small registers.
+//!
+//! [`FixedRegister`] stores its amplitudes inline in an array sized by a const generic, so it
+//! never allocates, and draws measurement randomness from any [`RngCore`] instead of the
+//! thread-local generator, so seeded measurements make tests deterministic. A `no_std` build is
+//! out of scope: the crate depends on `std`, including for the `f64` square roots and
+//! trigonometric functions used here.
+
+use crate::qubit::Qubit;
+use num_complex::Complex;
+use rand::RngCore;
+use std::f64::consts::FRAC_1_SQRT_2;
+
+/// The largest register a [`FixedRegister`] holds.
+pub const MAX_QUBITS: usize = 10;
+
+/// A single-qubit gate as a row-major 2x2 matrix.
+pub type Matrix2 = [[Complex<f64>; 2]; 2];
+
+const ZERO: Complex<f64> = Complex::new(0.0, 0.0);
+const ONE: Complex<f64> = Complex::new(1.0, 0.0);
+
+/// The Pauli-X gate.
+pub const X: Matrix2 = [[ZERO, ONE], [ONE, ZERO]];
+/// The Pauli-Y gate.
+pub const Y: Matrix2 = [
+    [ZERO, Complex::new(0.0, -1.0)],
+    [Complex::new(0.0, 1.0), ZERO],
+];
+/// The Pauli-Z gate.
+pub const Z: Matrix2 = [[ONE, ZERO], [ZERO, Complex::new(-1.0, 0.0)]];
+/// The Hadamard gate.
+pub const H: Matrix2 = [
+    [
+        Complex::new(FRAC_1_SQRT_2, 0.0),
+        Complex::new(FRAC_1_SQRT_2, 0.0),
+    ],
+    [
+        Complex::new(FRAC_1_SQRT_2, 0.0),
+        Complex::new(-FRAC_1_SQRT_2, 0.0),
+    ],
+];
+/// The phase gate `S`.
+pub const S: Matrix2 = [[ONE, ZERO], [ZERO, Complex::new(0.0, 1.0)]];
+/// The `T` gate.
+pub const T: Matrix2 = [
+    [ONE, ZERO],
+    [ZERO, Complex::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2)],
+];
+
+/// Returns the rotation about the X axis by `theta`.
+pub fn rx(theta: f64) -> Matrix2 {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    [
+        [Complex::new(cos, 0.0), Complex::new(0.0, -sin)],
+        [Complex::new(0.0, -sin), Complex::new(cos, 0.0)],
+    ]
+}
+
+/// Returns the rotation about the Y axis by `theta`.
+pub fn ry(theta: f64) -> Matrix2 {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    [
+        [Complex::new(cos, 0.0), Complex::new(-sin, 0.0)],
+        [Complex::new(sin, 0.0), Complex::new(cos, 0.0)],
+    ]
+}
+
+/// Returns the rotation about the Z axis by `theta`.
+pub fn rz(theta: f64) -> Matrix2 {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    [
+        [Complex::new(cos, -sin), ZERO],
+        [ZERO, Complex::new(cos, sin)],
+    ]
+}
+
+/// Returns the phase gate `diag(1, e^{iθ})`.
+pub fn phase(theta: f64) -> Matrix2 {
+    let (sin, cos) = theta.sin_cos();
+    [[ONE, ZERO], [ZERO, Complex::new(cos, sin)]]
+}
+
+/// A register of up to [`MAX_QUBITS`] qubits whose `DIM = 2^n` amplitudes live in an array.
+///
+/// Qubit `k` is bit `k` of the basis state index, as in [`Qubit`]. A `DIM` that is not `2^n`
+/// for `1 <= n <= MAX_QUBITS` is rejected when the register type is instantiated.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::fixed::{FixedRegister, H};
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+///
+/// let mut register = FixedRegister::<4>::new();
+/// register.apply(&H, 0);
+/// register.cnot(0, 1);
+/// assert!((register.probability(0b11) - 0.5).abs() < 1e-12);
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let first = register.measure_qubit_with_rng(0, &mut rng);
+/// assert_eq!(register.measure_qubit_with_rng(1, &mut rng), first);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedRegister<const DIM: usize> {
+    amplitudes: [Complex<f64>; DIM],
+}
+
+impl<const DIM: usize> FixedRegister<DIM> {
+    const VALID: () = assert!(
+        DIM.is_power_of_two() && DIM >= 2 && DIM <= 1 << MAX_QUBITS,
+        "a fixed register's dimension must be 2^n for 1 <= n <= MAX_QUBITS"
+    );
+
+    /// Creates a register in the all-zero state.
+    pub fn new() -> Self {
+        Self::basis_state(0)
+    }
+
+    /// Creates a register in the computational basis state `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= DIM`.
+    pub fn basis_state(index: usize) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+        assert!(
+            index < DIM,
+            "basis state {index} out of range for dimension {DIM}"
+        );
+        let mut amplitudes = [ZERO; DIM];
+        amplitudes[index] = ONE;
+        Self { amplitudes }
+    }
+
+    /// Returns the number of qubits in the register.
+    pub fn num_qubits(&self) -> usize {
+        DIM.trailing_zeros() as usize
+    }
+
+    /// Returns the amplitudes of the register.
+    pub fn amplitudes(&self) -> &[Complex<f64>; DIM] {
+        &self.amplitudes
+    }
+
+    /// Returns the probability of measuring the basis state `index`.
+    pub fn probability(&self, index: usize) -> f64 {
+        self.amplitudes[index].norm_sqr()
+    }
+
+    /// Applies a single-qubit gate to `target`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` is not a qubit of the register.
+    pub fn apply(&mut self, gate: &Matrix2, target: usize) {
+        self.apply_controlled_mask(gate, 0, target);
+    }
+
+    /// Applies a single-qubit gate to `target` when `control` is `|1⟩`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either qubit is out of range or they are the same qubit.
+    pub fn apply_controlled(&mut self, gate: &Matrix2, control: usize, target: usize) {
+        self.check_qubit(control);
+        assert_ne!(control, target, "control and target must differ");
+        self.apply_controlled_mask(gate, 1 << control, target);
+    }
+
+    /// Applies a controlled NOT from `control` to `target`.
+    pub fn cnot(&mut self, control: usize, target: usize) {
+        self.apply_controlled(&X, control, target);
+    }
+
+    /// Applies a controlled Z between `control` and `target`.
+    pub fn cz(&mut self, control: usize, target: usize) {
+        self.apply_controlled(&Z, control, target);
+    }
+
+    /// Swaps qubits `a` and `b`.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.check_qubit(a);
+        self.check_qubit(b);
+        for index in 0..DIM {
+            let partner = index ^ (1 << a) ^ (1 << b);
+            if (index >> a) & 1 == 1 && (index >> b) & 1 == 0 {
+                self.amplitudes.swap(index, partner);
+            }
+        }
+    }
+
+    /// Returns the probability that `qubit` is measured as `1`.
+    pub fn probability_one(&self, qubit: usize) -> f64 {
+        self.check_qubit(qubit);
+        (0..DIM)
+            .filter(|index| (index >> qubit) & 1 == 1)
+            .map(|index| self.amplitudes[index].norm_sqr())
+            .sum()
+    }
+
+    /// Measures `qubit`, collapsing the register, and returns the outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit to measure.
+    /// * `rng` - The source of randomness, such as a seeded generator in deterministic tests.
+    ///
+    /// # Returns
+    ///
+    /// `0` or `1`.
+    pub fn measure_qubit_with_rng<R: RngCore + ?Sized>(
+        &mut self,
+        qubit: usize,
+        rng: &mut R,
+    ) -> usize {
+        let p1 = self.probability_one(qubit);
+        let outcome = usize::from(uniform(rng) < p1);
+        let kept = if outcome == 1 { p1 } else { 1.0 - p1 };
+        let scale = 1.0 / kept.sqrt();
+        for (index, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            *amplitude = if (index >> qubit) & 1 == outcome {
+                *amplitude * scale
+            } else {
+                ZERO
+            };
+        }
+        outcome
+    }
+
+    /// Samples a basis state index from the register without collapsing it.
+    pub fn sample_with_rng<R: RngCore + ?Sized>(&self, rng: &mut R) -> usize {
+        let mut remaining = uniform(rng);
+        for (index, amplitude) in self.amplitudes.iter().enumerate() {
+            remaining -= amplitude.norm_sqr();
+            if remaining < 0.0 {
+                return index;
+            }
+        }
+        // Rounding can leave a sliver of probability; give it to the last nonzero amplitude
+        (0..DIM)
+            .rev()
+            .find(|&index| self.amplitudes[index].norm_sqr() > 0.0)
+            .unwrap_or(0)
+    }
+
+    fn apply_controlled_mask(&mut self, gate: &Matrix2, controls: usize, target: usize) {
+        self.check_qubit(target);
+        let bit = 1 << target;
+        for index in 0..DIM {
+            if index & bit != 0 || index & controls != controls {
+                continue;
+            }
+            let a0 = self.amplitudes[index];
+            let a1 = self.amplitudes[index | bit];
+            self.amplitudes[index] = gate[0][0] * a0 + gate[0][1] * a1;
+            self.amplitudes[index | bit] = gate[1][0] * a0 + gate[1][1] * a1;
+        }
+    }
+
+    fn check_qubit(&self, qubit: usize) {
+        assert!(
+            qubit < self.num_qubits(),
+            "qubit {qubit} out of range for a {}-qubit register",
+            self.num_qubits()
+        );
+    }
+}
+
+impl<const DIM: usize> Default for FixedRegister<DIM> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DIM: usize> From<FixedRegister<DIM>> for Qubit {
+    fn from(register: FixedRegister<DIM>) -> Self {
+        Qubit::from_state(register.amplitudes.to_vec())
+    }
+}
+
+/// Draws a uniform sample in `[0, 1)` from the top 53 bits of a `u64`.
+fn uniform<R: RngCore + ?Sized>(rng: &mut R) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,320 @@
+//! This module generates a gallery of canonical states for documentation and UI presets.
+//!
+//! [`Gallery::canonical`] collects the four Bell states and the GHZ, W, Dicke and a seeded random
+//! state of a register. Every [`GalleryEntry`] renders as an SVG card with the state in Dirac
+//! notation, the Bloch vector of each qubit projected onto its x-z plane and a heatmap of the
+//! magnitudes of its density matrix. [`Gallery::write`] saves one SVG per state together with a
+//! Markdown index linking them and a JSON file of the amplitudes that front ends load as presets.
+
+use crate::analysis::bloch_vector;
+use crate::density::DensityMatrix;
+use crate::qubit::{BellState, Qubit};
+use serde_json::{json, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Widest register a gallery is generated for; the heatmap has `4^n` cells.
+pub const MAX_GALLERY_QUBITS: usize = 6;
+
+/// Radius of a Bloch disc and width of the space it takes, in pixels.
+const BLOCH_RADIUS: usize = 30;
+const BLOCH_WIDTH: usize = 2 * BLOCH_RADIUS + 20;
+
+/// Side of one heatmap cell, in pixels.
+const HEATMAP_CELL: usize = 16;
+
+/// Space around and between the parts of a card, in pixels.
+const MARGIN: usize = 12;
+
+/// Height of the title and Dirac lines at the top of a card, in pixels.
+const HEADER_HEIGHT: usize = 40;
+
+/// One state of a gallery.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GalleryEntry {
+    /// The display name, e.g. `Bell Φ+`.
+    pub name: String,
+    /// A file-name-safe identifier, e.g. `bell-phi-plus`.
+    pub slug: String,
+    /// A one-line description of the state.
+    pub description: String,
+    /// The state itself.
+    pub state: Qubit,
+}
+
+impl GalleryEntry {
+    /// Creates an entry.
+    pub fn new(name: &str, slug: &str, description: &str, state: Qubit) -> Self {
+        GalleryEntry {
+            name: name.to_string(),
+            slug: slug.to_string(),
+            description: description.to_string(),
+            state,
+        }
+    }
+
+    /// Returns the state in Dirac notation.
+    pub fn dirac(&self) -> String {
+        self.state.to_dirac()
+    }
+
+    /// Returns the Bloch vector of every qubit, qubit 0 first.
+    pub fn bloch_vectors(&self) -> Vec<(f64, f64, f64)> {
+        (0..self.state.num_qubits())
+            .map(|qubit| bloch_vector(&self.state, qubit))
+            .collect()
+    }
+
+    /// Renders the entry as an SVG card: the name and Dirac notation, a Bloch disc per qubit
+    /// and a heatmap of `|ρ_ij|`, row `i` from the top.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gallery::GalleryEntry;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let entry = GalleryEntry::new("GHZ", "ghz-3", "GHZ state", Qubit::ghz(3));
+    /// let svg = entry.to_svg();
+    /// assert!(svg.starts_with("<svg"));
+    /// assert_eq!(svg.matches("<rect").count(), 1 + 64);
+    /// ```
+    pub fn to_svg(&self) -> String {
+        let num_qubits = self.state.num_qubits();
+        let dimension = self.state.state.len();
+        let discs_width = num_qubits * BLOCH_WIDTH;
+        let heatmap_size = dimension * HEATMAP_CELL;
+        let width = (discs_width + heatmap_size + 3 * MARGIN).max(360);
+        let height = HEADER_HEIGHT + heatmap_size.max(BLOCH_WIDTH) + 2 * MARGIN;
+        let top = HEADER_HEIGHT + MARGIN;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"11\">\n<rect width=\"{0}\" height=\"{1}\" fill=\"#ffffff\"/>\n",
+            width, height
+        );
+        svg.push_str(&format!(
+            "<text x=\"{0}\" y=\"{1}\" font-size=\"14\" font-weight=\"bold\">{2}</text>\n<text x=\"{0}\" y=\"{3}\" font-family=\"monospace\">{4}</text>\n",
+            MARGIN,
+            MARGIN + 8,
+            escape(&self.name),
+            MARGIN + 24,
+            escape(&self.dirac())
+        ));
+
+        // Bloch discs: the x-z plane, with the y component as the dot's opacity
+        for (qubit, (x, y, z)) in self.bloch_vectors().into_iter().enumerate() {
+            let cx = MARGIN + qubit * BLOCH_WIDTH + BLOCH_WIDTH / 2;
+            let cy = top + BLOCH_RADIUS;
+            let r = BLOCH_RADIUS as f64;
+            let (tip_x, tip_y) = (cx as f64 + x * r, cy as f64 - z * r);
+            svg.push_str(&format!(
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"{r}\" fill=\"none\" stroke=\"#888888\"/>\n<line x1=\"{cx}\" y1=\"{cy}\" x2=\"{tip_x:.2}\" y2=\"{tip_y:.2}\" stroke=\"#d62728\" stroke-width=\"2\"/>\n<circle cx=\"{tip_x:.2}\" cy=\"{tip_y:.2}\" r=\"3\" fill=\"#d62728\" fill-opacity=\"{opacity:.2}\"><title>q{qubit}: ({x:.3}, {y:.3}, {z:.3})</title></circle>\n<text x=\"{cx}\" y=\"{label_y}\" text-anchor=\"middle\">q{qubit}</text>\n",
+                cx = cx,
+                cy = cy,
+                r = BLOCH_RADIUS,
+                tip_x = tip_x,
+                tip_y = tip_y,
+                opacity = 0.5 + 0.5 * y.abs(),
+                qubit = qubit,
+                x = x,
+                y = y,
+                z = z,
+                label_y = cy + BLOCH_RADIUS + 14
+            ));
+        }
+
+        // Density matrix magnitudes, white for 0 and dark blue for the largest entry
+        let rho = DensityMatrix::from_state(&self.state);
+        let largest = rho
+            .matrix
+            .iter()
+            .flatten()
+            .map(|entry| entry.norm())
+            .fold(0.0, f64::max);
+        let left = 2 * MARGIN + discs_width;
+        for (i, row) in rho.matrix.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                let t = if largest > 0.0 {
+                    entry.norm() / largest
+                } else {
+                    0.0
+                };
+                let shade = |full: f64| (255.0 - t * (255.0 - full)).round() as u8;
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" fill=\"#{:02x}{:02x}{:02x}\"><title>|ρ[{}][{}]| = {:.4}</title></rect>\n",
+                    left + j * HEATMAP_CELL,
+                    top + i * HEATMAP_CELL,
+                    shade(31.0),
+                    shade(119.0),
+                    shade(180.0),
+                    i,
+                    j,
+                    entry.norm(),
+                    size = HEATMAP_CELL
+                ));
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Returns the entry as a JSON preset: its names, amplitudes as `[re, im]` pairs, Dirac
+    /// notation and Bloch vectors.
+    pub fn to_json_value(&self) -> Value {
+        let amplitudes: Vec<Value> = self
+            .state
+            .state
+            .iter()
+            .map(|amplitude| json!([amplitude.re, amplitude.im]))
+            .collect();
+        let bloch_vectors: Vec<Value> = self
+            .bloch_vectors()
+            .into_iter()
+            .map(|(x, y, z)| json!([x, y, z]))
+            .collect();
+        json!({
+            "name": self.name,
+            "slug": self.slug,
+            "description": self.description,
+            "num_qubits": self.state.num_qubits(),
+            "amplitudes": amplitudes,
+            "dirac": self.dirac(),
+            "bloch_vectors": bloch_vectors,
+        })
+    }
+}
+
+/// A collection of states rendered together.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gallery::Gallery;
+///
+/// let gallery = Gallery::canonical(3, 7);
+/// assert_eq!(gallery.entries.len(), 8);
+/// assert!(gallery.to_markdown().contains("ghz-3.svg"));
+/// assert!(gallery.to_json().contains("\"slug\": \"bell-phi-plus\""));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gallery {
+    /// The states, in display order.
+    pub entries: Vec<GalleryEntry>,
+}
+
+impl Gallery {
+    /// Collects the canonical states: the four Bell states on two qubits, then the GHZ, W,
+    /// half-filled Dicke and a random state of `num_qubits` qubits.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the GHZ, W, Dicke and random states.
+    /// * `seed` - The seed of the random state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_qubits` is below 2 or above [`MAX_GALLERY_QUBITS`].
+    pub fn canonical(num_qubits: usize, seed: u64) -> Self {
+        assert!(
+            (2..=MAX_GALLERY_QUBITS).contains(&num_qubits),
+            "a gallery needs 2 to {} qubits, not {}",
+            MAX_GALLERY_QUBITS,
+            num_qubits
+        );
+        let mut entries: Vec<GalleryEntry> = BellState::ALL
+            .iter()
+            .map(|&which| {
+                let (slug, description) = match which {
+                    BellState::PhiPlus => {
+                        ("bell-phi-plus", "(|00⟩ + |11⟩)/√2, prepared by H and CNOT")
+                    }
+                    BellState::PhiMinus => ("bell-phi-minus", "(|00⟩ - |11⟩)/√2"),
+                    BellState::PsiPlus => ("bell-psi-plus", "(|01⟩ + |10⟩)/√2"),
+                    BellState::PsiMinus => ("bell-psi-minus", "(|01⟩ - |10⟩)/√2, the singlet"),
+                };
+                GalleryEntry::new(
+                    &format!("Bell {}", which.symbol()),
+                    slug,
+                    description,
+                    Qubit::bell_state(which),
+                )
+            })
+            .collect();
+        let excitations = num_qubits.div_ceil(2);
+        entries.push(GalleryEntry::new(
+            "GHZ",
+            &format!("ghz-{}", num_qubits),
+            "All qubits 0 or all qubits 1, in equal superposition",
+            Qubit::ghz(num_qubits),
+        ));
+        entries.push(GalleryEntry::new(
+            "W",
+            &format!("w-{}", num_qubits),
+            "Exactly one qubit 1, in equal superposition",
+            Qubit::w_state(num_qubits),
+        ));
+        entries.push(GalleryEntry::new(
+            &format!("Dicke (k = {})", excitations),
+            &format!("dicke-{}-{}", num_qubits, excitations),
+            &format!("Exactly {} qubits 1, in equal superposition", excitations),
+            Qubit::dicke(num_qubits, excitations),
+        ));
+        entries.push(GalleryEntry::new(
+            "Random",
+            &format!("random-{}", num_qubits),
+            &format!("A Haar-random state drawn with seed {}", seed),
+            Qubit::random_state(num_qubits, seed),
+        ));
+        Gallery { entries }
+    }
+
+    /// Returns the presets of every entry as a pretty-printed JSON array.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<Value> = self
+            .entries
+            .iter()
+            .map(GalleryEntry::to_json_value)
+            .collect();
+        serde_json::to_string_pretty(&entries).expect("gallery presets serialize")
+    }
+
+    /// Returns a Markdown index with a section per entry linking its `<slug>.svg`.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# State gallery\n");
+        for entry in &self.entries {
+            out.push_str(&format!(
+                "\n## {}\n\n{}\n\n`{}`\n\n![{}]({}.svg)\n",
+                entry.name,
+                entry.description,
+                entry.dirac(),
+                entry.name,
+                entry.slug
+            ));
+        }
+        out
+    }
+
+    /// Writes `<slug>.svg` for every entry, `index.md` and `gallery.json` into `dir`, creating
+    /// it if needed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every file was written.
+    /// * `Err(io::Error)` - If the directory or a file could not be written.
+    pub fn write(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for entry in &self.entries {
+            fs::write(dir.join(format!("{}.svg", entry.slug)), entry.to_svg())?;
+        }
+        fs::write(dir.join("index.md"), self.to_markdown())?;
+        fs::write(dir.join("gallery.json"), self.to_json())
+    }
+}
+
+/// Escapes the characters that are special in SVG text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -1,11 +1,23 @@
 //! This module defines various quantum gates and their associated methods.
 
+use crate::error::QuantumError;
+use crate::linalg;
 use crate::qubit::Qubit;
+use crate::simd;
+use crate::stats;
+use crate::tolerance;
 use num_complex::Complex;
+use std::sync::Arc;
 
 /// A `Gate` represents a quantum gate with a matrix for multi-qubit operations.
+///
+/// The matrix is shared behind an [`Arc`], so cloning a gate, or a circuit holding it, never
+/// copies the exponentially sized matrix, and gates can be shared freely across threads. Use
+/// [`Arc::make_mut`] to modify a matrix in place; it copies the data only if it is shared.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gate {
-    pub matrix: Vec<Vec<Complex<f64>>>, // Matrix to handle multi-qubit gates
+    pub matrix: Arc<Vec<Vec<Complex<f64>>>>, // Matrix to handle multi-qubit gates
 }
 
 impl Gate {
@@ -28,7 +40,91 @@ impl Gate {
     /// let gate = Gate::new(matrix);
     /// ```
     pub fn new(matrix: Vec<Vec<Complex<f64>>>) -> Self {
-        Gate { matrix }
+        Gate {
+            matrix: Arc::new(matrix),
+        }
+    }
+
+    /// Creates a new `Gate` after checking that the matrix is square with a power-of-two dimension.
+    ///
+    /// When `unitarity_tolerance` is given, the matrix must also satisfy `U†U = I` to within that
+    /// tolerance entry-wise.
+    ///
+    /// # Arguments
+    ///
+    /// * `matrix` - A 2D vector representing the gate matrix.
+    /// * `unitarity_tolerance` - The allowed deviation from unitarity, or `None` to skip the check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::gates::Gate;
+    /// use num_complex::Complex;
+    ///
+    /// let one = Complex::new(1.0, 0.0);
+    /// let zero = Complex::new(0.0, 0.0);
+    /// assert!(Gate::try_new(vec![vec![zero, one], vec![one, zero]], Some(1e-10)).is_ok());
+    ///
+    /// let projector = vec![vec![one, zero], vec![zero, zero]];
+    /// assert!(Gate::try_new(projector.clone(), None).is_ok());
+    /// assert!(matches!(
+    ///     Gate::try_new(projector, Some(1e-10)),
+    ///     Err(QuantumError::NotUnitary { .. })
+    /// ));
+    /// ```
+    pub fn try_new(
+        matrix: Vec<Vec<Complex<f64>>>,
+        unitarity_tolerance: Option<f64>,
+    ) -> Result<Self, QuantumError> {
+        check_shape(&matrix)?;
+        if let Some(tolerance) = unitarity_tolerance {
+            let product = linalg::matmul(&linalg::dagger(&matrix), &matrix);
+            let deviation = linalg::max_deviation(&product, &linalg::identity(matrix.len()));
+            if deviation > tolerance {
+                return Err(QuantumError::NotUnitary { deviation });
+            }
+        }
+        Ok(Gate::new(matrix))
+    }
+
+    /// Creates a new `Gate` from a unitary matrix, e.g. an arbitrary single-qubit operation
+    /// calibrated on hardware, after checking that it is unitary entry-wise to within the
+    /// [`unitarity`](crate::tolerance::Tolerances::unitarity) tolerance, `1e-10` by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `matrix` - A square matrix with a power-of-two dimension.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Gate)` - The gate.
+    /// * `Err(QuantumError::InvalidDimension)` or `Err(QuantumError::DimensionMismatch)` - If the
+    ///   matrix is not square with a power-of-two dimension.
+    /// * `Err(QuantumError::NotUnitary)` - If `U†U` deviates from the identity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::gates::Gate;
+    /// use num_complex::Complex;
+    ///
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let sx = vec![
+    ///     vec![Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
+    ///     vec![Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
+    /// ];
+    /// assert!(Gate::from_unitary(sx).is_ok());
+    ///
+    /// let not_unitary = vec![vec![Complex::new(h, 0.0); 2]; 2];
+    /// assert!(matches!(
+    ///     Gate::from_unitary(not_unitary),
+    ///     Err(QuantumError::NotUnitary { .. })
+    /// ));
+    /// ```
+    pub fn from_unitary(matrix: Vec<Vec<Complex<f64>>>) -> Result<Self, QuantumError> {
+        Gate::try_new(matrix, Some(tolerance::current().unitarity))
     }
 
     /// Applies the gate to the given qubit.
@@ -58,44 +154,771 @@ impl Gate {
             .collect();
         qubit.state = new_state;
     }
+
+    /// Applies the gate to the given qubit after checking that their dimensions agree.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit on which to apply the gate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::gates::cnot;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut qubit = Qubit::new();
+    /// assert_eq!(
+    ///     cnot(0, 1, 2).try_apply(&mut qubit),
+    ///     Err(QuantumError::DimensionMismatch { expected: 4, found: 2 })
+    /// );
+    /// ```
+    pub fn try_apply(&self, qubit: &mut Qubit) -> Result<(), QuantumError> {
+        if self.matrix.len() != qubit.state.len() {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.matrix.len(),
+                found: qubit.state.len(),
+            });
+        }
+        self.apply(qubit);
+        Ok(())
+    }
+
+    /// Expands a single-qubit gate to act on `target` within an `num_qubits`-qubit register.
+    ///
+    /// The result is `I ⊗ … ⊗ U ⊗ … ⊗ I` with `U` in the position of qubit `target`, so it can be
+    /// applied directly to the full register state.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The index of the qubit the gate acts on.
+    /// * `num_qubits` - The total number of qubits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let gate = pauli_x().expand(1, 2);
+    /// let mut qubit = Qubit::from_state(vec![
+    ///     Complex::new(1.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    /// ]);
+    /// gate.apply(&mut qubit);
+    /// assert_eq!(qubit.state[2], Complex::new(1.0, 0.0)); // |10⟩
+    /// ```
+    pub fn expand(&self, target: usize, num_qubits: usize) -> Gate {
+        let size = 2usize.pow(num_qubits as u32);
+        let mask = 1 << target;
+        let mut matrix = vec![vec![Complex::new(0.0, 0.0); size]; size];
+
+        for (i, row) in matrix.iter_mut().enumerate() {
+            let i_t = (i >> target) & 1;
+            for j_t in 0..2 {
+                let j = (i & !mask) | (j_t << target);
+                row[j] = self.matrix[i_t][j_t];
+            }
+        }
+
+        Gate::new(matrix)
+    }
+
+    /// Returns the adjoint `U†` of the gate, which undoes it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{phase, s};
+    /// use std::f64::consts::PI;
+    ///
+    /// let s_dagger = s().dagger();
+    /// assert!((s_dagger.matrix[1][1] - phase(-PI / 2.0).matrix[1][1]).norm() < 1e-12);
+    /// ```
+    pub fn dagger(&self) -> Gate {
+        Gate::new(linalg::dagger(&self.matrix))
+    }
+
+    /// Returns the identity on `num_qubits` qubits; `Gate::identity(0)` is the `1×1` identity,
+    /// the neutral element of [`Gate::tensor`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{pauli_x, Gate};
+    ///
+    /// assert_eq!(Gate::identity(2).matrix.len(), 4);
+    /// assert_eq!(Gate::identity(0).tensor(&pauli_x()).unwrap(), pauli_x());
+    /// ```
+    pub fn identity(num_qubits: usize) -> Gate {
+        Gate::new(linalg::identity(1 << num_qubits))
+    }
+
+    /// Returns the Kronecker product `self ⊗ other`.
+    ///
+    /// Qubit `k` is bit `k` of a basis index, so `other` acts on the low qubits of the result and
+    /// `self` on the qubits above them: `h.tensor(&x)` applies `X` to qubit 0 and `H` to qubit 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The gate on the low qubits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Gate)` - The product, on as many qubits as both gates together.
+    /// * `Err(QuantumError::InvalidDimension)` or `Err(QuantumError::DimensionMismatch)` - If
+    ///   either matrix is not square with a power-of-two dimension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{hadamard, pauli_x, Gate};
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let gate = hadamard(1).tensor(&pauli_x()).unwrap();
+    /// let mut qubit = Qubit::zero_state(2);
+    /// gate.apply(&mut qubit);
+    /// assert!((qubit.state[0b01].norm_sqr() - 0.5).abs() < 1e-12);
+    /// assert!((qubit.state[0b11].norm_sqr() - 0.5).abs() < 1e-12);
+    ///
+    /// let ragged = Gate::new(vec![vec![]; 3]);
+    /// assert!(pauli_x().tensor(&ragged).is_err());
+    /// ```
+    pub fn tensor(&self, other: &Gate) -> Result<Gate, QuantumError> {
+        check_shape(&self.matrix)?;
+        check_shape(&other.matrix)?;
+        Ok(Gate::new(linalg::kron(&self.matrix, &other.matrix)))
+    }
+
+    /// Returns this gate with an extra control qubit prepended as local qubit 0.
+    ///
+    /// Unlike [`Gate::controlled`], this works for gates on any number of qubits and keeps the
+    /// result local: apply it to `[control, targets…]` with [`Gate::apply_to`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{pauli_x, swap, toffoli};
+    ///
+    /// assert_eq!(pauli_x().with_control().with_control().matrix, toffoli(0, 1, 2, 3).matrix);
+    /// assert_eq!(swap(0, 1, 2).with_control().matrix.len(), 8); // Fredkin
+    /// ```
+    pub fn with_control(&self) -> Gate {
+        let dim = self.matrix.len();
+        let mut matrix = vec![vec![Complex::new(0.0, 0.0); 2 * dim]; 2 * dim];
+        for i in 0..dim {
+            matrix[i << 1][i << 1] = Complex::new(1.0, 0.0);
+            for j in 0..dim {
+                matrix[(i << 1) | 1][(j << 1) | 1] = self.matrix[i][j];
+            }
+        }
+        Gate::new(matrix)
+    }
+
+    /// Returns this single-qubit gate controlled by `control` and acting on `target` in a
+    /// multi-qubit system.
+    ///
+    /// # Arguments
+    ///
+    /// * `control` - The control qubit index.
+    /// * `target` - The target qubit index.
+    /// * `num_qubits` - The total number of qubits.
+    ///
+    /// # Returns
+    ///
+    /// * The controlled gate, or an error as for [`Gate::multi_controlled`], e.g.
+    ///   `QuantumError::DuplicateQubit` if `control` is `target`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{cnot, pauli_x, phase};
+    /// use num_complex::Complex;
+    /// use std::f64::consts::PI;
+    ///
+    /// assert_eq!(pauli_x().controlled(0, 1, 2).unwrap(), cnot(0, 1, 2));
+    ///
+    /// let gate = phase(PI / 2.0).controlled(1, 0, 2).unwrap();
+    /// assert_eq!(gate.matrix[3][3], phase(PI / 2.0).matrix[1][1]);
+    /// assert_eq!(gate.matrix[1][1], Complex::new(1.0, 0.0)); // control not set
+    /// assert!(pauli_x().controlled(1, 1, 2).is_err());
+    /// ```
+    pub fn controlled(
+        &self,
+        control: usize,
+        target: usize,
+        num_qubits: usize,
+    ) -> Result<Gate, QuantumError> {
+        self.multi_controlled(&[control], target, num_qubits)
+    }
+
+    /// Returns this single-qubit gate acting on `target` only when every qubit in `controls` is
+    /// set.
+    ///
+    /// # Arguments
+    ///
+    /// * `controls` - The control qubit indices.
+    /// * `target` - The target qubit index.
+    /// * `num_qubits` - The total number of qubits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Gate)` - The controlled gate on `num_qubits` qubits.
+    /// * `Err(QuantumError::DimensionMismatch)` - If the gate is not a single-qubit gate.
+    /// * `Err(QuantumError::QubitOutOfRange)` - If a control or the target is not below
+    ///   `num_qubits`.
+    /// * `Err(QuantumError::DuplicateQubit)` - If a control is the target or is listed twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{pauli_x, toffoli};
+    ///
+    /// assert_eq!(
+    ///     pauli_x().multi_controlled(&[0, 1], 2, 3).unwrap(),
+    ///     toffoli(0, 1, 2, 3)
+    /// );
+    /// assert!(pauli_x().multi_controlled(&[0, 2], 2, 3).is_err());
+    /// ```
+    pub fn multi_controlled(
+        &self,
+        controls: &[usize],
+        target: usize,
+        num_qubits: usize,
+    ) -> Result<Gate, QuantumError> {
+        if self.matrix.len() != 2 {
+            return Err(QuantumError::DimensionMismatch {
+                expected: 2,
+                found: self.matrix.len(),
+            });
+        }
+        let qubits: Vec<usize> = std::iter::once(target)
+            .chain(controls.iter().copied())
+            .collect();
+        for (position, &qubit) in qubits.iter().enumerate() {
+            if qubit >= num_qubits {
+                return Err(QuantumError::QubitOutOfRange { qubit, num_qubits });
+            }
+            if qubits[..position].contains(&qubit) {
+                return Err(QuantumError::DuplicateQubit { qubit });
+            }
+        }
+        let control_mask: usize = controls.iter().map(|&control| 1 << control).sum();
+        let size = 2usize.pow(num_qubits as u32);
+        let mut matrix = vec![vec![Complex::new(0.0, 0.0); size]; size];
+
+        for (i, row) in matrix.iter_mut().enumerate() {
+            if i & control_mask == control_mask {
+                let i_t = (i >> target) & 1;
+                for j_t in 0..2 {
+                    let j = (i & !(1 << target)) | (j_t << target);
+                    row[j] = self.matrix[i_t][j_t];
+                }
+            } else {
+                row[i] = Complex::new(1.0, 0.0);
+            }
+        }
+
+        Ok(Gate::new(matrix))
+    }
+
+    /// Applies the gate to the given target qubits of a larger register, in place.
+    ///
+    /// Bit `j` of the gate's basis index corresponds to `targets[j]`. Only the `2^k` amplitudes
+    /// sharing the same non-target bits are mixed at a time, so the full-register matrix is
+    /// never built.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The register on which to apply the gate.
+    /// * `targets` - The register qubits the gate acts on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let mut qubit = Qubit::from_state(vec![
+    ///     Complex::new(1.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    /// ]);
+    /// pauli_x().apply_to(&mut qubit, &[1]);
+    /// assert_eq!(qubit.state[2], Complex::new(1.0, 0.0)); // |10⟩
+    /// ```
+    pub fn apply_to(&self, qubit: &mut Qubit, targets: &[usize]) {
+        stats::record_gate(qubit.memory_bytes(), || {
+            linalg::apply_local(&self.matrix, &mut qubit.state, targets)
+        });
+    }
 }
 
-/// Returns a Hadamard gate for the given number of qubits.
+/// A `GateKind` is a gate bound to its target qubits, stored in the cheapest structure that
+/// represents it exactly.
+///
+/// Most standard gates are diagonal (`Z`, `S`, `T`, `Rz`, `CZ`) or permute basis states up to a
+/// phase (`X`, `Y`, `CNOT`, `SWAP`, `iSWAP`, Toffoli), and those are applied without touching a
+/// dense matrix. In every variant, bit `j` of a local basis index corresponds to `targets[j]`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GateKind {
+    /// Multiplies local basis state `i` by `phases[i]`.
+    Diagonal {
+        phases: Vec<Complex<f64>>,
+        targets: Vec<usize>,
+    },
+    /// Sends local basis state `i` to `map[i]`, multiplied by `phases[i]`.
+    Permutation {
+        map: Vec<usize>,
+        phases: Vec<Complex<f64>>,
+        targets: Vec<usize>,
+    },
+    /// A general single-qubit gate.
+    SingleQubit {
+        matrix: [[Complex<f64>; 2]; 2],
+        target: usize,
+    },
+    /// A general two-qubit gate.
+    TwoQubit {
+        matrix: [[Complex<f64>; 4]; 4],
+        targets: [usize; 2],
+    },
+    /// Any other gate, applied through its dense matrix.
+    DenseFallback {
+        matrix: Arc<Vec<Vec<Complex<f64>>>>,
+        targets: Vec<usize>,
+    },
+}
+
+impl GateKind {
+    /// Classifies `gate` acting on `targets`.
+    ///
+    /// A matrix with exactly one non-zero entry in every column becomes a `Diagonal` or
+    /// `Permutation`; otherwise one- and two-qubit gates use fixed-size matrices and larger gates
+    /// fall back to the dense matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate; bit `j` of its basis index corresponds to `targets[j]`.
+    /// * `targets` - The register qubits the gate acts on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{cnot, hadamard, rz, GateKind};
+    ///
+    /// assert!(matches!(GateKind::new(&rz(0.3), &[0]), GateKind::Diagonal { .. }));
+    /// assert!(matches!(GateKind::new(&cnot(0, 1, 2), &[0, 1]), GateKind::Permutation { .. }));
+    /// assert!(matches!(GateKind::new(&hadamard(1), &[2]), GateKind::SingleQubit { target: 2, .. }));
+    /// ```
+    pub fn new(gate: &Gate, targets: &[usize]) -> Self {
+        let matrix = &gate.matrix;
+        let dimension = matrix.len();
+        if dimension != 1 << targets.len() || matrix.iter().any(|row| row.len() != dimension) {
+            return GateKind::DenseFallback {
+                matrix: Arc::clone(matrix),
+                targets: targets.to_vec(),
+            };
+        }
+
+        let zero = Complex::new(0.0, 0.0);
+        let mut map = Vec::with_capacity(dimension);
+        let mut hit = vec![false; dimension];
+        for column in 0..dimension {
+            let mut rows = matrix
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| row[column] != zero);
+            match (rows.next(), rows.next()) {
+                (Some((row, _)), None) if !hit[row] => {
+                    hit[row] = true;
+                    map.push(row);
+                }
+                _ => break,
+            }
+        }
+        if map.len() == dimension {
+            let phases = map
+                .iter()
+                .enumerate()
+                .map(|(column, &row)| matrix[row][column])
+                .collect();
+            let targets = targets.to_vec();
+            return if map.iter().enumerate().all(|(column, &row)| row == column) {
+                GateKind::Diagonal { phases, targets }
+            } else {
+                GateKind::Permutation {
+                    map,
+                    phases,
+                    targets,
+                }
+            };
+        }
+
+        match *targets {
+            [target] => GateKind::SingleQubit {
+                matrix: [[matrix[0][0], matrix[0][1]], [matrix[1][0], matrix[1][1]]],
+                target,
+            },
+            [first, second] => GateKind::TwoQubit {
+                matrix: std::array::from_fn(|row| std::array::from_fn(|col| matrix[row][col])),
+                targets: [first, second],
+            },
+            _ => GateKind::DenseFallback {
+                matrix: Arc::clone(matrix),
+                targets: targets.to_vec(),
+            },
+        }
+    }
+
+    /// Returns the register qubits the gate acts on.
+    pub fn targets(&self) -> &[usize] {
+        match self {
+            GateKind::Diagonal { targets, .. }
+            | GateKind::Permutation { targets, .. }
+            | GateKind::DenseFallback { targets, .. } => targets,
+            GateKind::SingleQubit { target, .. } => std::slice::from_ref(target),
+            GateKind::TwoQubit { targets, .. } => targets,
+        }
+    }
+
+    /// Returns the dense matrix of the gate on its targets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{pauli_y, GateKind};
+    ///
+    /// assert_eq!(GateKind::new(&pauli_y(), &[0]).to_gate(), pauli_y());
+    /// ```
+    pub fn to_gate(&self) -> Gate {
+        let zero = Complex::new(0.0, 0.0);
+        match self {
+            GateKind::Diagonal { phases, targets } => diagonal_gate(targets.len(), |i| phases[i]),
+            GateKind::Permutation { map, phases, .. } => {
+                let mut matrix = vec![vec![zero; map.len()]; map.len()];
+                for (column, (&row, &phase)) in map.iter().zip(phases).enumerate() {
+                    matrix[row][column] = phase;
+                }
+                Gate::new(matrix)
+            }
+            GateKind::SingleQubit { matrix, .. } => {
+                Gate::new(matrix.iter().map(|row| row.to_vec()).collect())
+            }
+            GateKind::TwoQubit { matrix, .. } => {
+                Gate::new(matrix.iter().map(|row| row.to_vec()).collect())
+            }
+            GateKind::DenseFallback { matrix, .. } => Gate {
+                matrix: Arc::clone(matrix),
+            },
+        }
+    }
+
+    /// Applies the gate to its targets in a register, in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The register on which to apply the gate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{pauli_x, GateKind};
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let mut qubit = Qubit::from_state(vec![
+    ///     Complex::new(1.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    /// ]);
+    /// GateKind::new(&pauli_x(), &[1]).apply(&mut qubit);
+    /// assert_eq!(qubit.state[2], Complex::new(1.0, 0.0)); // |10⟩
+    /// ```
+    pub fn apply(&self, qubit: &mut Qubit) {
+        stats::record_gate(qubit.memory_bytes(), || {
+            self.apply_to_state(&mut qubit.state)
+        });
+    }
+
+    fn apply_to_state(&self, state: &mut [Complex<f64>]) {
+        match self {
+            GateKind::Diagonal { phases, targets } => {
+                let (offsets, mask) = linalg::local_offsets(targets);
+                for base in (0..state.len()).filter(|index| index & mask == 0) {
+                    for (phase, offset) in phases.iter().zip(&offsets) {
+                        state[base + offset] *= phase;
+                    }
+                }
+            }
+            GateKind::Permutation {
+                map,
+                phases,
+                targets,
+            } => {
+                let one = Complex::new(1.0, 0.0);
+                if targets.len() == 2 && phases.iter().all(|&phase| phase == one) {
+                    match map.as_slice() {
+                        [0, 3, 2, 1] => return simd::apply_cnot(state, targets[0], targets[1]),
+                        [0, 1, 3, 2] => return simd::apply_cnot(state, targets[1], targets[0]),
+                        _ => {}
+                    }
+                }
+                let (offsets, mask) = linalg::local_offsets(targets);
+                let mut local = vec![Complex::new(0.0, 0.0); offsets.len()];
+                for base in (0..state.len()).filter(|index| index & mask == 0) {
+                    for (amplitude, offset) in local.iter_mut().zip(&offsets) {
+                        *amplitude = state[base + offset];
+                    }
+                    for (column, amplitude) in local.iter().enumerate() {
+                        state[base + offsets[map[column]]] = phases[column] * amplitude;
+                    }
+                }
+            }
+            GateKind::SingleQubit { matrix, target } => {
+                simd::apply_single_qubit(state, matrix, *target)
+            }
+            GateKind::TwoQubit { matrix, targets } => {
+                let (offsets, mask) = linalg::local_offsets(targets);
+                for base in (0..state.len()).filter(|index| index & mask == 0) {
+                    let local: [Complex<f64>; 4] =
+                        std::array::from_fn(|k| state[base + offsets[k]]);
+                    for (row, offset) in matrix.iter().zip(&offsets) {
+                        state[base + offset] = row.iter().zip(&local).map(|(m, a)| m * a).sum();
+                    }
+                }
+            }
+            GateKind::DenseFallback { matrix, targets } => {
+                linalg::apply_local(matrix, state, targets)
+            }
+        }
+    }
+}
+
+/// Merges runs of single-qubit gates on the same target into one gate each.
+///
+/// A single-qubit gate joins the run on its target until a multi-qubit gate touches that target,
+/// so the fused run is emitted just before that gate (or at the end). Gates on other qubits
+/// commute with the run, so the result applies the same unitary with fewer passes over the
+/// state.
 ///
 /// # Arguments
 ///
-/// * `qubit_count` - The number of qubits.
+/// * `kinds` - The gates in application order.
+///
+/// # Returns
+///
+/// * The fused gates in an equivalent application order.
 ///
 /// # Examples
 ///
 /// ```
-/// use quantum_simulator::gates::hadamard;
-/// let gate = hadamard(1);
+/// use quantum_simulator::gates::{cnot, fuse, hadamard, pauli_z, t, GateKind};
+///
+/// let kinds = vec![
+///     GateKind::new(&hadamard(1), &[0]),
+///     GateKind::new(&t(), &[0]),
+///     GateKind::new(&pauli_z(), &[1]),
+///     GateKind::new(&hadamard(1), &[0]),
+///     GateKind::new(&cnot(0, 1, 2), &[0, 1]),
+///     GateKind::new(&t(), &[1]),
+/// ];
+/// let fused = fuse(kinds);
+/// assert_eq!(fused.len(), 4);
+/// assert_eq!(fused[0].targets(), &[0]);
+/// assert_eq!(fused[1].targets(), &[1]);
+/// assert_eq!(fused[2].targets(), &[0, 1]);
 /// ```
-pub fn hadamard(qubit_count: usize) -> Gate {
-    let h = 1.0 / (2.0_f64).sqrt();
-    let size = 2usize.pow(qubit_count as u32);
-    let mut matrix = vec![vec![Complex::new(0.0, 0.0); size]; size];
+pub fn fuse(kinds: Vec<GateKind>) -> Vec<GateKind> {
+    fn flush(run: Vec<GateKind>, target: usize, fused: &mut Vec<GateKind>) {
+        if run.len() == 1 {
+            fused.extend(run);
+            return;
+        }
+        let product = run.iter().fold(linalg::identity(2), |product, kind| {
+            linalg::matmul(&kind.to_gate().matrix, &product)
+        });
+        fused.push(GateKind::new(&Gate::new(product), &[target]));
+    }
 
-    for (i, row) in matrix.iter_mut().enumerate().take(size) {
-        for (j, elem) in row.iter_mut().enumerate().take(size) {
-            let mut product = 1.0;
-            for k in 0..qubit_count {
-                let i_k = (i >> k) & 1;
-                let j_k = (j >> k) & 1;
-                if i_k == j_k {
-                    product *= h;
-                } else {
-                    product *= -h;
-                }
+    let mut fused = Vec::with_capacity(kinds.len());
+    let mut runs: std::collections::BTreeMap<usize, Vec<GateKind>> = Default::default();
+    for kind in kinds {
+        let single = match (&kind, kind.targets()) {
+            (GateKind::DenseFallback { .. }, _) => None,
+            (_, &[target]) => Some(target),
+            _ => None,
+        };
+        if let Some(target) = single {
+            runs.entry(target).or_default().push(kind);
+            continue;
+        }
+        for target in kind.targets() {
+            if let Some(run) = runs.remove(target) {
+                flush(run, *target, &mut fused);
             }
-            *elem = Complex::new(product, 0.0);
         }
+        fused.push(kind);
+    }
+    for (target, run) in runs {
+        flush(run, target, &mut fused);
+    }
+    fused
+}
+
+/// Returns the Kronecker product of `gates`, written in the usual order: `kron(&[h, i, x])` is
+/// `H ⊗ I ⊗ X`, which applies `X` to qubit 0, the identity to qubit 1 and `H` to qubit 2.
+///
+/// # Arguments
+///
+/// * `gates` - The factors, highest qubits first. An empty slice gives `Gate::identity(0)`.
+///
+/// # Returns
+///
+/// * The product, or the first dimension error of [`Gate::tensor`].
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{hadamard, kron, pauli_x, Gate};
+/// use quantum_simulator::qubit::Qubit;
+///
+/// let gate = kron(&[hadamard(1), Gate::identity(1), pauli_x()]).unwrap();
+/// assert_eq!(gate.matrix.len(), 8);
+///
+/// let mut qubit = Qubit::zero_state(3);
+/// gate.apply(&mut qubit);
+/// assert!((qubit.state[0b001].norm_sqr() - 0.5).abs() < 1e-12);
+/// assert!((qubit.state[0b101].norm_sqr() - 0.5).abs() < 1e-12);
+/// ```
+pub fn kron(gates: &[Gate]) -> Result<Gate, QuantumError> {
+    gates
+        .iter()
+        .try_fold(Gate::identity(0), |product, gate| product.tensor(gate))
+}
+
+/// Checks that `matrix` is square with a power-of-two dimension.
+fn check_shape(matrix: &[Vec<Complex<f64>>]) -> Result<(), QuantumError> {
+    let dimension = matrix.len();
+    if !dimension.is_power_of_two() {
+        return Err(QuantumError::InvalidDimension { dimension });
     }
+    if let Some(row) = matrix.iter().find(|row| row.len() != dimension) {
+        return Err(QuantumError::DimensionMismatch {
+            expected: dimension,
+            found: row.len(),
+        });
+    }
+    Ok(())
+}
 
+/// Returns a Hadamard gate on every one of `qubit_count` qubits.
+///
+/// Kept for compatibility, where `hadamard(1)` is the single-qubit gate; use [`hadamard_on`] for
+/// one qubit of a register and [`hadamard_all`] to make the all-qubit intent explicit.
+///
+/// # Arguments
+///
+/// * `qubit_count` - The number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::hadamard;
+/// use num_complex::Complex;
+///
+/// let gate = hadamard(1);
+/// let h = 1.0 / 2.0_f64.sqrt();
+/// assert_eq!(gate.matrix[0], vec![Complex::new(h, 0.0), Complex::new(h, 0.0)]);
+/// assert_eq!(gate.matrix[1], vec![Complex::new(h, 0.0), Complex::new(-h, 0.0)]);
+/// ```
+pub fn hadamard(qubit_count: usize) -> Gate {
+    hadamard_all(qubit_count)
+}
+
+/// Returns `H ⊗ … ⊗ H`, a Hadamard on every one of `num_qubits` qubits; the same gate as
+/// `kron(&vec![hadamard(1); num_qubits])`, without building the intermediate products.
+///
+/// Entry `(i, j)` is `2^(-n/2)`, negated when `i` and `j` share an odd number of set bits.
+///
+/// # Arguments
+///
+/// * `num_qubits` - The number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::hadamard_all;
+///
+/// let gate = hadamard_all(2);
+/// assert!((gate.matrix[3][3].re - 0.5).abs() < 1e-12);
+/// assert!((gate.matrix[1][3].re + 0.5).abs() < 1e-12);
+/// ```
+pub fn hadamard_all(num_qubits: usize) -> Gate {
+    let h = 1.0 / (2.0_f64).sqrt();
+    let size = 1 << num_qubits;
+    let magnitude = (0..num_qubits).fold(1.0, |product, _| product * h);
+    let matrix = (0..size)
+        .map(|i: usize| {
+            (0..size)
+                .map(|j: usize| {
+                    let sign = if (i & j).count_ones() % 2 == 1 {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+                    Complex::new(sign * magnitude, 0.0)
+                })
+                .collect()
+        })
+        .collect();
     Gate::new(matrix)
 }
 
+/// Returns a Hadamard on qubit `target` of a `num_qubits`-qubit register, the identity on the
+/// others.
+///
+/// The gate is kept as its 2×2 matrix and target, so applying it with [`GateKind::apply`] is one
+/// strided pass over the state and no register-sized matrix is ever formed. Call
+/// [`Gate::expand`] on `hadamard(1)` if the dense matrix itself is needed.
+///
+/// # Arguments
+///
+/// * `target` - The qubit the Hadamard acts on.
+/// * `num_qubits` - The total number of qubits.
+///
+/// # Panics
+///
+/// Panics if `target >= num_qubits`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::hadamard_on;
+/// use quantum_simulator::qubit::Qubit;
+///
+/// let mut qubit = Qubit::zero_state(4);
+/// hadamard_on(2, 4).apply(&mut qubit);
+/// assert!((qubit.state[0b0000].norm_sqr() - 0.5).abs() < 1e-12);
+/// assert!((qubit.state[0b0100].norm_sqr() - 0.5).abs() < 1e-12);
+/// ```
+pub fn hadamard_on(target: usize, num_qubits: usize) -> GateKind {
+    assert!(
+        target < num_qubits,
+        "target qubit {} out of range for {} qubits",
+        target,
+        num_qubits
+    );
+    GateKind::new(&hadamard(1), &[target])
+}
+
 /// Returns a Pauli-X gate.
 ///
 /// # Examples
@@ -197,18 +1020,407 @@ pub fn cnot(control: usize, target: usize, num_qubits: usize) -> Gate {
     let mut matrix = vec![vec![Complex::new(0.0, 0.0); size]; size];
 
     for i in 0..size {
-        for j in 0..size {
-            if i == j {
-                let control_bit = (i >> control) & 1;
-                let _target_bit = (i >> target) & 1; // Prefix with underscore
-                if control_bit == 1 {
-                    matrix[i ^ (1 << target)][j] = Complex::new(1.0, 0.0);
-                } else {
-                    matrix[i][j] = Complex::new(1.0, 0.0);
-                }
+        let control_bit = (i >> control) & 1;
+        if control_bit == 1 {
+            matrix[i ^ (1 << target)][i] = Complex::new(1.0, 0.0);
+        } else {
+            matrix[i][i] = Complex::new(1.0, 0.0);
+        }
+    }
+
+    Gate::new(matrix)
+}
+
+/// Returns a rotation about the X axis, `RX(θ) = exp(-iθX/2)`.
+///
+/// # Arguments
+///
+/// * `theta` - The rotation angle.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::rx;
+/// use num_complex::Complex;
+///
+/// let gate = rx(std::f64::consts::PI);
+/// assert!((gate.matrix[0][0] - Complex::new(0.0, 0.0)).norm() < 1e-12);
+/// assert!((gate.matrix[0][1] - Complex::new(0.0, -1.0)).norm() < 1e-12);
+/// assert!((gate.matrix[1][0] - Complex::new(0.0, -1.0)).norm() < 1e-12);
+/// ```
+pub fn rx(theta: f64) -> Gate {
+    let (c, s) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+    Gate::new(vec![
+        vec![Complex::new(c, 0.0), Complex::new(0.0, -s)],
+        vec![Complex::new(0.0, -s), Complex::new(c, 0.0)],
+    ])
+}
+
+/// Returns a rotation about the Y axis, `RY(θ) = exp(-iθY/2)`.
+///
+/// # Arguments
+///
+/// * `theta` - The rotation angle.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::ry;
+/// use num_complex::Complex;
+///
+/// let gate = ry(std::f64::consts::PI / 2.0);
+/// let h = 1.0 / 2.0_f64.sqrt();
+/// assert!((gate.matrix[0][1] - Complex::new(-h, 0.0)).norm() < 1e-12);
+/// assert!((gate.matrix[1][0] - Complex::new(h, 0.0)).norm() < 1e-12);
+/// ```
+pub fn ry(theta: f64) -> Gate {
+    let (c, s) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+    Gate::new(vec![
+        vec![Complex::new(c, 0.0), Complex::new(-s, 0.0)],
+        vec![Complex::new(s, 0.0), Complex::new(c, 0.0)],
+    ])
+}
+
+/// Returns a rotation about the Z axis, `RZ(θ) = exp(-iθZ/2)`.
+///
+/// # Arguments
+///
+/// * `theta` - The rotation angle.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::rz;
+/// use num_complex::Complex;
+///
+/// let gate = rz(std::f64::consts::PI);
+/// assert!((gate.matrix[0][0] - Complex::new(0.0, -1.0)).norm() < 1e-12);
+/// assert!((gate.matrix[1][1] - Complex::new(0.0, 1.0)).norm() < 1e-12);
+/// assert_eq!(gate.matrix[0][1], Complex::new(0.0, 0.0));
+/// ```
+pub fn rz(theta: f64) -> Gate {
+    Gate::new(vec![
+        vec![
+            Complex::from_polar(1.0, -theta / 2.0),
+            Complex::new(0.0, 0.0),
+        ],
+        vec![
+            Complex::new(0.0, 0.0),
+            Complex::from_polar(1.0, theta / 2.0),
+        ],
+    ])
+}
+
+/// Returns the general single-qubit gate `U(θ, φ, λ) = RZ(φ) RY(θ) RZ(λ)` up to global phase, as
+/// OpenQASM's `u3` and `U`:
+///
+/// ```text
+/// U(θ, φ, λ) = [ cos(θ/2)          -e^{iλ} sin(θ/2)     ]
+///              [ e^{iφ} sin(θ/2)   e^{i(φ+λ)} cos(θ/2)  ]
+/// ```
+///
+/// Every single-qubit unitary equals some `U(θ, φ, λ)` up to a global phase.
+///
+/// # Arguments
+///
+/// * `theta` - The rotation angle about Y.
+/// * `phi` - The rotation angle about Z after the Y rotation.
+/// * `lambda` - The rotation angle about Z before the Y rotation.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{hadamard, u3};
+/// use std::f64::consts::PI;
+///
+/// // H = U(π/2, 0, π)
+/// let gate = u3(PI / 2.0, 0.0, PI);
+/// for (row, expected) in gate.matrix.iter().zip(hadamard(1).matrix.iter()) {
+///     for (a, b) in row.iter().zip(expected) {
+///         assert!((a - b).norm() < 1e-12);
+///     }
+/// }
+/// ```
+pub fn u3(theta: f64, phi: f64, lambda: f64) -> Gate {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    Gate::new(vec![
+        vec![Complex::new(cos, 0.0), -Complex::from_polar(sin, lambda)],
+        vec![
+            Complex::from_polar(sin, phi),
+            Complex::from_polar(cos, phi + lambda),
+        ],
+    ])
+}
+
+/// Returns a rotation by `angle` about the axis `(nx, ny, nz)` of the Bloch sphere,
+/// `exp(-i angle (nx X + ny Y + nz Z) / 2)`. The axis is normalized first.
+///
+/// # Arguments
+///
+/// * `nx`, `ny`, `nz` - The components of the rotation axis.
+/// * `angle` - The rotation angle.
+///
+/// # Panics
+///
+/// Panics if the axis has zero length.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{rotation_about_axis, ry};
+/// use std::f64::consts::PI;
+///
+/// let gate = rotation_about_axis(0.0, 2.0, 0.0, 0.3);
+/// for (row, expected) in gate.matrix.iter().zip(ry(0.3).matrix.iter()) {
+///     for (a, b) in row.iter().zip(expected) {
+///         assert!((a - b).norm() < 1e-12);
+///     }
+/// }
+///
+/// // A half turn about (X + Z)/√2 is the Hadamard gate up to a global phase of -i
+/// let gate = rotation_about_axis(1.0, 0.0, 1.0, PI);
+/// let h = 1.0 / 2.0_f64.sqrt();
+/// assert!((gate.matrix[0][0].im + h).abs() < 1e-12);
+/// assert!((gate.matrix[1][0].im + h).abs() < 1e-12);
+/// ```
+pub fn rotation_about_axis(nx: f64, ny: f64, nz: f64, angle: f64) -> Gate {
+    let norm = (nx * nx + ny * ny + nz * nz).sqrt();
+    assert!(norm > 0.0, "the rotation axis must not be zero");
+    let (nx, ny, nz) = (nx / norm, ny / norm, nz / norm);
+    let (sin, cos) = (angle / 2.0).sin_cos();
+    // cos(a/2) I - i sin(a/2) (nx X + ny Y + nz Z)
+    Gate::new(vec![
+        vec![
+            Complex::new(cos, -sin * nz),
+            Complex::new(-sin * ny, -sin * nx),
+        ],
+        vec![
+            Complex::new(sin * ny, -sin * nx),
+            Complex::new(cos, sin * nz),
+        ],
+    ])
+}
+
+/// Returns a T gate, the π/4 phase gate.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::t;
+/// use num_complex::Complex;
+///
+/// let gate = t();
+/// let h = 1.0 / 2.0_f64.sqrt();
+/// assert_eq!(gate.matrix[0][0], Complex::new(1.0, 0.0));
+/// assert!((gate.matrix[1][1] - Complex::new(h, h)).norm() < 1e-12);
+/// ```
+pub fn t() -> Gate {
+    phase(std::f64::consts::PI / 4.0)
+}
+
+/// Returns a SWAP gate exchanging qubits `a` and `b` in a multi-qubit system.
+///
+/// # Arguments
+///
+/// * `a` - The first qubit index.
+/// * `b` - The second qubit index.
+/// * `num_qubits` - The total number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::swap;
+/// use num_complex::Complex;
+///
+/// let gate = swap(0, 1, 2);
+/// // |01⟩ (index 1) and |10⟩ (index 2) are exchanged.
+/// assert_eq!(gate.matrix[2][1], Complex::new(1.0, 0.0));
+/// assert_eq!(gate.matrix[1][2], Complex::new(1.0, 0.0));
+/// assert_eq!(gate.matrix[3][3], Complex::new(1.0, 0.0));
+/// ```
+pub fn swap(a: usize, b: usize, num_qubits: usize) -> Gate {
+    permutation_gate(num_qubits, |i| {
+        if ((i >> a) & 1) != ((i >> b) & 1) {
+            i ^ (1 << a) ^ (1 << b)
+        } else {
+            i
+        }
+    })
+}
+
+/// Returns a Toffoli (CCNOT) gate for the given controls and target in a multi-qubit system.
+///
+/// # Arguments
+///
+/// * `control1` - The first control qubit index.
+/// * `control2` - The second control qubit index.
+/// * `target` - The target qubit index.
+/// * `num_qubits` - The total number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::toffoli;
+/// use num_complex::Complex;
+///
+/// let gate = toffoli(0, 1, 2, 3);
+/// // |011⟩ (index 3) maps to |111⟩ (index 7); |001⟩ is unchanged.
+/// assert_eq!(gate.matrix[7][3], Complex::new(1.0, 0.0));
+/// assert_eq!(gate.matrix[1][1], Complex::new(1.0, 0.0));
+/// ```
+pub fn toffoli(control1: usize, control2: usize, target: usize, num_qubits: usize) -> Gate {
+    permutation_gate(num_qubits, |i| {
+        if (i >> control1) & 1 == 1 && (i >> control2) & 1 == 1 {
+            i ^ (1 << target)
+        } else {
+            i
+        }
+    })
+}
+
+/// Returns a multi-controlled X gate, the generalized Toffoli, which flips `target` when every
+/// qubit in `controls` is set.
+///
+/// # Arguments
+///
+/// * `controls` - The control qubit indices; none gives an X gate, one a CNOT, two a Toffoli.
+/// * `target` - The target qubit index.
+/// * `num_qubits` - The total number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{cnot, mcx, toffoli};
+/// use num_complex::Complex;
+///
+/// assert_eq!(mcx(&[0, 1], 2, 3), toffoli(0, 1, 2, 3));
+/// assert_eq!(mcx(&[1], 0, 2), cnot(1, 0, 2));
+///
+/// let gate = mcx(&[0, 1, 2], 3, 4);
+/// // |0111⟩ (index 7) maps to |1111⟩ (index 15); |0011⟩ is unchanged.
+/// assert_eq!(gate.matrix[15][7], Complex::new(1.0, 0.0));
+/// assert_eq!(gate.matrix[3][3], Complex::new(1.0, 0.0));
+/// ```
+pub fn mcx(controls: &[usize], target: usize, num_qubits: usize) -> Gate {
+    let control_mask: usize = controls.iter().map(|&control| 1 << control).sum();
+    permutation_gate(num_qubits, |i| {
+        if i & control_mask == control_mask {
+            i ^ (1 << target)
+        } else {
+            i
+        }
+    })
+}
+
+/// Returns a multi-controlled phase gate, which multiplies the states with `target` and every
+/// qubit in `controls` set by `e^{iθ}`. Like [`cz`], it is symmetric in all its qubits.
+///
+/// # Arguments
+///
+/// * `theta` - The phase angle in radians.
+/// * `controls` - The control qubit indices; none gives a [`phase`] gate.
+/// * `target` - The target qubit index.
+/// * `num_qubits` - The total number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{mcp, phase};
+/// use num_complex::Complex;
+/// use std::f64::consts::PI;
+///
+/// let gate = mcp(PI / 2.0, &[0, 1], 2, 3);
+/// assert_eq!(gate, phase(PI / 2.0).multi_controlled(&[0, 1], 2, 3).unwrap());
+/// assert_eq!(gate, mcp(PI / 2.0, &[1, 2], 0, 3));
+/// assert_eq!(gate.matrix[3][3], Complex::new(1.0, 0.0));
+/// ```
+pub fn mcp(theta: f64, controls: &[usize], target: usize, num_qubits: usize) -> Gate {
+    let mask: usize = controls.iter().map(|&control| 1 << control).sum::<usize>() | 1 << target;
+    diagonal_gate(num_qubits, |i| {
+        if i & mask == mask {
+            Complex::new(theta.cos(), theta.sin())
+        } else {
+            Complex::new(1.0, 0.0)
+        }
+    })
+}
+
+/// Returns a controlled-Z gate for the given control and target in a multi-qubit system.
+///
+/// # Arguments
+///
+/// * `control` - The control qubit index.
+/// * `target` - The target qubit index.
+/// * `num_qubits` - The total number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::cz;
+/// use num_complex::Complex;
+///
+/// let gate = cz(0, 1, 2);
+/// assert_eq!(gate.matrix[3][3], Complex::new(-1.0, 0.0));
+/// assert_eq!(gate.matrix[1][1], Complex::new(1.0, 0.0));
+/// ```
+pub fn cz(control: usize, target: usize, num_qubits: usize) -> Gate {
+    diagonal_gate(num_qubits, |i| {
+        if (i >> control) & 1 == 1 && (i >> target) & 1 == 1 {
+            Complex::new(-1.0, 0.0)
+        } else {
+            Complex::new(1.0, 0.0)
+        }
+    })
+}
+
+/// Returns an iSWAP gate on qubits `a` and `b`, which swaps them and adds a phase of `i` to the
+/// exchanged components.
+///
+/// # Arguments
+///
+/// * `a` - The first qubit index.
+/// * `b` - The second qubit index.
+/// * `num_qubits` - The total number of qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::iswap;
+/// use num_complex::Complex;
+///
+/// let gate = iswap(0, 1, 2);
+/// assert_eq!(gate.matrix[2][1], Complex::new(0.0, 1.0));
+/// assert_eq!(gate.matrix[1][2], Complex::new(0.0, 1.0));
+/// assert_eq!(gate.matrix[0][0], Complex::new(1.0, 0.0));
+/// ```
+pub fn iswap(a: usize, b: usize, num_qubits: usize) -> Gate {
+    let mut gate = swap(a, b, num_qubits);
+    for (i, row) in Arc::make_mut(&mut gate.matrix).iter_mut().enumerate() {
+        if ((i >> a) & 1) != ((i >> b) & 1) {
+            for elem in row.iter_mut() {
+                *elem *= Complex::new(0.0, 1.0);
             }
         }
     }
+    gate
+}
 
+/// Builds the gate that maps basis state `i` to basis state `map(i)`.
+fn permutation_gate(num_qubits: usize, map: impl Fn(usize) -> usize) -> Gate {
+    let size = 2usize.pow(num_qubits as u32);
+    let mut matrix = vec![vec![Complex::new(0.0, 0.0); size]; size];
+    for i in 0..size {
+        matrix[map(i)][i] = Complex::new(1.0, 0.0);
+    }
+    Gate::new(matrix)
+}
+
+/// Builds the diagonal gate that multiplies basis state `i` by `phase(i)`.
+pub(crate) fn diagonal_gate(num_qubits: usize, phase: impl Fn(usize) -> Complex<f64>) -> Gate {
+    let size = 2usize.pow(num_qubits as u32);
+    let mut matrix = vec![vec![Complex::new(0.0, 0.0); size]; size];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] = phase(i);
+    }
     Gate::new(matrix)
 }
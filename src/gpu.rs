@@ -0,0 +1,381 @@
+//! This module provides a GPU state-vector backend built on wgpu compute shaders.
+//!
+//! The state lives in a storage buffer and every gate is applied in place by a single- or
+//! two-qubit kernel. Gates are batched into one submission until the state is needed on the CPU,
+//! for a measurement, an expectation value or [`Backend::state`], where it is read back. The GPU
+//! works in single precision, so amplitudes agree with [`crate::backend::CpuBackend`] to about
+//! `1e-6`.
+
+use crate::backend::{self, Backend};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::precision::StateVector32;
+use crate::qubit::Qubit;
+use num_complex::Complex;
+use rand::RngCore;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{mpsc, Arc};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+/// The size in bytes of one amplitude on the GPU, two `f32`s.
+const AMPLITUDE_SIZE: u64 = 8;
+/// The size in bytes of the kernel parameters: four `u32`s and a 4x4 complex matrix.
+const PARAMS_SIZE: u64 = 16 + 16 * AMPLITUDE_SIZE;
+/// The number of invocations per workgroup, matching `@workgroup_size` in the shader.
+const WORKGROUP_SIZE: u32 = 64;
+/// The largest number of workgroups per dispatch dimension guaranteed by WebGPU.
+const MAX_WORKGROUPS_PER_DIMENSION: u32 = 65535;
+
+/// The `GpuBackend` holds a state vector on the first available GPU adapter.
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    single_qubit: wgpu::ComputePipeline,
+    two_qubit: wgpu::ComputePipeline,
+    name: String,
+    num_qubits: usize,
+    state: wgpu::Buffer,
+    /// The gates applied since the last submission, as `(number of targets, parameters)`.
+    pending: RefCell<Vec<(usize, Vec<u8>)>>,
+}
+
+impl GpuBackend {
+    /// Connects to the highest-performance GPU adapter and prepares `num_qubits` qubits in
+    /// `|0…0⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register.
+    ///
+    /// # Returns
+    ///
+    /// * The backend, or `QuantumError::BackendUnavailable` if no adapter or device is
+    ///   available, e.g. on a headless machine without GPU drivers, or
+    ///   `QuantumError::Unsupported` if the state does not fit the GPU's storage buffers.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use quantum_simulator::backend::{Backend, CpuBackend};
+    /// use quantum_simulator::gpu::GpuBackend;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// // Fall back to the CPU when there is no GPU
+    /// let backend: Box<dyn Backend<State = Qubit>> = match GpuBackend::new(20) {
+    ///     Ok(gpu) => Box::new(gpu),
+    ///     Err(_) => Box::new(CpuBackend::new(20)),
+    /// };
+    /// println!("running on {}", backend.name());
+    /// ```
+    pub fn new(num_qubits: usize) -> Result<Self, QuantumError> {
+        let mut state = vec![Complex::new(0.0, 0.0); 1 << num_qubits];
+        state[0] = Complex::new(1.0, 0.0);
+        Self::from_state(&Qubit::from_state(state))
+    }
+
+    /// Connects to the highest-performance GPU adapter and uploads `qubit` as the initial state.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The initial state of the register.
+    ///
+    /// # Returns
+    ///
+    /// * The backend, or an error as for [`GpuBackend::new`].
+    pub fn from_state(qubit: &Qubit) -> Result<Self, QuantumError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .ok_or_else(|| QuantumError::BackendUnavailable {
+            reason: "no GPU adapter found".to_string(),
+        })?;
+        let (device, queue) = block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("quantum_simulator"),
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+            },
+            None,
+        ))
+        .map_err(|error| QuantumError::BackendUnavailable {
+            reason: error.to_string(),
+        })?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("gate kernels"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("gate kernels"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(PARAMS_SIZE),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("gate kernels"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+                compilation_options: Default::default(),
+            })
+        };
+        let single_qubit = pipeline("apply_1q");
+        let two_qubit = pipeline("apply_2q");
+
+        let limits = device.limits();
+        let state_size = qubit.state.len() as u64 * AMPLITUDE_SIZE;
+        if state_size > limits.max_storage_buffer_binding_size as u64
+            || state_size > limits.max_buffer_size
+        {
+            return Err(QuantumError::Unsupported {
+                feature: format!(
+                    "a {}-qubit state on this GPU's storage buffers",
+                    qubit.num_qubits()
+                ),
+            });
+        }
+        let state = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("state"),
+            size: state_size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut backend = GpuBackend {
+            name: format!("gpu ({})", adapter.get_info().name),
+            device,
+            queue,
+            layout,
+            single_qubit,
+            two_qubit,
+            num_qubits: qubit.num_qubits(),
+            state,
+            pending: RefCell::new(vec![]),
+        };
+        backend.upload(qubit);
+        Ok(backend)
+    }
+
+    /// Overwrites the state on the GPU with `qubit`.
+    fn upload(&mut self, qubit: &Qubit) {
+        self.pending.borrow_mut().clear();
+        let bytes = StateVector32::from_qubit(qubit).to_le_bytes();
+        self.queue.write_buffer(&self.state, 0, &bytes);
+    }
+
+    /// Submits the pending gates as one compute pass.
+    fn flush(&self) {
+        let pending = self.pending.take();
+        if pending.is_empty() {
+            return;
+        }
+        // Every gate's parameters live in one uniform buffer, bound at its own offset
+        let stride = (PARAMS_SIZE as u32)
+            .next_multiple_of(self.device.limits().min_uniform_buffer_offset_alignment);
+        let mut params = vec![0; pending.len() * stride as usize];
+        for ((_, bytes), chunk) in pending.iter().zip(params.chunks_mut(stride as usize)) {
+            chunk[..bytes.len()].copy_from_slice(bytes);
+        }
+        let uniforms = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gate parameters"),
+            size: params.len() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&uniforms, 0, &params);
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("gate kernels"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.state.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &uniforms,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(PARAMS_SIZE),
+                    }),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("gates"),
+                timestamp_writes: None,
+            });
+            for (index, (arity, _)) in pending.iter().enumerate() {
+                let pipeline = match arity {
+                    1 => &self.single_qubit,
+                    _ => &self.two_qubit,
+                };
+                let groups = (1u32 << self.num_qubits) >> arity;
+                let workgroups = groups.div_ceil(WORKGROUP_SIZE);
+                let x = workgroups.min(MAX_WORKGROUPS_PER_DIMENSION);
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, &bind_group, &[index as u32 * stride]);
+                pass.dispatch_workgroups(x, workgroups.div_ceil(x), 1);
+            }
+        }
+        self.queue.submit([encoder.finish()]);
+    }
+
+    /// Runs the pending gates and reads the state back from the GPU.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device is lost while reading back.
+    fn download(&self) -> Qubit {
+        self.flush();
+        let size = self.state.size();
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&self.state, 0, &readback, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let slice = readback.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .expect("the readback callback runs during poll")
+            .expect("failed to read the state back from the GPU");
+
+        let state = StateVector32::from_le_bytes(&slice.get_mapped_range());
+        state.to_qubit()
+    }
+}
+
+impl Backend for GpuBackend {
+    type State = Qubit;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Queues a single- or two-qubit gate; gates on three or more qubits are rejected with
+    /// `QuantumError::Unsupported`.
+    fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+        backend::check_targets(gate, targets, self.num_qubits)?;
+        if !(1..=2).contains(&targets.len()) {
+            return Err(QuantumError::Unsupported {
+                feature: format!("{}-qubit gates on the GPU backend", targets.len()),
+            });
+        }
+        let info = [
+            targets[0] as u32,
+            targets.get(1).map_or(0, |&qubit| qubit as u32),
+            ((1usize << self.num_qubits) >> targets.len()) as u32,
+            0,
+        ];
+        let entries = gate
+            .matrix
+            .iter()
+            .flatten()
+            .flat_map(|entry| [entry.re as f32, entry.im as f32]);
+        let bytes = info
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .chain(entries.flat_map(f32::to_le_bytes))
+            .collect();
+        self.pending.borrow_mut().push((targets.len(), bytes));
+        Ok(())
+    }
+
+    fn measure_with_rng(&mut self, qubit: usize, rng: &mut dyn RngCore) -> usize {
+        let mut state = self.download();
+        let outcome = state.measure_qubit_with_rng(qubit, rng);
+        self.upload(&state);
+        outcome
+    }
+
+    fn expectation(&self, observable: &Gate, targets: &[usize]) -> f64 {
+        backend::local_expectation(&self.download(), observable, targets)
+    }
+
+    fn state(&self) -> Qubit {
+        self.download()
+    }
+
+    fn amplitudes(&self) -> Option<Vec<Complex<f64>>> {
+        Some(self.download().state)
+    }
+}
+
+/// Drives a future to completion on the current thread.
+///
+/// wgpu's native adapter and device requests resolve without an executor, so parking the
+/// thread until woken is enough.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// State-vector gate kernels for the wgpu backend.
+//
+// Amplitudes are stored as `vec2<f32>` (real, imaginary) with qubit `k` as bit `k` of the index.
+// Each invocation updates one group of amplitudes that differ only in the target bits.
+
+struct Params {
+    // x: target of local bit 0, y: target of local bit 1, z: number of groups
+    info: vec4<u32>,
+    // The row-major gate matrix, two complex entries per vector
+    matrix: array<vec4<f32>, 8>,
+}
+
+@group(0) @binding(0) var<storage, read_write> state: array<vec2<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+
+fn entry(index: u32) -> vec2<f32> {
+    let pair = params.matrix[index / 2u];
+    if (index % 2u == 0u) {
+        return pair.xy;
+    }
+    return pair.zw;
+}
+
+fn cmul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// Inserts a zero bit at position `bit` of `index`.
+fn insert_zero(index: u32, bit: u32) -> u32 {
+    let low = index & ((1u << bit) - 1u);
+    return ((index >> bit) << (bit + 1u)) | low;
+}
+
+// Dispatches larger than 65535 workgroups are spread over the y dimension.
+fn group_index(id: vec3<u32>, workgroups: vec3<u32>) -> u32 {
+    return id.x + id.y * workgroups.x * 64u;
+}
+
+@compute @workgroup_size(64)
+fn apply_1q(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let group = group_index(id, workgroups);
+    if (group >= params.info.z) {
+        return;
+    }
+    let i0 = insert_zero(group, params.info.x);
+    let i1 = i0 | (1u << params.info.x);
+    let a0 = state[i0];
+    let a1 = state[i1];
+    state[i0] = cmul(entry(0u), a0) + cmul(entry(1u), a1);
+    state[i1] = cmul(entry(2u), a0) + cmul(entry(3u), a1);
+}
+
+@compute @workgroup_size(64)
+fn apply_2q(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let group = group_index(id, workgroups);
+    if (group >= params.info.z) {
+        return;
+    }
+    let t0 = params.info.x;
+    let t1 = params.info.y;
+    let base = insert_zero(insert_zero(group, min(t0, t1)), max(t0, t1));
+    var indices = array<u32, 4>(
+        base,
+        base | (1u << t0),
+        base | (1u << t1),
+        base | (1u << t0) | (1u << t1),
+    );
+    var local = array<vec2<f32>, 4>(
+        state[indices[0]],
+        state[indices[1]],
+        state[indices[2]],
+        state[indices[3]],
+    );
+    for (var row = 0u; row < 4u; row++) {
+        var sum = vec2<f32>(0.0, 0.0);
+        for (var col = 0u; col < 4u; col++) {
+            sum += cmul(entry(row * 4u + col), local[col]);
+        }
+        state[indices[row]] = sum;
+    }
+}
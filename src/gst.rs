@@ -0,0 +1,226 @@
+//! This module implements a lightweight, single-qubit gate set tomography (GST) routine.
+//!
+//! The routine follows linear-inversion GST (LGST): a fixed set of fiducial preparations and
+//! measurements brackets every gate, and the resulting probability tables are inverted with the
+//! Gram matrix of the fiducials. Fiducials and SPAM are taken to be ideal, which fixes the gauge to
+//! the ideal frame so the estimates can be compared directly with the ideal gates.
+
+use crate::gates::Gate;
+use crate::linalg;
+use crate::noise::KrausChannel;
+use num_complex::Complex;
+
+/// A `GateSetEntry` pairs a named ideal gate with the noisy channel that implements it.
+pub struct GateSetEntry {
+    pub name: String,
+    pub ideal: Gate,
+    pub noisy: KrausChannel,
+}
+
+impl GateSetEntry {
+    /// Creates a new `GateSetEntry`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A label for the gate, used in the resulting estimates.
+    /// * `ideal` - The ideal single-qubit gate.
+    /// * `noisy` - The channel actually applied when the gate is executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::gst::GateSetEntry;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let entry = GateSetEntry::new("X", pauli_x(), KrausChannel::from_gate(&pauli_x()));
+    /// assert_eq!(entry.name, "X");
+    /// ```
+    pub fn new(name: &str, ideal: Gate, noisy: KrausChannel) -> Self {
+        GateSetEntry {
+            name: name.to_string(),
+            ideal,
+            noisy,
+        }
+    }
+}
+
+/// A `GateEstimate` holds the GST estimate of one gate alongside its ideal counterpart.
+///
+/// Process matrices are Pauli transfer matrices (PTMs) in the `I`, `X`, `Y`, `Z` basis.
+#[derive(Clone, Debug)]
+pub struct GateEstimate {
+    pub name: String,
+    pub estimated: Vec<Vec<f64>>,
+    pub ideal: Vec<Vec<f64>>,
+    pub process_fidelity: f64,
+    pub average_gate_fidelity: f64,
+    pub frobenius_distance: f64,
+}
+
+/// Runs linear-inversion GST on a single-qubit gate set using exact outcome probabilities.
+///
+/// # Arguments
+///
+/// * `gate_set` - The gates to characterize, each with its ideal and noisy implementation.
+///
+/// # Returns
+///
+/// * One `GateEstimate` per entry, in the same order as `gate_set`.
+///
+/// # Panics
+///
+/// Panics if any ideal gate or noisy channel does not act on a single qubit.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{hadamard, pauli_x};
+/// use quantum_simulator::gst::{gst_lite, GateSetEntry};
+/// use quantum_simulator::noise::KrausChannel;
+/// use num_complex::Complex;
+///
+/// // An X gate that fails (acts as identity) with probability 0.1.
+/// let p: f64 = 0.1;
+/// let noisy_x = KrausChannel::new(vec![
+///     vec![
+///         vec![Complex::new(0.0, 0.0), Complex::new((1.0 - p).sqrt(), 0.0)],
+///         vec![Complex::new((1.0 - p).sqrt(), 0.0), Complex::new(0.0, 0.0)],
+///     ],
+///     vec![
+///         vec![Complex::new(p.sqrt(), 0.0), Complex::new(0.0, 0.0)],
+///         vec![Complex::new(0.0, 0.0), Complex::new(p.sqrt(), 0.0)],
+///     ],
+/// ]);
+///
+/// let estimates = gst_lite(&[
+///     GateSetEntry::new("H", hadamard(1), KrausChannel::from_gate(&hadamard(1))),
+///     GateSetEntry::new("X", pauli_x(), noisy_x),
+/// ]);
+/// assert!((estimates[0].process_fidelity - 1.0).abs() < 1e-9);
+/// assert!((estimates[1].process_fidelity - 0.9).abs() < 1e-9);
+/// ```
+pub fn gst_lite(gate_set: &[GateSetEntry]) -> Vec<GateEstimate> {
+    let preparations = fiducial_states();
+    let effects = fiducial_states();
+
+    // Gram matrix g[j][i] = Tr(E_j ρ_i).
+    let gram: Vec<Vec<f64>> = effects
+        .iter()
+        .map(|effect| {
+            preparations
+                .iter()
+                .map(|rho| probability(effect, rho))
+                .collect()
+        })
+        .collect();
+    let gram_inverse =
+        linalg::invert_real(&gram).expect("fiducials must be informationally complete");
+
+    // Columns of `prep_basis` are the Pauli vectors of the ideal preparations.
+    let prep_vectors: Vec<Vec<f64>> = preparations.iter().map(|rho| pauli_vector(rho)).collect();
+    let prep_basis = transpose(&prep_vectors);
+    let prep_basis_inverse =
+        linalg::invert_real(&prep_basis).expect("fiducials must be informationally complete");
+
+    gate_set
+        .iter()
+        .map(|entry| {
+            assert_eq!(
+                entry.ideal.matrix.len(),
+                2,
+                "GST lite only supports single-qubit gates"
+            );
+            assert_eq!(
+                entry.noisy.dimension(),
+                2,
+                "GST lite only supports single-qubit channels"
+            );
+
+            let outcomes: Vec<Vec<f64>> = effects
+                .iter()
+                .map(|effect| {
+                    preparations
+                        .iter()
+                        .map(|rho| probability(effect, &entry.noisy.apply(rho)))
+                        .collect()
+                })
+                .collect();
+
+            let estimated = real_matmul(
+                &real_matmul(&real_matmul(&prep_basis, &gram_inverse), &outcomes),
+                &prep_basis_inverse,
+            );
+            let ideal = KrausChannel::from_gate(&entry.ideal).to_ptm();
+
+            let overlap: f64 = ideal
+                .iter()
+                .zip(&estimated)
+                .map(|(a, b)| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>())
+                .sum();
+            let process_fidelity = overlap / 4.0;
+            let frobenius_distance = ideal
+                .iter()
+                .zip(&estimated)
+                .map(|(a, b)| a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f64>())
+                .sum::<f64>()
+                .sqrt();
+
+            GateEstimate {
+                name: entry.name.clone(),
+                estimated,
+                ideal,
+                process_fidelity,
+                average_gate_fidelity: (2.0 * process_fidelity + 1.0) / 3.0,
+                frobenius_distance,
+            }
+        })
+        .collect()
+}
+
+/// Returns the fiducial density matrices `|0⟩`, `|1⟩`, `|+⟩` and `|+i⟩`.
+fn fiducial_states() -> Vec<Vec<Vec<Complex<f64>>>> {
+    let h = 1.0 / (2.0_f64).sqrt();
+    let kets = [
+        [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+        [Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+        [Complex::new(h, 0.0), Complex::new(h, 0.0)],
+        [Complex::new(h, 0.0), Complex::new(0.0, h)],
+    ];
+    kets.iter()
+        .map(|ket| {
+            ket.iter()
+                .map(|a| ket.iter().map(|b| a * b.conj()).collect())
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns `Tr(E ρ)` for Hermitian `E` and `ρ`.
+fn probability(effect: &[Vec<Complex<f64>>], rho: &[Vec<Complex<f64>>]) -> f64 {
+    linalg::trace(&linalg::matmul(effect, rho)).re
+}
+
+/// Returns the Pauli coordinates `Tr(P_i ρ)` of a single-qubit operator.
+fn pauli_vector(rho: &[Vec<Complex<f64>>]) -> Vec<f64> {
+    linalg::paulis()
+        .iter()
+        .map(|p| linalg::trace(&linalg::matmul(p, rho)).re)
+        .collect()
+}
+
+fn transpose(m: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    (0..m[0].len())
+        .map(|j| m.iter().map(|row| row[j]).collect())
+        .collect()
+}
+
+fn real_matmul(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    a.iter()
+        .map(|row| {
+            (0..b[0].len())
+                .map(|j| row.iter().zip(b).map(|(x, b_row)| x * b_row[j]).sum())
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,249 @@
+//! This module defines the `Hooks` callbacks that observe a running simulation.
+//!
+//! Hooks receive read-only access to the state at fixed points of the simulator loop, so
+//! telemetry, plotting or early stopping can be attached without reimplementing the loop.
+
+use crate::circuit::{Instruction, Operation};
+use crate::qubit::Qubit;
+use std::ops::ControlFlow;
+
+type GateHook<'a> = Box<dyn FnMut(usize, &Instruction, &Qubit) + 'a>;
+type MeasurementHook<'a> = Box<dyn FnMut(&[usize], usize, &Qubit) + 'a>;
+type ShotHook<'a> = Box<dyn FnMut(usize, usize, &Qubit) -> ControlFlow<()> + 'a>;
+type EventHook<'a> = Box<dyn FnMut(&SimulationEvent) + 'a>;
+
+/// A `SimulationEvent` is something that happened inside the simulator loop, passed to the
+/// observers registered with [`Hooks::on_event`] or
+/// [`Simulator::run_with_observer`](crate::simulator::Simulator::run_with_observer).
+///
+/// Events borrow the state at the moment they are emitted; observers that need it later must
+/// clone it.
+#[derive(Clone, Copy, Debug)]
+pub enum SimulationEvent<'s> {
+    /// A unitary gate and the noise attached to it were applied.
+    GateApplied {
+        /// The index of the instruction in the circuit.
+        instruction: usize,
+        /// The gate that was applied, unwrapped from its condition if it was classically
+        /// controlled.
+        operation: &'s Operation,
+        /// The qubits the gate acted on.
+        qubits: &'s [usize],
+        /// The state after the gate.
+        state: &'s Qubit,
+    },
+    /// A qubit was measured or reset.
+    MeasurementTaken {
+        /// The index of the instruction in the circuit.
+        instruction: usize,
+        /// The measured qubit.
+        qubit: usize,
+        /// The classical bit the outcome was written to, or `None` for a reset.
+        clbit: Option<usize>,
+        /// The measured outcome, `0` or `1`.
+        outcome: usize,
+        /// The state after the collapse, and after the flip back to `|0⟩` for a reset.
+        state: &'s Qubit,
+    },
+    /// A snapshot instruction was reached.
+    StateSnapshot {
+        /// The index of the instruction in the circuit.
+        instruction: usize,
+        /// The snapshot's label.
+        label: &'s str,
+        /// The state at the snapshot.
+        state: &'s Qubit,
+    },
+}
+
+impl SimulationEvent<'_> {
+    /// Returns the index of the instruction that emitted the event.
+    pub fn instruction(&self) -> usize {
+        match self {
+            SimulationEvent::GateApplied { instruction, .. }
+            | SimulationEvent::MeasurementTaken { instruction, .. }
+            | SimulationEvent::StateSnapshot { instruction, .. } => *instruction,
+        }
+    }
+
+    /// Returns the state at the moment of the event.
+    pub fn state(&self) -> &Qubit {
+        match self {
+            SimulationEvent::GateApplied { state, .. }
+            | SimulationEvent::MeasurementTaken { state, .. }
+            | SimulationEvent::StateSnapshot { state, .. } => state,
+        }
+    }
+}
+
+/// `Hooks` is a set of callbacks invoked by [`Simulator`](crate::simulator::Simulator) while it
+/// runs a circuit.
+///
+/// Callbacks may borrow local state for the lifetime `'a`, e.g. to collect telemetry.
+#[derive(Default)]
+pub struct Hooks<'a> {
+    gate_applied: Vec<GateHook<'a>>,
+    measurement: Vec<MeasurementHook<'a>>,
+    shot_complete: Vec<ShotHook<'a>>,
+    events: Vec<EventHook<'a>>,
+}
+
+impl<'a> Hooks<'a> {
+    /// Creates an empty set of hooks.
+    pub fn new() -> Self {
+        Hooks::default()
+    }
+
+    /// Registers a callback run after every instruction and its noise.
+    ///
+    /// The callback receives the instruction's index in the circuit, the instruction and the
+    /// state after it was applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::hooks::Hooks;
+    /// use quantum_simulator::noise::NoiseModel;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.h(0).h(0);
+    ///
+    /// let mut populations = vec![];
+    /// let mut hooks = Hooks::new();
+    /// hooks.on_gate_applied(|_, _, qubit| populations.push(qubit.state[1].norm_sqr()));
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// Simulator::run_with_hooks(&circuit, &initial_state, &NoiseModel::new(), &mut hooks);
+    /// drop(hooks);
+    /// assert!((populations[0] - 0.5).abs() < 1e-12 && populations[1] < 1e-12);
+    /// ```
+    pub fn on_gate_applied(
+        &mut self,
+        hook: impl FnMut(usize, &Instruction, &Qubit) + 'a,
+    ) -> &mut Self {
+        self.gate_applied.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a callback run on every measurement.
+    ///
+    /// The callback receives the measured qubits, the outcome (bit `j` is the result of the
+    /// `j`-th measured qubit) and the state before the measurement.
+    pub fn on_measurement(&mut self, hook: impl FnMut(&[usize], usize, &Qubit) + 'a) -> &mut Self {
+        self.measurement.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a callback run when a shot of a sampling campaign finishes.
+    ///
+    /// The callback receives the shot index, the measured outcome and the final state of the
+    /// shot. Returning `ControlFlow::Break(())` stops the campaign after this shot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::hooks::Hooks;
+    /// use quantum_simulator::noise::NoiseModel;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    /// use std::ops::ControlFlow;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0);
+    ///
+    /// let mut hooks = Hooks::new();
+    /// hooks.on_shot_complete(|shot, _, _| {
+    ///     if shot == 9 {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// });
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let counts =
+    ///     Simulator::sample_with_hooks(&circuit, &initial_state, &NoiseModel::new(), 1000, &mut hooks);
+    /// assert_eq!(counts.shots(), 10);
+    /// ```
+    pub fn on_shot_complete(
+        &mut self,
+        hook: impl FnMut(usize, usize, &Qubit) -> ControlFlow<()> + 'a,
+    ) -> &mut Self {
+        self.shot_complete.push(Box::new(hook));
+        self
+    }
+
+    /// Registers an observer of gates, measurements and snapshots as they happen.
+    ///
+    /// Unlike [`Hooks::on_gate_applied`], which runs after every instruction, the observer
+    /// only sees what actually changed or exposed the state: branches not taken, barriers and
+    /// assertions emit nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::hooks::{Hooks, SimulationEvent};
+    /// use quantum_simulator::noise::NoiseModel;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::with_clbits(1, 1);
+    /// circuit.x(0).barrier(&[0]).measure(0, 0);
+    ///
+    /// let mut outcomes = vec![];
+    /// let mut hooks = Hooks::new();
+    /// hooks.on_event(|event| {
+    ///     if let SimulationEvent::MeasurementTaken { outcome, .. } = event {
+    ///         outcomes.push(*outcome);
+    ///     }
+    /// });
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// Simulator::run_with_hooks(&circuit, &initial_state, &NoiseModel::new(), &mut hooks);
+    /// drop(hooks);
+    /// assert_eq!(outcomes, vec![1]);
+    /// ```
+    pub fn on_event(&mut self, hook: impl FnMut(&SimulationEvent) + 'a) -> &mut Self {
+        self.events.push(Box::new(hook));
+        self
+    }
+
+    pub(crate) fn gate_applied(&mut self, index: usize, instruction: &Instruction, qubit: &Qubit) {
+        for hook in self.gate_applied.iter_mut() {
+            hook(index, instruction, qubit);
+        }
+    }
+
+    pub(crate) fn measurement(&mut self, qubits: &[usize], outcome: usize, qubit: &Qubit) {
+        for hook in self.measurement.iter_mut() {
+            hook(qubits, outcome, qubit);
+        }
+    }
+
+    pub(crate) fn event(&mut self, event: SimulationEvent) {
+        for hook in self.events.iter_mut() {
+            hook(&event);
+        }
+    }
+
+    /// Runs every shot hook and breaks if any of them asks to stop.
+    pub(crate) fn shot_complete(
+        &mut self,
+        shot: usize,
+        outcome: usize,
+        qubit: &Qubit,
+    ) -> ControlFlow<()> {
+        let mut flow = ControlFlow::Continue(());
+        for hook in self.shot_complete.iter_mut() {
+            if hook(shot, outcome, qubit).is_break() {
+                flow = ControlFlow::Break(());
+            }
+        }
+        flow
+    }
+}
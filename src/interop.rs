@@ -0,0 +1,657 @@
+//! This module imports circuits drawn in Quirk or serialized by Cirq.
+//!
+//! [`from_quirk`] reads the JSON behind a Quirk link, or the link itself, and
+//! [`from_cirq_json`] reads the output of `cirq.to_json`. Their gates are mapped onto this
+//! crate's operations, controlled and fractional-power gates becoming `Unitary` operations where
+//! no native operation matches, and a gate with no counterpart is rejected with
+//! `QuantumError::Unsupported` naming it, so a circuit prototyped in either tool replays here
+//! exactly or not at all.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::error::QuantumError;
+use crate::gates::{pauli_z, swap, Gate};
+use crate::tolerance;
+use num_complex::Complex;
+use serde_json::Value;
+use std::f64::consts::PI;
+
+/// Prefixes of Quirk's display gates, which show the state without changing it.
+const QUIRK_DISPLAYS: [&str; 5] = ["Amps", "Bloch", "Chance", "Density", "Sample"];
+
+/// Parses a Quirk circuit into a `Circuit`.
+///
+/// Wire `k` becomes qubit `k`, counted from the top. The `init` states `0`, `1`, `+`, `-`, `i`
+/// and `-i` are prepared with gates at the start. Controls `•` and anti-controls `◦` apply to
+/// every gate in their column, `Measure` measures wire `k` into classical bit `k`, and display
+/// gates such as `Bloch` or `Chance` are skipped. Besides `H`, the Paulis and `Swap`, the
+/// fractional powers such as `Z^¼` or `X^-½` and the formula gates `Rxft`, `Ryft`, `Rzft`,
+/// `X^ft`, `Y^ft` and `Z^ft` are supported when their formula is a constant such as `pi/4`.
+///
+/// # Arguments
+///
+/// * `source` - The circuit JSON, e.g. `{"cols":[["H"],["•","X"]]}`, or a Quirk URL containing
+///   it after `#circuit=`, percent-encoded or not.
+///
+/// # Returns
+///
+/// * The circuit, `QuantumError::Parse` if the JSON is malformed, or
+///   `QuantumError::Unsupported` for a gate with no counterpart in this crate, e.g. a
+///   time-dependent or arithmetic gate or a custom gate.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Operation;
+/// use quantum_simulator::interop::from_quirk;
+///
+/// let circuit = from_quirk(r#"{"cols":[["H"],["•","X"],["Z^¼"],["Measure","Measure"]]}"#).unwrap();
+/// assert_eq!(circuit.instructions()[1].operation, Operation::Cnot);
+/// assert_eq!(circuit.instructions()[2].operation, Operation::T);
+/// assert_eq!(circuit.num_clbits(), 2);
+///
+/// let link = "https://algassert.com/quirk#circuit=%7B%22cols%22%3A%5B%5B%22H%22%5D%5D%7D";
+/// assert_eq!(from_quirk(link).unwrap().instructions()[0].operation, Operation::H);
+/// assert!(from_quirk(r#"{"cols":[["X^t"]]}"#).is_err());
+/// ```
+pub fn from_quirk(source: &str) -> Result<Circuit, QuantumError> {
+    let source = source.trim();
+    let json = match source.find("circuit=") {
+        Some(start) => {
+            let encoded = &source[start + "circuit=".len()..];
+            percent_decode(encoded.split('&').next().unwrap_or(encoded))?
+        }
+        None => source.to_string(),
+    };
+    quirk_circuit(&parse_json(&json)?)
+}
+
+/// Parses a circuit serialized with `cirq.to_json` into a `Circuit`.
+///
+/// The `LineQubit`s, `GridQubit`s and `NamedQubit`s of the circuit are numbered in Cirq's sorted
+/// order, so `LineQubit(k)` becomes qubit `k` when the qubits are `LineQubit(0)` up to
+/// `LineQubit(n - 1)`. Every qubit of a `MeasurementGate` is measured into the next free
+/// classical bit, in the order the measurements appear. `XPowGate`, `YPowGate`, `ZPowGate`,
+/// `HPowGate`, `CXPowGate`, `CZPowGate`, `CCXPowGate`, `CCZPowGate`, `SwapPowGate` and
+/// `ISwapPowGate` keep their exponent and global shift; `Rx`, `Ry`, `Rz`, `CSwapGate`,
+/// `IdentityGate`, `ResetChannel` and `ControlledGate` around any of these are supported too.
+///
+/// # Arguments
+///
+/// * `source` - The JSON of a `cirq.Circuit`.
+///
+/// # Returns
+///
+/// * The circuit, `QuantumError::Parse` if the JSON is malformed, or
+///   `QuantumError::Unsupported` for a gate with no counterpart in this crate or a symbolic
+///   exponent.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Operation;
+/// use quantum_simulator::interop::from_cirq_json;
+///
+/// let circuit = from_cirq_json(r#"{"cirq_type": "Circuit", "moments": [
+///     {"cirq_type": "Moment", "operations": [{"cirq_type": "GateOperation",
+///         "gate": {"cirq_type": "HPowGate", "exponent": 1.0, "global_shift": 0.0},
+///         "qubits": [{"cirq_type": "LineQubit", "x": 0}]}]},
+///     {"cirq_type": "Moment", "operations": [{"cirq_type": "GateOperation",
+///         "gate": {"cirq_type": "CXPowGate", "exponent": 1.0, "global_shift": 0.0},
+///         "qubits": [{"cirq_type": "LineQubit", "x": 0}, {"cirq_type": "LineQubit", "x": 1}]}]}
+/// ]}"#).unwrap();
+/// assert_eq!(circuit.num_qubits(), 2);
+/// assert_eq!(circuit.instructions()[1].operation, Operation::Cnot);
+/// ```
+pub fn from_cirq_json(source: &str) -> Result<Circuit, QuantumError> {
+    let root = parse_json(source)?;
+    if root["cirq_type"] != "Circuit" {
+        return Err(invalid("expected a cirq.Circuit"));
+    }
+    let moments = root["moments"]
+        .as_array()
+        .ok_or_else(|| invalid("expected a 'moments' array"))?;
+
+    // Collect the operations first, since qubits are numbered in sorted order
+    let mut operations = vec![];
+    for moment in moments {
+        let entries = moment["operations"]
+            .as_array()
+            .ok_or_else(|| invalid("expected an 'operations' array in every moment"))?;
+        for operation in entries {
+            if operation["cirq_type"] != "GateOperation" {
+                return Err(unsupported_cirq(&operation["cirq_type"]));
+            }
+            let qubits = operation["qubits"]
+                .as_array()
+                .ok_or_else(|| invalid("expected a 'qubits' array in every operation"))?
+                .iter()
+                .map(cirq_qubit)
+                .collect::<Result<Vec<_>, _>>()?;
+            operations.push((&operation["gate"], qubits));
+        }
+    }
+    let mut all_qubits: Vec<&CirqQubit> = operations.iter().flat_map(|(_, q)| q).collect();
+    all_qubits.sort();
+    all_qubits.dedup();
+
+    let mut instructions = vec![];
+    let mut num_clbits = 0;
+    for (gate, qubits) in &operations {
+        let qubits: Vec<usize> = qubits
+            .iter()
+            .map(|qubit| all_qubits.binary_search(&qubit).unwrap())
+            .collect();
+        match gate["cirq_type"].as_str() {
+            Some("MeasurementGate") => {
+                let inverted = gate["invert_mask"]
+                    .as_array()
+                    .is_some_and(|mask| mask.iter().any(|bit| bit == true));
+                if inverted {
+                    return Err(QuantumError::Unsupported {
+                        feature: "Cirq measurement with an invert mask".to_string(),
+                    });
+                }
+                for &qubit in &qubits {
+                    instructions.push(Instruction::new(
+                        Operation::Measure(num_clbits),
+                        vec![qubit],
+                    ));
+                    num_clbits += 1;
+                }
+            }
+            Some("ResetChannel") => instructions.extend(
+                qubits
+                    .iter()
+                    .map(|&qubit| Instruction::new(Operation::Reset, vec![qubit])),
+            ),
+            Some("IdentityGate") => {}
+            Some("ControlledGate") => {
+                let num_controls = gate["num_controls"]
+                    .as_u64()
+                    .ok_or_else(|| invalid("expected 'num_controls' in a ControlledGate"))?
+                    as usize;
+                if num_controls > qubits.len() {
+                    return Err(invalid("ControlledGate has more controls than qubits"));
+                }
+                let values = cirq_control_values(&gate["control_values"], num_controls)?;
+                let controls: Vec<(usize, bool)> =
+                    qubits[..num_controls].iter().copied().zip(values).collect();
+                let operation = cirq_unitary(&gate["sub_gate"])?;
+                push_controlled(
+                    &mut instructions,
+                    operation,
+                    &controls,
+                    &qubits[num_controls..],
+                );
+            }
+            _ => instructions.push(Instruction::new(cirq_unitary(gate)?, qubits)),
+        }
+    }
+    build(all_qubits.len(), num_clbits, instructions)
+}
+
+/// Parses a circuit from Quirk or Cirq, telling them apart by the content: a Quirk URL or JSON
+/// with `cols`, or Cirq JSON with a `cirq_type`; see [`from_quirk`] and [`from_cirq_json`].
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::interop::parse;
+///
+/// assert_eq!(parse(r#"{"cols":[["H","H"]]}"#).unwrap().num_qubits(), 2);
+/// assert!(parse(r#"{"gates":[]}"#).is_err());
+/// ```
+pub fn parse(source: &str) -> Result<Circuit, QuantumError> {
+    if !source.trim_start().starts_with('{') {
+        return from_quirk(source);
+    }
+    let root = parse_json(source)?;
+    if root.get("cirq_type").is_some() {
+        from_cirq_json(source)
+    } else if root.get("cols").is_some() {
+        quirk_circuit(&root)
+    } else {
+        Err(invalid("expected a Quirk or Cirq circuit"))
+    }
+}
+
+/// What one cell of a Quirk column does.
+enum QuirkCell {
+    Identity,
+    Control(bool),
+    Swap,
+    Measure,
+    Gate(Operation),
+}
+
+/// Translates the JSON of a Quirk circuit.
+fn quirk_circuit(root: &Value) -> Result<Circuit, QuantumError> {
+    if root.get("gates").is_some() {
+        return Err(QuantumError::Unsupported {
+            feature: "Quirk custom gates".to_string(),
+        });
+    }
+    let columns = root["cols"]
+        .as_array()
+        .ok_or_else(|| invalid("expected a 'cols' array"))?;
+    let init = match &root["init"] {
+        Value::Null => vec![],
+        Value::Array(init) => init.clone(),
+        _ => return Err(invalid("expected an 'init' array")),
+    };
+
+    let mut instructions = vec![];
+    for (qubit, state) in init.iter().enumerate() {
+        let preparation: &[Operation] = match (state.as_u64(), state.as_str()) {
+            (Some(0), _) => &[],
+            (Some(1), _) => &[Operation::X],
+            (_, Some("+")) => &[Operation::H],
+            (_, Some("-")) => &[Operation::X, Operation::H],
+            (_, Some("i")) => &[Operation::H, Operation::S],
+            (_, Some("-i")) => &[Operation::H, Operation::Phase(-PI / 2.0)],
+            _ => {
+                return Err(QuantumError::Unsupported {
+                    feature: format!("Quirk initial state {}", state),
+                })
+            }
+        };
+        instructions.extend(
+            preparation
+                .iter()
+                .map(|operation| Instruction::new(operation.clone(), vec![qubit])),
+        );
+    }
+
+    let mut num_qubits = init.len();
+    let mut measured = false;
+    for column in columns {
+        let cells = column
+            .as_array()
+            .ok_or_else(|| invalid("expected every column to be an array"))?;
+        num_qubits = num_qubits.max(cells.len());
+        let mut controls = vec![];
+        let mut swaps = vec![];
+        let mut gates = vec![];
+        for (qubit, cell) in cells.iter().enumerate() {
+            match quirk_cell(cell)? {
+                QuirkCell::Identity => {}
+                QuirkCell::Control(value) => controls.push((qubit, value)),
+                QuirkCell::Swap => swaps.push(qubit),
+                QuirkCell::Measure => gates.push((Operation::Measure(qubit), qubit)),
+                QuirkCell::Gate(operation) => gates.push((operation, qubit)),
+            }
+        }
+        for (operation, qubit) in gates {
+            if let Operation::Measure(_) = operation {
+                if !controls.is_empty() {
+                    return Err(QuantumError::Unsupported {
+                        feature: "controlled Quirk measurement".to_string(),
+                    });
+                }
+                measured = true;
+                instructions.push(Instruction::new(operation, vec![qubit]));
+            } else {
+                push_controlled(&mut instructions, operation, &controls, &[qubit]);
+            }
+        }
+        match swaps.as_slice() {
+            [] => {}
+            [a, b] => push_controlled(&mut instructions, Operation::Swap, &controls, &[*a, *b]),
+            _ => return Err(invalid("a Quirk column needs exactly two Swap cells")),
+        }
+    }
+    build(
+        num_qubits,
+        if measured { num_qubits } else { 0 },
+        instructions,
+    )
+}
+
+/// Translates one cell of a Quirk column, a gate name, an object with `id` and `arg`, or `1`.
+fn quirk_cell(cell: &Value) -> Result<QuirkCell, QuantumError> {
+    let id = match cell {
+        Value::Number(_) => return Ok(QuirkCell::Identity),
+        Value::String(id) => id.as_str(),
+        _ => cell["id"]
+            .as_str()
+            .ok_or_else(|| invalid("expected a gate name or an object with an 'id'"))?,
+    };
+    let argument = || {
+        let formula = match &cell["arg"] {
+            Value::String(formula) => formula.clone(),
+            Value::Number(number) => number.to_string(),
+            _ => {
+                return Err(invalid(format!(
+                    "expected an 'arg' for Quirk gate '{}'",
+                    id
+                )))
+            }
+        };
+        quirk_formula(&formula).ok_or_else(|| QuantumError::Unsupported {
+            feature: format!("Quirk formula '{}'", formula),
+        })
+    };
+    let operation = match id {
+        "…" => return Ok(QuirkCell::Identity),
+        id if QUIRK_DISPLAYS.iter().any(|prefix| id.starts_with(prefix)) => {
+            return Ok(QuirkCell::Identity)
+        }
+        "•" => return Ok(QuirkCell::Control(true)),
+        "◦" => return Ok(QuirkCell::Control(false)),
+        "Swap" => return Ok(QuirkCell::Swap),
+        "Measure" => return Ok(QuirkCell::Measure),
+        "H" => Operation::H,
+        "X" => Operation::X,
+        "Y" => Operation::Y,
+        "Z" => Operation::Z,
+        "Rxft" => Operation::Rx(argument()?),
+        "Ryft" => Operation::Ry(argument()?),
+        "Rzft" => Operation::Rz(argument()?),
+        "X^ft" => pow_operation(Operation::X, argument()?, 0.0),
+        "Y^ft" => pow_operation(Operation::Y, argument()?, 0.0),
+        "Z^ft" => pow_operation(Operation::Z, argument()?, 0.0),
+        id => {
+            let unsupported = || QuantumError::Unsupported {
+                feature: format!("Quirk gate '{}'", id),
+            };
+            let (base, exponent) = id.split_once('^').ok_or_else(unsupported)?;
+            let base = match base {
+                "X" => Operation::X,
+                "Y" => Operation::Y,
+                "Z" => Operation::Z,
+                _ => return Err(unsupported()),
+            };
+            let (sign, fraction) = match exponent.strip_prefix('-') {
+                Some(fraction) => (-1.0, fraction),
+                None => (1.0, exponent),
+            };
+            let exponent = match fraction {
+                "½" => 0.5,
+                "⅓" => 1.0 / 3.0,
+                "¼" => 0.25,
+                "⅛" => 0.125,
+                "⅟₁₆" => 0.0625,
+                _ => return Err(unsupported()),
+            };
+            pow_operation(base, sign * exponent, 0.0)
+        }
+    };
+    Ok(QuirkCell::Gate(operation))
+}
+
+/// Evaluates a constant Quirk formula made of numbers and `pi` joined by `*` and `/`, e.g.
+/// `-pi/4` or `3*π/2`, or returns `None`.
+fn quirk_formula(formula: &str) -> Option<f64> {
+    let text: String = formula
+        .replace('π', "pi")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let (sign, mut rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.as_str()),
+    };
+    let mut value = sign;
+    let mut divide = false;
+    loop {
+        let end = rest.find(['*', '/']).unwrap_or(rest.len());
+        let factor = match &rest[..end] {
+            "pi" => PI,
+            number => number.parse::<f64>().ok()?,
+        };
+        if divide {
+            value /= factor;
+        } else {
+            value *= factor;
+        }
+        if end == rest.len() {
+            return Some(value);
+        }
+        divide = rest[end..].starts_with('/');
+        rest = &rest[end + 1..];
+    }
+}
+
+/// A Cirq qubit, ordered like Cirq orders them.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum CirqQubit {
+    Line(i64),
+    Grid(i64, i64),
+    Named(String),
+}
+
+/// Reads a serialized Cirq qubit.
+fn cirq_qubit(qubit: &Value) -> Result<CirqQubit, QuantumError> {
+    let coordinate = |key: &str| {
+        qubit[key]
+            .as_i64()
+            .ok_or_else(|| invalid(format!("expected an integer '{}' in a Cirq qubit", key)))
+    };
+    match qubit["cirq_type"].as_str() {
+        Some("LineQubit") => Ok(CirqQubit::Line(coordinate("x")?)),
+        Some("GridQubit") => Ok(CirqQubit::Grid(coordinate("row")?, coordinate("col")?)),
+        Some("NamedQubit") => qubit["name"]
+            .as_str()
+            .map(|name| CirqQubit::Named(name.to_string()))
+            .ok_or_else(|| invalid("expected a 'name' in a NamedQubit")),
+        _ => Err(QuantumError::Unsupported {
+            feature: format!("Cirq qubit type {}", qubit["cirq_type"]),
+        }),
+    }
+}
+
+/// Reads the control values of a `ControlledGate`, `true` for a control on `|1⟩`; only one value
+/// per control is supported.
+fn cirq_control_values(values: &Value, num_controls: usize) -> Result<Vec<bool>, QuantumError> {
+    let values = match values {
+        Value::Null => return Ok(vec![true; num_controls]),
+        Value::Array(values) => values,
+        values => values["data"]
+            .as_array()
+            .ok_or_else(|| invalid("expected an array of control values"))?,
+    };
+    let values: Vec<bool> = values
+        .iter()
+        .map(|value| match value.as_array().map(Vec::as_slice) {
+            Some([value]) => value.as_u64(),
+            _ => value.as_u64(),
+        })
+        .map(|value| match value {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => Err(QuantumError::Unsupported {
+                feature: "Cirq control values other than a single 0 or 1".to_string(),
+            }),
+        })
+        .collect::<Result<_, _>>()?;
+    if values.len() != num_controls {
+        return Err(invalid("expected one control value per control"));
+    }
+    Ok(values)
+}
+
+/// Translates a unitary Cirq gate.
+fn cirq_unitary(gate: &Value) -> Result<Operation, QuantumError> {
+    let number = |key: &str| match &gate[key] {
+        Value::Number(number) => Ok(number.as_f64().unwrap_or_default()),
+        Value::Null if key == "global_shift" => Ok(0.0),
+        _ => Err(QuantumError::Unsupported {
+            feature: format!("symbolic Cirq parameter '{}' in {}", key, gate["cirq_type"]),
+        }),
+    };
+    let power = |base: Operation| {
+        Ok(pow_operation(
+            base,
+            number("exponent")?,
+            number("global_shift")?,
+        ))
+    };
+    match gate["cirq_type"].as_str() {
+        Some("XPowGate") => power(Operation::X),
+        Some("YPowGate") => power(Operation::Y),
+        Some("ZPowGate") => power(Operation::Z),
+        Some("HPowGate") => power(Operation::H),
+        Some("CXPowGate" | "CNotPowGate") => power(Operation::Cnot),
+        Some("CZPowGate") => power(Operation::Cz),
+        Some("CCXPowGate") => power(Operation::Toffoli),
+        Some("CCZPowGate") => power(Operation::Unitary(pauli_z().with_control().with_control())),
+        Some("SwapPowGate") => power(Operation::Swap),
+        Some("ISwapPowGate") => {
+            let exponent = number("exponent")?;
+            let shift = number("global_shift")?;
+            if is_close(exponent, 1.0) && is_close(shift, 0.0) {
+                return Ok(Operation::ISwap);
+            }
+            // iSWAP^t rotates |01⟩ and |10⟩ into each other by πt/2
+            let global = Complex::from_polar(1.0, PI * exponent * shift);
+            let (sin, cos) = (PI * exponent / 2.0).sin_cos();
+            let mut matrix = vec![vec![Complex::new(0.0, 0.0); 4]; 4];
+            matrix[0][0] = global;
+            matrix[3][3] = global;
+            matrix[1][1] = global * cos;
+            matrix[2][2] = global * cos;
+            matrix[1][2] = global * Complex::new(0.0, sin);
+            matrix[2][1] = global * Complex::new(0.0, sin);
+            Ok(Operation::Unitary(Gate::new(matrix)))
+        }
+        Some("Rx") => Ok(Operation::Rx(number("rads")?)),
+        Some("Ry") => Ok(Operation::Ry(number("rads")?)),
+        Some("Rz") => Ok(Operation::Rz(number("rads")?)),
+        Some("CSwapGate") => Ok(Operation::Unitary(swap(0, 1, 2).with_control())),
+        _ => Err(unsupported_cirq(&gate["cirq_type"])),
+    }
+}
+
+/// Returns `base^exponent` times the global phase `e^{iπ·exponent·shift}`, where `base` is an
+/// operation whose eigenvalues are `±1` and its power has eigenvalues `1` and `e^{iπ·exponent}`,
+/// as Quirk and Cirq define it. Native operations are kept where they match exactly.
+fn pow_operation(base: Operation, exponent: f64, shift: f64) -> Operation {
+    if is_close(exponent, 1.0) && is_close(shift, 0.0) {
+        return base;
+    }
+    match base {
+        Operation::Z if is_close(shift, 0.0) && is_close(exponent, 0.5) => Operation::S,
+        Operation::Z if is_close(shift, 0.0) && is_close(exponent, 0.25) => Operation::T,
+        Operation::Z if is_close(shift, 0.0) => Operation::Phase(PI * exponent),
+        Operation::X if is_close(shift, -0.5) => Operation::Rx(PI * exponent),
+        Operation::Y if is_close(shift, -0.5) => Operation::Ry(PI * exponent),
+        Operation::Z if is_close(shift, -0.5) => Operation::Rz(PI * exponent),
+        base => {
+            // (I + B)/2 projects onto the +1 eigenspace and (I - B)/2 onto the -1 eigenspace
+            let gate = base.gate();
+            let turn = Complex::from_polar(1.0, PI * exponent);
+            let global = Complex::from_polar(1.0, PI * exponent * shift);
+            let matrix = gate
+                .matrix
+                .iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(j, &entry)| {
+                            let identity = if i == j { 1.0 } else { 0.0 };
+                            let plus = (entry + identity) / 2.0;
+                            let minus = (-entry + identity) / 2.0;
+                            global * (plus + turn * minus)
+                        })
+                        .collect()
+                })
+                .collect();
+            Operation::Unitary(Gate::new(matrix))
+        }
+    }
+}
+
+/// Appends `operation` on `targets`, controlled by every `(qubit, value)` in `controls` being in
+/// `|value⟩`. Anti-controls are conjugated with X gates.
+fn push_controlled(
+    instructions: &mut Vec<Instruction>,
+    operation: Operation,
+    controls: &[(usize, bool)],
+    targets: &[usize],
+) {
+    let flips: Vec<Instruction> = controls
+        .iter()
+        .filter(|(_, value)| !value)
+        .map(|&(qubit, _)| Instruction::new(Operation::X, vec![qubit]))
+        .collect();
+    instructions.extend(flips.iter().cloned());
+    let mut qubits: Vec<usize> = controls.iter().map(|&(qubit, _)| qubit).collect();
+    qubits.extend_from_slice(targets);
+    let operation = match (operation, controls.len()) {
+        (operation, 0) => operation,
+        (Operation::X, 1) => Operation::Cnot,
+        (Operation::X, 2) => Operation::Toffoli,
+        (Operation::Z, 1) => Operation::Cz,
+        (operation, _) => {
+            let mut gate = operation.gate();
+            for _ in controls {
+                gate = gate.with_control();
+            }
+            Operation::Unitary(gate)
+        }
+    };
+    instructions.push(Instruction::new(operation, qubits));
+    instructions.extend(flips);
+}
+
+/// Assembles the translated instructions into a circuit, reporting invalid qubit lists as parse
+/// errors.
+fn build(
+    num_qubits: usize,
+    num_clbits: usize,
+    instructions: Vec<Instruction>,
+) -> Result<Circuit, QuantumError> {
+    let mut circuit = Circuit::with_clbits(num_qubits, num_clbits);
+    for instruction in instructions {
+        circuit
+            .add_instruction(instruction)
+            .map_err(|error| invalid(error.to_string()))?;
+    }
+    Ok(circuit)
+}
+
+/// Decodes the `%XX` escapes of a URL fragment.
+fn percent_decode(encoded: &str) -> Result<String, QuantumError> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = encoded
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| invalid("invalid percent escape in the URL"))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid("the URL does not decode to UTF-8"))
+}
+
+fn parse_json(source: &str) -> Result<Value, QuantumError> {
+    serde_json::from_str(source).map_err(|error| QuantumError::Parse {
+        line: error.line(),
+        message: error.to_string(),
+    })
+}
+
+fn invalid(message: impl Into<String>) -> QuantumError {
+    QuantumError::Parse {
+        line: 0,
+        message: message.into(),
+    }
+}
+
+fn unsupported_cirq(cirq_type: &Value) -> QuantumError {
+    QuantumError::Unsupported {
+        feature: format!("Cirq {}", cirq_type),
+    }
+}
+
+fn is_close(a: f64, b: f64) -> bool {
+    (a - b).abs() < tolerance::current().equality
+}
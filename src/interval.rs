@@ -0,0 +1,305 @@
+//! This module runs circuits in interval arithmetic to bound floating-point error.
+//!
+//! Every amplitude is carried as a rectangle `[re] + [im]i` whose bounds are rounded outward
+//! after each operation, so the final intervals enclose the amplitudes of the exact computation.
+//! Gate matrix entries are taken to be within two ulps of their true values, which covers the
+//! `sin`/`cos` evaluations used to build rotation gates.
+
+use crate::circuit::{Circuit, Operation};
+use crate::linalg;
+use num_complex::Complex;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// The number of ulps each gate matrix entry is widened by.
+const GATE_ENTRY_ULPS: usize = 2;
+
+/// An `Interval` is a closed range `[lo, hi]` of real numbers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    /// Creates a new `Interval`.
+    ///
+    /// # Arguments
+    ///
+    /// * `lo` - The lower bound.
+    /// * `hi` - The upper bound.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lo > hi`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::interval::Interval;
+    ///
+    /// let interval = Interval::new(0.25, 0.5);
+    /// assert_eq!(interval.width(), 0.25);
+    /// ```
+    pub fn new(lo: f64, hi: f64) -> Self {
+        assert!(lo <= hi, "interval bounds are reversed: [{}, {}]", lo, hi);
+        Interval { lo, hi }
+    }
+
+    /// Returns the degenerate interval `[value, value]`.
+    pub fn point(value: f64) -> Self {
+        Interval {
+            lo: value,
+            hi: value,
+        }
+    }
+
+    /// Returns `hi - lo`.
+    pub fn width(&self) -> f64 {
+        self.hi - self.lo
+    }
+
+    /// Returns the midpoint of the interval.
+    pub fn midpoint(&self) -> f64 {
+        0.5 * (self.lo + self.hi)
+    }
+
+    /// Returns `true` if `value` lies within the interval.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::interval::Interval;
+    ///
+    /// assert!(Interval::new(-1.0, 1.0).contains(0.0));
+    /// ```
+    pub fn contains(&self, value: f64) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    /// Returns the interval enclosing `x²`, which is tighter than `x * x` when `x` spans zero.
+    pub fn square(&self) -> Self {
+        let (lo, hi) = (
+            self.lo.abs().min(self.hi.abs()),
+            self.lo.abs().max(self.hi.abs()),
+        );
+        let lo = if self.contains(0.0) { 0.0 } else { lo * lo };
+        Interval {
+            lo: lo.next_down().max(0.0),
+            hi: (hi * hi).next_up(),
+        }
+    }
+
+    /// Widens the interval by `ulps` units in the last place on each side.
+    fn widened(self, ulps: usize) -> Self {
+        (0..ulps).fold(self, |interval, _| Interval {
+            lo: interval.lo.next_down(),
+            hi: interval.hi.next_up(),
+        })
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+
+    fn add(self, rhs: Interval) -> Interval {
+        Interval {
+            lo: (self.lo + rhs.lo).next_down(),
+            hi: (self.hi + rhs.hi).next_up(),
+        }
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+
+    fn sub(self, rhs: Interval) -> Interval {
+        self + (-rhs)
+    }
+}
+
+impl Neg for Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Interval {
+        Interval {
+            lo: -self.hi,
+            hi: -self.lo,
+        }
+    }
+}
+
+impl Mul for Interval {
+    type Output = Interval;
+
+    fn mul(self, rhs: Interval) -> Interval {
+        let products = [
+            self.lo * rhs.lo,
+            self.lo * rhs.hi,
+            self.hi * rhs.lo,
+            self.hi * rhs.hi,
+        ];
+        Interval {
+            lo: products
+                .iter()
+                .copied()
+                .fold(f64::INFINITY, f64::min)
+                .next_down(),
+            hi: products
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max)
+                .next_up(),
+        }
+    }
+}
+
+/// A `ComplexInterval` is a rectangle in the complex plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComplexInterval {
+    pub re: Interval,
+    pub im: Interval,
+}
+
+impl ComplexInterval {
+    /// Returns the degenerate rectangle containing only `value`.
+    pub fn point(value: Complex<f64>) -> Self {
+        ComplexInterval {
+            re: Interval::point(value.re),
+            im: Interval::point(value.im),
+        }
+    }
+
+    /// Returns `true` if `value` lies within the rectangle.
+    pub fn contains(&self, value: Complex<f64>) -> bool {
+        self.re.contains(value.re) && self.im.contains(value.im)
+    }
+
+    /// Returns the interval enclosing `|z|²` for every `z` in the rectangle.
+    pub fn norm_sqr(&self) -> Interval {
+        self.re.square() + self.im.square()
+    }
+}
+
+impl Add for ComplexInterval {
+    type Output = ComplexInterval;
+
+    fn add(self, rhs: ComplexInterval) -> ComplexInterval {
+        ComplexInterval {
+            re: self.re + rhs.re,
+            im: self.im + rhs.im,
+        }
+    }
+}
+
+impl Mul for ComplexInterval {
+    type Output = ComplexInterval;
+
+    fn mul(self, rhs: ComplexInterval) -> ComplexInterval {
+        ComplexInterval {
+            re: self.re * rhs.re - self.im * rhs.im,
+            im: self.re * rhs.im + self.im * rhs.re,
+        }
+    }
+}
+
+/// Runs the circuit in interval arithmetic, returning an enclosure of every final amplitude.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to run.
+/// * `initial_state` - The initial amplitudes, taken as exact.
+///
+/// # Panics
+///
+/// Panics if the circuit is not unitary (see [`Circuit::is_unitary`]).
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::interval::run_interval;
+/// use num_complex::Complex;
+///
+/// let mut circuit = Circuit::new(1);
+/// circuit.h(0);
+/// let amplitudes = run_interval(&circuit, &[Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]);
+/// assert!(amplitudes[1].re.contains(1.0 / 2.0_f64.sqrt()));
+/// assert!(amplitudes[1].re.width() < 1e-14);
+/// ```
+pub fn run_interval(circuit: &Circuit, initial_state: &[Complex<f64>]) -> Vec<ComplexInterval> {
+    let mut state: Vec<ComplexInterval> = initial_state
+        .iter()
+        .map(|&amplitude| ComplexInterval::point(amplitude))
+        .collect();
+
+    for instruction in circuit.instructions() {
+        if let Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_) =
+            instruction.operation
+        {
+            continue;
+        }
+        let matrix: Vec<Vec<ComplexInterval>> = instruction
+            .operation
+            .gate()
+            .matrix
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&entry| ComplexInterval {
+                        re: Interval::point(entry.re).widened(GATE_ENTRY_ULPS),
+                        im: Interval::point(entry.im).widened(GATE_ENTRY_ULPS),
+                    })
+                    .collect()
+            })
+            .collect();
+        let (offsets, target_mask) = linalg::local_offsets(&instruction.qubits);
+
+        for base in (0..state.len()).filter(|index| index & target_mask == 0) {
+            let local: Vec<ComplexInterval> =
+                offsets.iter().map(|offset| state[base + offset]).collect();
+            for (row, offset) in matrix.iter().zip(&offsets) {
+                state[base + offset] = row
+                    .iter()
+                    .zip(&local)
+                    .map(|(m, a)| *m * *a)
+                    .reduce(|sum, term| sum + term)
+                    .unwrap_or(ComplexInterval::point(Complex::new(0.0, 0.0)));
+            }
+        }
+    }
+    state
+}
+
+/// Returns guaranteed enclosures of the final measurement probabilities.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to run.
+/// * `initial_state` - The initial amplitudes, taken as exact.
+///
+/// # Panics
+///
+/// Panics if the circuit is not unitary (see [`Circuit::is_unitary`]).
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::interval::probability_bounds;
+/// use num_complex::Complex;
+///
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1).rx(1, 0.3).rx(1, -0.3);
+/// let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+/// initial_state[0] = Complex::new(1.0, 0.0);
+///
+/// let bounds = probability_bounds(&circuit, &initial_state);
+/// assert!(bounds[0].contains(0.5) && bounds[3].contains(0.5));
+/// assert!(bounds[1].hi < 1e-14);
+/// ```
+pub fn probability_bounds(circuit: &Circuit, initial_state: &[Complex<f64>]) -> Vec<Interval> {
+    run_interval(circuit, initial_state)
+        .iter()
+        .map(ComplexInterval::norm_sqr)
+        .collect()
+}
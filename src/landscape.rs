@@ -0,0 +1,352 @@
+//! This module sweeps one or two circuit parameters over a grid and records the expectation value
+//! of an observable at every point.
+//!
+//! A [`Landscape`] is the resulting grid. Its [`Landscape::variance`] shows how flat the surface
+//! is, e.g. to spot a barren plateau where gradient-based optimizers stall, and
+//! [`Landscape::to_svg`] renders it as a heatmap.
+
+use crate::analysis::expectation_value;
+use crate::circuit::Circuit;
+use crate::gates::Gate;
+
+/// Side length in pixels of one heatmap cell.
+const CELL_SIZE: usize = 12;
+
+/// Space in pixels around the heatmap for the axis labels.
+const MARGIN: usize = 40;
+
+/// `points` evenly spaced parameter values from `start` to `end`, both included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Axis {
+    pub start: f64,
+    pub end: f64,
+    pub points: usize,
+}
+
+impl Axis {
+    /// Creates an axis.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The first parameter value.
+    /// * `end` - The last parameter value.
+    /// * `points` - The number of values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` is zero.
+    pub fn new(start: f64, end: f64, points: usize) -> Self {
+        assert!(points > 0, "an axis needs at least one point");
+        Axis { start, end, points }
+    }
+
+    /// Returns the parameter values in increasing order of index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::landscape::Axis;
+    ///
+    /// assert_eq!(Axis::new(0.0, 1.0, 5).values(), vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+    /// assert_eq!(Axis::new(2.0, 3.0, 1).values(), vec![2.0]);
+    /// ```
+    pub fn values(&self) -> Vec<f64> {
+        if self.points == 1 {
+            return vec![self.start];
+        }
+        let step = (self.end - self.start) / (self.points - 1) as f64;
+        (0..self.points)
+            .map(|k| self.start + step * k as f64)
+            .collect()
+    }
+}
+
+/// A point of a [`Landscape`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LandscapePoint {
+    /// The value of the first parameter.
+    pub x: f64,
+    /// The value of the second parameter, `None` for a one-parameter sweep.
+    pub y: Option<f64>,
+    /// The expectation value at the point.
+    pub value: f64,
+}
+
+/// Expectation values over a grid of one or two parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Landscape {
+    /// The values of the first parameter.
+    pub x: Vec<f64>,
+    /// The values of the second parameter, empty for a one-parameter sweep.
+    pub y: Vec<f64>,
+    /// The expectation values, where `values[j][i]` belongs to `x[i]` and `y[j]`. A
+    /// one-parameter sweep has a single row.
+    pub values: Vec<Vec<f64>>,
+}
+
+impl Landscape {
+    /// Returns every point of the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = LandscapePoint> + '_ {
+        self.values.iter().enumerate().flat_map(move |(j, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(i, &value)| LandscapePoint {
+                    x: self.x[i],
+                    y: self.y.get(j).copied(),
+                    value,
+                })
+        })
+    }
+
+    /// Returns the point with the lowest expectation value, e.g. the best starting point for a
+    /// minimization.
+    pub fn min(&self) -> LandscapePoint {
+        self.points()
+            .min_by(|a, b| a.value.total_cmp(&b.value))
+            .expect("a landscape has at least one point")
+    }
+
+    /// Returns the point with the highest expectation value.
+    pub fn max(&self) -> LandscapePoint {
+        self.points()
+            .max_by(|a, b| a.value.total_cmp(&b.value))
+            .expect("a landscape has at least one point")
+    }
+
+    /// Returns the variance of the expectation values over the grid. A variance close to zero
+    /// means the landscape is flat, as on a barren plateau.
+    pub fn variance(&self) -> f64 {
+        let count = self.points().count() as f64;
+        let mean = self.points().map(|point| point.value).sum::<f64>() / count;
+        self.points()
+            .map(|point| (point.value - mean).powi(2))
+            .sum::<f64>()
+            / count
+    }
+
+    /// Renders the landscape as an SVG heatmap, from blue for the lowest value through white to
+    /// red for the highest, with the first parameter increasing to the right and the second
+    /// upwards. Every cell has a tooltip with its parameters and value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::landscape::{sweep_1d, Axis};
+    /// use quantum_simulator::gates::pauli_z;
+    /// use quantum_simulator::circuit::Circuit;
+    ///
+    /// let landscape = sweep_1d(
+    ///     &Axis::new(0.0, 3.0, 4),
+    ///     |theta| {
+    ///         let mut circuit = Circuit::new(1);
+    ///         circuit.ry(0, theta);
+    ///         circuit
+    ///     },
+    ///     &pauli_z(),
+    ///     &[0],
+    /// );
+    /// let svg = landscape.to_svg();
+    /// assert!(svg.starts_with("<svg"));
+    /// assert_eq!(svg.matches("<rect").count(), 4);
+    /// ```
+    pub fn to_svg(&self) -> String {
+        let columns = self.x.len();
+        let rows = self.values.len();
+        let width = columns * CELL_SIZE + 2 * MARGIN;
+        let height = rows * CELL_SIZE + 2 * MARGIN;
+        let (low, high) = (self.min().value, self.max().value);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"10\">\n",
+            width, height
+        );
+        for (index, point) in self.points().enumerate() {
+            let (i, j) = (index % columns, index / columns);
+            let t = if high > low {
+                (point.value - low) / (high - low)
+            } else {
+                0.5
+            };
+            let title = match point.y {
+                Some(y) => format!("x = {:.4}, y = {:.4}: {:.6}", point.x, y, point.value),
+                None => format!("x = {:.4}: {:.6}", point.x, point.value),
+            };
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" fill=\"{}\"><title>{}</title></rect>\n",
+                MARGIN + i * CELL_SIZE,
+                MARGIN + (rows - 1 - j) * CELL_SIZE,
+                color(t),
+                title,
+                size = CELL_SIZE
+            ));
+        }
+
+        // Parameter ranges along the axes and the value range above the heatmap
+        let bottom = MARGIN + rows * CELL_SIZE + 14;
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\">{:.3}</text>\n<text x=\"{}\" y=\"{}\" text-anchor=\"end\">x = {:.3}</text>\n",
+            MARGIN,
+            bottom,
+            self.x[0],
+            MARGIN + columns * CELL_SIZE,
+            bottom,
+            self.x[columns - 1]
+        ));
+        if let (Some(first), Some(last)) = (self.y.first(), self.y.last()) {
+            svg.push_str(&format!(
+                "<text x=\"{0}\" y=\"{1}\" text-anchor=\"end\">{2:.3}</text>\n<text x=\"{0}\" y=\"{3}\" text-anchor=\"end\">y = {4:.3}</text>\n",
+                MARGIN - 4,
+                MARGIN + rows * CELL_SIZE,
+                first,
+                MARGIN + 8,
+                last
+            ));
+        }
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\">min {:.4}, max {:.4}</text>\n</svg>\n",
+            MARGIN,
+            MARGIN - 8,
+            low,
+            high
+        ));
+        svg
+    }
+}
+
+/// Maps `t` in `[0, 1]` to a blue-white-red color.
+fn color(t: f64) -> String {
+    let (r, g, b) = if t < 0.5 {
+        let s = t * 2.0;
+        (s, s, 1.0)
+    } else {
+        let s = (1.0 - t) * 2.0;
+        (1.0, s, s)
+    };
+    let byte = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", byte(r), byte(g), byte(b))
+}
+
+/// Sweeps one parameter and evaluates the expectation value of an observable at every point.
+///
+/// # Arguments
+///
+/// * `axis` - The parameter values to sweep.
+/// * `circuit` - Builds the circuit for a parameter value; it is run on `|0…0⟩`.
+/// * `observable` - A Hermitian matrix acting on `support`, as for
+///   [`expectation_value`](crate::analysis::expectation_value).
+/// * `support` - The qubits the observable acts on.
+///
+/// # Returns
+///
+/// * A `Landscape` with a single row of values.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::gates::pauli_z;
+/// use quantum_simulator::landscape::{sweep_1d, Axis};
+/// use std::f64::consts::PI;
+///
+/// let landscape = sweep_1d(
+///     &Axis::new(0.0, 2.0 * PI, 9),
+///     |theta| {
+///         let mut circuit = Circuit::new(1);
+///         circuit.ry(0, theta);
+///         circuit
+///     },
+///     &pauli_z(),
+///     &[0],
+/// );
+/// // ⟨Z⟩ = cos θ is lowest at θ = π
+/// let min = landscape.min();
+/// assert!((min.x - PI).abs() < 1e-12);
+/// assert!((min.value + 1.0).abs() < 1e-12);
+/// ```
+pub fn sweep_1d(
+    axis: &Axis,
+    circuit: impl Fn(f64) -> Circuit,
+    observable: &Gate,
+    support: &[usize],
+) -> Landscape {
+    let x = axis.values();
+    let row = x
+        .iter()
+        .map(|&theta| expectation_value(&circuit(theta), observable, support))
+        .collect();
+    Landscape {
+        x,
+        y: vec![],
+        values: vec![row],
+    }
+}
+
+/// Sweeps two parameters over a grid and evaluates the expectation value of an observable at
+/// every point.
+///
+/// # Arguments
+///
+/// * `x_axis` - The values of the first parameter.
+/// * `y_axis` - The values of the second parameter.
+/// * `circuit` - Builds the circuit for a pair of parameter values; it is run on `|0…0⟩`.
+/// * `observable` - A Hermitian matrix acting on `support`, as for
+///   [`expectation_value`](crate::analysis::expectation_value).
+/// * `support` - The qubits the observable acts on.
+///
+/// # Returns
+///
+/// * A `Landscape` with one row per value of the second parameter.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::gates::Gate;
+/// use quantum_simulator::landscape::{sweep_2d, Axis};
+/// use num_complex::Complex;
+///
+/// let zz = Gate::new(
+///     (0..4)
+///         .map(|i| {
+///             (0..4)
+///                 .map(|j| {
+///                     let sign = if (i as u32).count_ones() % 2 == 0 { 1.0 } else { -1.0 };
+///                     Complex::new(if i == j { sign } else { 0.0 }, 0.0)
+///                 })
+///                 .collect()
+///         })
+///         .collect(),
+/// );
+/// let axis = Axis::new(0.0, 3.0, 4);
+/// let landscape = sweep_2d(
+///     &axis,
+///     &axis,
+///     |a, b| {
+///         let mut circuit = Circuit::new(2);
+///         circuit.ry(0, a).ry(1, b);
+///         circuit
+///     },
+///     &zz,
+///     &[0, 1],
+/// );
+/// // ⟨Z₀Z₁⟩ = cos a cos b
+/// assert!((landscape.values[2][1] - 1.0_f64.cos() * 2.0_f64.cos()).abs() < 1e-12);
+/// ```
+pub fn sweep_2d(
+    x_axis: &Axis,
+    y_axis: &Axis,
+    circuit: impl Fn(f64, f64) -> Circuit,
+    observable: &Gate,
+    support: &[usize],
+) -> Landscape {
+    let x = x_axis.values();
+    let y = y_axis.values();
+    let values = y
+        .iter()
+        .map(|&b| {
+            x.iter()
+                .map(|&a| expectation_value(&circuit(a, b), observable, support))
+                .collect()
+        })
+        .collect();
+    Landscape { x, y, values }
+}
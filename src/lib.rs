@@ -1,4 +1,66 @@
+pub mod algorithms;
+pub mod analysis;
+pub mod assertion;
+pub mod backend;
+pub mod benchmarking;
+#[cfg(feature = "chunked")]
+pub mod chunked;
 pub mod circuit;
+pub mod compression;
+pub mod conformance;
+pub mod convergence;
+pub mod cost;
+pub mod counts;
+pub mod debugger;
+pub mod decompose;
+pub mod density;
+mod diagram;
+pub mod diff;
+pub mod error;
+pub mod events;
+#[cfg(feature = "exact")]
+pub mod exact;
+pub mod execution;
+pub mod experiments;
+pub mod explain;
+pub mod external;
+pub mod fixed;
+pub mod gallery;
 pub mod gates;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod gst;
+pub mod hooks;
+pub mod interop;
+pub mod interval;
+pub mod landscape;
+pub mod library;
+mod linalg;
+pub mod noise;
+pub mod optimize;
+pub mod ordering;
+pub mod parameter;
+pub mod precision;
+pub mod prelude;
+pub mod presets;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod qasm;
 pub mod qubit;
+pub mod report;
+mod rng;
+pub mod schedule;
+pub mod shadows;
+pub mod simd;
 pub mod simulator;
+pub mod snapshot;
+pub mod soak;
+pub mod stabilizer;
+pub mod stats;
+pub mod subspace;
+pub mod tolerance;
+pub mod tomography;
+pub mod transpile;
+pub mod variational;
+pub mod verify;
+pub mod wasm;
@@ -0,0 +1,309 @@
+//! This module defines the `GateLibrary` of custom named gates.
+//!
+//! A gate is registered under a name either by its matrix or by a decomposition into existing
+//! gates, and becomes an [`Operation::Custom`] that keeps its name through the whole pipeline.
+//! The library in effect on the current thread is set with
+//! [`Simulator::set_gate_library`](crate::simulator::Simulator::set_gate_library) and consulted
+//! by [`Circuit::custom`], the QASM importer and exporter, which read and write the gate by name,
+//! the circuit drawer, which labels its box with the name, and the transpiler, which expands
+//! decomposed gates before routing.
+
+use crate::circuit::{Circuit, Instruction, Operation, INVERSE_SUFFIX};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::qasm;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Maximum nesting depth of decompositions that refer to other custom gates.
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+/// How a custom gate is defined.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Definition {
+    /// The gate's unitary matrix.
+    Matrix(Gate),
+    /// A unitary circuit on the gate's qubits, whose qubit `k` is the gate's `k`-th argument.
+    Decomposition(Circuit),
+}
+
+/// A `GateLibrary` maps names to custom gate definitions.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::library::GateLibrary;
+/// use quantum_simulator::simulator::Simulator;
+///
+/// // The cross-resonance-style gate of a device, defined by its native decomposition
+/// let mut zx = Circuit::new(2);
+/// zx.h(1).cnot(0, 1).rz(1, 0.5).cnot(0, 1).h(1);
+/// let mut library = GateLibrary::new();
+/// library.define_decomposition("zx90", zx).unwrap();
+///
+/// Simulator::set_gate_library(library);
+/// let mut circuit = Circuit::new(3);
+/// circuit.h(0).custom("zx90", &[0, 2]);
+/// assert_eq!(circuit.instructions()[1].operation.name(), "zx90");
+/// Simulator::clear_gate_library();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GateLibrary {
+    gates: BTreeMap<String, Definition>,
+}
+
+impl GateLibrary {
+    /// Creates an empty library.
+    pub fn new() -> Self {
+        GateLibrary::default()
+    }
+
+    /// Registers a gate by its matrix, replacing any earlier definition of the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The gate's name, an identifier that is not a standard QASM gate.
+    /// * `gate` - The gate's unitary matrix; bit `j` of its basis index is the `j`-th argument.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&mut GateLibrary)` - The library, for chaining.
+    /// * `Err(QuantumError::Unsupported)` - If the name is not an identifier or is reserved.
+    /// * `Err(QuantumError::InvalidDimension)` or `Err(QuantumError::NotUnitary)` - If the
+    ///   matrix is not a unitary gate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::Gate;
+    /// use quantum_simulator::library::GateLibrary;
+    /// use num_complex::Complex;
+    ///
+    /// let sy = Gate::from_unitary(vec![
+    ///     vec![Complex::new(0.5, 0.5), Complex::new(-0.5, -0.5)],
+    ///     vec![Complex::new(0.5, 0.5), Complex::new(0.5, 0.5)],
+    /// ])
+    /// .unwrap();
+    /// let mut library = GateLibrary::new();
+    /// library.define_matrix("sy", sy.clone()).unwrap();
+    /// assert_eq!(library.operation("sy").unwrap().gate(), sy);
+    /// assert!(library.define_matrix("cx", sy.clone()).is_err());
+    /// assert!(library.define_matrix("2q", sy).is_err());
+    /// ```
+    pub fn define_matrix(&mut self, name: &str, gate: Gate) -> Result<&mut Self, QuantumError> {
+        check_name(name)?;
+        let gate = Gate::from_unitary(gate.matrix.as_ref().clone())?;
+        if gate.matrix.len() < 2 {
+            return Err(QuantumError::InvalidDimension { dimension: 1 });
+        }
+        self.gates
+            .insert(name.to_string(), Definition::Matrix(gate));
+        Ok(self)
+    }
+
+    /// Registers a gate by a decomposition into existing gates, replacing any earlier definition
+    /// of the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The gate's name, an identifier that is not a standard QASM gate.
+    /// * `circuit` - The decomposition, acting on the gate's arguments as qubits `0..k`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&mut GateLibrary)` - The library, for chaining.
+    /// * `Err(QuantumError::Unsupported)` - If the name is not an identifier or is reserved, or
+    ///   the circuit measures, resets, conditions on classical bits or has no qubits.
+    /// * `Err(QuantumError::UnboundParameter)` - If the circuit has an unbound parameter.
+    pub fn define_decomposition(
+        &mut self,
+        name: &str,
+        circuit: Circuit,
+    ) -> Result<&mut Self, QuantumError> {
+        check_name(name)?;
+        if circuit.num_qubits() == 0 {
+            return Err(QuantumError::Unsupported {
+                feature: format!("gate '{}' without qubits", name),
+            });
+        }
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                Operation::Parametric { parameter, .. } => {
+                    return Err(QuantumError::UnboundParameter {
+                        name: parameter.name().to_string(),
+                    })
+                }
+                Operation::Barrier(_) | Operation::Snapshot(_) => {}
+                operation if operation.is_unitary() => {}
+                operation => {
+                    return Err(QuantumError::Unsupported {
+                        feature: format!("{} in the decomposition of '{}'", operation.name(), name),
+                    })
+                }
+            }
+        }
+        self.gates
+            .insert(name.to_string(), Definition::Decomposition(circuit));
+        Ok(self)
+    }
+
+    /// Returns the definition of a gate, or `None` if it is not registered.
+    pub fn definition(&self, name: &str) -> Option<&Definition> {
+        self.gates.get(name)
+    }
+
+    /// Returns the decomposition of a gate: its registered one or, for a gate `name_dg` that
+    /// [`Operation::inverse`] derived from a decomposed gate `name`, the inverse of `name`'s.
+    pub(crate) fn decomposition(&self, name: &str) -> Option<Circuit> {
+        match self.gates.get(name) {
+            Some(Definition::Decomposition(circuit)) => Some(circuit.clone()),
+            Some(Definition::Matrix(_)) => None,
+            None => match self.gates.get(name.strip_suffix(INVERSE_SUFFIX)?)? {
+                Definition::Decomposition(circuit) => Some(circuit.inverse()),
+                Definition::Matrix(_) => None,
+            },
+        }
+    }
+
+    /// Returns the names of the registered gates in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.gates.keys().map(String::as_str)
+    }
+
+    /// Returns the [`Operation::Custom`] applying a registered gate, or `None` if it is not
+    /// registered.
+    pub fn operation(&self, name: &str) -> Option<Operation> {
+        let gate = match self.gates.get(name)? {
+            Definition::Matrix(gate) => gate.clone(),
+            Definition::Decomposition(circuit) => {
+                Gate::new(circuit.to_unitary(circuit.num_qubits()))
+            }
+        };
+        Some(Operation::Custom {
+            name: name.to_string(),
+            gate,
+        })
+    }
+
+    /// Replaces every custom gate with a registered decomposition, including conditional ones,
+    /// those nested inside other decompositions and the inverses of decomposed gates, by the
+    /// gates it decomposes into.
+    ///
+    /// Gates defined by their matrix, or not registered in this library, are kept as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to expand.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Circuit)` - The expanded circuit.
+    /// * `Err(QuantumError::Unsupported)` - If decompositions refer to each other recursively.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Instruction, Operation};
+    /// use quantum_simulator::library::GateLibrary;
+    ///
+    /// let mut bell = Circuit::new(2);
+    /// bell.h(0).cnot(0, 1);
+    /// let mut library = GateLibrary::new();
+    /// library.define_decomposition("bell", bell).unwrap();
+    ///
+    /// let mut circuit = Circuit::new(3);
+    /// let bell = library.operation("bell").unwrap();
+    /// circuit.add_instruction(Instruction::new(bell, vec![2, 1])).unwrap();
+    /// let expanded = library.expand(&circuit).unwrap();
+    /// assert_eq!(expanded.instructions()[0].operation, Operation::H);
+    /// assert_eq!(expanded.instructions()[1].qubits, vec![2, 1]);
+    /// ```
+    pub fn expand(&self, circuit: &Circuit) -> Result<Circuit, QuantumError> {
+        let mut expanded = Circuit::with_clbits(circuit.num_qubits(), circuit.num_clbits());
+        for instruction in circuit.instructions() {
+            self.expand_into(&mut expanded, instruction, None, 0)?;
+        }
+        Ok(expanded)
+    }
+
+    /// Appends `instruction` to `circuit` with its custom gates expanded, conditioned on
+    /// `clbit` if given.
+    fn expand_into(
+        &self,
+        circuit: &mut Circuit,
+        instruction: &Instruction,
+        clbit: Option<usize>,
+        depth: usize,
+    ) -> Result<(), QuantumError> {
+        let (operation, clbit) = match &instruction.operation {
+            Operation::Conditional { clbit, operation } => (operation.as_ref(), Some(*clbit)),
+            operation => (operation, clbit),
+        };
+        if let Operation::Custom { name, .. } = operation {
+            if let Some(body) = self.decomposition(name) {
+                if depth >= MAX_EXPANSION_DEPTH {
+                    return Err(QuantumError::Unsupported {
+                        feature: format!("recursive gate '{}'", name),
+                    });
+                }
+                for call in body.instructions() {
+                    let qubits = call.qubits.iter().map(|&q| instruction.qubits[q]).collect();
+                    let call = Instruction::new(call.operation.clone(), qubits);
+                    self.expand_into(circuit, &call, clbit, depth + 1)?;
+                }
+                return Ok(());
+            }
+        }
+        let operation = match clbit {
+            Some(clbit) => Operation::Conditional {
+                clbit,
+                operation: Box::new(operation.clone()),
+            },
+            None => operation.clone(),
+        };
+        circuit.add_instruction(Instruction::new(operation, instruction.qubits.clone()))
+    }
+}
+
+/// Returns an error unless `name` is an identifier other than a standard QASM gate.
+fn check_name(name: &str) -> Result<(), QuantumError> {
+    let mut chars = name.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !identifier {
+        return Err(QuantumError::Unsupported {
+            feature: format!("gate name '{}', which is not an identifier", name),
+        });
+    }
+    if qasm::is_reserved(name) {
+        return Err(QuantumError::Unsupported {
+            feature: format!("redefining the standard gate '{}'", name),
+        });
+    }
+    Ok(())
+}
+
+thread_local! {
+    static LIBRARY: RefCell<Option<Rc<GateLibrary>>> = const { RefCell::new(None) };
+}
+
+/// Makes `library` the gate library of this thread.
+pub(crate) fn set(library: GateLibrary) {
+    LIBRARY.with(|cell| *cell.borrow_mut() = Some(Rc::new(library)));
+}
+
+/// Returns this thread to an empty gate library.
+pub(crate) fn clear() {
+    LIBRARY.with(|cell| *cell.borrow_mut() = None);
+}
+
+/// Returns the gate library in effect on this thread.
+pub(crate) fn current() -> Rc<GateLibrary> {
+    LIBRARY
+        .with(|cell| cell.borrow().clone())
+        .unwrap_or_default()
+}
@@ -0,0 +1,340 @@
+//! This module provides small dense linear-algebra helpers shared by the simulator internals.
+
+use crate::tolerance;
+use num_complex::Complex;
+
+/// Returns an `rows` × `cols` matrix filled with zeros.
+pub(crate) fn zeros(rows: usize, cols: usize) -> Vec<Vec<Complex<f64>>> {
+    vec![vec![Complex::new(0.0, 0.0); cols]; rows]
+}
+
+/// Returns the `size` × `size` identity matrix.
+pub(crate) fn identity(size: usize) -> Vec<Vec<Complex<f64>>> {
+    let mut matrix = zeros(size, size);
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] = Complex::new(1.0, 0.0);
+    }
+    matrix
+}
+
+/// Multiplies two matrices.
+pub(crate) fn matmul(a: &[Vec<Complex<f64>>], b: &[Vec<Complex<f64>>]) -> Vec<Vec<Complex<f64>>> {
+    let cols = b.first().map_or(0, |row| row.len());
+    let mut result = zeros(a.len(), cols);
+    for (i, row) in a.iter().enumerate() {
+        for (k, a_ik) in row.iter().enumerate() {
+            if *a_ik == Complex::new(0.0, 0.0) {
+                continue;
+            }
+            for (j, b_kj) in b[k].iter().enumerate() {
+                result[i][j] += a_ik * b_kj;
+            }
+        }
+    }
+    result
+}
+
+/// Returns the conjugate transpose of a matrix.
+pub(crate) fn dagger(a: &[Vec<Complex<f64>>]) -> Vec<Vec<Complex<f64>>> {
+    let cols = a.first().map_or(0, |row| row.len());
+    let mut result = zeros(cols, a.len());
+    for (i, row) in a.iter().enumerate() {
+        for (j, elem) in row.iter().enumerate() {
+            result[j][i] = elem.conj();
+        }
+    }
+    result
+}
+
+/// Returns the Kronecker product `a ⊗ b`.
+pub(crate) fn kron(a: &[Vec<Complex<f64>>], b: &[Vec<Complex<f64>>]) -> Vec<Vec<Complex<f64>>> {
+    let b_rows = b.len();
+    let b_cols = b.first().map_or(0, |row| row.len());
+    let a_cols = a.first().map_or(0, |row| row.len());
+    let mut result = zeros(a.len() * b_rows, a_cols * b_cols);
+    for (i, a_row) in a.iter().enumerate() {
+        for (j, a_ij) in a_row.iter().enumerate() {
+            for (k, b_row) in b.iter().enumerate() {
+                for (l, b_kl) in b_row.iter().enumerate() {
+                    result[i * b_rows + k][j * b_cols + l] = a_ij * b_kl;
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Returns the register offsets of each local basis state of `targets`, and the mask of all
+/// target bits.
+///
+/// Bit `j` of a local index corresponds to `targets[j]`, so the amplitudes a local operator mixes
+/// are `base + offsets[local]` for every `base` with no target bits set.
+pub(crate) fn local_offsets(targets: &[usize]) -> (Vec<usize>, usize) {
+    let offsets = (0..1usize << targets.len())
+        .map(|local| {
+            targets
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| (local >> j) & 1 == 1)
+                .map(|(_, &target)| 1 << target)
+                .sum()
+        })
+        .collect();
+    let mask = targets.iter().map(|&target| 1 << target).sum();
+    (offsets, mask)
+}
+
+/// Applies a local operator on `targets` to a full register state in place.
+pub(crate) fn apply_local(
+    matrix: &[Vec<Complex<f64>>],
+    state: &mut [Complex<f64>],
+    targets: &[usize],
+) {
+    let (offsets, target_mask) = local_offsets(targets);
+    let mut local_state = vec![Complex::new(0.0, 0.0); offsets.len()];
+
+    for base in (0..state.len()).filter(|index| index & target_mask == 0) {
+        for (amplitude, offset) in local_state.iter_mut().zip(&offsets) {
+            *amplitude = state[base + offset];
+        }
+        for (row, offset) in matrix.iter().zip(&offsets) {
+            state[base + offset] = row.iter().zip(&local_state).map(|(m, a)| m * a).sum();
+        }
+    }
+}
+
+/// Returns the positive square root of a positive semidefinite matrix.
+pub(crate) fn positive_sqrt(matrix: &[Vec<Complex<f64>>]) -> Vec<Vec<Complex<f64>>> {
+    let (eigenvalues, eigenvectors) = eigh(matrix);
+    let scaled: Vec<Vec<Complex<f64>>> = eigenvectors
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&eigenvalues)
+                .map(|(entry, value)| entry * value.max(0.0).sqrt())
+                .collect()
+        })
+        .collect();
+    matmul(&scaled, &dagger(&eigenvectors))
+}
+
+/// Adds `b` to `a` entry by entry.
+pub(crate) fn add_assign(a: &mut [Vec<Complex<f64>>], b: &[Vec<Complex<f64>>]) {
+    for (a_row, b_row) in a.iter_mut().zip(b) {
+        for (x, y) in a_row.iter_mut().zip(b_row) {
+            *x += y;
+        }
+    }
+}
+
+/// Returns the trace of a square matrix.
+pub(crate) fn trace(a: &[Vec<Complex<f64>>]) -> Complex<f64> {
+    a.iter().enumerate().map(|(i, row)| row[i]).sum()
+}
+
+/// Returns the determinant of a square matrix by Gaussian elimination with partial pivoting.
+pub(crate) fn determinant(a: &[Vec<Complex<f64>>]) -> Complex<f64> {
+    let mut a = a.to_vec();
+    let n = a.len();
+    let mut det = Complex::new(1.0, 0.0);
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&x, &y| a[x][col].norm().total_cmp(&a[y][col].norm()))
+            .expect("the column has rows below the diagonal");
+        if a[pivot][col].norm() == 0.0 {
+            return Complex::new(0.0, 0.0);
+        }
+        if pivot != col {
+            a.swap(pivot, col);
+            det = -det;
+        }
+        det *= a[col][col];
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower.iter_mut() {
+            let factor = row[col] / pivot_row[col];
+            for (x, p) in row.iter_mut().zip(pivot_row).skip(col) {
+                *x -= factor * p;
+            }
+        }
+    }
+    det
+}
+
+/// Returns the largest absolute entry-wise difference between two matrices.
+pub(crate) fn max_deviation(a: &[Vec<Complex<f64>>], b: &[Vec<Complex<f64>>]) -> f64 {
+    a.iter()
+        .zip(b)
+        .flat_map(|(row_a, row_b)| row_a.iter().zip(row_b).map(|(x, y)| (x - y).norm()))
+        .fold(0.0, f64::max)
+}
+
+/// Returns the single-qubit Pauli matrices in the order `I`, `X`, `Y`, `Z`.
+pub(crate) fn paulis() -> [Vec<Vec<Complex<f64>>>; 4] {
+    let zero = Complex::new(0.0, 0.0);
+    let one = Complex::new(1.0, 0.0);
+    let i = Complex::new(0.0, 1.0);
+    [
+        vec![vec![one, zero], vec![zero, one]],
+        vec![vec![zero, one], vec![one, zero]],
+        vec![vec![zero, -i], vec![i, zero]],
+        vec![vec![one, zero], vec![zero, -one]],
+    ]
+}
+
+/// Returns the `4^n` Pauli strings on `num_qubits` qubits.
+///
+/// Index `m` encodes one Pauli per qubit in base 4 (`0 = I`, `1 = X`, `2 = Y`, `3 = Z`), with the
+/// lowest digit acting on qubit 0.
+pub(crate) fn pauli_basis(num_qubits: usize) -> Vec<Vec<Vec<Complex<f64>>>> {
+    let paulis = paulis();
+    (0..4usize.pow(num_qubits as u32))
+        .map(|m| {
+            (0..num_qubits).fold(identity(1), |acc, k| {
+                kron(&paulis[(m / 4usize.pow(k as u32)) % 4], &acc)
+            })
+        })
+        .collect()
+}
+
+/// Diagonalizes a Hermitian matrix with cyclic complex Jacobi rotations.
+///
+/// Returns the eigenvalues in ascending order together with a matrix whose columns are the
+/// corresponding orthonormal eigenvectors.
+pub(crate) fn eigh(matrix: &[Vec<Complex<f64>>]) -> (Vec<f64>, Vec<Vec<Complex<f64>>>) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut v = identity(n);
+
+    for _sweep in 0..100 {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j].norm_sqr())
+            .sum();
+        if off_diagonal < 1e-30 {
+            break;
+        }
+
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let magnitude = a[p][q].norm();
+                if magnitude < 1e-300 {
+                    continue;
+                }
+                let phase = Complex::from_polar(1.0, -a[p][q].arg());
+                let tau = (a[q][q].re - a[p][p].re) / (2.0 * magnitude);
+                let t = if tau >= 0.0 {
+                    1.0 / (tau + (1.0 + tau * tau).sqrt())
+                } else {
+                    -1.0 / (-tau + (1.0 + tau * tau).sqrt())
+                };
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = t * c;
+
+                // Rotation G acting on the (p, q) plane: G = diag(1, e^{-iφ}) · R(θ).
+                let g_pp = Complex::new(c, 0.0);
+                let g_pq = Complex::new(s, 0.0);
+                let g_qp = phase * -s;
+                let g_qq = phase * c;
+
+                for row in a.iter_mut().chain(v.iter_mut()) {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = x * g_pp + y * g_qp;
+                    row[q] = x * g_pq + y * g_qq;
+                }
+                let (upper, lower) = a.split_at_mut(q);
+                for (x, y) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    let (x0, y0) = (*x, *y);
+                    *x = g_pp.conj() * x0 + g_qp.conj() * y0;
+                    *y = g_pq.conj() * x0 + g_qq.conj() * y0;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&x, &y| a[x][x].re.total_cmp(&a[y][y].re));
+    let eigenvalues = order.iter().map(|&i| a[i][i].re).collect();
+    let eigenvectors = (0..n)
+        .map(|row| order.iter().map(|&col| v[row][col]).collect())
+        .collect();
+    (eigenvalues, eigenvectors)
+}
+
+/// Irrational weight combining two commuting parts of a matrix, e.g. the Hermitian and
+/// anti-Hermitian parts of a unitary, so that degenerate eigenvalues of one are split by the other.
+pub(crate) const MIXING: f64 = 0.577_215_664_901_532_9;
+
+/// Raises a unitary matrix to a real power `t` through its eigendecomposition.
+///
+/// The Hermitian and anti-Hermitian parts of a unitary commute, so a generic real combination of
+/// them shares the unitary's eigenvectors and can be diagonalized with [`eigh`].
+pub(crate) fn unitary_power(u: &[Vec<Complex<f64>>], t: f64) -> Vec<Vec<Complex<f64>>> {
+    let n = u.len();
+    let u_dagger = dagger(u);
+    let combined: Vec<Vec<Complex<f64>>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| {
+                    let hermitian = (u[i][j] + u_dagger[i][j]) / 2.0;
+                    let anti_hermitian = (u[i][j] - u_dagger[i][j]) / Complex::new(0.0, 2.0);
+                    hermitian + anti_hermitian * MIXING
+                })
+                .collect()
+        })
+        .collect();
+    let (_, vectors) = eigh(&combined);
+
+    let mut result = zeros(n, n);
+    for k in 0..n {
+        let v: Vec<Complex<f64>> = vectors.iter().map(|row| row[k]).collect();
+        let uv: Vec<Complex<f64>> = u
+            .iter()
+            .map(|row| row.iter().zip(&v).map(|(a, b)| a * b).sum())
+            .collect();
+        let eigenvalue: Complex<f64> = v.iter().zip(&uv).map(|(a, b)| a.conj() * b).sum();
+        let powered = Complex::from_polar(1.0, eigenvalue.arg() * t);
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, elem) in row.iter_mut().enumerate() {
+                *elem += powered * v[i] * v[j].conj();
+            }
+        }
+    }
+    result
+}
+
+/// Inverts a real square matrix with Gauss-Jordan elimination, returning `None` if it is singular,
+/// i.e. a pivot falls below the truncation tolerance.
+pub(crate) fn invert_real(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut a: Vec<Vec<f64>> = matrix.to_vec();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&x, &y| a[x][col].abs().total_cmp(&a[y][col].abs()))?;
+        if a[pivot][col].abs() < tolerance::current().truncation {
+            return None;
+        }
+        a.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = a[col][col];
+        for j in 0..n {
+            a[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in 0..n {
+            if row != col {
+                let factor = a[row][col];
+                for j in 0..n {
+                    a[row][j] -= factor * a[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+    }
+
+    Some(inverse)
+}
@@ -1,37 +1,1823 @@
 use bevy::color::palettes::tailwind::{BLUE_500, GREEN_500, RED_500, YELLOW_500};
 use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
-use num_complex::Complex;
-use quantum_simulator::circuit::Circuit;
-use quantum_simulator::gates::{cnot, hadamard, pauli_x, pauli_y, pauli_z, s};
-use quantum_simulator::simulator::Simulator;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use quantum_simulator::analysis::{
+    bloch_vector, circuit_equivalence, entanglement_entropy, gate_activity, GateActivity,
+};
+use quantum_simulator::debugger::{Breakpoint, Debugger, Stop};
+use quantum_simulator::explain::{explain, Explanation};
+use quantum_simulator::gallery::{Gallery, MAX_GALLERY_QUBITS};
+use quantum_simulator::interop;
+use quantum_simulator::noise::{depolarizing, NoiseModel};
+use quantum_simulator::prelude::*;
+use quantum_simulator::presets::{MAX_GROVER_PRESET_QUBITS, MAX_PRESET_QUBITS, PRESETS};
+use quantum_simulator::qasm::{self, QasmVersion};
+use quantum_simulator::report::{ExperimentReport, ReportFormat};
+use quantum_simulator::soak::{Soak, MAX_SOAK_QUBITS};
+use quantum_simulator::transpile::{compare_placements, Calibration};
+use quantum_simulator::variational::{Ansatz, GradientDescent, Hamiltonian, Iteration};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Playback speed in gates per second, and the range the arrow keys can adjust it to
+const DEFAULT_SPEED: f32 = 1.0;
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 16.0;
+
+// Seconds a point of a qubit's trail stays visible while it fades out
+const TRAIL_SECONDS: f32 = 3.0;
+
+// Shots sampled from the live state by the shots button
+const SHOTS: usize = 1024;
+
+// The widest register the run and debug commands simulate; its state vector alone takes 1 GiB
+const MAX_SIMULATED_QUBITS: usize = 26;
+
+// Height in pixels of a histogram bar for probability 1, and the largest register the
+// histogram shows one bar per basis state for
+const HISTOGRAM_HEIGHT: f32 = 120.0;
+const MAX_HISTOGRAM_QUBITS: usize = 6;
+
+// Side in pixels of a gate activity heatmap cell, and the largest register the heatmap is shown
+// for
+const HEATMAP_CELL: f32 = 18.0;
+const MAX_HEATMAP_QUBITS: usize = 12;
+
+// Background colors of the measure and shots buttons
+const BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.25);
+const BUTTON_HOVERED_COLOR: Color = Color::srgb(0.3, 0.3, 0.4);
+const BUTTON_PRESSED_COLOR: Color = Color::srgb(0.4, 0.4, 0.6);
+
+// Limits on the size of the circuit in the editor, and the amount the angle buttons change the
+// angle of newly placed rotations by
+const MAX_EDITOR_QUBITS: usize = 8;
+const MAX_EDITOR_STEPS: usize = 16;
+const ANGLE_STEP: f64 = PI / 8.0;
+
+// The amount the noise buttons change the depolarizing probability after every gate by, and the
+// largest probability they allow
+const NOISE_STEP: f64 = 0.01;
+const MAX_NOISE: f64 = 0.5;
+
+// The workspace file restored on launch and written by the save button, unless another path is
+// given with `--workspace`
+const DEFAULT_WORKSPACE: &str = "workspace.qsim";
+
+// The lessons built into the binary, by the name `--lesson` accepts
+const LESSONS: [(&str, &str); 3] = [
+    ("bell", include_str!("../lessons/bell.toml")),
+    (
+        "teleportation",
+        include_str!("../lessons/teleportation.toml"),
+    ),
+    ("grover", include_str!("../lessons/grover.toml")),
+];
+
+// Differences from a lesson step's target listed under the status line
+const MAX_LESSON_HINTS: usize = 3;
+
+// Size of the optimizer progress chart in logical pixels
+const CHART_WIDTH: f32 = 800.0;
+const CHART_HEIGHT: f32 = 400.0;
+
+// Command line
+#[derive(Parser)]
+#[command(
+    name = "quantum_simulator",
+    about = "Simulate and visualize quantum circuits"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Simulate an OpenQASM circuit without opening a window and print the counts and final state
+    Run(RunArgs),
+    /// Open the 3D visualization, the default without a subcommand
+    View {
+        /// An OpenQASM file, or Quirk or Cirq JSON, to load into the editor instead of the saved
+        /// workspace
+        circuit: Option<PathBuf>,
+        /// The workspace file to restore and save
+        #[arg(long, default_value = DEFAULT_WORKSPACE)]
+        workspace: PathBuf,
+        /// A built-in lesson (bell, teleportation or grover) or a lesson file to work through
+        #[arg(long, conflicts_with = "circuit")]
+        lesson: Option<String>,
+        /// Breakpoints that pause playback: an instruction (3), a qubit (q1) or a condition
+        /// ("P(|11>) > 0.4")
+        #[arg(long = "break")]
+        breakpoints: Vec<String>,
+    },
+    /// Minimize the energy of a Hamiltonian with VQE or QAOA, printing every optimizer iteration
+    Optimize(OptimizeArgs),
+    /// Place and route an OpenQASM circuit onto a device, preferring its low-error qubits and
+    /// couplings, and compare the predicted fidelity with a naive placement
+    Transpile {
+        /// The OpenQASM file, or Quirk or Cirq JSON, to transpile
+        circuit: PathBuf,
+        /// The TOML calibration data with gate_error, readout_error and couplings
+        #[arg(long)]
+        calibration: PathBuf,
+        /// Write the routed circuit as OpenQASM 2.0 to this file
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Step forwards and backwards through one run of an OpenQASM circuit; type `help` at the
+    /// prompt for the commands
+    Debug {
+        /// The OpenQASM file, or Quirk or Cirq JSON, to debug
+        circuit: PathBuf,
+        /// The seed for measurements and noise, random if omitted
+        #[arg(long)]
+        seed: Option<u64>,
+        /// The depolarizing probability after every gate
+        #[arg(long, default_value_t = 0.0)]
+        noise: f64,
+        /// Breakpoints to start with: an instruction (3), a qubit (q1) or a condition
+        /// ("P(|11>) > 0.4")
+        #[arg(long = "break")]
+        breakpoints: Vec<String>,
+    },
+    /// List the built-in example circuits, which `Circuit::preset` builds by name and the editor
+    /// loads from its Presets menu
+    Presets,
+    /// Write SVG cards, a Markdown index and JSON presets of the canonical states (Bell, GHZ,
+    /// W, Dicke and random) to a directory
+    Gallery {
+        /// The directory to write to
+        out: PathBuf,
+        /// The width of the GHZ, W, Dicke and random states
+        #[arg(long, default_value_t = 3)]
+        qubits: usize,
+        /// The seed of the random state
+        #[arg(long, default_value_t = 7)]
+        seed: u64,
+    },
+    /// Run random circuits on every backend for a while, checking that the states stay
+    /// normalized, the backends agree and memory does not grow, and print a report
+    Soak(SoakArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// The OpenQASM file, or Quirk or Cirq JSON, to simulate
+    circuit: PathBuf,
+    /// The number of shots to sample
+    #[arg(long, default_value_t = 1000)]
+    shots: usize,
+    /// The seed for measurements and sampling, random if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+    /// The format of the results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// Write the results to this file instead of stdout
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Also write an experiment report to this file, Markdown for .md and HTML otherwise
+    #[arg(long)]
+    report: Option<PathBuf>,
+    /// Also write the gates on each qubit and between each pair of qubits to this CSV file
+    #[arg(long)]
+    activity: Option<PathBuf>,
+    /// Also explain every step of the final run: the gate, the state after it in Dirac notation
+    /// and the probabilities it changed
+    #[arg(long)]
+    explain: bool,
+}
+
+#[derive(Args)]
+struct SoakArgs {
+    /// How long to run, in seconds
+    #[arg(long, default_value_t = 60)]
+    duration: u64,
+    /// The widest random circuit
+    #[arg(long, default_value_t = 6)]
+    max_qubits: usize,
+    /// The seed of the random circuits, random if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+    /// The format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// Write the report to this file instead of stdout
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Args)]
+struct OptimizeArgs {
+    /// The Hamiltonian as a sum of Pauli strings, e.g. "Z0 Z1 + 0.5 X0 + 0.5 X1"
+    hamiltonian: String,
+    /// The parameterized circuit preparing the trial states
+    #[arg(long, value_enum, default_value_t = AnsatzKind::HardwareEfficient)]
+    ansatz: AnsatzKind,
+    /// The number of ansatz layers
+    #[arg(long, default_value_t = 1)]
+    layers: usize,
+    /// The maximum number of optimizer steps
+    #[arg(long, default_value_t = 200)]
+    iterations: usize,
+    /// The gradient descent learning rate
+    #[arg(long, default_value_t = 0.1)]
+    learning_rate: f64,
+    /// The seed for the random starting parameters, random if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Also write every iteration to this CSV file
+    #[arg(long)]
+    csv: Option<PathBuf>,
+    /// Plot the energy against the step in a window while the optimizer runs
+    #[arg(long)]
+    plot: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AnsatzKind {
+    /// Layers of Ry rotations and CNOT chains (VQE)
+    HardwareEfficient,
+    /// Alternating cost and mixer layers for a diagonal Hamiltonian (QAOA)
+    Qaoa,
+}
 
 // Components
 #[derive(Component)]
-struct QubitSphere;
+struct QubitSphere(usize);
 
 #[derive(Component)]
 struct Position(Vec3);
 
+// Recent positions of a qubit sphere with the time they were reached, oldest first
+#[derive(Component, Default)]
+struct Trail(VecDeque<(Vec3, f32)>);
+
+#[derive(Component)]
+struct EventList;
+
+// A button measuring one qubit, or every qubit if `None`
+#[derive(Component)]
+struct MeasureButton(Option<usize>);
+
+#[derive(Component)]
+struct ResultsPanel;
+
+#[derive(Component)]
+struct ShotsButton;
+
+type ShotsButtonChanged = (Changed<Interaction>, With<ShotsButton>);
+
+// The histogram bars of a basis state's probability and of its share of the sampled shots
+#[derive(Component)]
+struct ProbabilityBar(usize);
+
+#[derive(Component)]
+struct CountsBar(usize);
+
+// Everything added to the scene for one simulation run, removed again when the circuit is edited
+#[derive(Component)]
+struct SimulationView;
+
+#[derive(Component)]
+struct EditorPanel;
+
+#[derive(Component)]
+struct ToolButton(Tool);
+
+// The slot of the editor grid on `qubit` at time step `step`
+#[derive(Component)]
+struct SlotButton {
+    step: usize,
+    qubit: usize,
+}
+
+#[derive(Component, Clone, Copy)]
+enum EditorAction {
+    AddStep,
+    RemoveStep,
+    AddQubit,
+    RemoveQubit,
+    DecreaseAngle,
+    IncreaseAngle,
+    DecreaseNoise,
+    IncreaseNoise,
+    NewSeed,
+    Clear,
+    Example,
+    TogglePresets,
+    Preset(&'static str),
+}
+
+#[derive(Component)]
+struct LessonPanel;
+
+#[derive(Component)]
+struct ProgressText;
+
+#[derive(Component)]
+struct SaveButton;
+
+type SaveButtonChanged = (Changed<Interaction>, With<SaveButton>);
+
+type EditorButton = (
+    &'static Interaction,
+    &'static mut BackgroundColor,
+    Option<&'static ToolButton>,
+    Option<&'static SlotButton>,
+    Option<&'static EditorAction>,
+);
+
+type EditorButtonChanged = (
+    Changed<Interaction>,
+    Or<(With<ToolButton>, With<SlotButton>, With<EditorAction>)>,
+);
+
+// A simulation running on the async compute task pool; the entity is despawned once it finishes
+#[derive(Component)]
+struct SimulationTask(Task<SimulationOutput>);
+
+// Everything the scene needs from a simulation, computed off the main thread
+struct SimulationOutput {
+    activity: GateActivity,
+    debugger: Debugger,
+    events: Vec<String>,
+    explanations: Vec<Explanation>,
+    positions: Vec<Vec<Vec3>>,
+    probabilities: Vec<Vec<f64>>,
+    final_qubit: Qubit,
+}
+
+// The state after the circuit and every measurement made from the UI so far
+#[derive(Resource)]
+struct LiveState(Qubit);
+
+// The counts of the last shots sampled from the live state, cleared when it is measured
+#[derive(Resource, Default)]
+struct SampledCounts(Option<Counts>);
+
+// The generator behind the measure and shots buttons, reseeded from the workspace seed whenever
+// the circuit is simulated so a prepared demo measures the same outcomes every time
+#[derive(Resource)]
+struct MeasurementRng(StdRng);
+
+// The breakpoints that pause playback, kept across edits of the circuit
+#[derive(Resource, Default)]
+struct Breakpoints(Vec<Breakpoint>);
+
+// Where the workspace is saved, and the camera view it was restored with, if any
+#[derive(Resource)]
+struct WorkspaceFile {
+    path: PathBuf,
+    camera: Option<CameraView>,
+}
+
+// The orbit of the camera as stored in a workspace file
+#[derive(Clone, Copy)]
+struct CameraView {
+    focus: Vec3,
+    yaw: f32,
+    pitch: f32,
+    radius: f32,
+}
+
+impl CameraView {
+    fn of(camera: &PanOrbitCamera) -> Self {
+        CameraView {
+            focus: camera.focus,
+            yaw: camera.yaw.unwrap_or(0.0),
+            pitch: camera.pitch.unwrap_or(0.0),
+            radius: camera.radius.unwrap_or(1.0),
+        }
+    }
+
+    fn camera(self) -> PanOrbitCamera {
+        PanOrbitCamera {
+            focus: self.focus,
+            yaw: Some(self.yaw),
+            pitch: Some(self.pitch),
+            radius: Some(self.radius),
+            ..Default::default()
+        }
+    }
+}
+
+// A guided scenario: after every edit the circuit in the editor is compared with the target of
+// each step, and `completed` counts the steps of the longest target it matches
+#[derive(Resource)]
+struct Lesson {
+    title: String,
+    intro: String,
+    num_qubits: usize,
+    steps: Vec<LessonStep>,
+    completed: usize,
+    matches: bool,
+}
+
+// One instruction of a lesson and the circuit built by the end of it
+struct LessonStep {
+    instruction: String,
+    target: Circuit,
+}
+
+// Sent when a simulation task completes
+#[derive(Event)]
+struct SimulationFinished(SimulationOutput);
+
+// Iterations streamed from the optimizer thread for the live chart
+#[derive(Resource)]
+struct OptimizerProgress {
+    receiver: Mutex<Receiver<Iteration>>,
+    iterations: Vec<Iteration>,
+    max_iterations: usize,
+}
+
+// Recorded gates with the Bloch positions and basis-state probabilities before the first and
+// after each one. The spheres and histogram sit `progress` of the way from the state after
+// `step - 1` gates to the state after `step` gates.
+#[derive(Resource)]
+struct Timeline {
+    debugger: Debugger,
+    events: Vec<String>,
+    explanations: Vec<Explanation>,
+    positions: Vec<Vec<Vec3>>,
+    probabilities: Vec<Vec<f64>>,
+    step: usize,
+    progress: f32,
+    playing: bool,
+    speed: f32,
+}
+
+// The gates the editor palette offers, plus CNOT and an eraser
+#[derive(Clone, Copy, PartialEq)]
+enum Tool {
+    H,
+    X,
+    Y,
+    Z,
+    S,
+    T,
+    Rx,
+    Ry,
+    Rz,
+    Cnot,
+    Erase,
+}
+
+const TOOLS: [Tool; 11] = [
+    Tool::H,
+    Tool::X,
+    Tool::Y,
+    Tool::Z,
+    Tool::S,
+    Tool::T,
+    Tool::Rx,
+    Tool::Ry,
+    Tool::Rz,
+    Tool::Cnot,
+    Tool::Erase,
+];
+
+impl Tool {
+    fn label(self) -> &'static str {
+        match self {
+            Tool::H => "H",
+            Tool::X => "X",
+            Tool::Y => "Y",
+            Tool::Z => "Z",
+            Tool::S => "S",
+            Tool::T => "T",
+            Tool::Rx => "Rx",
+            Tool::Ry => "Ry",
+            Tool::Rz => "Rz",
+            Tool::Cnot => "CNOT",
+            Tool::Erase => "Erase",
+        }
+    }
+
+    // The single-qubit gate the tool places, with rotations by `angle`
+    fn operation(self, angle: f64) -> Option<Operation> {
+        match self {
+            Tool::H => Some(Operation::H),
+            Tool::X => Some(Operation::X),
+            Tool::Y => Some(Operation::Y),
+            Tool::Z => Some(Operation::Z),
+            Tool::S => Some(Operation::S),
+            Tool::T => Some(Operation::T),
+            Tool::Rx => Some(Operation::Rx(angle)),
+            Tool::Ry => Some(Operation::Ry(angle)),
+            Tool::Rz => Some(Operation::Rz(angle)),
+            Tool::Cnot | Tool::Erase => None,
+        }
+    }
+}
+
+// What one qubit does at one time step; the two halves of a CNOT point at each other
+#[derive(Clone, PartialEq)]
+enum Slot {
+    Empty,
+    Gate(Operation),
+    Control { target: usize },
+    Target { control: usize },
+}
+
+// The circuit being edited as a grid of slots indexed by time step, then qubit, together with the
+// depolarizing probability after every gate and the seed it is simulated with. `revision` is
+// bumped on every edit of these, so the simulation re-runs only when its input changes rather
+// than on every change to the palette. A loaded preset the grid cannot show, e.g. one that
+// measures, is simulated read-only in place of the grid until the next edit.
+#[derive(Resource)]
+struct CircuitEditor {
+    num_qubits: usize,
+    steps: Vec<Vec<Slot>>,
+    tool: Tool,
+    angle: f64,
+    noise: f64,
+    seed: u64,
+    pending_control: Option<(usize, usize)>,
+    revision: u64,
+    preset: Option<&'static str>,
+    show_presets: bool,
+}
+
+impl CircuitEditor {
+    // The default example: layers of single-qubit gates on every qubit joined by a chain of CNOTs
+    fn example() -> Self {
+        let num_qubits = 4;
+        let layer = |operation: Operation| vec![Slot::Gate(operation); num_qubits];
+        let cnot = |control: usize, target: usize| {
+            let mut step = vec![Slot::Empty; num_qubits];
+            step[control] = Slot::Control { target };
+            step[target] = Slot::Target { control };
+            step
+        };
+        CircuitEditor {
+            num_qubits,
+            steps: vec![
+                layer(Operation::H),
+                cnot(0, 1),
+                layer(Operation::X),
+                cnot(1, 2),
+                layer(Operation::Y),
+                cnot(2, 3),
+                layer(Operation::S),
+                layer(Operation::Z),
+            ],
+            tool: Tool::H,
+            angle: FRAC_PI_2,
+            noise: 0.0,
+            seed: rand::random(),
+            pending_control: None,
+            revision: 0,
+            preset: None,
+            show_presets: false,
+        }
+    }
+
+    // Lay out a circuit in the grid, placing each gate in the first time step after the last gate
+    // on any of its qubits
+    fn from_circuit(circuit: &Circuit) -> Result<Self, String> {
+        let num_qubits = circuit.num_qubits();
+        if !(1..=MAX_EDITOR_QUBITS).contains(&num_qubits) {
+            return Err(format!(
+                "the editor supports 1 to {} qubits",
+                MAX_EDITOR_QUBITS
+            ));
+        }
+        let mut editor = CircuitEditor::example();
+        editor.num_qubits = num_qubits;
+        editor.steps.clear();
+        let mut next_free = vec![0; num_qubits];
+        for instruction in circuit.instructions() {
+            let slots = match (&instruction.operation, instruction.qubits.as_slice()) {
+                (Operation::Cnot, &[control, target]) => vec![
+                    (control, Slot::Control { target }),
+                    (target, Slot::Target { control }),
+                ],
+                (
+                    operation @ (Operation::H
+                    | Operation::X
+                    | Operation::Y
+                    | Operation::Z
+                    | Operation::S
+                    | Operation::T
+                    | Operation::Rx(_)
+                    | Operation::Ry(_)
+                    | Operation::Rz(_)),
+                    &[qubit],
+                ) => vec![(qubit, Slot::Gate(operation.clone()))],
+                (Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_), _) => {
+                    continue
+                }
+                (operation, _) => {
+                    return Err(format!("the editor cannot show {}", operation.name()));
+                }
+            };
+            let step = slots
+                .iter()
+                .map(|&(qubit, _)| next_free[qubit])
+                .max()
+                .unwrap_or(0);
+            if step == editor.steps.len() {
+                if step == MAX_EDITOR_STEPS {
+                    return Err(format!(
+                        "the editor supports at most {} time steps",
+                        MAX_EDITOR_STEPS
+                    ));
+                }
+                editor.steps.push(vec![Slot::Empty; num_qubits]);
+            }
+            for (qubit, slot) in slots {
+                next_free[qubit] = step + 1;
+                editor.steps[step][qubit] = slot;
+            }
+        }
+        if editor.steps.is_empty() {
+            editor.steps.push(vec![Slot::Empty; num_qubits]);
+        }
+        Ok(editor)
+    }
+
+    // An empty grid, e.g. for the user to build a lesson's circuit in
+    fn empty(num_qubits: usize, num_steps: usize) -> Self {
+        let mut editor = CircuitEditor::example();
+        editor.num_qubits = num_qubits;
+        editor.steps = vec![vec![Slot::Empty; num_qubits]; num_steps];
+        editor
+    }
+
+    fn noise_model(&self) -> NoiseModel {
+        let mut noise_model = NoiseModel::new();
+        if self.noise > 0.0 {
+            noise_model.add_error(depolarizing(self.noise));
+        }
+        noise_model
+    }
+
+    fn to_circuit(&self) -> Circuit {
+        if let Some(name) = self.preset {
+            return Circuit::preset(name).expect("listed presets exist");
+        }
+        let mut circuit = Circuit::new(self.num_qubits);
+        for step in &self.steps {
+            for (qubit, slot) in step.iter().enumerate() {
+                match slot {
+                    Slot::Gate(operation) => circuit
+                        .add_instruction(Instruction::new(operation.clone(), vec![qubit]))
+                        .expect("editor slots lie on the circuit's qubits"),
+                    Slot::Control { target } => {
+                        circuit.cnot(qubit, *target);
+                    }
+                    Slot::Empty | Slot::Target { .. } => {}
+                }
+            }
+        }
+        circuit
+    }
+
+    fn slot_label(&self, step: usize, qubit: usize) -> String {
+        match &self.steps[step][qubit] {
+            _ if self.pending_control == Some((step, qubit)) => "*?".to_string(),
+            Slot::Empty => "-".to_string(),
+            Slot::Gate(operation) => {
+                let mut label = operation.name().to_string();
+                label[..1].make_ascii_uppercase();
+                label
+            }
+            Slot::Control { .. } => "*".to_string(),
+            Slot::Target { .. } => "(+)".to_string(),
+        }
+    }
+
+    fn select(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.pending_control = None;
+    }
+
+    // Empty a slot, together with the other half if it holds part of a CNOT
+    fn clear(&mut self, step: usize, qubit: usize) {
+        let partner = match &self.steps[step][qubit] {
+            Slot::Control { target } => Some(*target),
+            Slot::Target { control } => Some(*control),
+            _ => None,
+        };
+        if let Some(partner) = partner {
+            self.steps[step][partner] = Slot::Empty;
+        }
+        self.steps[step][qubit] = Slot::Empty;
+    }
+
+    // Apply the selected tool to a slot. A CNOT takes two clicks in the same time step, first on
+    // the control and then on the target.
+    fn place(&mut self, step: usize, qubit: usize) {
+        if self.preset.take().is_some() {
+            self.revision += 1;
+        }
+        match self.tool {
+            Tool::Cnot => match self.pending_control.take() {
+                Some((pending_step, control)) if pending_step == step && control != qubit => {
+                    self.clear(step, control);
+                    self.clear(step, qubit);
+                    self.steps[step][control] = Slot::Control { target: qubit };
+                    self.steps[step][qubit] = Slot::Target { control };
+                    self.revision += 1;
+                }
+                _ => self.pending_control = Some((step, qubit)),
+            },
+            tool => {
+                self.pending_control = None;
+                self.clear(step, qubit);
+                if let Some(operation) = tool.operation(self.angle) {
+                    self.steps[step][qubit] = Slot::Gate(operation);
+                }
+                self.revision += 1;
+            }
+        }
+    }
+
+    // Lay out a preset in the grid, or keep it read-only if the grid cannot show it, keeping the
+    // rotation angle, noise and seed
+    fn load_preset(&mut self, name: &'static str) {
+        let circuit = Circuit::preset(name).expect("listed presets exist");
+        match CircuitEditor::from_circuit(&circuit) {
+            Ok(editor) => {
+                self.num_qubits = editor.num_qubits;
+                self.steps = editor.steps;
+            }
+            Err(_) => {
+                self.num_qubits = circuit.num_qubits();
+                self.steps = vec![vec![Slot::Empty; self.num_qubits]];
+                self.preset = Some(name);
+            }
+        }
+        self.show_presets = false;
+    }
+
+    fn apply(&mut self, action: EditorAction) {
+        self.pending_control = None;
+        if !matches!(
+            action,
+            EditorAction::DecreaseAngle
+                | EditorAction::IncreaseAngle
+                | EditorAction::DecreaseNoise
+                | EditorAction::IncreaseNoise
+                | EditorAction::NewSeed
+                | EditorAction::TogglePresets
+        ) && self.preset.take().is_some()
+        {
+            self.revision += 1;
+        }
+        match action {
+            EditorAction::AddStep if self.steps.len() < MAX_EDITOR_STEPS => {
+                self.steps.push(vec![Slot::Empty; self.num_qubits]);
+            }
+            EditorAction::RemoveStep if self.steps.len() > 1 => {
+                self.steps.pop();
+            }
+            EditorAction::AddQubit if self.num_qubits < MAX_EDITOR_QUBITS => {
+                for step in &mut self.steps {
+                    step.push(Slot::Empty);
+                }
+                self.num_qubits += 1;
+            }
+            EditorAction::RemoveQubit if self.num_qubits > 1 => {
+                for step in &mut self.steps {
+                    match step.pop() {
+                        Some(Slot::Control { target }) => step[target] = Slot::Empty,
+                        Some(Slot::Target { control }) => step[control] = Slot::Empty,
+                        _ => {}
+                    }
+                }
+                self.num_qubits -= 1;
+            }
+            EditorAction::DecreaseAngle => {
+                self.angle -= ANGLE_STEP;
+                return;
+            }
+            EditorAction::IncreaseAngle => {
+                self.angle += ANGLE_STEP;
+                return;
+            }
+            EditorAction::DecreaseNoise => self.noise = (self.noise - NOISE_STEP).max(0.0),
+            EditorAction::IncreaseNoise => self.noise = (self.noise + NOISE_STEP).min(MAX_NOISE),
+            EditorAction::NewSeed => self.seed = rand::random(),
+            EditorAction::Clear => {
+                for step in &mut self.steps {
+                    step.fill(Slot::Empty);
+                }
+            }
+            EditorAction::Example => {
+                let revision = self.revision;
+                *self = CircuitEditor::example();
+                self.revision = revision;
+            }
+            EditorAction::TogglePresets => {
+                self.show_presets = !self.show_presets;
+                return;
+            }
+            EditorAction::Preset(name) => self.load_preset(name),
+            _ => return,
+        }
+        self.revision += 1;
+    }
+}
+
+// Write the editor and camera as a workspace file: one `key value` line per setting, including
+// `preset name` while a preset is shown read-only, followed by one `step` line per time step with a token per qubit, `-` for an empty slot, the gate's name
+// such as `h` or `rx(0.5)`, `@t` for the control of a CNOT with target `t` and `+c` for the
+// target of a CNOT with control `c`
+fn workspace_text(editor: &CircuitEditor, camera: CameraView) -> String {
+    let mut text = format!(
+        "# quantum_simulator workspace\nqubits {}\nangle {}\nnoise {}\nseed {}\ncamera {} {} {} {} {} {}\n",
+        editor.num_qubits,
+        editor.angle,
+        editor.noise,
+        editor.seed,
+        camera.focus.x,
+        camera.focus.y,
+        camera.focus.z,
+        camera.yaw,
+        camera.pitch,
+        camera.radius
+    );
+    if let Some(name) = editor.preset {
+        text.push_str(&format!("preset {}\n", name));
+    }
+    for step in &editor.steps {
+        let tokens: Vec<String> = step
+            .iter()
+            .map(|slot| match slot {
+                Slot::Empty => "-".to_string(),
+                Slot::Gate(Operation::Rx(theta)) => format!("rx({})", theta),
+                Slot::Gate(Operation::Ry(theta)) => format!("ry({})", theta),
+                Slot::Gate(Operation::Rz(theta)) => format!("rz({})", theta),
+                Slot::Gate(operation) => operation.name().to_string(),
+                Slot::Control { target } => format!("@{}", target),
+                Slot::Target { control } => format!("+{}", control),
+            })
+            .collect();
+        text.push_str(&format!("step {}\n", tokens.join(" ")));
+    }
+    text
+}
+
+// Read a workspace file written by `workspace_text`, checking that every step has one slot per
+// qubit and that the two halves of each CNOT point at each other
+fn parse_workspace(text: &str) -> Result<(CircuitEditor, CameraView), String> {
+    let mut editor = CircuitEditor::example();
+    editor.steps.clear();
+    let mut camera = None;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: &str| format!("line {}: {}", number + 1, message);
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let number_of = |token: &str| token.parse::<f64>().map_err(|_| error("invalid number"));
+        match key {
+            "qubits" => {
+                editor.num_qubits = value.parse().map_err(|_| error("invalid qubit count"))?;
+                if !(1..=MAX_EDITOR_QUBITS).contains(&editor.num_qubits) {
+                    return Err(error("unsupported qubit count"));
+                }
+            }
+            "angle" => editor.angle = number_of(value)?,
+            "noise" => editor.noise = number_of(value)?.clamp(0.0, MAX_NOISE),
+            "seed" => editor.seed = value.parse().map_err(|_| error("invalid seed"))?,
+            "preset" => {
+                let preset = PRESETS
+                    .iter()
+                    .find(|preset| preset.name == value)
+                    .ok_or_else(|| error("unknown preset"))?;
+                editor.preset = Some(preset.name);
+            }
+            "camera" => {
+                let values = value
+                    .split_whitespace()
+                    .map(|token| number_of(token).map(|value| value as f32))
+                    .collect::<Result<Vec<f32>, String>>()?;
+                let [x, y, z, yaw, pitch, radius] = values[..] else {
+                    return Err(error("expected focus, yaw, pitch and radius"));
+                };
+                camera = Some(CameraView {
+                    focus: Vec3::new(x, y, z),
+                    yaw,
+                    pitch,
+                    radius,
+                });
+            }
+            "step" => {
+                let slots = value
+                    .split_whitespace()
+                    .map(|token| parse_slot(token).ok_or_else(|| error("invalid slot")))
+                    .collect::<Result<Vec<Slot>, String>>()?;
+                editor.steps.push(slots);
+            }
+            _ => return Err(error("unknown setting")),
+        }
+    }
+
+    if editor.steps.is_empty() || editor.steps.len() > MAX_EDITOR_STEPS {
+        return Err("unsupported number of steps".to_string());
+    }
+    for (index, step) in editor.steps.iter().enumerate() {
+        let paired = |qubit: usize, expected: Slot| step.get(qubit) == Some(&expected);
+        let valid = step.len() == editor.num_qubits
+            && step.iter().enumerate().all(|(qubit, slot)| match *slot {
+                Slot::Control { target } => {
+                    target != qubit && paired(target, Slot::Target { control: qubit })
+                }
+                Slot::Target { control } => {
+                    control != qubit && paired(control, Slot::Control { target: qubit })
+                }
+                _ => true,
+            });
+        if !valid {
+            return Err(format!("step {}: slots do not match the qubits", index));
+        }
+    }
+    let camera = camera.ok_or("missing camera")?;
+    Ok((editor, camera))
+}
+
+fn parse_slot(token: &str) -> Option<Slot> {
+    let rotation = |name: &str| {
+        token
+            .strip_prefix(name)?
+            .strip_prefix('(')?
+            .strip_suffix(')')?
+            .parse::<f64>()
+            .ok()
+    };
+    let slot = match token {
+        "-" => Slot::Empty,
+        "h" => Slot::Gate(Operation::H),
+        "x" => Slot::Gate(Operation::X),
+        "y" => Slot::Gate(Operation::Y),
+        "z" => Slot::Gate(Operation::Z),
+        "s" => Slot::Gate(Operation::S),
+        "t" => Slot::Gate(Operation::T),
+        _ => {
+            if let Some(target) = token.strip_prefix('@') {
+                Slot::Control {
+                    target: target.parse().ok()?,
+                }
+            } else if let Some(control) = token.strip_prefix('+') {
+                Slot::Target {
+                    control: control.parse().ok()?,
+                }
+            } else if let Some(theta) = rotation("rx") {
+                Slot::Gate(Operation::Rx(theta))
+            } else if let Some(theta) = rotation("ry") {
+                Slot::Gate(Operation::Ry(theta))
+            } else {
+                Slot::Gate(Operation::Rz(rotation("rz")?))
+            }
+        }
+    };
+    Some(slot)
+}
+
 fn main() {
-    App::new()
-        .add_plugins((DefaultPlugins, PanOrbitCameraPlugin))
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::View {
+        circuit: None,
+        workspace: PathBuf::from(DEFAULT_WORKSPACE),
+        lesson: None,
+        breakpoints: vec![],
+    });
+    match command {
+        Command::Run(args) => {
+            if let Err(error) = run_headless(args) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Command::View {
+            circuit,
+            workspace,
+            lesson,
+            breakpoints,
+        } => view(
+            circuit.as_deref(),
+            lesson.as_deref(),
+            &breakpoints,
+            workspace,
+        ),
+        Command::Optimize(args) => {
+            if let Err(error) = run_optimizer(args) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Command::Transpile {
+            circuit,
+            calibration,
+            out,
+        } => {
+            if let Err(error) = run_transpiler(&circuit, &calibration, out.as_deref()) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Command::Debug {
+            circuit,
+            seed,
+            noise,
+            breakpoints,
+        } => {
+            if let Err(error) = run_debugger(&circuit, seed, noise, &breakpoints) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Command::Presets => list_presets(),
+        Command::Gallery { out, qubits, seed } => {
+            if let Err(error) = write_gallery(&out, qubits, seed) {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        }
+        Command::Soak(args) => match run_soak(args) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(error) => {
+                eprintln!("error: {}", error);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+// Simulate a circuit from |0...0> without opening a window, sampling `shots` runs and reporting
+// the counts together with the final state of one more run, and optionally writing an
+// experiment report and the circuit's gate activity and explaining every step of that run
+fn run_headless(args: RunArgs) -> Result<(), String> {
+    let RunArgs {
+        circuit: path,
+        shots,
+        seed,
+        output: format,
+        out,
+        report: report_path,
+        activity: activity_path,
+        explain: explain_steps,
+    } = args;
+    let circuit = read_circuit(&path)?;
+    check_width(&path, &circuit)?;
+    if let Some(activity_path) = &activity_path {
+        fs::write(activity_path, gate_activity(&circuit).to_csv())
+            .map_err(|error| format!("cannot write {}: {}", activity_path.display(), error))?;
+    }
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let initial_state = Qubit::zero_state(circuit.num_qubits()).state;
+    let noise_model = NoiseModel::new();
+    let started = Instant::now();
+    let counts = Simulator::sample_trajectories_with_rng(
+        &circuit,
+        &initial_state,
+        &noise_model,
+        shots,
+        &mut rng,
+    );
+    let sampling = started.elapsed();
+    let started = Instant::now();
+    let (state, explanations) = if explain_steps {
+        let trace = Simulator::run_stepwise_trajectory_with_rng(
+            &circuit,
+            &initial_state,
+            &noise_model,
+            &mut rng,
+        );
+        (trace.final_state().to_qubit(), explain(&circuit, &trace))
+    } else {
+        let state =
+            Simulator::run_trajectory_with_rng(&circuit, &initial_state, &noise_model, &mut rng);
+        (state, vec![])
+    };
+    let final_state = started.elapsed();
+
+    if let Some(report_path) = &report_path {
+        let title = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into(),
+        );
+        let mut experiment = ExperimentReport::new(&title, &circuit)
+            .noise(&noise_model)
+            .counts(&counts)
+            .timing(&format!("sampling {} shots", shots), sampling)
+            .timing("final state", final_state);
+        if circuit.is_unitary() {
+            // Without measurements the final state gives the exact outcome probabilities
+            let probabilities = state
+                .probabilities()
+                .into_iter()
+                .enumerate()
+                .filter(|&(_, probability)| probability > 1e-12)
+                .map(|(index, probability)| {
+                    let bitstring = format!("{:0width$b}", index, width = circuit.num_qubits());
+                    (bitstring, probability)
+                })
+                .collect();
+            experiment = experiment.reference(probabilities);
+        }
+        fs::write(
+            report_path,
+            experiment.render(ReportFormat::from_path(report_path)),
+        )
+        .map_err(|error| format!("cannot write {}: {}", report_path.display(), error))?;
+    }
+
+    let num_qubits = circuit.num_qubits();
+    let basis = |index: usize| format!("{:0width$b}", index, width = num_qubits);
+    let report = match format {
+        OutputFormat::Text => {
+            let mut text = format!(
+                "qubits: {}\nseed: {}\ncounts ({} shots):\n",
+                num_qubits, seed, shots
+            );
+            for (bitstring, count) in counts.iter() {
+                text.push_str(&format!("  {}: {}\n", bitstring, count));
+            }
+            text.push_str("state:\n");
+            for (index, amplitude) in state.state.iter().enumerate() {
+                if amplitude.norm_sqr() > 1e-12 {
+                    text.push_str(&format!("  |{}>: {:.6}\n", basis(index), amplitude));
+                }
+            }
+            if explain_steps {
+                text.push_str("steps:\n");
+                for explanation in &explanations {
+                    text.push_str(&format!("  {}\n", explanation));
+                }
+            }
+            text
+        }
+        OutputFormat::Json => {
+            // Non-finite amplitudes become null rather than invalid `NaN` or `inf` literals
+            let counts: serde_json::Map<String, serde_json::Value> = counts
+                .iter()
+                .map(|(bitstring, count)| (bitstring.to_string(), count.into()))
+                .collect();
+            let state: Vec<serde_json::Value> = state
+                .state
+                .iter()
+                .map(|amplitude| serde_json::json!([amplitude.re, amplitude.im]))
+                .collect();
+            let mut value = serde_json::json!({
+                "qubits": num_qubits,
+                "shots": shots,
+                "seed": seed,
+                "counts": counts,
+                "state": state,
+            });
+            if explain_steps {
+                value["steps"] = explanations
+                    .iter()
+                    .map(Explanation::to_json_value)
+                    .collect();
+            }
+            format!(
+                "{}\n",
+                serde_json::to_string_pretty(&value).expect("the results serialize")
+            )
+        }
+    };
+    match &out {
+        Some(out) => fs::write(out, report)
+            .map_err(|error| format!("cannot write {}: {}", out.display(), error)),
+        None => {
+            print!("{}", report);
+            Ok(())
+        }
+    }
+}
+
+fn read_circuit(path: &Path) -> Result<Circuit, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|error| format!("cannot read {}: {}", path.display(), error))?;
+    // Circuits exported from Quirk or Cirq are JSON, everything else is read as OpenQASM
+    let parsed = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        interop::parse(&source)
+    } else {
+        qasm::parse(&source)
+    };
+    parsed.map_err(|error| format!("cannot parse {}: {}", path.display(), error))
+}
+
+// Reject a circuit too wide to simulate before allocating its state vector
+fn check_width(path: &Path, circuit: &Circuit) -> Result<(), String> {
+    if circuit.num_qubits() > MAX_SIMULATED_QUBITS {
+        return Err(format!(
+            "{} has {} qubits, more than the {} that can be simulated",
+            path.display(),
+            circuit.num_qubits(),
+            MAX_SIMULATED_QUBITS
+        ));
+    }
+    Ok(())
+}
+
+// Parse the breakpoints given on the command line
+fn parse_breakpoints(specs: &[String]) -> Result<Vec<Breakpoint>, String> {
+    specs
+        .iter()
+        .map(|spec| spec.parse().map_err(|error| format!("{}", error)))
+        .collect()
+}
+
+// Record one run of a circuit and read debugger commands from stdin until `quit` or the end of
+// the input
+fn run_debugger(
+    path: &Path,
+    seed: Option<u64>,
+    noise: f64,
+    breakpoints: &[String],
+) -> Result<(), String> {
+    let circuit = read_circuit(path)?;
+    check_width(path, &circuit)?;
+    if !(0.0..=1.0).contains(&noise) {
+        return Err(format!("the noise {} is not a probability", noise));
+    }
+    let mut noise_model = NoiseModel::new();
+    if noise > 0.0 {
+        noise_model.add_error(depolarizing(noise));
+    }
+    let seed = seed.unwrap_or_else(rand::random);
+    let initial_state = Qubit::zero_state(circuit.num_qubits()).state;
+    let mut debugger = Debugger::new(&circuit, &initial_state, &noise_model, seed);
+    for breakpoint in parse_breakpoints(breakpoints)? {
+        debugger
+            .add_breakpoint(breakpoint.clone())
+            .map_err(|error| format!("breakpoint {}: {}", breakpoint, error))?;
+    }
+    println!(
+        "Recorded {} instructions with seed {}; type `help` for the commands",
+        debugger.len(),
+        seed
+    );
+    print_location(&debugger);
+
+    let stdin = std::io::stdin();
+    let mut line = String::new();
+    loop {
+        print!("(qdb) ");
+        std::io::stdout()
+            .flush()
+            .map_err(|error| error.to_string())?;
+        line.clear();
+        if stdin
+            .read_line(&mut line)
+            .map_err(|error| error.to_string())?
+            == 0
+        {
+            println!();
+            return Ok(());
+        }
+        let (command, argument) = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .map_or((line.trim(), ""), |(command, argument)| {
+                (command, argument.trim())
+            });
+        let count = || argument.parse().unwrap_or(1);
+        match command {
+            "" => continue,
+            "s" | "step" => {
+                for _ in 0..count() {
+                    debugger.step();
+                }
+            }
+            "b" | "back" => {
+                for _ in 0..count() {
+                    debugger.step_back();
+                }
+            }
+            "c" | "continue" => {
+                let stop = debugger.resume();
+                print_stop(&debugger, stop);
+            }
+            "rc" | "reverse" => {
+                let stop = debugger.reverse();
+                print_stop(&debugger, stop);
+            }
+            "g" | "goto" => match argument.parse() {
+                Ok(position) => debugger.goto(position),
+                Err(_) => println!("goto needs a position"),
+            },
+            "break" => match argument
+                .parse()
+                .and_then(|breakpoint| debugger.add_breakpoint(breakpoint))
+            {
+                Ok(index) => {
+                    println!("breakpoint {}: {}", index, debugger.breakpoints()[index]);
+                    continue;
+                }
+                Err(error) => {
+                    println!("{}", error);
+                    continue;
+                }
+            },
+            "delete" => match argument
+                .parse()
+                .ok()
+                .and_then(|index| debugger.remove_breakpoint(index))
+            {
+                Some(breakpoint) => {
+                    println!("deleted {}", breakpoint);
+                    continue;
+                }
+                None => {
+                    println!("no breakpoint {}", argument);
+                    continue;
+                }
+            },
+            "info" => {
+                for (index, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                    println!("breakpoint {}: {}", index, breakpoint);
+                }
+                continue;
+            }
+            "p" | "print" => {
+                let num_qubits = circuit.num_qubits();
+                for (index, amplitude) in debugger.state().iter().enumerate() {
+                    if amplitude.norm_sqr() > 1e-12 {
+                        println!(
+                            "  |{:0width$b}>: {:.6} (p = {:.4})",
+                            index,
+                            amplitude,
+                            amplitude.norm_sqr(),
+                            width = num_qubits
+                        );
+                    }
+                }
+                continue;
+            }
+            "e" | "events" => {
+                for event in debugger.events() {
+                    println!("  {}", event);
+                }
+                continue;
+            }
+            "h" | "help" => {
+                println!(
+                    "step|s [n], back|b [n]     move n instructions forwards or backwards\n\
+                     continue|c, reverse|rc    run to the next breakpoint forwards or backwards\n\
+                     goto|g <position>         jump to the state after <position> instructions\n\
+                     break <spec>              break at an instruction (3), before gates on a\n\
+                     \x20                         qubit (q1) or on a condition (P(|11>) > 0.4)\n\
+                     delete <n>, info          remove or list breakpoints\n\
+                     print|p, events|e         show the state or the events so far\n\
+                     quit|q                    leave the debugger"
+                );
+                continue;
+            }
+            "q" | "quit" => return Ok(()),
+            _ => {
+                println!(
+                    "unknown command `{}`; type `help` for the commands",
+                    command
+                );
+                continue;
+            }
+        }
+        print_location(&debugger);
+    }
+}
+
+// Print the breakpoint a continue or reverse stopped at
+fn print_stop(debugger: &Debugger, stop: Stop) {
+    if let Stop::Breakpoint(index) = stop {
+        println!("breakpoint {}: {}", index, debugger.breakpoints()[index]);
+    }
+}
+
+// Print the position and the instruction about to run
+fn print_location(debugger: &Debugger) {
+    match debugger.next_instruction() {
+        Some(instruction) => println!(
+            "at {}/{}, next #{} {:?} on {:?}",
+            debugger.position(),
+            debugger.len(),
+            debugger.position(),
+            instruction.operation,
+            instruction.qubits
+        ),
+        None => println!(
+            "at {}/{}, end of the circuit",
+            debugger.position(),
+            debugger.len()
+        ),
+    }
+}
+
+// Place a circuit onto the calibrated device both naively and noise-adaptively, print both
+// predictions and optionally write the adaptive routing
+fn run_transpiler(
+    circuit_path: &Path,
+    calibration_path: &Path,
+    out: Option<&Path>,
+) -> Result<(), String> {
+    let circuit = read_circuit(circuit_path)?;
+    let source = fs::read_to_string(calibration_path)
+        .map_err(|error| format!("cannot read {}: {}", calibration_path.display(), error))?;
+    let calibration = Calibration::parse(&source)
+        .map_err(|error| format!("cannot parse {}: {}", calibration_path.display(), error))?;
+    let comparison = compare_placements(&circuit, &calibration)
+        .map_err(|error| format!("cannot transpile {}: {}", circuit_path.display(), error))?;
+    print!("{}", comparison);
+    println!("final layout: {:?}", comparison.adaptive.final_layout);
+    if let Some(out) = out {
+        let routed = qasm::to_qasm(&comparison.adaptive.circuit, QasmVersion::V2)
+            .map_err(|error| format!("cannot export the routed circuit: {}", error))?;
+        fs::write(out, routed)
+            .map_err(|error| format!("cannot write {}: {}", out.display(), error))?;
+    }
+    Ok(())
+}
+
+// Print every listed preset with its width, size and description
+fn list_presets() {
+    for preset in PRESETS {
+        let circuit = preset.circuit();
+        println!(
+            "{:<14} {:>2} qubits {:>4} instructions  {}",
+            preset.name,
+            circuit.num_qubits(),
+            circuit.instructions().len(),
+            preset.description
+        );
+    }
+    println!(
+        "ghz-<n> and qft-<n> take 2 to {} qubits, grover-<n> 2 to {}",
+        MAX_PRESET_QUBITS, MAX_GROVER_PRESET_QUBITS
+    );
+}
+
+// Write the canonical state gallery and list the files written
+fn write_gallery(out: &Path, num_qubits: usize, seed: u64) -> Result<(), String> {
+    if !(2..=MAX_GALLERY_QUBITS).contains(&num_qubits) {
+        return Err(format!(
+            "--qubits must be between 2 and {}",
+            MAX_GALLERY_QUBITS
+        ));
+    }
+    let gallery = Gallery::canonical(num_qubits, seed);
+    gallery
+        .write(out)
+        .map_err(|error| format!("cannot write {}: {}", out.display(), error))?;
+    for entry in &gallery.entries {
+        println!("{:<16} {}", entry.slug, entry.dirac());
+    }
+    println!(
+        "wrote {} states to {}",
+        gallery.entries.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+// Run the soak test, printing a status line to stderr every ten seconds, then write the report;
+// returns whether every invariant held
+fn run_soak(args: SoakArgs) -> Result<bool, String> {
+    if args.max_qubits == 0 || args.max_qubits > MAX_SOAK_QUBITS {
+        return Err(format!(
+            "--max-qubits must be between 1 and {}",
+            MAX_SOAK_QUBITS
+        ));
+    }
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let soak = Soak::new(Duration::from_secs(args.duration))
+        .with_max_qubits(args.max_qubits)
+        .with_seed(seed);
+    let mut next_status = Duration::from_secs(10);
+    let report = soak.run_with(|report| {
+        if report.elapsed >= next_status {
+            eprintln!(
+                "{:.0} s: {} circuits, {} failures",
+                report.elapsed.as_secs_f64(),
+                report.iterations,
+                report.failures.len()
+            );
+            next_status += Duration::from_secs(10);
+        }
+    });
+    let text = match args.output {
+        OutputFormat::Text => format!("seed: {}\n{}", seed, report),
+        OutputFormat::Json => {
+            let mut value = report.to_json_value();
+            value["seed"] = seed.into();
+            format!(
+                "{}\n",
+                serde_json::to_string_pretty(&value).expect("the report serializes")
+            )
+        }
+    };
+    match &args.out {
+        Some(out) => fs::write(out, text)
+            .map_err(|error| format!("cannot write {}: {}", out.display(), error))?,
+        None => print!("{}", text),
+    }
+    Ok(report.passed())
+}
+
+// Minimize the energy of a Hamiltonian from random starting parameters, streaming every
+// iteration to stdout, the CSV file and, with `--plot`, a live chart
+fn run_optimizer(args: OptimizeArgs) -> Result<(), String> {
+    let hamiltonian = Hamiltonian::parse(&args.hamiltonian)
+        .map_err(|error| format!("cannot parse the Hamiltonian: {}", error))?;
+    let ansatz = match args.ansatz {
+        AnsatzKind::HardwareEfficient => Ansatz::HardwareEfficient {
+            num_qubits: hamiltonian.num_qubits(),
+            layers: args.layers,
+        },
+        AnsatzKind::Qaoa if !hamiltonian.is_diagonal() => {
+            return Err("QAOA needs a Hamiltonian of Z terms only".to_string());
+        }
+        AnsatzKind::Qaoa => Ansatz::Qaoa {
+            hamiltonian: hamiltonian.clone(),
+            layers: args.layers,
+        },
+    };
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let initial: Vec<f64> = (0..ansatz.num_parameters())
+        .map(|_| rng.gen_range(-PI..PI))
+        .collect();
+    let csv = match &args.csv {
+        Some(path) => {
+            let mut file = File::create(path)
+                .map_err(|error| format!("cannot write {}: {}", path.display(), error))?;
+            writeln!(file, "{}", Iteration::csv_header(initial.len()))
+                .map_err(|error| format!("cannot write {}: {}", path.display(), error))?;
+            Some((path.clone(), file))
+        }
+        None => None,
+    };
+    let optimizer = GradientDescent::new(args.learning_rate, args.iterations);
+    println!("qubits: {}\nseed: {}", hamiltonian.num_qubits(), seed);
+
+    let last = if args.plot {
+        let (sender, receiver) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            optimize(
+                &ansatz,
+                &hamiltonian,
+                optimizer,
+                &initial,
+                csv,
+                Some(sender),
+            )
+        });
+        App::new()
+            .add_plugins(DefaultPlugins)
+            .insert_resource(OptimizerProgress {
+                receiver: Mutex::new(receiver),
+                iterations: vec![],
+                max_iterations: args.iterations,
+            })
+            .add_systems(Startup, setup_progress_chart)
+            .add_systems(Update, update_progress_chart)
+            .run();
+        // Closing the window early leaves the optimizer running in the terminal
+        worker
+            .join()
+            .expect("the optimizer thread does not panic")?
+    } else {
+        optimize(&ansatz, &hamiltonian, optimizer, &initial, csv, None)?
+    };
+    println!(
+        "final energy: {:.8} after {} steps\nparameters: {:?}",
+        last.energy, last.step, last.params
+    );
+    Ok(())
+}
+
+// Run gradient descent on the energy of the ansatz state, printing each iteration, appending it
+// to the CSV file and sending it to the chart as soon as it is known
+fn optimize(
+    ansatz: &Ansatz,
+    hamiltonian: &Hamiltonian,
+    optimizer: GradientDescent,
+    initial: &[f64],
+    mut csv: Option<(PathBuf, File)>,
+    sender: Option<Sender<Iteration>>,
+) -> Result<Iteration, String> {
+    let initial_state = Qubit::zero_state(ansatz.num_qubits()).state;
+    let energy = |params: &[f64]| {
+        hamiltonian.expectation(&Simulator::run(&ansatz.circuit(params), &initial_state))
+    };
+    let mut csv_error = None;
+    let last = optimizer.minimize(energy, initial, |iteration| {
+        println!(
+            "step {:>4}  energy {:>12.8}  gradient {:.3e}",
+            iteration.step, iteration.energy, iteration.gradient_norm
+        );
+        if let Some((path, file)) = &mut csv {
+            // Flush every row so the file can be followed while the optimizer runs
+            if let Err(error) =
+                writeln!(file, "{}", iteration.to_csv_row()).and_then(|_| file.flush())
+            {
+                csv_error = Some(format!("cannot write {}: {}", path.display(), error));
+                csv = None;
+            }
+        }
+        if let Some(sender) = &sender {
+            // The chart may have been closed already
+            let _ = sender.send(iteration.clone());
+        }
+    });
+    match csv_error {
+        Some(error) => Err(error),
+        None => Ok(last),
+    }
+}
+
+// Read a lesson file: a `title`, an `intro`, the number of `qubits` and an array of `steps`, each
+// with an `instruction` and an OpenQASM `target` that extends the targets of the steps before it
+fn parse_lesson(text: &str) -> Result<Lesson, String> {
+    let table: toml::Table = text
+        .parse()
+        .map_err(|error: toml::de::Error| error.to_string())?;
+    let string = |table: &toml::Table, key: &str| {
+        table
+            .get(key)
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+            .ok_or(format!("missing string {}", key))
+    };
+    let num_qubits = table
+        .get("qubits")
+        .and_then(toml::Value::as_integer)
+        .and_then(|qubits| usize::try_from(qubits).ok())
+        .filter(|qubits| (1..=MAX_EDITOR_QUBITS).contains(qubits))
+        .ok_or(format!("qubits must be 1 to {}", MAX_EDITOR_QUBITS))?;
+    let steps = table
+        .get("steps")
+        .and_then(toml::Value::as_array)
+        .ok_or("missing steps")?;
+
+    let mut source = format!(
+        "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[{}];\n",
+        num_qubits
+    );
+    let mut lesson = Lesson {
+        title: string(&table, "title")?,
+        intro: string(&table, "intro").unwrap_or_default(),
+        num_qubits,
+        steps: vec![],
+        completed: 0,
+        matches: true,
+    };
+    for (index, step) in steps.iter().enumerate() {
+        let step = step
+            .as_table()
+            .ok_or(format!("step {} is not a table", index + 1))?;
+        source.push_str(&string(step, "target")?);
+        source.push('\n');
+        let target =
+            qasm::parse(&source).map_err(|error| format!("step {}: {}", index + 1, error))?;
+        lesson.steps.push(LessonStep {
+            instruction: string(step, "instruction")?,
+            target,
+        });
+    }
+    Ok(lesson)
+}
+
+fn load_lesson(name: &str) -> Result<Lesson, String> {
+    let text = match LESSONS.iter().find(|(lesson, _)| *lesson == name) {
+        Some((_, text)) => text.to_string(),
+        None => {
+            fs::read_to_string(name).map_err(|error| format!("cannot read {}: {}", name, error))?
+        }
+    };
+    parse_lesson(&text).map_err(|error| format!("invalid lesson {}: {}", name, error))
+}
+
+// Open the visualizer with a lesson or a circuit file loaded into the editor, or else with the
+// saved workspace, falling back to the default example if there is none yet
+fn view(circuit: Option<&Path>, lesson: Option<&str>, breakpoints: &[String], path: PathBuf) {
+    let breakpoints = parse_breakpoints(breakpoints).unwrap_or_else(|error| {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    });
+    let lesson = lesson.map(|name| {
+        load_lesson(name).unwrap_or_else(|error| {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        })
+    });
+    let (editor, camera) = match (circuit, &lesson) {
+        (_, Some(lesson)) => {
+            // Leave room for the final target with a couple of spare time steps
+            let depth = lesson.steps.last().map_or(0, |step| step.target.depth());
+            let num_steps = (depth + 2).min(MAX_EDITOR_STEPS);
+            (CircuitEditor::empty(lesson.num_qubits, num_steps), None)
+        }
+        (Some(circuit), None) => {
+            match read_circuit(circuit).and_then(|circuit| CircuitEditor::from_circuit(&circuit)) {
+                Ok(editor) => (editor, None),
+                Err(error) => {
+                    eprintln!("error: {}", error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        (None, None) => match fs::read_to_string(&path) {
+            Ok(text) => match parse_workspace(&text) {
+                Ok((editor, camera)) => {
+                    println!("Restored workspace from {}", path.display());
+                    (editor, Some(camera))
+                }
+                Err(error) => {
+                    eprintln!("Ignoring workspace {}: {}", path.display(), error);
+                    (CircuitEditor::example(), None)
+                }
+            },
+            Err(_) => (CircuitEditor::example(), None),
+        },
+    };
+
+    let mut app = App::new();
+    app.add_plugins((DefaultPlugins, PanOrbitCameraPlugin))
+        .add_event::<SimulationFinished>()
+        .init_resource::<SampledCounts>()
+        .insert_resource(Breakpoints(breakpoints))
+        .insert_resource(editor)
+        .insert_resource(WorkspaceFile { path, camera })
         .add_systems(
             Startup,
-            (setup_camera_and_light, run_quantum_simulation).chain(),
+            (
+                setup_camera_and_light,
+                setup_scene,
+                spawn_lesson_panel.run_if(resource_exists::<Lesson>),
+            ),
         )
-        .add_systems(Update, gizmo_draw)
-        .run();
+        .add_systems(
+            Update,
+            (
+                handle_editor_buttons,
+                save_workspace,
+                rebuild_editor_panel,
+                check_lesson.run_if(resource_exists::<Lesson>),
+                rerun_simulation,
+                poll_simulation_tasks,
+                show_simulation_results,
+                (
+                    handle_measure_buttons,
+                    handle_shots_button,
+                    control_playback,
+                    advance_playback,
+                    animate_spheres,
+                    sync_event_list,
+                    update_histogram,
+                )
+                    .chain()
+                    .run_if(resource_exists::<Timeline>),
+                gizmo_draw,
+            )
+                .chain(),
+        );
+    if let Some(lesson) = lesson {
+        app.insert_resource(lesson);
+    }
+    app.run();
 }
 
-fn setup_camera_and_light(mut commands: Commands) {
-    // Setup camera
+fn setup_camera_and_light(mut commands: Commands, workspace: Res<WorkspaceFile>) {
+    // Setup camera, restoring the orbit saved in the workspace
     commands.spawn((
         Camera3dBundle {
             transform: Transform::from_xyz(0.0, 2.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
             ..Default::default()
         },
-        PanOrbitCamera::default(),
+        workspace
+            .camera
+            .map_or_else(PanOrbitCamera::default, CameraView::camera),
     ));
 
     // Setup light
@@ -45,54 +1831,11 @@ fn setup_camera_and_light(mut commands: Commands) {
     });
 }
 
-fn run_quantum_simulation(
+fn setup_scene(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let num_qubits: usize = 4;
-
-    // Define the initial state |000>
-    let initial_state: Vec<Complex<f64>> = vec![
-        Complex::new(1.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-        Complex::new(0.0, 0.0),
-    ];
-
-    let mut circuit = Circuit::new();
-
-    // Apply different gates to each qubit
-    circuit.add_gate(hadamard(num_qubits)); // Apply Hadamard to all qubits
-    circuit.add_gate(cnot(0, 1, num_qubits)); // Apply CNOT with control=0, target=1
-    circuit.add_gate(pauli_x()); // Apply Pauli-X gate to all qubits
-    circuit.add_gate(cnot(1, 2, num_qubits)); // Apply CNOT with control=1, target=2
-    circuit.add_gate(pauli_y()); // Apply Pauli-Y gate to all qubits
-    circuit.add_gate(cnot(2, 3, num_qubits)); // Apply CNOT with control=2, target=3
-    circuit.add_gate(s()); // Apply S gate to all qubits
-    circuit.add_gate(pauli_z()); // Apply Pauli-Z gate to all qubits
-
-    let final_qubit = Simulator::run(&circuit, &initial_state);
-    println!("Final qubit state: {:?}", final_qubit.state);
-
-    // Display the probabilities of each basis state
-    let probabilities: Vec<f64> = final_qubit.state.iter().map(|amp| amp.norm_sqr()).collect();
-    for (index, prob) in probabilities.iter().enumerate() {
-        println!("|{}>: {:.4}", index, prob);
-    }
-
     commands.spawn(PbrBundle {
         mesh: meshes.add(Sphere::new(1.0).mesh()),
         material: materials.add(StandardMaterial {
@@ -102,84 +1845,1032 @@ fn run_quantum_simulation(
         ..Default::default()
     });
 
-    // Calculate and display Bloch sphere coordinates for each qubit
-    for qubit_index in 0..num_qubits {
-        let reduced_state = get_reduced_state(&final_qubit.state, qubit_index, num_qubits);
-        let (theta, phi) = calculate_angles(reduced_state);
-        println!("Qubit {}: Theta: {}, Phi: {}", qubit_index, theta, phi);
-        let (x, y, z) = bloch_sphere_coordinates(theta, phi);
-        println!(
-            "Qubit {}: Cartesian coordinates on Bloch sphere: (x: {}, y: {}, z: {})",
-            qubit_index, x, y, z
-        );
+    // The event list shows progress until the simulation results arrive
+    commands.spawn((
+        TextBundle::from_section("Simulating...", TextStyle::default()).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..Default::default()
+        }),
+        EventList,
+    ));
+}
 
-        // Add sphere for each qubit
-        commands
-            .spawn(PbrBundle {
-                mesh: meshes.add(Sphere::new(0.1).mesh()),
-                material: materials.add(StandardMaterial {
-                    base_color: Color::srgb(0.0, 0.0, 0.0),
+// Run the edited circuit on the async compute task pool whenever it changes, so the UI keeps
+// rendering while large circuits are simulated. The scene of the previous circuit is removed and
+// a run still in flight for it is cancelled by dropping its task.
+fn rerun_simulation(
+    mut commands: Commands,
+    editor: Res<CircuitEditor>,
+    mut simulated: Local<Option<u64>>,
+    tasks: Query<Entity, With<SimulationTask>>,
+    views: Query<Entity, With<SimulationView>>,
+    mut sampled: ResMut<SampledCounts>,
+    mut lists: Query<&mut Text, With<EventList>>,
+) {
+    if *simulated == Some(editor.revision) {
+        return;
+    }
+    *simulated = Some(editor.revision);
+
+    for entity in tasks.iter() {
+        commands.entity(entity).despawn();
+    }
+    for entity in views.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Timeline>();
+    commands.remove_resource::<LiveState>();
+    sampled.0 = None;
+    for mut text in lists.iter_mut() {
+        *text = Text::from_section("Simulating...", TextStyle::default());
+    }
+
+    // Start from the all-zeros state
+    let circuit = editor.to_circuit();
+    let noise_model = editor.noise_model();
+    let seed = editor.seed;
+    let initial_state = Qubit::zero_state(editor.num_qubits).state;
+    let task = AsyncComputeTaskPool::get()
+        .spawn(async move { simulate(&circuit, &initial_state, &noise_model, seed) });
+    commands.spawn(SimulationTask(task));
+    commands.insert_resource(MeasurementRng(StdRng::seed_from_u64(seed)));
+}
+
+// Record one trajectory of the circuit in a debugger and get the Bloch positions at every step to
+// animate the spheres along
+fn simulate(
+    circuit: &Circuit,
+    initial_state: &[Complex<f64>],
+    noise_model: &NoiseModel,
+    seed: u64,
+) -> SimulationOutput {
+    let debugger = Debugger::new(circuit, initial_state, noise_model, seed);
+    let states: Vec<Qubit> = (0..=debugger.len())
+        .map(|position| debugger.state_at(position).to_qubit())
+        .collect();
+    SimulationOutput {
+        activity: gate_activity(circuit),
+        explanations: explain(circuit, debugger.trace()),
+        events: circuit
+            .instructions()
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| {
+                format!(
+                    "#{} {:?} on {:?}",
+                    index, instruction.operation, instruction.qubits
+                )
+            })
+            .collect(),
+        positions: states
+            .iter()
+            .map(|state| bloch_positions(state, circuit.num_qubits()))
+            .collect(),
+        probabilities: states.iter().map(Qubit::probabilities).collect(),
+        final_qubit: states.last().expect("a run has an initial state").clone(),
+        debugger,
+    }
+}
+
+// Check running simulations without blocking and announce the ones that have finished
+fn poll_simulation_tasks(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut SimulationTask)>,
+    mut finished: EventWriter<SimulationFinished>,
+) {
+    for (entity, mut task) in tasks.iter_mut() {
+        if let Some(output) = block_on(future::poll_once(&mut task.0)) {
+            finished.send(SimulationFinished(output));
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Print the results of finished simulations and add their qubits and timeline to the scene
+fn show_simulation_results(
+    mut commands: Commands,
+    mut finished: EventReader<SimulationFinished>,
+    breakpoints: Res<Breakpoints>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for SimulationFinished(output) in finished.read() {
+        let final_qubit = &output.final_qubit;
+        println!("Final qubit state: {:?}", final_qubit.state);
+
+        // Display the probabilities of each basis state
+        for (index, prob) in final_qubit.probabilities().iter().enumerate() {
+            println!("|{}>: {:.4}", index, prob);
+        }
+
+        // Calculate and display Bloch sphere coordinates for each qubit; the spheres start at
+        // the initial state and are animated towards the final one
+        for (qubit_index, &start) in output.positions[0].iter().enumerate() {
+            let (x, y, z) = bloch_vector(final_qubit, qubit_index);
+            println!(
+                "Qubit {}: Bloch vector: (x: {}, y: {}, z: {}), entanglement entropy: {}",
+                qubit_index,
+                x,
+                y,
+                z,
+                entanglement_entropy(final_qubit, &[qubit_index])
+            );
+
+            // Add sphere for each qubit
+            commands
+                .spawn(PbrBundle {
+                    mesh: meshes.add(Sphere::new(0.1).mesh()),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(0.0, 0.0, 0.0),
+                        ..Default::default()
+                    }),
+                    transform: Transform::from_translation(start),
                     ..Default::default()
-                }),
-                transform: Transform::from_xyz(x as f32, y as f32, z as f32),
+                })
+                .insert((QubitSphere(qubit_index), SimulationView))
+                .insert(Position(start))
+                .insert(Trail::default());
+        }
+
+        println!("Command: Measure");
+        let measurement = final_qubit.measure();
+        println!("Measurement result: |{}>", measurement);
+
+        let num_qubits = output.positions[0].len();
+        spawn_measurement_panel(&mut commands, num_qubits, &output.activity);
+        if num_qubits <= MAX_HISTOGRAM_QUBITS {
+            spawn_histogram(&mut commands, num_qubits);
+        }
+        commands.insert_resource(LiveState(final_qubit.clone()));
+        let mut debugger = output.debugger.clone();
+        for breakpoint in &breakpoints.0 {
+            if let Err(error) = debugger.add_breakpoint(breakpoint.clone()) {
+                eprintln!("Ignoring breakpoint {}: {}", breakpoint, error);
+            }
+        }
+        commands.insert_resource(Timeline {
+            debugger,
+            events: output.events.clone(),
+            explanations: output.explanations.clone(),
+            positions: output.positions.clone(),
+            probabilities: output.probabilities.clone(),
+            step: 0,
+            progress: 1.0,
+            playing: true,
+            speed: DEFAULT_SPEED,
+        });
+    }
+}
+
+// Add the measure buttons, the results panel and the gate activity heatmap in the top right
+// corner
+fn spawn_measurement_panel(commands: &mut Commands, num_qubits: usize, activity: &GateActivity) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(12.0),
+                    right: Val::Px(12.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SimulationView,
+        ))
+        .with_children(|panel| {
+            let buttons = std::iter::once((None, "Measure".to_string()))
+                .chain((0..num_qubits).map(|qubit| (Some(qubit), format!("Measure q{}", qubit))));
+            for (target, label) in buttons {
+                panel
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                                ..Default::default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..Default::default()
+                        },
+                        MeasureButton(target),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(label, TextStyle::default()));
+                    });
+            }
+            panel
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                            ..Default::default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..Default::default()
+                    },
+                    ShotsButton,
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        format!("Run {} shots", SHOTS),
+                        TextStyle::default(),
+                    ));
+                });
+            panel.spawn((
+                TextBundle::from_section("Results:\n", TextStyle::default()),
+                ResultsPanel,
+            ));
+            if num_qubits <= MAX_HEATMAP_QUBITS {
+                spawn_heatmap(panel, activity);
+            }
+        });
+}
+
+// Add a grid with the number of gates on each qubit on the diagonal and the number of gates
+// between each pair of qubits off it, redder for busier qubits and couplings
+fn spawn_heatmap(panel: &mut ChildBuilder, activity: &GateActivity) {
+    let max = activity.max().max(1) as f32;
+    let label = |text: String| {
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font_size: 12.0,
+                ..Default::default()
+            },
+        )
+    };
+    panel.spawn(TextBundle::from_section(
+        "Gate activity:",
+        TextStyle::default(),
+    ));
+    for (qubit, row) in activity.matrix().into_iter().enumerate() {
+        panel
+            .spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(1.0),
+                    ..Default::default()
+                },
                 ..Default::default()
             })
-            .insert(QubitSphere)
-            .insert(Position(Vec3::new(x as f32, y as f32, z as f32)));
+            .with_children(|cells| {
+                cells.spawn(label(format!("q{:<3}", qubit)));
+                for count in row {
+                    let heat = count as f32 / max;
+                    cells
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(HEATMAP_CELL),
+                                height: Val::Px(HEATMAP_CELL),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: Color::srgb(
+                                0.15 + 0.85 * heat,
+                                0.15 + 0.1 * heat,
+                                0.2 * (1.0 - heat),
+                            )
+                            .into(),
+                            ..Default::default()
+                        })
+                        .with_children(|cell| {
+                            cell.spawn(label(count.to_string()));
+                        });
+                }
+            });
     }
-
-    println!("Command: Measure");
-    let measurement = final_qubit.measure();
-    println!("Measurement result: |{}>", measurement);
 }
 
-fn get_reduced_state(
-    state: &[Complex<f64>],
-    qubit_index: usize,
-    _num_qubits: usize,
-) -> [Complex<f64>; 2] {
-    let mut reduced_state = [Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)];
-    let mask = 1 << qubit_index;
+// Collapse the live state when a measure button is pressed, log the outcome and animate the
+// spheres snapping to their post-measurement positions
+fn handle_measure_buttons(
+    mut buttons: Query<(&Interaction, &MeasureButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut live: ResMut<LiveState>,
+    mut rng: ResMut<MeasurementRng>,
+    mut timeline: ResMut<Timeline>,
+    mut sampled: ResMut<SampledCounts>,
+    mut results: Query<&mut Text, With<ResultsPanel>>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        *color = button_color(interaction);
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
 
-    for (i, amplitude) in state.iter().enumerate() {
-        if (i & mask) == 0 {
-            reduced_state[0] += amplitude;
-        } else {
-            reduced_state[1] += amplitude;
+        let qubit = &mut live.0;
+        let outcome = match button.0 {
+            Some(target) => format!(
+                "q{} -> {}",
+                target,
+                qubit.measure_qubit_with_rng(target, &mut rng.0)
+            ),
+            None => {
+                let bits: String = qubit
+                    .measure_all_with_rng(&mut rng.0)
+                    .iter()
+                    .rev()
+                    .map(|bit| bit.to_string())
+                    .collect();
+                format!("all -> |{}>", bits)
+            }
+        };
+        println!("Measurement result: {}", outcome);
+
+        let positions = bloch_positions(qubit, timeline.positions[0].len());
+        timeline.events.push(format!("measure {}", outcome));
+        timeline.positions.push(positions);
+        timeline.probabilities.push(qubit.probabilities());
+        timeline.step = timeline.events.len();
+        sampled.0 = None;
+        timeline.progress = 0.0;
+        timeline.playing = false;
+        for mut text in results.iter_mut() {
+            text.sections.push(TextSection::new(
+                format!("{}\n", outcome),
+                TextStyle::default(),
+            ));
         }
     }
+}
+
+// Sample shots from the live state when the shots button is pressed
+fn handle_shots_button(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), ShotsButtonChanged>,
+    live: Res<LiveState>,
+    mut rng: ResMut<MeasurementRng>,
+    mut sampled: ResMut<SampledCounts>,
+    mut results: Query<&mut Text, With<ResultsPanel>>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = button_color(interaction);
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let counts = live.0.sample_with_rng(SHOTS, &mut rng.0);
+        if let Some((bitstring, count)) = counts.most_frequent() {
+            let summary = format!("{} shots, most frequent |{}>: {}", SHOTS, bitstring, count);
+            println!("{}", summary);
+            for mut text in results.iter_mut() {
+                text.sections.push(TextSection::new(
+                    format!("{}\n", summary),
+                    TextStyle::default(),
+                ));
+            }
+        }
+        sampled.0 = Some(counts);
+    }
+}
+
+fn button_color(interaction: &Interaction) -> BackgroundColor {
+    match interaction {
+        Interaction::Pressed => BUTTON_PRESSED_COLOR,
+        Interaction::Hovered => BUTTON_HOVERED_COLOR,
+        Interaction::None => BUTTON_COLOR,
+    }
+    .into()
+}
+
+// Rebuild the circuit editor in the bottom right corner whenever it changes: the gate palette,
+// the buttons resizing the circuit and one row of slots per qubit, one slot per time step
+fn rebuild_editor_panel(
+    mut commands: Commands,
+    editor: Res<CircuitEditor>,
+    panels: Query<Entity, With<EditorPanel>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+    for entity in panels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 
-    let norm = (reduced_state[0].norm_sqr() + reduced_state[1].norm_sqr()).sqrt();
-    if norm > 0.0 {
-        reduced_state[0] /= norm;
-        reduced_state[1] /= norm;
+    let row = || NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(4.0),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(12.0),
+                    right: Val::Px(12.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            EditorPanel,
+        ))
+        .with_children(|panel| {
+            let heading = match editor.preset {
+                Some(name) => format!(
+                    "Showing preset {} read-only; click a slot to start a new circuit",
+                    name
+                ),
+                None => "Select a gate, then click a slot".to_string(),
+            };
+            panel.spawn(TextBundle::from_section(
+                format!(
+                    "{}\nRotations by {:.3} rad, depolarizing noise {:.2}, seed {}",
+                    heading, editor.angle, editor.noise, editor.seed
+                ),
+                TextStyle {
+                    font_size: 16.0,
+                    ..Default::default()
+                },
+            ));
+            panel.spawn(row()).with_children(|row| {
+                for tool in TOOLS {
+                    editor_button(row, tool.label(), tool == editor.tool, ToolButton(tool));
+                }
+            });
+            panel.spawn(row()).with_children(|row| {
+                for (action, label) in [
+                    (EditorAction::AddStep, "+ step"),
+                    (EditorAction::RemoveStep, "- step"),
+                    (EditorAction::AddQubit, "+ qubit"),
+                    (EditorAction::RemoveQubit, "- qubit"),
+                    (EditorAction::Clear, "Clear"),
+                    (EditorAction::Example, "Example"),
+                ] {
+                    editor_button(row, label, false, action);
+                }
+                editor_button(
+                    row,
+                    "Presets",
+                    editor.show_presets,
+                    EditorAction::TogglePresets,
+                );
+            });
+            if editor.show_presets {
+                panel.spawn(row()).with_children(|row| {
+                    for preset in PRESETS {
+                        editor_button(
+                            row,
+                            preset.name,
+                            editor.preset == Some(preset.name),
+                            EditorAction::Preset(preset.name),
+                        );
+                    }
+                });
+            }
+            panel.spawn(row()).with_children(|row| {
+                for (action, label) in [
+                    (EditorAction::DecreaseAngle, "- angle"),
+                    (EditorAction::IncreaseAngle, "+ angle"),
+                    (EditorAction::DecreaseNoise, "- noise"),
+                    (EditorAction::IncreaseNoise, "+ noise"),
+                    (EditorAction::NewSeed, "New seed"),
+                ] {
+                    editor_button(row, label, false, action);
+                }
+                editor_button(row, "Save", false, SaveButton);
+            });
+            for qubit in 0..editor.num_qubits {
+                panel.spawn(row()).with_children(|row| {
+                    row.spawn(
+                        TextBundle::from_section(format!("q{}", qubit), TextStyle::default())
+                            .with_style(Style {
+                                width: Val::Px(32.0),
+                                ..Default::default()
+                            }),
+                    );
+                    for step in 0..editor.steps.len() {
+                        editor_button(
+                            row,
+                            &editor.slot_label(step, qubit),
+                            editor.pending_control == Some((step, qubit)),
+                            SlotButton { step, qubit },
+                        );
+                    }
+                });
+            }
+        });
+}
+
+fn editor_button(parent: &mut ChildBuilder, label: &str, selected: bool, marker: impl Component) {
+    let color = if selected {
+        BUTTON_PRESSED_COLOR
+    } else {
+        BUTTON_COLOR
+    };
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    min_width: Val::Px(36.0),
+                    justify_content: JustifyContent::Center,
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                    ..Default::default()
+                },
+                background_color: color.into(),
+                ..Default::default()
+            },
+            marker,
+        ))
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 16.0,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+// Select palette tools, place gates into slots and resize the circuit; editing the circuit
+// re-runs the simulation
+fn handle_editor_buttons(
+    mut buttons: Query<EditorButton, EditorButtonChanged>,
+    mut editor: ResMut<CircuitEditor>,
+) {
+    for (interaction, mut color, tool, slot, action) in buttons.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            let selected = tool.is_some_and(|tool| tool.0 == editor.tool)
+                || slot.is_some_and(|slot| editor.pending_control == Some((slot.step, slot.qubit)));
+            *color = if selected {
+                BUTTON_PRESSED_COLOR.into()
+            } else {
+                button_color(interaction)
+            };
+            continue;
+        }
+        if let Some(tool) = tool {
+            editor.select(tool.0);
+        }
+        if let Some(slot) = slot {
+            editor.place(slot.step, slot.qubit);
+        }
+        if let Some(&action) = action {
+            editor.apply(action);
+        }
     }
+}
 
-    reduced_state
+// Add the lesson's instructions along the top edge, between the event list and the measure buttons
+fn spawn_lesson_panel(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::default().with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Percent(30.0),
+            max_width: Val::Percent(40.0),
+            ..Default::default()
+        }),
+        LessonPanel,
+    ));
 }
 
-fn calculate_angles(state: [Complex<f64>; 2]) -> (f64, f64) {
-    let alpha = state[0];
-    let beta = state[1];
+// Compare the circuit with the lesson's targets whenever it is edited, and show the steps done
+// so far, the current step and whether the circuit is on track
+fn check_lesson(
+    editor: Res<CircuitEditor>,
+    mut lesson: ResMut<Lesson>,
+    mut checked: Local<Option<u64>>,
+    mut panels: Query<&mut Text, With<LessonPanel>>,
+) {
+    if *checked == Some(editor.revision) {
+        return;
+    }
+    *checked = Some(editor.revision);
+
+    // An empty circuit matches the start of the lesson, before the first step
+    let circuit = editor.to_circuit();
+    let matched = (1..=lesson.steps.len())
+        .rev()
+        .find(|&step| {
+            circuit_equivalence(&circuit, &lesson.steps[step - 1].target).unwrap_or(false)
+        })
+        .or_else(|| {
+            circuit_equivalence(&circuit, &Circuit::new(lesson.num_qubits))
+                .unwrap_or(false)
+                .then_some(0)
+        });
+    lesson.matches = matched.is_some();
+    if let Some(step) = matched {
+        lesson.completed = step;
+    }
 
-    let theta = 2.0 * beta.norm().acos();
-    let phi = if alpha.norm() == 0.0 {
-        0.0
+    let section = |text: String, color: Color| {
+        TextSection::new(
+            text,
+            TextStyle {
+                font_size: 16.0,
+                color,
+                ..Default::default()
+            },
+        )
+    };
+    let status = if lesson.completed == lesson.steps.len() {
+        section("Lesson complete!".to_string(), GREEN_500.into())
+    } else if lesson.matches {
+        section("Your circuit is on track.".to_string(), Color::WHITE)
     } else {
-        alpha.arg() - beta.arg()
+        // Show how the circuit differs from the step's target, which it matches up to global
+        // phase once the step is done
+        let diff = circuit.diff(&lesson.steps[lesson.completed].target);
+        let hints: Vec<String> = diff
+            .changes
+            .iter()
+            .take(MAX_LESSON_HINTS)
+            .map(|change| format!("\n  {}", change))
+            .collect();
+        section(
+            format!(
+                "Your circuit does not match step {} yet. Compared with the target:{}",
+                lesson.completed + 1,
+                hints.concat()
+            ),
+            RED_500.into(),
+        )
     };
+    for mut text in panels.iter_mut() {
+        text.sections = [
+            section(format!("{}\n", lesson.title), YELLOW_500.into()),
+            section(format!("{}\n", lesson.intro), Color::WHITE),
+        ]
+        .into_iter()
+        .chain(lesson.steps.iter().enumerate().map(|(index, step)| {
+            let (marker, color) = if index < lesson.completed {
+                ("[x]", GREEN_500.into())
+            } else if index == lesson.completed {
+                ("[ ]", Color::WHITE)
+            } else {
+                ("[ ]", Color::srgb(0.5, 0.5, 0.5))
+            };
+            section(
+                format!("{} {}. {}\n", marker, index + 1, step.instruction),
+                color,
+            )
+        }))
+        .chain(std::iter::once(status.clone()))
+        .collect();
+    }
+}
 
-    (theta, phi)
+// Write the circuit, its settings and the camera to the workspace file when the save button or
+// Ctrl+S is pressed
+fn save_workspace(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), SaveButtonChanged>,
+    keys: Res<ButtonInput<KeyCode>>,
+    editor: Res<CircuitEditor>,
+    workspace: Res<WorkspaceFile>,
+    cameras: Query<&PanOrbitCamera>,
+) {
+    let mut save = keys.pressed(KeyCode::ControlLeft) && keys.just_pressed(KeyCode::KeyS);
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = button_color(interaction);
+        save |= *interaction == Interaction::Pressed;
+    }
+    if !save {
+        return;
+    }
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let text = workspace_text(&editor, CameraView::of(camera));
+    match fs::write(&workspace.path, text) {
+        Ok(()) => println!("Saved workspace to {}", workspace.path.display()),
+        Err(error) => eprintln!(
+            "Cannot save workspace {}: {}",
+            workspace.path.display(),
+            error
+        ),
+    }
 }
 
-fn bloch_sphere_coordinates(theta: f64, phi: f64) -> (f64, f64, f64) {
-    let x = theta.sin() * phi.cos();
-    let y = theta.sin() * phi.sin();
-    let z = theta.cos();
-    (x, y, z)
+// Add a bar chart along the bottom edge with, for each basis state, its probability in yellow
+// and its share of the sampled shots in blue
+fn spawn_histogram(commands: &mut Commands, num_qubits: usize) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(12.0),
+                    left: Val::Px(12.0),
+                    align_items: AlignItems::FlexEnd,
+                    column_gap: Val::Px(4.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SimulationView,
+        ))
+        .with_children(|histogram| {
+            for index in 0..1 << num_qubits {
+                histogram
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with_children(|column| {
+                        column
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    height: Val::Px(HISTOGRAM_HEIGHT),
+                                    align_items: AlignItems::FlexEnd,
+                                    column_gap: Val::Px(1.0),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            })
+                            .with_children(|bars| {
+                                bars.spawn((
+                                    histogram_bar(YELLOW_500.into()),
+                                    ProbabilityBar(index),
+                                ));
+                                bars.spawn((histogram_bar(BLUE_500.into()), CountsBar(index)));
+                            });
+                        column.spawn(TextBundle::from_section(
+                            format!("{:0width$b}", index, width = num_qubits),
+                            TextStyle {
+                                font_size: 12.0,
+                                ..Default::default()
+                            },
+                        ));
+                    });
+            }
+        });
 }
 
-fn gizmo_draw(mut gizmos: Gizmos, query: Query<&Position, With<QubitSphere>>) {
+fn histogram_bar(color: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Px(8.0),
+            height: Val::Px(0.0),
+            ..Default::default()
+        },
+        background_color: color.into(),
+        ..Default::default()
+    }
+}
+
+// Resize the probability bars to the animated state and the counts bars to the sampled shots
+fn update_histogram(
+    timeline: Res<Timeline>,
+    sampled: Res<SampledCounts>,
+    mut probability_bars: Query<(&ProbabilityBar, &mut Style), Without<CountsBar>>,
+    mut counts_bars: Query<(&CountsBar, &mut Style), Without<ProbabilityBar>>,
+) {
+    let target = &timeline.probabilities[timeline.step];
+    let start = timeline
+        .step
+        .checked_sub(1)
+        .map_or(target, |previous| &timeline.probabilities[previous]);
+    for (bar, mut style) in probability_bars.iter_mut() {
+        let probability = start[bar.0] + (target[bar.0] - start[bar.0]) * timeline.progress as f64;
+        style.height = Val::Px(probability as f32 * HISTOGRAM_HEIGHT);
+    }
+
+    let num_qubits = timeline.positions[0].len();
+    for (bar, mut style) in counts_bars.iter_mut() {
+        let frequency = sampled.0.as_ref().map_or(0.0, |counts| {
+            let bitstring = format!("{:0width$b}", bar.0, width = num_qubits);
+            counts.get(&bitstring) as f32 / counts.shots() as f32
+        });
+        style.height = Val::Px(frequency * HISTOGRAM_HEIGHT);
+    }
+}
+
+// Space plays or pauses, the left and right arrow keys step through the gates one at a time, the
+// up and down arrow keys change the playback speed and B toggles a breakpoint before the next gate
+fn control_playback(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut timeline: ResMut<Timeline>,
+    mut breakpoints: ResMut<Breakpoints>,
+) {
+    let last = timeline.events.len();
+    if keys.just_pressed(KeyCode::KeyB) {
+        let breakpoint = Breakpoint::Instruction(timeline.step);
+        match breakpoints
+            .0
+            .iter()
+            .position(|existing| *existing == breakpoint)
+        {
+            Some(index) => {
+                breakpoints.0.remove(index);
+                // A breakpoint the circuit rejected is missing from the debugger, so look it up
+                let added = timeline.debugger.breakpoints();
+                if let Some(index) = added.iter().position(|existing| *existing == breakpoint) {
+                    timeline.debugger.remove_breakpoint(index);
+                }
+            }
+            None => {
+                breakpoints.0.push(breakpoint.clone());
+                timeline
+                    .debugger
+                    .add_breakpoint(breakpoint)
+                    .expect("instruction breakpoints fit every circuit");
+            }
+        }
+    }
+    if keys.just_pressed(KeyCode::Space) {
+        if !timeline.playing && timeline.step == last && timeline.progress >= 1.0 {
+            // Replay from the start once the end has been reached
+            timeline.step = 0;
+        }
+        timeline.playing = !timeline.playing;
+    }
+    if keys.just_pressed(KeyCode::ArrowLeft) && timeline.step > 0 {
+        timeline.playing = false;
+        timeline.step -= 1;
+        timeline.progress = 1.0;
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) && timeline.step < last {
+        timeline.playing = false;
+        timeline.step += 1;
+        timeline.progress = 0.0;
+    }
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        timeline.speed = (timeline.speed * 2.0).min(MAX_SPEED);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        timeline.speed = (timeline.speed / 2.0).max(MIN_SPEED);
+    }
+}
+
+// Move the playback position forward, finishing a single step even while paused, and pause once
+// a step reaches a breakpoint
+fn advance_playback(time: Res<Time>, mut timeline: ResMut<Timeline>) {
+    if !timeline.playing && timeline.progress >= 1.0 {
+        return;
+    }
+    timeline.progress += timeline.speed * time.delta_seconds();
+    while timeline.progress >= 1.0 {
+        if timeline.playing && timeline.step < timeline.events.len() {
+            timeline.step += 1;
+            timeline.progress -= 1.0;
+            if timeline.debugger.breakpoint_at(timeline.step).is_some() {
+                timeline.playing = false;
+            }
+        } else {
+            timeline.progress = 1.0;
+            timeline.playing = false;
+            break;
+        }
+    }
+}
+
+// Place the qubit spheres along the current transition and extend their trails
+fn animate_spheres(
+    time: Res<Time>,
+    timeline: Res<Timeline>,
+    mut spheres: Query<(&QubitSphere, &mut Transform, &mut Position, &mut Trail)>,
+) {
+    let now = time.elapsed_seconds();
+    for (sphere, mut transform, mut position, mut trail) in spheres.iter_mut() {
+        let target = timeline.positions[timeline.step][sphere.0];
+        let current = match timeline.step.checked_sub(1) {
+            Some(previous) => {
+                let start = timeline.positions[previous][sphere.0];
+                bloch_arc(start, target, timeline.progress)
+            }
+            None => target,
+        };
+        transform.translation = current;
+        position.0 = current;
+
+        let moved = !trail
+            .0
+            .back()
+            .is_some_and(|(last, _)| last.distance(current) <= 1e-4);
+        if moved {
+            trail.0.push_back((current, now));
+        }
+        while trail
+            .0
+            .front()
+            .is_some_and(|(_, reached)| now - reached > TRAIL_SECONDS)
+        {
+            trail.0.pop_front();
+        }
+    }
+}
+
+// Interpolate between two Bloch vectors by rotating the direction along the great circle and
+// blending the length, so pure states stay on the sphere's surface
+fn bloch_arc(start: Vec3, end: Vec3, t: f32) -> Vec3 {
+    let length = start.length() + (end.length() - start.length()) * t;
+    match (start.try_normalize(), end.try_normalize()) {
+        (Some(from), Some(to)) => {
+            Quat::IDENTITY.slerp(Quat::from_rotation_arc(from, to), t) * from * length
+        }
+        _ => start.lerp(end, t),
+    }
+}
+
+// Show the playback state and highlight the current gate in the event list
+fn sync_event_list(
+    timeline: Res<Timeline>,
+    mut shown: Local<Option<(usize, bool, u32, usize)>>,
+    mut lists: Query<&mut Text, With<EventList>>,
+) {
+    let breakpoints = timeline.debugger.breakpoints();
+    let state = (
+        timeline.step,
+        timeline.playing,
+        timeline.speed.to_bits(),
+        breakpoints.len(),
+    );
+    if *shown == Some(state) && !timeline.is_added() {
+        return;
+    }
+    *shown = Some(state);
+    for mut text in lists.iter_mut() {
+        let mut header = format!(
+            "Step {}/{} {} at {} gates/s\n(space: play/pause, ←/→: step, ↑/↓: speed, b: breakpoint)\n",
+            timeline.step,
+            timeline.events.len(),
+            if timeline.playing {
+                "playing"
+            } else {
+                "paused"
+            },
+            timeline.speed
+        );
+        if let Some(index) = timeline.debugger.breakpoint_at(timeline.step) {
+            header.push_str(&format!("Breakpoint: {}\n", breakpoints[index]));
+        }
+        text.sections = std::iter::once(TextSection::new(header, TextStyle::default()))
+            .chain(
+                timeline
+                    .events
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, event)| {
+                        let current = index + 1 == timeline.step;
+                        let color = if current {
+                            Color::from(YELLOW_500)
+                        } else if index < timeline.step {
+                            Color::WHITE
+                        } else {
+                            Color::srgb(0.5, 0.5, 0.5)
+                        };
+                        let marker = if breakpoints.contains(&Breakpoint::Instruction(index)) {
+                            "● "
+                        } else {
+                            ""
+                        };
+                        let line = TextSection::new(
+                            format!("{}{}\n", marker, event),
+                            TextStyle {
+                                font_size: 16.0,
+                                color,
+                                ..Default::default()
+                            },
+                        );
+                        // Explain the step just taken under its gate
+                        let explanation = timeline.explanations.get(index).filter(|_| current).map(
+                            |explanation| {
+                                TextSection::new(
+                                    format!("  → {}\n", explanation.effect()),
+                                    TextStyle {
+                                        font_size: 14.0,
+                                        color: Color::from(YELLOW_500),
+                                        ..Default::default()
+                                    },
+                                )
+                            },
+                        );
+                        std::iter::once(line).chain(explanation)
+                    }),
+            )
+            .collect();
+    }
+}
+
+fn bloch_positions(qubit: &Qubit, num_qubits: usize) -> Vec<Vec3> {
+    (0..num_qubits)
+        .map(|qubit_index| {
+            let (x, y, z) = bloch_vector(qubit, qubit_index);
+            Vec3::new(x as f32, y as f32, z as f32)
+        })
+        .collect()
+}
+
+fn gizmo_draw(
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+    query: Query<&Position, With<QubitSphere>>,
+    trails: Query<&Trail>,
+) {
     gizmos
         .grid_3d(
             Vec3::ZERO,
@@ -217,4 +2908,79 @@ fn gizmo_draw(mut gizmos: Gizmos, query: Query<&Position, With<QubitSphere>>) {
         let end_position = position.0 * 1.5; // Overshoot a little
         gizmos.arrow(Vec3::ZERO, end_position, YELLOW_500);
     }
+
+    // Fade each trail out from the sphere towards its oldest point
+    let now = time.elapsed_seconds();
+    for trail in trails.iter() {
+        for ((from, _), (to, reached)) in trail.0.iter().zip(trail.0.iter().skip(1)) {
+            let alpha = (1.0 - (now - reached) / TRAIL_SECONDS).clamp(0.0, 1.0);
+            gizmos.line(*from, *to, YELLOW_500.with_alpha(alpha));
+        }
+    }
+}
+
+fn setup_progress_chart(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+    commands.spawn((
+        TextBundle::from_section(
+            "Waiting for the optimizer...",
+            TextStyle {
+                font_size: 18.0,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..Default::default()
+        }),
+        ProgressText,
+    ));
+}
+
+// Append the iterations the optimizer sent since the last frame and draw the energy against the
+// step, scaled to the full step range and the energy range seen so far
+fn update_progress_chart(
+    mut progress: ResMut<OptimizerProgress>,
+    mut text: Query<&mut Text, With<ProgressText>>,
+    mut gizmos: Gizmos,
+) {
+    let received: Vec<Iteration> = progress
+        .receiver
+        .lock()
+        .expect("the receiver is only used by this system")
+        .try_iter()
+        .collect();
+    progress.iterations.extend(received);
+
+    let origin = Vec2::new(-CHART_WIDTH / 2.0, -CHART_HEIGHT / 2.0);
+    gizmos.line_2d(origin, origin + Vec2::X * CHART_WIDTH, Color::WHITE);
+    gizmos.line_2d(origin, origin + Vec2::Y * CHART_HEIGHT, Color::WHITE);
+    let Some(last) = progress.iterations.last() else {
+        return;
+    };
+
+    let energies = progress.iterations.iter().map(|iteration| iteration.energy);
+    let low = energies.clone().fold(f64::INFINITY, f64::min);
+    let high = energies.fold(f64::NEG_INFINITY, f64::max);
+    let range = (high - low).max(1e-12);
+    let steps = progress.max_iterations.max(1) as f32;
+    gizmos.linestrip_2d(
+        progress.iterations.iter().map(|iteration| {
+            origin
+                + Vec2::new(
+                    iteration.step as f32 / steps * CHART_WIDTH,
+                    ((iteration.energy - low) / range) as f32 * CHART_HEIGHT,
+                )
+        }),
+        YELLOW_500,
+    );
+
+    for mut text in text.iter_mut() {
+        text.sections[0].value = format!(
+            "step {} of at most {}\nenergy {:.8}\ngradient {:.3e}\nenergy axis {:.6} to {:.6}",
+            last.step, progress.max_iterations, last.energy, last.gradient_norm, low, high
+        );
+    }
 }
@@ -0,0 +1,1591 @@
+//! This module defines quantum noise channels expressed as Kraus operators.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::linalg;
+use crate::tolerance::{self, Tolerances};
+use num_complex::Complex;
+use std::fmt;
+use std::mem;
+use std::ops::{Add, BitXor, Mul};
+
+/// A `KrausChannel` represents a completely positive map `ρ → Σ Kᵢ ρ Kᵢ†`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KrausChannel {
+    pub operators: Vec<Vec<Vec<Complex<f64>>>>, // Kraus operators Kᵢ
+}
+
+impl KrausChannel {
+    /// Creates a new `KrausChannel` from the given Kraus operators.
+    ///
+    /// # Arguments
+    ///
+    /// * `operators` - The Kraus operators, all of the same square dimension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::KrausChannel;
+    /// use num_complex::Complex;
+    ///
+    /// let p: f64 = 0.1;
+    /// let channel = KrausChannel::new(vec![
+    ///     vec![
+    ///         vec![Complex::new((1.0 - p).sqrt(), 0.0), Complex::new(0.0, 0.0)],
+    ///         vec![Complex::new(0.0, 0.0), Complex::new((1.0 - p).sqrt(), 0.0)],
+    ///     ],
+    ///     vec![
+    ///         vec![Complex::new(0.0, 0.0), Complex::new(p.sqrt(), 0.0)],
+    ///         vec![Complex::new(p.sqrt(), 0.0), Complex::new(0.0, 0.0)],
+    ///     ],
+    /// ]);
+    /// assert_eq!(channel.dimension(), 2);
+    /// ```
+    pub fn new(operators: Vec<Vec<Vec<Complex<f64>>>>) -> Self {
+        KrausChannel { operators }
+    }
+
+    /// Creates the unitary channel `ρ → U ρ U†` for the given gate.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate whose matrix is used as the single Kraus operator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let channel = KrausChannel::from_gate(&pauli_x());
+    /// assert_eq!(channel.operators.len(), 1);
+    /// ```
+    pub fn from_gate(gate: &Gate) -> Self {
+        KrausChannel {
+            operators: vec![gate.matrix.to_vec()],
+        }
+    }
+
+    /// Creates the identity channel on a `dimension`-dimensional system.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let idle = KrausChannel::identity(2);
+    /// assert!(idle.validate().is_ok());
+    /// ```
+    pub fn identity(dimension: usize) -> Self {
+        KrausChannel {
+            operators: vec![linalg::identity(dimension)],
+        }
+    }
+
+    /// Returns the dimension of the Hilbert space the channel acts on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::hadamard;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// assert_eq!(KrausChannel::from_gate(&hadamard(2)).dimension(), 4);
+    /// ```
+    pub fn dimension(&self) -> usize {
+        self.operators.first().map_or(0, |op| op.len())
+    }
+
+    /// Returns the process fidelity `Σ |Tr Kᵢ|² / d²` of the channel with the identity, `1` for
+    /// a channel that does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::{bit_flip, KrausChannel};
+    ///
+    /// assert!((KrausChannel::identity(2).process_fidelity() - 1.0).abs() < 1e-12);
+    /// assert!((bit_flip(0.1).process_fidelity() - 0.9).abs() < 1e-12);
+    /// ```
+    pub fn process_fidelity(&self) -> f64 {
+        let dimension = self.dimension() as f64;
+        self.operators
+            .iter()
+            .map(|op| {
+                (0..op.len())
+                    .map(|i| op[i][i])
+                    .sum::<Complex<f64>>()
+                    .norm_sqr()
+            })
+            .sum::<f64>()
+            / (dimension * dimension)
+    }
+
+    /// Applies the channel to a density matrix and returns the resulting density matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `rho` - The density matrix to transform.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::KrausChannel;
+    /// use num_complex::Complex;
+    ///
+    /// let rho = vec![
+    ///     vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+    ///     vec![Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)],
+    /// ]; // |0⟩⟨0|
+    /// let flipped = KrausChannel::from_gate(&pauli_x()).apply(&rho);
+    /// assert_eq!(flipped[1][1], Complex::new(1.0, 0.0));
+    /// ```
+    pub fn apply(&self, rho: &[Vec<Complex<f64>>]) -> Vec<Vec<Complex<f64>>> {
+        let dim = rho.len();
+        let mut result = linalg::zeros(dim, dim);
+        for op in &self.operators {
+            let term = linalg::matmul(&linalg::matmul(op, rho), &linalg::dagger(op));
+            for (row, term_row) in result.iter_mut().zip(&term) {
+                for (elem, t) in row.iter_mut().zip(term_row) {
+                    *elem += t;
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the channel that applies `self` first and then `next`.
+    ///
+    /// The Kraus operators of the result are all products `Bⱼ Aᵢ`. The same composition is
+    /// available as `next * self`, mirroring matrix multiplication.
+    ///
+    /// # Arguments
+    ///
+    /// * `next` - The channel applied after `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let x = KrausChannel::from_gate(&pauli_x());
+    /// let identity = x.then(&x);
+    /// assert!((identity.to_ptm()[3][3] - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn then(&self, next: &KrausChannel) -> KrausChannel {
+        let operators = next
+            .operators
+            .iter()
+            .flat_map(|b| self.operators.iter().map(move |a| linalg::matmul(b, a)))
+            .collect();
+        KrausChannel { operators }
+    }
+
+    /// Returns the tensor product channel `self ⊗ other`.
+    ///
+    /// Following the state-vector layout, `other` acts on the lower-index qubits and `self` on
+    /// the qubits above them. The same product is available as `self ^ other`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The channel acting on the lower-index subsystem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let x_on_qubit_1 = KrausChannel::from_gate(&pauli_x()).tensor(&KrausChannel::identity(2));
+    /// assert_eq!(x_on_qubit_1.dimension(), 4);
+    /// ```
+    pub fn tensor(&self, other: &KrausChannel) -> KrausChannel {
+        let operators = self
+            .operators
+            .iter()
+            .flat_map(|a| other.operators.iter().map(move |b| linalg::kron(a, b)))
+            .collect();
+        KrausChannel { operators }
+    }
+
+    /// Returns the probabilistic mixture `p·self + (1 - p)·other`.
+    ///
+    /// The same mixture can be written declaratively as `p * self + (1.0 - p) * other`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The channel applied with probability `1 - p`.
+    /// * `p` - The probability of applying `self`, in `[0, 1]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let bit_flip = KrausChannel::identity(2).mix(&KrausChannel::from_gate(&pauli_x()), 0.9);
+    /// assert!(bit_flip.validate().is_ok());
+    /// assert!((bit_flip.to_ptm()[3][3] - 0.8).abs() < 1e-12);
+    /// ```
+    pub fn mix(&self, other: &KrausChannel, p: f64) -> KrausChannel {
+        self.scaled(p).combined(&other.scaled(1.0 - p))
+    }
+
+    /// Returns the channel with every Kraus operator weighted by `√weight`.
+    fn scaled(&self, weight: f64) -> KrausChannel {
+        let factor = weight.max(0.0).sqrt();
+        let operators = self
+            .operators
+            .iter()
+            .map(|op| {
+                op.iter()
+                    .map(|row| row.iter().map(|elem| elem * factor).collect())
+                    .collect()
+            })
+            .collect();
+        KrausChannel { operators }
+    }
+
+    /// Returns the channel whose Kraus operators are those of `self` followed by `other`.
+    fn combined(&self, other: &KrausChannel) -> KrausChannel {
+        let operators = self
+            .operators
+            .iter()
+            .chain(&other.operators)
+            .cloned()
+            .collect();
+        KrausChannel { operators }
+    }
+
+    /// Checks that the channel is well formed and trace preserving (`Σ Kᵢ†Kᵢ = I`).
+    ///
+    /// Channels in Kraus form are completely positive by construction, so only the operator
+    /// dimensions and trace preservation need to be verified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::KrausChannel;
+    /// use num_complex::Complex;
+    ///
+    /// assert!(KrausChannel::from_gate(&pauli_x()).validate().is_ok());
+    ///
+    /// let lossy = KrausChannel::new(vec![vec![
+    ///     vec![Complex::new(0.5, 0.0), Complex::new(0.0, 0.0)],
+    ///     vec![Complex::new(0.0, 0.0), Complex::new(0.5, 0.0)],
+    /// ]]);
+    /// assert!(lossy.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), QuantumError> {
+        self.validate_with(&tolerance::current())
+    }
+
+    /// Checks the channel like [`KrausChannel::validate`], allowing `Σ Kᵢ†Kᵢ` to deviate from
+    /// the identity by the normalization threshold of `tolerances` rather than of the tolerances
+    /// in effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::KrausChannel;
+    /// use quantum_simulator::tolerance::Tolerances;
+    /// use num_complex::Complex;
+    ///
+    /// let drifted = KrausChannel::new(vec![vec![
+    ///     vec![Complex::new(1.0 + 1e-6, 0.0), Complex::new(0.0, 0.0)],
+    ///     vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+    /// ]]);
+    /// assert!(drifted.validate().is_err());
+    /// let relaxed = Tolerances::default().scaled(1e3).unwrap();
+    /// assert!(drifted.validate_with(&relaxed).is_ok());
+    /// ```
+    pub fn validate_with(&self, tolerances: &Tolerances) -> Result<(), QuantumError> {
+        let dim = self.dimension();
+        for op in &self.operators {
+            if op.len() != dim {
+                return Err(QuantumError::DimensionMismatch {
+                    expected: dim,
+                    found: op.len(),
+                });
+            }
+            if let Some(row) = op.iter().find(|row| row.len() != dim) {
+                return Err(QuantumError::DimensionMismatch {
+                    expected: dim,
+                    found: row.len(),
+                });
+            }
+        }
+
+        let mut sum = linalg::zeros(dim, dim);
+        for op in &self.operators {
+            let product = linalg::matmul(&linalg::dagger(op), op);
+            for (row, product_row) in sum.iter_mut().zip(&product) {
+                for (elem, p) in row.iter_mut().zip(product_row) {
+                    *elem += p;
+                }
+            }
+        }
+        let deviation = linalg::max_deviation(&sum, &linalg::identity(dim));
+        if deviation > tolerances.normalization {
+            return Err(QuantumError::NotTracePreserving { deviation });
+        }
+        Ok(())
+    }
+
+    /// Returns the (unnormalized) Choi matrix `J = Σᵢⱼ |i⟩⟨j| ⊗ Λ(|i⟩⟨j|)`.
+    ///
+    /// Row `i·d + a` and column `j·d + b` hold `⟨a|Λ(|i⟩⟨j|)|b⟩`, so `Tr J = d` for a
+    /// trace-preserving channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_z;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let choi = KrausChannel::from_gate(&pauli_z()).to_choi();
+    /// assert_eq!(choi.len(), 4);
+    /// assert!((choi[0][3].re + 1.0).abs() < 1e-12);
+    /// ```
+    pub fn to_choi(&self) -> Vec<Vec<Complex<f64>>> {
+        let dim = self.dimension();
+        let mut choi = linalg::zeros(dim * dim, dim * dim);
+        for op in &self.operators {
+            let vectorized: Vec<Complex<f64>> = (0..dim * dim)
+                .map(|index| op[index % dim][index / dim])
+                .collect();
+            for (row, v_r) in choi.iter_mut().zip(&vectorized) {
+                for (elem, v_c) in row.iter_mut().zip(&vectorized) {
+                    *elem += v_r * v_c.conj();
+                }
+            }
+        }
+        choi
+    }
+
+    /// Creates a channel from its Choi matrix, validating that it is CPTP.
+    ///
+    /// # Arguments
+    ///
+    /// * `choi` - A `d² × d²` Choi matrix using the layout of [`KrausChannel::to_choi`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(KrausChannel)` - The channel, with one Kraus operator per nonzero eigenvalue.
+    /// * `Err(QuantumError::DimensionMismatch)` - If the matrix is not `d² × d²`.
+    /// * `Err(QuantumError::Unsupported)` - If the matrix is not Hermitian within the
+    ///   normalization tolerance.
+    /// * `Err(QuantumError::NotCompletelyPositive)` or `Err(QuantumError::NotTracePreserving)` -
+    ///   If the channel is not CPTP.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::s;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let choi = KrausChannel::from_gate(&s()).to_choi();
+    /// let channel = KrausChannel::from_choi(&choi).unwrap();
+    /// assert_eq!(channel.operators.len(), 1);
+    /// ```
+    pub fn from_choi(choi: &[Vec<Complex<f64>>]) -> Result<Self, QuantumError> {
+        let dim = (choi.len() as f64).sqrt().round() as usize;
+        if dim * dim != choi.len() {
+            return Err(QuantumError::DimensionMismatch {
+                expected: dim * dim,
+                found: choi.len(),
+            });
+        }
+        check_hermitian(choi, "Choi")?;
+
+        let (eigenvalues, eigenvectors) = linalg::eigh(choi);
+        let cutoff = tolerance::current().normalization;
+        if let Some(&min_eigenvalue) = eigenvalues.first() {
+            if min_eigenvalue < -cutoff {
+                return Err(QuantumError::NotCompletelyPositive { min_eigenvalue });
+            }
+        }
+
+        let operators = eigenvalues
+            .iter()
+            .enumerate()
+            .filter(|(_, &lambda)| lambda > cutoff)
+            .map(|(k, &lambda)| {
+                let scale = lambda.sqrt();
+                (0..dim)
+                    .map(|a| {
+                        (0..dim)
+                            .map(|i| eigenvectors[i * dim + a][k] * scale)
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let channel = KrausChannel { operators };
+        channel.validate()?;
+        Ok(channel)
+    }
+
+    /// Returns the Pauli transfer matrix `R_ij = Tr(P_i Λ(P_j)) / d` of a qubit channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel dimension is not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let ptm = KrausChannel::from_gate(&pauli_x()).to_ptm();
+    /// // X preserves ⟨X⟩ and flips ⟨Y⟩ and ⟨Z⟩.
+    /// assert!((ptm[1][1] - 1.0).abs() < 1e-12);
+    /// assert!((ptm[3][3] + 1.0).abs() < 1e-12);
+    /// ```
+    pub fn to_ptm(&self) -> Vec<Vec<f64>> {
+        let dim = self.dimension();
+        let basis = linalg::pauli_basis(qubit_count(dim));
+        let images: Vec<Vec<Vec<Complex<f64>>>> = basis.iter().map(|p| self.apply(p)).collect();
+        basis
+            .iter()
+            .map(|p_i| {
+                images
+                    .iter()
+                    .map(|image| linalg::trace(&linalg::matmul(p_i, image)).re / dim as f64)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Creates a qubit channel from its Pauli transfer matrix, validating that it is CPTP.
+    ///
+    /// # Arguments
+    ///
+    /// * `ptm` - A `4ⁿ × 4ⁿ` Pauli transfer matrix in the basis order of [`KrausChannel::to_ptm`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// // Completely depolarizing channel: only the identity component survives.
+    /// let mut ptm = vec![vec![0.0; 4]; 4];
+    /// ptm[0][0] = 1.0;
+    /// let channel = KrausChannel::from_ptm(&ptm).unwrap();
+    /// assert_eq!(channel.operators.len(), 4);
+    /// ```
+    pub fn from_ptm(ptm: &[Vec<f64>]) -> Result<Self, QuantumError> {
+        let num_qubits = pauli_count_qubits(ptm.len())?;
+        let dim = 1 << num_qubits;
+        let basis = linalg::pauli_basis(num_qubits);
+
+        // Λ(P_j) = Σ_l R_lj P_l, so each matrix unit |i⟩⟨j| maps through its Pauli expansion.
+        let pauli_images: Vec<Vec<Vec<Complex<f64>>>> = (0..basis.len())
+            .map(|j| {
+                let mut image = linalg::zeros(dim, dim);
+                for (l, p_l) in basis.iter().enumerate() {
+                    let weight = ptm[l][j];
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    for (row, p_row) in image.iter_mut().zip(p_l) {
+                        for (elem, p) in row.iter_mut().zip(p_row) {
+                            *elem += p * weight;
+                        }
+                    }
+                }
+                image
+            })
+            .collect();
+
+        let mut choi = linalg::zeros(dim * dim, dim * dim);
+        for i in 0..dim {
+            for j in 0..dim {
+                for (p_k, image) in basis.iter().zip(&pauli_images) {
+                    let coefficient = p_k[j][i] / dim as f64;
+                    if coefficient == Complex::new(0.0, 0.0) {
+                        continue;
+                    }
+                    for a in 0..dim {
+                        for b in 0..dim {
+                            choi[i * dim + a][j * dim + b] += coefficient * image[a][b];
+                        }
+                    }
+                }
+            }
+        }
+
+        Self::from_choi(&choi)
+    }
+
+    /// Returns the process (chi) matrix in the Pauli basis, defined by `Λ(ρ) = Σ χ_mn P_m ρ P_n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel dimension is not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_y;
+    /// use quantum_simulator::noise::KrausChannel;
+    ///
+    /// let chi = KrausChannel::from_gate(&pauli_y()).to_chi();
+    /// assert!((chi[2][2].re - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn to_chi(&self) -> Vec<Vec<Complex<f64>>> {
+        let dim = self.dimension();
+        let basis = linalg::pauli_basis(qubit_count(dim));
+        let mut chi = linalg::zeros(basis.len(), basis.len());
+        for op in &self.operators {
+            let coefficients: Vec<Complex<f64>> = basis
+                .iter()
+                .map(|p| linalg::trace(&linalg::matmul(p, op)) / dim as f64)
+                .collect();
+            for (row, a_m) in chi.iter_mut().zip(&coefficients) {
+                for (elem, a_n) in row.iter_mut().zip(&coefficients) {
+                    *elem += a_m * a_n.conj();
+                }
+            }
+        }
+        chi
+    }
+
+    /// Creates a qubit channel from its chi matrix, validating that it is CPTP.
+    ///
+    /// # Arguments
+    ///
+    /// * `chi` - A `4ⁿ × 4ⁿ` chi matrix in the basis order of [`KrausChannel::to_chi`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(KrausChannel)` - The channel, with one Kraus operator per nonzero eigenvalue.
+    /// * `Err(QuantumError::DimensionMismatch)` - If the matrix is not `4ⁿ × 4ⁿ`.
+    /// * `Err(QuantumError::Unsupported)` - If the matrix is not Hermitian within the
+    ///   normalization tolerance.
+    /// * `Err(QuantumError::NotCompletelyPositive)` or `Err(QuantumError::NotTracePreserving)` -
+    ///   If the channel is not CPTP.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::KrausChannel;
+    /// use num_complex::Complex;
+    ///
+    /// // Bit flip with probability 0.25.
+    /// let mut chi = vec![vec![Complex::new(0.0, 0.0); 4]; 4];
+    /// chi[0][0] = Complex::new(0.75, 0.0);
+    /// chi[1][1] = Complex::new(0.25, 0.0);
+    /// let channel = KrausChannel::from_chi(&chi).unwrap();
+    /// assert_eq!(channel.operators.len(), 2);
+    /// ```
+    pub fn from_chi(chi: &[Vec<Complex<f64>>]) -> Result<Self, QuantumError> {
+        let num_qubits = pauli_count_qubits(chi.len())?;
+        let dim = 1 << num_qubits;
+        let basis = linalg::pauli_basis(num_qubits);
+        check_hermitian(chi, "chi")?;
+
+        let (eigenvalues, eigenvectors) = linalg::eigh(chi);
+        let cutoff = tolerance::current().normalization;
+        if let Some(&min_eigenvalue) = eigenvalues.first() {
+            if min_eigenvalue < -cutoff {
+                return Err(QuantumError::NotCompletelyPositive { min_eigenvalue });
+            }
+        }
+
+        let operators = eigenvalues
+            .iter()
+            .enumerate()
+            .filter(|(_, &lambda)| lambda > cutoff)
+            .map(|(k, &lambda)| {
+                let scale = lambda.sqrt();
+                let mut op = linalg::zeros(dim, dim);
+                for (m, p_m) in basis.iter().enumerate() {
+                    let weight = eigenvectors[m][k] * scale;
+                    for (row, p_row) in op.iter_mut().zip(p_m) {
+                        for (elem, p) in row.iter_mut().zip(p_row) {
+                            *elem += p * weight;
+                        }
+                    }
+                }
+                op
+            })
+            .collect();
+
+        let channel = KrausChannel { operators };
+        channel.validate()?;
+        Ok(channel)
+    }
+}
+
+/// Returns a three-level leakage/seepage channel on the space `{|0⟩, |1⟩, |2⟩}`.
+///
+/// Population in `|1⟩` leaks to the non-computational `|2⟩` with probability `leakage`, and
+/// population in `|2⟩` seeps back to `|1⟩` with probability `seepage`. The channel acts on 3×3
+/// density matrices through [`KrausChannel::apply`].
+///
+/// # Arguments
+///
+/// * `leakage` - The probability of `|1⟩ → |2⟩` per application.
+/// * `seepage` - The probability of `|2⟩ → |1⟩` per application.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::noise::leakage;
+/// use num_complex::Complex;
+///
+/// let channel = leakage(0.01, 0.1);
+/// assert_eq!(channel.dimension(), 3);
+/// assert!(channel.validate().is_ok());
+///
+/// let mut rho = vec![vec![Complex::new(0.0, 0.0); 3]; 3];
+/// rho[1][1] = Complex::new(1.0, 0.0); // |1⟩⟨1|
+/// assert!((channel.apply(&rho)[2][2].re - 0.01).abs() < 1e-12);
+/// ```
+pub fn leakage(leakage: f64, seepage: f64) -> KrausChannel {
+    let zero = Complex::new(0.0, 0.0);
+    let mut no_jump = linalg::zeros(3, 3);
+    no_jump[0][0] = Complex::new(1.0, 0.0);
+    no_jump[1][1] = Complex::new((1.0 - leakage).sqrt(), 0.0);
+    no_jump[2][2] = Complex::new((1.0 - seepage).sqrt(), 0.0);
+
+    let mut leak = vec![vec![zero; 3]; 3];
+    leak[2][1] = Complex::new(leakage.sqrt(), 0.0);
+    let mut seep = vec![vec![zero; 3]; 3];
+    seep[1][2] = Complex::new(seepage.sqrt(), 0.0);
+
+    KrausChannel::new(vec![no_jump, leak, seep])
+}
+
+/// Returns the single-qubit depolarizing channel `ρ → (1 - p)ρ + p I/2`.
+///
+/// # Arguments
+///
+/// * `p` - The probability of replacing the state with the maximally mixed state.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::noise::depolarizing;
+/// use num_complex::Complex;
+///
+/// let channel = depolarizing(0.2);
+/// assert!(channel.validate().is_ok());
+///
+/// let mut rho = vec![vec![Complex::new(0.0, 0.0); 2]; 2];
+/// rho[0][0] = Complex::new(1.0, 0.0);
+/// assert!((channel.apply(&rho)[1][1].re - 0.1).abs() < 1e-12);
+/// ```
+pub fn depolarizing(p: f64) -> KrausChannel {
+    let [i, x, y, z] = linalg::paulis();
+    let weights = [1.0 - 0.75 * p, 0.25 * p, 0.25 * p, 0.25 * p];
+    KrausChannel::new(
+        [i, x, y, z]
+            .into_iter()
+            .zip(weights)
+            .map(|(pauli, weight)| scaled_operator(&pauli, weight.sqrt()))
+            .collect(),
+    )
+}
+
+/// Returns the amplitude-damping channel, which relaxes `|1⟩` to `|0⟩` with probability `gamma`.
+///
+/// # Arguments
+///
+/// * `gamma` - The decay probability, `1 - e^{-t/T₁}` for an idle time `t`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::noise::amplitude_damping;
+/// use num_complex::Complex;
+///
+/// let mut rho = vec![vec![Complex::new(0.0, 0.0); 2]; 2];
+/// rho[1][1] = Complex::new(1.0, 0.0); // |1⟩⟨1|
+/// let rho = amplitude_damping(0.3).apply(&rho);
+/// assert!((rho[0][0].re - 0.3).abs() < 1e-12);
+/// ```
+pub fn amplitude_damping(gamma: f64) -> KrausChannel {
+    let zero = Complex::new(0.0, 0.0);
+    let one = Complex::new(1.0, 0.0);
+    KrausChannel::new(vec![
+        vec![
+            vec![one, zero],
+            vec![zero, Complex::new((1.0 - gamma).sqrt(), 0.0)],
+        ],
+        vec![
+            vec![zero, Complex::new(gamma.sqrt(), 0.0)],
+            vec![zero, zero],
+        ],
+    ])
+}
+
+/// Returns the phase-damping channel, which shrinks off-diagonal terms by `sqrt(1 - lambda)`.
+///
+/// # Arguments
+///
+/// * `lambda` - The dephasing probability, `1 - e^{-2t/T_φ}` for an idle time `t`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::noise::phase_damping;
+/// use num_complex::Complex;
+///
+/// let rho = vec![vec![Complex::new(0.5, 0.0); 2]; 2]; // |+⟩⟨+|
+/// let rho = phase_damping(0.36).apply(&rho);
+/// assert!((rho[0][1].re - 0.4).abs() < 1e-12);
+/// ```
+pub fn phase_damping(lambda: f64) -> KrausChannel {
+    let zero = Complex::new(0.0, 0.0);
+    let one = Complex::new(1.0, 0.0);
+    KrausChannel::new(vec![
+        vec![
+            vec![one, zero],
+            vec![zero, Complex::new((1.0 - lambda).sqrt(), 0.0)],
+        ],
+        vec![
+            vec![zero, zero],
+            vec![zero, Complex::new(lambda.sqrt(), 0.0)],
+        ],
+    ])
+}
+
+/// Returns the bit-flip channel, which applies `X` with probability `p`.
+///
+/// # Arguments
+///
+/// * `p` - The flip probability.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::noise::bit_flip;
+///
+/// assert_eq!(bit_flip(0.1).operators.len(), 2);
+/// ```
+pub fn bit_flip(p: f64) -> KrausChannel {
+    let [i, x, _, _] = linalg::paulis();
+    KrausChannel::new(vec![
+        scaled_operator(&i, (1.0 - p).sqrt()),
+        scaled_operator(&x, p.sqrt()),
+    ])
+}
+
+/// Returns the phase-flip channel, which applies `Z` with probability `p`.
+///
+/// # Arguments
+///
+/// * `p` - The flip probability.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::noise::phase_flip;
+///
+/// assert!(phase_flip(0.1).validate().is_ok());
+/// ```
+pub fn phase_flip(p: f64) -> KrausChannel {
+    let [i, _, _, z] = linalg::paulis();
+    KrausChannel::new(vec![
+        scaled_operator(&i, (1.0 - p).sqrt()),
+        scaled_operator(&z, p.sqrt()),
+    ])
+}
+
+/// Returns the thermal relaxation channel of a qubit left idle for `time`: amplitude damping
+/// towards `|0⟩` with time constant `T₁`, followed by pure dephasing that brings the decay of the
+/// off-diagonal terms to `e^{-t/T₂}`.
+///
+/// # Arguments
+///
+/// * `t1` - The energy relaxation time `T₁`.
+/// * `t2` - The dephasing time `T₂`, at most `2·T₁`.
+/// * `time` - The idle time, in the same unit as `t1` and `t2`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::noise::thermal_relaxation;
+/// use num_complex::Complex;
+///
+/// let rho = vec![vec![Complex::new(0.5, 0.0); 2]; 2]; // |+⟩⟨+|
+/// let rho = thermal_relaxation(100.0, 50.0, 50.0).apply(&rho);
+/// assert!((rho[0][1].re - 0.5 * (-1.0f64).exp()).abs() < 1e-12);
+/// assert!((rho[1][1].re - 0.5 * (-0.5f64).exp()).abs() < 1e-12);
+/// ```
+pub fn thermal_relaxation(t1: f64, t2: f64, time: f64) -> KrausChannel {
+    let gamma = 1.0 - (-time / t1).exp();
+    // 1/T_φ = 1/T₂ - 1/(2·T₁), clamped at zero for T₂ = 2·T₁
+    let dephasing_rate = (1.0 / t2 - 0.5 / t1).max(0.0);
+    let lambda = 1.0 - (-2.0 * time * dephasing_rate).exp();
+    phase_damping(lambda) * amplitude_damping(gamma)
+}
+
+/// `LeakagePreset` lists ready-made leakage/seepage rates for superconducting transmons.
+///
+/// The rates are representative per-operation values from published transmon characterizations:
+/// DRAG-corrected single-qubit gates leak around `1e-6` per gate, flux-pulsed CZ gates around
+/// `1e-3`, and seepage is set by the relaxation of `|2⟩` during the operation time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LeakagePreset {
+    /// A 25 ns DRAG single-qubit gate.
+    TransmonSingleQubitGate,
+    /// A 40 ns flux-pulsed controlled-Z gate, per participating transmon.
+    TransmonCzGate,
+    /// A 1 µs readout or idle window, where leakage is thermal and seepage dominates.
+    TransmonIdle,
+}
+
+impl LeakagePreset {
+    /// Returns the `(leakage, seepage)` probabilities of the preset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::LeakagePreset;
+    ///
+    /// let (leak, seep) = LeakagePreset::TransmonCzGate.rates();
+    /// assert!(leak > 0.0 && seep > 0.0);
+    /// ```
+    pub fn rates(&self) -> (f64, f64) {
+        match self {
+            LeakagePreset::TransmonSingleQubitGate => (1e-6, 5e-4),
+            LeakagePreset::TransmonCzGate => (1e-3, 8e-4),
+            LeakagePreset::TransmonIdle => (1e-5, 2e-2),
+        }
+    }
+
+    /// Returns the three-level leakage channel configured with the preset's rates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::LeakagePreset;
+    ///
+    /// let channel = LeakagePreset::TransmonSingleQubitGate.channel();
+    /// assert_eq!(channel.dimension(), 3);
+    /// ```
+    pub fn channel(&self) -> KrausChannel {
+        let (leak, seep) = self.rates();
+        leakage(leak, seep)
+    }
+}
+
+/// A `CoherentError` describes a systematic miscalibration applied identically to every gate.
+///
+/// Unlike stochastic channels, a coherent error keeps the state pure: each ideal gate `U` is
+/// replaced by a perturbed unitary. Single-qubit gates `e^{iα} R_n(θ)` become
+/// `e^{iα} R_n'(θ(1 + ε))`, where `n'` is the rotation axis tilted by `axis_tilt` radians toward
+/// the z axis (toward x for rotations about z). Multi-qubit gates are overrotated as `U^(1 + ε)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoherentError {
+    pub overrotation: f64, // Relative overrotation ε
+    pub axis_tilt: f64,    // Axis tilt in radians
+}
+
+impl CoherentError {
+    /// Creates a new `CoherentError`.
+    ///
+    /// # Arguments
+    ///
+    /// * `overrotation` - The relative overrotation `ε`; `0.01` rotates 1% too far.
+    /// * `axis_tilt` - The tilt of single-qubit rotation axes, in radians.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::CoherentError;
+    ///
+    /// let error = CoherentError::new(0.02, 0.0);
+    /// assert_eq!(error.overrotation, 0.02);
+    /// ```
+    pub fn new(overrotation: f64, axis_tilt: f64) -> Self {
+        CoherentError {
+            overrotation,
+            axis_tilt,
+        }
+    }
+
+    /// Returns the perturbed version of an ideal gate.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The ideal gate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::{pauli_x, phase};
+    /// use quantum_simulator::noise::CoherentError;
+    ///
+    /// // A 10% overrotated π/2 phase gate is a 0.55π phase gate up to global phase.
+    /// let perturbed = CoherentError::new(0.1, 0.0).perturb(&phase(std::f64::consts::PI / 2.0));
+    /// let ratio = perturbed.matrix[1][1] / perturbed.matrix[0][0];
+    /// assert!((ratio.arg() - 0.55 * std::f64::consts::PI).abs() < 1e-9);
+    /// ```
+    pub fn perturb(&self, gate: &Gate) -> Gate {
+        if gate.matrix.len() == 2 {
+            self.perturb_single_qubit(&gate.matrix)
+        } else {
+            Gate::new(linalg::unitary_power(&gate.matrix, 1.0 + self.overrotation))
+        }
+    }
+
+    fn perturb_single_qubit(&self, u: &[Vec<Complex<f64>>]) -> Gate {
+        // Write U = e^{iα} (cos(θ/2) I - i sin(θ/2) n·σ).
+        let det = u[0][0] * u[1][1] - u[0][1] * u[1][0];
+        let global = Complex::from_polar(1.0, det.arg() / 2.0);
+        let a = u[0][0] / global;
+        let b = u[0][1] / global;
+        let sin_half = (a.im * a.im + b.norm_sqr()).sqrt();
+        let theta = 2.0 * sin_half.atan2(a.re);
+        let truncation = tolerance::current().truncation;
+        let mut axis = if sin_half > truncation {
+            [-b.im / sin_half, -b.re / sin_half, -a.im / sin_half]
+        } else {
+            [0.0, 0.0, 1.0]
+        };
+
+        if self.axis_tilt != 0.0 {
+            // Unit vector orthogonal to the axis pointing toward z (or x if the axis is z).
+            let reference = if axis[2].abs() > 1.0 - truncation {
+                [1.0, 0.0, 0.0]
+            } else {
+                [0.0, 0.0, 1.0]
+            };
+            let dot: f64 = axis.iter().zip(&reference).map(|(x, y)| x * y).sum();
+            let mut towards = [0.0; 3];
+            for k in 0..3 {
+                towards[k] = reference[k] - dot * axis[k];
+            }
+            let norm = towards.iter().map(|x| x * x).sum::<f64>().sqrt();
+            for k in 0..3 {
+                axis[k] = axis[k] * self.axis_tilt.cos() + towards[k] / norm * self.axis_tilt.sin();
+            }
+        }
+
+        let half = theta * (1.0 + self.overrotation) / 2.0;
+        let (c, s) = (half.cos(), half.sin());
+        let [nx, ny, nz] = axis;
+        Gate::new(vec![
+            vec![
+                global * Complex::new(c, -s * nz),
+                global * Complex::new(-s * ny, -s * nx),
+            ],
+            vec![
+                global * Complex::new(s * ny, -s * nx),
+                global * Complex::new(c, s * nz),
+            ],
+        ])
+    }
+}
+
+/// `Decoherence` describes how qubits relax while they wait for their next instruction.
+///
+/// Every operation takes a duration from a table keyed by operation kind, and instructions start
+/// as soon as their qubits and classical bit are free. A qubit that waits between two
+/// instructions, or after its last one until the whole circuit has finished, undergoes
+/// [`thermal_relaxation`] for the waiting time with its own `T₁` and `T₂`. Deeper and less
+/// parallel schedules therefore lose more fidelity. Barriers take no time but make their qubits
+/// wait for each other; snapshots and assertions take no time and are not waited for.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decoherence {
+    t1: Vec<f64>,
+    t2: Vec<f64>,
+    gate_duration: f64,
+    durations: Vec<(Operation, f64)>,
+}
+
+impl Decoherence {
+    /// Creates a `Decoherence` from per-qubit coherence times, with every operation taking
+    /// `gate_duration` until [`Decoherence::set_duration`] changes it.
+    ///
+    /// Qubits beyond the listed ones do not decohere.
+    ///
+    /// # Arguments
+    ///
+    /// * `t1` - The energy relaxation time `T₁` of each qubit.
+    /// * `t2` - The dephasing time `T₂` of each qubit, at most twice its `T₁`.
+    /// * `gate_duration` - The default duration of an operation, in the unit of the times.
+    ///
+    /// # Returns
+    ///
+    /// * The decoherence, or an error if the lists differ in length, a time is not positive, a
+    ///   `T₂` exceeds `2·T₁` or the gate duration is negative or NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    /// use quantum_simulator::noise::Decoherence;
+    ///
+    /// // Times in nanoseconds: 35 ns single-qubit gates, 300 ns CNOTs, 1 µs readout
+    /// let mut decoherence = Decoherence::new(&[80_000.0, 60_000.0], &[50_000.0, 70_000.0], 35.0)
+    ///     .unwrap();
+    /// decoherence
+    ///     .set_duration(Operation::Cnot, 300.0)
+    ///     .unwrap()
+    ///     .set_duration(Operation::Measure(0), 1000.0)
+    ///     .unwrap();
+    /// assert_eq!(decoherence.duration(&Operation::Measure(3)), 1000.0);
+    /// assert!(decoherence.set_duration(Operation::H, -1.0).is_err());
+    /// assert!(Decoherence::new(&[10.0], &[25.0], 1.0).is_err());
+    /// ```
+    pub fn new(t1: &[f64], t2: &[f64], gate_duration: f64) -> Result<Self, QuantumError> {
+        if t1.len() != t2.len() {
+            return Err(QuantumError::DimensionMismatch {
+                expected: t1.len(),
+                found: t2.len(),
+            });
+        }
+        for (qubit, (&t1, &t2)) in t1.iter().zip(t2).enumerate() {
+            if !(t1 > 0.0 && t2 > 0.0 && t2 <= 2.0 * t1) {
+                return Err(QuantumError::Unsupported {
+                    feature: format!(
+                        "coherence times T1 = {} and T2 = {} on qubit {}; both must be positive \
+                         with T2 at most 2·T1",
+                        t1, t2, qubit
+                    ),
+                });
+            }
+        }
+        if gate_duration.is_nan() || gate_duration < 0.0 {
+            return Err(QuantumError::Unsupported {
+                feature: format!("gate duration {}", gate_duration),
+            });
+        }
+        Ok(Decoherence {
+            t1: t1.to_vec(),
+            t2: t2.to_vec(),
+            gate_duration,
+            durations: vec![],
+        })
+    }
+
+    /// Sets the duration of every operation of the same kind as `operation`.
+    ///
+    /// Operations match by kind only, so `Operation::Measure(0)` sets the duration of every
+    /// measurement. Classically conditioned operations take the duration of the operation they
+    /// guard.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The kind of operation.
+    /// * `duration` - Its duration, in the unit of the coherence times.
+    ///
+    /// # Returns
+    ///
+    /// * The decoherence for chaining, or `QuantumError::Unsupported` if the duration is negative
+    ///   or NaN, in which case it is left unchanged.
+    pub fn set_duration(
+        &mut self,
+        operation: Operation,
+        duration: f64,
+    ) -> Result<&mut Self, QuantumError> {
+        if duration.is_nan() || duration < 0.0 {
+            return Err(QuantumError::Unsupported {
+                feature: format!("gate duration {}", duration),
+            });
+        }
+        self.durations
+            .retain(|(existing, _)| mem::discriminant(existing) != mem::discriminant(&operation));
+        self.durations.push((operation, duration));
+        Ok(self)
+    }
+
+    /// Returns how long `operation` takes.
+    pub fn duration(&self, operation: &Operation) -> f64 {
+        match operation {
+            Operation::Conditional { operation, .. } => self.duration(operation),
+            Operation::Barrier(_) | Operation::Snapshot(_) | Operation::Assert(_) => 0.0,
+            operation => self
+                .durations
+                .iter()
+                .find(|(existing, _)| mem::discriminant(existing) == mem::discriminant(operation))
+                .map_or(self.gate_duration, |(_, duration)| *duration),
+        }
+    }
+
+    /// Returns the thermal relaxation channel of `qubit` idling for `time`, or `None` if the
+    /// qubit does not decohere or does not wait.
+    pub fn channel(&self, qubit: usize, time: f64) -> Option<KrausChannel> {
+        let (&t1, &t2) = self.t1.get(qubit).zip(self.t2.get(qubit))?;
+        (time > 0.0).then(|| thermal_relaxation(t1, t2, time))
+    }
+
+    /// Returns how long `circuit` takes when every instruction starts as early as possible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    /// use quantum_simulator::noise::Decoherence;
+    ///
+    /// let mut decoherence = Decoherence::new(&[100.0; 2], &[100.0; 2], 1.0).unwrap();
+    /// decoherence.set_duration(Operation::Cnot, 5.0).unwrap();
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).h(1).cnot(0, 1).x(0);
+    /// assert_eq!(decoherence.circuit_duration(&circuit), 7.0);
+    /// ```
+    pub fn circuit_duration(&self, circuit: &Circuit) -> f64 {
+        self.idle_times(circuit).1
+    }
+
+    /// Returns, for every instruction, how long each of its qubits waited for it, followed by
+    /// how long each qubit waits after its last instruction, and the duration of the circuit.
+    fn idle_times(&self, circuit: &Circuit) -> (Vec<Vec<(usize, f64)>>, f64) {
+        let mut qubit_free = vec![0.0f64; circuit.num_qubits()];
+        let mut clbit_free = vec![0.0f64; circuit.num_clbits()];
+        let mut idle = vec![];
+        for instruction in circuit.instructions() {
+            let clbit = match &instruction.operation {
+                Operation::Snapshot(_) | Operation::Assert(_) => {
+                    idle.push(vec![]);
+                    continue;
+                }
+                Operation::Measure(clbit) | Operation::Conditional { clbit, .. } => Some(*clbit),
+                _ => None,
+            };
+            let start = instruction
+                .qubits
+                .iter()
+                .map(|&qubit| qubit_free[qubit])
+                .chain(clbit.map(|clbit| clbit_free[clbit]))
+                .fold(0.0, f64::max);
+            let end = start + self.duration(&instruction.operation);
+            idle.push(
+                instruction
+                    .qubits
+                    .iter()
+                    .map(|&qubit| (qubit, start - qubit_free[qubit]))
+                    .collect(),
+            );
+            for &qubit in &instruction.qubits {
+                qubit_free[qubit] = end;
+            }
+            if let Some(clbit) = clbit {
+                clbit_free[clbit] = end;
+            }
+        }
+        let total = qubit_free
+            .iter()
+            .chain(&clbit_free)
+            .copied()
+            .fold(0.0, f64::max);
+        idle.push(
+            qubit_free
+                .iter()
+                .enumerate()
+                .map(|(qubit, &free)| (qubit, total - free))
+                .collect(),
+        );
+        (idle, total)
+    }
+}
+
+/// A `NoiseModel` describes how an ideal circuit is corrupted when it is executed.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoiseModel {
+    coherent_error: Option<CoherentError>,
+    errors: Vec<ErrorRule>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    decoherence: Option<Decoherence>,
+}
+
+/// An `ErrorRule` attaches a channel to the instructions it matches.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ErrorRule {
+    /// The kind of operation matched, or `None` for every operation.
+    operation: Option<Operation>,
+    /// The exact qubits matched, or `None` for any qubits.
+    qubits: Option<Vec<usize>>,
+    channel: KrausChannel,
+}
+
+impl ErrorRule {
+    fn matches(&self, instruction: &Instruction) -> bool {
+        let operation_matches = self.operation.as_ref().is_none_or(|operation| {
+            mem::discriminant(operation) == mem::discriminant(&instruction.operation)
+        });
+        let qubits_matches = self
+            .qubits
+            .as_ref()
+            .is_none_or(|qubits| *qubits == instruction.qubits);
+        operation_matches && qubits_matches
+    }
+}
+
+impl fmt::Display for NoiseModel {
+    /// Describes the coherent error and every error rule on its own line, e.g.
+    /// `after cx: 2-qubit channel, 4 Kraus operators, process infidelity 0.02`, or `noiseless`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.coherent_error.is_none() && self.errors.is_empty() && self.decoherence.is_none() {
+            return write!(f, "noiseless");
+        }
+        let mut lines = vec![];
+        if let Some(error) = &self.coherent_error {
+            lines.push(format!(
+                "coherent error: overrotation {}, axis tilt {} rad",
+                error.overrotation, error.axis_tilt
+            ));
+        }
+        for rule in &self.errors {
+            let target = match (&rule.operation, &rule.qubits) {
+                (None, _) => "every instruction".to_string(),
+                (Some(operation), None) => operation.name(),
+                (Some(operation), Some(qubits)) => format!("{} on {:?}", operation.name(), qubits),
+            };
+            lines.push(format!(
+                "after {}: {}-qubit channel, {} Kraus operators, process infidelity {:.6}",
+                target,
+                rule.channel.dimension().trailing_zeros(),
+                rule.channel.operators.len(),
+                1.0 - rule.channel.process_fidelity()
+            ));
+        }
+        if let Some(decoherence) = &self.decoherence {
+            lines.push(format!(
+                "idle decoherence: T1 {:?}, T2 {:?}, default gate duration {}",
+                decoherence.t1, decoherence.t2, decoherence.gate_duration
+            ));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl NoiseModel {
+    /// Creates a noise model without any errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::NoiseModel;
+    ///
+    /// let model = NoiseModel::new();
+    /// assert!(model.coherent_error().is_none());
+    /// ```
+    pub fn new() -> Self {
+        NoiseModel {
+            coherent_error: None,
+            errors: vec![],
+            decoherence: None,
+        }
+    }
+
+    /// Replaces every ideal gate with a systematically perturbed unitary.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The coherent error applied to each gate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::{CoherentError, NoiseModel};
+    ///
+    /// let mut model = NoiseModel::new();
+    /// model.set_coherent_error(CoherentError::new(0.01, 0.005));
+    /// assert!(model.coherent_error().is_some());
+    /// ```
+    pub fn set_coherent_error(&mut self, error: CoherentError) {
+        self.coherent_error = Some(error);
+    }
+
+    /// Returns the coherent error applied to each gate, if any.
+    pub fn coherent_error(&self) -> Option<&CoherentError> {
+        self.coherent_error.as_ref()
+    }
+
+    /// Makes qubits decohere while they wait for their next instruction; see [`Decoherence`].
+    ///
+    /// # Arguments
+    ///
+    /// * `decoherence` - The coherence times and gate durations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::noise::{Decoherence, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    ///
+    /// let mut model = NoiseModel::new();
+    /// model.set_decoherence(Decoherence::new(&[10.0; 2], &[10.0; 2], 1.0).unwrap());
+    ///
+    /// // Qubit 1 waits in |1⟩ through the last four of qubit 0's five gates
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.x(1).x(0).x(0).x(0).x(0).x(0);
+    /// let rho = Simulator::run_density_with_noise(&circuit, &DensityMatrix::new(2), &model);
+    /// let excited = rho.probabilities()[0b11];
+    /// assert!((excited - (-0.4f64).exp()).abs() < 1e-12);
+    /// ```
+    pub fn set_decoherence(&mut self, decoherence: Decoherence) -> &mut Self {
+        self.decoherence = Some(decoherence);
+        self
+    }
+
+    /// Returns the idle decoherence, if any.
+    pub fn decoherence(&self) -> Option<&Decoherence> {
+        self.decoherence.as_ref()
+    }
+
+    /// Returns the idle decoherence channels of a run of `circuit`, each with the qubit it acts
+    /// on: one list per instruction, to apply before it, and a last list to apply after the
+    /// final instruction. All lists are empty without [`NoiseModel::set_decoherence`].
+    pub fn idle_errors(&self, circuit: &Circuit) -> Vec<Vec<(KrausChannel, Vec<usize>)>> {
+        let Some(decoherence) = &self.decoherence else {
+            return vec![vec![]; circuit.instructions().len() + 1];
+        };
+        decoherence
+            .idle_times(circuit)
+            .0
+            .into_iter()
+            .map(|waits| {
+                waits
+                    .into_iter()
+                    .filter_map(|(qubit, time)| {
+                        decoherence
+                            .channel(qubit, time)
+                            .map(|channel| (channel, vec![qubit]))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Applies `channel` after every instruction.
+    ///
+    /// A single-qubit channel acts on each qubit of the instruction independently; a larger
+    /// channel acts on the instruction's qubits jointly and must match their number.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The error channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::noise::{depolarizing, NoiseModel};
+    ///
+    /// let mut model = NoiseModel::new();
+    /// model.add_error(depolarizing(0.01));
+    /// ```
+    pub fn add_error(&mut self, channel: KrausChannel) -> &mut Self {
+        self.push_error(None, None, channel)
+    }
+
+    /// Applies `channel` after every instruction of the same kind as `operation`.
+    ///
+    /// Operations match by kind only, so `Operation::Rx(0.0)` matches X rotations of any angle.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The kind of operation to attach the error to.
+    /// * `channel` - The error channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    /// use quantum_simulator::noise::{depolarizing, NoiseModel};
+    ///
+    /// let mut model = NoiseModel::new();
+    /// model.add_gate_error(Operation::Cnot, depolarizing(0.02) ^ depolarizing(0.02));
+    /// ```
+    pub fn add_gate_error(&mut self, operation: Operation, channel: KrausChannel) -> &mut Self {
+        self.push_error(Some(operation), None, channel)
+    }
+
+    /// Applies `channel` after instructions of the same kind as `operation` on exactly `qubits`.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The kind of operation to attach the error to.
+    /// * `qubits` - The qubits the instruction must act on, in order.
+    /// * `channel` - The error channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    /// use quantum_simulator::noise::{amplitude_damping, NoiseModel};
+    ///
+    /// let mut model = NoiseModel::new();
+    /// model.add_qubit_error(Operation::X, &[3], amplitude_damping(0.05));
+    /// ```
+    pub fn add_qubit_error(
+        &mut self,
+        operation: Operation,
+        qubits: &[usize],
+        channel: KrausChannel,
+    ) -> &mut Self {
+        self.push_error(Some(operation), Some(qubits.to_vec()), channel)
+    }
+
+    /// Returns the channels to apply after `instruction`, each with the qubits it acts on.
+    ///
+    /// Snapshots do nothing to the state and draw no errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The instruction that was just applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Instruction, Operation};
+    /// use quantum_simulator::noise::{bit_flip, NoiseModel};
+    ///
+    /// let mut model = NoiseModel::new();
+    /// model.add_gate_error(Operation::Cz, bit_flip(0.1));
+    ///
+    /// let errors = model.errors_for(&Instruction::new(Operation::Cz, vec![0, 2]));
+    /// let qubits: Vec<Vec<usize>> = errors.into_iter().map(|(_, qubits)| qubits).collect();
+    /// assert_eq!(qubits, vec![vec![0], vec![2]]);
+    /// ```
+    pub fn errors_for(&self, instruction: &Instruction) -> Vec<(&KrausChannel, Vec<usize>)> {
+        let mut errors = vec![];
+        if let Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_) =
+            instruction.operation
+        {
+            return errors;
+        }
+        for rule in self.errors.iter().filter(|rule| rule.matches(instruction)) {
+            if rule.channel.dimension() == 2 {
+                errors.extend(instruction.qubits.iter().map(|&q| (&rule.channel, vec![q])));
+            } else {
+                errors.push((&rule.channel, instruction.qubits.clone()));
+            }
+        }
+        errors
+    }
+
+    fn push_error(
+        &mut self,
+        operation: Option<Operation>,
+        qubits: Option<Vec<usize>>,
+        channel: KrausChannel,
+    ) -> &mut Self {
+        self.errors.push(ErrorRule {
+            operation,
+            qubits,
+            channel,
+        });
+        self
+    }
+
+    /// Returns the gate that is actually executed in place of the ideal `gate`.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The ideal gate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::NoiseModel;
+    ///
+    /// let ideal = pauli_x();
+    /// assert_eq!(NoiseModel::new().noisy_gate(&ideal).matrix, ideal.matrix);
+    /// ```
+    pub fn noisy_gate(&self, gate: &Gate) -> Gate {
+        match &self.coherent_error {
+            Some(error) => error.perturb(gate),
+            None => gate.clone(),
+        }
+    }
+}
+
+/// `a * b` composes channels like matrices: `b` is applied first, then `a`.
+impl Mul for KrausChannel {
+    type Output = KrausChannel;
+
+    fn mul(self, rhs: KrausChannel) -> KrausChannel {
+        rhs.then(&self)
+    }
+}
+
+/// `p * channel` weights a channel for use in a probabilistic mixture.
+impl Mul<KrausChannel> for f64 {
+    type Output = KrausChannel;
+
+    fn mul(self, rhs: KrausChannel) -> KrausChannel {
+        rhs.scaled(self)
+    }
+}
+
+/// `a + b` sums weighted channels, so `p * a + (1.0 - p) * b` builds a mixture.
+impl Add for KrausChannel {
+    type Output = KrausChannel;
+
+    fn add(self, rhs: KrausChannel) -> KrausChannel {
+        self.combined(&rhs)
+    }
+}
+
+/// `a ^ b` is the tensor product `a ⊗ b`, with `b` on the lower-index qubits.
+impl BitXor for KrausChannel {
+    type Output = KrausChannel;
+
+    fn bitxor(self, rhs: KrausChannel) -> KrausChannel {
+        self.tensor(&rhs)
+    }
+}
+
+/// Returns `factor * operator`.
+fn scaled_operator(operator: &[Vec<Complex<f64>>], factor: f64) -> Vec<Vec<Complex<f64>>> {
+    operator
+        .iter()
+        .map(|row| row.iter().map(|elem| elem * factor).collect())
+        .collect()
+}
+
+/// Returns the number of qubits of a `dim`-dimensional register.
+fn qubit_count(dim: usize) -> usize {
+    assert!(
+        dim.is_power_of_two(),
+        "Pauli representations require a qubit channel"
+    );
+    dim.trailing_zeros() as usize
+}
+
+/// Rejects a `matrix` that differs from its conjugate transpose by more than the normalization
+/// tolerance, since [`linalg::eigh`] assumes Hermitian input.
+fn check_hermitian(matrix: &[Vec<Complex<f64>>], name: &str) -> Result<(), QuantumError> {
+    let deviation = linalg::max_deviation(matrix, &linalg::dagger(matrix));
+    if deviation > tolerance::current().normalization {
+        return Err(QuantumError::Unsupported {
+            feature: format!("a non-Hermitian {} matrix (deviation {})", name, deviation),
+        });
+    }
+    Ok(())
+}
+
+/// Returns `n` for a Pauli-basis representation with `4ⁿ` rows.
+fn pauli_count_qubits(len: usize) -> Result<usize, QuantumError> {
+    let num_qubits = (len.trailing_zeros() / 2) as usize;
+    if len == 0 || 4usize.pow(num_qubits as u32) != len {
+        return Err(QuantumError::DimensionMismatch {
+            expected: 4usize.pow(num_qubits as u32),
+            found: len,
+        });
+    }
+    Ok(num_qubits)
+}
@@ -0,0 +1,361 @@
+//! This module simplifies circuits with a sequence of peephole optimization passes.
+//!
+//! A [`PassManager`] runs [`Pass`]es over a circuit until none of them changes it any more. The
+//! built-in passes cancel adjacent inverse gate pairs, merge consecutive rotations about the same
+//! axis and remove gates that act as the identity, and [`LowerUnitaries`] rewrites arbitrary
+//! one- and two-qubit `Unitary` gates as CNOTs and rotations. Two gates are adjacent when no
+//! instruction between them touches any of their qubits, so a pass looks through gates on other
+//! qubits.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::decompose;
+use crate::linalg;
+use crate::tolerance;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// Upper bound on the rounds a [`PassManager`] runs before giving up on reaching a fixed point.
+const MAX_ROUNDS: usize = 32;
+
+/// A `Pass` rewrites a circuit into an equivalent one.
+pub trait Pass {
+    /// Returns a short name for the pass, e.g. for logging.
+    fn name(&self) -> &str;
+
+    /// Returns the rewritten circuit, which must implement the same operation as `circuit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to rewrite.
+    fn run(&self, circuit: &Circuit) -> Circuit;
+}
+
+/// Removes pairs of adjacent gates on the same qubits that undo each other, such as `H·H`,
+/// `X·X`, `CNOT·CNOT` or `Rz(θ)·Rz(-θ)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CancelInverses;
+
+impl Pass for CancelInverses {
+    fn name(&self) -> &str {
+        "cancel-inverses"
+    }
+
+    fn run(&self, circuit: &Circuit) -> Circuit {
+        peephole(circuit, |previous, operation| {
+            (previous.inverse() == *operation).then_some(None)
+        })
+    }
+}
+
+/// Merges adjacent `Rx`, `Ry`, `Rz` or `Phase` gates on the same qubit into one rotation by the
+/// summed angle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeRotations;
+
+impl Pass for MergeRotations {
+    fn name(&self) -> &str {
+        "merge-rotations"
+    }
+
+    fn run(&self, circuit: &Circuit) -> Circuit {
+        peephole(circuit, |previous, operation| {
+            let merged = match (previous, operation) {
+                (Operation::Rx(a), Operation::Rx(b)) => Operation::Rx(a + b),
+                (Operation::Ry(a), Operation::Ry(b)) => Operation::Ry(a + b),
+                (Operation::Rz(a), Operation::Rz(b)) => Operation::Rz(a + b),
+                (Operation::Phase(a), Operation::Phase(b)) => Operation::Phase(a + b),
+                _ => return None,
+            };
+            Some(Some(merged))
+        })
+    }
+}
+
+/// Removes gates equal to the identity: rotations by a multiple of `4π`, phases by a multiple of
+/// `2π` and `Unitary` gates whose matrix is the identity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RemoveIdentities;
+
+impl Pass for RemoveIdentities {
+    fn name(&self) -> &str {
+        "remove-identities"
+    }
+
+    fn run(&self, circuit: &Circuit) -> Circuit {
+        let mut optimized = Circuit::with_clbits(circuit.num_qubits(), circuit.num_clbits());
+        for instruction in circuit.instructions() {
+            if !is_identity(&instruction.operation) {
+                add(&mut optimized, instruction.clone());
+            }
+        }
+        optimized
+    }
+}
+
+/// Rewrites one- and two-qubit `Unitary` gates, including conditional ones, as CNOTs and `Ry`
+/// and `Rz` rotations with [`decompose::single_qubit`] and [`decompose::two_qubit`]. The result
+/// equals the original up to a global phase of each rewritten gate. Gates on more qubits, and
+/// matrices that are not unitary within the tolerance, are kept as they are.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::{Circuit, Instruction, Operation};
+/// use quantum_simulator::gates::swap;
+/// use quantum_simulator::optimize::{LowerUnitaries, Pass};
+///
+/// let mut circuit = Circuit::new(3);
+/// let swap = Operation::Unitary(swap(0, 1, 2));
+/// circuit.add_instruction(Instruction::new(swap, vec![0, 2])).unwrap();
+/// let lowered = LowerUnitaries.run(&circuit);
+/// let unitary = |i: &Instruction| matches!(i.operation, Operation::Unitary(_));
+/// assert!(!lowered.instructions().iter().any(unitary));
+/// assert_eq!(lowered.instructions().iter().filter(|i| i.qubits.len() == 2).count(), 3);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LowerUnitaries;
+
+impl Pass for LowerUnitaries {
+    fn name(&self) -> &str {
+        "lower-unitaries"
+    }
+
+    fn run(&self, circuit: &Circuit) -> Circuit {
+        let mut lowered = Circuit::with_clbits(circuit.num_qubits(), circuit.num_clbits());
+        for instruction in circuit.instructions() {
+            let (gate, clbit) = match &instruction.operation {
+                Operation::Unitary(gate) => (gate, None),
+                Operation::Conditional { clbit, operation } => match operation.as_ref() {
+                    Operation::Unitary(gate) => (gate, Some(*clbit)),
+                    _ => {
+                        add(&mut lowered, instruction.clone());
+                        continue;
+                    }
+                },
+                _ => {
+                    add(&mut lowered, instruction.clone());
+                    continue;
+                }
+            };
+            let synthesized = match instruction.qubits.len() {
+                1 => decompose::single_qubit(gate),
+                2 => decompose::two_qubit(gate),
+                _ => {
+                    add(&mut lowered, instruction.clone());
+                    continue;
+                }
+            };
+            // Gate::new does not check unitarity, so matrices that are not unitary stay as they are
+            let Ok(synthesized) = synthesized else {
+                add(&mut lowered, instruction.clone());
+                continue;
+            };
+            for call in synthesized.instructions() {
+                let qubits = call.qubits.iter().map(|&q| instruction.qubits[q]).collect();
+                let operation = match clbit {
+                    Some(clbit) => Operation::Conditional {
+                        clbit,
+                        operation: Box::new(call.operation.clone()),
+                    },
+                    None => call.operation.clone(),
+                };
+                add(&mut lowered, Instruction::new(operation, qubits));
+            }
+        }
+        lowered
+    }
+}
+
+/// The sizes of a circuit before and after optimization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OptimizationReport {
+    /// The number of gates, i.e. unitary instructions, before optimization.
+    pub gates_before: usize,
+    /// The number of gates after optimization.
+    pub gates_after: usize,
+    /// The number of as-soon-as-possible layers before optimization.
+    pub depth_before: usize,
+    /// The number of as-soon-as-possible layers after optimization.
+    pub depth_after: usize,
+}
+
+impl fmt::Display for OptimizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gates: {} -> {}, depth: {} -> {}",
+            self.gates_before, self.gates_after, self.depth_before, self.depth_after
+        )
+    }
+}
+
+/// A `PassManager` runs a sequence of passes repeatedly until the circuit stops changing.
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+impl PassManager {
+    /// Creates a pass manager with no passes.
+    pub fn new() -> Self {
+        PassManager { passes: vec![] }
+    }
+
+    /// Creates a pass manager running [`CancelInverses`], [`MergeRotations`] and
+    /// [`RemoveIdentities`], in that order.
+    pub fn standard() -> Self {
+        let mut manager = PassManager::new();
+        manager
+            .add_pass(CancelInverses)
+            .add_pass(MergeRotations)
+            .add_pass(RemoveIdentities);
+        manager
+    }
+
+    /// Appends a pass to the sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `pass` - The pass to run after the ones already added.
+    pub fn add_pass(&mut self, pass: impl Pass + 'static) -> &mut Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Returns the names of the passes, in the order they run.
+    pub fn pass_names(&self) -> Vec<&str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
+    /// Runs every pass in order, repeating the sequence until a round leaves the circuit
+    /// unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to optimize.
+    ///
+    /// # Returns
+    ///
+    /// * The optimized circuit and the gate counts and depths before and after.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::optimize::{CancelInverses, PassManager};
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).x(1).h(0).cnot(0, 1).cnot(0, 1).x(1);
+    ///
+    /// let mut manager = PassManager::new();
+    /// manager.add_pass(CancelInverses);
+    /// let (optimized, report) = manager.run(&circuit);
+    /// assert!(optimized.instructions().is_empty());
+    /// assert_eq!(report.gates_before, 6);
+    /// assert_eq!(report.depth_after, 0);
+    /// ```
+    pub fn run(&self, circuit: &Circuit) -> (Circuit, OptimizationReport) {
+        let mut optimized = circuit.clone();
+        for _ in 0..MAX_ROUNDS {
+            let before = optimized.clone();
+            for pass in &self.passes {
+                optimized = pass.run(&optimized);
+            }
+            if optimized == before {
+                break;
+            }
+        }
+        let report = OptimizationReport {
+            gates_before: circuit.gate_count(),
+            gates_after: optimized.gate_count(),
+            depth_before: circuit.depth(),
+            depth_after: optimized.depth(),
+        };
+        (optimized, report)
+    }
+}
+
+/// Appends an instruction taken from a valid circuit of the same width.
+fn add(circuit: &mut Circuit, instruction: Instruction) {
+    circuit
+        .add_instruction(instruction)
+        .expect("instructions of a valid circuit fit a circuit of the same width");
+}
+
+/// Rewrites each gate together with the previous surviving instruction on exactly the same
+/// qubits, provided no other instruction touched those qubits in between.
+///
+/// `combine(previous, operation)` returns `None` to keep both gates, `Some(None)` to drop both
+/// and `Some(Some(merged))` to replace them with one gate. After a pair cancels, the instructions
+/// before it become adjacent to the gates that follow, so cancellations cascade.
+fn peephole(
+    circuit: &Circuit,
+    combine: impl Fn(&Operation, &Operation) -> Option<Option<Operation>>,
+) -> Circuit {
+    let mut kept: Vec<Option<Instruction>> = vec![];
+    let mut stacks: Vec<Vec<usize>> = vec![vec![]; circuit.num_qubits()];
+    for instruction in circuit.instructions() {
+        let previous = instruction
+            .qubits
+            .first()
+            .and_then(|&qubit| stacks[qubit].last().copied())
+            .filter(|&index| {
+                instruction
+                    .qubits
+                    .iter()
+                    .all(|&qubit| stacks[qubit].last() == Some(&index))
+            })
+            .filter(|&index| kept[index].as_ref().unwrap().qubits == instruction.qubits);
+        if let Some(index) = previous {
+            let operation = &kept[index].as_ref().unwrap().operation;
+            let combined = if operation.is_unitary() && instruction.operation.is_unitary() {
+                combine(operation, &instruction.operation)
+            } else {
+                None
+            };
+            match combined {
+                Some(None) => {
+                    kept[index] = None;
+                    for &qubit in &instruction.qubits {
+                        stacks[qubit].pop();
+                    }
+                    continue;
+                }
+                Some(Some(merged)) => {
+                    kept[index].as_mut().unwrap().operation = merged;
+                    continue;
+                }
+                None => {}
+            }
+        }
+        for &qubit in &instruction.qubits {
+            stacks[qubit].push(kept.len());
+        }
+        kept.push(Some(instruction.clone()));
+    }
+
+    let mut optimized = Circuit::with_clbits(circuit.num_qubits(), circuit.num_clbits());
+    for instruction in kept.into_iter().flatten() {
+        add(&mut optimized, instruction);
+    }
+    optimized
+}
+
+/// Returns whether `operation` is a gate equal to the identity, including its global phase.
+fn is_identity(operation: &Operation) -> bool {
+    let is_multiple = |angle: f64, period: f64| {
+        let remainder = angle.rem_euclid(period);
+        remainder.min(period - remainder) < tolerance::current().truncation
+    };
+    match operation {
+        Operation::Rx(theta) | Operation::Ry(theta) | Operation::Rz(theta) => {
+            is_multiple(*theta, 4.0 * PI)
+        }
+        Operation::Phase(theta) => is_multiple(*theta, 2.0 * PI),
+        Operation::Unitary(gate) => {
+            linalg::max_deviation(&gate.matrix, &linalg::identity(gate.matrix.len()))
+                < tolerance::current().truncation
+        }
+        _ => false,
+    }
+}
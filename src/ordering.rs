@@ -0,0 +1,185 @@
+//! This module converts basis indices, state vectors, bitstrings and counts between the two
+//! common conventions for ordering qubits.
+//!
+//! The simulator itself is little-endian: qubit `k` is bit `k` of a basis index, and bitstrings
+//! are written from the highest qubit down, so qubit 0 is the rightmost character. Qiskit uses
+//! the same convention. Cirq, many textbooks and kets written as `|q0 q1 … ⟩` are big-endian:
+//! qubit 0 is the most significant bit and the leftmost character. A [`QubitOrdering`] names the
+//! convention data comes in or should go out in, and converts it to or from the simulator's.
+
+use crate::counts::Counts;
+use crate::error::QuantumError;
+use num_complex::Complex;
+
+/// The convention mapping qubits to the bits of basis indices and the characters of bitstrings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QubitOrdering {
+    /// Qubit `k` is bit `k` of the index, and qubit 0 is the last character of a bitstring, as
+    /// in Qiskit and in the rest of this crate.
+    #[default]
+    LittleEndian,
+    /// Qubit 0 is the most significant bit of the index and the first character of a bitstring,
+    /// as in Cirq and in kets written `|q0 q1 … ⟩`.
+    BigEndian,
+}
+
+impl QubitOrdering {
+    /// Converts a basis index between this convention and the simulator's. The conversion is its
+    /// own inverse, so the same call works in both directions.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The basis index.
+    /// * `num_qubits` - The register width.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::ordering::QubitOrdering;
+    ///
+    /// // Only qubit 0 set: index 1 little-endian, index 4 big-endian on three qubits
+    /// assert_eq!(QubitOrdering::BigEndian.convert_index(1, 3), 4);
+    /// assert_eq!(QubitOrdering::BigEndian.convert_index(4, 3), 1);
+    /// assert_eq!(QubitOrdering::LittleEndian.convert_index(1, 3), 1);
+    /// ```
+    pub fn convert_index(self, index: usize, num_qubits: usize) -> usize {
+        match self {
+            QubitOrdering::LittleEndian => index,
+            QubitOrdering::BigEndian if num_qubits == 0 => index,
+            QubitOrdering::BigEndian => index.reverse_bits() >> (usize::BITS as usize - num_qubits),
+        }
+    }
+
+    /// Reorders the amplitudes of a state vector between this convention and the simulator's.
+    /// Like [`QubitOrdering::convert_index`], the conversion is its own inverse.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The amplitudes, `2^n` of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `state` is not a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::ordering::QubitOrdering;
+    /// use num_complex::Complex;
+    ///
+    /// // |01⟩ written big-endian has qubit 1 set, which the simulator stores at index 2
+    /// let mut ket = vec![Complex::new(0.0, 0.0); 4];
+    /// ket[1] = Complex::new(1.0, 0.0);
+    /// let state = QubitOrdering::BigEndian.convert_state(&ket);
+    /// assert_eq!(state[2], Complex::new(1.0, 0.0));
+    /// ```
+    pub fn convert_state(self, state: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        assert!(
+            state.len().is_power_of_two(),
+            "a state has a power-of-two number of amplitudes, not {}",
+            state.len()
+        );
+        let num_qubits = state.len().trailing_zeros() as usize;
+        (0..state.len())
+            .map(|index| state[self.convert_index(index, num_qubits)])
+            .collect()
+    }
+
+    /// Writes a basis state of the simulator as a bitstring in this convention.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The simulator's basis index.
+    /// * `num_qubits` - The register width, which sets the bitstring length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::ordering::QubitOrdering;
+    ///
+    /// // Qubits 0 and 1 set, qubit 2 clear
+    /// assert_eq!(QubitOrdering::LittleEndian.bitstring(0b011, 3), "011");
+    /// assert_eq!(QubitOrdering::BigEndian.bitstring(0b011, 3), "110");
+    /// ```
+    pub fn bitstring(self, index: usize, num_qubits: usize) -> String {
+        let bits = (0..num_qubits).map(|qubit| if (index >> qubit) & 1 == 1 { '1' } else { '0' });
+        match self {
+            QubitOrdering::LittleEndian => bits.rev().collect(),
+            QubitOrdering::BigEndian => bits.collect(),
+        }
+    }
+
+    /// Reads a bitstring in this convention as a basis index of the simulator.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitstring` - One `0` or `1` per qubit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - The simulator's basis index.
+    /// * `Err(QuantumError::Parse)` - If a character is not `0` or `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::ordering::QubitOrdering;
+    ///
+    /// assert_eq!(QubitOrdering::BigEndian.parse_bitstring("100").unwrap(), 1);
+    /// assert_eq!(QubitOrdering::LittleEndian.parse_bitstring("100").unwrap(), 4);
+    /// assert!(QubitOrdering::BigEndian.parse_bitstring("1x").is_err());
+    /// ```
+    pub fn parse_bitstring(self, bitstring: &str) -> Result<usize, QuantumError> {
+        let mut index = 0;
+        for (position, bit) in bitstring.chars().enumerate() {
+            let value = match bit {
+                '0' => 0,
+                '1' => 1,
+                _ => {
+                    return Err(QuantumError::Parse {
+                        line: 1,
+                        message: format!("invalid bit {:?} at position {}", bit, position),
+                    })
+                }
+            };
+            index = (index << 1) | value;
+        }
+        Ok(self.convert_index(index, bitstring.chars().count()))
+    }
+
+    /// Rewrites the bitstrings of counts between this convention and the simulator's, keeping
+    /// their shot numbers. Like [`QubitOrdering::convert_index`], the conversion is its own
+    /// inverse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::counts::Counts;
+    /// use quantum_simulator::ordering::QubitOrdering;
+    ///
+    /// let mut counts = Counts::new();
+    /// counts.record_many(0b001, 3, 10);
+    /// let big_endian = QubitOrdering::BigEndian.convert_counts(&counts);
+    /// assert_eq!(big_endian.get("100"), 10);
+    /// assert_eq!(QubitOrdering::BigEndian.convert_counts(&big_endian), counts);
+    /// ```
+    pub fn convert_counts(self, counts: &Counts) -> Counts {
+        if self == QubitOrdering::LittleEndian {
+            return counts.clone();
+        }
+        let mut converted = Counts::new();
+        for (bitstring, count) in counts.iter() {
+            // Reading the key MSB-first and reversing the bits reverses the string
+            let index = QubitOrdering::LittleEndian
+                .parse_bitstring(bitstring)
+                .expect("counts hold bitstrings");
+            converted.record_many(
+                self.convert_index(index, bitstring.len()),
+                bitstring.len(),
+                count,
+            );
+        }
+        converted
+    }
+}
@@ -0,0 +1,162 @@
+//! This module defines symbolic parameters for rotation angles, so a parameterized circuit such
+//! as a variational ansatz can be built once and bound to different values with
+//! [`Circuit::bind_parameters`](crate::circuit::Circuit::bind_parameters).
+
+use crate::circuit::Operation;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Mul, Neg};
+
+/// A named symbol standing for an angle, optionally scaled by a constant factor, e.g. `2·γ`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parameter {
+    name: String,
+    scale: f64,
+}
+
+impl Parameter {
+    /// Creates a parameter standing for the value bound to `name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the value is bound to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::parameter::Parameter;
+    /// use std::collections::HashMap;
+    ///
+    /// let gamma = Parameter::new("gamma") * 2.0;
+    /// assert_eq!(gamma.name(), "gamma");
+    /// assert_eq!(gamma.value(&HashMap::from([("gamma".to_string(), 0.25)])), Some(0.5));
+    /// assert_eq!((-gamma).value(&HashMap::new()), None);
+    /// ```
+    pub fn new(name: &str) -> Self {
+        Parameter {
+            name: name.to_string(),
+            scale: 1.0,
+        }
+    }
+
+    /// Returns the name the value is bound to.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the factor the bound value is multiplied by.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Returns the scaled value bound to the parameter's name, or `None` if `values` has no
+    /// value for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The values by parameter name.
+    pub fn value(&self, values: &HashMap<String, f64>) -> Option<f64> {
+        values.get(&self.name).map(|value| self.scale * value)
+    }
+}
+
+impl fmt::Display for Parameter {
+    /// Formats the parameter as its name, preceded by the scale unless it is `1` or `-1`, e.g.
+    /// `theta`, `-theta` or `0.5*theta`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.scale == 1.0 {
+            write!(f, "{}", self.name)
+        } else if self.scale == -1.0 {
+            write!(f, "-{}", self.name)
+        } else {
+            write!(f, "{}*{}", self.scale, self.name)
+        }
+    }
+}
+
+impl Mul<f64> for Parameter {
+    type Output = Parameter;
+
+    fn mul(mut self, factor: f64) -> Parameter {
+        self.scale *= factor;
+        self
+    }
+}
+
+impl Neg for Parameter {
+    type Output = Parameter;
+
+    fn neg(self) -> Parameter {
+        self * -1.0
+    }
+}
+
+/// The angle of a rotation: either a number or a [`Parameter`] bound later.
+///
+/// The rotation builders of [`Circuit`](crate::circuit::Circuit) accept both, so
+/// `circuit.rz(0, 0.5)` and `circuit.rz(0, Parameter::new("theta"))` both work.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Angle {
+    Value(f64),
+    Parameter(Parameter),
+}
+
+impl From<f64> for Angle {
+    fn from(value: f64) -> Self {
+        Angle::Value(value)
+    }
+}
+
+impl From<Parameter> for Angle {
+    fn from(parameter: Parameter) -> Self {
+        Angle::Parameter(parameter)
+    }
+}
+
+/// A rotation gate whose angle is a [`Parameter`], see [`Operation::Parametric`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParametricGate {
+    Rx,
+    Ry,
+    Rz,
+    Phase,
+}
+
+impl ParametricGate {
+    /// Returns the operation rotating by `angle`, or the [`Operation::Parametric`] rotating by a
+    /// parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `angle` - The rotation angle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Operation;
+    /// use quantum_simulator::parameter::ParametricGate;
+    ///
+    /// assert_eq!(ParametricGate::Ry.with_angle(0.5.into()), Operation::Ry(0.5));
+    /// ```
+    pub fn with_angle(self, angle: Angle) -> Operation {
+        match (self, angle) {
+            (ParametricGate::Rx, Angle::Value(theta)) => Operation::Rx(theta),
+            (ParametricGate::Ry, Angle::Value(theta)) => Operation::Ry(theta),
+            (ParametricGate::Rz, Angle::Value(theta)) => Operation::Rz(theta),
+            (ParametricGate::Phase, Angle::Value(theta)) => Operation::Phase(theta),
+            (gate, Angle::Parameter(parameter)) => Operation::Parametric { gate, parameter },
+        }
+    }
+
+    /// Returns the lowercase name of the gate, matching [`Operation::name`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParametricGate::Rx => "rx",
+            ParametricGate::Ry => "ry",
+            ParametricGate::Rz => "rz",
+            ParametricGate::Phase => "p",
+        }
+    }
+}
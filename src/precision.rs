@@ -0,0 +1,282 @@
+//! This module provides a single-precision state vector and the backend that runs circuits on it.
+//!
+//! [`StateVector32`] stores `Complex<f32>` amplitudes, half the memory of a [`Qubit`], in the
+//! same interleaved layout the [`gpu`](crate::gpu) shaders use. Gates stay double precision and
+//! are rounded to `f32` as they are applied; [`F32Backend`] runs whole circuits through the
+//! [`Backend`] trait, so results can be compared against [`CpuBackend`](crate::backend::CpuBackend)
+//! to see what the lower precision costs.
+
+use crate::backend::{check_targets, local_expectation, Backend};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::linalg;
+use crate::qubit::Qubit;
+use crate::stats;
+use num_complex::{Complex, Complex32};
+use rand::{Rng, RngCore};
+
+/// A state vector of `2^n` single-precision amplitudes, with qubit `k` as bit `k` of the index.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{cnot, hadamard};
+/// use quantum_simulator::precision::StateVector32;
+///
+/// let mut state = StateVector32::zero_state(2);
+/// state.apply_gate(&hadamard(1), &[0]).unwrap();
+/// state.apply_gate(&cnot(0, 1, 2), &[0, 1]).unwrap();
+/// assert!((state.probabilities()[3] - 0.5).abs() < 1e-6);
+/// assert_eq!(state.memory_bytes(), 4 * 8);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateVector32 {
+    /// The amplitudes, indexed by basis state.
+    pub state: Vec<Complex32>,
+}
+
+impl StateVector32 {
+    /// Creates `num_qubits` qubits in `|0…0⟩`.
+    pub fn zero_state(num_qubits: usize) -> Self {
+        let mut state = vec![Complex32::new(0.0, 0.0); 1 << num_qubits];
+        state[0] = Complex32::new(1.0, 0.0);
+        StateVector32 { state }
+    }
+
+    /// Creates a state from its amplitudes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length is not a power of two.
+    pub fn from_state(state: Vec<Complex32>) -> Self {
+        assert!(
+            state.len().is_power_of_two(),
+            "state length {} is not a power of two",
+            state.len()
+        );
+        StateVector32 { state }
+    }
+
+    /// Rounds a double-precision state to single precision.
+    pub fn from_qubit(qubit: &Qubit) -> Self {
+        let state = qubit
+            .state
+            .iter()
+            .map(|amplitude| Complex32::new(amplitude.re as f32, amplitude.im as f32))
+            .collect();
+        StateVector32 { state }
+    }
+
+    /// Widens the state to a double-precision [`Qubit`].
+    pub fn to_qubit(&self) -> Qubit {
+        Qubit::from_state(self.to_f64())
+    }
+
+    /// Returns the number of qubits.
+    pub fn num_qubits(&self) -> usize {
+        self.state.len().trailing_zeros() as usize
+    }
+
+    /// Returns the bytes taken by the amplitudes.
+    pub fn memory_bytes(&self) -> usize {
+        self.state.len() * std::mem::size_of::<Complex32>()
+    }
+
+    /// Returns the probability of each basis state.
+    pub fn probabilities(&self) -> Vec<f32> {
+        self.state
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .collect()
+    }
+
+    /// Returns how far the squared norm has drifted from one, accumulated rounding included.
+    pub fn norm_error(&self) -> f64 {
+        let norm: f64 = self
+            .state
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr() as f64)
+            .sum();
+        (norm - 1.0).abs()
+    }
+
+    /// Applies `gate` to the given qubits, rounding its matrix to single precision.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The gate to apply; bit `j` of its basis index corresponds to `targets[j]`.
+    /// * `targets` - The qubits the gate acts on.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`, or an error if the gate does not fit the targets.
+    pub fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+        check_targets(gate, targets, self.num_qubits())?;
+        stats::record_gate(self.memory_bytes(), || self.apply_checked(gate, targets));
+        Ok(())
+    }
+
+    fn apply_checked(&mut self, gate: &Gate, targets: &[usize]) {
+        let matrix: Vec<Vec<Complex32>> = gate
+            .matrix
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|entry| Complex32::new(entry.re as f32, entry.im as f32))
+                    .collect()
+            })
+            .collect();
+        let (offsets, target_mask) = linalg::local_offsets(targets);
+        let mut local_state = vec![Complex32::new(0.0, 0.0); offsets.len()];
+        for base in (0..self.state.len()).filter(|index| index & target_mask == 0) {
+            for (amplitude, offset) in local_state.iter_mut().zip(&offsets) {
+                *amplitude = self.state[base + offset];
+            }
+            for (row, offset) in matrix.iter().zip(&offsets) {
+                self.state[base + offset] = row.iter().zip(&local_state).map(|(m, a)| m * a).sum();
+            }
+        }
+    }
+
+    /// Measures `qubit` in the computational basis, collapsing the state, and returns the
+    /// outcome, drawing randomness from `rng`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `qubit` is not a qubit of the register.
+    pub fn measure_qubit_with_rng<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> usize {
+        assert!(
+            qubit < self.num_qubits(),
+            "qubit {} is out of range for a {}-qubit register",
+            qubit,
+            self.num_qubits()
+        );
+        let bit = 1 << qubit;
+        let prob_1: f64 = self
+            .state
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| index & bit != 0)
+            .map(|(_, amplitude)| amplitude.norm_sqr() as f64)
+            .sum();
+        let outcome = usize::from(rng.gen::<f64>() < prob_1);
+        let kept = if outcome == 1 { prob_1 } else { 1.0 - prob_1 };
+        let scale = (1.0 / kept.sqrt()) as f32;
+        for (index, amplitude) in self.state.iter_mut().enumerate() {
+            if (index & bit != 0) == (outcome == 1) {
+                *amplitude *= scale;
+            } else {
+                *amplitude = Complex32::new(0.0, 0.0);
+            }
+        }
+        outcome
+    }
+
+    /// Returns the amplitudes as little-endian `f32` pairs, the layout of a GPU state buffer.
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.state
+            .iter()
+            .flat_map(|amplitude| [amplitude.re, amplitude.im])
+            .flat_map(f32::to_le_bytes)
+            .collect()
+    }
+
+    /// Reads amplitudes written by [`StateVector32::to_le_bytes`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes do not hold a power-of-two number of amplitudes.
+    pub fn from_le_bytes(bytes: &[u8]) -> Self {
+        let state = bytes
+            .chunks_exact(8)
+            .map(|chunk| {
+                let re = f32::from_le_bytes(chunk[..4].try_into().unwrap());
+                let im = f32::from_le_bytes(chunk[4..].try_into().unwrap());
+                Complex32::new(re, im)
+            })
+            .collect();
+        Self::from_state(state)
+    }
+
+    fn to_f64(&self) -> Vec<Complex<f64>> {
+        self.state
+            .iter()
+            .map(|amplitude| Complex::new(amplitude.re as f64, amplitude.im as f64))
+            .collect()
+    }
+}
+
+/// The `F32Backend` is a dense state-vector engine in single precision.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::backend::Backend;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::gates::pauli_z;
+/// use quantum_simulator::precision::F32Backend;
+///
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1);
+///
+/// let mut backend = F32Backend::new(2);
+/// backend.run(&circuit).unwrap();
+/// assert!(backend.expectation(&pauli_z(), &[1]).abs() < 1e-6);
+/// assert_eq!(backend.measure(0), backend.measure(1));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct F32Backend {
+    state: StateVector32,
+}
+
+impl F32Backend {
+    /// Creates a backend holding `num_qubits` qubits in `|0…0⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register.
+    pub fn new(num_qubits: usize) -> Self {
+        Self::from_state(StateVector32::zero_state(num_qubits))
+    }
+
+    /// Creates a backend holding the given state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The initial state of the register.
+    pub fn from_state(state: StateVector32) -> Self {
+        F32Backend { state }
+    }
+}
+
+impl Backend for F32Backend {
+    type State = StateVector32;
+
+    fn name(&self) -> &str {
+        "cpu-f32"
+    }
+
+    fn num_qubits(&self) -> usize {
+        self.state.num_qubits()
+    }
+
+    fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+        self.state.apply_gate(gate, targets)
+    }
+
+    fn measure_with_rng(&mut self, qubit: usize, rng: &mut dyn RngCore) -> usize {
+        self.state.measure_qubit_with_rng(qubit, rng)
+    }
+
+    fn expectation(&self, observable: &Gate, targets: &[usize]) -> f64 {
+        local_expectation(&self.state.to_qubit(), observable, targets)
+    }
+
+    fn state(&self) -> StateVector32 {
+        self.state.clone()
+    }
+
+    fn amplitudes(&self) -> Option<Vec<Complex<f64>>> {
+        Some(self.state.to_f64())
+    }
+}
@@ -0,0 +1,29 @@
+//! This module re-exports the types and functions most programs need, so they can start with a
+//! single `use` line.
+//!
+//! # Examples
+//!
+//! ```
+//! use quantum_simulator::prelude::*;
+//!
+//! let mut circuit = Circuit::new(2);
+//! circuit.h(0).cnot(0, 1);
+//!
+//! let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+//! initial_state[0] = Complex::new(1.0, 0.0);
+//! let state: StateVector = Simulator::run(&circuit, &initial_state);
+//!
+//! let counts: Counts = state.sample(100);
+//! assert_eq!(counts.get("00") + counts.get("11"), 100);
+//! ```
+
+pub use crate::circuit::{Circuit, Instruction, Operation};
+pub use crate::counts::Counts;
+pub use crate::error::QuantumError;
+pub use crate::gates::{
+    cnot, cz, hadamard, iswap, pauli_x, pauli_y, pauli_z, phase, rx, ry, rz, s, swap, t, toffoli,
+    Gate,
+};
+pub use crate::qubit::{Basis, Qubit, StateVector};
+pub use crate::simulator::Simulator;
+pub use num_complex::Complex;
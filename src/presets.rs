@@ -0,0 +1,117 @@
+//! This module provides a library of named example circuits.
+//!
+//! [`Circuit::preset`] builds a circuit from its name: `bell`, `teleportation`, or one of the
+//! families `ghz-<n>` and `qft-<n>` for 2 to [`MAX_PRESET_QUBITS`] qubits and `grover-<n>` for 2
+//! to [`MAX_GROVER_PRESET_QUBITS`].
+//! [`PRESETS`] lists a representative of each, which the command line's `presets` subcommand
+//! prints and the visualizer offers in its editor.
+
+use crate::algorithms::{self, qft, Grover, Oracle};
+use crate::circuit::Circuit;
+use crate::error::QuantumError;
+use std::f64::consts::FRAC_PI_2;
+
+/// Widest register of the `ghz-<n>` and `qft-<n>` families.
+pub const MAX_PRESET_QUBITS: usize = 16;
+
+/// Widest register of the `grover-<n>` family, whose oracle and diffusion are dense `2ⁿ × 2ⁿ`
+/// matrices repeated in each of the `O(√2ⁿ)` iterations.
+pub const MAX_GROVER_PRESET_QUBITS: usize = 10;
+
+/// A named example circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preset {
+    /// The name passed to [`Circuit::preset`].
+    pub name: &'static str,
+    /// A one-line description of what the circuit does.
+    pub description: &'static str,
+}
+
+impl Preset {
+    /// Builds the circuit.
+    pub fn circuit(&self) -> Circuit {
+        preset(self.name).expect("listed presets exist")
+    }
+}
+
+/// The presets listed by the command line and the visualizer.
+pub const PRESETS: [Preset; 6] = [
+    Preset {
+        name: "bell",
+        description: "Bell pair (|00⟩ + |11⟩)/√2 from H and CNOT",
+    },
+    Preset {
+        name: "ghz-3",
+        description: "3-qubit GHZ state from H and a chain of CNOTs",
+    },
+    Preset {
+        name: "ghz-5",
+        description: "5-qubit GHZ state from H and a chain of CNOTs",
+    },
+    Preset {
+        name: "qft-4",
+        description: "Quantum Fourier transform on 4 qubits",
+    },
+    Preset {
+        name: "grover-3",
+        description: "Grover search for |111⟩ on 3 qubits, 2 iterations",
+    },
+    Preset {
+        name: "teleportation",
+        description: "Teleport Ry(π/2)|0⟩ from q0 to q2 with mid-circuit measurements",
+    },
+];
+
+/// Builds the example circuit called `name`; see [`Circuit::preset`].
+pub fn preset(name: &str) -> Result<Circuit, QuantumError> {
+    match name {
+        "bell" => return Ok(ghz(2)),
+        "teleportation" => return Ok(teleportation()),
+        _ => {}
+    }
+    let unknown = || QuantumError::Parse {
+        line: 0,
+        message: format!(
+            "unknown preset '{}', expected bell, teleportation, ghz-<n>, qft-<n> or grover-<n>",
+            name
+        ),
+    };
+    let (family, width) = name.rsplit_once('-').ok_or_else(unknown)?;
+    let (build, max_qubits): (fn(usize) -> Circuit, usize) = match family {
+        "ghz" => (ghz, MAX_PRESET_QUBITS),
+        "qft" => (qft, MAX_PRESET_QUBITS),
+        "grover" => (grover, MAX_GROVER_PRESET_QUBITS),
+        _ => return Err(unknown()),
+    };
+    let num_qubits: usize = width.parse().map_err(|_| unknown())?;
+    if !(2..=max_qubits).contains(&num_qubits) {
+        return Err(QuantumError::Parse {
+            line: 0,
+            message: format!("preset '{}' needs 2 to {} qubits", name, max_qubits),
+        });
+    }
+    Ok(build(num_qubits))
+}
+
+fn ghz(num_qubits: usize) -> Circuit {
+    let mut circuit = Circuit::new(num_qubits);
+    circuit.h(0);
+    for qubit in 1..num_qubits {
+        circuit.cnot(qubit - 1, qubit);
+    }
+    circuit
+}
+
+/// Searches for the all-ones state with the optimal number of Grover iterations.
+fn grover(num_qubits: usize) -> Circuit {
+    let marked = "1".repeat(num_qubits);
+    Grover::new(Oracle::from_bitstrings(num_qubits, &[marked.as_str()])).circuit()
+}
+
+/// Teleports `Ry(π/2)|0⟩` from qubit 0 to qubit 2 with [`algorithms::teleportation`].
+fn teleportation() -> Circuit {
+    let mut circuit = Circuit::new(3);
+    circuit.ry(0, FRAC_PI_2);
+    circuit.append(algorithms::teleportation().circuit());
+    circuit
+}
@@ -0,0 +1,446 @@
+//! This module exposes the simulator to Python through PyO3, behind the `python` feature.
+//!
+//! Build and install the extension module into the active virtual environment with
+//! `maturin develop --release`, which reads `pyproject.toml` and enables the feature. The module
+//! mirrors the Rust API: a `Circuit` class with the same chainable gate methods, the gate
+//! constructors returning their matrices as nested lists of complex numbers, and `run`,
+//! `sample` and `expectation` functions:
+//!
+//! ```python
+//! import quantum_simulator as qs
+//!
+//! circuit = qs.Circuit(2).h(0).cnot(0, 1)
+//! state = qs.run(circuit)                 # [0.707+0j, 0j, 0j, 0.707+0j]
+//! counts = qs.sample(circuit, 1000, seed=7)  # {"00": 508, "11": 492}
+//! qs.expectation(circuit, "Z0 Z1")        # 1.0
+//! ```
+//!
+//! Amplitudes are indexed like the Rust state vector, with qubit `k` as bit `k` of the index, so
+//! they compare directly with Qiskit's `Statevector.data` and with NumPy references built in the
+//! same convention. Invalid input raises `ValueError` instead of panicking.
+
+// The code generated by `#[pymethods]` converts every returned `PyErr` into a `PyErr`
+#![allow(clippy::useless_conversion)]
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::error::QuantumError;
+use crate::gates::{self, Gate};
+use crate::qasm::{self, QasmVersion};
+use crate::qubit::Qubit;
+use crate::simulator::Simulator;
+use crate::tolerance;
+use crate::variational::Hamiltonian;
+use num_complex::Complex;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+
+/// A square matrix as Python sees it, a list of rows of complex numbers.
+type Matrix = Vec<Vec<Complex<f64>>>;
+
+/// Factor by which the [`unitarity`](crate::tolerance::Tolerances::unitarity) tolerance is widened
+/// for matrices passed from Python, which often went through a NumPy round trip: `1e-9` by
+/// default.
+const UNITARITY_SLACK: f64 = 10.0;
+
+/// Widest register the `cnot`, `cz` and `swap` matrix builders accept, a matrix of `4¹²`
+/// entries.
+const MAX_MATRIX_QUBITS: usize = 12;
+
+impl From<QuantumError> for PyErr {
+    fn from(error: QuantumError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+/// A quantum circuit whose gate methods return the circuit, so calls chain as in Rust.
+#[pyclass(name = "Circuit", module = "quantum_simulator")]
+#[derive(Clone)]
+struct PyCircuit {
+    circuit: Circuit,
+}
+
+impl PyCircuit {
+    /// Appends an operation, raising `ValueError` for qubits outside the register.
+    fn push(
+        mut slf: PyRefMut<'_, Self>,
+        operation: Operation,
+        qubits: Vec<usize>,
+    ) -> PyResult<PyRefMut<'_, Self>> {
+        slf.circuit
+            .add_instruction(Instruction::new(operation, qubits))?;
+        Ok(slf)
+    }
+}
+
+#[pymethods]
+impl PyCircuit {
+    #[new]
+    fn new(num_qubits: usize) -> PyResult<Self> {
+        dimension(num_qubits)?;
+        Ok(PyCircuit {
+            circuit: Circuit::new(num_qubits),
+        })
+    }
+
+    /// Parses an OpenQASM 2 or 3 program.
+    #[staticmethod]
+    fn from_qasm(source: &str) -> PyResult<Self> {
+        Ok(PyCircuit {
+            circuit: qasm::parse(source)?,
+        })
+    }
+
+    /// Returns the circuit as an OpenQASM 2 program.
+    fn to_qasm(&self) -> PyResult<String> {
+        Ok(qasm::to_qasm(&self.circuit, QasmVersion::V2)?)
+    }
+
+    #[getter]
+    fn num_qubits(&self) -> usize {
+        self.circuit.num_qubits()
+    }
+
+    #[getter]
+    fn num_clbits(&self) -> usize {
+        self.circuit.num_clbits()
+    }
+
+    fn __len__(&self) -> usize {
+        self.circuit.instructions().len()
+    }
+
+    fn __str__(&self) -> String {
+        self.circuit.draw()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Circuit(num_qubits={}, instructions={})",
+            self.circuit.num_qubits(),
+            self.circuit.instructions().len()
+        )
+    }
+
+    fn h(slf: PyRefMut<'_, Self>, qubit: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::H, vec![qubit])
+    }
+
+    fn x(slf: PyRefMut<'_, Self>, qubit: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::X, vec![qubit])
+    }
+
+    fn y(slf: PyRefMut<'_, Self>, qubit: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Y, vec![qubit])
+    }
+
+    fn z(slf: PyRefMut<'_, Self>, qubit: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Z, vec![qubit])
+    }
+
+    fn s(slf: PyRefMut<'_, Self>, qubit: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::S, vec![qubit])
+    }
+
+    fn t(slf: PyRefMut<'_, Self>, qubit: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::T, vec![qubit])
+    }
+
+    fn rx(slf: PyRefMut<'_, Self>, qubit: usize, theta: f64) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Rx(theta), vec![qubit])
+    }
+
+    fn ry(slf: PyRefMut<'_, Self>, qubit: usize, theta: f64) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Ry(theta), vec![qubit])
+    }
+
+    fn rz(slf: PyRefMut<'_, Self>, qubit: usize, theta: f64) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Rz(theta), vec![qubit])
+    }
+
+    fn phase(slf: PyRefMut<'_, Self>, qubit: usize, theta: f64) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Phase(theta), vec![qubit])
+    }
+
+    fn cnot(
+        slf: PyRefMut<'_, Self>,
+        control: usize,
+        target: usize,
+    ) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Cnot, vec![control, target])
+    }
+
+    fn cz(slf: PyRefMut<'_, Self>, control: usize, target: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Cz, vec![control, target])
+    }
+
+    fn swap(slf: PyRefMut<'_, Self>, a: usize, b: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Swap, vec![a, b])
+    }
+
+    fn iswap(slf: PyRefMut<'_, Self>, a: usize, b: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::ISwap, vec![a, b])
+    }
+
+    fn toffoli(
+        slf: PyRefMut<'_, Self>,
+        control1: usize,
+        control2: usize,
+        target: usize,
+    ) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Toffoli, vec![control1, control2, target])
+    }
+
+    /// Applies an arbitrary unitary; bit `j` of its basis index corresponds to `qubits[j]`.
+    fn unitary(
+        slf: PyRefMut<'_, Self>,
+        matrix: Matrix,
+        qubits: Vec<usize>,
+    ) -> PyResult<PyRefMut<'_, Self>> {
+        let gate = Gate::try_new(
+            matrix,
+            Some(tolerance::current().unitarity * UNITARITY_SLACK),
+        )?;
+        let expected = dimension(qubits.len())?;
+        if gate.matrix.len() != expected {
+            return Err(QuantumError::DimensionMismatch {
+                expected,
+                found: gate.matrix.len(),
+            }
+            .into());
+        }
+        Self::push(slf, Operation::Unitary(gate), qubits)
+    }
+
+    fn measure(
+        slf: PyRefMut<'_, Self>,
+        qubit: usize,
+        clbit: usize,
+    ) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Measure(clbit), vec![qubit])
+    }
+
+    fn reset(slf: PyRefMut<'_, Self>, qubit: usize) -> PyResult<PyRefMut<'_, Self>> {
+        Self::push(slf, Operation::Reset, vec![qubit])
+    }
+
+    /// Returns a copy with the gates inverted and in reverse order.
+    fn inverse(&self) -> PyResult<Self> {
+        if !self.circuit.is_unitary() {
+            return Err(PyValueError::new_err(
+                "only circuits without measurements or resets can be inverted",
+            ));
+        }
+        Ok(PyCircuit {
+            circuit: self.circuit.inverse(),
+        })
+    }
+}
+
+/// Runs a circuit on an initial state, `|0…0⟩` by default, and returns the final amplitudes.
+#[pyfunction]
+#[pyo3(signature = (circuit, initial_state = None))]
+fn run(
+    circuit: &PyCircuit,
+    initial_state: Option<Vec<Complex<f64>>>,
+) -> PyResult<Vec<Complex<f64>>> {
+    Ok(final_state(circuit, initial_state)?.state)
+}
+
+/// Runs a circuit from `|0…0⟩` and samples the final state `shots` times, returning counts keyed
+/// by bitstrings with qubit 0 last.
+#[pyfunction]
+#[pyo3(signature = (circuit, shots, seed = None))]
+fn sample(
+    circuit: &PyCircuit,
+    shots: usize,
+    seed: Option<u64>,
+) -> PyResult<BTreeMap<String, usize>> {
+    let state = final_state(circuit, None)?;
+    let counts = match seed {
+        Some(seed) => state.sample_with_rng(shots, &mut StdRng::seed_from_u64(seed)),
+        None => state.sample(shots),
+    };
+    Ok(counts
+        .iter()
+        .map(|(bitstring, count)| (bitstring.to_string(), count))
+        .collect())
+}
+
+/// Runs a circuit from `|0…0⟩` and returns the expectation of an observable written as a sum of
+/// Pauli strings, e.g. `"Z0 Z1 - 0.5 X0"`.
+#[pyfunction]
+fn expectation(circuit: &PyCircuit, observable: &str) -> PyResult<f64> {
+    let hamiltonian = Hamiltonian::parse(observable)?;
+    if hamiltonian.num_qubits() > circuit.circuit.num_qubits() {
+        return Err(QuantumError::QubitOutOfRange {
+            qubit: hamiltonian.num_qubits() - 1,
+            num_qubits: circuit.circuit.num_qubits(),
+        }
+        .into());
+    }
+    Ok(hamiltonian.expectation(&final_state(circuit, None)?))
+}
+
+/// Runs a circuit, checking the initial state, and returns the final state.
+fn final_state(circuit: &PyCircuit, initial_state: Option<Vec<Complex<f64>>>) -> PyResult<Qubit> {
+    let num_qubits = circuit.circuit.num_qubits();
+    let expected = dimension(num_qubits)?;
+    let initial_state = initial_state.unwrap_or_else(|| Qubit::zero_state(num_qubits).state);
+    if initial_state.len() != expected {
+        return Err(QuantumError::DimensionMismatch {
+            expected,
+            found: initial_state.len(),
+        }
+        .into());
+    }
+    let norm = initial_state
+        .iter()
+        .map(|a| a.norm_sqr())
+        .sum::<f64>()
+        .sqrt();
+    if (norm - 1.0).abs() > tolerance::current().normalization {
+        return Err(QuantumError::NotNormalized { norm }.into());
+    }
+    Ok(Simulator::run(&circuit.circuit, &initial_state))
+}
+
+#[pyfunction]
+fn hadamard() -> Matrix {
+    matrix(gates::hadamard(1))
+}
+
+#[pyfunction]
+fn pauli_x() -> Matrix {
+    matrix(gates::pauli_x())
+}
+
+#[pyfunction]
+fn pauli_y() -> Matrix {
+    matrix(gates::pauli_y())
+}
+
+#[pyfunction]
+fn pauli_z() -> Matrix {
+    matrix(gates::pauli_z())
+}
+
+#[pyfunction]
+fn s() -> Matrix {
+    matrix(gates::s())
+}
+
+#[pyfunction]
+fn t() -> Matrix {
+    matrix(gates::t())
+}
+
+#[pyfunction]
+fn phase(theta: f64) -> Matrix {
+    matrix(gates::phase(theta))
+}
+
+#[pyfunction]
+fn rx(theta: f64) -> Matrix {
+    matrix(gates::rx(theta))
+}
+
+#[pyfunction]
+fn ry(theta: f64) -> Matrix {
+    matrix(gates::ry(theta))
+}
+
+#[pyfunction]
+fn rz(theta: f64) -> Matrix {
+    matrix(gates::rz(theta))
+}
+
+#[pyfunction]
+fn u3(theta: f64, phi: f64, lambda: f64) -> Matrix {
+    matrix(gates::u3(theta, phi, lambda))
+}
+
+/// Returns the CNOT on `control` and `target` of a `num_qubits`-qubit register.
+#[pyfunction]
+#[pyo3(signature = (control = 0, target = 1, num_qubits = 2))]
+fn cnot(control: usize, target: usize, num_qubits: usize) -> PyResult<Matrix> {
+    register_matrix(Operation::Cnot, vec![control, target], num_qubits)
+}
+
+/// Returns the CZ on `control` and `target` of a `num_qubits`-qubit register.
+#[pyfunction]
+#[pyo3(signature = (control = 0, target = 1, num_qubits = 2))]
+fn cz(control: usize, target: usize, num_qubits: usize) -> PyResult<Matrix> {
+    register_matrix(Operation::Cz, vec![control, target], num_qubits)
+}
+
+/// Returns the SWAP of `a` and `b` of a `num_qubits`-qubit register.
+#[pyfunction]
+#[pyo3(signature = (a = 0, b = 1, num_qubits = 2))]
+fn swap(a: usize, b: usize, num_qubits: usize) -> PyResult<Matrix> {
+    register_matrix(Operation::Swap, vec![a, b], num_qubits)
+}
+
+/// Returns a copy of a gate's matrix.
+fn matrix(gate: Gate) -> Matrix {
+    gate.matrix.as_ref().clone()
+}
+
+/// Returns the matrix of `operation` on `qubits` of a `num_qubits`-qubit register, raising
+/// `ValueError` for qubits outside the register, repeated qubits or too wide a register.
+fn register_matrix(
+    operation: Operation,
+    qubits: Vec<usize>,
+    num_qubits: usize,
+) -> PyResult<Matrix> {
+    if num_qubits > MAX_MATRIX_QUBITS {
+        return Err(QuantumError::Unsupported {
+            feature: format!(
+                "{}-qubit matrices, at most {} qubits",
+                num_qubits, MAX_MATRIX_QUBITS
+            ),
+        }
+        .into());
+    }
+    let mut circuit = Circuit::new(num_qubits);
+    circuit.add_instruction(Instruction::new(operation, qubits))?;
+    Ok(circuit.to_unitary(num_qubits))
+}
+
+/// Returns the length `2ⁿ` of a state vector on `num_qubits` qubits, or an error if it does not
+/// fit in a `usize`.
+fn dimension(num_qubits: usize) -> Result<usize, QuantumError> {
+    u32::try_from(num_qubits)
+        .ok()
+        .and_then(|num_qubits| 1usize.checked_shl(num_qubits))
+        .ok_or_else(|| QuantumError::Unsupported {
+            feature: format!("{}-qubit state vectors", num_qubits),
+        })
+}
+
+/// The `quantum_simulator` Python module; pass it to `pyo3::append_to_inittab!` to embed it in
+/// an interpreter started from Rust.
+#[pymodule]
+pub fn quantum_simulator(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyCircuit>()?;
+    module.add_function(wrap_pyfunction!(run, module)?)?;
+    module.add_function(wrap_pyfunction!(sample, module)?)?;
+    module.add_function(wrap_pyfunction!(expectation, module)?)?;
+    module.add_function(wrap_pyfunction!(hadamard, module)?)?;
+    module.add_function(wrap_pyfunction!(pauli_x, module)?)?;
+    module.add_function(wrap_pyfunction!(pauli_y, module)?)?;
+    module.add_function(wrap_pyfunction!(pauli_z, module)?)?;
+    module.add_function(wrap_pyfunction!(s, module)?)?;
+    module.add_function(wrap_pyfunction!(t, module)?)?;
+    module.add_function(wrap_pyfunction!(phase, module)?)?;
+    module.add_function(wrap_pyfunction!(rx, module)?)?;
+    module.add_function(wrap_pyfunction!(ry, module)?)?;
+    module.add_function(wrap_pyfunction!(rz, module)?)?;
+    module.add_function(wrap_pyfunction!(u3, module)?)?;
+    module.add_function(wrap_pyfunction!(cnot, module)?)?;
+    module.add_function(wrap_pyfunction!(cz, module)?)?;
+    module.add_function(wrap_pyfunction!(swap, module)?)?;
+    Ok(())
+}
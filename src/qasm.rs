@@ -0,0 +1,1188 @@
+//! This module imports and exports circuits as OpenQASM 2.0 and 3.0 source.
+//!
+//! Qubit `q[k]` of a register maps to circuit qubit `k`, offset by the sizes of the registers
+//! declared before it, and classical registers are laid out the same way.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::error::QuantumError;
+use crate::gates::{hadamard, pauli_y, phase, rx, ry, rz, swap, u3, Gate};
+use crate::library::{self, GateLibrary};
+use crate::tolerance;
+use num_complex::Complex;
+use std::collections::{BTreeSet, HashMap};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+use std::fmt::Write;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Maximum nesting depth of user-defined gates, guarding against recursive definitions.
+const MAX_GATE_DEPTH: usize = 64;
+
+/// Maximum nesting depth of expressions and `if` statements, guarding against stack overflow.
+const MAX_NESTING_DEPTH: usize = 256;
+
+/// Maximum total size of the quantum or classical registers a program may declare.
+const MAX_REGISTER_SIZE: usize = 1 << 20;
+
+/// Maximum number of gate applications and instructions a program may expand to, guarding
+/// against definitions that call each other exponentially often.
+const MAX_INSTRUCTIONS: usize = 1 << 20;
+
+/// Statement keywords that cannot name a gate.
+const KEYWORDS: [&str; 16] = [
+    "OPENQASM", "include", "qreg", "creg", "qubit", "bit", "gate", "measure", "barrier", "reset",
+    "if", "opaque", "ctrl", "inv", "pow", "negctrl",
+];
+
+/// The OpenQASM language version to emit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QasmVersion {
+    V2,
+    V3,
+}
+
+/// Parses OpenQASM 2.0 or 3.0 source into a `Circuit`.
+///
+/// The standard `qelib1.inc` and `stdgates.inc` gates are built in, user `gate` definitions are
+/// expanded inline and register arguments broadcast. Gates registered in the thread's
+/// [`GateLibrary`] become [`Operation::Custom`] instructions and take precedence over `gate`
+/// definitions of the same name, so exported custom gates read back under their names. Measurements, `barrier`, `reset` and `if`
+/// statements conditioned on a single classical bit being `1` become circuit instructions; other
+/// classical control and `opaque` gates are not supported.
+///
+/// # Arguments
+///
+/// * `source` - The QASM source text.
+///
+/// # Returns
+///
+/// * The parsed circuit, or `QuantumError::Parse` with the offending line.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Operation;
+/// use quantum_simulator::qasm::parse;
+///
+/// let circuit = parse(
+///     r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[2];
+///     creg c[2];
+///     h q[0];
+///     cx q[0], q[1];
+///     measure q -> c;
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(circuit.num_qubits(), 2);
+/// assert_eq!(circuit.num_clbits(), 2);
+/// assert_eq!(circuit.instructions()[3].operation, Operation::Measure(1));
+/// ```
+pub fn parse(source: &str) -> Result<Circuit, QuantumError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        qregs: vec![],
+        cregs: vec![],
+        gates: HashMap::new(),
+        library: library::current(),
+        instructions: vec![],
+        nesting: 0,
+        expansions: 0,
+    };
+    while parser.pos < parser.tokens.len() {
+        parser.statement()?;
+    }
+
+    let num_qubits = parser.qregs.iter().map(|reg| reg.size).sum();
+    let num_clbits = parser.cregs.iter().map(|reg| reg.size).sum();
+    let mut circuit = Circuit::with_clbits(num_qubits, num_clbits);
+    for (instruction, line) in parser.instructions {
+        circuit
+            .add_instruction(instruction)
+            .map_err(|error| parse_error(line, error.to_string()))?;
+    }
+    Ok(circuit)
+}
+
+/// Serializes a circuit as OpenQASM source.
+///
+/// Single-qubit `Unitary` operations are written as `u3`/`U` up to a global phase, and `iswap` is
+/// emitted together with its definition since neither standard library provides it. Custom gates
+/// are written by name after a `gate` definition built from their decomposition in the thread's
+/// [`GateLibrary`], or from their matrix if they act on a single qubit. OpenQASM 2
+/// can only condition on whole registers, so a circuit with conditional operations declares one
+/// single-bit register `cK` per classical bit.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to serialize.
+/// * `version` - The OpenQASM version to emit.
+///
+/// # Returns
+///
+/// * The QASM source, or `QuantumError::Unsupported` for multi-qubit `Unitary` operations and
+///   multi-qubit custom gates without a decomposition.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::qasm::{to_qasm, QasmVersion};
+///
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1).measure(0, 0).measure(1, 1);
+///
+/// let source = to_qasm(&circuit, QasmVersion::V2).unwrap();
+/// assert!(source.contains("cx q[0], q[1];"));
+/// assert!(source.contains("measure q[1] -> c[1];"));
+///
+/// let source = to_qasm(&circuit, QasmVersion::V3).unwrap();
+/// assert!(source.contains("qubit[2] q;"));
+/// assert!(source.contains("c[1] = measure q[1];"));
+/// ```
+pub fn to_qasm(circuit: &Circuit, version: QasmVersion) -> Result<String, QuantumError> {
+    let mut out = String::new();
+    let instructions = circuit.instructions();
+    match version {
+        QasmVersion::V2 => out.push_str("OPENQASM 2.0;\ninclude \"qelib1.inc\";\n"),
+        QasmVersion::V3 => out.push_str("OPENQASM 3.0;\ninclude \"stdgates.inc\";\n"),
+    }
+    let library = library::current();
+    let mut definitions = String::new();
+    let mut defined = BTreeSet::new();
+    for instruction in instructions {
+        define_custom(
+            &instruction.operation,
+            version,
+            &library,
+            &mut definitions,
+            &mut defined,
+        )?;
+    }
+    if defined.contains("iswap") {
+        out.push_str("gate iswap a, b { s a; s b; h a; cx a, b; cx b, a; h b; }\n");
+    }
+    out.push_str(&definitions);
+    let split_clbits = version == QasmVersion::V2
+        && instructions
+            .iter()
+            .any(|instruction| matches!(instruction.operation, Operation::Conditional { .. }));
+    let clbit = |k: usize| {
+        if split_clbits {
+            format!("c{}[0]", k)
+        } else {
+            format!("c[{}]", k)
+        }
+    };
+    let num_clbits = circuit.num_clbits();
+    match version {
+        QasmVersion::V2 => {
+            let _ = writeln!(out, "qreg q[{}];", circuit.num_qubits());
+            if split_clbits {
+                for k in 0..num_clbits {
+                    let _ = writeln!(out, "creg c{}[1];", k);
+                }
+            } else if num_clbits > 0 {
+                let _ = writeln!(out, "creg c[{}];", num_clbits);
+            }
+        }
+        QasmVersion::V3 => {
+            let _ = writeln!(out, "qubit[{}] q;", circuit.num_qubits());
+            if num_clbits > 0 {
+                let _ = writeln!(out, "bit[{}] c;", num_clbits);
+            }
+        }
+    }
+
+    for instruction in instructions {
+        let qubits: Vec<String> = instruction
+            .qubits
+            .iter()
+            .map(|q| format!("q[{}]", q))
+            .collect();
+        let qubits = qubits.join(", ");
+        let operation = match &instruction.operation {
+            Operation::Measure(k) => {
+                let _ = match version {
+                    QasmVersion::V2 => writeln!(out, "measure {} -> {};", qubits, clbit(*k)),
+                    QasmVersion::V3 => writeln!(out, "{} = measure {};", clbit(*k), qubits),
+                };
+                continue;
+            }
+            Operation::Reset => {
+                let _ = writeln!(out, "reset {};", qubits);
+                continue;
+            }
+            Operation::Barrier(_) => {
+                let _ = writeln!(out, "barrier {};", qubits);
+                continue;
+            }
+            Operation::Snapshot(label) => {
+                let _ = writeln!(out, "// snapshot {:?}", label);
+                continue;
+            }
+            // Assertions only check the simulation and have no QASM equivalent
+            Operation::Assert(_) => continue,
+            Operation::Conditional {
+                clbit: k,
+                operation,
+            } => {
+                let _ = match version {
+                    QasmVersion::V2 => write!(out, "if(c{}==1) ", k),
+                    QasmVersion::V3 => write!(out, "if ({} == 1) ", clbit(*k)),
+                };
+                operation
+            }
+            operation => operation,
+        };
+        let _ = writeln!(out, "{} {};", gate_call(operation, version)?, qubits);
+    }
+    Ok(out)
+}
+
+/// Returns the QASM gate name of an operation followed by its parameters, e.g. `rz(0.5)`.
+fn gate_call(operation: &Operation, version: QasmVersion) -> Result<String, QuantumError> {
+    if let Operation::Custom { name, .. } = operation {
+        return Ok(name.clone());
+    }
+    let (name, params) = qasm_name(operation, version)?;
+    if params.is_empty() {
+        return Ok(name.to_string());
+    }
+    let params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+    Ok(format!("{}({})", name, params.join(", ")))
+}
+
+/// Appends the `gate` definition of a custom gate, after those of the custom gates its
+/// decomposition uses, unless `defined` already holds its name. `iswap` is recorded in `defined`
+/// without a definition, which the caller emits.
+fn define_custom(
+    operation: &Operation,
+    version: QasmVersion,
+    library: &GateLibrary,
+    out: &mut String,
+    defined: &mut BTreeSet<String>,
+) -> Result<(), QuantumError> {
+    let (name, gate) = match operation {
+        Operation::Conditional { operation, .. } => {
+            return define_custom(operation, version, library, out, defined)
+        }
+        Operation::ISwap => {
+            defined.insert("iswap".to_string());
+            return Ok(());
+        }
+        Operation::Custom { name, gate } => (name, gate),
+        _ => return Ok(()),
+    };
+    if !defined.insert(name.clone()) {
+        return Ok(());
+    }
+    let num_qubits = gate.matrix.len().trailing_zeros() as usize;
+    let args: Vec<String> = (0..num_qubits).map(|k| format!("a{}", k)).collect();
+    let mut body = vec![];
+    match library.decomposition(name) {
+        Some(circuit) => {
+            for instruction in circuit.instructions() {
+                define_custom(&instruction.operation, version, library, out, defined)?;
+                let qubits: Vec<&str> = instruction
+                    .qubits
+                    .iter()
+                    .map(|&q| args[q].as_str())
+                    .collect();
+                let call = match &instruction.operation {
+                    Operation::Barrier(_) => "barrier".to_string(),
+                    Operation::Snapshot(_) => continue,
+                    operation => gate_call(operation, version)?,
+                };
+                body.push(format!("{} {};", call, qubits.join(", ")));
+            }
+        }
+        _ if num_qubits == 1 => {
+            let call = gate_call(&Operation::Unitary(gate.clone()), version)?;
+            body.push(format!("{} a0;", call));
+        }
+        _ => {
+            return Err(QuantumError::Unsupported {
+                feature: format!(
+                    "multi-qubit custom gate '{}' without a decomposition in QASM export",
+                    name
+                ),
+            })
+        }
+    }
+    let _ = writeln!(
+        out,
+        "gate {} {} {{ {} }}",
+        name,
+        args.join(", "),
+        body.join(" ")
+    );
+    Ok(())
+}
+
+/// Returns the QASM gate name and parameters for an operation.
+fn qasm_name(
+    operation: &Operation,
+    version: QasmVersion,
+) -> Result<(&'static str, Vec<f64>), QuantumError> {
+    Ok(match operation {
+        Operation::H => ("h", vec![]),
+        Operation::X => ("x", vec![]),
+        Operation::Y => ("y", vec![]),
+        Operation::Z => ("z", vec![]),
+        Operation::S => ("s", vec![]),
+        Operation::T => ("t", vec![]),
+        Operation::Rx(theta) => ("rx", vec![*theta]),
+        Operation::Ry(theta) => ("ry", vec![*theta]),
+        Operation::Rz(theta) => ("rz", vec![*theta]),
+        Operation::Phase(theta) => match version {
+            QasmVersion::V2 => ("u1", vec![*theta]),
+            QasmVersion::V3 => ("p", vec![*theta]),
+        },
+        Operation::Cnot => ("cx", vec![]),
+        Operation::Cz => ("cz", vec![]),
+        Operation::Swap => ("swap", vec![]),
+        Operation::ISwap => ("iswap", vec![]),
+        Operation::Toffoli => ("ccx", vec![]),
+        Operation::Unitary(gate) if gate.matrix.len() == 2 => {
+            let (theta, phi, lambda) = u3_angles(gate);
+            match version {
+                QasmVersion::V2 => ("u3", vec![theta, phi, lambda]),
+                QasmVersion::V3 => ("U", vec![theta, phi, lambda]),
+            }
+        }
+        Operation::Unitary(_) => {
+            return Err(QuantumError::Unsupported {
+                feature: "multi-qubit unitary operations in QASM export".to_string(),
+            })
+        }
+        Operation::Custom { .. } => unreachable!("custom gates are written by gate_call"),
+        Operation::Parametric { parameter, .. } => {
+            return Err(QuantumError::UnboundParameter {
+                name: parameter.name().to_string(),
+            })
+        }
+        Operation::Measure(_)
+        | Operation::Reset
+        | Operation::Conditional { .. }
+        | Operation::Snapshot(_)
+        | Operation::Barrier(_)
+        | Operation::Assert(_) => {
+            return Err(QuantumError::Unsupported {
+                feature: format!("{:?} as a QASM gate", operation),
+            })
+        }
+    })
+}
+
+/// Recovers `(θ, φ, λ)` with `gate = e^{iα} U(θ, φ, λ)` for some global phase `α`.
+fn u3_angles(gate: &Gate) -> (f64, f64, f64) {
+    let m = &gate.matrix;
+    let theta = 2.0 * m[1][0].norm().atan2(m[0][0].norm());
+    if m[0][0].norm() < tolerance::current().truncation {
+        let alpha = m[1][0].arg();
+        return (theta, 0.0, (-m[0][1]).arg() - alpha);
+    }
+    let alpha = m[0][0].arg();
+    if m[1][0].norm() < tolerance::current().truncation {
+        return (theta, 0.0, m[1][1].arg() - alpha);
+    }
+    (theta, m[1][0].arg() - alpha, (-m[0][1]).arg() - alpha)
+}
+
+/// Returns `gate` controlled on local qubit 0, with the gate acting on the following qubits.
+fn controlled(gate: Gate) -> Operation {
+    Operation::Unitary(gate.with_control())
+}
+
+/// Returns the standard-library operation for a gate name, `Ok(None)` for the identity.
+fn standard_gate(name: &str, params: &[f64]) -> Option<Option<Operation>> {
+    let operation = match (name, params) {
+        ("id" | "i", []) | ("gphase", [_]) => return Some(None),
+        ("x", []) => Operation::X,
+        ("y", []) => Operation::Y,
+        ("z", []) => Operation::Z,
+        ("h", []) => Operation::H,
+        ("s", []) => Operation::S,
+        ("sdg", []) => Operation::Phase(-FRAC_PI_2),
+        ("t", []) => Operation::T,
+        ("tdg", []) => Operation::Phase(-FRAC_PI_4),
+        ("sx", []) => Operation::Unitary(Gate::new(vec![
+            vec![Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
+            vec![Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
+        ])),
+        ("rx", [theta]) => Operation::Rx(*theta),
+        ("ry", [theta]) => Operation::Ry(*theta),
+        ("rz", [theta]) => Operation::Rz(*theta),
+        ("p" | "u1" | "phase", [lambda]) => Operation::Phase(*lambda),
+        ("u2", [phi, lambda]) => Operation::Unitary(u3(FRAC_PI_2, *phi, *lambda)),
+        ("u3" | "u" | "U", [theta, phi, lambda]) => Operation::Unitary(u3(*theta, *phi, *lambda)),
+        ("cx" | "CX" | "cnot", []) => Operation::Cnot,
+        ("cy", []) => controlled(pauli_y()),
+        ("cz", []) => Operation::Cz,
+        ("ch", []) => controlled(hadamard(1)),
+        ("crx", [theta]) => controlled(rx(*theta)),
+        ("cry", [theta]) => controlled(ry(*theta)),
+        ("crz", [theta]) => controlled(rz(*theta)),
+        ("cp" | "cu1" | "cphase", [lambda]) => controlled(phase(*lambda)),
+        ("cu3", [theta, phi, lambda]) => controlled(u3(*theta, *phi, *lambda)),
+        ("cu", [theta, phi, lambda, gamma]) => {
+            let mut gate = u3(*theta, *phi, *lambda);
+            let global = Complex::from_polar(1.0, *gamma);
+            Arc::make_mut(&mut gate.matrix)
+                .iter_mut()
+                .flatten()
+                .for_each(|elem| *elem *= global);
+            controlled(gate)
+        }
+        ("swap", []) => Operation::Swap,
+        ("iswap", []) => Operation::ISwap,
+        ("ccx", []) => Operation::Toffoli,
+        ("cswap", []) => controlled(swap(0, 1, 2)),
+        _ => return None,
+    };
+    Some(Some(operation))
+}
+
+/// Returns whether `name` is a standard gate or a statement keyword, and so cannot name a custom
+/// gate.
+pub(crate) fn is_reserved(name: &str) -> bool {
+    KEYWORDS.contains(&name) || (0..=4).any(|n| standard_gate(name, &vec![0.0; n]).is_some())
+}
+
+/// Builds a parse error at `line`.
+fn parse_error(line: usize, message: impl Into<String>) -> QuantumError {
+    QuantumError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Symbol(&'static str),
+}
+
+/// Splits QASM source into tokens tagged with their line, dropping comments.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, QuantumError> {
+    const SYMBOLS: [&str; 16] = [
+        "->", "==", "(", ")", "[", "]", "{", "}", ",", ";", "=", "+", "-", "*", "/", "^",
+    ];
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if chars[i..].starts_with(&['/', '/']) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if chars[i..].starts_with(&['/', '*']) {
+            i += 2;
+            while i < chars.len() && !chars[i..].starts_with(&['*', '/']) {
+                line += (chars[i] == '\n') as usize;
+                i += 1;
+            }
+            i += 2;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+                if i < chars.len() && (chars[i] == '+' || chars[i] == '-') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse()
+                .map_err(|_| parse_error(line, format!("invalid number '{}'", text)))?;
+            tokens.push((Token::Number(value), line));
+        } else if c == '"' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != '"' {
+                i += 1;
+            }
+            tokens.push((Token::Str(chars[start..i].iter().collect()), line));
+            i += 1;
+        } else if c == 'π' {
+            tokens.push((Token::Ident("pi".to_string()), line));
+            i += 1;
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| {
+                    let symbol: Vec<char> = symbol.chars().collect();
+                    chars[i..].starts_with(&symbol)
+                })
+                .ok_or_else(|| parse_error(line, format!("unexpected character '{}'", c)))?;
+            tokens.push((Token::Symbol(symbol), line));
+            i += symbol.len();
+        }
+    }
+    Ok(tokens)
+}
+
+/// A parameter expression, kept symbolic so gate bodies can be evaluated per call.
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, env: &HashMap<String, f64>, line: usize) -> Result<f64, QuantumError> {
+        Ok(match self {
+            Expr::Number(value) => *value,
+            Expr::Variable(name) => match (env.get(name), name.as_str()) {
+                (Some(value), _) => *value,
+                (None, "pi") => PI,
+                (None, "tau") => 2.0 * PI,
+                _ => return Err(parse_error(line, format!("unknown parameter '{}'", name))),
+            },
+            Expr::Negate(inner) => -inner.eval(env, line)?,
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(env, line)?, rhs.eval(env, line)?);
+                match *op {
+                    "+" => lhs + rhs,
+                    "-" => lhs - rhs,
+                    "*" => lhs * rhs,
+                    "/" => lhs / rhs,
+                    _ => lhs.powf(rhs),
+                }
+            }
+            Expr::Call(function, arg) => {
+                let arg = arg.eval(env, line)?;
+                match function.as_str() {
+                    "sin" => arg.sin(),
+                    "cos" => arg.cos(),
+                    "tan" => arg.tan(),
+                    "exp" => arg.exp(),
+                    "ln" => arg.ln(),
+                    "sqrt" => arg.sqrt(),
+                    _ => {
+                        return Err(parse_error(
+                            line,
+                            format!("unknown function '{}'", function),
+                        ))
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// A `gate` statement inside a user gate definition.
+#[derive(Clone, Debug)]
+struct GateCall {
+    name: String,
+    params: Vec<Expr>,
+    args: Vec<String>,
+    line: usize,
+}
+
+#[derive(Clone, Debug)]
+struct GateDefinition {
+    params: Vec<String>,
+    qargs: Vec<String>,
+    body: Vec<GateCall>,
+}
+
+struct Register {
+    name: String,
+    start: usize,
+    size: usize,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    qregs: Vec<Register>,
+    cregs: Vec<Register>,
+    gates: HashMap<String, GateDefinition>,
+    library: Rc<GateLibrary>,
+    instructions: Vec<(Instruction, usize)>,
+    nesting: usize,
+    expansions: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, QuantumError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| parse_error(self.line(), "unexpected end of input"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, symbol: &'static str) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), QuantumError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(parse_error(self.line(), format!("expected '{}'", symbol)))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, QuantumError> {
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            token => Err(parse_error(
+                self.line(),
+                format!("expected identifier, found {:?}", token),
+            )),
+        }
+    }
+
+    fn integer(&mut self) -> Result<usize, QuantumError> {
+        match self.next()? {
+            Token::Number(value) if value >= 0.0 && value.fract() == 0.0 => Ok(value as usize),
+            token => Err(parse_error(
+                self.line(),
+                format!("expected integer, found {:?}", token),
+            )),
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), QuantumError> {
+        let line = self.line();
+        let keyword = self.ident()?;
+        match keyword.as_str() {
+            "OPENQASM" => {
+                self.next()?;
+                self.expect(";")
+            }
+            "include" => {
+                self.next()?;
+                self.expect(";")
+            }
+            "qreg" | "creg" => {
+                let name = self.ident()?;
+                self.expect("[")?;
+                let size = self.integer()?;
+                self.expect("]")?;
+                self.expect(";")?;
+                self.declare(keyword == "qreg", name, size, line)
+            }
+            "qubit" | "bit" => {
+                let mut size = 1;
+                if self.eat("[") {
+                    size = self.integer()?;
+                    self.expect("]")?;
+                }
+                let name = self.ident()?;
+                self.expect(";")?;
+                self.declare(keyword == "qubit", name, size, line)
+            }
+            "gate" => self.gate_definition(),
+            "measure" => {
+                let qubits = self.argument(true)?;
+                self.expect("->")?;
+                let clbits = self.argument(false)?;
+                self.expect(";")?;
+                self.measure(qubits, clbits, line)
+            }
+            "barrier" => {
+                let mut qubits: Vec<usize> = vec![];
+                if !self.eat(";") {
+                    loop {
+                        for qubit in self.argument(true)? {
+                            if !qubits.contains(&qubit) {
+                                qubits.push(qubit);
+                            }
+                        }
+                        if self.eat(";") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                } else {
+                    // A bare `barrier;` spans every qubit declared so far
+                    let num_qubits = self.qregs.iter().map(|reg| reg.size).sum();
+                    qubits = (0..num_qubits).collect();
+                }
+                let barrier = Operation::Barrier(qubits.len());
+                self.emit(Instruction::new(barrier, qubits), line)
+            }
+            "reset" => {
+                let qubits = self.argument(true)?;
+                self.expect(";")?;
+                for qubit in qubits {
+                    self.emit(Instruction::new(Operation::Reset, vec![qubit]), line)?;
+                }
+                Ok(())
+            }
+            "if" => self.conditional(line),
+            "opaque" | "ctrl" | "inv" | "pow" | "negctrl" => {
+                Err(parse_error(line, format!("'{}' is not supported", keyword)))
+            }
+            _ if matches!(self.peek(), Some(Token::Symbol("[" | "="))) => {
+                // OpenQASM 3 measurement assignment: `c[0] = measure q[0];`
+                self.pos -= 1;
+                let clbits = self.argument(false)?;
+                self.expect("=")?;
+                if self.ident()? != "measure" {
+                    return Err(parse_error(
+                        line,
+                        "only measurement assignments are supported",
+                    ));
+                }
+                let qubits = self.argument(true)?;
+                self.expect(";")?;
+                self.measure(qubits, clbits, line)
+            }
+            _ => self.gate_statement(keyword, line),
+        }
+    }
+
+    fn declare(
+        &mut self,
+        quantum: bool,
+        name: String,
+        size: usize,
+        line: usize,
+    ) -> Result<(), QuantumError> {
+        let registers = if quantum {
+            &mut self.qregs
+        } else {
+            &mut self.cregs
+        };
+        let start: usize = registers.iter().map(|reg| reg.size).sum();
+        if size > MAX_REGISTER_SIZE - start {
+            return Err(parse_error(
+                line,
+                format!(
+                    "registers larger than {} bits are not supported",
+                    MAX_REGISTER_SIZE
+                ),
+            ));
+        }
+        registers.push(Register { name, start, size });
+        Ok(())
+    }
+
+    /// Appends an instruction, failing once the program has expanded to too many.
+    fn emit(&mut self, instruction: Instruction, line: usize) -> Result<(), QuantumError> {
+        if self.instructions.len() == MAX_INSTRUCTIONS {
+            return Err(parse_error(
+                line,
+                format!(
+                    "programs longer than {} instructions are not supported",
+                    MAX_INSTRUCTIONS
+                ),
+            ));
+        }
+        self.instructions.push((instruction, line));
+        Ok(())
+    }
+
+    /// Descends one level of nesting, failing once the source nests too deeply. Callers restore
+    /// `self.nesting` when they return successfully.
+    fn enter(&mut self) -> Result<(), QuantumError> {
+        self.nesting += 1;
+        if self.nesting > MAX_NESTING_DEPTH {
+            return Err(parse_error(
+                self.line(),
+                format!(
+                    "nesting deeper than {} levels is not supported",
+                    MAX_NESTING_DEPTH
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses `name` or `name[index]` and resolves it to register indices.
+    fn argument(&mut self, quantum: bool) -> Result<Vec<usize>, QuantumError> {
+        let line = self.line();
+        let name = self.ident()?;
+        let index = if self.eat("[") {
+            let index = self.integer()?;
+            self.expect("]")?;
+            Some(index)
+        } else {
+            None
+        };
+        let registers = if quantum { &self.qregs } else { &self.cregs };
+        let register = registers
+            .iter()
+            .find(|reg| reg.name == name)
+            .ok_or_else(|| parse_error(line, format!("unknown register '{}'", name)))?;
+        match index {
+            Some(index) if index < register.size => Ok(vec![register.start + index]),
+            Some(index) => Err(parse_error(
+                line,
+                format!("index {} out of range for register '{}'", index, name),
+            )),
+            None => Ok((register.start..register.start + register.size).collect()),
+        }
+    }
+
+    fn measure(
+        &mut self,
+        qubits: Vec<usize>,
+        clbits: Vec<usize>,
+        line: usize,
+    ) -> Result<(), QuantumError> {
+        if qubits.len() != clbits.len() {
+            return Err(parse_error(line, "measurement register sizes differ"));
+        }
+        for (qubit, clbit) in qubits.into_iter().zip(clbits) {
+            self.emit(
+                Instruction::new(Operation::Measure(clbit), vec![qubit]),
+                line,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Parses `if (c == 1) body` or `if (c[k] == 1) body`, where the condition names one bit.
+    fn conditional(&mut self, line: usize) -> Result<(), QuantumError> {
+        self.expect("(")?;
+        let clbits = self.argument(false)?;
+        let value = if self.eat("==") { self.integer()? } else { 1 };
+        self.expect(")")?;
+        let clbit = match clbits[..] {
+            [clbit] if value == 1 => clbit,
+            _ => {
+                return Err(parse_error(
+                    line,
+                    "only conditions on a single classical bit being 1 are supported",
+                ))
+            }
+        };
+
+        let nesting = self.nesting;
+        self.enter()?;
+        let first = self.instructions.len();
+        if self.eat("{") {
+            while !self.eat("}") {
+                self.statement()?;
+            }
+        } else {
+            self.statement()?;
+        }
+        for (instruction, body_line) in &mut self.instructions[first..] {
+            if !instruction.operation.is_unitary() {
+                return Err(parse_error(
+                    *body_line,
+                    "only gates can be classically conditioned",
+                ));
+            }
+            let operation = std::mem::replace(&mut instruction.operation, Operation::Reset);
+            instruction.operation = Operation::Conditional {
+                clbit,
+                operation: Box::new(operation),
+            };
+        }
+        self.nesting = nesting;
+        Ok(())
+    }
+
+    fn expressions(&mut self) -> Result<Vec<Expr>, QuantumError> {
+        let mut params = vec![];
+        if self.eat("(") && !self.eat(")") {
+            loop {
+                params.push(self.expression()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(params)
+    }
+
+    fn expression(&mut self) -> Result<Expr, QuantumError> {
+        let nesting = self.nesting;
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat("+") {
+                "+"
+            } else if self.eat("-") {
+                "-"
+            } else {
+                self.nesting = nesting;
+                return Ok(lhs);
+            };
+            // Evaluating a chain recurses once per operator, so each link counts as a level.
+            self.enter()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, QuantumError> {
+        let nesting = self.nesting;
+        let mut lhs = self.factor()?;
+        loop {
+            let op = if self.eat("*") {
+                "*"
+            } else if self.eat("/") {
+                "/"
+            } else {
+                self.nesting = nesting;
+                return Ok(lhs);
+            };
+            self.enter()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Expr, QuantumError> {
+        let nesting = self.nesting;
+        self.enter()?;
+        let expr = if self.eat("-") {
+            Expr::Negate(Box::new(self.factor()?))
+        } else {
+            let base = self.primary()?;
+            if self.eat("^") {
+                Expr::Binary("^", Box::new(base), Box::new(self.factor()?))
+            } else {
+                base
+            }
+        };
+        self.nesting = nesting;
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, QuantumError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Number(value) => Ok(Expr::Number(value)),
+            Token::Ident(name) if self.eat("(") => {
+                let arg = self.expression()?;
+                self.expect(")")?;
+                Ok(Expr::Call(name, Box::new(arg)))
+            }
+            Token::Ident(name) => Ok(Expr::Variable(name)),
+            Token::Symbol("(") => {
+                let inner = self.expression()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            token => Err(parse_error(
+                line,
+                format!("unexpected {:?} in expression", token),
+            )),
+        }
+    }
+
+    fn gate_definition(&mut self) -> Result<(), QuantumError> {
+        let name = self.ident()?;
+        let mut params = vec![];
+        if self.eat("(") && !self.eat(")") {
+            loop {
+                params.push(self.ident()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        let mut qargs = vec![self.ident()?];
+        while self.eat(",") {
+            qargs.push(self.ident()?);
+        }
+        self.expect("{")?;
+        let mut body = vec![];
+        while !self.eat("}") {
+            let line = self.line();
+            let call_name = self.ident()?;
+            let call_params = self.expressions()?;
+            let mut args = vec![];
+            while !self.eat(";") {
+                args.push(self.ident()?);
+                self.eat(",");
+            }
+            if call_name != "barrier" {
+                body.push(GateCall {
+                    name: call_name,
+                    params: call_params,
+                    args,
+                    line,
+                });
+            }
+        }
+        self.gates.insert(
+            name,
+            GateDefinition {
+                params,
+                qargs,
+                body,
+            },
+        );
+        Ok(())
+    }
+
+    fn gate_statement(&mut self, name: String, line: usize) -> Result<(), QuantumError> {
+        let params = self
+            .expressions()?
+            .iter()
+            .map(|expr| expr.eval(&HashMap::new(), line))
+            .collect::<Result<Vec<f64>, _>>()?;
+        let mut args = vec![];
+        while !self.eat(";") {
+            args.push(self.argument(true)?);
+            self.eat(",");
+        }
+
+        // Register arguments broadcast over their elements; single qubits repeat.
+        let width = args.iter().map(Vec::len).max().unwrap_or(1);
+        if args.iter().any(|arg| arg.len() != 1 && arg.len() != width) {
+            return Err(parse_error(line, "register arguments have different sizes"));
+        }
+        for k in 0..width {
+            let qubits: Vec<usize> = args
+                .iter()
+                .map(|arg| arg[if arg.len() == 1 { 0 } else { k }])
+                .collect();
+            self.apply_gate(&name, &params, &qubits, line, 0)?;
+        }
+        Ok(())
+    }
+
+    fn apply_gate(
+        &mut self,
+        name: &str,
+        params: &[f64],
+        qubits: &[usize],
+        line: usize,
+        depth: usize,
+    ) -> Result<(), QuantumError> {
+        if depth > MAX_GATE_DEPTH {
+            return Err(parse_error(line, format!("gate '{}' is recursive", name)));
+        }
+        if let Some(param) = params.iter().find(|param| !param.is_finite()) {
+            return Err(parse_error(
+                line,
+                format!("parameter {} of gate '{}' is not finite", param, name),
+            ));
+        }
+        self.expansions += 1;
+        if self.expansions > MAX_INSTRUCTIONS {
+            return Err(parse_error(
+                line,
+                format!(
+                    "programs longer than {} instructions are not supported",
+                    MAX_INSTRUCTIONS
+                ),
+            ));
+        }
+
+        if let Some(operation) = self.library.operation(name) {
+            if !params.is_empty() || operation.num_qubits() != qubits.len() {
+                return Err(parse_error(
+                    line,
+                    format!(
+                        "custom gate '{}' expects no parameters and {} qubits",
+                        name,
+                        operation.num_qubits()
+                    ),
+                ));
+            }
+            return self.emit(Instruction::new(operation, qubits.to_vec()), line);
+        }
+
+        if let Some(definition) = self.gates.get(name).cloned() {
+            if definition.params.len() != params.len() || definition.qargs.len() != qubits.len() {
+                return Err(parse_error(
+                    line,
+                    format!("wrong number of arguments to gate '{}'", name),
+                ));
+            }
+            let env: HashMap<String, f64> = definition
+                .params
+                .iter()
+                .cloned()
+                .zip(params.iter().copied())
+                .collect();
+            for call in &definition.body {
+                let call_params = call
+                    .params
+                    .iter()
+                    .map(|expr| expr.eval(&env, call.line))
+                    .collect::<Result<Vec<f64>, _>>()?;
+                let call_qubits = call
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        definition
+                            .qargs
+                            .iter()
+                            .position(|qarg| qarg == arg)
+                            .map(|position| qubits[position])
+                            .ok_or_else(|| {
+                                parse_error(call.line, format!("unknown qubit '{}'", arg))
+                            })
+                    })
+                    .collect::<Result<Vec<usize>, _>>()?;
+                self.apply_gate(&call.name, &call_params, &call_qubits, call.line, depth + 1)?;
+            }
+            return Ok(());
+        }
+
+        match standard_gate(name, params) {
+            Some(Some(operation)) => {
+                if operation.num_qubits() != qubits.len() {
+                    return Err(parse_error(
+                        line,
+                        format!("gate '{}' expects {} qubits", name, operation.num_qubits()),
+                    ));
+                }
+                self.emit(Instruction::new(operation, qubits.to_vec()), line)
+            }
+            Some(None) => Ok(()),
+            None => Err(parse_error(
+                line,
+                format!("unknown gate '{}' with {} parameters", name, params.len()),
+            )),
+        }
+    }
+}
@@ -1,8 +1,98 @@
 //! This module defines the `Qubit` struct and its associated methods.
 
+use crate::counts::{Counts, WeightedCounts};
+use crate::error::QuantumError;
+use crate::execution::{Execution, ExecutionMode, Warning};
+use crate::gates::Gate;
+use crate::linalg;
+use crate::rng;
+use crate::tolerance;
 use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+
+/// The ascending eigenvalues of an observable and the matrix whose columns are its eigenvectors.
+type Eigenbasis = (Vec<f64>, Vec<Vec<Complex<f64>>>);
+
+/// A single-qubit Pauli measurement basis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Basis {
+    /// The `|+⟩`, `|-⟩` basis.
+    X,
+    /// The `|+i⟩`, `|-i⟩` basis.
+    Y,
+    /// The computational `|0⟩`, `|1⟩` basis.
+    Z,
+}
+
+impl Basis {
+    /// Returns the gate that maps the basis onto the computational basis, taking its `+1`
+    /// eigenstate to `|0⟩` and its `-1` eigenstate to `|1⟩`: `H` for `X`, `H·S†` for `Y` and
+    /// the identity for `Z`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::{Basis, Qubit};
+    ///
+    /// let mut plus = Qubit::plus_state(1);
+    /// Basis::X.rotation().apply(&mut plus);
+    /// assert!((plus.state[0].norm() - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn rotation(&self) -> Gate {
+        let h = 1.0 / 2.0_f64.sqrt();
+        let (zero, one, i) = (
+            Complex::new(0.0, 0.0),
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 1.0),
+        );
+        match self {
+            Basis::X => Gate::new(vec![vec![one * h, one * h], vec![one * h, -one * h]]),
+            Basis::Y => Gate::new(vec![vec![one * h, -i * h], vec![one * h, i * h]]),
+            Basis::Z => Gate::new(vec![vec![one, zero], vec![zero, one]]),
+        }
+    }
+}
+
+/// One of the four maximally entangled two-qubit Bell states.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BellState {
+    /// `(|00⟩ + |11⟩)/√2`.
+    PhiPlus,
+    /// `(|00⟩ - |11⟩)/√2`.
+    PhiMinus,
+    /// `(|01⟩ + |10⟩)/√2`.
+    PsiPlus,
+    /// `(|01⟩ - |10⟩)/√2`.
+    PsiMinus,
+}
+
+impl BellState {
+    /// All four Bell states.
+    pub const ALL: [BellState; 4] = [
+        BellState::PhiPlus,
+        BellState::PhiMinus,
+        BellState::PsiPlus,
+        BellState::PsiMinus,
+    ];
+
+    /// Returns the usual symbol of the state, e.g. `Φ+`.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BellState::PhiPlus => "Φ+",
+            BellState::PhiMinus => "Φ-",
+            BellState::PsiPlus => "Ψ+",
+            BellState::PsiMinus => "Ψ-",
+        }
+    }
+}
 
 /// A `Qubit` represents a quantum bit, which can exist in a superposition of states.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Qubit {
     pub state: Vec<Complex<f64>>,
 }
@@ -47,31 +137,286 @@ impl Qubit {
         Qubit { state }
     }
 
-    /// Measures the qubit, collapsing its state to one of the basis states.
+    /// Creates a register of `num_qubits` qubits in the `|0...0⟩` state.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The number of qubits.
     ///
     /// # Examples
     ///
     /// ```
     /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
     ///
-    /// let qubit = Qubit::new();
-    /// let result = qubit.measure();
-    /// assert!(result == 0 || result == 1);
+    /// let register = Qubit::zero_state(3);
+    /// assert_eq!(register.state.len(), 8);
+    /// assert_eq!(register.state[0], Complex::new(1.0, 0.0));
     /// ```
-    pub fn measure(&self) -> usize {
-        let prob_0 = self.state[0].norm_sqr();
-        let random_number = rand::random::<f64>();
+    pub fn zero_state(num_qubits: usize) -> Self {
+        Self::basis_state(num_qubits, 0)
+    }
+
+    /// Creates the computational basis state written as `bitstring`, most significant qubit
+    /// first like the keys of [`Counts`], so `"01"` puts qubit 0 in `|1⟩` and qubit 1 in `|0⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitstring` - One `0` or `1` per qubit, from qubit `n - 1` down to qubit 0.
+    ///
+    /// # Returns
+    ///
+    /// * The basis state, or `QuantumError::Parse` if `bitstring` contains another character or
+    ///   has more bits than a basis index holds, 63 on 64-bit targets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let register = Qubit::from_bitstring("0101").unwrap();
+    /// assert_eq!(register.num_qubits(), 4);
+    /// assert_eq!(register.state[5], Complex::new(1.0, 0.0));
+    /// assert!(Qubit::from_bitstring("01x").is_err());
+    /// assert!(Qubit::from_bitstring(&"0".repeat(64)).is_err());
+    /// ```
+    pub fn from_bitstring(bitstring: &str) -> Result<Self, QuantumError> {
+        let index = parse_bitstring(bitstring)?;
+        Ok(Self::basis_state(bitstring.chars().count(), index))
+    }
 
-        if random_number < prob_0 {
-            0
-        } else {
-            1
+    /// Creates the state whose measurement distribution is `probabilities`, with real,
+    /// non-negative amplitudes. The probabilities are normalized, so relative weights work too.
+    ///
+    /// # Arguments
+    ///
+    /// * `probabilities` - The weight of each basis state, where bit `k` of the index is qubit `k`.
+    ///
+    /// # Returns
+    ///
+    /// * The state, or an error if the length is not a power of two, a weight is negative or not
+    ///   finite, or every weight is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let register = Qubit::from_probabilities(&[3.0, 1.0]).unwrap();
+    /// assert!((register.state[0].norm_sqr() - 0.75).abs() < 1e-12);
+    /// assert!((register.state[1].norm_sqr() - 0.25).abs() < 1e-12);
+    /// assert!(Qubit::from_probabilities(&[0.5, -0.5]).is_err());
+    /// ```
+    pub fn from_probabilities(probabilities: &[f64]) -> Result<Self, QuantumError> {
+        if !probabilities.len().is_power_of_two() {
+            return Err(QuantumError::InvalidDimension {
+                dimension: probabilities.len(),
+            });
         }
+        if let Some((index, &probability)) = probabilities
+            .iter()
+            .enumerate()
+            .find(|(_, p)| !p.is_finite() || **p < 0.0)
+        {
+            return Err(QuantumError::InvalidProbability { index, probability });
+        }
+        let total: f64 = probabilities.iter().sum();
+        if total == 0.0 {
+            return Err(QuantumError::NotNormalized { norm: 0.0 });
+        }
+        Ok(Qubit::from_state(
+            probabilities
+                .iter()
+                .map(|p| Complex::new((p / total).sqrt(), 0.0))
+                .collect(),
+        ))
     }
-}
 
-impl Default for Qubit {
-    /// Creates a default instance of `Qubit`, which is initialized to the `|0⟩` state.
+    /// Creates the `|+...+⟩` state, an equal superposition of all `2^n` basis states.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The number of qubits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let register = Qubit::plus_state(2);
+    /// assert!(register.state.iter().all(|a| (a.norm_sqr() - 0.25).abs() < 1e-12));
+    /// ```
+    pub fn plus_state(num_qubits: usize) -> Self {
+        let dimension = 1 << num_qubits;
+        let amplitude = Complex::new(1.0 / (dimension as f64).sqrt(), 0.0);
+        Qubit::from_state(vec![amplitude; dimension])
+    }
+
+    /// Creates the GHZ state `(|0...0⟩ + |1...1⟩)/√2`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The number of qubits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_qubits` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let register = Qubit::ghz(3);
+    /// assert!((register.state[0].norm_sqr() - 0.5).abs() < 1e-12);
+    /// assert!((register.state[7].norm_sqr() - 0.5).abs() < 1e-12);
+    /// ```
+    pub fn ghz(num_qubits: usize) -> Self {
+        assert!(num_qubits > 0, "a GHZ state needs at least one qubit");
+        let mut probabilities = vec![0.0; 1 << num_qubits];
+        probabilities[0] = 1.0;
+        probabilities[(1 << num_qubits) - 1] = 1.0;
+        Self::from_probabilities(&probabilities).expect("the GHZ distribution is valid")
+    }
+
+    /// Creates the W state, an equal superposition of the `n` basis states with exactly one
+    /// qubit in `|1⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The number of qubits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_qubits` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let register = Qubit::w_state(3);
+    /// for index in [1, 2, 4] {
+    ///     assert!((register.state[index].norm_sqr() - 1.0 / 3.0).abs() < 1e-12);
+    /// }
+    /// ```
+    pub fn w_state(num_qubits: usize) -> Self {
+        assert!(num_qubits > 0, "a W state needs at least one qubit");
+        let mut probabilities = vec![0.0; 1 << num_qubits];
+        for qubit in 0..num_qubits {
+            probabilities[1 << qubit] = 1.0;
+        }
+        Self::from_probabilities(&probabilities).expect("the W distribution is valid")
+    }
+
+    /// Creates the Dicke state with `excitations` qubits in `|1⟩`, an equal superposition of all
+    /// basis states with that many ones; one excitation gives the W state.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The number of qubits.
+    /// * `excitations` - The number of ones in every basis state of the superposition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_qubits` is zero or `excitations` exceeds it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let register = Qubit::dicke(4, 2);
+    /// assert!((register.state[0b0101].norm_sqr() - 1.0 / 6.0).abs() < 1e-12);
+    /// assert_eq!(register.state[0b0111].norm_sqr(), 0.0);
+    /// assert_eq!(Qubit::dicke(3, 1), Qubit::w_state(3));
+    /// ```
+    pub fn dicke(num_qubits: usize, excitations: usize) -> Self {
+        assert!(num_qubits > 0, "a Dicke state needs at least one qubit");
+        assert!(
+            excitations <= num_qubits,
+            "{} excitations do not fit in {} qubits",
+            excitations,
+            num_qubits
+        );
+        let probabilities: Vec<f64> = (0..1usize << num_qubits)
+            .map(|index| f64::from(index.count_ones() as usize == excitations))
+            .collect();
+        Self::from_probabilities(&probabilities).expect("the Dicke distribution is valid")
+    }
+
+    /// Creates one of the four Bell states on two qubits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::{BellState, Qubit};
+    ///
+    /// let singlet = Qubit::bell_state(BellState::PsiMinus);
+    /// assert!((singlet.state[0b01].re - singlet.state[0b10].re.abs()).abs() < 1e-12);
+    /// assert!(singlet.state[0b10].re < 0.0);
+    /// let ghz = Qubit::ghz(2);
+    /// let phi_plus = Qubit::bell_state(BellState::PhiPlus);
+    /// assert!(phi_plus.state.iter().zip(&ghz.state).all(|(a, b)| (a - b).norm() < 1e-12));
+    /// ```
+    pub fn bell_state(which: BellState) -> Self {
+        let h = 1.0 / 2.0_f64.sqrt();
+        let (first, second, sign) = match which {
+            BellState::PhiPlus => (0b00, 0b11, 1.0),
+            BellState::PhiMinus => (0b00, 0b11, -1.0),
+            BellState::PsiPlus => (0b01, 0b10, 1.0),
+            BellState::PsiMinus => (0b01, 0b10, -1.0),
+        };
+        let mut state = vec![Complex::new(0.0, 0.0); 4];
+        state[first] = Complex::new(h, 0.0);
+        state[second] = Complex::new(sign * h, 0.0);
+        Qubit::from_state(state)
+    }
+
+    /// Creates a state drawn uniformly at random (from the Haar measure), reproducibly for a
+    /// given `seed`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The number of qubits.
+    /// * `seed` - The seed of the generator the amplitudes are drawn from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let register = Qubit::random_state(3, 42);
+    /// let norm: f64 = register.state.iter().map(|a| a.norm_sqr()).sum();
+    /// assert!((norm - 1.0).abs() < 1e-12);
+    /// assert_eq!(register, Qubit::random_state(3, 42));
+    /// ```
+    pub fn random_state(num_qubits: usize, seed: u64) -> Self {
+        // Independent complex Gaussian amplitudes, normalized, are Haar distributed; the
+        // Gaussians come from the Box-Muller transform
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state: Vec<Complex<f64>> = (0..1 << num_qubits)
+            .map(|_| {
+                let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+                Complex::from_polar(radius, 2.0 * PI * rng.gen::<f64>())
+            })
+            .collect();
+        let norm = state.iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt();
+        for amplitude in state.iter_mut() {
+            *amplitude /= norm;
+        }
+        Qubit::from_state(state)
+    }
+
+    /// Creates the basis state `index` of a `num_qubits`-qubit register.
+    fn basis_state(num_qubits: usize, index: usize) -> Self {
+        let mut state = vec![Complex::new(0.0, 0.0); 1 << num_qubits];
+        state[index] = Complex::new(1.0, 0.0);
+        Qubit::from_state(state)
+    }
+
+    /// Returns the number of qubits represented by the state vector.
     ///
     /// # Examples
     ///
@@ -79,11 +424,1274 @@ impl Default for Qubit {
     /// use quantum_simulator::qubit::Qubit;
     /// use num_complex::Complex;
     ///
-    /// let qubit = Qubit::default();
-    /// assert_eq!(qubit.state[0], Complex::new(1.0, 0.0));
-    /// assert_eq!(qubit.state[1], Complex::new(0.0, 0.0));
+    /// let qubit = Qubit::from_state(vec![Complex::new(1.0, 0.0); 8]);
+    /// assert_eq!(qubit.num_qubits(), 3);
     /// ```
-    fn default() -> Self {
-        Self::new()
+    pub fn num_qubits(&self) -> usize {
+        self.state.len().trailing_zeros() as usize
+    }
+
+    /// Returns the global phase of the state in radians: the phase of its reference amplitude,
+    /// the first one of largest magnitude.
+    ///
+    /// Picking the largest amplitude, not merely the first nonzero one, keeps the reference
+    /// stable when rounding leaves tiny amplitudes behind, so equal states up to global phase
+    /// report phases differing by exactly that phase.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let minus_i = Qubit::from_state(vec![Complex::new(0.0, -1.0), Complex::new(0.0, 0.0)]);
+    /// assert!((minus_i.global_phase() + std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+    /// ```
+    pub fn global_phase(&self) -> f64 {
+        let largest = self
+            .state
+            .iter()
+            .map(|amplitude| amplitude.norm())
+            .fold(0.0, f64::max);
+        self.state
+            .iter()
+            .find(|amplitude| amplitude.norm() >= largest - tolerance::current().equality)
+            .map_or(0.0, |amplitude| amplitude.arg())
+    }
+
+    /// Strips the global phase, making the reference amplitude of
+    /// [`global_phase`](Qubit::global_phase) real and positive. Two states equal up to global
+    /// phase have equal amplitudes once both are canonicalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// // Z·X·Z is -X, the same operation as X up to global phase
+    /// let mut x = Qubit::zero_state(1);
+    /// Circuit::new(1).x(0).run(&mut x);
+    /// let mut minus_x = Qubit::zero_state(1);
+    /// Circuit::new(1).z(0).x(0).z(0).run(&mut minus_x);
+    /// assert!(minus_x.state[1].re < 0.0);
+    ///
+    /// minus_x.canonicalize();
+    /// x.canonicalize();
+    /// assert!((minus_x.state[1] - x.state[1]).norm() < 1e-12);
+    /// ```
+    pub fn canonicalize(&mut self) {
+        let rotation = Complex::from_polar(1.0, -self.global_phase());
+        for amplitude in &mut self.state {
+            *amplitude *= rotation;
+        }
+    }
+
+    /// Writes the state in Dirac notation, e.g. `0.7071|00⟩ - 0.7071|11⟩`, with coefficients
+    /// rounded to four decimals and bitstrings written with qubit 0 last. Amplitudes, and real or
+    /// imaginary parts, below the [`equality`](crate::tolerance::Tolerances::equality) tolerance
+    /// are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::{BellState, Qubit};
+    /// use num_complex::Complex;
+    ///
+    /// assert_eq!(
+    ///     Qubit::bell_state(BellState::PhiMinus).to_dirac(),
+    ///     "0.7071|00⟩ - 0.7071|11⟩"
+    /// );
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let plus_i = Qubit::from_state(vec![Complex::new(h, 0.0), Complex::new(0.0, h)]);
+    /// assert_eq!(plus_i.to_dirac(), "0.7071|0⟩ + 0.7071i|1⟩");
+    /// ```
+    pub fn to_dirac(&self) -> String {
+        let negligible = tolerance::current().equality;
+        let num_qubits = self.num_qubits();
+        let mut dirac = String::new();
+        for (index, amplitude) in self.state.iter().enumerate() {
+            if amplitude.norm() < negligible {
+                continue;
+            }
+            let ket = format!("|{:0width$b}⟩", index, width = num_qubits);
+            let (negative, coefficient) = if amplitude.im.abs() < negligible {
+                (amplitude.re < 0.0, format!("{:.4}", amplitude.re.abs()))
+            } else if amplitude.re.abs() < negligible {
+                (amplitude.im < 0.0, format!("{:.4}i", amplitude.im.abs()))
+            } else {
+                (false, format!("({:.4}{:+.4}i)", amplitude.re, amplitude.im))
+            };
+            match (dirac.is_empty(), negative) {
+                (true, true) => dirac.push('-'),
+                (true, false) => {}
+                (false, true) => dirac.push_str(" - "),
+                (false, false) => dirac.push_str(" + "),
+            }
+            dirac.push_str(&coefficient);
+            dirac.push_str(&ket);
+        }
+        dirac
+    }
+
+    /// Returns the bytes taken by the amplitudes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// assert_eq!(Qubit::zero_state(10).memory_bytes(), 16 * 1024);
+    /// ```
+    pub fn memory_bytes(&self) -> usize {
+        self.state.len() * std::mem::size_of::<Complex<f64>>()
+    }
+
+    /// Returns the probability of every basis state, indexed like the amplitudes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::{BellState, Qubit};
+    ///
+    /// let probabilities = Qubit::bell_state(BellState::PhiPlus).probabilities();
+    /// assert!((probabilities[0b00] - 0.5).abs() < 1e-12);
+    /// assert_eq!(probabilities[0b01], 0.0);
+    /// ```
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.state
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .collect()
+    }
+
+    /// Returns the probability of measuring the basis state written as `bitstring`, most
+    /// significant qubit first like the keys of [`Counts`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bitstring` - One `0` or `1` per qubit, from qubit `n - 1` down to qubit 0.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The probability `|⟨bitstring|ψ⟩|²`.
+    /// * `Err(QuantumError::Parse)` - If `bitstring` contains another character.
+    /// * `Err(QuantumError::DimensionMismatch)` - If `bitstring` does not have one bit per qubit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut register = Qubit::zero_state(4);
+    /// Circuit::new(4).x(1).h(2).run(&mut register);
+    /// assert!((register.prob_of("0110").unwrap() - 0.5).abs() < 1e-12);
+    /// assert_eq!(register.prob_of("1000").unwrap(), 0.0);
+    /// assert!(register.prob_of("011").is_err());
+    /// ```
+    pub fn prob_of(&self, bitstring: &str) -> Result<f64, QuantumError> {
+        let length = bitstring.chars().count();
+        if length != self.num_qubits() {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.num_qubits(),
+                found: length,
+            });
+        }
+        Ok(self.state[parse_bitstring(bitstring)?].norm_sqr())
+    }
+
+    /// Returns the amplitude of basis state `index`, where bit `k` of the index is the value of
+    /// qubit `k`, or `None` if the register has no such basis state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::{BellState, Qubit};
+    ///
+    /// let bell = Qubit::bell_state(BellState::PhiMinus);
+    /// assert!(bell.amplitude_of(0b11).unwrap().re < 0.0);
+    /// assert_eq!(bell.amplitude_of(4), None);
+    /// ```
+    pub fn amplitude_of(&self, index: usize) -> Option<Complex<f64>> {
+        self.state.get(index).copied()
+    }
+
+    /// Returns the `k` most probable basis states as `(index, probability)` pairs, most probable
+    /// first and, among equally probable states, lowest index first. Fewer pairs are returned if
+    /// the register has fewer than `k` basis states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut register = Qubit::zero_state(3);
+    /// Circuit::new(3).ry(0, 1.0).cnot(0, 2).run(&mut register);
+    /// let top = register.top_k_states(2);
+    /// assert_eq!(top[0].0, 0b000);
+    /// assert_eq!(top[1].0, 0b101);
+    /// assert!((top[0].1 + top[1].1 - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn top_k_states(&self, k: usize) -> Vec<(usize, f64)> {
+        let mut states: Vec<(usize, f64)> = self.probabilities().into_iter().enumerate().collect();
+        let order = |a: &(usize, f64), b: &(usize, f64)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+        if k < states.len() {
+            // Partition around the k-th state so only the top k need sorting
+            states.select_nth_unstable_by(k, order);
+            states.truncate(k);
+        }
+        states.sort_by(order);
+        states
+    }
+
+    /// Returns the probabilities of the outcomes of measuring only `qubits`, where bit `j` of an
+    /// outcome's index is the value of `qubits[j]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubits` - The measured qubits, in the order of the outcome's bits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<f64>)` - The `2^qubits.len()` outcome probabilities.
+    /// * `Err(QuantumError::QubitOutOfRange)` - If a qubit is outside the register.
+    /// * `Err(QuantumError::DuplicateQubit)` - If a qubit is listed twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let mut register = Qubit::zero_state(3);
+    /// Circuit::new(3).h(0).cnot(0, 2).x(1).run(&mut register);
+    /// let marginal = register.marginal_probabilities(&[2, 1]).unwrap();
+    /// // Qubit 1 is always one, qubit 2 is one half of the time
+    /// assert!((marginal[0b10] - 0.5).abs() < 1e-12);
+    /// assert!((marginal[0b11] - 0.5).abs() < 1e-12);
+    /// assert!(register.marginal_probabilities(&[3]).is_err());
+    /// ```
+    pub fn marginal_probabilities(&self, qubits: &[usize]) -> Result<Vec<f64>, QuantumError> {
+        let num_qubits = self.num_qubits();
+        for (position, &qubit) in qubits.iter().enumerate() {
+            if qubit >= num_qubits {
+                return Err(QuantumError::QubitOutOfRange { qubit, num_qubits });
+            }
+            if qubits[..position].contains(&qubit) {
+                return Err(QuantumError::DuplicateQubit { qubit });
+            }
+        }
+        let mut marginal = vec![0.0; 1 << qubits.len()];
+        for (index, amplitude) in self.state.iter().enumerate() {
+            let outcome: usize = qubits
+                .iter()
+                .enumerate()
+                .map(|(bit, &qubit)| ((index >> qubit) & 1) << bit)
+                .sum();
+            marginal[outcome] += amplitude.norm_sqr();
+        }
+        Ok(marginal)
+    }
+
+    /// Samples a basis state from the full probability distribution without collapsing the state.
+    ///
+    /// The result is the index of the sampled basis state, where bit `k` of the index is the
+    /// value of qubit `k`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let qubit = Qubit::new();
+    /// let result = qubit.measure();
+    /// assert!(result == 0 || result == 1);
+    /// ```
+    pub fn measure(&self) -> usize {
+        rng::with_rng(|rng| self.measure_with_rng(rng))
+    }
+
+    /// Samples a basis state like [`Qubit::measure`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let qubit = Qubit::from_state(vec![Complex::new(h, 0.0), Complex::new(h, 0.0)]);
+    /// let first = qubit.measure_with_rng(&mut StdRng::seed_from_u64(7));
+    /// let second = qubit.measure_with_rng(&mut StdRng::seed_from_u64(7));
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn measure_with_rng<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let random_number = rng.gen::<f64>();
+        let mut cumulative = 0.0;
+
+        for (index, amplitude) in self.state.iter().enumerate() {
+            cumulative += amplitude.norm_sqr();
+            if random_number < cumulative {
+                return index;
+            }
+        }
+
+        // Rounding can leave the cumulative sum just below 1; fall back to the last populated state.
+        self.state
+            .iter()
+            .rposition(|amplitude| amplitude.norm_sqr() > 0.0)
+            .unwrap_or(0)
+    }
+
+    /// Measures a single qubit, collapsing and renormalizing the state to match the outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the qubit to measure.
+    ///
+    /// # Returns
+    ///
+    /// * The classical bit (`0` or `1`) observed on the qubit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// // (|00⟩ + |11⟩) / √2
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let mut qubit = Qubit::from_state(vec![
+    ///     Complex::new(h, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(h, 0.0),
+    /// ]);
+    /// let bit = qubit.measure_qubit(0);
+    /// // The other qubit of the Bell pair always agrees.
+    /// assert_eq!(qubit.measure_qubit(1), bit);
+    /// ```
+    pub fn measure_qubit(&mut self, index: usize) -> usize {
+        rng::with_rng(|rng| self.measure_qubit_with_rng(index, rng))
+    }
+
+    /// Measures a single qubit like [`Qubit::measure_qubit`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the qubit to measure.
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not a qubit of the register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut qubit = Qubit::new();
+    /// assert_eq!(qubit.measure_qubit_with_rng(0, &mut StdRng::seed_from_u64(1)), 0);
+    /// ```
+    pub fn measure_qubit_with_rng<R: Rng + ?Sized>(&mut self, index: usize, rng: &mut R) -> usize {
+        assert!(
+            index < self.num_qubits(),
+            "qubit {} is out of range for a {}-qubit register",
+            index,
+            self.num_qubits()
+        );
+        let prob_1 = self.probability_of_one(index);
+        let outcome = if rng.gen::<f64>() < prob_1 { 1 } else { 0 };
+        self.collapse(index, outcome);
+        outcome
+    }
+
+    /// Projects a qubit onto a chosen measurement outcome and renormalizes the state, as if the
+    /// measurement had produced that outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the qubit to postselect.
+    /// * `outcome` - The outcome to keep, `0` or `1`.
+    /// * `mode` - Whether an outcome with probability below `1e-12` is an error or is kept with
+    ///   a [`Warning::UnlikelyPostselection`].
+    ///
+    /// # Returns
+    ///
+    /// * The probability of `outcome` before projection, or `QuantumError::PostselectionFailed`
+    ///   if the outcome is impossible or, in strict mode, too unlikely. The state is unchanged on
+    ///   error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::execution::ExecutionMode;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let mut qubit = Qubit::from_state(vec![Complex::new(h, 0.0), Complex::new(h, 0.0)]);
+    /// let execution = qubit.postselect(0, 1, ExecutionMode::Strict).unwrap();
+    /// assert!((execution.value - 0.5).abs() < 1e-12);
+    /// assert_eq!(qubit.state[1], Complex::new(1.0, 0.0));
+    ///
+    /// assert!(matches!(
+    ///     qubit.postselect(0, 0, ExecutionMode::Lenient),
+    ///     Err(QuantumError::PostselectionFailed { .. })
+    /// ));
+    /// ```
+    pub fn postselect(
+        &mut self,
+        index: usize,
+        outcome: usize,
+        mode: ExecutionMode,
+    ) -> Result<Execution<f64>, QuantumError> {
+        let num_qubits = self.num_qubits();
+        if index >= num_qubits {
+            return Err(QuantumError::QubitOutOfRange {
+                qubit: index,
+                num_qubits,
+            });
+        }
+        if outcome > 1 {
+            return Err(QuantumError::Unsupported {
+                feature: format!("measurement outcome {}", outcome),
+            });
+        }
+        let prob_1 = self.probability_of_one(index);
+        let probability = if outcome == 1 { prob_1 } else { 1.0 - prob_1 };
+        let mut warnings = vec![];
+        if probability < tolerance::current().truncation {
+            let kept =
+                self.state.iter().enumerate().any(|(i, amplitude)| {
+                    (i >> index) & 1 == outcome && amplitude.norm_sqr() > 0.0
+                });
+            if mode == ExecutionMode::Strict || !kept {
+                return Err(QuantumError::PostselectionFailed {
+                    qubit: index,
+                    outcome,
+                    probability,
+                });
+            }
+            warnings.push(Warning::UnlikelyPostselection {
+                qubit: index,
+                outcome,
+                probability,
+            });
+        }
+        self.collapse(index, outcome);
+        Ok(Execution {
+            value: probability,
+            warnings,
+        })
+    }
+
+    /// Measures every qubit, collapsing the state onto the observed basis state.
+    ///
+    /// # Returns
+    ///
+    /// * The classical bits, where element `k` holds the outcome of qubit `k`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// // |10⟩: qubit 1 is set, qubit 0 is clear.
+    /// let mut qubit = Qubit::from_state(vec![
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(1.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    /// ]);
+    /// assert_eq!(qubit.measure_all(), vec![0, 1]);
+    /// ```
+    pub fn measure_all(&mut self) -> Vec<usize> {
+        rng::with_rng(|rng| self.measure_all_with_rng(rng))
+    }
+
+    /// Measures every qubit like [`Qubit::measure_all`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut qubit = Qubit::new();
+    /// assert_eq!(qubit.measure_all_with_rng(&mut StdRng::seed_from_u64(3)), vec![0]);
+    /// ```
+    pub fn measure_all_with_rng<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Vec<usize> {
+        let index = self.measure_with_rng(rng);
+        for (i, amplitude) in self.state.iter_mut().enumerate() {
+            *amplitude = if i == index {
+                Complex::new(1.0, 0.0)
+            } else {
+                Complex::new(0.0, 0.0)
+            };
+        }
+        (0..self.num_qubits()).map(|k| (index >> k) & 1).collect()
+    }
+
+    /// Measures a single qubit in a Pauli basis, collapsing it onto the observed eigenstate of
+    /// that basis.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the qubit to measure.
+    /// * `basis` - The basis to measure in.
+    ///
+    /// # Returns
+    ///
+    /// * `0` for the `+1` eigenstate of the basis (`|+⟩`, `|+i⟩` or `|0⟩`), `1` for the `-1`
+    ///   eigenstate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::{Basis, Qubit};
+    ///
+    /// // |+⟩ is certain in the X basis and stays |+⟩
+    /// let mut qubit = Qubit::plus_state(1);
+    /// assert_eq!(qubit.measure_in_basis(0, Basis::X), 0);
+    /// assert_eq!(qubit, Qubit::plus_state(1));
+    /// ```
+    pub fn measure_in_basis(&mut self, index: usize, basis: Basis) -> usize {
+        rng::with_rng(|rng| self.measure_in_basis_with_rng(index, basis, rng))
+    }
+
+    /// Measures a single qubit in a Pauli basis like [`Qubit::measure_in_basis`], drawing
+    /// randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the qubit to measure.
+    /// * `basis` - The basis to measure in.
+    /// * `rng` - The random number generator to draw from.
+    pub fn measure_in_basis_with_rng<R: Rng + ?Sized>(
+        &mut self,
+        index: usize,
+        basis: Basis,
+        rng: &mut R,
+    ) -> usize {
+        let rotation = basis.rotation();
+        rotation.apply_to(self, &[index]);
+        let outcome = self.measure_qubit_with_rng(index, rng);
+        rotation.dagger().apply_to(self, &[index]);
+        outcome
+    }
+
+    /// Measures a Hermitian observable on some of the qubits, collapsing the state onto the
+    /// eigenspace of the observed eigenvalue.
+    ///
+    /// The state is rotated into the observable's eigenbasis, the support is sampled there and
+    /// the state is rotated back, so degenerate eigenvalues keep their superpositions.
+    ///
+    /// # Arguments
+    ///
+    /// * `observable` - The observable, where bit `j` of its basis index is `support[j]`.
+    /// * `support` - The qubits the observable acts on.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The observed eigenvalue.
+    /// * `Err(QuantumError::QubitOutOfRange)` or `Err(QuantumError::DuplicateQubit)` - If the
+    ///   support is invalid.
+    /// * `Err(QuantumError::DimensionMismatch)` - If the observable does not act on
+    ///   `support.len()` qubits.
+    /// * `Err(QuantumError::Unsupported)` - If the observable is not Hermitian.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::Gate;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// // Z⊗Z has eigenvalue +1 on both |00⟩ and |11⟩, so the Bell pair is left untouched
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let (zero, one) = (Complex::new(0.0, 0.0), Complex::new(h, 0.0));
+    /// let mut bell = Qubit::from_state(vec![one, zero, zero, one]);
+    /// let zz = Gate::new(
+    ///     (0..4)
+    ///         .map(|row| {
+    ///             (0..4)
+    ///                 .map(|column| {
+    ///                     let sign = if (row as u32).count_ones() % 2 == 0 { 1.0 } else { -1.0 };
+    ///                     Complex::new(if row == column { sign } else { 0.0 }, 0.0)
+    ///                 })
+    ///                 .collect()
+    ///         })
+    ///         .collect(),
+    /// );
+    /// assert!((bell.measure_observable(&zz, &[0, 1]).unwrap() - 1.0).abs() < 1e-12);
+    /// assert!((bell.state[0].re - h).abs() < 1e-12 && (bell.state[3].re - h).abs() < 1e-12);
+    /// ```
+    pub fn measure_observable(
+        &mut self,
+        observable: &Gate,
+        support: &[usize],
+    ) -> Result<f64, QuantumError> {
+        rng::with_rng(|rng| self.measure_observable_with_rng(observable, support, rng))
+    }
+
+    /// Measures an observable like [`Qubit::measure_observable`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `observable` - The observable, where bit `j` of its basis index is `support[j]`.
+    /// * `support` - The qubits the observable acts on.
+    /// * `rng` - The random number generator to draw from.
+    pub fn measure_observable_with_rng<R: Rng + ?Sized>(
+        &mut self,
+        observable: &Gate,
+        support: &[usize],
+        rng: &mut R,
+    ) -> Result<f64, QuantumError> {
+        let (eigenvalues, eigenvectors) = self.eigenbasis(observable, support)?;
+        linalg::apply_local(&linalg::dagger(&eigenvectors), &mut self.state, support);
+        let probabilities = self.local_probabilities(support);
+
+        // Eigenvalues are ascending, so each outcome is a run of (nearly) equal ones
+        let mut outcomes: Vec<(f64, Vec<usize>, f64)> = vec![];
+        for (local, (&eigenvalue, &probability)) in
+            eigenvalues.iter().zip(&probabilities).enumerate()
+        {
+            match outcomes.last_mut() {
+                Some((value, locals, total))
+                    if eigenvalue - *value < tolerance::current().equality =>
+                {
+                    locals.push(local);
+                    *total += probability;
+                }
+                _ => outcomes.push((eigenvalue, vec![local], probability)),
+            }
+        }
+        let random_number = rng.gen::<f64>() * probabilities.iter().sum::<f64>();
+        let mut cumulative = 0.0;
+        let (value, kept, _) = outcomes
+            .iter()
+            .find(|(_, _, total)| {
+                cumulative += total;
+                random_number < cumulative
+            })
+            .or_else(|| outcomes.iter().rev().find(|(_, _, total)| *total > 0.0))
+            .expect("an observable has at least one eigenvalue");
+
+        let (offsets, mask) = linalg::local_offsets(support);
+        let mut norm = 0.0;
+        for base in (0..self.state.len()).filter(|index| index & mask == 0) {
+            for (local, offset) in offsets.iter().enumerate() {
+                if kept.contains(&local) {
+                    norm += self.state[base + offset].norm_sqr();
+                } else {
+                    self.state[base + offset] = Complex::new(0.0, 0.0);
+                }
+            }
+        }
+        let norm = norm.sqrt();
+        if norm > 0.0 {
+            for amplitude in self.state.iter_mut() {
+                *amplitude /= norm;
+            }
+        }
+        linalg::apply_local(&eigenvectors, &mut self.state, support);
+        Ok(*value)
+    }
+
+    /// Estimates the expectation value of a Hermitian observable from `shots` measurements,
+    /// without collapsing the state.
+    ///
+    /// Each shot measures the observable in its eigenbasis and records the observed eigenvalue,
+    /// as a device would, so the estimate carries shot noise of order `1/√shots`.
+    ///
+    /// # Arguments
+    ///
+    /// * `observable` - The observable, where bit `j` of its basis index is `support[j]`.
+    /// * `support` - The qubits the observable acts on.
+    /// * `shots` - The number of measurements to average.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The mean observed eigenvalue, `0` for no shots.
+    /// * `Err(QuantumError)` - If the observable or support is invalid, see
+    ///   [`Qubit::measure_observable`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let estimate = Qubit::plus_state(1).estimate_observable(&pauli_x(), &[0], 100).unwrap();
+    /// assert!((estimate - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn estimate_observable(
+        &self,
+        observable: &Gate,
+        support: &[usize],
+        shots: usize,
+    ) -> Result<f64, QuantumError> {
+        rng::with_rng(|rng| self.estimate_observable_with_rng(observable, support, shots, rng))
+    }
+
+    /// Estimates an expectation value like [`Qubit::estimate_observable`], drawing randomness
+    /// from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `observable` - The observable, where bit `j` of its basis index is `support[j]`.
+    /// * `support` - The qubits the observable acts on.
+    /// * `shots` - The number of measurements to average.
+    /// * `rng` - The random number generator to draw from.
+    pub fn estimate_observable_with_rng<R: Rng + ?Sized>(
+        &self,
+        observable: &Gate,
+        support: &[usize],
+        shots: usize,
+        rng: &mut R,
+    ) -> Result<f64, QuantumError> {
+        let (eigenvalues, eigenvectors) = self.eigenbasis(observable, support)?;
+        if shots == 0 {
+            return Ok(0.0);
+        }
+        let mut rotated = self.clone();
+        linalg::apply_local(&linalg::dagger(&eigenvectors), &mut rotated.state, support);
+        let mut cumulative = Vec::with_capacity(eigenvalues.len());
+        let mut total = 0.0;
+        for probability in rotated.local_probabilities(support) {
+            total += probability;
+            cumulative.push(total);
+        }
+        let sum: f64 = (0..shots)
+            .map(|_| {
+                let random_number = rng.gen::<f64>() * total;
+                let local = cumulative
+                    .partition_point(|&c| c <= random_number)
+                    .min(eigenvalues.len() - 1);
+                eigenvalues[local]
+            })
+            .sum();
+        Ok(sum / shots as f64)
+    }
+
+    /// Samples the full register `shots` times without collapsing the state.
+    ///
+    /// The cumulative distribution is built once, so each shot costs a single binary search.
+    ///
+    /// # Arguments
+    ///
+    /// * `shots` - The number of samples to draw.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    ///
+    /// let counts = Qubit::new().sample(100);
+    /// assert_eq!(counts.get("0"), 100);
+    /// ```
+    pub fn sample(&self, shots: usize) -> Counts {
+        rng::with_rng(|rng| self.sample_with_rng(shots, rng))
+    }
+
+    /// Samples the register like [`Qubit::sample`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `shots` - The number of samples to draw.
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let qubit = Qubit::from_state(vec![Complex::new(h, 0.0), Complex::new(h, 0.0)]);
+    /// let a = qubit.sample_with_rng(50, &mut StdRng::seed_from_u64(42));
+    /// let b = qubit.sample_with_rng(50, &mut StdRng::seed_from_u64(42));
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn sample_with_rng<R: Rng + ?Sized>(&self, shots: usize, rng: &mut R) -> Counts {
+        let num_qubits = self.num_qubits();
+        let cumulative: Vec<f64> = self
+            .state
+            .iter()
+            .scan(0.0, |total, amplitude| {
+                *total += amplitude.norm_sqr();
+                Some(*total)
+            })
+            .collect();
+        let total = cumulative.last().copied().unwrap_or(0.0);
+
+        let mut counts = Counts::new();
+        for _ in 0..shots {
+            let target = rng.gen::<f64>() * total;
+            let index = cumulative
+                .partition_point(|&c| c <= target)
+                .min(self.state.len() - 1);
+            counts.record(index, num_qubits);
+        }
+        counts
+    }
+
+    /// Samples the register from a biased distribution `q(x) ∝ p(x) · bias(x)` and records the
+    /// importance weight `p(x) / q(x)` of every shot, without collapsing the state.
+    ///
+    /// A `bias` of `0` on some outcomes restricts sampling to the remaining ones, e.g. a fixed
+    /// parity sector, so rare outcomes are resolved with far fewer shots while
+    /// [`WeightedCounts::estimate`] stays an unbiased estimate of their true probability.
+    ///
+    /// # Arguments
+    ///
+    /// * `shots` - The number of samples to draw.
+    /// * `bias` - The non-negative factor by which each basis-state index is favoured.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bias` is negative, or zero on every outcome with non-zero probability.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// // |1⟩ has probability 1e-6, far too rare to see in 100 unbiased shots
+    /// let qubit = Qubit::from_state(vec![
+    ///     Complex::new((1.0 - 1e-6_f64).sqrt(), 0.0),
+    ///     Complex::new(1e-3, 0.0),
+    /// ]);
+    /// let counts = qubit.sample_weighted(100, |index| (index == 1) as u8 as f64);
+    /// assert_eq!(counts.counts().get("1"), 100);
+    /// assert!((counts.estimate("1") - 1e-6).abs() < 1e-12);
+    /// ```
+    pub fn sample_weighted(&self, shots: usize, bias: impl Fn(usize) -> f64) -> WeightedCounts {
+        rng::with_rng(|rng| self.sample_weighted_with_rng(shots, bias, rng))
+    }
+
+    /// Samples the register like [`Qubit::sample_weighted`], drawing randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `shots` - The number of samples to draw.
+    /// * `bias` - The non-negative factor by which each basis-state index is favoured.
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bias` is negative, or zero on every outcome with non-zero probability.
+    pub fn sample_weighted_with_rng<R: Rng + ?Sized>(
+        &self,
+        shots: usize,
+        bias: impl Fn(usize) -> f64,
+        rng: &mut R,
+    ) -> WeightedCounts {
+        let num_qubits = self.num_qubits();
+        let biases: Vec<f64> = (0..self.state.len()).map(bias).collect();
+        assert!(
+            biases.iter().all(|&b| b >= 0.0),
+            "sampling bias must be non-negative"
+        );
+        let norm: f64 = self.state.iter().map(|a| a.norm_sqr()).sum();
+        let cumulative: Vec<f64> = self
+            .state
+            .iter()
+            .zip(&biases)
+            .scan(0.0, |total, (amplitude, b)| {
+                *total += amplitude.norm_sqr() * b;
+                Some(*total)
+            })
+            .collect();
+        let total = cumulative.last().copied().unwrap_or(0.0);
+        assert!(total > 0.0, "sampling bias excludes every possible outcome");
+
+        let mut counts = WeightedCounts::new();
+        for _ in 0..shots {
+            let target = rng.gen::<f64>() * total;
+            let index = cumulative
+                .partition_point(|&c| c <= target)
+                .min(self.state.len() - 1);
+            counts.record(index, num_qubits, total / norm / biases[index]);
+        }
+        counts
+    }
+
+    /// Relabels the qubits of the register, moving qubit `i` to position `permutation[i]`.
+    ///
+    /// Basis indices are rewritten a byte at a time through precomputed lookup tables, so the
+    /// cost is a few table lookups per amplitude regardless of the permutation.
+    ///
+    /// # Arguments
+    ///
+    /// * `permutation` - The new position of every qubit; must be a permutation of `0..n`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`, or an error if `permutation` has the wrong length, an entry outside the
+    ///   register or a repeated entry. The state is unchanged on error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::StateVector;
+    /// use num_complex::Complex;
+    ///
+    /// // |001⟩ becomes |100⟩ when qubit 0 moves to position 2
+    /// let mut state = vec![Complex::new(0.0, 0.0); 8];
+    /// state[0b001] = Complex::new(1.0, 0.0);
+    /// let mut register = StateVector::from_state(state);
+    /// register.permute_qubits(&[2, 0, 1]).unwrap();
+    /// assert_eq!(register.state[0b100], Complex::new(1.0, 0.0));
+    /// ```
+    pub fn permute_qubits(&mut self, permutation: &[usize]) -> Result<(), QuantumError> {
+        let num_qubits = self.num_qubits();
+        if permutation.len() != num_qubits {
+            return Err(QuantumError::DimensionMismatch {
+                expected: num_qubits,
+                found: permutation.len(),
+            });
+        }
+        for (position, &qubit) in permutation.iter().enumerate() {
+            if qubit >= num_qubits {
+                return Err(QuantumError::QubitOutOfRange { qubit, num_qubits });
+            }
+            if permutation[..position].contains(&qubit) {
+                return Err(QuantumError::DuplicateQubit { qubit });
+            }
+        }
+
+        let tables = bit_permutation_tables(permutation);
+        let mut permuted = vec![Complex::new(0.0, 0.0); self.state.len()];
+        for (index, amplitude) in self.state.iter().enumerate() {
+            let target = tables
+                .iter()
+                .enumerate()
+                .map(|(chunk, table)| table[(index >> (8 * chunk)) & 0xff])
+                .fold(0, |target, bits| target | bits);
+            permuted[target] = *amplitude;
+        }
+        self.state = permuted;
+        Ok(())
+    }
+
+    /// Zeroes every amplitude whose magnitude is below `threshold` and renormalizes the rest.
+    ///
+    /// If every amplitude is below `threshold` nothing would be left to renormalize, so the
+    /// state is left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The smallest amplitude magnitude that is kept.
+    ///
+    /// # Returns
+    ///
+    /// * The total probability that was discarded, `0.0` if the state was left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let mut qubit = Qubit::from_state(vec![Complex::new(0.999_f64.sqrt(), 0.0), Complex::new(0.001_f64.sqrt(), 0.0)]);
+    /// let discarded = qubit.truncate(0.1);
+    /// assert!((discarded - 0.001).abs() < 1e-12);
+    /// assert_eq!(qubit.state[0], Complex::new(1.0, 0.0));
+    ///
+    /// // A threshold above every amplitude keeps the state as it was
+    /// let mut plus = Qubit::plus_state(1);
+    /// assert_eq!(plus.truncate(0.9), 0.0);
+    /// assert_eq!(plus, Qubit::plus_state(1));
+    /// ```
+    pub fn truncate(&mut self, threshold: f64) -> f64 {
+        let kept: f64 = self
+            .state
+            .iter()
+            .filter(|amplitude| amplitude.norm() >= threshold)
+            .map(|amplitude| amplitude.norm_sqr())
+            .sum();
+        if kept == 0.0 {
+            return 0.0;
+        }
+        let mut discarded = 0.0;
+        for amplitude in self.state.iter_mut() {
+            if amplitude.norm() < threshold {
+                discarded += amplitude.norm_sqr();
+                *amplitude = Complex::new(0.0, 0.0);
+            }
+        }
+        if discarded > 0.0 {
+            let norm = kept.sqrt();
+            for amplitude in self.state.iter_mut() {
+                *amplitude /= norm;
+            }
+        }
+        discarded
+    }
+
+    /// Extracts the state of the given qubits when they are unentangled with the rest.
+    ///
+    /// Bit `j` of the returned state's basis index corresponds to `qubits[j]`. The subsystem is
+    /// considered unentangled when the purity `Tr(ρ²)` of its reduced state is within the
+    /// [`normalization`](crate::tolerance::Tolerances::normalization) tolerance of one; the
+    /// global phase of the result is arbitrary.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubits` - The register qubits making up the subsystem.
+    ///
+    /// # Returns
+    ///
+    /// * The subsystem state, or `QuantumError::EntangledSubsystem` if it is entangled with the
+    ///   remaining qubits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::StateVector;
+    /// use num_complex::Complex;
+    ///
+    /// // |1⟩ on qubit 0, |+⟩ on qubit 1.
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let state = StateVector::from_state(vec![
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(h, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(h, 0.0),
+    /// ]);
+    /// let ancilla = state.extract_subsystem(&[0]).unwrap();
+    /// assert!((ancilla.state[1].norm() - 1.0).abs() < 1e-10);
+    ///
+    /// // A Bell pair cannot be split.
+    /// let bell = StateVector::from_state(vec![
+    ///     Complex::new(h, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(h, 0.0),
+    /// ]);
+    /// assert!(bell.extract_subsystem(&[0]).is_err());
+    /// ```
+    pub fn extract_subsystem(&self, qubits: &[usize]) -> Result<Qubit, QuantumError> {
+        let num_qubits = self.num_qubits();
+        for (position, &qubit) in qubits.iter().enumerate() {
+            if qubit >= num_qubits {
+                return Err(QuantumError::QubitOutOfRange { qubit, num_qubits });
+            }
+            if qubits[..position].contains(&qubit) {
+                return Err(QuantumError::DuplicateQubit { qubit });
+            }
+        }
+
+        // Reshape the state into a matrix with one row per subsystem basis state.
+        let sub_dim = 1 << qubits.len();
+        let mut rows = vec![Vec::with_capacity(self.state.len() / sub_dim); sub_dim];
+        for (index, amplitude) in self.state.iter().enumerate() {
+            let local = qubits
+                .iter()
+                .enumerate()
+                .filter(|(_, &qubit)| (index >> qubit) & 1 == 1)
+                .map(|(j, _)| 1 << j)
+                .sum::<usize>();
+            rows[local].push(*amplitude);
+        }
+
+        let norm: f64 = self.state.iter().map(|a| a.norm_sqr()).sum();
+        let mut purity = 0.0;
+        for a in &rows {
+            for b in &rows {
+                let rho_ab: Complex<f64> = a.iter().zip(b).map(|(x, y)| x * y.conj()).sum();
+                purity += rho_ab.norm_sqr();
+            }
+        }
+        let purity = purity / (norm * norm);
+        if purity < 1.0 - tolerance::current().normalization {
+            return Err(QuantumError::EntangledSubsystem { purity });
+        }
+
+        // For a product state every column is proportional to the subsystem state.
+        let column = (0..rows[0].len())
+            .max_by(|&i, &j| {
+                let weight = |k: usize| rows.iter().map(|row| row[k].norm_sqr()).sum::<f64>();
+                weight(i).total_cmp(&weight(j))
+            })
+            .unwrap_or(0);
+        let state: Vec<Complex<f64>> = rows.iter().map(|row| row[column]).collect();
+        let column_norm = state.iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt();
+        Ok(Qubit::from_state(
+            state.into_iter().map(|a| a / column_norm).collect(),
+        ))
+    }
+
+    /// Checks an observable on `support` and returns its ascending eigenvalues and the matrix
+    /// whose columns are the matching eigenvectors.
+    fn eigenbasis(&self, observable: &Gate, support: &[usize]) -> Result<Eigenbasis, QuantumError> {
+        let num_qubits = self.num_qubits();
+        for (position, &qubit) in support.iter().enumerate() {
+            if qubit >= num_qubits {
+                return Err(QuantumError::QubitOutOfRange { qubit, num_qubits });
+            }
+            if support[..position].contains(&qubit) {
+                return Err(QuantumError::DuplicateQubit { qubit });
+            }
+        }
+        let dimension = 1 << support.len();
+        if observable.matrix.len() != dimension
+            || observable.matrix.iter().any(|row| row.len() != dimension)
+        {
+            return Err(QuantumError::DimensionMismatch {
+                expected: dimension,
+                found: observable.matrix.len(),
+            });
+        }
+        if linalg::max_deviation(&observable.matrix, &linalg::dagger(&observable.matrix))
+            > tolerance::current().equality
+        {
+            return Err(QuantumError::Unsupported {
+                feature: "measuring a non-Hermitian observable".to_string(),
+            });
+        }
+        Ok(linalg::eigh(&observable.matrix))
+    }
+
+    /// Returns the marginal probabilities of the local basis states of `support`, where bit `j`
+    /// of the local index is `support[j]`.
+    fn local_probabilities(&self, support: &[usize]) -> Vec<f64> {
+        let (offsets, mask) = linalg::local_offsets(support);
+        let mut probabilities = vec![0.0; offsets.len()];
+        for base in (0..self.state.len()).filter(|index| index & mask == 0) {
+            for (probability, offset) in probabilities.iter_mut().zip(&offsets) {
+                *probability += self.state[base + offset].norm_sqr();
+            }
+        }
+        probabilities
+    }
+
+    /// Returns the marginal probability of observing `1` on the given qubit.
+    pub(crate) fn probability_of_one(&self, index: usize) -> f64 {
+        let mask = 1 << index;
+        self.state
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i & mask != 0)
+            .map(|(_, amplitude)| amplitude.norm_sqr())
+            .sum()
+    }
+
+    /// Projects the given qubit onto `outcome` and renormalizes the remaining amplitudes.
+    pub(crate) fn collapse(&mut self, index: usize, outcome: usize) {
+        let mask = 1 << index;
+        let mut norm = 0.0;
+        for (i, amplitude) in self.state.iter_mut().enumerate() {
+            if ((i & mask) != 0) as usize != outcome {
+                *amplitude = Complex::new(0.0, 0.0);
+            } else {
+                norm += amplitude.norm_sqr();
+            }
+        }
+
+        let norm = norm.sqrt();
+        if norm > 0.0 {
+            for amplitude in self.state.iter_mut() {
+                *amplitude /= norm;
+            }
+        }
+    }
+}
+
+/// Builds one lookup table per byte of a basis index, mapping the byte's bits to their permuted
+/// positions so that bit `i` lands on bit `permutation[i]`.
+fn bit_permutation_tables(permutation: &[usize]) -> Vec<[usize; 256]> {
+    permutation
+        .chunks(8)
+        .map(|chunk| {
+            let mut table = [0; 256];
+            for (byte, entry) in table.iter_mut().enumerate() {
+                *entry = chunk
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| (byte >> bit) & 1 == 1)
+                    .map(|(_, &target)| 1 << target)
+                    .sum();
+            }
+            table
+        })
+        .collect()
+}
+
+/// A `StateVector` is the full amplitude vector of a multi-qubit register.
+///
+/// `Qubit` already stores the whole register, so this is an alias that reads better at call sites
+/// dealing with more than one qubit.
+pub type StateVector = Qubit;
+
+impl Default for Qubit {
+    /// Creates a default instance of `Qubit`, which is initialized to the `|0⟩` state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use num_complex::Complex;
+    ///
+    /// let qubit = Qubit::default();
+    /// assert_eq!(qubit.state[0], Complex::new(1.0, 0.0));
+    /// assert_eq!(qubit.state[1], Complex::new(0.0, 0.0));
+    /// ```
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the basis index written as `bitstring`, most significant qubit first, or
+/// `QuantumError::Parse` if it has another character or more bits than an index holds.
+fn parse_bitstring(bitstring: &str) -> Result<usize, QuantumError> {
+    let length = bitstring.chars().count();
+    if length >= usize::BITS as usize {
+        return Err(QuantumError::Parse {
+            line: 1,
+            message: format!(
+                "bitstring of {} bits is longer than the {} a basis index holds",
+                length,
+                usize::BITS - 1
+            ),
+        });
+    }
+    let mut index = 0;
+    for (position, bit) in bitstring.chars().enumerate() {
+        let value = match bit {
+            '0' => 0,
+            '1' => 1,
+            _ => {
+                return Err(QuantumError::Parse {
+                    line: 1,
+                    message: format!("invalid bit {:?} at position {}", bit, position),
+                })
+            }
+        };
+        index = (index << 1) | value;
     }
+    Ok(index)
 }
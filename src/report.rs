@@ -0,0 +1,635 @@
+//! This module assembles everything known about one run of a circuit, i.e. its diagram,
+//! resource counts, noise settings, measured counts, fidelity metrics and timings, into a single
+//! self-contained HTML or Markdown report.
+//!
+//! The HTML report inlines its styles and draws the histogram as inline SVG, so it can be opened
+//! or shared as one file without any network access.
+//!
+//! For post-processing, e.g. in a Python notebook, a [`ResultExport`] writes the raw results of
+//! a run instead: the final amplitudes, the probability of every basis state, the Bloch vector
+//! of every qubit and the shot counts, as JSON or CSV.
+
+use crate::analysis::bloch_vector;
+use crate::circuit::{Circuit, Operation};
+use crate::counts::Counts;
+use crate::noise::NoiseModel;
+use crate::qubit::Qubit;
+use crate::simulator::SimulationResult;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Width of a bar's label column and of the longest bar of the SVG histogram, in pixels.
+const LABEL_WIDTH: usize = 120;
+const BAR_WIDTH: usize = 400;
+
+/// Height of one bar of the SVG histogram, in pixels.
+const BAR_HEIGHT: usize = 18;
+
+/// Number of characters of the longest bar of the Markdown histogram.
+const TEXT_BAR_WIDTH: usize = 40;
+
+/// The file format of a report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// Returns the format matching a file's extension: Markdown for `.md` and `.markdown`, HTML
+    /// otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::report::ReportFormat;
+    /// use std::path::Path;
+    ///
+    /// assert_eq!(ReportFormat::from_path(Path::new("run.md")), ReportFormat::Markdown);
+    /// assert_eq!(ReportFormat::from_path(Path::new("run.html")), ReportFormat::Html);
+    /// ```
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension)
+                if extension.eq_ignore_ascii_case("md")
+                    || extension.eq_ignore_ascii_case("markdown") =>
+            {
+                ReportFormat::Markdown
+            }
+            _ => ReportFormat::Html,
+        }
+    }
+}
+
+/// An `ExperimentReport` collects the results of one run and renders them as a document.
+#[derive(Clone, Debug)]
+pub struct ExperimentReport {
+    title: String,
+    circuit: Circuit,
+    noise: Option<NoiseModel>,
+    counts: Option<Counts>,
+    reference: Option<BTreeMap<String, f64>>,
+    metrics: Vec<(String, f64)>,
+    timings: Vec<(String, Duration)>,
+}
+
+impl ExperimentReport {
+    /// Creates a report on a circuit with nothing but its diagram and resource counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The heading of the report.
+    /// * `circuit` - The circuit that was run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::report::ExperimentReport;
+    /// use std::collections::BTreeMap;
+    /// use std::time::Duration;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// let mut state = Qubit::zero_state(2);
+    /// circuit.run(&mut state);
+    /// let counts = state.sample(100);
+    /// let ideal = BTreeMap::from([("00".to_string(), 0.5), ("11".to_string(), 0.5)]);
+    ///
+    /// let report = ExperimentReport::new("Bell pair", &circuit)
+    ///     .counts(&counts)
+    ///     .reference(ideal)
+    ///     .timing("sampling", Duration::from_millis(3));
+    /// let markdown = report.to_markdown();
+    /// assert!(markdown.starts_with("# Bell pair"));
+    /// assert!(markdown.contains("| depth | 2 |"));
+    /// assert!(markdown.contains("Hellinger fidelity"));
+    /// assert!(report.to_html().contains("<svg"));
+    /// ```
+    pub fn new(title: &str, circuit: &Circuit) -> Self {
+        ExperimentReport {
+            title: title.to_string(),
+            circuit: circuit.clone(),
+            noise: None,
+            counts: None,
+            reference: None,
+            metrics: vec![],
+            timings: vec![],
+        }
+    }
+
+    /// Adds the noise model the circuit ran under.
+    pub fn noise(mut self, model: &NoiseModel) -> Self {
+        self.noise = Some(model.clone());
+        self
+    }
+
+    /// Adds the measured counts, shown as a histogram.
+    pub fn counts(mut self, counts: &Counts) -> Self {
+        self.counts = Some(counts.clone());
+        self
+    }
+
+    /// Adds the expected outcome probabilities, e.g. of a noiseless run, keyed like the counts.
+    /// Together with the counts they give the Hellinger fidelity and the total variation
+    /// distance of the measured distribution.
+    pub fn reference(mut self, probabilities: BTreeMap<String, f64>) -> Self {
+        self.reference = Some(probabilities);
+        self
+    }
+
+    /// Adds a named figure of merit, e.g. a state fidelity computed by the caller.
+    pub fn metric(mut self, name: &str, value: f64) -> Self {
+        self.metrics.push((name.to_string(), value));
+        self
+    }
+
+    /// Adds how long a stage of the run took.
+    pub fn timing(mut self, stage: &str, duration: Duration) -> Self {
+        self.timings.push((stage.to_string(), duration));
+        self
+    }
+
+    /// Renders the report in the given format.
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Html => self.to_html(),
+            ReportFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// Renders the report as Markdown, with the diagram in a code block and the histogram as a
+    /// table of text bars.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# {}\n\n## Circuit\n\n```\n{}```\n",
+            self.title,
+            self.circuit.draw()
+        );
+
+        out.push_str("\n## Resources\n\n| resource | value |\n| --- | --- |\n");
+        for (name, value) in self.resources() {
+            out.push_str(&format!("| {} | {} |\n", name, value));
+        }
+
+        out.push_str("\n## Noise\n\n");
+        for line in self.noise_description().lines() {
+            out.push_str(&format!("- {}\n", line));
+        }
+
+        if let Some(counts) = &self.counts {
+            out.push_str(&format!(
+                "\n## Counts\n\n{} shots\n\n| outcome | count | |\n| --- | --- | --- |\n",
+                counts.shots()
+            ));
+            let most = counts.most_frequent().map_or(1, |(_, count)| count).max(1);
+            for (bitstring, count) in counts.iter() {
+                out.push_str(&format!(
+                    "| `{}` | {} | {} |\n",
+                    bitstring,
+                    count,
+                    "█".repeat((count * TEXT_BAR_WIDTH).div_ceil(most))
+                ));
+            }
+        }
+
+        let metrics = self.all_metrics();
+        if !metrics.is_empty() {
+            out.push_str("\n## Metrics\n\n| metric | value |\n| --- | --- |\n");
+            for (name, value) in metrics {
+                out.push_str(&format!("| {} | {:.6} |\n", name, value));
+            }
+        }
+
+        if !self.timings.is_empty() {
+            out.push_str("\n## Timing\n\n| stage | time |\n| --- | --- |\n");
+            for (stage, duration) in &self.timings {
+                out.push_str(&format!("| {} | {} |\n", stage, format_duration(*duration)));
+            }
+        }
+        out
+    }
+
+    /// Renders the report as a standalone HTML page with inline styles and an SVG histogram.
+    pub fn to_html(&self) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>\n\
+             body {{ font-family: sans-serif; max-width: 960px; margin: 2em auto; color: #222; }}\n\
+             pre {{ background: #f4f4f4; padding: 1em; overflow-x: auto; }}\n\
+             table {{ border-collapse: collapse; }}\n\
+             td, th {{ border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }}\n\
+             </style>\n</head>\n<body>\n<h1>{0}</h1>\n<h2>Circuit</h2>\n<pre>{1}</pre>\n",
+            escape(&self.title),
+            escape(&self.circuit.draw())
+        );
+
+        out.push_str("<h2>Resources</h2>\n");
+        out.push_str(&html_table(
+            ("resource", "value"),
+            self.resources()
+                .into_iter()
+                .map(|(name, value)| (name, value.to_string())),
+        ));
+
+        out.push_str("<h2>Noise</h2>\n<ul>\n");
+        for line in self.noise_description().lines() {
+            out.push_str(&format!("<li>{}</li>\n", escape(line)));
+        }
+        out.push_str("</ul>\n");
+
+        if let Some(counts) = &self.counts {
+            out.push_str(&format!(
+                "<h2>Counts</h2>\n<p>{} shots</p>\n{}",
+                counts.shots(),
+                histogram_svg(counts)
+            ));
+        }
+
+        let metrics = self.all_metrics();
+        if !metrics.is_empty() {
+            out.push_str("<h2>Metrics</h2>\n");
+            out.push_str(&html_table(
+                ("metric", "value"),
+                metrics
+                    .into_iter()
+                    .map(|(name, value)| (name, format!("{:.6}", value))),
+            ));
+        }
+
+        if !self.timings.is_empty() {
+            out.push_str("<h2>Timing</h2>\n");
+            out.push_str(&html_table(
+                ("stage", "time"),
+                self.timings
+                    .iter()
+                    .map(|(stage, duration)| (stage.clone(), format_duration(*duration))),
+            ));
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    /// Returns the circuit's size: register widths, instruction counts, depth and the number of
+    /// instructions of every kind.
+    fn resources(&self) -> Vec<(String, usize)> {
+        let instructions = self.circuit.instructions();
+        let gates = instructions
+            .iter()
+            .filter(|instruction| instruction.operation.is_unitary());
+        let mut resources = vec![
+            ("qubits".to_string(), self.circuit.num_qubits()),
+            ("classical bits".to_string(), self.circuit.num_clbits()),
+            ("instructions".to_string(), instructions.len()),
+            ("gates".to_string(), self.circuit.gate_count()),
+            (
+                "multi-qubit gates".to_string(),
+                gates
+                    .filter(|instruction| instruction.qubits.len() > 1)
+                    .count(),
+            ),
+            (
+                "measurements".to_string(),
+                instructions
+                    .iter()
+                    .filter(|instruction| matches!(instruction.operation, Operation::Measure(_)))
+                    .count(),
+            ),
+            ("depth".to_string(), self.circuit.depth()),
+        ];
+        resources.extend(
+            self.circuit
+                .count_ops()
+                .into_iter()
+                .map(|(name, count)| (format!("`{}`", name), count)),
+        );
+        resources
+    }
+
+    /// Returns one line per noise setting.
+    fn noise_description(&self) -> String {
+        match &self.noise {
+            Some(model) => model.to_string(),
+            None => "noiseless".to_string(),
+        }
+    }
+
+    /// Returns the caller's metrics followed by the ones derived from the counts and reference.
+    fn all_metrics(&self) -> Vec<(String, f64)> {
+        let mut metrics = self.metrics.clone();
+        if let (Some(counts), Some(reference)) = (&self.counts, &self.reference) {
+            let measured = counts.frequencies();
+            let outcomes: Vec<&String> = measured.keys().chain(reference.keys()).collect();
+            let probability =
+                |map: &BTreeMap<String, f64>, key: &String| map.get(key).copied().unwrap_or(0.0);
+            let overlap: f64 = reference
+                .iter()
+                .map(|(key, p)| (p * probability(&measured, key)).sqrt())
+                .sum();
+            let mut distance = 0.0;
+            let mut seen = std::collections::BTreeSet::new();
+            for key in outcomes {
+                if seen.insert(key) {
+                    distance += (probability(&measured, key) - probability(reference, key)).abs();
+                }
+            }
+            metrics.push(("Hellinger fidelity".to_string(), overlap * overlap));
+            metrics.push(("total variation distance".to_string(), distance / 2.0));
+        }
+        metrics
+    }
+}
+
+/// The file format of exported results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One object with `num_qubits`, `amplitudes`, `probabilities`, `bloch_vectors` and
+    /// `counts` fields.
+    Json,
+    /// One `quantity,key,value` row per number, which pivots into a table in one step.
+    Csv,
+}
+
+impl ExportFormat {
+    /// Returns the format matching a file's extension: CSV for `.csv`, JSON otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::report::ExportFormat;
+    /// use std::path::Path;
+    ///
+    /// assert_eq!(ExportFormat::from_path(Path::new("run.csv")), ExportFormat::Csv);
+    /// assert_eq!(ExportFormat::from_path(Path::new("run.json")), ExportFormat::Json);
+    /// ```
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => ExportFormat::Csv,
+            _ => ExportFormat::Json,
+        }
+    }
+}
+
+/// A `ResultExport` holds the final state and shot counts of one run, ready to be written to a
+/// file.
+///
+/// Basis states are labelled by bitstrings with qubit 0 as the last character, like the keys of
+/// [`Counts`].
+#[derive(Clone, Debug)]
+pub struct ResultExport {
+    state: Qubit,
+    counts: Option<Counts>,
+}
+
+impl ResultExport {
+    /// Creates an export of a final state without shot counts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::report::ResultExport;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// let mut state = Qubit::zero_state(2);
+    /// circuit.run(&mut state);
+    /// let counts = state.sample(100);
+    ///
+    /// let export = ResultExport::new(&state).counts(&counts);
+    /// let json = export.to_json();
+    /// assert!(json.contains("\"bloch_vectors\""));
+    /// assert!(export.to_csv().starts_with("quantity,key,value\nnum_qubits,,2\n"));
+    /// ```
+    pub fn new(state: &Qubit) -> Self {
+        ResultExport {
+            state: state.clone(),
+            counts: None,
+        }
+    }
+
+    /// Adds the shot counts of the run.
+    pub fn counts(mut self, counts: &Counts) -> Self {
+        self.counts = Some(counts.clone());
+        self
+    }
+
+    /// Renders the results in the given format.
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Json => self.to_json(),
+            ExportFormat::Csv => self.to_csv(),
+        }
+    }
+
+    /// Renders the results as a pretty-printed JSON object. Amplitudes are `[re, im]` pairs in
+    /// basis order, probabilities and counts map bitstrings to numbers, and Bloch vectors are
+    /// `[x, y, z]` triples in qubit order.
+    pub fn to_json(&self) -> String {
+        let amplitudes: Vec<Value> = self
+            .state
+            .state
+            .iter()
+            .map(|amplitude| json!([amplitude.re, amplitude.im]))
+            .collect();
+        let probabilities: Map<String, Value> = self
+            .probabilities()
+            .map(|(label, probability)| (label, json!(probability)))
+            .collect();
+        let bloch_vectors: Vec<Value> = self
+            .bloch_vectors()
+            .map(|(x, y, z)| json!([x, y, z]))
+            .collect();
+        let counts: Map<String, Value> = self
+            .counts
+            .iter()
+            .flat_map(|counts| counts.iter())
+            .map(|(bitstring, count)| (bitstring.to_string(), json!(count)))
+            .collect();
+        let root = json!({
+            "num_qubits": self.state.num_qubits(),
+            "amplitudes": amplitudes,
+            "probabilities": probabilities,
+            "bloch_vectors": bloch_vectors,
+            "counts": counts,
+        });
+        let mut out = serde_json::to_string_pretty(&root).expect("results are valid JSON");
+        out.push('\n');
+        out
+    }
+
+    /// Renders the results as CSV rows `quantity,key,value`, where the key is a bitstring for
+    /// `amplitude_re`, `amplitude_im`, `probability` and `count` rows and a qubit index for
+    /// `bloch_x`, `bloch_y` and `bloch_z` rows.
+    pub fn to_csv(&self) -> String {
+        let mut out = format!(
+            "quantity,key,value\nnum_qubits,,{}\n",
+            self.state.num_qubits()
+        );
+        for (index, amplitude) in self.state.state.iter().enumerate() {
+            let label = self.label(index);
+            out.push_str(&format!("amplitude_re,{},{}\n", label, amplitude.re));
+            out.push_str(&format!("amplitude_im,{},{}\n", label, amplitude.im));
+        }
+        for (label, probability) in self.probabilities() {
+            out.push_str(&format!("probability,{},{}\n", label, probability));
+        }
+        for (qubit, (x, y, z)) in self.bloch_vectors().enumerate() {
+            out.push_str(&format!("bloch_x,{},{}\n", qubit, x));
+            out.push_str(&format!("bloch_y,{},{}\n", qubit, y));
+            out.push_str(&format!("bloch_z,{},{}\n", qubit, z));
+        }
+        for (bitstring, count) in self.counts.iter().flat_map(|counts| counts.iter()) {
+            out.push_str(&format!("count,{},{}\n", bitstring, count));
+        }
+        out
+    }
+
+    /// Writes the results to a file, as CSV if its extension is `.csv` and as JSON otherwise.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the file was written.
+    /// * `Err(io::Error)` - If the file could not be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.render(ExportFormat::from_path(path)))
+    }
+
+    /// Returns the bitstring of a basis state, qubit 0 last.
+    fn label(&self, index: usize) -> String {
+        format!("{:0width$b}", index, width = self.state.num_qubits())
+    }
+
+    /// Returns the probability of every basis state with its bitstring.
+    fn probabilities(&self) -> impl Iterator<Item = (String, f64)> + '_ {
+        self.state
+            .state
+            .iter()
+            .enumerate()
+            .map(|(index, amplitude)| (self.label(index), amplitude.norm_sqr()))
+    }
+
+    /// Returns the Bloch vector of every qubit.
+    fn bloch_vectors(&self) -> impl Iterator<Item = (f64, f64, f64)> + '_ {
+        (0..self.state.num_qubits()).map(|qubit| bloch_vector(&self.state, qubit))
+    }
+}
+
+impl From<&SimulationResult> for ResultExport {
+    /// Exports the final state, with the final classical register, if any, as a single shot.
+    fn from(result: &SimulationResult) -> Self {
+        let export = ResultExport::new(&result.state);
+        if result.clbits.is_empty() {
+            return export;
+        }
+        let index = result
+            .clbits
+            .iter()
+            .enumerate()
+            .filter(|(_, &bit)| bit)
+            .fold(0, |index, (clbit, _)| index | (1 << clbit));
+        let mut counts = Counts::new();
+        counts.record(index, result.clbits.len());
+        export.counts(&counts)
+    }
+}
+
+impl SimulationResult {
+    /// Writes the final amplitudes, probabilities and Bloch vectors of the run, together with
+    /// the final classical register as a single shot, to a JSON file or, if the extension is
+    /// `.csv`, a CSV file. Use a [`ResultExport`] to include the counts of many shots instead.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the file was written.
+    /// * `Err(io::Error)` - If the file could not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::noise::NoiseModel;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0).measure(0, 0);
+    /// let initial = [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let result = Simulator::run_recorded(&circuit, &initial, &NoiseModel::new());
+    ///
+    /// let path = std::env::temp_dir().join("quantum_simulator_result.json");
+    /// result.save(&path).unwrap();
+    /// let json = std::fs::read_to_string(&path).unwrap();
+    /// assert!(json.contains("\"counts\": {\n    \"1\": 1\n  }"));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        ResultExport::from(self).save(path)
+    }
+}
+
+/// Renders the counts as horizontal bars, one per outcome, scaled to the most frequent one.
+fn histogram_svg(counts: &Counts) -> String {
+    let most = counts.most_frequent().map_or(1, |(_, count)| count).max(1);
+    let rows = counts.iter().count();
+    let width = LABEL_WIDTH + BAR_WIDTH + 60;
+    let height = rows * BAR_HEIGHT + 4;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"monospace\" font-size=\"12\">\n",
+        width, height
+    );
+    for (row, (bitstring, count)) in counts.iter().enumerate() {
+        let y = row * BAR_HEIGHT + 2;
+        let length = count * BAR_WIDTH / most;
+        svg.push_str(&format!(
+            "<text x=\"{0}\" y=\"{1}\" text-anchor=\"end\">{2}</text>\n<rect x=\"{3}\" y=\"{4}\" width=\"{5}\" height=\"{6}\" fill=\"#4a7bd0\"><title>{2}: {7}</title></rect>\n<text x=\"{8}\" y=\"{1}\">{7}</text>\n",
+            LABEL_WIDTH - 6,
+            y + BAR_HEIGHT - 5,
+            bitstring,
+            LABEL_WIDTH,
+            y + 2,
+            length,
+            BAR_HEIGHT - 4,
+            count,
+            LABEL_WIDTH + length + 4
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders a two-column HTML table.
+fn html_table(header: (&str, &str), rows: impl IntoIterator<Item = (String, String)>) -> String {
+    let mut table = format!(
+        "<table>\n<tr><th>{}</th><th>{}</th></tr>\n",
+        header.0, header.1
+    );
+    for (name, value) in rows {
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            escape(name.trim_matches('`')),
+            escape(&value)
+        ));
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+/// Formats a duration in milliseconds.
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3} ms", duration.as_secs_f64() * 1e3)
+}
+
+/// Escapes the characters HTML gives a meaning to.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,66 @@
+//! This module holds the thread-local random number generator behind measurement and sampling.
+//!
+//! Unseeded draws use `rand::thread_rng`, which on `wasm32-unknown-unknown` is seeded from the
+//! browser's `crypto.getRandomValues`, so the same code runs natively and in WebAssembly.
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    static SEEDED_RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+/// Makes every subsequent draw on this thread deterministic, starting from `seed`.
+pub(crate) fn set_seed(seed: u64) {
+    SEEDED_RNG.with(|rng| *rng.borrow_mut() = Some(StdRng::seed_from_u64(seed)));
+}
+
+/// Returns this thread to non-deterministic, entropy-seeded draws.
+pub(crate) fn clear_seed() {
+    SEEDED_RNG.with(|rng| *rng.borrow_mut() = None);
+}
+
+/// Runs `f` with the seeded generator if one is set, or with `rand::thread_rng` otherwise.
+///
+/// The seeded generator is borrowed only for each draw, so `f` may call back into code that
+/// draws again, e.g. an observer that measures during
+/// [`Simulator::run_with_observer`](crate::simulator::Simulator::run_with_observer), and the
+/// nested draws continue the same seeded sequence.
+pub(crate) fn with_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    if SEEDED_RNG.with(|cell| cell.borrow().is_some()) {
+        f(&mut SeededRng)
+    } else {
+        f(&mut rand::thread_rng())
+    }
+}
+
+/// Draws from the thread's seeded generator, or from `rand::thread_rng` once the seed is cleared.
+struct SeededRng;
+
+impl SeededRng {
+    fn draw<T>(&mut self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        SEEDED_RNG.with(|cell| match cell.borrow_mut().as_mut() {
+            Some(rng) => f(rng),
+            None => f(&mut rand::thread_rng()),
+        })
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.draw(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.draw(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.draw(|rng| rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.draw(|rng| rng.try_fill_bytes(dest))
+    }
+}
@@ -0,0 +1,211 @@
+//! This module schedules circuit instructions into layers of gates that can run in parallel.
+//!
+//! A [`Schedule`] places every instruction in the earliest layer after all earlier instructions
+//! sharing a qubit or classical bit with it (as-soon-as-possible scheduling), and
+//! [`Circuit::moments`] and [`Circuit::depth`] are built on it. It reports which
+//! instructions run in each layer, how long each qubit sits idle, and renders the result as an
+//! ASCII Gantt chart.
+
+use crate::circuit::{Circuit, Operation};
+use std::fmt;
+use std::ops::Range;
+
+/// An as-soon-as-possible `Schedule` of a circuit's instructions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule<'a> {
+    circuit: &'a Circuit,
+    layers: Vec<Vec<usize>>,
+    layer_of: Vec<usize>,
+}
+
+impl<'a> Schedule<'a> {
+    /// Schedules `circuit` as soon as possible.
+    ///
+    /// Classically conditioned operations wait for the measurement writing their classical bit,
+    /// and measurements wait for earlier operations reading the bit they overwrite. A barrier
+    /// takes no layer of its own: instructions after it on any of its qubits wait for every
+    /// instruction before it on all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to schedule.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::schedule::Schedule;
+    ///
+    /// let mut circuit = Circuit::new(3);
+    /// circuit.h(0).h(1).h(2).cnot(0, 1).x(2);
+    ///
+    /// let schedule = Schedule::new(&circuit);
+    /// assert_eq!(schedule.depth(), 2);
+    /// assert_eq!(schedule.layers(), &[vec![0, 1, 2], vec![3, 4]]);
+    /// assert_eq!(schedule.parallelism(), vec![3, 2]);
+    /// ```
+    pub fn new(circuit: &'a Circuit) -> Self {
+        let mut qubit_free = vec![0; circuit.num_qubits()];
+        let mut clbit_free = vec![0; circuit.num_clbits()];
+        let mut layers: Vec<Vec<usize>> = vec![];
+        let mut layer_of = vec![];
+        for (index, instruction) in circuit.instructions().iter().enumerate() {
+            let clbit = match instruction.operation {
+                Operation::Measure(clbit) | Operation::Conditional { clbit, .. } => Some(clbit),
+                _ => None,
+            };
+            let layer = instruction
+                .qubits
+                .iter()
+                .map(|&qubit| qubit_free[qubit])
+                .chain(clbit.map(|clbit| clbit_free[clbit]))
+                .max()
+                .unwrap_or(0);
+            if let Operation::Barrier(_) = instruction.operation {
+                for &qubit in &instruction.qubits {
+                    qubit_free[qubit] = layer;
+                }
+                layer_of.push(layer);
+                continue;
+            }
+            for &qubit in &instruction.qubits {
+                qubit_free[qubit] = layer + 1;
+            }
+            if let Some(clbit) = clbit {
+                clbit_free[clbit] = layer + 1;
+            }
+            if layer == layers.len() {
+                layers.push(vec![]);
+            }
+            layers[layer].push(index);
+            layer_of.push(layer);
+        }
+        Schedule {
+            circuit,
+            layers,
+            layer_of,
+        }
+    }
+
+    /// Returns the number of layers.
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns the indices into [`Circuit::instructions`] of the instructions in each layer.
+    pub fn layers(&self) -> &[Vec<usize>] {
+        &self.layers
+    }
+
+    /// Returns the layer an instruction is scheduled in, or for a barrier the earliest layer the
+    /// instructions after it can be scheduled in.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The index of the instruction in the circuit.
+    pub fn layer_of(&self, instruction: usize) -> usize {
+        self.layer_of[instruction]
+    }
+
+    /// Returns the number of instructions running in each layer.
+    pub fn parallelism(&self) -> Vec<usize> {
+        self.layers.iter().map(Vec::len).collect()
+    }
+
+    /// Returns the maximal runs of layers in which `qubit` is not acted on, including any before
+    /// its first and after its last instruction.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The qubit to inspect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::schedule::Schedule;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).x(0).cnot(0, 1).z(0).z(0);
+    ///
+    /// let schedule = Schedule::new(&circuit);
+    /// assert_eq!(schedule.idle_spans(0), vec![]);
+    /// assert_eq!(schedule.idle_spans(1), vec![0..2, 3..5]);
+    /// ```
+    pub fn idle_spans(&self, qubit: usize) -> Vec<Range<usize>> {
+        let mut spans = vec![];
+        let mut start = None;
+        for (layer, indices) in self.layers.iter().enumerate() {
+            let busy = indices
+                .iter()
+                .any(|&index| self.circuit.instructions()[index].qubits.contains(&qubit));
+            match (busy, start) {
+                (false, None) => start = Some(layer),
+                (true, Some(first)) => {
+                    spans.push(first..layer);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(first) = start {
+            spans.push(first..self.depth());
+        }
+        spans
+    }
+}
+
+/// Renders the schedule as an ASCII Gantt chart with one row per qubit and one column per
+/// layer; idle slots are shown as `.`.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::schedule::Schedule;
+///
+/// let mut circuit = Circuit::new(3);
+/// circuit.h(0).cnot(0, 1).h(2).measure(1, 0);
+///
+/// let chart = Schedule::new(&circuit).to_string();
+/// assert_eq!(chart, "   0 1  2\nq0 h cx .\nq1 . cx m\nq2 h .  .\n");
+/// ```
+impl fmt::Display for Schedule<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let num_qubits = self.circuit.num_qubits();
+        let mut cells = vec![vec![".".to_string(); self.depth()]; num_qubits];
+        for (layer, indices) in self.layers.iter().enumerate() {
+            for &index in indices {
+                let instruction = &self.circuit.instructions()[index];
+                for &qubit in &instruction.qubits {
+                    cells[qubit][layer] = instruction.operation.name();
+                }
+            }
+        }
+        let widths: Vec<usize> = (0..self.depth())
+            .map(|layer| {
+                cells
+                    .iter()
+                    .map(|row| row[layer].len())
+                    .chain([layer.to_string().len()])
+                    .max()
+                    .unwrap_or(1)
+            })
+            .collect();
+        let label_width = format!("q{}", num_qubits.saturating_sub(1)).len();
+
+        let mut header = " ".repeat(label_width);
+        for (layer, width) in widths.iter().enumerate() {
+            header += &format!(" {:<width$}", layer, width = width);
+        }
+        writeln!(f, "{}", header.trim_end())?;
+        for (qubit, row) in cells.iter().enumerate() {
+            let mut line = format!("{:<width$}", format!("q{}", qubit), width = label_width);
+            for (cell, width) in row.iter().zip(&widths) {
+                line += &format!(" {:<width$}", cell, width = width);
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,445 @@
+//! This module estimates properties of a state from classical shadows, i.e. from measurements of
+//! every qubit in a randomly chosen Pauli basis.
+//!
+//! Each shot of a measurement setting yields a snapshot: a basis and a `±1` outcome `sₖ` per
+//! qubit. The inverted measurement channel turns a snapshot into the unbiased single-shot
+//! estimate `ρ̂ = ⊗ₖ (I + 3·sₖ·Pₖ)/2` of the state, so averages over snapshots estimate Pauli
+//! expectations, weighted sums of them and the fidelity `⟨ψ|ρ|ψ⟩` with a target state, without
+//! reconstructing the full density matrix and with far fewer settings than the `3ⁿ` of full
+//! tomography. [`analysis::classical_shadows`](crate::analysis::classical_shadows) takes the
+//! snapshots of a circuit's output directly.
+
+use crate::counts::Counts;
+use crate::density::DensityMatrix;
+use crate::error::QuantumError;
+use crate::interval::Interval;
+use crate::qubit::{Basis, Qubit};
+use crate::rng;
+use crate::variational::{Hamiltonian, PauliString};
+use num_complex::Complex;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// The random bases and the outcome of one shot, where bit `k` of `outcome` is `1` if qubit `k`
+/// was found in the `-1` eigenstate of `bases[k]`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ShadowSnapshot {
+    /// The index of the measurement setting the shot was taken in.
+    pub setting: usize,
+    pub bases: Vec<Basis>,
+    pub outcome: usize,
+}
+
+/// An estimate from a classical shadow with its standard error.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowEstimate {
+    /// The mean of the single-snapshot estimates.
+    pub value: f64,
+    /// The standard error of the mean, treating measurement settings as independent.
+    pub standard_error: f64,
+}
+
+impl ShadowEstimate {
+    /// Returns the interval `value ± z·standard_error`, a confidence interval for the true value
+    /// at the normal coverage of `z`, e.g. about 95% for `z = 1.96`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `z` is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::shadows::ShadowEstimate;
+    ///
+    /// let estimate = ShadowEstimate {
+    ///     value: 0.5,
+    ///     standard_error: 0.1,
+    /// };
+    /// let interval = estimate.confidence_interval(2.0);
+    /// assert!((interval.lo - 0.3).abs() < 1e-12 && (interval.hi - 0.7).abs() < 1e-12);
+    /// ```
+    pub fn confidence_interval(&self, z: f64) -> Interval {
+        assert!(z >= 0.0, "the z-score {} is negative", z);
+        let margin = z * self.standard_error;
+        Interval::new(self.value - margin, self.value + margin)
+    }
+}
+
+/// A `ClassicalShadow` is a collection of snapshots of one state.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClassicalShadow {
+    num_qubits: usize,
+    snapshots: Vec<ShadowSnapshot>,
+}
+
+impl ClassicalShadow {
+    /// Creates an empty shadow of a register, to be filled with [`ClassicalShadow::add_counts`].
+    pub fn new(num_qubits: usize) -> Self {
+        ClassicalShadow {
+            num_qubits,
+            snapshots: vec![],
+        }
+    }
+
+    /// Measures a state in `settings` random Pauli settings, `shots` times each.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state to take snapshots of.
+    /// * `settings` - The number of random measurement settings.
+    /// * `shots` - The number of shots per setting.
+    pub fn collect(state: &Qubit, settings: usize, shots: usize) -> Self {
+        rng::with_rng(|rng| Self::collect_with_rng(state, settings, shots, rng))
+    }
+
+    /// Measures a state like [`ClassicalShadow::collect`], drawing the settings and outcomes
+    /// from `rng`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::shadows::ClassicalShadow;
+    /// use quantum_simulator::variational::PauliString;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// let mut bell = Qubit::zero_state(2);
+    /// circuit.run(&mut bell);
+    ///
+    /// let shadow = ClassicalShadow::collect_with_rng(&bell, 50, 20, &mut StdRng::seed_from_u64(1));
+    /// assert_eq!(shadow.snapshots().len(), 1000);
+    /// let zz = shadow.expectation(&PauliString::parse("Z0 Z1").unwrap());
+    /// assert!((zz.value - 1.0).abs() < 4.0 * zz.standard_error);
+    /// let fidelity = shadow.fidelity(&bell).unwrap();
+    /// assert!((fidelity.value - 1.0).abs() < 4.0 * fidelity.standard_error);
+    /// ```
+    pub fn collect_with_rng<R: Rng + ?Sized>(
+        state: &Qubit,
+        settings: usize,
+        shots: usize,
+        rng: &mut R,
+    ) -> Self {
+        let num_qubits = state.num_qubits();
+        Self::collect_from(num_qubits, settings, shots, rng, |bases| {
+            let mut rotated = state.clone();
+            for (qubit, basis) in bases.iter().enumerate() {
+                basis.rotation().apply_to(&mut rotated, &[qubit]);
+            }
+            rotated
+                .state
+                .iter()
+                .map(|amplitude| amplitude.norm_sqr())
+                .collect()
+        })
+    }
+
+    /// Measures a mixed state, e.g. the output of a noisy simulation, in `settings` random Pauli
+    /// settings, `shots` times each.
+    pub fn collect_density(rho: &DensityMatrix, settings: usize, shots: usize) -> Self {
+        rng::with_rng(|rng| Self::collect_density_with_rng(rho, settings, shots, rng))
+    }
+
+    /// Measures a mixed state like [`ClassicalShadow::collect_density`], drawing the settings
+    /// and outcomes from `rng`.
+    pub fn collect_density_with_rng<R: Rng + ?Sized>(
+        rho: &DensityMatrix,
+        settings: usize,
+        shots: usize,
+        rng: &mut R,
+    ) -> Self {
+        let num_qubits = rho.num_qubits();
+        Self::collect_from(num_qubits, settings, shots, rng, |bases| {
+            let mut rotated = rho.clone();
+            for (qubit, basis) in bases.iter().enumerate() {
+                rotated.apply_gate(&basis.rotation(), &[qubit]);
+            }
+            rotated.probabilities()
+        })
+    }
+
+    /// Adds the snapshots of measurement counts taken in one new setting, e.g. on hardware.
+    ///
+    /// # Arguments
+    ///
+    /// * `bases` - The basis of every qubit, in qubit order.
+    /// * `counts` - The counts, keyed like those of [`Qubit::sample`], where a `1` is the `-1`
+    ///   eigenstate of the qubit's basis.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the snapshots were added.
+    /// * `Err(QuantumError::DimensionMismatch)` - If there is not one basis per qubit or a
+    ///   bitstring has the wrong length.
+    pub fn add_counts(&mut self, bases: &[Basis], counts: &Counts) -> Result<(), QuantumError> {
+        if bases.len() != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: bases.len(),
+            });
+        }
+        if let Some((bitstring, _)) = counts
+            .iter()
+            .find(|(bitstring, _)| bitstring.len() != self.num_qubits)
+        {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: bitstring.len(),
+            });
+        }
+        let setting = self.num_settings();
+        for (bitstring, count) in counts.iter() {
+            let outcome = usize::from_str_radix(bitstring, 2).expect("counts hold bitstrings");
+            for _ in 0..count {
+                self.snapshots.push(ShadowSnapshot {
+                    setting,
+                    bases: bases.to_vec(),
+                    outcome,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of qubits of the measured register.
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Returns the number of measurement settings.
+    pub fn num_settings(&self) -> usize {
+        self.snapshots
+            .last()
+            .map_or(0, |snapshot| snapshot.setting + 1)
+    }
+
+    /// Returns the snapshots in the order they were taken.
+    pub fn snapshots(&self) -> &[ShadowSnapshot] {
+        &self.snapshots
+    }
+
+    /// Estimates the expectation of a Pauli string. Only snapshots whose bases match every
+    /// non-identity factor contribute, each with `±3ʷ` for a string of weight `w`, so the
+    /// variance grows as `3ʷ` and low-weight strings are the cheap ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string acts on a qubit outside the register.
+    pub fn expectation(&self, pauli: &PauliString) -> ShadowEstimate {
+        self.check_support(pauli);
+        mean(
+            self.snapshots
+                .iter()
+                .map(|snapshot| (snapshot.setting, self.snapshot_estimate(snapshot, pauli), 1)),
+        )
+    }
+
+    /// Estimates the expectation of a weighted sum of Pauli strings from the same snapshots. The
+    /// standard error accounts for the correlations between the terms' estimates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a term acts on a qubit outside the register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::shadows::ClassicalShadow;
+    /// use quantum_simulator::variational::Hamiltonian;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// // ⟨00|(Z0 + Z1 + 0.5 X0)|00⟩ = 2
+    /// let hamiltonian = Hamiltonian::parse("Z0 + Z1 + 0.5 X0").unwrap();
+    /// let mut rng = StdRng::seed_from_u64(3);
+    /// let shadow = ClassicalShadow::collect_with_rng(&Qubit::zero_state(2), 500, 1, &mut rng);
+    /// let energy = shadow.observable(&hamiltonian);
+    /// assert!((energy.value - 2.0).abs() < 4.0 * energy.standard_error);
+    /// ```
+    pub fn observable(&self, hamiltonian: &Hamiltonian) -> ShadowEstimate {
+        for (_, pauli) in hamiltonian.terms() {
+            self.check_support(pauli);
+        }
+        mean(self.snapshots.iter().map(|snapshot| {
+            let estimate = hamiltonian
+                .terms()
+                .iter()
+                .map(|(weight, pauli)| weight * self.snapshot_estimate(snapshot, pauli))
+                .sum();
+            (snapshot.setting, estimate, 1)
+        }))
+    }
+
+    /// Estimates the fidelity `⟨ψ|ρ|ψ⟩` of the measured state `ρ` with a pure target state.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The state `|ψ⟩` the measured state should be, e.g. the output of the ideal
+    ///   circuit.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ShadowEstimate)` - The estimated fidelity. Single snapshots are unbiased but not
+    ///   bounded, so the estimate can leave `[0, 1]` by about its standard error.
+    /// * `Err(QuantumError::DimensionMismatch)` - If the target has a different number of
+    ///   qubits.
+    pub fn fidelity(&self, target: &Qubit) -> Result<ShadowEstimate, QuantumError> {
+        if target.num_qubits() != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: target.num_qubits(),
+            });
+        }
+        // Repeated snapshots are common with few settings, so each distinct one is evaluated once
+        let mut distinct: HashMap<&ShadowSnapshot, usize> = HashMap::new();
+        for snapshot in &self.snapshots {
+            *distinct.entry(snapshot).or_insert(0) += 1;
+        }
+        let estimates = distinct.into_iter().map(|(snapshot, count)| {
+            let mut image = target.state.clone();
+            for (qubit, basis) in snapshot.bases.iter().enumerate() {
+                let sign = if (snapshot.outcome >> qubit) & 1 == 1 {
+                    -1.0
+                } else {
+                    1.0
+                };
+                apply_snapshot_factor(&mut image, qubit, *basis, sign);
+            }
+            let overlap: Complex<f64> = target
+                .state
+                .iter()
+                .zip(&image)
+                .map(|(a, b)| a.conj() * b)
+                .sum();
+            (snapshot.setting, overlap.re, count)
+        });
+        Ok(mean(estimates))
+    }
+
+    /// Panics unless a Pauli string acts inside the register.
+    fn check_support(&self, pauli: &PauliString) {
+        assert!(
+            pauli.num_qubits() <= self.num_qubits,
+            "{} acts outside the {}-qubit register",
+            pauli,
+            self.num_qubits
+        );
+    }
+
+    /// Returns the single-snapshot estimate of a Pauli string: `±3ʷ` if the snapshot's bases
+    /// match its `w` non-identity factors and zero otherwise.
+    fn snapshot_estimate(&self, snapshot: &ShadowSnapshot, pauli: &PauliString) -> f64 {
+        (0..self.num_qubits)
+            .filter_map(|qubit| pauli.factor(qubit).map(|basis| (qubit, basis)))
+            .fold(1.0, |product, (qubit, basis)| {
+                if snapshot.bases[qubit] != basis {
+                    0.0
+                } else if (snapshot.outcome >> qubit) & 1 == 1 {
+                    -3.0 * product
+                } else {
+                    3.0 * product
+                }
+            })
+    }
+
+    /// Draws the snapshots of random settings, given the outcome probabilities of the rotated
+    /// state for each setting.
+    fn collect_from<R: Rng + ?Sized>(
+        num_qubits: usize,
+        settings: usize,
+        shots: usize,
+        rng: &mut R,
+        probabilities: impl Fn(&[Basis]) -> Vec<f64>,
+    ) -> Self {
+        let mut shadow = ClassicalShadow::new(num_qubits);
+        for setting in 0..settings {
+            let bases: Vec<Basis> = (0..num_qubits)
+                .map(|_| [Basis::X, Basis::Y, Basis::Z][rng.gen_range(0..3)])
+                .collect();
+            let cumulative: Vec<f64> = probabilities(&bases)
+                .into_iter()
+                .scan(0.0, |total, probability| {
+                    *total += probability;
+                    Some(*total)
+                })
+                .collect();
+            let total = cumulative.last().copied().unwrap_or(0.0);
+            for _ in 0..shots {
+                let target = rng.gen::<f64>() * total;
+                let outcome = cumulative
+                    .partition_point(|&c| c <= target)
+                    .min(cumulative.len() - 1);
+                shadow.snapshots.push(ShadowSnapshot {
+                    setting,
+                    bases: bases.clone(),
+                    outcome,
+                });
+            }
+        }
+        shadow
+    }
+}
+
+/// Applies the single-qubit snapshot factor `(I + 3·sign·P)/2` to a state vector.
+fn apply_snapshot_factor(state: &mut [Complex<f64>], qubit: usize, basis: Basis, sign: f64) {
+    let (one, i) = (Complex::new(1.0, 0.0), Complex::new(0.0, 1.0));
+    let (zero, three) = (Complex::new(0.0, 0.0), Complex::new(3.0 * sign, 0.0));
+    // The Pauli of the basis, as [[p00, p01], [p10, p11]]
+    let pauli = match basis {
+        Basis::X => [[zero, one], [one, zero]],
+        Basis::Y => [[zero, -i], [i, zero]],
+        Basis::Z => [[one, zero], [zero, -one]],
+    };
+    let factor = |row: usize, column: usize| {
+        let identity = if row == column { one } else { zero };
+        (identity + three * pauli[row][column]) * 0.5
+    };
+    let bit = 1 << qubit;
+    for index in (0..state.len()).filter(|index| index & bit == 0) {
+        let (a, b) = (state[index], state[index | bit]);
+        state[index] = factor(0, 0) * a + factor(0, 1) * b;
+        state[index | bit] = factor(1, 0) * a + factor(1, 1) * b;
+    }
+}
+
+/// Returns the mean of `(setting, value, count)` samples, where `count` snapshots of a setting
+/// had the same value, and its standard error.
+///
+/// Shots of one setting are correlated, so the standard error treats settings as the
+/// independent units: it is the cluster-robust standard error of the mean.
+fn mean(samples: impl Iterator<Item = (usize, f64, usize)>) -> ShadowEstimate {
+    let mut settings: HashMap<usize, (f64, usize)> = HashMap::new();
+    for (index, value, count) in samples {
+        let setting = settings.entry(index).or_insert((0.0, 0));
+        setting.0 += value * count as f64;
+        setting.1 += count;
+    }
+    let total: usize = settings.values().map(|&(_, count)| count).sum();
+    if total == 0 {
+        return ShadowEstimate {
+            value: 0.0,
+            standard_error: f64::INFINITY,
+        };
+    }
+    let n = total as f64;
+    let value = settings.values().map(|&(sum, _)| sum).sum::<f64>() / n;
+    let groups = settings.len() as f64;
+    let standard_error = if settings.len() > 1 {
+        let spread: f64 = settings
+            .values()
+            .map(|&(sum, count)| (sum - value * count as f64).powi(2))
+            .sum();
+        (spread * groups / (groups - 1.0)).sqrt() / n
+    } else {
+        f64::INFINITY
+    };
+    ShadowEstimate {
+        value,
+        standard_error,
+    }
+}
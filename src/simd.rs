@@ -0,0 +1,227 @@
+//! This module holds the vectorized inner loops of the state-vector engine.
+//!
+//! Single-qubit gates and CNOTs dominate most circuits, and
+//! [`GateKind::apply`](crate::gates::GateKind::apply), which [`Simulator::run`] and the
+//! [`CpuBackend`](crate::backend::CpuBackend) go through, hands both to the kernels here. On
+//! x86-64 processors with AVX2 and FMA, detected at runtime, they update two amplitudes per
+//! 256-bit register with fused multiply-adds; elsewhere, or after [`set_enabled`]`(false)`, the
+//! scalar loops run. Both paths give the same amplitudes up to rounding in the last bits.
+//!
+//! [`Simulator::run`]: crate::simulator::Simulator::run
+
+use num_complex::Complex;
+use std::cell::Cell;
+use std::fmt;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+/// The instruction set the kernels run with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionSet {
+    /// Plain loops over one amplitude at a time.
+    Scalar,
+    /// 256-bit AVX2 registers with fused multiply-add.
+    Avx2,
+}
+
+impl fmt::Display for InstructionSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstructionSet::Scalar => write!(f, "scalar"),
+            InstructionSet::Avx2 => write!(f, "avx2+fma"),
+        }
+    }
+}
+
+/// Returns the instruction set the kernels use on this thread.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::simd::{self, InstructionSet};
+///
+/// simd::set_enabled(false);
+/// assert_eq!(simd::active(), InstructionSet::Scalar);
+/// simd::set_enabled(true);
+/// #[cfg(target_arch = "x86_64")]
+/// assert_eq!(
+///     simd::active() == InstructionSet::Avx2,
+///     is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+/// );
+/// ```
+pub fn active() -> InstructionSet {
+    if ENABLED.with(Cell::get) && avx2_detected() {
+        InstructionSet::Avx2
+    } else {
+        InstructionSet::Scalar
+    }
+}
+
+/// Allows or forbids the vectorized kernels on this thread, e.g. to compare them against the
+/// scalar loops. They are allowed by default and used wherever the processor supports them.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.with(|cell| cell.set(enabled));
+}
+
+/// Applies the single-qubit gate `matrix` to `target` of a state vector.
+pub(crate) fn apply_single_qubit(
+    state: &mut [Complex<f64>],
+    matrix: &[[Complex<f64>; 2]; 2],
+    target: usize,
+) {
+    #[cfg(target_arch = "x86_64")]
+    if active() == InstructionSet::Avx2 {
+        // SAFETY: AVX2 and FMA were detected at runtime
+        unsafe { avx2::single_qubit(state, matrix, target) };
+        return;
+    }
+    let stride = 1 << target;
+    for base in (0..state.len()).filter(|index| index & stride == 0) {
+        let (a, b) = (state[base], state[base + stride]);
+        state[base] = matrix[0][0] * a + matrix[0][1] * b;
+        state[base + stride] = matrix[1][0] * a + matrix[1][1] * b;
+    }
+}
+
+/// Applies a CNOT from `control` to `target` of a state vector.
+pub(crate) fn apply_cnot(state: &mut [Complex<f64>], control: usize, target: usize) {
+    #[cfg(target_arch = "x86_64")]
+    if active() == InstructionSet::Avx2 {
+        // SAFETY: AVX2 and FMA were detected at runtime
+        unsafe { avx2::cnot(state, control, target) };
+        return;
+    }
+    let (control, target) = (1 << control, 1 << target);
+    for index in (0..state.len()).filter(|index| index & control != 0 && index & target == 0) {
+        state.swap(index, index | target);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn avx2_detected() -> bool {
+    is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn avx2_detected() -> bool {
+    false
+}
+
+/// The AVX2 kernels. A `__m256d` holds two amplitudes as `[re₀, im₀, re₁, im₁]`, the layout of
+/// two consecutive `Complex<f64>`s.
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use num_complex::Complex;
+    use std::arch::x86_64::*;
+
+    /// Applies a single-qubit gate, two amplitudes per load.
+    ///
+    /// # Safety
+    ///
+    /// The processor must support AVX2 and FMA, and `state` must have at least two amplitudes.
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn single_qubit(
+        state: &mut [Complex<f64>],
+        matrix: &[[Complex<f64>; 2]; 2],
+        target: usize,
+    ) {
+        let len = state.len();
+        let ptr = state.as_mut_ptr() as *mut f64;
+        if target == 0 {
+            // Each register holds a pair [a, b] and becomes [m00 a + m01 b, m10 a + m11 b]
+            let (diagonal_re, diagonal_im) = split(matrix[0][0], matrix[1][1]);
+            let (off_re, off_im) = split(matrix[0][1], matrix[1][0]);
+            for pair in (0..len).step_by(2) {
+                let p = ptr.add(2 * pair);
+                let v = _mm256_loadu_pd(p);
+                let swapped = _mm256_permute2f128_pd(v, v, 1);
+                let product = multiply(v, diagonal_re, diagonal_im);
+                _mm256_storeu_pd(p, _mm256_add_pd(product, multiply(swapped, off_re, off_im)));
+            }
+            return;
+        }
+        // Two neighbouring bases at once: a from the lower half of each block, b from the upper
+        let stride = 1 << target;
+        let [[m00, m01], [m10, m11]] = *matrix;
+        let (m00, m01, m10, m11) = (
+            split(m00, m00),
+            split(m01, m01),
+            split(m10, m10),
+            split(m11, m11),
+        );
+        for block in (0..len).step_by(2 * stride) {
+            for base in (block..block + stride).step_by(2) {
+                let pa = ptr.add(2 * base);
+                let pb = ptr.add(2 * (base + stride));
+                let a = _mm256_loadu_pd(pa);
+                let b = _mm256_loadu_pd(pb);
+                let upper = _mm256_add_pd(multiply(a, m00.0, m00.1), multiply(b, m01.0, m01.1));
+                let lower = _mm256_add_pd(multiply(a, m10.0, m10.1), multiply(b, m11.0, m11.1));
+                _mm256_storeu_pd(pa, upper);
+                _mm256_storeu_pd(pb, lower);
+            }
+        }
+    }
+
+    /// Applies a CNOT, swapping two amplitudes per load.
+    ///
+    /// # Safety
+    ///
+    /// The processor must support AVX2, and `state` must have at least four amplitudes.
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn cnot(state: &mut [Complex<f64>], control: usize, target: usize) {
+        let len = state.len();
+        let ptr = state.as_mut_ptr() as *mut f64;
+        let (control_bit, target_bit) = (1usize << control, 1usize << target);
+        // Pairs of amplitudes differing in qubit 0, visited by their even index
+        let pairs = (0..len).step_by(2);
+        if target == 0 {
+            // Swap the two amplitudes of each pair whose control is set
+            for pair in pairs.filter(|index| index & control_bit != 0) {
+                let p = ptr.add(2 * pair);
+                let v = _mm256_loadu_pd(p);
+                _mm256_storeu_pd(p, _mm256_permute2f128_pd(v, v, 1));
+            }
+        } else if control == 0 {
+            // Swap the odd amplitude, whose control is set, between pairs differing in the target
+            for pair in pairs.filter(|index| index & target_bit == 0) {
+                let pa = ptr.add(2 * pair);
+                let pb = ptr.add(2 * (pair | target_bit));
+                let a = _mm256_loadu_pd(pa);
+                let b = _mm256_loadu_pd(pb);
+                _mm256_storeu_pd(pa, _mm256_blend_pd(a, b, 0b1100));
+                _mm256_storeu_pd(pb, _mm256_blend_pd(b, a, 0b1100));
+            }
+        } else {
+            // Swap whole pairs
+            for pair in pairs.filter(|index| index & control_bit != 0 && index & target_bit == 0) {
+                let pa = ptr.add(2 * pair);
+                let pb = ptr.add(2 * (pair | target_bit));
+                let a = _mm256_loadu_pd(pa);
+                _mm256_storeu_pd(pa, _mm256_loadu_pd(pb));
+                _mm256_storeu_pd(pb, a);
+            }
+        }
+    }
+
+    /// Returns the real and imaginary parts of two complex numbers, each repeated in both halves
+    /// of its amplitude: `[a.re, a.re, b.re, b.re]` and `[a.im, a.im, b.im, b.im]`.
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn split(a: Complex<f64>, b: Complex<f64>) -> (__m256d, __m256d) {
+        (
+            _mm256_setr_pd(a.re, a.re, b.re, b.re),
+            _mm256_setr_pd(a.im, a.im, b.im, b.im),
+        )
+    }
+
+    /// Multiplies two amplitudes by two complex numbers given as by [`split`]:
+    /// `(x + iy)(c + id) = (xc - yd) + i(yc + xd)`.
+    #[inline]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn multiply(v: __m256d, re: __m256d, im: __m256d) -> __m256d {
+        let swapped = _mm256_permute_pd(v, 0b0101);
+        _mm256_fmaddsub_pd(v, re, _mm256_mul_pd(swapped, im))
+    }
+}
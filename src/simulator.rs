@@ -1,8 +1,56 @@
 //! This module defines the `Simulator` struct and its associated methods for running quantum circuits on qubits.
 
-use crate::circuit::Circuit;
+use crate::analysis::reduced_density_matrix;
+use crate::backend::Backend;
+#[cfg(feature = "chunked")]
+use crate::chunked::{self, ChunkedBackend, DEFAULT_MEMORY_BUDGET};
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::convergence::{Convergence, Estimate};
+use crate::counts::Counts;
+use crate::density::DensityMatrix;
+use crate::error::QuantumError;
+use crate::events::{Event, EventLog};
+use crate::execution::{Execution, ExecutionMode};
+use crate::gates::{pauli_x, Gate};
+use crate::hooks::{Hooks, SimulationEvent};
+use crate::library::{self, GateLibrary};
+use crate::linalg;
+use crate::noise::{KrausChannel, NoiseModel};
+use crate::ordering::QubitOrdering;
+use crate::parameter::Angle;
 use crate::qubit::Qubit;
+use crate::rng;
+use crate::snapshot::Trace;
+use crate::stats::{self, Stats};
+use crate::tolerance::{self, Tolerances};
+use crate::variational::Hamiltonian;
 use num_complex::Complex;
+use rand::{Rng, RngCore};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::f64::consts::FRAC_PI_2;
+use std::iter::{Enumerate, Peekable};
+use std::ops::ControlFlow;
+use std::slice;
+
+/// The final state of a recorded simulation together with its event log.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationResult {
+    pub state: Qubit,
+    /// The final classical register, where element `k` is classical bit `k`.
+    pub clbits: Vec<bool>,
+    pub events: EventLog,
+}
+
+/// The final state of a truncated simulation together with the error truncation introduced.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TruncatedResult {
+    pub state: Qubit,
+    /// The total probability discarded over all truncation steps.
+    pub truncation_error: f64,
+}
 
 /// The `Simulator` struct provides functionality to run quantum circuits on qubits.
 pub struct Simulator;
@@ -19,6 +67,11 @@ impl Simulator {
     ///
     /// * A `Qubit` representing the final state after the circuit has been applied.
     ///
+    /// # Panics
+    ///
+    /// Panics if an assertion of the circuit fails; use [`Simulator::try_run`] to get the
+    /// failure as an error.
+    ///
     /// # Examples
     ///
     /// ```
@@ -27,7 +80,7 @@ impl Simulator {
     /// use quantum_simulator::simulator::Simulator;
     /// use num_complex::Complex;
     ///
-    /// let mut circuit = Circuit::new();
+    /// let mut circuit = Circuit::new(1);
     /// circuit.add_gate(hadamard(1));
     /// circuit.add_gate(pauli_x());
     ///
@@ -41,4 +94,1734 @@ impl Simulator {
         circuit.run(&mut qubit);
         qubit
     }
+
+    /// Runs the circuit like [`Simulator::run`], with the initial and final amplitudes indexed in
+    /// `ordering` instead of the simulator's little-endian convention. Qubit labels and the
+    /// instructions are unchanged; only the mapping between qubits and the bits of basis indices
+    /// differs.
+    ///
+    /// Counts sampled from the result are little-endian; convert them with
+    /// [`QubitOrdering::convert_counts`](crate::ordering::QubitOrdering::convert_counts).
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to run.
+    /// * `initial_state` - The initial amplitudes in `ordering`.
+    /// * `ordering` - The convention of the initial and final amplitudes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::ordering::QubitOrdering;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// // Flipping qubit 0 of |00⟩ gives |10⟩ when written big-endian, index 2
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.x(0);
+    /// let initial_state = Qubit::zero_state(2).state;
+    /// let state = Simulator::run_ordered(&circuit, &initial_state, QubitOrdering::BigEndian);
+    /// assert_eq!(state.state[2], Complex::new(1.0, 0.0));
+    /// ```
+    pub fn run_ordered(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        ordering: QubitOrdering,
+    ) -> Qubit {
+        let mut qubit = Qubit::from_state(ordering.convert_state(initial_state));
+        circuit.run(&mut qubit);
+        Qubit::from_state(ordering.convert_state(&qubit.state))
+    }
+
+    /// Runs the circuit like [`Simulator::run`], but returns an error instead of panicking on
+    /// bad input and treats recoverable problems according to `mode` (see
+    /// [`Circuit::run_with_mode`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - The initial amplitudes of the register.
+    /// * `mode` - Whether recoverable problems abort the run or are reported as warnings.
+    ///
+    /// # Returns
+    ///
+    /// * The final qubit state with any warnings, or the error that stopped the run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::error::QuantumError;
+    /// use quantum_simulator::execution::ExecutionMode;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    ///
+    /// let too_short = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// assert!(matches!(
+    ///     Simulator::try_run(&circuit, &too_short, ExecutionMode::Lenient),
+    ///     Err(QuantumError::DimensionMismatch { expected: 4, found: 2 })
+    /// ));
+    ///
+    /// let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+    /// initial_state[0] = Complex::new(1.0, 0.0);
+    /// let execution = Simulator::try_run(&circuit, &initial_state, ExecutionMode::Strict).unwrap();
+    /// assert!(execution.warnings.is_empty());
+    /// assert_eq!(execution.value, Simulator::run(&circuit, &initial_state));
+    /// ```
+    pub fn try_run(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        mode: ExecutionMode,
+    ) -> Result<Execution<Qubit>, QuantumError> {
+        let mut qubit = Qubit::from_state(initial_state.to_vec());
+        let execution = circuit.run_with_mode(&mut qubit, mode)?;
+        Ok(Execution {
+            value: qubit,
+            warnings: execution.warnings,
+        })
+    }
+
+    /// Runs the circuit on the given backend, which may be chosen at runtime, and returns the
+    /// backend's final state.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The engine holding the initial state, e.g. [`crate::backend::CpuBackend`]
+    ///   or, with the `gpu` feature, `GpuBackend`.
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    ///
+    /// # Returns
+    ///
+    /// * The final state, or the backend's error if it cannot run the circuit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::backend::{Backend, CpuBackend};
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+    /// initial_state[0] = Complex::new(1.0, 0.0);
+    ///
+    /// let mut backend: Box<dyn Backend<State = Qubit>> = Box::new(CpuBackend::new(2));
+    /// let final_qubit = Simulator::run_on(backend.as_mut(), &circuit).unwrap();
+    /// assert_eq!(final_qubit, Simulator::run(&circuit, &initial_state));
+    /// ```
+    pub fn run_on<B: Backend + ?Sized>(
+        backend: &mut B,
+        circuit: &Circuit,
+    ) -> Result<B::State, QuantumError> {
+        backend.run(circuit)?;
+        Ok(backend.state())
+    }
+
+    /// Runs the circuit, zeroing amplitudes smaller than `threshold` after every instruction.
+    ///
+    /// Truncation is opt-in: it keeps the state sparse at the cost of accuracy, and the
+    /// probability discarded at each step is accumulated in the result so the trade-off can be
+    /// checked afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `threshold` - The smallest amplitude magnitude that is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit is not unitary (see [`Circuit::is_unitary`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.ry(0, 0.01);
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let result = Simulator::run_truncated(&circuit, &initial_state, 1e-2);
+    /// assert_eq!(result.state.state[1], Complex::new(0.0, 0.0));
+    /// assert!((result.truncation_error - 0.005_f64.sin().powi(2)).abs() < 1e-12);
+    /// ```
+    pub fn run_truncated(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        threshold: f64,
+    ) -> TruncatedResult {
+        let mut state = Qubit::from_state(initial_state.to_vec());
+        let mut truncation_error = 0.0;
+        for instruction in circuit.instructions() {
+            instruction.apply(&mut state);
+            truncation_error += state.truncate(threshold);
+        }
+        TruncatedResult {
+            state,
+            truncation_error,
+        }
+    }
+
+    /// Runs the circuit with every gate replaced by its noisy counterpart from `noise_model`.
+    ///
+    /// Only the model's coherent error is applied; use [`Simulator::run_trajectory`] to include
+    /// its Kraus channels. Measurements and resets draw from the thread-local generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The noise model describing how each gate is corrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::noise::{CoherentError, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.add_gate(pauli_x());
+    ///
+    /// let mut model = NoiseModel::new();
+    /// model.set_coherent_error(CoherentError::new(0.1, 0.0));
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let final_qubit = Simulator::run_with_noise(&circuit, &initial_state, &model);
+    /// // The overrotated X leaves a little population behind in |0⟩.
+    /// assert!(final_qubit.state[0].norm_sqr() > 0.0);
+    /// ```
+    pub fn run_with_noise(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+    ) -> Qubit {
+        let mut coherent = NoiseModel::new();
+        if let Some(error) = noise_model.coherent_error() {
+            coherent.set_coherent_error(*error);
+        }
+        Self::run_trajectory(circuit, initial_state, &coherent)
+    }
+
+    /// Runs one stochastic trajectory of the circuit under `noise_model`.
+    ///
+    /// After each instruction, every error channel attached to it picks one Kraus operator `Kᵢ`
+    /// with probability `‖Kᵢ|ψ⟩‖²` and the state is replaced by `Kᵢ|ψ⟩` renormalized, and so do
+    /// the idle decoherence channels before it (see [`NoiseModel::set_decoherence`]). Averaging
+    /// `|ψ⟩⟨ψ|` over many trajectories reproduces the noisy density matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    /// use quantum_simulator::noise::{bit_flip, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0);
+    /// let mut model = NoiseModel::new();
+    /// model.add_gate_error(Operation::X, bit_flip(1.0));
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let final_qubit = Simulator::run_trajectory(&circuit, &initial_state, &model);
+    /// assert!((final_qubit.state[0].norm() - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn run_trajectory(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+    ) -> Qubit {
+        rng::with_rng(|rng| Self::run_trajectory_with_rng(circuit, initial_state, noise_model, rng))
+    }
+
+    /// Runs one stochastic trajectory like [`Simulator::run_trajectory`], drawing randomness
+    /// from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::noise::{depolarizing, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.h(0);
+    /// let mut model = NoiseModel::new();
+    /// model.add_error(depolarizing(0.5));
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let a = Simulator::run_trajectory_with_rng(&circuit, &initial_state, &model, &mut StdRng::seed_from_u64(3));
+    /// let b = Simulator::run_trajectory_with_rng(&circuit, &initial_state, &model, &mut StdRng::seed_from_u64(3));
+    /// assert_eq!(a.state, b.state);
+    /// ```
+    pub fn run_trajectory_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        rng: &mut R,
+    ) -> Qubit {
+        Self::run_recorded_with_rng(circuit, initial_state, noise_model, rng).state
+    }
+
+    /// Runs one stochastic trajectory and records every gate and noise realization.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    ///
+    /// # Returns
+    ///
+    /// * A `SimulationResult` holding the final state and the ordered event log.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::{Circuit, Operation};
+    /// use quantum_simulator::events::Event;
+    /// use quantum_simulator::noise::{bit_flip, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0);
+    /// let mut model = NoiseModel::new();
+    /// model.add_error(bit_flip(1.0));
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let result = Simulator::run_recorded(&circuit, &initial_state, &model);
+    /// assert_eq!(result.events.len(), 2);
+    /// assert_eq!(
+    ///     result.events.events()[1],
+    ///     Event::Channel { instruction: 0, qubits: vec![0], operator: 1 }
+    /// );
+    /// ```
+    pub fn run_recorded(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+    ) -> SimulationResult {
+        rng::with_rng(|rng| Self::run_recorded_with_rng(circuit, initial_state, noise_model, rng))
+    }
+
+    /// Runs one recorded trajectory like [`Simulator::run_recorded`], drawing randomness from
+    /// `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `rng` - The random number generator to draw from.
+    pub fn run_recorded_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        rng: &mut R,
+    ) -> SimulationResult {
+        Self::run_with_hooks_with_rng(circuit, initial_state, noise_model, &mut Hooks::new(), rng)
+    }
+
+    /// Runs one recorded trajectory like [`Simulator::run_recorded`], invoking `hooks` as it
+    /// goes.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `hooks` - The callbacks to invoke.
+    pub fn run_with_hooks(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        hooks: &mut Hooks,
+    ) -> SimulationResult {
+        rng::with_rng(|rng| {
+            Self::run_with_hooks_with_rng(circuit, initial_state, noise_model, hooks, rng)
+        })
+    }
+
+    /// Runs one hooked trajectory like [`Simulator::run_with_hooks`], drawing randomness from
+    /// `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `hooks` - The callbacks to invoke.
+    /// * `rng` - The random number generator to draw from.
+    pub fn run_with_hooks_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        hooks: &mut Hooks,
+        rng: &mut R,
+    ) -> SimulationResult {
+        Self::execute(
+            circuit,
+            initial_state,
+            noise_model,
+            hooks,
+            &mut Choices::Sampled(rng),
+        )
+        // A sampled trajectory has no log to mismatch, so only an assertion can fail
+        .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Runs the circuit and passes every gate, measurement and snapshot to `observer` as it
+    /// happens, e.g. to log a long simulation, drive a progress bar or stream it to a
+    /// visualizer.
+    ///
+    /// Measurements and resets draw from the thread-local generator. For noisy runs or several
+    /// kinds of callbacks at once, register the observer with [`Hooks::on_event`] and call
+    /// [`Simulator::run_with_hooks`].
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `observer` - The callback to invoke with each event.
+    ///
+    /// # Returns
+    ///
+    /// * A `SimulationResult` with the final state and classical bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::hooks::SimulationEvent;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::with_clbits(2, 2);
+    /// circuit.h(0).snapshot("superposed").cnot(0, 1).measure(0, 0).measure(1, 1);
+    ///
+    /// let mut log = vec![];
+    /// let initial_state = vec![
+    ///     Complex::new(1.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    /// ];
+    /// let result = Simulator::run_with_observer(&circuit, &initial_state, |event| match event {
+    ///     SimulationEvent::GateApplied { operation, .. } => log.push(operation.name()),
+    ///     SimulationEvent::MeasurementTaken { qubit, .. } => log.push(format!("measure {}", qubit)),
+    ///     SimulationEvent::StateSnapshot { label, .. } => log.push(label.to_string()),
+    /// });
+    /// assert_eq!(log, ["h", "superposed", "cx", "measure 0", "measure 1"]);
+    /// assert_eq!(result.clbits[0], result.clbits[1]);
+    /// ```
+    pub fn run_with_observer(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        observer: impl FnMut(&SimulationEvent),
+    ) -> SimulationResult {
+        rng::with_rng(|rng| Self::run_with_observer_with_rng(circuit, initial_state, observer, rng))
+    }
+
+    /// Runs the circuit like [`Simulator::run_with_observer`], drawing measurement outcomes from
+    /// `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `observer` - The callback to invoke with each event.
+    /// * `rng` - The random number generator to draw from.
+    pub fn run_with_observer_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        observer: impl FnMut(&SimulationEvent),
+        rng: &mut R,
+    ) -> SimulationResult {
+        let mut hooks = Hooks::new();
+        hooks.on_event(observer);
+        Self::run_with_hooks_with_rng(circuit, initial_state, &NoiseModel::new(), &mut hooks, rng)
+    }
+
+    /// Runs the circuit one instruction at a time and records the state after each, e.g. for
+    /// debugging a circuit or animating its evolution.
+    ///
+    /// Measurements and resets draw from the thread-local generator. States are stored as
+    /// copy-on-write snapshots, so each step costs memory only for the amplitudes it changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    ///
+    /// # Returns
+    ///
+    /// * A `Trace` with the initial state and the state after every instruction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).snapshot("superposed").cnot(0, 1);
+    ///
+    /// let initial_state = vec![
+    ///     Complex::new(1.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    /// ];
+    /// let trace = Simulator::run_stepwise(&circuit, &initial_state);
+    /// assert_eq!(trace.steps().len(), 3);
+    /// assert_eq!(trace.states().count(), 4);
+    ///
+    /// let superposed = trace.snapshot("superposed").unwrap();
+    /// assert!((superposed.get(1).re - 0.5_f64.sqrt()).abs() < 1e-12);
+    /// assert!((trace.final_state().get(3).re - 0.5_f64.sqrt()).abs() < 1e-12);
+    /// ```
+    pub fn run_stepwise(circuit: &Circuit, initial_state: &[Complex<f64>]) -> Trace {
+        rng::with_rng(|rng| Self::run_stepwise_with_rng(circuit, initial_state, rng))
+    }
+
+    /// Runs the circuit stepwise like [`Simulator::run_stepwise`], drawing measurement outcomes
+    /// from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `rng` - The random number generator to draw from.
+    pub fn run_stepwise_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        rng: &mut R,
+    ) -> Trace {
+        Self::run_stepwise_trajectory_with_rng(circuit, initial_state, &NoiseModel::new(), rng)
+    }
+
+    /// Runs one noisy trajectory stepwise, recording the state after each instruction and the
+    /// errors sampled for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    ///
+    /// # Returns
+    ///
+    /// * A `Trace` with the initial state and the state after every instruction.
+    pub fn run_stepwise_trajectory(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+    ) -> Trace {
+        rng::with_rng(|rng| {
+            Self::run_stepwise_trajectory_with_rng(circuit, initial_state, noise_model, rng)
+        })
+    }
+
+    /// Runs one noisy trajectory stepwise like [`Simulator::run_stepwise_trajectory`], drawing
+    /// measurement outcomes and errors from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::noise::{bit_flip, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0);
+    ///
+    /// // A bit flip after every gate that always fires undoes the X
+    /// let mut noise_model = NoiseModel::new();
+    /// noise_model.add_error(bit_flip(1.0));
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let mut rng = StdRng::seed_from_u64(7);
+    /// let trace =
+    ///     Simulator::run_stepwise_trajectory_with_rng(&circuit, &initial_state, &noise_model, &mut rng);
+    /// assert!((trace.final_state().get(0).norm() - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn run_stepwise_trajectory_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        rng: &mut R,
+    ) -> Trace {
+        let mut trace = Trace::new(initial_state);
+        let mut hooks = Hooks::new();
+        hooks.on_gate_applied(|index, instruction, qubit| trace.record(index, instruction, qubit));
+        let result =
+            Self::run_with_hooks_with_rng(circuit, initial_state, noise_model, &mut hooks, rng);
+        drop(hooks);
+        trace.set_clbits(result.clbits);
+        trace
+    }
+
+    /// Deterministically replays a recorded trajectory.
+    ///
+    /// Instead of sampling, every noise channel applies the Kraus operator recorded in `events`,
+    /// so a rare shot captured with [`Simulator::run_recorded`] can be reproduced exactly.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit that was recorded.
+    /// * `initial_state` - The initial state of the recorded run.
+    /// * `noise_model` - The noise model of the recorded run.
+    /// * `events` - The recorded event log.
+    ///
+    /// # Returns
+    ///
+    /// * A `SimulationResult` with the replayed final state and a copy of the log, or
+    ///   `QuantumError::ReplayMismatch` if the log does not fit the circuit and noise model, or
+    ///   records an operator that cannot occur from the replayed state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::noise::{depolarizing, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    /// let mut model = NoiseModel::new();
+    /// model.add_error(depolarizing(0.3));
+    ///
+    /// let initial_state = vec![
+    ///     Complex::new(1.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    ///     Complex::new(0.0, 0.0),
+    /// ];
+    /// let recorded = Simulator::run_recorded(&circuit, &initial_state, &model);
+    /// let replayed = Simulator::replay(&circuit, &initial_state, &model, &recorded.events).unwrap();
+    /// assert_eq!(replayed, recorded);
+    /// ```
+    pub fn replay(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        events: &EventLog,
+    ) -> Result<SimulationResult, QuantumError> {
+        let mut choices: Choices<dyn RngCore> = Choices::Recorded {
+            events: events.events().iter().enumerate().peekable(),
+            len: events.len(),
+        };
+        Self::execute(
+            circuit,
+            initial_state,
+            noise_model,
+            &mut Hooks::new(),
+            &mut choices,
+        )
+    }
+
+    /// Runs one trajectory, taking measurement outcomes and Kraus operators from `choices`.
+    fn execute<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        hooks: &mut Hooks,
+        choices: &mut Choices<R>,
+    ) -> Result<SimulationResult, QuantumError> {
+        let mut qubit = Qubit::from_state(initial_state.to_vec());
+        let mut clbits = vec![false; circuit.num_clbits()];
+        let mut events = EventLog::new();
+        let idle = noise_model.idle_errors(circuit);
+        let last = circuit.instructions().len().saturating_sub(1);
+        for (index, instruction) in circuit.instructions().iter().enumerate() {
+            let waits = idle[index]
+                .iter()
+                .map(|(channel, qubits)| (channel, qubits.clone()));
+            Self::apply_errors(&mut qubit, waits, index, choices, &mut events)?;
+            let applied = match &instruction.operation {
+                Operation::Measure(_) | Operation::Reset => {
+                    let target = instruction.qubits[0];
+                    let outcome = choices.outcome(&qubit, target)?;
+                    if let Operation::Measure(clbit) = instruction.operation {
+                        hooks.measurement(&instruction.qubits, outcome, &qubit);
+                        clbits[clbit] = outcome == 1;
+                    }
+                    qubit.collapse(target, outcome);
+                    if instruction.operation == Operation::Reset && outcome == 1 {
+                        pauli_x().apply_to(&mut qubit, &instruction.qubits);
+                    }
+                    hooks.event(SimulationEvent::MeasurementTaken {
+                        instruction: index,
+                        qubit: target,
+                        clbit: match instruction.operation {
+                            Operation::Measure(clbit) => Some(clbit),
+                            _ => None,
+                        },
+                        outcome,
+                        state: &qubit,
+                    });
+                    let event = Event::Measurement {
+                        instruction: index,
+                        qubit: target,
+                        outcome,
+                    };
+                    choices.record(&mut events, event)?;
+                    Some(Cow::Borrowed(instruction))
+                }
+                Operation::Assert(assertion) => {
+                    let rho = reduced_density_matrix(&qubit, &instruction.qubits);
+                    assertion.check(index, &rho)?;
+                    None
+                }
+                Operation::Conditional { clbit, operation } => {
+                    let taken = clbits[*clbit];
+                    let event = Event::Branch {
+                        instruction: index,
+                        clbit: *clbit,
+                        taken,
+                    };
+                    choices.record(&mut events, event)?;
+                    taken.then(|| {
+                        Cow::Owned(Instruction::new(
+                            (**operation).clone(),
+                            instruction.qubits.clone(),
+                        ))
+                    })
+                }
+                _ => Some(Cow::Borrowed(instruction)),
+            };
+
+            if let Some(applied) = applied {
+                if applied.operation.is_unitary() {
+                    let gate = noise_model.noisy_gate(&applied.operation.gate());
+                    gate.apply_to(&mut qubit, &applied.qubits);
+                    let event = Event::Gate {
+                        instruction: index,
+                        operation: applied.operation.clone(),
+                        qubits: applied.qubits.clone(),
+                    };
+                    choices.record(&mut events, event)?;
+                }
+                let errors = noise_model.errors_for(&applied);
+                Self::apply_errors(&mut qubit, errors, index, choices, &mut events)?;
+                if applied.operation.is_unitary() {
+                    hooks.event(SimulationEvent::GateApplied {
+                        instruction: index,
+                        operation: &applied.operation,
+                        qubits: &applied.qubits,
+                        state: &qubit,
+                    });
+                }
+            }
+            if let Operation::Snapshot(label) = &instruction.operation {
+                hooks.event(SimulationEvent::StateSnapshot {
+                    instruction: index,
+                    label,
+                    state: &qubit,
+                });
+            }
+            if index == last {
+                // Qubits that finished early wait for the rest of the circuit
+                let waits = idle[index + 1]
+                    .iter()
+                    .map(|(channel, qubits)| (channel, qubits.clone()));
+                Self::apply_errors(&mut qubit, waits, index, choices, &mut events)?;
+            }
+            hooks.gate_applied(index, instruction, &qubit);
+        }
+        choices.finish()?;
+        Ok(SimulationResult {
+            state: qubit,
+            clbits,
+            events,
+        })
+    }
+
+    /// Applies one Kraus operator of each error channel attributed to instruction `index`.
+    fn apply_errors<'a, R: Rng + ?Sized>(
+        qubit: &mut Qubit,
+        errors: impl IntoIterator<Item = (&'a KrausChannel, Vec<usize>)>,
+        index: usize,
+        choices: &mut Choices<R>,
+        events: &mut EventLog,
+    ) -> Result<(), QuantumError> {
+        for (channel, qubits) in errors {
+            let (next, operator) = choices.kraus(qubit, channel, &qubits)?;
+            *qubit = next;
+            let event = Event::Channel {
+                instruction: index,
+                qubits,
+                operator,
+            };
+            choices.record(events, event)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `shots` independent noisy trajectories and measures each one once.
+    ///
+    /// A circuit with a classical register is recorded by its final classical bits; otherwise
+    /// every qubit is measured at the end of the trajectory.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `shots` - The number of trajectories to run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::noise::{amplitude_damping, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0);
+    /// let mut model = NoiseModel::new();
+    /// model.add_error(amplitude_damping(0.2));
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let counts = Simulator::sample_trajectories(&circuit, &initial_state, &model, 2000);
+    /// assert!(counts.get("0") > 300 && counts.get("0") < 500);
+    /// ```
+    pub fn sample_trajectories(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        shots: usize,
+    ) -> Counts {
+        rng::with_rng(|rng| {
+            Self::sample_trajectories_with_rng(circuit, initial_state, noise_model, shots, rng)
+        })
+    }
+
+    /// Samples noisy trajectories like [`Simulator::sample_trajectories`], drawing randomness
+    /// from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `shots` - The number of trajectories to run.
+    /// * `rng` - The random number generator to draw from.
+    pub fn sample_trajectories_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        shots: usize,
+        rng: &mut R,
+    ) -> Counts {
+        Self::sample_with_hooks_with_rng(
+            circuit,
+            initial_state,
+            noise_model,
+            shots,
+            &mut Hooks::new(),
+            rng,
+        )
+    }
+
+    /// Samples noisy trajectories like [`Simulator::sample_trajectories`], invoking `hooks` for
+    /// every gate, measurement and completed shot.
+    ///
+    /// The campaign stops early once a shot hook returns `ControlFlow::Break(())`, so the
+    /// returned counts may hold fewer than `shots` shots.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `shots` - The maximum number of trajectories to run.
+    /// * `hooks` - The callbacks to invoke.
+    pub fn sample_with_hooks(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        shots: usize,
+        hooks: &mut Hooks,
+    ) -> Counts {
+        rng::with_rng(|rng| {
+            Self::sample_with_hooks_with_rng(circuit, initial_state, noise_model, shots, hooks, rng)
+        })
+    }
+
+    /// Samples hooked trajectories like [`Simulator::sample_with_hooks`], drawing randomness
+    /// from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `shots` - The maximum number of trajectories to run.
+    /// * `hooks` - The callbacks to invoke.
+    /// * `rng` - The random number generator to draw from.
+    pub fn sample_with_hooks_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        shots: usize,
+        hooks: &mut Hooks,
+        rng: &mut R,
+    ) -> Counts {
+        let mut counts = Counts::new();
+        let all_qubits: Vec<usize> = (0..initial_state.len().trailing_zeros() as usize).collect();
+        for shot in 0..shots {
+            let result =
+                Self::run_with_hooks_with_rng(circuit, initial_state, noise_model, hooks, rng);
+            let (outcome, width) = if circuit.num_clbits() > 0 {
+                let outcome = result
+                    .clbits
+                    .iter()
+                    .rev()
+                    .fold(0, |value, &bit| (value << 1) | bit as usize);
+                (outcome, result.clbits.len())
+            } else {
+                let outcome = result.state.measure_with_rng(rng);
+                hooks.measurement(&all_qubits, outcome, &result.state);
+                (outcome, all_qubits.len())
+            };
+            counts.record(outcome, width);
+            if hooks.shot_complete(shot, outcome, &result.state).is_break() {
+                break;
+            }
+        }
+        counts
+    }
+
+    /// Samples noisy trajectories until the estimate of `criterion`'s observable converges,
+    /// running at most `max_shots` shots.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `max_shots` - The largest number of trajectories to run.
+    /// * `criterion` - The observable to estimate and its target standard error.
+    ///
+    /// # Returns
+    ///
+    /// * The counts of every shot taken and the final estimate of the observable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::convergence::Convergence;
+    /// use quantum_simulator::noise::NoiseModel;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.h(0);
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let criterion = Convergence::probability(1, 0.02);
+    /// let (counts, estimate) =
+    ///     Simulator::sample_until_converged(&circuit, &initial_state, &NoiseModel::new(), 100_000, &criterion);
+    /// assert!(counts.shots() < 1000);
+    /// assert!(estimate.standard_error < 0.02);
+    /// assert!((estimate.mean - 0.5).abs() < 0.1);
+    /// ```
+    pub fn sample_until_converged(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        max_shots: usize,
+        criterion: &Convergence,
+    ) -> (Counts, Estimate) {
+        rng::with_rng(|rng| {
+            Self::sample_until_converged_with_rng(
+                circuit,
+                initial_state,
+                noise_model,
+                max_shots,
+                criterion,
+                rng,
+            )
+        })
+    }
+
+    /// Samples trajectories like [`Simulator::sample_until_converged`], drawing randomness from
+    /// `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    /// * `max_shots` - The largest number of trajectories to run.
+    /// * `criterion` - The observable to estimate and its target standard error.
+    /// * `rng` - The random number generator to draw from.
+    pub fn sample_until_converged_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        noise_model: &NoiseModel,
+        max_shots: usize,
+        criterion: &Convergence,
+        rng: &mut R,
+    ) -> (Counts, Estimate) {
+        let mut estimate = Estimate::default();
+        let mut hooks = Hooks::new();
+        hooks.on_shot_complete(|_, outcome, _| {
+            estimate.push(criterion.value(outcome));
+            if criterion.is_converged(&estimate) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        let counts = Self::sample_with_hooks_with_rng(
+            circuit,
+            initial_state,
+            noise_model,
+            max_shots,
+            &mut hooks,
+            rng,
+        );
+        drop(hooks);
+        (counts, estimate)
+    }
+
+    /// Returns the gradient of the energy `⟨ψ(θ)|H|ψ(θ)⟩` with respect to the circuit's
+    /// parameters, where `|ψ(θ)⟩` is the circuit run from `|0…0⟩`.
+    ///
+    /// The derivatives are exact: by the parameter-shift rule, a rotation `exp(-iθG/2)` whose
+    /// generator `G` has eigenvalues `±1` contributes `(E(θ + π/2) - E(θ - π/2)) / 2`, so each
+    /// parametric gate costs two extra simulations. A parameter used by several gates, or scaled
+    /// like `2·γ`, accumulates the contributions by the chain rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A unitary circuit with [`Parameter`](crate::parameter::Parameter) angles.
+    /// * `observable` - The Hamiltonian to differentiate the expectation value of.
+    /// * `params` - The values by parameter name.
+    ///
+    /// # Returns
+    ///
+    /// * The partial derivatives, in the order of [`Circuit::parameters`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit measures, resets or conditions on classical bits, if `params` has no
+    /// value for one of its parameters, or if the circuit has fewer qubits than the observable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::parameter::Parameter;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use quantum_simulator::variational::Hamiltonian;
+    /// use std::collections::HashMap;
+    ///
+    /// // ⟨Z⟩ = cos θ after Ry(θ), so the derivative is -sin θ
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.ry(0, Parameter::new("theta"));
+    /// let z = Hamiltonian::parse("Z0").unwrap();
+    ///
+    /// let gradient = Simulator::gradient(&circuit, &z, &HashMap::from([("theta".to_string(), 0.3)]));
+    /// assert!((gradient[0] + 0.3f64.sin()).abs() < 1e-12);
+    /// ```
+    pub fn gradient(
+        circuit: &Circuit,
+        observable: &Hamiltonian,
+        params: &HashMap<String, f64>,
+    ) -> Vec<f64> {
+        assert!(
+            circuit.is_unitary(),
+            "the parameter-shift rule needs a unitary circuit"
+        );
+        let bound = circuit
+            .bind_parameters(params)
+            .unwrap_or_else(|error| panic!("{}", error));
+        let energy = |circuit: &Circuit| {
+            let mut qubit = Qubit::zero_state(circuit.num_qubits());
+            circuit.run(&mut qubit);
+            observable.expectation(&qubit)
+        };
+        // Evaluates the bound circuit with the angle of the instruction at `index` replaced
+        let shifted = |index: usize, operation: Operation| {
+            let mut shifted = Circuit::new(bound.num_qubits());
+            for (i, instruction) in bound.instructions().iter().enumerate() {
+                let instruction = if i == index {
+                    Instruction::new(operation.clone(), instruction.qubits.clone())
+                } else {
+                    instruction.clone()
+                };
+                shifted
+                    .add_instruction(instruction)
+                    .expect("the instructions come from a valid circuit");
+            }
+            energy(&shifted)
+        };
+
+        let names = circuit.parameters();
+        let mut gradient = vec![0.0; names.len()];
+        for (index, instruction) in circuit.instructions().iter().enumerate() {
+            let Operation::Parametric { gate, parameter } = &instruction.operation else {
+                continue;
+            };
+            let angle = parameter
+                .value(params)
+                .expect("every parameter was bound above");
+            let derivative = (shifted(index, gate.with_angle(Angle::Value(angle + FRAC_PI_2)))
+                - shifted(index, gate.with_angle(Angle::Value(angle - FRAC_PI_2))))
+                / 2.0;
+            let position = names
+                .binary_search_by(|name| name.as_str().cmp(parameter.name()))
+                .expect("the circuit's parameters include every parametric gate's");
+            gradient[position] += parameter.scale() * derivative;
+        }
+        gradient
+    }
+
+    /// Runs the circuit on a density matrix, evolving `ρ ↦ U ρ U†` for every instruction.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - The initial density matrix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::simulator::Simulator;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    ///
+    /// let rho = Simulator::run_density(&circuit, &DensityMatrix::new(2));
+    /// // Each half of a Bell pair is maximally mixed.
+    /// assert!((rho.partial_trace(&[0]).purity() - 0.5).abs() < 1e-12);
+    /// ```
+    pub fn run_density(circuit: &Circuit, initial_state: &DensityMatrix) -> DensityMatrix {
+        Self::run_density_with_noise(circuit, initial_state, &NoiseModel::new())
+    }
+
+    /// Runs the circuit on a density matrix under `noise_model`.
+    ///
+    /// Each instruction applies the idle decoherence of its qubits, the noisy gate and every error
+    /// channel attached to it, giving exactly the state that [`Simulator::run_trajectory`] reproduces on average.
+    /// Measurements split the state into one branch per value of the classical register, so
+    /// classically conditioned operations are exact too; the result is the sum over branches.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - The initial density matrix.
+    /// * `noise_model` - The coherent and incoherent errors to apply.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an assertion of the circuit fails for the mixture of all measurement branches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::noise::{amplitude_damping, NoiseModel};
+    /// use quantum_simulator::simulator::Simulator;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.x(0);
+    /// let mut model = NoiseModel::new();
+    /// model.add_error(amplitude_damping(0.2));
+    ///
+    /// let rho = Simulator::run_density_with_noise(&circuit, &DensityMatrix::new(1), &model);
+    /// assert!((rho.probabilities()[0] - 0.2).abs() < 1e-12);
+    /// ```
+    pub fn run_density_with_noise(
+        circuit: &Circuit,
+        initial_state: &DensityMatrix,
+        noise_model: &NoiseModel,
+    ) -> DensityMatrix {
+        let evolve = |rho: &mut DensityMatrix, applied: &Instruction| {
+            if applied.operation.is_unitary() {
+                let gate = noise_model.noisy_gate(&applied.operation.gate());
+                rho.apply_gate(&gate, &applied.qubits);
+            }
+            for (channel, qubits) in noise_model.errors_for(applied) {
+                rho.apply_channel(channel, &qubits);
+            }
+        };
+
+        // One unnormalized branch per value of the classical register.
+        let mut branches = vec![(vec![false; circuit.num_clbits()], initial_state.clone())];
+        let idle = noise_model.idle_errors(circuit);
+        let wait = |branches: &mut Vec<(Vec<bool>, DensityMatrix)>,
+                    waits: &[(KrausChannel, Vec<usize>)]| {
+            for (_, rho) in branches.iter_mut() {
+                for (channel, qubits) in waits {
+                    rho.apply_channel(channel, qubits);
+                }
+            }
+        };
+        for (index, instruction) in circuit.instructions().iter().enumerate() {
+            wait(&mut branches, &idle[index]);
+            if let Operation::Assert(assertion) = &instruction.operation {
+                // Assertions hold for the ensemble, not for each branch
+                let rho = Self::sum_branches(&branches).partial_trace(&instruction.qubits);
+                if let Err(error) = assertion.check(index, &rho) {
+                    panic!("{}", error);
+                }
+                continue;
+            }
+            let mut next: Vec<(Vec<bool>, DensityMatrix)> = vec![];
+            for (clbits, mut rho) in branches {
+                match &instruction.operation {
+                    Operation::Measure(clbit) => {
+                        for outcome in [false, true] {
+                            let mut projector = linalg::zeros(2, 2);
+                            projector[outcome as usize][outcome as usize] = Complex::new(1.0, 0.0);
+                            let mut projected = rho.clone();
+                            projected.apply_channel(
+                                &KrausChannel::new(vec![projector]),
+                                &instruction.qubits,
+                            );
+                            evolve(&mut projected, instruction);
+                            let mut clbits = clbits.clone();
+                            clbits[*clbit] = outcome;
+                            Self::add_branch(&mut next, clbits, projected);
+                        }
+                        continue;
+                    }
+                    Operation::Reset => {
+                        let mut decay = linalg::zeros(2, 2);
+                        decay[0][1] = Complex::new(1.0, 0.0);
+                        let mut keep = linalg::zeros(2, 2);
+                        keep[0][0] = Complex::new(1.0, 0.0);
+                        rho.apply_channel(
+                            &KrausChannel::new(vec![keep, decay]),
+                            &instruction.qubits,
+                        );
+                        evolve(&mut rho, instruction);
+                    }
+                    Operation::Conditional { clbit, operation } => {
+                        if clbits[*clbit] {
+                            let applied =
+                                Instruction::new((**operation).clone(), instruction.qubits.clone());
+                            evolve(&mut rho, &applied);
+                        }
+                    }
+                    _ => evolve(&mut rho, instruction),
+                }
+                Self::add_branch(&mut next, clbits, rho);
+            }
+            branches = next;
+        }
+        wait(&mut branches, &idle[circuit.instructions().len()]);
+
+        Self::sum_branches(&branches)
+    }
+
+    /// Returns the sum of the density matrices of all branches.
+    fn sum_branches(branches: &[(Vec<bool>, DensityMatrix)]) -> DensityMatrix {
+        let mut branches = branches.iter().map(|(_, rho)| rho);
+        let first = branches
+            .next()
+            .expect("there is always at least one branch")
+            .clone();
+        branches.fold(first, |mut sum, rho| {
+            linalg::add_assign(&mut sum.matrix, &rho.matrix);
+            sum
+        })
+    }
+
+    /// Adds `rho` to the branch with classical register `clbits`, creating it if needed.
+    fn add_branch(
+        branches: &mut Vec<(Vec<bool>, DensityMatrix)>,
+        clbits: Vec<bool>,
+        rho: DensityMatrix,
+    ) {
+        match branches
+            .iter_mut()
+            .find(|(existing, _)| *existing == clbits)
+        {
+            Some((_, sum)) => linalg::add_assign(&mut sum.matrix, &rho.matrix),
+            None => branches.push((clbits, rho)),
+        }
+    }
+
+    /// Applies one randomly chosen Kraus operator of `channel` to `qubits` and renormalizes.
+    ///
+    /// Returns the new state together with the index of the chosen operator.
+    fn sample_kraus<R: Rng + ?Sized>(
+        qubit: &Qubit,
+        channel: &KrausChannel,
+        qubits: &[usize],
+        rng: &mut R,
+    ) -> (Qubit, usize) {
+        let target = rng.gen::<f64>();
+        let mut cumulative = 0.0;
+        let mut chosen = None;
+        for (index, operator) in channel.operators.iter().enumerate() {
+            let (candidate, weight) = Self::apply_kraus(qubit, operator, qubits);
+            cumulative += weight;
+            if weight > 0.0 {
+                chosen = Some((candidate, weight, index));
+            }
+            if target < cumulative {
+                break;
+            }
+        }
+
+        let (candidate, weight, index) = chosen.unwrap_or_else(|| (qubit.clone(), 1.0, 0));
+        (Self::renormalized(candidate, weight), index)
+    }
+
+    /// Applies one Kraus operator to `qubits`, returning the unnormalized state and its weight.
+    fn apply_kraus(
+        qubit: &Qubit,
+        operator: &[Vec<Complex<f64>>],
+        qubits: &[usize],
+    ) -> (Qubit, f64) {
+        let mut candidate = qubit.clone();
+        Gate::new(operator.to_vec()).apply_to(&mut candidate, qubits);
+        let weight = candidate.state.iter().map(|a| a.norm_sqr()).sum();
+        (candidate, weight)
+    }
+
+    /// Divides the state by the square root of its weight `‖ψ‖²`.
+    fn renormalized(mut qubit: Qubit, weight: f64) -> Qubit {
+        let norm = weight.sqrt();
+        for amplitude in qubit.state.iter_mut() {
+            *amplitude /= norm;
+        }
+        qubit
+    }
+
+    /// Runs the circuit once and samples the final state `shots` times.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `shots` - The number of measurement samples to draw.
+    ///
+    /// # Returns
+    ///
+    /// * A `Counts` histogram keyed by bitstring, e.g. `{"01": 498, "10": 502}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::gates::pauli_x;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let mut circuit = Circuit::new(1);
+    /// circuit.add_gate(pauli_x());
+    ///
+    /// let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let counts = Simulator::sample(&circuit, &initial_state, 1000);
+    /// assert_eq!(counts.get("1"), 1000);
+    /// assert_eq!(counts.shots(), 1000);
+    /// ```
+    pub fn sample(circuit: &Circuit, initial_state: &[Complex<f64>], shots: usize) -> Counts {
+        Self::run(circuit, initial_state).sample(shots)
+    }
+
+    /// Runs the circuit once and samples the final state like [`Simulator::sample`], drawing
+    /// randomness from `rng`.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    /// * `initial_state` - A reference to a vector representing the initial state of the qubit.
+    /// * `shots` - The number of measurement samples to draw.
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let plus = vec![Complex::new(h, 0.0), Complex::new(h, 0.0)];
+    /// let circuit = Circuit::new(1);
+    /// let a = Simulator::sample_with_rng(&circuit, &plus, 100, &mut StdRng::seed_from_u64(9));
+    /// let b = Simulator::sample_with_rng(&circuit, &plus, 100, &mut StdRng::seed_from_u64(9));
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn sample_with_rng<R: Rng + ?Sized>(
+        circuit: &Circuit,
+        initial_state: &[Complex<f64>],
+        shots: usize,
+        rng: &mut R,
+    ) -> Counts {
+        Self::run(circuit, initial_state).sample_with_rng(shots, rng)
+    }
+
+    /// Seeds the generator used by measurement and sampling on the current thread.
+    ///
+    /// After seeding, `Qubit::measure*` and `Simulator::sample` produce the same outcomes on every
+    /// run until the seed is changed or cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed for the deterministic generator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use num_complex::Complex;
+    ///
+    /// let h = 1.0 / 2.0_f64.sqrt();
+    /// let plus = vec![Complex::new(h, 0.0), Complex::new(h, 0.0)];
+    ///
+    /// Simulator::set_seed(42);
+    /// let first = Simulator::sample(&Circuit::new(1), &plus, 100);
+    /// Simulator::set_seed(42);
+    /// let second = Simulator::sample(&Circuit::new(1), &plus, 100);
+    /// Simulator::clear_seed();
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn set_seed(seed: u64) {
+        rng::set_seed(seed);
+    }
+
+    /// Removes a seed set with [`Simulator::set_seed`], restoring entropy-seeded randomness.
+    pub fn clear_seed() {
+        rng::clear_seed();
+    }
+
+    /// Returns the gate count, gate time and peak state memory recorded on this thread since the
+    /// last [`Simulator::reset_stats`], while collection was on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::density::DensityMatrix;
+    /// use quantum_simulator::simulator::Simulator;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1);
+    ///
+    /// Simulator::enable_stats();
+    /// Simulator::reset_stats();
+    /// Simulator::run_density(&circuit, &DensityMatrix::new(2));
+    /// let stats = Simulator::stats();
+    /// assert_eq!(stats.gates, 2);
+    /// assert_eq!(stats.peak_memory_bytes, 16 * 16);
+    /// assert!(stats.to_string().starts_with("2 gates in "));
+    ///
+    /// // Nothing is recorded once collection is off again
+    /// Simulator::disable_stats();
+    /// Simulator::run_density(&circuit, &DensityMatrix::new(2));
+    /// assert_eq!(Simulator::stats().gates, 2);
+    /// ```
+    pub fn stats() -> Stats {
+        stats::snapshot()
+    }
+
+    /// Clears the counters reported by [`Simulator::stats`] on this thread.
+    pub fn reset_stats() {
+        stats::reset();
+    }
+
+    /// Starts counting and timing gates on this thread for [`Simulator::stats`]; collection is
+    /// off by default so that ordinary runs do not read the clock around every gate.
+    pub fn enable_stats() {
+        stats::set_enabled(true);
+    }
+
+    /// Stops the collection started by [`Simulator::enable_stats`], keeping the counters.
+    pub fn disable_stats() {
+        stats::set_enabled(false);
+    }
+
+    /// Replaces the numerical tolerances used by simulation as well as by gate, state and channel
+    /// validation, on every thread, until they are set again or cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `tolerances` - The tolerances to use.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the tolerances are in effect.
+    /// * `Err(QuantumError::Unsupported)` - If a threshold is negative or NaN; the tolerances in
+    ///   effect are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use num_complex::Complex;
+    /// use quantum_simulator::gates::Gate;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use quantum_simulator::tolerance::Tolerances;
+    ///
+    /// // Entries rounded to four digits are unitary only to about 1e-4
+    /// let h = Complex::new(0.7071, 0.0);
+    /// let rounded = vec![vec![h, h], vec![h, -h]];
+    /// assert!(Gate::from_unitary(rounded.clone()).is_err());
+    ///
+    /// Simulator::set_tolerances(Tolerances::default().scaled(1e7).unwrap()).unwrap();
+    /// assert!(Gate::from_unitary(rounded.clone()).is_ok());
+    ///
+    /// // Other threads see the same tolerances
+    /// let relaxed = std::thread::spawn(move || Gate::from_unitary(rounded).is_ok());
+    /// assert!(relaxed.join().unwrap());
+    /// Simulator::clear_tolerances();
+    /// assert_eq!(Simulator::tolerances(), Tolerances::default());
+    /// ```
+    pub fn set_tolerances(tolerances: Tolerances) -> Result<(), QuantumError> {
+        tolerance::set(tolerances)
+    }
+
+    /// Restores the default tolerances after [`Simulator::set_tolerances`].
+    pub fn clear_tolerances() {
+        tolerance::clear();
+    }
+
+    /// Returns the numerical tolerances in effect.
+    pub fn tolerances() -> Tolerances {
+        tolerance::current()
+    }
+
+    /// Makes `library` the gate library of the current thread, until it is set again or
+    /// cleared, so its gates can be added with [`Circuit::custom`] and are read and written by
+    /// name in QASM; see [`GateLibrary`].
+    ///
+    /// # Arguments
+    ///
+    /// * `library` - The custom gates to register.
+    pub fn set_gate_library(library: GateLibrary) {
+        library::set(library);
+    }
+
+    /// Restores the empty gate library after [`Simulator::set_gate_library`].
+    pub fn clear_gate_library() {
+        library::clear();
+    }
+
+    /// Returns the gate library in effect on the current thread.
+    pub fn gate_library() -> GateLibrary {
+        library::current().as_ref().clone()
+    }
+
+    #[cfg(feature = "chunked")]
+    /// Limits the memory that out-of-core runs on the current thread may map at once, until it
+    /// is set again or cleared; see [`Simulator::run_out_of_core`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The budget in bytes of amplitudes.
+    pub fn set_memory_budget(bytes: usize) {
+        chunked::set_budget(bytes);
+    }
+
+    #[cfg(feature = "chunked")]
+    /// Restores the default memory budget, [`DEFAULT_MEMORY_BUDGET`], after
+    /// [`Simulator::set_memory_budget`].
+    pub fn clear_memory_budget() {
+        chunked::clear_budget();
+    }
+
+    #[cfg(feature = "chunked")]
+    /// Returns the memory budget in effect on the current thread.
+    pub fn memory_budget() -> usize {
+        chunked::budget().unwrap_or(DEFAULT_MEMORY_BUDGET)
+    }
+
+    #[cfg(feature = "chunked")]
+    /// Runs the circuit from `|0…0⟩` with the state stored on disk in chunks, mapping no more
+    /// than the [memory budget](Simulator::set_memory_budget) into memory at once, so circuits
+    /// whose state does not fit in memory still run, slowly. The state file lives in the
+    /// system's temporary directory; use [`ChunkedBackend`] directly to choose another.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - A reference to the quantum circuit to be run.
+    ///
+    /// # Returns
+    ///
+    /// * The backend holding the final state with the final classical register, or the error
+    ///   that stopped the run, e.g. `QuantumError::BackendUnavailable` if the state file cannot
+    ///   be created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::backend::Backend;
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::gates::pauli_z;
+    /// use quantum_simulator::simulator::Simulator;
+    ///
+    /// let mut circuit = Circuit::new(14);
+    /// circuit.h(13);
+    /// for qubit in 0..13 {
+    ///     circuit.cnot(13, qubit);
+    /// }
+    /// circuit.measure(0, 0);
+    ///
+    /// Simulator::set_memory_budget(1 << 20);
+    /// let (backend, clbits) = Simulator::run_out_of_core(&circuit).unwrap();
+    /// assert!(backend.chunked_state().num_chunks() > 1);
+    /// let parity = if clbits[0] { -1.0 } else { 1.0 };
+    /// assert!((backend.expectation(&pauli_z(), &[13]) - parity).abs() < 1e-12);
+    /// Simulator::clear_memory_budget();
+    /// ```
+    pub fn run_out_of_core(circuit: &Circuit) -> Result<(ChunkedBackend, Vec<bool>), QuantumError> {
+        let mut backend = ChunkedBackend::new(circuit.num_qubits(), Self::memory_budget())?;
+        let clbits = backend.run(circuit)?;
+        Ok((backend, clbits))
+    }
+}
+
+/// Where the random choices of a trajectory come from.
+enum Choices<'a, R: ?Sized> {
+    /// Draw every choice from a random number generator.
+    Sampled(&'a mut R),
+    /// Take every choice from a recorded log, checking each event against it.
+    Recorded {
+        events: Peekable<Enumerate<slice::Iter<'a, Event>>>,
+        len: usize,
+    },
+}
+
+impl<R: Rng + ?Sized> Choices<'_, R> {
+    /// Chooses the outcome of measuring `target`.
+    fn outcome(&mut self, qubit: &Qubit, target: usize) -> Result<usize, QuantumError> {
+        let probability_of_one = qubit.probability_of_one(target);
+        match self {
+            Choices::Sampled(rng) => Ok((rng.gen::<f64>() < probability_of_one) as usize),
+            Choices::Recorded { events, len } => match events.peek() {
+                Some(&(position, Event::Measurement { outcome, .. })) => {
+                    let probability = match outcome {
+                        0 => 1.0 - probability_of_one,
+                        1 => probability_of_one,
+                        _ => 0.0,
+                    };
+                    if probability > 0.0 {
+                        Ok(*outcome)
+                    } else {
+                        Err(QuantumError::ReplayMismatch { event: position })
+                    }
+                }
+                next => Err(mismatch(next.map(|(position, _)| *position), *len)),
+            },
+        }
+    }
+
+    /// Chooses a Kraus operator of `channel`, returning the renormalized state and its index.
+    fn kraus(
+        &mut self,
+        qubit: &Qubit,
+        channel: &KrausChannel,
+        qubits: &[usize],
+    ) -> Result<(Qubit, usize), QuantumError> {
+        match self {
+            Choices::Sampled(rng) => Ok(Simulator::sample_kraus(qubit, channel, qubits, *rng)),
+            Choices::Recorded { events, len } => match events.peek() {
+                Some(&(position, Event::Channel { operator, .. }))
+                    if *operator < channel.operators.len() =>
+                {
+                    let (next, weight) =
+                        Simulator::apply_kraus(qubit, &channel.operators[*operator], qubits);
+                    if weight <= 0.0 {
+                        return Err(QuantumError::ReplayMismatch { event: position });
+                    }
+                    Ok((Simulator::renormalized(next, weight), *operator))
+                }
+                next => Err(mismatch(next.map(|(position, _)| *position), *len)),
+            },
+        }
+    }
+
+    /// Appends `event` to `events`, first checking it against the log when replaying.
+    fn record(&mut self, events: &mut EventLog, event: Event) -> Result<(), QuantumError> {
+        if let Choices::Recorded {
+            events: recorded,
+            len,
+        } = self
+        {
+            match recorded.next() {
+                Some((_, recorded_event)) if *recorded_event == event => {}
+                next => return Err(mismatch(next.map(|(position, _)| position), *len)),
+            }
+        }
+        events.push(event);
+        Ok(())
+    }
+
+    /// Fails if a replayed log has events left over.
+    fn finish(&mut self) -> Result<(), QuantumError> {
+        match self {
+            Choices::Recorded { events, .. } => match events.next() {
+                Some((position, _)) => Err(QuantumError::ReplayMismatch { event: position }),
+                None => Ok(()),
+            },
+            Choices::Sampled(_) => Ok(()),
+        }
+    }
+}
+
+/// Returns the mismatch error for the log entry at `position`, or for a log of `len` events that
+/// ended early.
+fn mismatch(position: Option<usize>, len: usize) -> QuantumError {
+    QuantumError::ReplayMismatch {
+        event: position.unwrap_or(len),
+    }
 }
@@ -0,0 +1,278 @@
+//! This module defines `Snapshot`, a cheap copy-on-write capture of a state vector, and the
+//! `Trace` of snapshots recorded by stepwise simulation.
+//!
+//! A snapshot splits the amplitudes into fixed-size chunks held behind an [`Arc`]. Cloning a
+//! snapshot only bumps reference counts, and [`Snapshot::capture_next`] reuses every chunk of the
+//! previous snapshot that a gate left unchanged. A trace of snapshots after each gate therefore
+//! costs memory only for the chunks that actually changed, e.g. just the half of the state a
+//! diagonal gate on a high qubit touches, rather than one full state vector per step.
+
+use crate::circuit::{Instruction, Operation};
+use crate::qubit::Qubit;
+use num_complex::Complex;
+use std::sync::Arc;
+
+/// Number of amplitudes per chunk (16 KiB of `Complex<f64>`).
+const CHUNK_LEN: usize = 1 << 10;
+
+/// A `Snapshot` is an immutable-by-default capture of a state vector whose chunks are shared
+/// with clones and with the snapshots it was derived from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    chunks: Vec<Arc<Vec<Complex<f64>>>>,
+    len: usize,
+}
+
+impl Snapshot {
+    /// Captures a copy of `state` that shares no chunks with any other snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The amplitudes to capture.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::snapshot::Snapshot;
+    /// use num_complex::Complex;
+    ///
+    /// let snapshot = Snapshot::capture(&[Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)]);
+    /// assert_eq!(snapshot.len(), 2);
+    /// assert_eq!(snapshot.get(1), Complex::new(1.0, 0.0));
+    /// ```
+    pub fn capture(state: &[Complex<f64>]) -> Self {
+        Snapshot {
+            chunks: state
+                .chunks(CHUNK_LEN)
+                .map(|chunk| Arc::new(chunk.to_vec()))
+                .collect(),
+            len: state.len(),
+        }
+    }
+
+    /// Captures `state` as the successor of this snapshot, sharing every chunk whose amplitudes
+    /// are unchanged and copying only the others.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The amplitudes to capture, usually the state one gate after this snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `state` does not have the same length as the snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::pauli_z;
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::snapshot::Snapshot;
+    /// use num_complex::Complex;
+    ///
+    /// // An equal superposition over 12 qubits, i.e. four chunks of 1024 amplitudes
+    /// let amplitude = Complex::new(1.0 / 64.0, 0.0);
+    /// let mut qubit = Qubit::from_state(vec![amplitude; 1 << 12]);
+    /// let before = Snapshot::capture(&qubit.state);
+    ///
+    /// // Z on the top qubit only flips the sign of the upper half of the state
+    /// pauli_z().apply_to(&mut qubit, &[11]);
+    /// let after = before.capture_next(&qubit.state);
+    /// assert_eq!(after.shared_chunks(&before), 2);
+    /// assert_eq!(after.to_qubit(), qubit);
+    /// ```
+    pub fn capture_next(&self, state: &[Complex<f64>]) -> Self {
+        assert_eq!(
+            state.len(),
+            self.len,
+            "state length must match the previous snapshot"
+        );
+        Snapshot {
+            chunks: self
+                .chunks
+                .iter()
+                .zip(state.chunks(CHUNK_LEN))
+                .map(|(previous, chunk)| {
+                    if previous.as_slice() == chunk {
+                        Arc::clone(previous)
+                    } else {
+                        Arc::new(chunk.to_vec())
+                    }
+                })
+                .collect(),
+            len: self.len,
+        }
+    }
+
+    /// Returns the number of amplitudes in the snapshot.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the snapshot holds no amplitudes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the amplitude of basis state `index`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The basis-state index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn get(&self, index: usize) -> Complex<f64> {
+        assert!(index < self.len, "amplitude index out of range");
+        self.chunks[index / CHUNK_LEN][index % CHUNK_LEN]
+    }
+
+    /// Sets the amplitude of basis state `index`, first copying its chunk if another snapshot
+    /// shares it, so no other snapshot observes the change.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The basis-state index.
+    /// * `amplitude` - The new amplitude.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::snapshot::Snapshot;
+    /// use num_complex::Complex;
+    ///
+    /// let original = Snapshot::capture(&[Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]);
+    /// let mut edited = original.clone();
+    /// edited.set(0, Complex::new(0.0, 0.0));
+    /// edited.set(1, Complex::new(1.0, 0.0));
+    /// assert_eq!(original.get(0), Complex::new(1.0, 0.0));
+    /// assert_eq!(edited.get(1), Complex::new(1.0, 0.0));
+    /// ```
+    pub fn set(&mut self, index: usize, amplitude: Complex<f64>) {
+        assert!(index < self.len, "amplitude index out of range");
+        Arc::make_mut(&mut self.chunks[index / CHUNK_LEN])[index % CHUNK_LEN] = amplitude;
+    }
+
+    /// Returns an iterator over the amplitudes in basis-state order.
+    pub fn iter(&self) -> impl Iterator<Item = &Complex<f64>> {
+        self.chunks.iter().flat_map(|chunk| chunk.iter())
+    }
+
+    /// Returns the number of chunks this snapshot shares with `other`, i.e. holds without a copy
+    /// of its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The snapshot to compare with.
+    pub fn shared_chunks(&self, other: &Snapshot) -> usize {
+        self.chunks
+            .iter()
+            .zip(&other.chunks)
+            .filter(|(a, b)| Arc::ptr_eq(a, b))
+            .count()
+    }
+
+    /// Copies the snapshot into a fresh `Qubit`, e.g. to resume simulation from this point.
+    pub fn to_qubit(&self) -> Qubit {
+        Qubit::from_state(self.iter().copied().collect())
+    }
+}
+
+impl From<&Qubit> for Snapshot {
+    fn from(qubit: &Qubit) -> Self {
+        Snapshot::capture(&qubit.state)
+    }
+}
+
+/// The state after one instruction of a stepwise simulation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// The index of the instruction in the circuit.
+    pub instruction: usize,
+    /// The label of a [`Operation::Snapshot`] instruction, `None` for every other instruction.
+    pub label: Option<String>,
+    /// The state after the instruction and its noise.
+    pub state: Snapshot,
+}
+
+/// A `Trace` holds the state of a simulation before the first instruction and after every
+/// instruction, e.g. for debugging a circuit or animating its evolution.
+///
+/// Consecutive states share every chunk a step left unchanged, see [`Snapshot::capture_next`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace {
+    initial: Snapshot,
+    steps: Vec<Step>,
+    clbits: Vec<bool>,
+}
+
+impl Trace {
+    /// Creates a trace with no steps starting from `initial_state`.
+    pub(crate) fn new(initial_state: &[Complex<f64>]) -> Self {
+        Trace {
+            initial: Snapshot::capture(initial_state),
+            steps: vec![],
+            clbits: vec![],
+        }
+    }
+
+    /// Records the state after instruction `index`.
+    pub(crate) fn record(&mut self, index: usize, instruction: &Instruction, qubit: &Qubit) {
+        let label = match &instruction.operation {
+            Operation::Snapshot(label) => Some(label.clone()),
+            _ => None,
+        };
+        let state = self.final_state().capture_next(&qubit.state);
+        self.steps.push(Step {
+            instruction: index,
+            label,
+            state,
+        });
+    }
+
+    /// Sets the final classical register.
+    pub(crate) fn set_clbits(&mut self, clbits: Vec<bool>) {
+        self.clbits = clbits;
+    }
+
+    /// Returns the state before the first instruction.
+    pub fn initial_state(&self) -> &Snapshot {
+        &self.initial
+    }
+
+    /// Returns the state after each instruction, in circuit order.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Returns the state after the last instruction.
+    pub fn final_state(&self) -> &Snapshot {
+        self.steps.last().map_or(&self.initial, |step| &step.state)
+    }
+
+    /// Returns the final classical register, where element `k` is classical bit `k`.
+    pub fn clbits(&self) -> &[bool] {
+        &self.clbits
+    }
+
+    /// Returns the initial state followed by the state after every instruction.
+    pub fn states(&self) -> impl Iterator<Item = &Snapshot> {
+        std::iter::once(&self.initial).chain(self.steps.iter().map(|step| &step.state))
+    }
+
+    /// Returns the state at the first snapshot instruction labeled `label`.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label passed to [`Circuit::snapshot`](crate::circuit::Circuit::snapshot).
+    pub fn snapshot(&self, label: &str) -> Option<&Snapshot> {
+        self.steps
+            .iter()
+            .find(|step| step.label.as_deref() == Some(label))
+            .map(|step| &step.state)
+    }
+}
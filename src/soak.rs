@@ -0,0 +1,461 @@
+//! This module runs a long stability test of the simulator, e.g. before relying on it for
+//! research.
+//!
+//! A [`Soak`] generates random circuits until its time runs out, alternating between Clifford
+//! circuits and circuits from a universal gate set, and runs each on every backend that is always
+//! available: the double-precision [`CpuBackend`], the single-precision [`F32Backend`], the
+//! density-matrix simulator and, for Clifford circuits, the [`StabilizerBackend`]. After every
+//! circuit it checks that each state is normalized and that the backends agree; between circuits
+//! it samples the resident memory of the process, which must not keep growing. The
+//! [`SoakReport`] lists every violated invariant with the circuit that violated it.
+
+use crate::backend::{Backend, CpuBackend};
+use crate::benchmarking::{random_clifford_circuit, random_universal_circuit};
+use crate::circuit::Circuit;
+use crate::density::DensityMatrix;
+use crate::gates::pauli_z;
+use crate::precision::F32Backend;
+use crate::qasm::{self, QasmVersion};
+use crate::qubit::Qubit;
+use crate::simulator::Simulator;
+use crate::stabilizer::StabilizerBackend;
+use crate::tolerance;
+use num_complex::Complex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use std::fmt;
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Largest difference between a single-precision amplitude and the double-precision one.
+const F32_TOLERANCE: f64 = 1e-4;
+
+/// Largest difference between the `⟨Z⟩` of a qubit on the stabilizer backend and on the CPU.
+const STABILIZER_TOLERANCE: f64 = 1e-6;
+
+/// Widest random circuit a soak test runs; every circuit is also run as a density matrix of
+/// `4ⁿ` entries.
+pub const MAX_SOAK_QUBITS: usize = 10;
+
+/// An invariant checked by a soak test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Invariant {
+    /// A state has unit norm, or a density matrix unit trace.
+    Normalization,
+    /// Every backend gives the CPU backend's outcome probabilities.
+    Agreement,
+    /// The resident memory of the process does not grow by more than the allowed amount.
+    Memory,
+}
+
+impl fmt::Display for Invariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Invariant::Normalization => "normalization",
+            Invariant::Agreement => "agreement",
+            Invariant::Memory => "memory",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One violation of an invariant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoakFailure {
+    /// The iteration that violated the invariant, counting from 0.
+    pub iteration: usize,
+    /// The invariant.
+    pub invariant: Invariant,
+    /// The name of the backend at fault, e.g. `cpu-f32`, or `process` for memory growth.
+    pub backend: String,
+    /// What went wrong.
+    pub message: String,
+    /// The circuit that violated the invariant, run from `|0…0⟩`, or `None` for memory growth.
+    pub circuit: Option<Circuit>,
+}
+
+/// The results of a soak test.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoakReport {
+    /// The number of random circuits run.
+    pub iterations: usize,
+    /// The number of invariants checked.
+    pub checks: usize,
+    /// The time the test ran for.
+    pub elapsed: Duration,
+    /// The resident memory in bytes after the first circuit, if the platform reports it.
+    pub initial_memory: Option<usize>,
+    /// The largest resident memory in bytes sampled during the test.
+    pub peak_memory: Option<usize>,
+    /// The resident memory in bytes after the last circuit.
+    pub final_memory: Option<usize>,
+    /// Every violated invariant, in the order found.
+    pub failures: Vec<SoakFailure>,
+}
+
+impl SoakReport {
+    /// Returns `true` if no invariant was violated.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns how many bytes the resident memory grew between the first and the last circuit,
+    /// or `None` if the platform does not report it.
+    pub fn memory_growth(&self) -> Option<usize> {
+        Some(self.final_memory?.saturating_sub(self.initial_memory?))
+    }
+
+    /// Returns the report as a JSON object with the fields of the struct, the circuits as
+    /// OpenQASM 2.0 source and the elapsed time in seconds.
+    pub fn to_json_value(&self) -> Value {
+        let failures: Vec<Value> = self
+            .failures
+            .iter()
+            .map(|failure| {
+                let circuit = failure
+                    .circuit
+                    .as_ref()
+                    .and_then(|circuit| qasm::to_qasm(circuit, QasmVersion::V2).ok());
+                json!({
+                    "iteration": failure.iteration,
+                    "invariant": failure.invariant.to_string(),
+                    "backend": failure.backend,
+                    "message": failure.message,
+                    "circuit": circuit,
+                })
+            })
+            .collect();
+        json!({
+            "passed": self.passed(),
+            "iterations": self.iterations,
+            "checks": self.checks,
+            "elapsed": self.elapsed.as_secs_f64(),
+            "initial_memory": self.initial_memory,
+            "peak_memory": self.peak_memory,
+            "final_memory": self.final_memory,
+            "failures": failures,
+        })
+    }
+}
+
+impl fmt::Display for SoakReport {
+    /// Writes a summary line, the memory samples and every failure with its circuit.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} circuits, {} checks, {} failures in {:.1} s",
+            if self.passed() { "passed" } else { "FAILED" },
+            self.iterations,
+            self.checks,
+            self.failures.len(),
+            self.elapsed.as_secs_f64()
+        )?;
+        match (self.initial_memory, self.peak_memory, self.final_memory) {
+            (Some(initial), Some(peak), Some(last)) => writeln!(
+                f,
+                "memory: {:.1} MiB initially, {:.1} MiB at peak, {:.1} MiB finally",
+                mebibytes(initial),
+                mebibytes(peak),
+                mebibytes(last)
+            )?,
+            _ => writeln!(f, "memory: not reported on this platform")?,
+        }
+        for failure in &self.failures {
+            writeln!(
+                f,
+                "#{} {} on {}: {}",
+                failure.iteration, failure.invariant, failure.backend, failure.message
+            )?;
+            if let Some(qasm) = failure
+                .circuit
+                .as_ref()
+                .and_then(|circuit| qasm::to_qasm(circuit, QasmVersion::V2).ok())
+            {
+                for line in qasm.lines() {
+                    writeln!(f, "    {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A randomized stability test across backends.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::soak::Soak;
+/// use std::time::Duration;
+///
+/// let report = Soak::new(Duration::from_secs(60))
+///     .with_max_iterations(10)
+///     .with_seed(3)
+///     .run();
+/// assert!(report.passed(), "{}", report);
+/// assert_eq!(report.iterations, 10);
+/// assert!(report.to_string().starts_with("passed: 10 circuits"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Soak {
+    /// How long to keep generating circuits.
+    pub duration: Duration,
+    /// The most circuits run, or `None` to run until the time is up.
+    pub max_iterations: Option<usize>,
+    /// The widest random circuit, clamped to between 1 and [`MAX_SOAK_QUBITS`] when the test
+    /// runs.
+    pub max_qubits: usize,
+    /// The most layers of a random circuit.
+    pub max_depth: usize,
+    /// The largest growth of the resident memory, in bytes, that passes.
+    pub max_memory_growth: usize,
+    /// The seed of the random circuits; equal seeds give equal circuits.
+    pub seed: u64,
+}
+
+impl Soak {
+    /// Creates a soak test running circuits of up to 6 qubits and 20 layers for `duration`,
+    /// allowing the resident memory to grow by 64 MiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long to keep generating circuits.
+    pub fn new(duration: Duration) -> Self {
+        Soak {
+            duration,
+            max_iterations: None,
+            max_qubits: 6,
+            max_depth: 20,
+            max_memory_growth: 64 << 20,
+            seed: 0,
+        }
+    }
+
+    /// Returns a copy of the test that stops after `max_iterations` circuits, even if time is
+    /// left.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = Some(max_iterations);
+        self
+    }
+
+    /// Returns a copy of the test that runs circuits of up to `max_qubits` qubits, clamped to
+    /// between 1 and [`MAX_SOAK_QUBITS`].
+    pub fn with_max_qubits(mut self, max_qubits: usize) -> Self {
+        self.max_qubits = max_qubits.clamp(1, MAX_SOAK_QUBITS);
+        self
+    }
+
+    /// Returns a copy of the test that draws its circuits from `seed`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Runs the test until the time is up or the most circuits were run.
+    pub fn run(&self) -> SoakReport {
+        self.run_with(|_| {})
+    }
+
+    /// Runs the test like [`Soak::run`], passing the report so far to `progress` after every
+    /// circuit, e.g. to print a status line.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - Called with the report so far after every circuit.
+    ///
+    /// # Returns
+    ///
+    /// * The report of the whole test.
+    pub fn run_with(&self, mut progress: impl FnMut(&SoakReport)) -> SoakReport {
+        let started = Instant::now();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut report = SoakReport {
+            iterations: 0,
+            checks: 0,
+            elapsed: Duration::ZERO,
+            initial_memory: None,
+            peak_memory: None,
+            final_memory: None,
+            failures: vec![],
+        };
+        while started.elapsed() < self.duration
+            && self
+                .max_iterations
+                .is_none_or(|max| report.iterations < max)
+        {
+            let num_qubits = rng.gen_range(1..=self.max_qubits.clamp(1, MAX_SOAK_QUBITS));
+            let depth = rng.gen_range(1..=self.max_depth.max(1));
+            let clifford = report.iterations.is_multiple_of(2);
+            let circuit = if clifford {
+                random_clifford_circuit(num_qubits, depth, rng.gen())
+            } else {
+                random_universal_circuit(num_qubits, depth, rng.gen())
+            };
+            check_circuit(&circuit, clifford, report.iterations, &mut report);
+            report.iterations += 1;
+
+            let memory = resident_memory();
+            report.initial_memory = report.initial_memory.or(memory);
+            report.peak_memory = report.peak_memory.max(memory);
+            report.final_memory = memory;
+            report.elapsed = started.elapsed();
+            progress(&report);
+        }
+
+        if let Some(growth) = report.memory_growth() {
+            report.checks += 1;
+            if growth > self.max_memory_growth {
+                report.failures.push(SoakFailure {
+                    iteration: report.iterations.saturating_sub(1),
+                    invariant: Invariant::Memory,
+                    backend: "process".to_string(),
+                    message: format!(
+                        "resident memory grew by {:.1} MiB, more than the allowed {:.1} MiB",
+                        mebibytes(growth),
+                        mebibytes(self.max_memory_growth)
+                    ),
+                    circuit: None,
+                });
+            }
+        }
+        report.elapsed = started.elapsed();
+        report
+    }
+}
+
+/// Runs `circuit` on every backend and records the violated invariants in `report`.
+fn check_circuit(circuit: &Circuit, clifford: bool, iteration: usize, report: &mut SoakReport) {
+    let num_qubits = circuit.num_qubits();
+    let normalization = tolerance::current().normalization;
+    let mut fail = |invariant, backend: &str, message: String| {
+        report.failures.push(SoakFailure {
+            iteration,
+            invariant,
+            backend: backend.to_string(),
+            message,
+            circuit: Some(circuit.clone()),
+        })
+    };
+    let mut checks = 0;
+
+    let mut cpu = CpuBackend::new(num_qubits);
+    if let Err(error) = cpu.run(circuit) {
+        fail(Invariant::Agreement, cpu.name(), error.to_string());
+        report.checks += 1;
+        return;
+    }
+    let expected = cpu.amplitudes().expect("the CPU backend holds amplitudes");
+    let probabilities: Vec<f64> = expected.iter().map(|a| a.norm_sqr()).collect();
+    checks += 1;
+    let norm: f64 = probabilities.iter().sum();
+    if (norm - 1.0).abs() > normalization {
+        fail(
+            Invariant::Normalization,
+            cpu.name(),
+            format!("the state has norm {}", norm),
+        );
+    }
+
+    let mut f32_backend = F32Backend::new(num_qubits);
+    checks += 2;
+    match f32_backend.run(circuit) {
+        Ok(_) => {
+            let actual = f32_backend
+                .amplitudes()
+                .expect("the single-precision backend holds amplitudes");
+            let norm: f64 = actual.iter().map(|a| a.norm_sqr()).sum();
+            if (norm - 1.0).abs() > F32_TOLERANCE {
+                fail(
+                    Invariant::Normalization,
+                    f32_backend.name(),
+                    format!("the state has norm {}", norm),
+                );
+            }
+            let difference = largest_difference(&expected, &actual);
+            if difference > F32_TOLERANCE {
+                fail(
+                    Invariant::Agreement,
+                    f32_backend.name(),
+                    format!("an amplitude differs from the CPU's by {:e}", difference),
+                );
+            }
+        }
+        Err(error) => fail(Invariant::Agreement, f32_backend.name(), error.to_string()),
+    }
+
+    let rho = Simulator::run_density(
+        circuit,
+        &DensityMatrix::from_state(&Qubit::zero_state(num_qubits)),
+    );
+    checks += 2;
+    let trace = rho.trace();
+    if (trace - 1.0).abs() > normalization {
+        fail(
+            Invariant::Normalization,
+            "density",
+            format!("the density matrix has trace {}", trace),
+        );
+    }
+    let difference = rho
+        .probabilities()
+        .iter()
+        .zip(&probabilities)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max);
+    if difference > normalization {
+        fail(
+            Invariant::Agreement,
+            "density",
+            format!("a probability differs from the CPU's by {:e}", difference),
+        );
+    }
+
+    if clifford {
+        let mut stabilizer = StabilizerBackend::new(num_qubits);
+        checks += 1;
+        match stabilizer.run(circuit) {
+            Ok(_) => {
+                for qubit in 0..num_qubits {
+                    let actual = stabilizer.expectation(&pauli_z(), &[qubit]);
+                    let expected = cpu.expectation(&pauli_z(), &[qubit]);
+                    if (actual - expected).abs() > STABILIZER_TOLERANCE {
+                        fail(
+                            Invariant::Agreement,
+                            stabilizer.name(),
+                            format!("⟨Z{}⟩ is {} but {} on the CPU", qubit, actual, expected),
+                        );
+                        break;
+                    }
+                }
+            }
+            Err(error) => fail(Invariant::Agreement, stabilizer.name(), error.to_string()),
+        }
+    }
+    report.checks += checks;
+}
+
+/// Returns the largest distance between corresponding amplitudes.
+fn largest_difference(expected: &[Complex<f64>], actual: &[Complex<f64>]) -> f64 {
+    if expected.len() != actual.len() {
+        return f64::INFINITY;
+    }
+    expected
+        .iter()
+        .zip(actual)
+        .map(|(a, b)| (a - b).norm())
+        .fold(0.0, f64::max)
+}
+
+/// Returns the resident memory of the process in bytes, read from `/proc/self/status`, or `None`
+/// on platforms without it.
+fn resident_memory() -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kibibytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kibibytes * 1024)
+}
+
+fn mebibytes(bytes: usize) -> f64 {
+    bytes as f64 / (1 << 20) as f64
+}
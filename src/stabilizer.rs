@@ -0,0 +1,380 @@
+//! This module provides a stabilizer-tableau backend for Clifford circuits.
+//!
+//! A state reachable from `|0…0⟩` by Clifford gates (H, S, CNOT, Paulis, ...) and measurements
+//! is fully described by `n` stabilizer generators, so the [`StabilizerBackend`] stores a
+//! `2n × 2n` bit tableau (Aaronson–Gottesman) instead of `2^n` amplitudes. Gates cost `O(n)`
+//! and measurements `O(n²)`, which makes thousands of qubits practical for error-correction
+//! experiments. Any gate is accepted as long as it maps Pauli operators to Pauli operators; the
+//! tableau update is derived from its matrix.
+
+use crate::backend::{self, Backend};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::linalg;
+use crate::tolerance;
+use num_complex::Complex;
+use rand::{Rng, RngCore};
+
+/// A Hermitian Pauli operator `(-1)^sign ⊗ⱼ Pⱼ`, where qubit `j` carries `I`, `X`, `Z` or `Y`
+/// for bits `(x, z)` = `(0, 0)`, `(1, 0)`, `(0, 1)` or `(1, 1)`.
+#[derive(Clone, Debug, PartialEq)]
+struct PauliRow {
+    x: Vec<u64>,
+    z: Vec<u64>,
+    sign: bool,
+}
+
+impl PauliRow {
+    fn identity(num_qubits: usize) -> Self {
+        let words = num_qubits.div_ceil(64);
+        PauliRow {
+            x: vec![0; words],
+            z: vec![0; words],
+            sign: false,
+        }
+    }
+
+    fn x_bit(&self, qubit: usize) -> bool {
+        self.x[qubit / 64] >> (qubit % 64) & 1 == 1
+    }
+
+    fn z_bit(&self, qubit: usize) -> bool {
+        self.z[qubit / 64] >> (qubit % 64) & 1 == 1
+    }
+
+    fn set_bits(&mut self, qubit: usize, x: bool, z: bool) {
+        let mask = 1 << (qubit % 64);
+        let word = qubit / 64;
+        self.x[word] = (self.x[word] & !mask) | if x { mask } else { 0 };
+        self.z[word] = (self.z[word] & !mask) | if z { mask } else { 0 };
+    }
+
+    /// Returns the number of `Y` factors, each contributing `i` when written as `i·XZ`.
+    fn y_count(&self) -> u32 {
+        self.x
+            .iter()
+            .zip(&self.z)
+            .map(|(x, z)| (x & z).count_ones())
+            .sum()
+    }
+
+    fn anticommutes_with(&self, other: &PauliRow) -> bool {
+        let overlaps: u32 = (0..self.x.len())
+            .map(|w| ((self.x[w] & other.z[w]) ^ (self.z[w] & other.x[w])).count_ones())
+            .sum();
+        overlaps & 1 == 1
+    }
+
+    /// Replaces `self` with the product `self · other`.
+    ///
+    /// Writing each operator as `i^e ∏ XˣZᶻ`, moving `other`'s `X`s past `self`'s `Z`s gives
+    /// the extra sign. The sign of a non-Hermitian product of anticommuting rows is arbitrary,
+    /// which only happens for destabilizers whose sign is never read.
+    fn multiply_by(&mut self, other: &PauliRow) {
+        let mut exponent = 2 * self.sign as u32 + self.y_count();
+        exponent += 2 * other.sign as u32 + other.y_count();
+        for w in 0..self.x.len() {
+            exponent += 2 * (self.z[w] & other.x[w]).count_ones();
+            self.x[w] ^= other.x[w];
+            self.z[w] ^= other.z[w];
+        }
+        exponent = exponent.wrapping_sub(self.y_count());
+        self.sign = exponent & 3 >= 2;
+    }
+}
+
+/// Returns whether a gate maps Pauli operators to Pauli operators, i.e. whether the
+/// [`StabilizerBackend`] can apply it.
+pub(crate) fn is_clifford(gate: &Gate) -> bool {
+    CliffordImages::from_gate(gate).is_some()
+}
+
+/// The action of a Clifford gate on the Pauli generators of its own qubits.
+///
+/// `images[j]` and `images[k + j]` are `U Xⱼ U†` and `U Zⱼ U†` for a `k`-qubit gate, written as
+/// `(x, z, e)` for `iᵉ XˣZᶻ`.
+struct CliffordImages {
+    images: Vec<(usize, usize, u32)>,
+}
+
+impl CliffordImages {
+    /// Derives the images from the gate matrix, or returns `None` if the gate is not Clifford.
+    fn from_gate(gate: &Gate) -> Option<Self> {
+        let dimension = gate.matrix.len();
+        let k = dimension.trailing_zeros() as usize;
+        let adjoint = linalg::dagger(&gate.matrix);
+        let images = (0..2 * k)
+            .map(|g| {
+                let (x, z) = if g < k {
+                    (1 << g, 0)
+                } else {
+                    (0, 1 << (g - k))
+                };
+                let generator = pauli_matrix(dimension, x, z, 0);
+                let image = linalg::matmul(&linalg::matmul(&gate.matrix, &generator), &adjoint);
+                decompose_pauli(&image)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(CliffordImages { images })
+    }
+
+    /// Conjugates the Hermitian local Pauli with bits `(x, z)` and returns its new bits and
+    /// whether its sign flips.
+    fn conjugate(&self, x: usize, z: usize) -> (usize, usize, bool) {
+        let k = self.images.len() / 2;
+        let (mut image_x, mut image_z, mut exponent) = (0, 0, (x & z).count_ones());
+        for j in 0..k {
+            for (bit, image) in [(x, self.images[j]), (z, self.images[k + j])] {
+                if bit >> j & 1 == 1 {
+                    let (ix, iz, ie) = image;
+                    exponent += ie + 2 * (image_z & ix).count_ones();
+                    image_x ^= ix;
+                    image_z ^= iz;
+                }
+            }
+        }
+        let exponent = exponent.wrapping_sub((image_x & image_z).count_ones()) & 3;
+        (image_x, image_z, exponent == 2)
+    }
+}
+
+/// Returns the matrix of `iᵉ XˣZᶻ` on `log₂ dimension` qubits.
+fn pauli_matrix(dimension: usize, x: usize, z: usize, e: u32) -> Vec<Vec<Complex<f64>>> {
+    let phase = Complex::i().powu(e);
+    let mut matrix = linalg::zeros(dimension, dimension);
+    for column in 0..dimension {
+        let sign = if (z & column).count_ones() & 1 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        matrix[column ^ x][column] = phase * sign;
+    }
+    matrix
+}
+
+/// Writes a matrix as `iᵉ XˣZᶻ`, or returns `None` if it is not a Pauli operator.
+fn decompose_pauli(matrix: &[Vec<Complex<f64>>]) -> Option<(usize, usize, u32)> {
+    let dimension = matrix.len();
+    let x = (0..dimension).find(|&row| matrix[row][0].norm() > 0.5)?;
+    let phase = matrix[x][0];
+    let e = [
+        Complex::new(1.0, 0.0),
+        Complex::i(),
+        Complex::new(-1.0, 0.0),
+        -Complex::i(),
+    ]
+    .iter()
+    .position(|candidate| (phase - candidate).norm() < tolerance::current().equality)?
+        as u32;
+    let z = (0..dimension.trailing_zeros())
+        .filter(|j| (matrix[(1 << j) ^ x][1 << j] / phase).re < 0.0)
+        .map(|j| 1 << j)
+        .sum();
+    let expected = pauli_matrix(dimension, x, z, e);
+    (linalg::max_deviation(matrix, &expected) < tolerance::current().equality).then_some((x, z, e))
+}
+
+/// The `StabilizerBackend` simulates Clifford circuits on a stabilizer tableau.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::backend::Backend;
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::stabilizer::StabilizerBackend;
+///
+/// // A 1000-qubit GHZ state
+/// let n = 1000;
+/// let mut circuit = Circuit::new(n);
+/// circuit.h(0);
+/// for qubit in 1..n {
+///     circuit.cnot(qubit - 1, qubit);
+/// }
+/// let mut backend = StabilizerBackend::new(n);
+/// backend.run(&circuit).unwrap();
+///
+/// let first = backend.measure(0);
+/// assert!((1..n).all(|qubit| backend.measure(qubit) == first));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StabilizerBackend {
+    num_qubits: usize,
+    /// Rows `0..n` are destabilizers and rows `n..2n` stabilizers.
+    rows: Vec<PauliRow>,
+}
+
+impl StabilizerBackend {
+    /// Creates a backend holding `num_qubits` qubits in `|0…0⟩`, stabilized by every `Zⱼ`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the register.
+    pub fn new(num_qubits: usize) -> Self {
+        let mut rows = vec![PauliRow::identity(num_qubits); 2 * num_qubits];
+        for qubit in 0..num_qubits {
+            rows[qubit].set_bits(qubit, true, false);
+            rows[num_qubits + qubit].set_bits(qubit, false, true);
+        }
+        StabilizerBackend { num_qubits, rows }
+    }
+
+    /// Returns the stabilizer generators as signed Pauli strings, highest qubit first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::backend::Backend;
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::stabilizer::StabilizerBackend;
+    ///
+    /// let mut circuit = Circuit::new(2);
+    /// circuit.h(0).cnot(0, 1).x(0);
+    /// let mut backend = StabilizerBackend::new(2);
+    /// backend.run(&circuit).unwrap();
+    /// assert_eq!(backend.stabilizers(), vec!["+XX", "-ZZ"]);
+    /// ```
+    pub fn stabilizers(&self) -> Vec<String> {
+        self.rows[self.num_qubits..]
+            .iter()
+            .map(|row| {
+                let sign = if row.sign { '-' } else { '+' };
+                let paulis = (0..self.num_qubits).rev().map(|qubit| {
+                    match (row.x_bit(qubit), row.z_bit(qubit)) {
+                        (false, false) => 'I',
+                        (true, false) => 'X',
+                        (false, true) => 'Z',
+                        (true, true) => 'Y',
+                    }
+                });
+                std::iter::once(sign).chain(paulis).collect()
+            })
+            .collect()
+    }
+
+    /// Returns the expectation value `±1` or `0` of a Hermitian Pauli operator.
+    fn pauli_expectation(&self, pauli: &PauliRow) -> f64 {
+        let (destabilizers, stabilizers) = self.rows.split_at(self.num_qubits);
+        if stabilizers.iter().any(|row| row.anticommutes_with(pauli)) {
+            return 0.0;
+        }
+        // The operator is ± a product of stabilizers, picked out by the destabilizers
+        let mut product = PauliRow::identity(self.num_qubits);
+        for (destabilizer, stabilizer) in destabilizers.iter().zip(stabilizers) {
+            if destabilizer.anticommutes_with(pauli) {
+                product.multiply_by(stabilizer);
+            }
+        }
+        if product.sign == pauli.sign {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+}
+
+impl Backend for StabilizerBackend {
+    type State = Vec<String>;
+
+    fn name(&self) -> &str {
+        "stabilizer"
+    }
+
+    fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Applies a Clifford gate; any other gate is rejected with `QuantumError::Unsupported`.
+    fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+        backend::check_targets(gate, targets, self.num_qubits)?;
+        let clifford =
+            CliffordImages::from_gate(gate).ok_or_else(|| QuantumError::Unsupported {
+                feature: format!(
+                    "non-Clifford gate on qubits {:?} in the stabilizer backend",
+                    targets
+                ),
+            })?;
+        for row in &mut self.rows {
+            let (mut x, mut z) = (0, 0);
+            for (j, &qubit) in targets.iter().enumerate() {
+                x |= (row.x_bit(qubit) as usize) << j;
+                z |= (row.z_bit(qubit) as usize) << j;
+            }
+            if x == 0 && z == 0 {
+                continue;
+            }
+            let (image_x, image_z, flip) = clifford.conjugate(x, z);
+            for (j, &qubit) in targets.iter().enumerate() {
+                row.set_bits(qubit, image_x >> j & 1 == 1, image_z >> j & 1 == 1);
+            }
+            row.sign ^= flip;
+        }
+        Ok(())
+    }
+
+    fn measure_with_rng(&mut self, qubit: usize, rng: &mut dyn RngCore) -> usize {
+        let n = self.num_qubits;
+        let Some(pivot) = (n..2 * n).find(|&row| self.rows[row].x_bit(qubit)) else {
+            let mut z = PauliRow::identity(n);
+            z.set_bits(qubit, false, true);
+            return (self.pauli_expectation(&z) < 0.0) as usize;
+        };
+
+        // The outcome is random; every other row anticommuting with Z is fixed up by the pivot
+        let pivot_row = self.rows[pivot].clone();
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            if index != pivot && row.x_bit(qubit) {
+                row.multiply_by(&pivot_row);
+            }
+        }
+        let outcome = rng.gen::<bool>();
+        self.rows[pivot - n] = pivot_row;
+        let mut measured = PauliRow::identity(n);
+        measured.set_bits(qubit, false, true);
+        measured.sign = outcome;
+        self.rows[pivot] = measured;
+        outcome as usize
+    }
+
+    /// Returns `⟨O⟩` by expanding the observable in Pauli operators on its targets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the observable's dimension does not match `targets`.
+    fn expectation(&self, observable: &Gate, targets: &[usize]) -> f64 {
+        let dimension = 1 << targets.len();
+        assert_eq!(
+            observable.matrix.len(),
+            dimension,
+            "expected a {0}x{0} observable on {1} qubits",
+            dimension,
+            targets.len()
+        );
+        let mut total = 0.0;
+        for x in 0..dimension {
+            for z in 0..dimension {
+                // Tr(P O) / d for the Hermitian Pauli P = i^{x·z} XˣZᶻ
+                let pauli = pauli_matrix(dimension, x, z, (x & z).count_ones());
+                let coefficient = (0..dimension)
+                    .map(|column| pauli[column ^ x][column] * observable.matrix[column][column ^ x])
+                    .sum::<Complex<f64>>()
+                    .re
+                    / dimension as f64;
+                if coefficient.abs() < tolerance::current().equality {
+                    continue;
+                }
+                let mut row = PauliRow::identity(self.num_qubits);
+                for (j, &qubit) in targets.iter().enumerate() {
+                    row.set_bits(qubit, x >> j & 1 == 1, z >> j & 1 == 1);
+                }
+                total += coefficient * self.pauli_expectation(&row);
+            }
+        }
+        total
+    }
+
+    /// Returns the stabilizer generators, as [`StabilizerBackend::stabilizers`].
+    fn state(&self) -> Vec<String> {
+        self.stabilizers()
+    }
+}
@@ -0,0 +1,125 @@
+//! This module collects the performance counters reported by
+//! [`Simulator::stats`](crate::simulator::Simulator::stats).
+//!
+//! Once enabled with [`Simulator::enable_stats`](crate::simulator::Simulator::enable_stats),
+//! every gate applied to a state vector or density matrix is counted and timed, and the size of
+//! the largest state it touched is kept, in thread-local counters like the seeded generator in
+//! `rng`. Benchmarks and scaling tests read them after a run to see the time per gate and the
+//! memory a backend needed without instrumenting the code under test. Collection is off by
+//! default, so ordinary runs pay one flag check per gate instead of two clock reads. Timing is
+//! skipped on `wasm32`, where `std::time::Instant` is unavailable.
+
+use std::cell::Cell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    static STATS: Cell<Stats> = const { Cell::new(Stats::new()) };
+}
+
+/// Counters accumulated by the gates applied on one thread since the last reset.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::qubit::Qubit;
+/// use quantum_simulator::simulator::Simulator;
+///
+/// let mut circuit = Circuit::new(3);
+/// circuit.h(0).x(1);
+///
+/// Simulator::enable_stats();
+/// Simulator::reset_stats();
+/// Simulator::run(&circuit, &Qubit::zero_state(3).state);
+/// let stats = Simulator::stats();
+/// assert!(stats.gates >= 1);
+/// assert_eq!(stats.peak_memory_bytes, 8 * 16);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of gate applications, counting a fused run of gates once.
+    pub gates: u64,
+    /// The total time spent applying those gates.
+    pub gate_time: Duration,
+    /// The size in bytes of the largest state vector or density matrix a gate was applied to.
+    pub peak_memory_bytes: usize,
+}
+
+impl Stats {
+    /// Returns counters with nothing recorded.
+    pub const fn new() -> Self {
+        Stats {
+            gates: 0,
+            gate_time: Duration::ZERO,
+            peak_memory_bytes: 0,
+        }
+    }
+
+    /// Returns the mean time per gate application, or zero if no gate was applied.
+    pub fn time_per_gate(&self) -> Duration {
+        match u32::try_from(self.gates) {
+            Ok(0) => Duration::ZERO,
+            Ok(gates) => self.gate_time / gates,
+            Err(_) => Duration::from_secs_f64(self.gate_time.as_secs_f64() / self.gates as f64),
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} gates in {:?} ({:?} per gate), peak state {} bytes",
+            self.gates,
+            self.gate_time,
+            self.time_per_gate(),
+            self.peak_memory_bytes
+        )
+    }
+}
+
+/// Runs `apply`, which applies one gate to a state of `state_bytes` bytes, and records it if
+/// collection is enabled on this thread.
+pub(crate) fn record_gate<T>(state_bytes: usize, apply: impl FnOnce() -> T) -> T {
+    if !ENABLED.with(Cell::get) {
+        return apply();
+    }
+    let started = now();
+    let result = apply();
+    let elapsed = started.map_or(Duration::ZERO, |started| started.elapsed());
+    STATS.with(|stats| {
+        let mut current = stats.get();
+        current.gates += 1;
+        current.gate_time += elapsed;
+        current.peak_memory_bytes = current.peak_memory_bytes.max(state_bytes);
+        stats.set(current);
+    });
+    result
+}
+
+/// Returns the counters recorded on this thread.
+pub(crate) fn snapshot() -> Stats {
+    STATS.with(Cell::get)
+}
+
+/// Clears the counters recorded on this thread.
+pub(crate) fn reset() {
+    STATS.with(|stats| stats.set(Stats::new()));
+}
+
+/// Turns collection on or off for this thread, keeping the counters recorded so far.
+pub(crate) fn set_enabled(enabled: bool) {
+    ENABLED.with(|flag| flag.set(enabled));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> Option<Instant> {
+    None
+}
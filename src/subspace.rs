@@ -0,0 +1,266 @@
+//! This module simulates circuits inside a fixed-Hamming-weight symmetry sector.
+//!
+//! When every gate conserves the number of qubits in `|1⟩` (e.g. particle number in
+//! chemistry-style circuits built from swaps, iSWAPs and phases), a state that starts in the
+//! sector of weight `k` never leaves it, so only `C(n, k)` amplitudes need to be stored instead
+//! of `2^n`.
+
+use crate::circuit::{Circuit, Operation};
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::linalg;
+use crate::tolerance;
+use num_complex::Complex;
+
+/// A `Sector` is the subspace of an `n`-qubit register spanned by the basis states with exactly
+/// `weight` qubits in `|1⟩`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sector {
+    num_qubits: usize,
+    weight: usize,
+    basis: Vec<usize>,
+}
+
+impl Sector {
+    /// Creates the sector of `num_qubits`-qubit basis states with Hamming weight `weight`.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_qubits` - The width of the full register.
+    /// * `weight` - The number of qubits in `|1⟩`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::subspace::Sector;
+    ///
+    /// let sector = Sector::new(4, 2);
+    /// assert_eq!(sector.dimension(), 6);
+    /// assert_eq!(sector.basis(), &[0b0011, 0b0101, 0b0110, 0b1001, 0b1010, 0b1100]);
+    /// ```
+    pub fn new(num_qubits: usize, weight: usize) -> Self {
+        let mut basis = vec![];
+        if weight <= num_qubits {
+            // Gosper's hack walks the weight-k integers in increasing order
+            let mut state: usize = (1 << weight) - 1;
+            while state < 1 << num_qubits {
+                basis.push(state);
+                if state == 0 {
+                    break;
+                }
+                let lowest = state & state.wrapping_neg();
+                let ripple = state + lowest;
+                state = (((ripple ^ state) >> 2) / lowest) | ripple;
+            }
+        }
+        Sector {
+            num_qubits,
+            weight,
+            basis,
+        }
+    }
+
+    /// Returns the width of the full register.
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Returns the Hamming weight of the sector's basis states.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// Returns the number of amplitudes in the sector, `C(n, k)`.
+    pub fn dimension(&self) -> usize {
+        self.basis.len()
+    }
+
+    /// Returns the full-register basis states of the sector in increasing order.
+    pub fn basis(&self) -> &[usize] {
+        &self.basis
+    }
+
+    /// Returns the position of a full-register basis state in the sector, if it belongs to it.
+    pub fn index_of(&self, basis_state: usize) -> Option<usize> {
+        self.basis.binary_search(&basis_state).ok()
+    }
+
+    /// Restricts a full-register state to the sector, dropping the other amplitudes.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The `2^n` amplitudes of the full register.
+    pub fn project(&self, state: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        self.basis.iter().map(|&index| state[index]).collect()
+    }
+
+    /// Expands sector amplitudes back to the full `2^n`-dimensional register.
+    ///
+    /// # Arguments
+    ///
+    /// * `amplitudes` - One amplitude per sector basis state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::subspace::Sector;
+    /// use num_complex::Complex;
+    ///
+    /// let sector = Sector::new(3, 1);
+    /// let amplitudes = vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+    /// let state = sector.embed(&amplitudes);
+    /// assert_eq!(state.len(), 8);
+    /// assert_eq!(state[0b010], Complex::new(1.0, 0.0));
+    /// assert_eq!(sector.project(&state), amplitudes);
+    /// ```
+    pub fn embed(&self, amplitudes: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let mut state = vec![Complex::new(0.0, 0.0); 1 << self.num_qubits];
+        for (&index, &amplitude) in self.basis.iter().zip(amplitudes) {
+            state[index] = amplitude;
+        }
+        state
+    }
+
+    /// Runs `circuit` on amplitudes stored in the sector.
+    ///
+    /// # Arguments
+    ///
+    /// * `circuit` - The circuit to run; every gate must conserve Hamming weight.
+    /// * `amplitudes` - One amplitude per sector basis state.
+    ///
+    /// # Returns
+    ///
+    /// * The final sector amplitudes, `QuantumError::DimensionMismatch` if the circuit or the
+    ///   amplitudes do not match the sector, or `QuantumError::Unsupported` if an instruction is
+    ///   not a gate that conserves Hamming weight.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::circuit::Circuit;
+    /// use quantum_simulator::subspace::Sector;
+    /// use num_complex::Complex;
+    ///
+    /// // Hop one excitation along a chain of three sites
+    /// let mut circuit = Circuit::new(3);
+    /// circuit.iswap(0, 1).iswap(1, 2);
+    ///
+    /// let sector = Sector::new(3, 1);
+    /// let mut initial = vec![Complex::new(0.0, 0.0); sector.dimension()];
+    /// initial[sector.index_of(0b001).unwrap()] = Complex::new(1.0, 0.0);
+    /// let amplitudes = sector.run(&circuit, &initial).unwrap();
+    /// assert!((amplitudes[sector.index_of(0b100).unwrap()].norm() - 1.0).abs() < 1e-12);
+    ///
+    /// let mut mixing = Circuit::new(3);
+    /// mixing.h(0);
+    /// assert!(sector.run(&mixing, &initial).is_err());
+    /// ```
+    pub fn run(
+        &self,
+        circuit: &Circuit,
+        amplitudes: &[Complex<f64>],
+    ) -> Result<Vec<Complex<f64>>, QuantumError> {
+        if circuit.num_qubits() != self.num_qubits {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.num_qubits,
+                found: circuit.num_qubits(),
+            });
+        }
+        if amplitudes.len() != self.dimension() {
+            return Err(QuantumError::DimensionMismatch {
+                expected: self.dimension(),
+                found: amplitudes.len(),
+            });
+        }
+        let gates = circuit
+            .instructions()
+            .iter()
+            .filter(|instruction| {
+                !matches!(
+                    instruction.operation,
+                    Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_)
+                )
+            })
+            .map(|instruction| {
+                let operation = &instruction.operation;
+                if !operation.is_unitary() || !conserves_hamming_weight(&operation.gate()) {
+                    return Err(QuantumError::Unsupported {
+                        feature: format!("{:?} in a Hamming-weight sector", operation),
+                    });
+                }
+                Ok((operation.gate(), &instruction.qubits))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut amplitudes = amplitudes.to_vec();
+        for (gate, targets) in gates {
+            self.apply(&gate.matrix, &mut amplitudes, targets);
+        }
+        Ok(amplitudes)
+    }
+
+    /// Applies a weight-conserving local gate to the sector amplitudes in place.
+    fn apply(
+        &self,
+        matrix: &[Vec<Complex<f64>>],
+        amplitudes: &mut [Complex<f64>],
+        targets: &[usize],
+    ) {
+        let (offsets, target_mask) = linalg::local_offsets(targets);
+        let mut by_weight = vec![vec![]; targets.len() + 1];
+        for local in 0..offsets.len() {
+            by_weight[local.count_ones() as usize].push(local);
+        }
+
+        for &state in &self.basis {
+            // Each group of states sharing the non-target bits is mixed once, from its first member
+            let base = state & !target_mask;
+            let group = &by_weight[(state & target_mask).count_ones() as usize];
+            if base + offsets[group[0]] != state {
+                continue;
+            }
+            let positions: Vec<usize> = group
+                .iter()
+                .map(|&local| {
+                    self.index_of(base + offsets[local])
+                        .expect("states of equal weight share the sector")
+                })
+                .collect();
+            let local_state: Vec<Complex<f64>> = positions
+                .iter()
+                .map(|&position| amplitudes[position])
+                .collect();
+            for (&row, &position) in group.iter().zip(&positions) {
+                amplitudes[position] = group
+                    .iter()
+                    .zip(&local_state)
+                    .map(|(&col, amplitude)| matrix[row][col] * amplitude)
+                    .sum();
+            }
+        }
+    }
+}
+
+/// Returns `true` if `gate` never maps a basis state to one with a different Hamming weight.
+///
+/// # Arguments
+///
+/// * `gate` - The gate to check, acting on its own qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::gates::{hadamard, iswap, phase};
+/// use quantum_simulator::subspace::conserves_hamming_weight;
+///
+/// assert!(conserves_hamming_weight(&iswap(0, 1, 2)));
+/// assert!(conserves_hamming_weight(&phase(0.3)));
+/// assert!(!conserves_hamming_weight(&hadamard(1)));
+/// ```
+pub fn conserves_hamming_weight(gate: &Gate) -> bool {
+    gate.matrix.iter().enumerate().all(|(i, row)| {
+        row.iter().enumerate().all(|(j, entry)| {
+            i.count_ones() == j.count_ones() || entry.norm() < tolerance::current().truncation
+        })
+    })
+}
@@ -0,0 +1,120 @@
+//! This module holds the numerical tolerances used across the simulator.
+//!
+//! A [`Tolerances`] value groups every threshold the library compares floating-point results
+//! against: how far a matrix may be from unitary, a state or channel from normalized, two
+//! numbers from each other before they count as different, and how small a value must be to be
+//! dropped as rounding noise. The tolerances in effect are shared by every thread; they are set
+//! with [`Simulator::set_tolerances`](crate::simulator::Simulator::set_tolerances) and read by
+//! gate, state and channel validation, the optimizer and the analysis functions alike.
+//! [`KrausChannel::validate_with`](crate::noise::KrausChannel::validate_with) checks against
+//! explicit tolerances instead.
+
+use crate::error::QuantumError;
+use std::sync::{PoisonError, RwLock};
+
+/// The numerical tolerances in effect for a computation.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::tolerance::Tolerances;
+///
+/// let strict = Tolerances::default().scaled(1e-2).unwrap();
+/// assert_eq!(strict.unitarity, 1e-12);
+/// assert!(strict.equality < Tolerances::default().equality);
+/// assert!(Tolerances::default().scaled(-1.0).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tolerances {
+    /// Largest entry of `U†U - I` accepted for a unitary gate.
+    pub unitarity: f64,
+    /// Largest deviation from unit norm of a state, from unit purity of a pure state, or from
+    /// trace preservation and positivity of a channel.
+    pub normalization: f64,
+    /// Largest difference between two numbers, or matrix entries, that still count as equal,
+    /// e.g. when comparing circuits, recognizing gates or grouping eigenvalues.
+    pub equality: f64,
+    /// Magnitude below which a value is treated as zero, e.g. a vanishing eigenvalue, rotation
+    /// angle or postselection probability.
+    pub truncation: f64,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Tolerances {
+            unitarity: 1e-10,
+            normalization: 1e-8,
+            equality: 1e-9,
+            truncation: 1e-12,
+        }
+    }
+}
+
+impl Tolerances {
+    /// Returns the tolerances with every threshold multiplied by `factor`, e.g. `1e-2` to
+    /// tighten all of them a hundredfold.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Tolerances)` - The scaled tolerances.
+    /// * `Err(QuantumError::Unsupported)` - If a scaled threshold is negative or NaN, as for a
+    ///   negative or NaN `factor`.
+    pub fn scaled(&self, factor: f64) -> Result<Self, QuantumError> {
+        let scaled = Tolerances {
+            unitarity: self.unitarity * factor,
+            normalization: self.normalization * factor,
+            equality: self.equality * factor,
+            truncation: self.truncation * factor,
+        };
+        scaled.validate()?;
+        Ok(scaled)
+    }
+
+    /// Checks that every threshold is non-negative and not NaN.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the tolerances can be used.
+    /// * `Err(QuantumError::Unsupported)` - Naming the first negative or NaN threshold.
+    pub fn validate(&self) -> Result<(), QuantumError> {
+        let thresholds = [
+            ("unitarity", self.unitarity),
+            ("normalization", self.normalization),
+            ("equality", self.equality),
+            ("truncation", self.truncation),
+        ];
+        match thresholds
+            .iter()
+            .find(|(_, value)| value.is_nan() || *value < 0.0)
+        {
+            Some((name, value)) => Err(QuantumError::Unsupported {
+                feature: format!("{} tolerance {}", name, value),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+static TOLERANCES: RwLock<Option<Tolerances>> = RwLock::new(None);
+
+/// Makes `tolerances` apply to every subsequent computation on any thread, after checking them
+/// with [`Tolerances::validate`].
+pub(crate) fn set(tolerances: Tolerances) -> Result<(), QuantumError> {
+    tolerances.validate()?;
+    *TOLERANCES.write().unwrap_or_else(PoisonError::into_inner) = Some(tolerances);
+    Ok(())
+}
+
+/// Returns every thread to the default tolerances.
+pub(crate) fn clear() {
+    *TOLERANCES.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Returns the tolerances in effect.
+pub(crate) fn current() -> Tolerances {
+    TOLERANCES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .unwrap_or_default()
+}
@@ -0,0 +1,346 @@
+//! This module reconstructs the density matrix of one or two qubits from measurement counts in
+//! the Pauli `X`, `Y` and `Z` bases.
+//!
+//! A measurement setting is a string with one basis letter per tomographed qubit, e.g. `"XZ"`
+//! measures the first qubit in the `X` basis and the second in the `Z` basis. Every Pauli
+//! expectation value is estimated from the settings that measure its non-identity factors, and
+//! the linear-inversion estimate `ρ = Σ ⟨P⟩ P / 2ⁿ` is optionally projected onto the nearest
+//! physical state with the fast maximum-likelihood method of Smolin, Gambetta and Smith.
+
+use crate::circuit::Circuit;
+use crate::counts::Counts;
+use crate::density::DensityMatrix;
+use crate::error::QuantumError;
+use crate::linalg;
+use crate::noise::NoiseModel;
+use crate::qubit::{Basis, Qubit};
+use crate::rng;
+use crate::simulator::Simulator;
+use num_complex::Complex;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// The largest number of qubits reconstructed at once.
+const MAX_QUBITS: usize = 2;
+
+/// The basis letters in the order of `linalg::paulis`, after the identity.
+const BASES: [char; 3] = ['X', 'Y', 'Z'];
+
+/// How [`reconstruct`] turns Pauli expectation values into a density matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Estimator {
+    /// Linear inversion, which is unbiased but may have slightly negative eigenvalues from shot
+    /// noise.
+    LinearInversion,
+    /// Linear inversion followed by the closest physical state in the maximum-likelihood sense
+    /// for Gaussian noise: negative eigenvalues are zeroed and the rest shifted down evenly.
+    MaximumLikelihood,
+}
+
+/// Returns all `3ⁿ` measurement settings of `num_qubits` qubits, e.g. `"X"`, `"Y"`, `"Z"` for one
+/// qubit and `"XX"`, `"XY"`, …, `"ZZ"` for two.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::tomography::settings;
+///
+/// assert_eq!(settings(1), vec!["X", "Y", "Z"]);
+/// assert_eq!(settings(2).len(), 9);
+/// ```
+pub fn settings(num_qubits: usize) -> Vec<String> {
+    (0..num_qubits).fold(vec![String::new()], |prefixes, _| {
+        prefixes
+            .iter()
+            .flat_map(|prefix| {
+                BASES
+                    .iter()
+                    .map(move |basis| format!("{}{}", prefix, basis))
+            })
+            .collect()
+    })
+}
+
+/// Returns the circuit that prepares a state with `circuit` and then measures `qubits` in the
+/// bases of `setting`.
+///
+/// Qubit `qubits[j]` is rotated into the basis of letter `j` (`H` for `X`, `S†` then `H` for `Y`)
+/// and measured into classical bit `c + j`, where `c` is the number of classical bits `circuit`
+/// already has. The tomographed qubits are therefore the leading characters of every outcome,
+/// `qubits[0]` last.
+///
+/// # Arguments
+///
+/// * `circuit` - The state preparation.
+/// * `qubits` - The one or two qubits to reconstruct.
+/// * `setting` - One basis letter per qubit, see [`settings`].
+///
+/// # Returns
+///
+/// * `Ok(Circuit)` - The measurement circuit.
+/// * `Err(QuantumError::Unsupported)` - If `qubits` has no or more than two qubits.
+/// * `Err(QuantumError::QubitOutOfRange)` - If a qubit is not in the circuit.
+/// * `Err(QuantumError::DuplicateQubit)` - If a qubit is listed twice.
+/// * `Err(QuantumError::Parse)` - If the setting is not one `X`, `Y` or `Z` per qubit.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::tomography::measurement_circuit;
+///
+/// let mut bell = Circuit::new(2);
+/// bell.h(0).cnot(0, 1);
+/// let circuit = measurement_circuit(&bell, &[0, 1], "XY").unwrap();
+/// assert_eq!(circuit.num_clbits(), 2);
+/// assert!(measurement_circuit(&bell, &[0, 1], "XW").is_err());
+/// ```
+pub fn measurement_circuit(
+    circuit: &Circuit,
+    qubits: &[usize],
+    setting: &str,
+) -> Result<Circuit, QuantumError> {
+    check_qubits(qubits, circuit.num_qubits())?;
+    let bases: Vec<char> = setting.chars().collect();
+    if bases.len() != qubits.len() || bases.iter().any(|basis| !BASES.contains(basis)) {
+        return Err(QuantumError::Parse {
+            line: 0,
+            message: format!(
+                "expected one of X, Y or Z per qubit in setting '{}'",
+                setting
+            ),
+        });
+    }
+
+    let mut measured = circuit.clone();
+    let offset = circuit.num_clbits();
+    for (j, (&qubit, basis)) in qubits.iter().zip(bases).enumerate() {
+        let basis = match basis {
+            'X' => Basis::X,
+            'Y' => Basis::Y,
+            _ => Basis::Z,
+        };
+        measured.measure_in_basis(qubit, offset + j, basis);
+    }
+    Ok(measured)
+}
+
+/// Samples every measurement setting of `qubits` after `circuit`, like
+/// [`collect_counts_with_rng`] with the thread-local generator.
+pub fn collect_counts(
+    circuit: &Circuit,
+    qubits: &[usize],
+    noise_model: &NoiseModel,
+    shots: usize,
+) -> Result<HashMap<String, Counts>, QuantumError> {
+    rng::with_rng(|rng| collect_counts_with_rng(circuit, qubits, noise_model, shots, rng))
+}
+
+/// Samples every measurement setting of `qubits` after `circuit` with the noisy shot sampler.
+///
+/// # Arguments
+///
+/// * `circuit` - The state preparation, run from `|0…0⟩`.
+/// * `qubits` - The one or two qubits to reconstruct.
+/// * `noise_model` - The errors applied while sampling, including to the basis rotations.
+/// * `shots` - The number of shots per setting.
+/// * `rng` - The random number generator to draw from.
+///
+/// # Returns
+///
+/// * `Ok(HashMap<String, Counts>)` - The counts of every setting, ready for [`reconstruct`].
+/// * `Err(QuantumError)` - If `qubits` is invalid, see [`measurement_circuit`].
+pub fn collect_counts_with_rng<R: Rng + ?Sized>(
+    circuit: &Circuit,
+    qubits: &[usize],
+    noise_model: &NoiseModel,
+    shots: usize,
+    rng: &mut R,
+) -> Result<HashMap<String, Counts>, QuantumError> {
+    let initial_state = Qubit::zero_state(circuit.num_qubits()).state;
+    let mut counts = HashMap::new();
+    for setting in settings(qubits.len()) {
+        let measured = measurement_circuit(circuit, qubits, &setting)?;
+        let sampled = Simulator::sample_trajectories_with_rng(
+            &measured,
+            &initial_state,
+            noise_model,
+            shots,
+            rng,
+        );
+        counts.insert(setting, sampled);
+    }
+    Ok(counts)
+}
+
+/// Reconstructs the density matrix of the tomographed qubits from their measurement counts.
+///
+/// Qubit `j` of the result is the qubit of letter `j` of the settings. Every outcome's leading
+/// characters are the tomographed qubits' results, the last qubit first, as produced by
+/// [`measurement_circuit`]. Settings need not be complete as long as every Pauli operator has
+/// its non-identity factors measured by some setting; all settings that do are pooled.
+///
+/// # Arguments
+///
+/// * `counts` - The counts of each measurement setting, e.g. from [`collect_counts`].
+/// * `estimator` - Whether to correct the estimate to a physical state.
+///
+/// # Returns
+///
+/// * `Ok(DensityMatrix)` - The estimated state, with unit trace.
+/// * `Err(QuantumError::Unsupported)` - If the settings name no or more than two qubits, or
+///   no setting measures some Pauli operator.
+/// * `Err(QuantumError::Parse)` - If the settings have different lengths or invalid letters, or
+///   an outcome is shorter than its setting.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::density::DensityMatrix;
+/// use quantum_simulator::noise::NoiseModel;
+/// use quantum_simulator::qubit::Qubit;
+/// use quantum_simulator::tomography::{collect_counts_with_rng, reconstruct, Estimator};
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+///
+/// let mut circuit = Circuit::new(1);
+/// circuit.ry(0, 0.8);
+/// let mut rng = StdRng::seed_from_u64(3);
+/// let counts = collect_counts_with_rng(&circuit, &[0], &NoiseModel::new(), 4000, &mut rng).unwrap();
+/// let rho = reconstruct(&counts, Estimator::MaximumLikelihood).unwrap();
+///
+/// let mut ideal = Qubit::new();
+/// circuit.run(&mut ideal);
+/// assert!(rho.fidelity(&DensityMatrix::from_state(&ideal)) > 0.99);
+/// ```
+pub fn reconstruct(
+    counts: &HashMap<String, Counts>,
+    estimator: Estimator,
+) -> Result<DensityMatrix, QuantumError> {
+    let invalid = |message: String| QuantumError::Parse { line: 0, message };
+    let num_qubits = counts.keys().next().map_or(0, |setting| setting.len());
+    if num_qubits == 0 || num_qubits > MAX_QUBITS {
+        return Err(QuantumError::Unsupported {
+            feature: format!("tomography of {} qubits", num_qubits),
+        });
+    }
+    for setting in counts.keys() {
+        if setting.len() != num_qubits || !setting.chars().all(|basis| BASES.contains(&basis)) {
+            return Err(invalid(format!(
+                "expected one of X, Y or Z per qubit in setting '{}'",
+                setting
+            )));
+        }
+    }
+
+    // Pauli string m has digit j (base 4, 0 = I, 1 = X, 2 = Y, 3 = Z) on qubit j
+    let basis = linalg::pauli_basis(num_qubits);
+    let dimension = 1 << num_qubits;
+    let mut matrix = linalg::zeros(dimension, dimension);
+    for (m, pauli) in basis.iter().enumerate() {
+        let factors: Vec<usize> = (0..num_qubits)
+            .map(|j| (m / 4usize.pow(j as u32)) % 4)
+            .collect();
+        let expectation = if m == 0 {
+            1.0
+        } else {
+            let mut total = 0.0;
+            let mut shots = 0;
+            for (setting, setting_counts) in counts {
+                let letters: Vec<char> = setting.chars().collect();
+                if factors
+                    .iter()
+                    .zip(&letters)
+                    .any(|(&factor, &letter)| factor != 0 && BASES[factor - 1] != letter)
+                {
+                    continue;
+                }
+                for (outcome, count) in setting_counts.iter() {
+                    let bits = outcome.as_bytes();
+                    if bits.len() < num_qubits {
+                        return Err(invalid(format!(
+                            "outcome '{}' is shorter than setting '{}'",
+                            outcome, setting
+                        )));
+                    }
+                    // The leading characters hold the tomographed qubits, the last one first
+                    let parity = (0..num_qubits)
+                        .filter(|&j| factors[j] != 0 && bits[num_qubits - 1 - j] == b'1')
+                        .count();
+                    let sign = if parity.is_multiple_of(2) { 1.0 } else { -1.0 };
+                    total += sign * count as f64;
+                }
+                shots += setting_counts.shots();
+            }
+            if shots == 0 {
+                let name: String = factors
+                    .iter()
+                    .map(|&factor| ['I', 'X', 'Y', 'Z'][factor])
+                    .collect();
+                return Err(QuantumError::Unsupported {
+                    feature: format!("estimating {} without a setting that measures it", name),
+                });
+            }
+            total / shots as f64
+        };
+        for (row, pauli_row) in matrix.iter_mut().zip(pauli) {
+            for (entry, value) in row.iter_mut().zip(pauli_row) {
+                *entry += value * (expectation / dimension as f64);
+            }
+        }
+    }
+
+    if estimator == Estimator::MaximumLikelihood {
+        matrix = closest_physical(&matrix);
+    }
+    Ok(DensityMatrix::from_matrix(matrix))
+}
+
+/// Returns the density matrix closest to a unit-trace Hermitian matrix, by the algorithm of
+/// Smolin, Gambetta and Smith (PRL 108, 070502): the most negative eigenvalues are zeroed one by
+/// one and their weight spread over the rest until none is negative.
+fn closest_physical(matrix: &[Vec<Complex<f64>>]) -> Vec<Vec<Complex<f64>>> {
+    let (mut eigenvalues, eigenvectors) = linalg::eigh(matrix);
+    let dimension = eigenvalues.len();
+    let mut accumulated = 0.0;
+    let mut remaining = dimension;
+    // Eigenvalues are ascending, so the smallest come first
+    for value in eigenvalues.iter_mut() {
+        if *value + accumulated / remaining as f64 >= 0.0 {
+            break;
+        }
+        accumulated += *value;
+        *value = 0.0;
+        remaining -= 1;
+    }
+    let shift = accumulated / remaining.max(1) as f64;
+    let mut physical = linalg::zeros(dimension, dimension);
+    for (k, value) in eigenvalues.iter().enumerate().skip(dimension - remaining) {
+        let value = value + shift;
+        for (i, row) in physical.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry += eigenvectors[i][k] * eigenvectors[j][k].conj() * value;
+            }
+        }
+    }
+    physical
+}
+
+/// Checks that `qubits` are one or two distinct qubits of a `num_qubits`-qubit register.
+fn check_qubits(qubits: &[usize], num_qubits: usize) -> Result<(), QuantumError> {
+    if qubits.is_empty() || qubits.len() > MAX_QUBITS {
+        return Err(QuantumError::Unsupported {
+            feature: format!("tomography of {} qubits", qubits.len()),
+        });
+    }
+    for (j, &qubit) in qubits.iter().enumerate() {
+        if qubit >= num_qubits {
+            return Err(QuantumError::QubitOutOfRange { qubit, num_qubits });
+        }
+        if qubits[..j].contains(&qubit) {
+            return Err(QuantumError::DuplicateQubit { qubit });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,703 @@
+//! This module places and routes circuits onto a device described by calibration data, so that
+//! every two-qubit gate acts on a coupled pair of physical qubits.
+//!
+//! A [`Calibration`] lists the error rates of a device: one single-qubit gate error and one
+//! readout error per qubit, and one two-qubit gate error per coupling. [`transpile`] maps the
+//! logical qubits of a circuit onto physical ones and inserts SWAPs wherever a gate acts on an
+//! uncoupled pair. With [`Placement::NoiseAdaptive`] it prefers low-error qubits and couplings,
+//! both when choosing the initial layout and when choosing the path of the SWAPs;
+//! [`compare_placements`] reports how much that raises the predicted fidelity over a naive
+//! placement.
+
+use crate::circuit::{Circuit, Instruction, Operation};
+use crate::error::QuantumError;
+use crate::library;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A SWAP is compiled into this many two-qubit gates on the same coupling.
+const GATES_PER_SWAP: i32 = 3;
+
+/// Added to every coupling's routing cost, so that among equally good paths the shortest wins.
+const HOP_COST: f64 = 1e-9;
+
+/// The most rounds of layout improvement [`transpile`] runs.
+const MAX_ROUNDS: usize = 16;
+
+/// The error rates of a device's qubits and couplings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    gate_errors: Vec<f64>,
+    readout_errors: Vec<f64>,
+    couplings: BTreeMap<(usize, usize), f64>,
+}
+
+impl Calibration {
+    /// Creates the calibration of a device with `num_qubits` perfect, uncoupled qubits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::transpile::Calibration;
+    ///
+    /// let mut calibration = Calibration::new(3);
+    /// calibration.add_coupling(0, 1, 0.01).add_coupling(1, 2, 0.05);
+    /// assert_eq!(calibration.coupling_error(2, 1), Some(0.05));
+    /// assert_eq!(calibration.coupling_error(0, 2), None);
+    /// ```
+    pub fn new(num_qubits: usize) -> Self {
+        Calibration {
+            gate_errors: vec![0.0; num_qubits],
+            readout_errors: vec![0.0; num_qubits],
+            couplings: BTreeMap::new(),
+        }
+    }
+
+    /// Parses calibration data from TOML with a `gate_error` array of one single-qubit gate error
+    /// per qubit, an optional `readout_error` array of the same length and an optional
+    /// `couplings` array of `[a, b, error]` triples.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The calibration data.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Calibration)` - The parsed calibration, on as many qubits as `gate_error` lists.
+    /// * `Err(QuantumError::Parse)` - If the TOML is malformed, a key is unknown or missing, an
+    ///   error rate is not in `[0, 1)` or a coupling names a qubit that does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::transpile::Calibration;
+    ///
+    /// let calibration = Calibration::parse(
+    ///     "gate_error = [0.001, 0.002, 0.001]\n\
+    ///      readout_error = [0.02, 0.03, 0.01]\n\
+    ///      couplings = [[0, 1, 0.01], [1, 2, 0.02]]",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(calibration.num_qubits(), 3);
+    /// assert_eq!(calibration.readout_error(1), 0.03);
+    /// assert!(Calibration::parse("gate_error = [0.001]\ncouplings = [[0, 1, 0.01]]").is_err());
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, QuantumError> {
+        let table: toml::Table =
+            source
+                .parse()
+                .map_err(|error: toml::de::Error| QuantumError::Parse {
+                    line: error
+                        .span()
+                        .map_or(0, |span| source[..span.start].lines().count().max(1)),
+                    message: error.message().to_string(),
+                })?;
+        let invalid = |message: String| QuantumError::Parse { line: 0, message };
+        let rate = |value: &toml::Value, key: &str| {
+            value
+                .as_float()
+                .or_else(|| value.as_integer().map(|value| value as f64))
+                .filter(|rate| (0.0..1.0).contains(rate))
+                .ok_or_else(|| invalid(format!("'{}' error rates must be in [0, 1)", key)))
+        };
+        let rates = |key: &str| -> Result<Option<Vec<f64>>, QuantumError> {
+            match table.get(key) {
+                None => Ok(None),
+                Some(value) => value
+                    .as_array()
+                    .ok_or_else(|| invalid(format!("'{}' must be an array", key)))?
+                    .iter()
+                    .map(|value| rate(value, key))
+                    .collect::<Result<Vec<f64>, QuantumError>>()
+                    .map(Some),
+            }
+        };
+        if let Some(key) = table
+            .keys()
+            .find(|key| !matches!(key.as_str(), "gate_error" | "readout_error" | "couplings"))
+        {
+            return Err(invalid(format!("unknown key '{}'", key)));
+        }
+
+        let gate_errors =
+            rates("gate_error")?.ok_or_else(|| invalid("missing 'gate_error'".to_string()))?;
+        let num_qubits = gate_errors.len();
+        let readout_errors = rates("readout_error")?.unwrap_or_else(|| vec![0.0; num_qubits]);
+        if readout_errors.len() != num_qubits {
+            return Err(invalid(format!(
+                "expected {} readout errors, found {}",
+                num_qubits,
+                readout_errors.len()
+            )));
+        }
+        let mut calibration = Calibration {
+            gate_errors,
+            readout_errors,
+            couplings: BTreeMap::new(),
+        };
+        let couplings = match table.get("couplings") {
+            None => vec![],
+            Some(value) => value
+                .as_array()
+                .ok_or_else(|| invalid("'couplings' must be an array".to_string()))?
+                .clone(),
+        };
+        for coupling in &couplings {
+            let triple = coupling.as_array().map(Vec::as_slice);
+            let Some([a, b, error]) = triple else {
+                return Err(invalid("expected [a, b, error] couplings".to_string()));
+            };
+            let qubit = |value: &toml::Value| {
+                value
+                    .as_integer()
+                    .filter(|&qubit| qubit >= 0 && (qubit as usize) < num_qubits)
+                    .map(|qubit| qubit as usize)
+                    .ok_or_else(|| {
+                        invalid(format!(
+                            "coupling qubits must be integers below {}",
+                            num_qubits
+                        ))
+                    })
+            };
+            let (a, b) = (qubit(a)?, qubit(b)?);
+            if a == b {
+                return Err(invalid(format!("qubit {} is coupled to itself", a)));
+            }
+            calibration.add_coupling(a, b, rate(error, "couplings")?);
+        }
+        Ok(calibration)
+    }
+
+    /// Returns the number of physical qubits.
+    pub fn num_qubits(&self) -> usize {
+        self.gate_errors.len()
+    }
+
+    /// Sets the single-qubit gate error of `qubit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `qubit` is not on the device.
+    pub fn set_gate_error(&mut self, qubit: usize, error: f64) -> &mut Self {
+        self.gate_errors[qubit] = error;
+        self
+    }
+
+    /// Sets the readout error of `qubit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `qubit` is not on the device.
+    pub fn set_readout_error(&mut self, qubit: usize, error: f64) -> &mut Self {
+        self.readout_errors[qubit] = error;
+        self
+    }
+
+    /// Couples qubits `a` and `b`, in both directions, with the given two-qubit gate error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either qubit is not on the device or `a == b`.
+    pub fn add_coupling(&mut self, a: usize, b: usize, error: f64) -> &mut Self {
+        assert!(
+            a < self.num_qubits() && b < self.num_qubits() && a != b,
+            "cannot couple qubits {} and {} on a {}-qubit device",
+            a,
+            b,
+            self.num_qubits()
+        );
+        self.couplings.insert((a.min(b), a.max(b)), error);
+        self
+    }
+
+    /// Returns the single-qubit gate error of `qubit`.
+    pub fn gate_error(&self, qubit: usize) -> f64 {
+        self.gate_errors[qubit]
+    }
+
+    /// Returns the readout error of `qubit`.
+    pub fn readout_error(&self, qubit: usize) -> f64 {
+        self.readout_errors[qubit]
+    }
+
+    /// Returns the two-qubit gate error of the coupling between `a` and `b`, or `None` if they
+    /// are not coupled.
+    pub fn coupling_error(&self, a: usize, b: usize) -> Option<f64> {
+        self.couplings.get(&(a.min(b), a.max(b))).copied()
+    }
+
+    /// Returns the coupled pairs, smaller qubit first, with their two-qubit gate errors.
+    pub fn couplings(&self) -> impl Iterator<Item = ((usize, usize), f64)> + '_ {
+        self.couplings.iter().map(|(&pair, &error)| (pair, error))
+    }
+
+    /// Returns the cheapest paths between all pairs of qubits, where a coupling costs one hop or,
+    /// with `noise_aware`, the negative log of its success probability.
+    fn paths(&self, noise_aware: bool) -> Paths {
+        let n = self.num_qubits();
+        let mut cost = vec![vec![f64::INFINITY; n]; n];
+        let mut next = vec![vec![None; n]; n];
+        for (qubit, row) in cost.iter_mut().enumerate() {
+            row[qubit] = 0.0;
+            next[qubit][qubit] = Some(qubit);
+        }
+        for ((a, b), error) in self.couplings() {
+            let weight = if noise_aware {
+                -(1.0 - error).ln() + HOP_COST
+            } else {
+                1.0
+            };
+            cost[a][b] = weight;
+            cost[b][a] = weight;
+            next[a][b] = Some(b);
+            next[b][a] = Some(a);
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    if cost[i][k] + cost[k][j] < cost[i][j] {
+                        cost[i][j] = cost[i][k] + cost[k][j];
+                        next[i][j] = next[i][k];
+                    }
+                }
+            }
+        }
+        Paths { cost, next }
+    }
+}
+
+/// How [`transpile`] places and routes a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Logical qubit `k` starts on physical qubit `k`, and SWAPs take the fewest hops.
+    Naive,
+    /// The layout and the SWAP paths maximize the predicted fidelity.
+    NoiseAdaptive,
+}
+
+/// A circuit placed and routed onto a device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transpilation {
+    /// The routed circuit, on the device's physical qubits and the original classical bits.
+    pub circuit: Circuit,
+    /// The physical qubit each logical qubit starts on.
+    pub initial_layout: Vec<usize>,
+    /// The physical qubit each logical qubit ends on, after the inserted SWAPs.
+    pub final_layout: Vec<usize>,
+    /// The number of SWAPs inserted.
+    pub swaps: usize,
+    /// The predicted fidelity of the routed circuit, see [`predicted_fidelity`].
+    pub fidelity: f64,
+}
+
+/// The result of [`compare_placements`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlacementComparison {
+    /// The circuit transpiled with [`Placement::NoiseAdaptive`].
+    pub adaptive: Transpilation,
+    /// The circuit transpiled with [`Placement::Naive`].
+    pub naive: Transpilation,
+}
+
+impl PlacementComparison {
+    /// Returns how much the noise-adaptive placement raises the predicted fidelity.
+    pub fn improvement(&self) -> f64 {
+        self.adaptive.fidelity - self.naive.fidelity
+    }
+}
+
+impl fmt::Display for PlacementComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, transpilation) in [("naive", &self.naive), ("adaptive", &self.adaptive)] {
+            writeln!(
+                f,
+                "{:<9} layout {:?}, {} swaps, predicted fidelity {:.4}",
+                name, transpilation.initial_layout, transpilation.swaps, transpilation.fidelity
+            )?;
+        }
+        writeln!(f, "improvement: {:+.4}", self.improvement())
+    }
+}
+
+/// Returns the probability that a circuit on the device's physical qubits runs without error,
+/// the product of the success probabilities of its gates and measurements.
+///
+/// A SWAP counts as three two-qubit gates, and resets and snapshots are free.
+///
+/// # Arguments
+///
+/// * `circuit` - A circuit on the physical qubits, e.g. [`Transpilation::circuit`].
+/// * `calibration` - The device's error rates.
+///
+/// # Returns
+///
+/// * `Ok(f64)` - The predicted fidelity.
+/// * `Err(QuantumError::Unsupported)` - If the circuit is wider than the device, or a gate
+///   acts on more than two qubits or on an uncoupled pair.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::transpile::{predicted_fidelity, Calibration};
+///
+/// let mut calibration = Calibration::new(2);
+/// calibration.set_gate_error(0, 0.01).add_coupling(0, 1, 0.1);
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1);
+/// let fidelity = predicted_fidelity(&circuit, &calibration).unwrap();
+/// assert!((fidelity - 0.99 * 0.9).abs() < 1e-12);
+/// ```
+pub fn predicted_fidelity(
+    circuit: &Circuit,
+    calibration: &Calibration,
+) -> Result<f64, QuantumError> {
+    check_width(circuit, calibration)?;
+    let mut fidelity = 1.0;
+    for instruction in circuit.instructions() {
+        fidelity *= success(instruction, calibration)?;
+    }
+    Ok(fidelity)
+}
+
+/// Places a circuit onto a device and inserts SWAPs so that every two-qubit gate acts on a
+/// coupled pair.
+///
+/// With [`Placement::NoiseAdaptive`], a greedy layout puts strongly interacting logical qubits
+/// on low-error couplings, and is then improved by moving single logical qubits for as long as
+/// the predicted fidelity of the routed circuit rises. Measurements keep their classical bits,
+/// so counts over the classical register match the original circuit's. Custom gates with a
+/// decomposition in the thread's [`GateLibrary`](crate::library::GateLibrary) are replaced by
+/// their decomposition before placement.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to place, with gates on at most two qubits once custom gates are
+///   expanded.
+/// * `calibration` - The device's coupling map and error rates.
+/// * `placement` - The placement and routing strategy.
+///
+/// # Returns
+///
+/// * `Ok(Transpilation)` - The routed circuit, its layouts and its predicted fidelity.
+/// * `Err(QuantumError::Unsupported)` - If the circuit is wider than the device, has a gate on
+///   more than two qubits, has recursive custom gates, or has a two-qubit gate between qubits that no path of couplings
+///   connects.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::transpile::{transpile, Calibration, Placement};
+///
+/// // A line 0 - 1 - 2 whose first coupling is much noisier than the second
+/// let mut calibration = Calibration::new(3);
+/// calibration.add_coupling(0, 1, 0.2).add_coupling(1, 2, 0.01);
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1);
+///
+/// let adaptive = transpile(&circuit, &calibration, Placement::NoiseAdaptive).unwrap();
+/// let mut placed = adaptive.initial_layout.clone();
+/// placed.sort();
+/// assert_eq!(placed, vec![1, 2]);
+/// assert!((adaptive.fidelity - 0.99).abs() < 1e-12);
+/// ```
+pub fn transpile(
+    circuit: &Circuit,
+    calibration: &Calibration,
+    placement: Placement,
+) -> Result<Transpilation, QuantumError> {
+    check_width(circuit, calibration)?;
+    let circuit = &library::current().expand(circuit)?;
+    if let Some(instruction) = circuit
+        .instructions()
+        .iter()
+        .find(|instruction| instruction.qubits.len() > 2)
+    {
+        return Err(QuantumError::Unsupported {
+            feature: format!(
+                "routing {} on {} qubits",
+                instruction.operation.name(),
+                instruction.qubits.len()
+            ),
+        });
+    }
+
+    match placement {
+        Placement::Naive => {
+            let layout: Vec<usize> = (0..circuit.num_qubits()).collect();
+            route(circuit, calibration, &calibration.paths(false), &layout)
+        }
+        Placement::NoiseAdaptive => {
+            let paths = calibration.paths(true);
+            let trivial: Vec<usize> = (0..circuit.num_qubits()).collect();
+            let greedy = greedy_layout(circuit, calibration, &paths);
+            let mut best = route(circuit, calibration, &paths, &greedy)?;
+            if let Ok(candidate) = route(circuit, calibration, &paths, &trivial) {
+                if candidate.fidelity > best.fidelity {
+                    best = candidate;
+                }
+            }
+            for _ in 0..MAX_ROUNDS {
+                let mut improved = false;
+                for logical in 0..circuit.num_qubits() {
+                    for physical in 0..calibration.num_qubits() {
+                        let mut layout = best.initial_layout.clone();
+                        match layout.iter().position(|&used| used == physical) {
+                            Some(other) => layout.swap(logical, other),
+                            None => layout[logical] = physical,
+                        }
+                        if layout == best.initial_layout {
+                            continue;
+                        }
+                        if let Ok(candidate) = route(circuit, calibration, &paths, &layout) {
+                            if candidate.fidelity > best.fidelity {
+                                best = candidate;
+                                improved = true;
+                            }
+                        }
+                    }
+                }
+                if !improved {
+                    break;
+                }
+            }
+            Ok(best)
+        }
+    }
+}
+
+/// Transpiles a circuit with both placements, to show how much the calibration data helps.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to place.
+/// * `calibration` - The device's coupling map and error rates.
+///
+/// # Returns
+///
+/// * `Ok(PlacementComparison)` - Both transpilations.
+/// * `Err(QuantumError)` - If the circuit cannot be routed, see [`transpile`].
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::transpile::{compare_placements, Calibration};
+///
+/// let mut calibration = Calibration::new(3);
+/// calibration.add_coupling(0, 1, 0.2).add_coupling(1, 2, 0.01);
+/// let mut circuit = Circuit::new(2);
+/// circuit.h(0).cnot(0, 1);
+/// let comparison = compare_placements(&circuit, &calibration).unwrap();
+/// assert!(comparison.improvement() > 0.15);
+/// ```
+pub fn compare_placements(
+    circuit: &Circuit,
+    calibration: &Calibration,
+) -> Result<PlacementComparison, QuantumError> {
+    Ok(PlacementComparison {
+        adaptive: transpile(circuit, calibration, Placement::NoiseAdaptive)?,
+        naive: transpile(circuit, calibration, Placement::Naive)?,
+    })
+}
+
+/// The cheapest path costs between all pairs of qubits and the next hop along each path.
+struct Paths {
+    cost: Vec<Vec<f64>>,
+    next: Vec<Vec<Option<usize>>>,
+}
+
+impl Paths {
+    /// Returns the qubits on the cheapest path from `a` to `b`, both included, or `None` if they
+    /// are not connected.
+    fn path(&self, a: usize, b: usize) -> Option<Vec<usize>> {
+        let mut path = vec![a];
+        let mut current = a;
+        while current != b {
+            current = self.next[current][b]?;
+            path.push(current);
+        }
+        Some(path)
+    }
+}
+
+/// Returns an error if the circuit has more qubits than the device.
+fn check_width(circuit: &Circuit, calibration: &Calibration) -> Result<(), QuantumError> {
+    if circuit.num_qubits() > calibration.num_qubits() {
+        return Err(QuantumError::Unsupported {
+            feature: format!(
+                "placing {} qubits on a {}-qubit device",
+                circuit.num_qubits(),
+                calibration.num_qubits()
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the success probability of one instruction on physical qubits.
+fn success(instruction: &Instruction, calibration: &Calibration) -> Result<f64, QuantumError> {
+    let operation = match &instruction.operation {
+        Operation::Conditional { operation, .. } => operation.as_ref(),
+        operation => operation,
+    };
+    match (operation, instruction.qubits.as_slice()) {
+        (
+            Operation::Snapshot(_)
+            | Operation::Barrier(_)
+            | Operation::Assert(_)
+            | Operation::Reset,
+            _,
+        ) => Ok(1.0),
+        (Operation::Measure(_), &[qubit]) => Ok(1.0 - calibration.readout_error(qubit)),
+        (_, &[qubit]) => Ok(1.0 - calibration.gate_error(qubit)),
+        (_, &[a, b]) => {
+            let error =
+                calibration
+                    .coupling_error(a, b)
+                    .ok_or_else(|| QuantumError::Unsupported {
+                        feature: format!(
+                            "{} on uncoupled qubits {} and {}",
+                            operation.name(),
+                            a,
+                            b
+                        ),
+                    })?;
+            let gates = if *operation == Operation::Swap {
+                GATES_PER_SWAP
+            } else {
+                1
+            };
+            Ok((1.0 - error).powi(gates))
+        }
+        (_, qubits) => Err(QuantumError::Unsupported {
+            feature: format!("{} on {} qubits", operation.name(), qubits.len()),
+        }),
+    }
+}
+
+/// Routes a circuit from an initial layout, moving the first qubit of every uncoupled two-qubit
+/// gate along the cheapest path until it neighbours the second.
+fn route(
+    circuit: &Circuit,
+    calibration: &Calibration,
+    paths: &Paths,
+    layout: &[usize],
+) -> Result<Transpilation, QuantumError> {
+    let mut routed = Circuit::with_clbits(calibration.num_qubits(), circuit.num_clbits());
+    let mut position = layout.to_vec();
+    let mut occupant: Vec<Option<usize>> = vec![None; calibration.num_qubits()];
+    for (logical, &physical) in layout.iter().enumerate() {
+        occupant[physical] = Some(logical);
+    }
+    let mut swaps = 0;
+    for instruction in circuit.instructions() {
+        if let &[a, b] = instruction.qubits.as_slice() {
+            let path =
+                paths
+                    .path(position[a], position[b])
+                    .ok_or_else(|| QuantumError::Unsupported {
+                        feature: format!(
+                            "routing between unconnected physical qubits {} and {}",
+                            position[a], position[b]
+                        ),
+                    })?;
+            for hop in path.windows(2).take(path.len().saturating_sub(2)) {
+                let (from, to) = (hop[0], hop[1]);
+                routed.swap(from, to);
+                swaps += 1;
+                occupant.swap(from, to);
+                for physical in [from, to] {
+                    if let Some(logical) = occupant[physical] {
+                        position[logical] = physical;
+                    }
+                }
+            }
+        }
+        routed.add_instruction(Instruction::new(
+            instruction.operation.clone(),
+            instruction
+                .qubits
+                .iter()
+                .map(|&qubit| position[qubit])
+                .collect(),
+        ))?;
+    }
+    let fidelity = predicted_fidelity(&routed, calibration)?;
+    Ok(Transpilation {
+        circuit: routed,
+        initial_layout: layout.to_vec(),
+        final_layout: position,
+        swaps,
+        fidelity,
+    })
+}
+
+/// Places logical qubits one at a time, most interacting first, each on the free physical qubit
+/// that is cheapest to reach from its already placed partners and has the lowest own errors.
+fn greedy_layout(circuit: &Circuit, calibration: &Calibration, paths: &Paths) -> Vec<usize> {
+    let n = circuit.num_qubits();
+    let mut interactions = vec![vec![0usize; n]; n];
+    let mut single = vec![0usize; n];
+    let mut measured = vec![0usize; n];
+    for instruction in circuit.instructions() {
+        match (&instruction.operation, instruction.qubits.as_slice()) {
+            (Operation::Measure(_), &[qubit]) => measured[qubit] += 1,
+            (_, &[qubit]) => single[qubit] += 1,
+            (_, &[a, b]) => {
+                interactions[a][b] += 1;
+                interactions[b][a] += 1;
+            }
+            _ => {}
+        }
+    }
+    let degree = |logical: usize| interactions[logical].iter().sum::<usize>();
+
+    let mut layout: Vec<Option<usize>> = vec![None; n];
+    let mut used = vec![false; calibration.num_qubits()];
+    for _ in 0..n {
+        let logical = (0..n)
+            .filter(|&logical| layout[logical].is_none())
+            .max_by_key(|&logical| {
+                let placed: usize = (0..n)
+                    .filter(|&other| layout[other].is_some())
+                    .map(|other| interactions[logical][other])
+                    .sum();
+                (placed, degree(logical), std::cmp::Reverse(logical))
+            })
+            .expect("an unplaced logical qubit remains");
+        let cost = |physical: usize| -> f64 {
+            let own = -(single[logical] as f64) * (1.0 - calibration.gate_error(physical)).ln()
+                - (measured[logical] as f64) * (1.0 - calibration.readout_error(physical)).ln();
+            let partners: f64 = (0..n)
+                .filter_map(|other| layout[other].map(|placed| (other, placed)))
+                .map(|(other, placed)| {
+                    interactions[logical][other] as f64 * paths.cost[physical][placed]
+                })
+                .sum();
+            let placed_partners =
+                (0..n).any(|other| layout[other].is_some() && interactions[logical][other] > 0);
+            if partners > 0.0 || placed_partners || degree(logical) == 0 {
+                return own + partners;
+            }
+            // The first qubit of a group has no partners yet, so rate its best coupling instead
+            let best_coupling = calibration
+                .couplings()
+                .filter(|((a, b), _)| *a == physical || *b == physical)
+                .map(|(_, error)| -(1.0 - error).ln())
+                .fold(f64::INFINITY, f64::min);
+            own + degree(logical) as f64 * best_coupling
+        };
+        let physical = (0..calibration.num_qubits())
+            .filter(|&physical| !used[physical])
+            .min_by(|&p, &q| cost(p).total_cmp(&cost(q)))
+            .expect("the device is at least as wide as the circuit");
+        layout[logical] = Some(physical);
+        used[physical] = true;
+    }
+    layout
+        .into_iter()
+        .map(|physical| physical.expect("every logical qubit is placed"))
+        .collect()
+}
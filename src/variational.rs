@@ -0,0 +1,885 @@
+//! This module runs variational algorithms such as VQE and QAOA: a parameterized [`Ansatz`]
+//! prepares a trial state, a [`Hamiltonian`] gives its energy and [`GradientDescent`] tunes the
+//! parameters to minimize that energy.
+//!
+//! A `Hamiltonian` also generates time evolution: [`Hamiltonian::trotter_circuit`] builds the
+//! Trotterized circuit for `exp(-iHt)` and [`Hamiltonian::evolution`] the exact operator to check
+//! it against on small registers.
+//!
+//! The optimizer reports every [`Iteration`] to a callback as it goes, so a run can be streamed to
+//! a log, a CSV file or a live chart instead of only returning the final result.
+
+use crate::circuit::Circuit;
+use crate::error::QuantumError;
+use crate::gates::Gate;
+use crate::linalg;
+use crate::qubit::{Basis, Qubit};
+use num_complex::Complex;
+use std::f64::consts::FRAC_PI_2;
+use std::fmt;
+
+/// Step of the central differences [`GradientDescent`] estimates gradients with.
+const FINITE_DIFFERENCE_STEP: f64 = 1e-5;
+
+/// A `PauliString` is a tensor product of single-qubit Pauli operators, e.g. `X0 Z2`, with the
+/// identity on every qubit it does not name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PauliString {
+    // Qubit `k` has `X` if only bit `k` of `x_mask` is set, `Z` if only bit `k` of `z_mask` is
+    // set, `Y` if both are set and the identity otherwise
+    x_mask: usize,
+    z_mask: usize,
+}
+
+impl PauliString {
+    /// Returns the identity, the empty product.
+    pub fn identity() -> Self {
+        PauliString::default()
+    }
+
+    /// Parses Pauli factors such as `X0`, `Y2` or `Z10` naming the qubit they act on, separated
+    /// by whitespace or `*`. An empty string is the identity.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The factors, e.g. `"X0 Z1"`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PauliString)` - The product.
+    /// * `Err(QuantumError::Parse)` - If a token is not a Pauli factor or names a qubit beyond
+    ///   the 64th (on 64-bit targets).
+    /// * `Err(QuantumError::DuplicateQubit)` - If a qubit is named twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::variational::PauliString;
+    ///
+    /// let pauli = PauliString::parse("X0 * Y3").unwrap();
+    /// assert_eq!(pauli.num_qubits(), 4);
+    /// assert_eq!(pauli.to_string(), "X0 Y3");
+    /// assert!(PauliString::parse("Z1 Z1").is_err());
+    /// assert!(PauliString::parse("X1000").is_err());
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, QuantumError> {
+        source
+            .split(|c: char| c.is_whitespace() || c == '*')
+            .filter(|token| !token.is_empty())
+            .try_fold(PauliString::identity(), PauliString::with_factor)
+    }
+
+    /// Returns the product with one more factor, e.g. `Z3`.
+    fn with_factor(mut self, token: &str) -> Result<Self, QuantumError> {
+        let mut chars = token.chars();
+        let pauli = chars.next().expect("tokens are not empty");
+        let qubit: usize = chars.as_str().parse().map_err(|_| QuantumError::Parse {
+            line: 1,
+            message: format!("unexpected token '{}'", token),
+        })?;
+        if qubit >= usize::BITS as usize {
+            return Err(QuantumError::Parse {
+                line: 1,
+                message: format!(
+                    "qubit {} in '{}' is beyond the {} a Pauli string can name",
+                    qubit,
+                    token,
+                    usize::BITS
+                ),
+            });
+        }
+        let bit = 1 << qubit;
+        if (self.x_mask | self.z_mask) & bit != 0 {
+            return Err(QuantumError::DuplicateQubit { qubit });
+        }
+        match pauli.to_ascii_uppercase() {
+            'I' => {}
+            'X' => self.x_mask |= bit,
+            'Y' => {
+                self.x_mask |= bit;
+                self.z_mask |= bit;
+            }
+            'Z' => self.z_mask |= bit,
+            _ => {
+                return Err(QuantumError::Parse {
+                    line: 1,
+                    message: format!("unknown Pauli operator in '{}'", token),
+                })
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns the number of qubits up to the highest one with a non-identity factor, `0` for the
+    /// identity.
+    pub fn num_qubits(&self) -> usize {
+        (usize::BITS - (self.x_mask | self.z_mask).leading_zeros()) as usize
+    }
+
+    /// Returns the factor acting on a qubit, named by the basis that measures it, or `None` for
+    /// the identity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Basis;
+    /// use quantum_simulator::variational::PauliString;
+    ///
+    /// let pauli = PauliString::parse("X0 Y2").unwrap();
+    /// assert_eq!(pauli.factor(0), Some(Basis::X));
+    /// assert_eq!(pauli.factor(1), None);
+    /// assert_eq!(pauli.factor(2), Some(Basis::Y));
+    /// ```
+    pub fn factor(&self, qubit: usize) -> Option<Basis> {
+        let bit = |mask: usize| {
+            u32::try_from(qubit)
+                .ok()
+                .and_then(|shift| mask.checked_shr(shift))
+                .map_or(0, |shifted| shifted & 1)
+        };
+        match (bit(self.x_mask), bit(self.z_mask)) {
+            (1, 0) => Some(Basis::X),
+            (1, 1) => Some(Basis::Y),
+            (0, 1) => Some(Basis::Z),
+            _ => None,
+        }
+    }
+
+    /// Returns whether every factor is `Z` or the identity, so the string is diagonal in the
+    /// computational basis.
+    pub fn is_diagonal(&self) -> bool {
+        self.x_mask == 0
+    }
+
+    /// Appends `exp(-iθP)` to a circuit: each factor is rotated onto `Z`, a CNOT ladder gathers
+    /// the parity onto the highest qubit, `Rz(2θ)` rotates it, and the ladder and basis changes
+    /// are undone. The identity only contributes a global phase and appends nothing.
+    fn append_exponential(&self, circuit: &mut Circuit, theta: f64) {
+        let support: Vec<(usize, Basis)> = (0..self.num_qubits())
+            .filter_map(|qubit| self.factor(qubit).map(|basis| (qubit, basis)))
+            .collect();
+        let Some(&(last, _)) = support.last() else {
+            return;
+        };
+        // H Z H = X and Rx(-π/2) Z Rx(π/2) = Y
+        for &(qubit, basis) in &support {
+            match basis {
+                Basis::X => {
+                    circuit.h(qubit);
+                }
+                Basis::Y => {
+                    circuit.rx(qubit, FRAC_PI_2);
+                }
+                Basis::Z => {}
+            }
+        }
+        for pair in support.windows(2) {
+            circuit.cnot(pair[0].0, pair[1].0);
+        }
+        circuit.rz(last, 2.0 * theta);
+        for pair in support.windows(2).rev() {
+            circuit.cnot(pair[0].0, pair[1].0);
+        }
+        for &(qubit, basis) in &support {
+            match basis {
+                Basis::X => {
+                    circuit.h(qubit);
+                }
+                Basis::Y => {
+                    circuit.rx(qubit, -FRAC_PI_2);
+                }
+                Basis::Z => {}
+            }
+        }
+    }
+
+    /// Returns the expectation value `⟨ψ|P|ψ⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The state `|ψ⟩`, with at least [`PauliString::num_qubits`] qubits.
+    pub fn expectation(&self, qubit: &Qubit) -> f64 {
+        // P|i⟩ = i^(#Y) (-1)^(popcount(i & z_mask)) |i ⊕ x_mask⟩
+        let y_phase = Complex::<f64>::i().powu((self.x_mask & self.z_mask).count_ones());
+        let sum: Complex<f64> = qubit
+            .state
+            .iter()
+            .enumerate()
+            .map(|(i, amplitude)| {
+                let sign = if (i & self.z_mask).count_ones().is_multiple_of(2) {
+                    1.0
+                } else {
+                    -1.0
+                };
+                qubit.state[i ^ self.x_mask].conj() * amplitude * sign
+            })
+            .sum();
+        (y_phase * sum).re
+    }
+}
+
+impl fmt::Display for PauliString {
+    /// Formats the factors in qubit order, e.g. `X0 Z2`, or `I` for the identity.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let factors: Vec<String> = (0..self.num_qubits())
+            .filter_map(|qubit| {
+                let (x, z) = (self.x_mask >> qubit & 1, self.z_mask >> qubit & 1);
+                match (x, z) {
+                    (1, 0) => Some(format!("X{}", qubit)),
+                    (1, 1) => Some(format!("Y{}", qubit)),
+                    (0, 1) => Some(format!("Z{}", qubit)),
+                    _ => None,
+                }
+            })
+            .collect();
+        if factors.is_empty() {
+            write!(f, "I")
+        } else {
+            write!(f, "{}", factors.join(" "))
+        }
+    }
+}
+
+/// A `Hamiltonian` is a real-weighted sum of Pauli strings, e.g. `Z0 Z1 + 0.5 X0 + 0.5 X1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hamiltonian {
+    terms: Vec<(f64, PauliString)>,
+    num_qubits: usize,
+}
+
+impl Hamiltonian {
+    /// Creates the weighted sum of the given Pauli strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `terms` - The coefficients and their Pauli strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::variational::{Hamiltonian, PauliString};
+    ///
+    /// let zz = PauliString::parse("Z0 Z1").unwrap();
+    /// let x = PauliString::parse("X0").unwrap();
+    /// let hamiltonian = Hamiltonian::from_terms([(1.0, zz), (0.5, x)]);
+    /// assert_eq!(hamiltonian, Hamiltonian::parse("Z0 Z1 + 0.5 X0").unwrap());
+    /// ```
+    pub fn from_terms(terms: impl IntoIterator<Item = (f64, PauliString)>) -> Self {
+        let terms: Vec<(f64, PauliString)> = terms.into_iter().collect();
+        let num_qubits = terms
+            .iter()
+            .map(|(_, pauli)| pauli.num_qubits())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        Hamiltonian { terms, num_qubits }
+    }
+
+    /// Parses a sum of terms separated by `+` or `-`, where each term is an optional coefficient
+    /// followed by Pauli factors such as `X0`, `Y2` or `Z10` naming the qubit they act on.
+    /// Factors and the coefficient are separated by whitespace or `*`, and a term without factors
+    /// is a constant offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The Hamiltonian, e.g. `"Z0*Z1 - 0.5 X0 + 2"`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Hamiltonian)` - The parsed Hamiltonian, acting on one more qubit than the highest
+    ///   qubit it names.
+    /// * `Err(QuantumError::Parse)` - If a token is neither a number nor a Pauli factor, or a term
+    ///   is empty.
+    /// * `Err(QuantumError::DuplicateQubit)` - If a term names a qubit twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::variational::Hamiltonian;
+    ///
+    /// let hamiltonian = Hamiltonian::parse("Z0 Z1 + 0.5*X0 - 0.5 Y2").unwrap();
+    /// assert_eq!(hamiltonian.num_qubits(), 3);
+    /// assert!(!hamiltonian.is_diagonal());
+    /// assert!(Hamiltonian::parse("Z0 Q1").is_err());
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, QuantumError> {
+        // A sign starts a new term unless it belongs to a coefficient's exponent or follows `*`
+        let mut terms = vec![String::new()];
+        for c in source.chars() {
+            let current = terms.last_mut().expect("there is always a current term");
+            let previous = current.trim_end().chars().last();
+            if (c == '+' || c == '-')
+                && previous.is_some_and(|p| !matches!(p, 'e' | 'E' | '*' | '+' | '-'))
+            {
+                terms.push(c.to_string());
+            } else {
+                current.push(c);
+            }
+        }
+
+        let mut parsed = vec![];
+        for term in &terms {
+            let mut rest = term.trim();
+            let mut coefficient = 1.0;
+            if let Some(stripped) = rest.strip_prefix('-') {
+                coefficient = -1.0;
+                rest = stripped;
+            } else if let Some(stripped) = rest.strip_prefix('+') {
+                rest = stripped;
+            }
+            let tokens: Vec<&str> = rest
+                .split(|c: char| c.is_whitespace() || c == '*')
+                .filter(|token| !token.is_empty())
+                .collect();
+            if tokens.is_empty() {
+                return Err(QuantumError::Parse {
+                    line: 1,
+                    message: format!("empty term in '{}'", source),
+                });
+            }
+
+            let mut pauli = PauliString::identity();
+            for token in tokens {
+                match token.parse::<f64>() {
+                    Ok(value) => coefficient *= value,
+                    Err(_) => pauli = pauli.with_factor(token)?,
+                }
+            }
+            parsed.push((coefficient, pauli));
+        }
+        Ok(Hamiltonian::from_terms(parsed))
+    }
+
+    /// Returns the coefficients and Pauli strings of the terms, in order.
+    pub fn terms(&self) -> &[(f64, PauliString)] {
+        &self.terms
+    }
+
+    /// Returns the number of qubits the Hamiltonian acts on.
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Returns whether every term is a product of `Z`s and identities, i.e. the Hamiltonian is
+    /// diagonal in the computational basis, as a QAOA cost Hamiltonian must be.
+    pub fn is_diagonal(&self) -> bool {
+        self.terms.iter().all(|(_, pauli)| pauli.is_diagonal())
+    }
+
+    /// Returns the expectation value `⟨ψ|H|ψ⟩`.
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The normalized state `|ψ⟩`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state has fewer qubits than the Hamiltonian acts on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::variational::Hamiltonian;
+    ///
+    /// // |10⟩ has qubit 1 set, so ⟨Z0⟩ = 1 and ⟨Z1⟩ = -1
+    /// let hamiltonian = Hamiltonian::parse("Z0 + 2 Z1").unwrap();
+    /// let state = Qubit::from_bitstring("10").unwrap();
+    /// assert!((hamiltonian.expectation(&state) + 1.0).abs() < 1e-12);
+    ///
+    /// let x = Hamiltonian::parse("X0 X1").unwrap();
+    /// assert!((x.expectation(&Qubit::plus_state(2)) - 1.0).abs() < 1e-12);
+    /// ```
+    pub fn expectation(&self, qubit: &Qubit) -> f64 {
+        assert!(
+            qubit.state.len() >= 1 << self.num_qubits,
+            "the state has fewer qubits than the Hamiltonian"
+        );
+        self.terms
+            .iter()
+            .map(|(coefficient, pauli)| coefficient * pauli.expectation(qubit))
+            .sum()
+    }
+
+    /// Returns the first-order Trotter circuit for the time evolution `exp(-iHt)`.
+    ///
+    /// This is [`Hamiltonian::trotter_circuit_with_order`] with [`TrotterOrder::First`].
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The evolution time `t`.
+    /// * `steps` - The number of Trotter steps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `steps` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use quantum_simulator::variational::Hamiltonian;
+    ///
+    /// // A transverse-field Ising chain, where the terms do not commute
+    /// let hamiltonian = Hamiltonian::parse("Z0 Z1 + Z1 Z2 + 0.7 X0 + 0.7 X1 + 0.7 X2").unwrap();
+    /// let initial = Qubit::zero_state(3);
+    /// let exact = hamiltonian.evolve(&initial, 1.0);
+    ///
+    /// let circuit = hamiltonian.trotter_circuit(1.0, 50);
+    /// let trotterized = Simulator::run(&circuit, &initial.state);
+    /// let overlap: num_complex::Complex<f64> =
+    ///     exact.state.iter().zip(&trotterized.state).map(|(a, b)| a.conj() * b).sum();
+    /// assert!(overlap.norm() > 0.999);
+    /// ```
+    pub fn trotter_circuit(&self, time: f64, steps: usize) -> Circuit {
+        self.trotter_circuit_with_order(time, steps, TrotterOrder::First)
+    }
+
+    /// Returns a Trotterized circuit for the time evolution `exp(-iHt)`, splitting `t` into
+    /// `steps` equal steps of a product of one `exp(-iθP)` per term.
+    ///
+    /// The circuit acts on [`Hamiltonian::num_qubits`] qubits and drops the global phase of
+    /// constant terms.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The evolution time `t`.
+    /// * `steps` - The number of Trotter steps.
+    /// * `order` - The product formula to apply per step.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `steps` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::qubit::Qubit;
+    /// use quantum_simulator::simulator::Simulator;
+    /// use quantum_simulator::variational::{Hamiltonian, TrotterOrder};
+    ///
+    /// let hamiltonian = Hamiltonian::parse("X0 X1 + 0.5 Y0 + 0.5 Z1").unwrap();
+    /// let initial = Qubit::zero_state(2);
+    /// let exact = hamiltonian.evolve(&initial, 2.0);
+    /// let infidelity = |order| {
+    ///     let circuit = hamiltonian.trotter_circuit_with_order(2.0, 8, order);
+    ///     let state = Simulator::run(&circuit, &initial.state);
+    ///     let overlap: num_complex::Complex<f64> =
+    ///         exact.state.iter().zip(&state.state).map(|(a, b)| a.conj() * b).sum();
+    ///     1.0 - overlap.norm_sqr()
+    /// };
+    /// assert!(infidelity(TrotterOrder::Second) < infidelity(TrotterOrder::First));
+    /// ```
+    pub fn trotter_circuit_with_order(
+        &self,
+        time: f64,
+        steps: usize,
+        order: TrotterOrder,
+    ) -> Circuit {
+        assert!(steps > 0, "a Trotter circuit needs at least one step");
+        let step = time / steps as f64;
+        let mut circuit = Circuit::new(self.num_qubits);
+        for _ in 0..steps {
+            match order {
+                TrotterOrder::First => {
+                    for (coefficient, pauli) in &self.terms {
+                        pauli.append_exponential(&mut circuit, coefficient * step);
+                    }
+                }
+                TrotterOrder::Second => {
+                    for (coefficient, pauli) in self.terms.iter().chain(self.terms.iter().rev()) {
+                        pauli.append_exponential(&mut circuit, coefficient * step / 2.0);
+                    }
+                }
+            }
+        }
+        circuit
+    }
+
+    /// Returns the exact time-evolution operator `exp(-iHt)`, including the phase of constant
+    /// terms, by diagonalizing the dense matrix of `H`.
+    ///
+    /// The matrix has `4^n` entries, so this is meant for cross-checking Trotter circuits on
+    /// small registers.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The evolution time `t`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::gates::rx;
+    /// use quantum_simulator::variational::Hamiltonian;
+    ///
+    /// // exp(-iθX) is Rx(2θ)
+    /// let evolution = Hamiltonian::parse("X0").unwrap().evolution(0.3);
+    /// let expected = rx(0.6);
+    /// for (row, expected_row) in evolution.matrix.iter().zip(expected.matrix.iter()) {
+    ///     for (a, b) in row.iter().zip(expected_row) {
+    ///         assert!((a - b).norm() < 1e-10);
+    ///     }
+    /// }
+    /// ```
+    pub fn evolution(&self, time: f64) -> Gate {
+        let (energies, vectors) = linalg::eigh(&self.matrix());
+        let size = energies.len();
+        let mut result = linalg::zeros(size, size);
+        for (k, energy) in energies.iter().enumerate() {
+            let phase = Complex::from_polar(1.0, -energy * time);
+            for (i, row) in result.iter_mut().enumerate() {
+                for (j, elem) in row.iter_mut().enumerate() {
+                    *elem += phase * vectors[i][k] * vectors[j][k].conj();
+                }
+            }
+        }
+        Gate::new(result)
+    }
+
+    /// Returns `exp(-iHt)|ψ⟩`, evolved exactly with [`Hamiltonian::evolution`].
+    ///
+    /// # Arguments
+    ///
+    /// * `qubit` - The state `|ψ⟩`, on exactly [`Hamiltonian::num_qubits`] qubits.
+    /// * `time` - The evolution time `t`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state does not have as many qubits as the Hamiltonian.
+    pub fn evolve(&self, qubit: &Qubit, time: f64) -> Qubit {
+        assert_eq!(
+            qubit.state.len(),
+            1 << self.num_qubits,
+            "the state must have as many qubits as the Hamiltonian"
+        );
+        let mut evolved = qubit.clone();
+        let targets: Vec<usize> = (0..self.num_qubits).collect();
+        self.evolution(time).apply_to(&mut evolved, &targets);
+        evolved
+    }
+
+    /// Returns the dense matrix of `H` on [`Hamiltonian::num_qubits`] qubits.
+    fn matrix(&self) -> Vec<Vec<Complex<f64>>> {
+        let size = 1 << self.num_qubits;
+        let mut matrix = linalg::zeros(size, size);
+        for (coefficient, pauli) in &self.terms {
+            // P|i⟩ = i^(#Y) (-1)^(popcount(i & z_mask)) |i ⊕ x_mask⟩
+            let y_phase = Complex::<f64>::i().powu((pauli.x_mask & pauli.z_mask).count_ones());
+            for i in 0..size {
+                let sign = if (i & pauli.z_mask).count_ones().is_multiple_of(2) {
+                    1.0
+                } else {
+                    -1.0
+                };
+                matrix[i ^ pauli.x_mask][i] += y_phase * sign * *coefficient;
+            }
+        }
+        matrix
+    }
+}
+
+/// The order of the product formula [`Hamiltonian::trotter_circuit_with_order`] approximates
+/// `exp(-iHt)` with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrotterOrder {
+    /// `exp(-iH₁δ) ⋯ exp(-iHₘδ)` per step, with an error of `O(t²/steps)`.
+    First,
+    /// The symmetric product `exp(-iH₁δ/2) ⋯ exp(-iHₘδ/2) exp(-iHₘδ/2) ⋯ exp(-iH₁δ/2)` per
+    /// step, with an error of `O(t³/steps²)`.
+    Second,
+}
+
+/// A parameterized circuit preparing the trial states of a variational algorithm from `|0…0⟩`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ansatz {
+    /// `layers` rounds of `Ry` on every qubit followed by a chain of CNOTs, finished by one more
+    /// round of `Ry`, with one parameter per rotation.
+    HardwareEfficient { num_qubits: usize, layers: usize },
+    /// The QAOA circuit for a diagonal cost Hamiltonian: a uniform superposition followed by
+    /// `layers` rounds of `exp(-iγH)` and the mixer `exp(-iβ Σ Xₖ)`, with the parameters
+    /// `[γ₁, β₁, γ₂, β₂, …]`.
+    Qaoa {
+        hamiltonian: Hamiltonian,
+        layers: usize,
+    },
+}
+
+impl Ansatz {
+    /// Returns the number of qubits of the circuit.
+    pub fn num_qubits(&self) -> usize {
+        match self {
+            Ansatz::HardwareEfficient { num_qubits, .. } => *num_qubits,
+            Ansatz::Qaoa { hamiltonian, .. } => hamiltonian.num_qubits(),
+        }
+    }
+
+    /// Returns the number of parameters [`Ansatz::circuit`] expects.
+    pub fn num_parameters(&self) -> usize {
+        match self {
+            Ansatz::HardwareEfficient { num_qubits, layers } => (layers + 1) * num_qubits,
+            Ansatz::Qaoa { layers, .. } => 2 * layers,
+        }
+    }
+
+    /// Builds the circuit for a set of parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters, see the variants for their order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `params` does not have [`Ansatz::num_parameters`] entries, or a QAOA cost
+    /// Hamiltonian is not diagonal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::variational::{Ansatz, Hamiltonian};
+    ///
+    /// let ansatz = Ansatz::HardwareEfficient { num_qubits: 2, layers: 1 };
+    /// assert_eq!(ansatz.num_parameters(), 4);
+    /// assert_eq!(ansatz.circuit(&[0.1, 0.2, 0.3, 0.4]).num_qubits(), 2);
+    ///
+    /// let cost = Hamiltonian::parse("Z0 Z1 + Z1 Z2").unwrap();
+    /// let qaoa = Ansatz::Qaoa { hamiltonian: cost, layers: 2 };
+    /// assert_eq!(qaoa.num_parameters(), 4);
+    /// ```
+    pub fn circuit(&self, params: &[f64]) -> Circuit {
+        assert_eq!(
+            params.len(),
+            self.num_parameters(),
+            "expected {} parameters",
+            self.num_parameters()
+        );
+        let num_qubits = self.num_qubits();
+        let mut circuit = Circuit::new(num_qubits);
+        match self {
+            Ansatz::HardwareEfficient { layers, .. } => {
+                for layer in 0..=*layers {
+                    for qubit in 0..num_qubits {
+                        circuit.ry(qubit, params[layer * num_qubits + qubit]);
+                    }
+                    if layer < *layers {
+                        for qubit in 1..num_qubits {
+                            circuit.cnot(qubit - 1, qubit);
+                        }
+                    }
+                }
+            }
+            Ansatz::Qaoa {
+                hamiltonian,
+                layers,
+            } => {
+                assert!(
+                    hamiltonian.is_diagonal(),
+                    "a QAOA cost Hamiltonian must be diagonal"
+                );
+                for qubit in 0..num_qubits {
+                    circuit.h(qubit);
+                }
+                for layer in 0..*layers {
+                    let (gamma, beta) = (params[2 * layer], params[2 * layer + 1]);
+                    for (coefficient, pauli) in &hamiltonian.terms {
+                        // exp(-iγcZ…Z) is an Rz(2γc) on the parity of the term's qubits
+                        let qubits: Vec<usize> = (0..num_qubits)
+                            .filter(|qubit| pauli.z_mask & (1 << qubit) != 0)
+                            .collect();
+                        let Some(&last) = qubits.last() else {
+                            continue;
+                        };
+                        for pair in qubits.windows(2) {
+                            circuit.cnot(pair[0], pair[1]);
+                        }
+                        circuit.rz(last, 2.0 * gamma * coefficient);
+                        for pair in qubits.windows(2).rev() {
+                            circuit.cnot(pair[0], pair[1]);
+                        }
+                    }
+                    for qubit in 0..num_qubits {
+                        circuit.rx(qubit, 2.0 * beta);
+                    }
+                }
+            }
+        }
+        circuit
+    }
+}
+
+/// The state of an optimization after one step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Iteration {
+    /// The number of steps taken, `0` for the starting point.
+    pub step: usize,
+    /// The cost at `params`.
+    pub energy: f64,
+    /// The Euclidean norm of the gradient at `params`, or NaN for an optimizer that does not
+    /// compute gradients.
+    pub gradient_norm: f64,
+    /// The parameters.
+    pub params: Vec<f64>,
+}
+
+impl Iteration {
+    /// Returns the header of the CSV rows written by [`Iteration::to_csv_row`].
+    ///
+    /// # Arguments
+    ///
+    /// * `num_parameters` - The number of parameters, one column each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::variational::Iteration;
+    ///
+    /// assert_eq!(Iteration::csv_header(2), "step,energy,gradient_norm,theta0,theta1");
+    /// let iteration = Iteration { step: 3, energy: -0.5, gradient_norm: 0.25, params: vec![1.0, 2.0] };
+    /// assert_eq!(iteration.to_csv_row(), "3,-0.5,0.25,1,2");
+    /// ```
+    pub fn csv_header(num_parameters: usize) -> String {
+        let mut header = String::from("step,energy,gradient_norm");
+        for k in 0..num_parameters {
+            header.push_str(&format!(",theta{}", k));
+        }
+        header
+    }
+
+    /// Returns the iteration as a CSV row without a line break, matching
+    /// [`Iteration::csv_header`].
+    pub fn to_csv_row(&self) -> String {
+        let mut row = format!("{},{},{}", self.step, self.energy, self.gradient_norm);
+        for param in &self.params {
+            row.push_str(&format!(",{}", param));
+        }
+        row
+    }
+}
+
+/// Minimizes a cost function by gradient descent with central-difference gradients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientDescent {
+    /// The factor the gradient is scaled by for each step.
+    pub learning_rate: f64,
+    /// The maximum number of steps.
+    pub max_iterations: usize,
+    /// The optimization stops early once the gradient norm drops below this value.
+    pub tolerance: f64,
+}
+
+impl GradientDescent {
+    /// Creates an optimizer with a tolerance of `1e-6`.
+    ///
+    /// # Arguments
+    ///
+    /// * `learning_rate` - The factor the gradient is scaled by for each step.
+    /// * `max_iterations` - The maximum number of steps.
+    pub fn new(learning_rate: f64, max_iterations: usize) -> Self {
+        GradientDescent {
+            learning_rate,
+            max_iterations,
+            tolerance: 1e-6,
+        }
+    }
+
+    /// Returns a copy of the optimizer that stops once the gradient norm drops below
+    /// `tolerance`.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Minimizes `cost` starting from `initial`, calling `on_iteration` with the starting point and
+    /// after every step.
+    ///
+    /// # Arguments
+    ///
+    /// * `cost` - The function to minimize, e.g. the energy of an ansatz state.
+    /// * `initial` - The starting parameters.
+    /// * `on_iteration` - Called with each iteration as soon as it is known.
+    ///
+    /// # Returns
+    ///
+    /// * The last iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quantum_simulator::prelude::*;
+    /// use quantum_simulator::variational::{Ansatz, GradientDescent, Hamiltonian};
+    ///
+    /// // The ground state of Z0 + Z1 is |11⟩ with energy -2
+    /// let hamiltonian = Hamiltonian::parse("Z0 + Z1").unwrap();
+    /// let ansatz = Ansatz::HardwareEfficient { num_qubits: 2, layers: 0 };
+    /// let energy = |params: &[f64]| {
+    ///     hamiltonian.expectation(&Simulator::run(&ansatz.circuit(params), &Qubit::zero_state(2).state))
+    /// };
+    ///
+    /// let mut energies = vec![];
+    /// let last = GradientDescent::new(0.4, 200).minimize(energy, &[0.5, 0.5], |iteration| {
+    ///     energies.push(iteration.energy);
+    /// });
+    /// assert!((last.energy + 2.0).abs() < 1e-6);
+    /// assert_eq!(energies.len(), last.step + 1);
+    /// assert!(energies.windows(2).all(|pair| pair[1] <= pair[0]));
+    /// ```
+    pub fn minimize(
+        &self,
+        cost: impl Fn(&[f64]) -> f64,
+        initial: &[f64],
+        on_iteration: impl FnMut(&Iteration),
+    ) -> Iteration {
+        self.descend(
+            &cost,
+            |params| central_difference(&cost, params),
+            initial,
+            on_iteration,
+        )
+    }
+
+    /// Runs [`GradientDescent::minimize`] with `gradient` in place of central differences.
+    pub(crate) fn descend(
+        &self,
+        cost: impl Fn(&[f64]) -> f64,
+        gradient: impl Fn(&[f64]) -> Vec<f64>,
+        initial: &[f64],
+        mut on_iteration: impl FnMut(&Iteration),
+    ) -> Iteration {
+        let mut params = initial.to_vec();
+        let mut step = 0;
+        loop {
+            let energy = cost(&params);
+            let gradient = gradient(&params);
+            let iteration = Iteration {
+                step,
+                energy,
+                gradient_norm: gradient
+                    .iter()
+                    .map(|slope| slope * slope)
+                    .sum::<f64>()
+                    .sqrt(),
+                params: params.clone(),
+            };
+            on_iteration(&iteration);
+            if step == self.max_iterations || iteration.gradient_norm < self.tolerance {
+                return iteration;
+            }
+            for (param, slope) in params.iter_mut().zip(&gradient) {
+                *param -= self.learning_rate * slope;
+            }
+            step += 1;
+        }
+    }
+}
+
+/// Returns the central-difference estimate of the gradient of `cost` at `params`.
+pub(crate) fn central_difference(cost: impl Fn(&[f64]) -> f64, params: &[f64]) -> Vec<f64> {
+    (0..params.len())
+        .map(|k| {
+            let (mut plus, mut minus) = (params.to_vec(), params.to_vec());
+            plus[k] += FINITE_DIFFERENCE_STEP;
+            minus[k] -= FINITE_DIFFERENCE_STEP;
+            (cost(&plus) - cost(&minus)) / (2.0 * FINITE_DIFFERENCE_STEP)
+        })
+        .collect()
+}
@@ -0,0 +1,256 @@
+//! This module checks the simulator's optimized gate kernels against a naive reference: every
+//! instruction is expanded into its full `2^n × 2^n` matrix and applied as a plain matrix-vector
+//! product, with none of the fusion, diagonal or permutation shortcuts [`Circuit::run`] takes.
+//!
+//! It is meant for tests of custom gates and kernels, e.g. in CI: [`verify_circuit`] runs a
+//! circuit both ways on several random states and reports every disagreement, including the
+//! first instruction whose kernel is wrong.
+
+use crate::circuit::{Circuit, Operation};
+use crate::error::QuantumError;
+use crate::qubit::Qubit;
+use num_complex::Complex;
+use std::fmt;
+
+/// A disagreement between the optimized run and the full-matrix reference on one input state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// The input state, [`Qubit::random_state`] of the circuit's width with this seed.
+    pub state: u64,
+    /// The first instruction whose own kernel disagrees with its full matrix on this state, or
+    /// `None` if every instruction agrees on its own and only the whole run, e.g. after fusing
+    /// gates, differs.
+    pub instruction: Option<usize>,
+    /// The basis state of the amplitude with the largest error in the final state.
+    pub index: usize,
+    /// The reference amplitude.
+    pub expected: Complex<f64>,
+    /// The amplitude the optimized run produced.
+    pub actual: Complex<f64>,
+}
+
+impl Mismatch {
+    /// Returns the distance between the actual and the expected amplitude.
+    pub fn error(&self) -> f64 {
+        (self.actual - self.expected).norm()
+    }
+}
+
+/// The result of [`verify_circuit`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerificationReport {
+    /// The number of random input states checked.
+    pub states: usize,
+    /// The largest amplitude error allowed.
+    pub tolerance: f64,
+    /// The largest amplitude error over all states, whether or not it exceeds the tolerance.
+    pub max_error: f64,
+    /// One entry per input state on which the runs disagree beyond the tolerance.
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl VerificationReport {
+    /// Returns `true` if the optimized run matched the reference on every state.
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} states within {:e}, largest error {:e}",
+            self.states - self.mismatches.len(),
+            self.states,
+            self.tolerance,
+            self.max_error
+        )?;
+        for mismatch in &self.mismatches {
+            write!(
+                f,
+                "  state {}: amplitude {} is {:.9}, expected {:.9} (error {:e})",
+                mismatch.state,
+                mismatch.index,
+                mismatch.actual,
+                mismatch.expected,
+                mismatch.error()
+            )?;
+            match mismatch.instruction {
+                Some(instruction) => writeln!(f, ", first wrong at instruction {}", instruction)?,
+                None => writeln!(f, ", every instruction correct on its own")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs a circuit with the optimized kernels and with explicit full matrices on `n` random states
+/// and compares the results amplitude by amplitude.
+///
+/// State `k` is [`Qubit::random_state`] with seed `k`, so a mismatch can be reproduced from the
+/// report. The reference costs `4^n` operations per instruction and state, so keep circuits to a
+/// dozen qubits or fewer.
+///
+/// # Arguments
+///
+/// * `circuit` - The circuit to check. Snapshots are skipped.
+/// * `n` - The number of random input states.
+/// * `tolerance` - The largest amplitude error allowed.
+///
+/// # Returns
+///
+/// * `Ok(VerificationReport)` - The comparison, with one [`Mismatch`] per failing state.
+/// * `Err(QuantumError::Unsupported)` - If the circuit measures, resets or conditions on a
+///   classical bit.
+/// * `Err(QuantumError::UnboundParameter)` - If the circuit has an unbound parameter.
+/// * `Err(QuantumError::DimensionMismatch)` - If a gate's matrix does not match the number of
+///   qubits it is applied to.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::circuit::Circuit;
+/// use quantum_simulator::verify::verify_circuit;
+///
+/// let mut circuit = Circuit::new(3);
+/// circuit.h(0).cnot(0, 2).rz(2, 0.4).t(1).swap(1, 2).toffoli(0, 1, 2);
+/// let report = verify_circuit(&circuit, 8, 1e-12).unwrap();
+/// assert!(report.passed(), "{}", report);
+/// ```
+pub fn verify_circuit(
+    circuit: &Circuit,
+    n: usize,
+    tolerance: f64,
+) -> Result<VerificationReport, QuantumError> {
+    let num_qubits = circuit.num_qubits();
+    let mut matrices = vec![];
+    for (position, instruction) in circuit.instructions().iter().enumerate() {
+        match &instruction.operation {
+            Operation::Snapshot(_) | Operation::Barrier(_) | Operation::Assert(_) => continue,
+            Operation::Parametric { parameter, .. } => {
+                return Err(QuantumError::UnboundParameter {
+                    name: parameter.name().to_string(),
+                })
+            }
+            operation if !operation.is_unitary() => {
+                return Err(QuantumError::Unsupported {
+                    feature: format!(
+                        "verifying non-unitary instruction {} ({})",
+                        position,
+                        operation.name()
+                    ),
+                })
+            }
+            operation => {
+                let gate = operation.gate();
+                let dimension = 1 << instruction.qubits.len();
+                if gate.matrix.len() != dimension {
+                    return Err(QuantumError::DimensionMismatch {
+                        expected: dimension,
+                        found: gate.matrix.len(),
+                    });
+                }
+                matrices.push((
+                    position,
+                    expand(&gate.matrix, &instruction.qubits, num_qubits),
+                ));
+            }
+        }
+    }
+
+    let mut max_error: f64 = 0.0;
+    let mut mismatches = vec![];
+    for seed in 0..n as u64 {
+        let input = Qubit::random_state(num_qubits, seed);
+        let mut actual = input.clone();
+        circuit.run(&mut actual);
+
+        let mut expected = input.state;
+        let mut first_wrong = None;
+        for (position, matrix) in &matrices {
+            let mut kernel = Qubit::from_state(expected.clone());
+            circuit.instructions()[*position].apply(&mut kernel);
+            expected = multiply(matrix, &expected);
+            if first_wrong.is_none() && largest_error(&kernel.state, &expected).1 > tolerance {
+                first_wrong = Some(*position);
+            }
+        }
+
+        let (index, error) = largest_error(&actual.state, &expected);
+        max_error = max_error.max(error);
+        if error > tolerance || first_wrong.is_some() {
+            mismatches.push(Mismatch {
+                state: seed,
+                instruction: first_wrong,
+                index,
+                expected: expected[index],
+                actual: actual.state[index],
+            });
+        }
+    }
+    Ok(VerificationReport {
+        states: n,
+        tolerance,
+        max_error,
+        mismatches,
+    })
+}
+
+/// Expands a gate on `targets`, where bit `j` of its basis index is `targets[j]`, into a matrix
+/// on the whole register, entry by entry.
+fn expand(
+    local: &[Vec<Complex<f64>>],
+    targets: &[usize],
+    num_qubits: usize,
+) -> Vec<Vec<Complex<f64>>> {
+    let mask: usize = targets.iter().map(|&target| 1 << target).sum();
+    let local_index = |index: usize| -> usize {
+        targets
+            .iter()
+            .enumerate()
+            .map(|(j, &target)| ((index >> target) & 1) << j)
+            .sum()
+    };
+    let size = 1 << num_qubits;
+    (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|column| {
+                    if row & !mask == column & !mask {
+                        local[local_index(row)][local_index(column)]
+                    } else {
+                        Complex::new(0.0, 0.0)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the product of a matrix and a state vector.
+fn multiply(matrix: &[Vec<Complex<f64>>], state: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    matrix
+        .iter()
+        .map(|row| row.iter().zip(state).map(|(m, a)| m * a).sum())
+        .collect()
+}
+
+/// Returns the index and size of the largest difference between two states.
+fn largest_error(actual: &[Complex<f64>], expected: &[Complex<f64>]) -> (usize, f64) {
+    actual
+        .iter()
+        .zip(expected)
+        .map(|(a, e)| (a - e).norm())
+        .enumerate()
+        .fold(
+            (0, 0.0),
+            |best, (index, error)| {
+                if error > best.1 {
+                    (index, error)
+                } else {
+                    best
+                }
+            },
+        )
+}
@@ -0,0 +1,132 @@
+//! This module is the JSON entry point for running the simulator in a browser.
+//!
+//! With the `wasm` feature, [`run_circuit_json`] is exported to JavaScript through wasm-bindgen;
+//! without it, it is an ordinary function, so the same requests can be tested natively. Build
+//! the WebAssembly module and its JavaScript glue for the demo page in `web/` with:
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir web/pkg \
+//!     target/wasm32-unknown-unknown/release/quantum_simulator.wasm
+//! ```
+//!
+//! On `wasm32-unknown-unknown`, the crate's unseeded randomness comes from the browser's
+//! `crypto.getRandomValues` through the `js` backend of `getrandom`.
+
+use crate::error::QuantumError;
+use crate::noise::{depolarizing, NoiseModel};
+use crate::qasm;
+use crate::qubit::Qubit;
+use crate::report::ResultExport;
+use crate::simulator::Simulator;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{json, Value};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Number of shots sampled when a request does not name one.
+const DEFAULT_SHOTS: usize = 1024;
+
+/// Widest register a request may declare; its state vector takes 16 MiB of the page's memory.
+const MAX_QUBITS: usize = 20;
+
+/// Runs a circuit described by a JSON request and returns its results as JSON.
+///
+/// The request is an object with the fields
+///
+/// * `qasm` - The circuit as an OpenQASM 2 or 3 program.
+/// * `shots` - Optional, the number of shots to sample, 1024 by default.
+/// * `seed` - Optional, the seed of the shots and of the final run, random by default.
+/// * `depolarizing` - Optional, the probability of a depolarizing error after every gate.
+///
+/// The response is the [`ResultExport`] JSON of the final state of one more run, with the
+/// `amplitudes`, `probabilities`, per-qubit `bloch_vectors` and the sampled `counts`, or an
+/// object with a single `error` message if the request is invalid or its circuit has more than
+/// 20 qubits.
+///
+/// # Examples
+///
+/// ```
+/// use quantum_simulator::wasm::run_circuit_json;
+///
+/// let request = r#"{
+///     "qasm": "OPENQASM 2.0; include \"qelib1.inc\"; qreg q[2]; h q[0]; cx q[0], q[1];",
+///     "shots": 100,
+///     "seed": 3
+/// }"#;
+/// let response: serde_json::Value = serde_json::from_str(&run_circuit_json(request)).unwrap();
+/// assert_eq!(response["num_qubits"], 2);
+/// assert_eq!(response["bloch_vectors"].as_array().unwrap().len(), 2);
+///
+/// let error: serde_json::Value = serde_json::from_str(&run_circuit_json("{}")).unwrap();
+/// assert!(error["error"].as_str().unwrap().contains("qasm"));
+/// ```
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn run_circuit_json(json: &str) -> String {
+    match run(json) {
+        Ok(response) => response,
+        Err(error) => json!({ "error": error.to_string() }).to_string(),
+    }
+}
+
+/// Parses a request, runs it and renders the response.
+fn run(json: &str) -> Result<String, QuantumError> {
+    let request: Value = serde_json::from_str(json).map_err(|error| QuantumError::Parse {
+        line: error.line(),
+        message: error.to_string(),
+    })?;
+    let invalid = |message: &str| QuantumError::Parse {
+        line: 0,
+        message: message.to_string(),
+    };
+    let source = request["qasm"]
+        .as_str()
+        .ok_or_else(|| invalid("expected a 'qasm' string"))?;
+    let circuit = qasm::parse(source)?;
+    if circuit.num_qubits() > MAX_QUBITS {
+        return Err(QuantumError::Unsupported {
+            feature: format!(
+                "{}-qubit circuits in the browser (at most {})",
+                circuit.num_qubits(),
+                MAX_QUBITS
+            ),
+        });
+    }
+    let shots = match &request["shots"] {
+        Value::Null => DEFAULT_SHOTS,
+        shots => shots
+            .as_u64()
+            .ok_or_else(|| invalid("expected 'shots' to be a non-negative integer"))?
+            as usize,
+    };
+    let seed = match &request["seed"] {
+        Value::Null => rand::random(),
+        seed => seed
+            .as_u64()
+            .ok_or_else(|| invalid("expected 'seed' to be a non-negative integer"))?,
+    };
+    let mut noise_model = NoiseModel::new();
+    match &request["depolarizing"] {
+        Value::Null => {}
+        probability => match probability.as_f64() {
+            Some(p) if (0.0..=1.0).contains(&p) => {
+                noise_model.add_error(depolarizing(p));
+            }
+            _ => return Err(invalid("expected 'depolarizing' to be a probability")),
+        },
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let initial_state = Qubit::zero_state(circuit.num_qubits()).state;
+    let counts = Simulator::sample_trajectories_with_rng(
+        &circuit,
+        &initial_state,
+        &noise_model,
+        shots,
+        &mut rng,
+    );
+    let state =
+        Simulator::run_trajectory_with_rng(&circuit, &initial_state, &noise_model, &mut rng);
+    Ok(ResultExport::new(&state).counts(&counts).to_json())
+}
@@ -1,9 +1,48 @@
 #[cfg(test)]
 mod tests {
     use num_complex::Complex;
-    use quantum_simulator::circuit::Circuit;
-    use quantum_simulator::gates::{pauli_y, pauli_z, phase};
+    use quantum_simulator::algorithms::{inverse_qft, phase_estimation, qft, Grover, Oracle};
+    use quantum_simulator::analysis::{
+        bloch_vector, concurrence, entanglement_entropy, expectation_value, light_cone,
+        reduced_density_matrix, schmidt_coefficients, verify_uncomputation,
+    };
+    use quantum_simulator::circuit::{Circuit, Instruction, Operation};
+    use quantum_simulator::convergence::Convergence;
+    use quantum_simulator::density::DensityMatrix;
+    use quantum_simulator::error::QuantumError;
+    use quantum_simulator::events::{Event, EventLog};
+    use quantum_simulator::execution::{ExecutionMode, Warning};
+    use quantum_simulator::experiments;
+    use quantum_simulator::gates::{
+        cnot, cz, fuse, hadamard, mcp, mcx, pauli_x, pauli_y, pauli_z, phase, rotation_about_axis,
+        ry, rz, toffoli, u3, Gate, GateKind,
+    };
+    use quantum_simulator::gst::{gst_lite, GateSetEntry};
+    use quantum_simulator::hooks::{Hooks, SimulationEvent};
+    use quantum_simulator::interval::{probability_bounds, Interval};
+    use quantum_simulator::landscape::{sweep_1d, sweep_2d, Axis};
+    use quantum_simulator::library::GateLibrary;
+    use quantum_simulator::noise::{
+        bit_flip, depolarizing, phase_damping, CoherentError, KrausChannel, NoiseModel,
+    };
+    use quantum_simulator::parameter::Parameter;
+    use quantum_simulator::qasm::{self, QasmVersion};
+    use quantum_simulator::qubit::{Qubit, StateVector};
+    use quantum_simulator::schedule::Schedule;
     use quantum_simulator::simulator::Simulator;
+    use quantum_simulator::snapshot::Snapshot;
+    use quantum_simulator::soak::Soak;
+    use quantum_simulator::subspace::Sector;
+    use quantum_simulator::variational::{
+        Ansatz, GradientDescent, Hamiltonian, Iteration, TrotterOrder,
+    };
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+    use std::ops::ControlFlow;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
 
     const TOLERANCE: f64 = 1e-10;
 
@@ -11,29 +50,29 @@ mod tests {
         (a - b).norm() < tol
     }
 
-    // #[test]
-    // fn test_hadamard_pauli_x() {
-    //     let mut circuit = Circuit::new();
-    //     circuit.add_gate(hadamard(1));
-    //     circuit.add_gate(pauli_x());
-    //
-    //     let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]; // |0⟩ state
-    //     let final_qubit = Simulator::run(&circuit, &initial_state);
-    //
-    //     assert!(complex_approx_eq(
-    //         final_qubit.state[0],
-    //         Complex::new(0.7071067811865475, 0.0),
-    //         TOLERANCE
-    //     ));
-    //     assert!(complex_approx_eq(
-    //         final_qubit.state[1],
-    //         Complex::new(0.7071067811865475, 0.0),
-    //         TOLERANCE
-    //     ));
-    // }
+    #[test]
+    fn test_hadamard_pauli_x() {
+        let mut circuit = Circuit::new(1);
+        circuit.add_gate(hadamard(1));
+        circuit.add_gate(pauli_x());
+
+        let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]; // |0⟩ state
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+
+        assert!(complex_approx_eq(
+            final_qubit.state[0],
+            Complex::new(0.7071067811865475, 0.0),
+            TOLERANCE
+        ));
+        assert!(complex_approx_eq(
+            final_qubit.state[1],
+            Complex::new(0.7071067811865475, 0.0),
+            TOLERANCE
+        ));
+    }
     #[test]
     fn test_pauli_y() {
-        let mut circuit = Circuit::new();
+        let mut circuit = Circuit::new(1);
         circuit.add_gate(pauli_y());
 
         let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]; // |0⟩ state
@@ -53,7 +92,7 @@ mod tests {
 
     #[test]
     fn test_pauli_z() {
-        let mut circuit = Circuit::new();
+        let mut circuit = Circuit::new(1);
         circuit.add_gate(pauli_z());
 
         let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]; // |0⟩ state
@@ -73,7 +112,7 @@ mod tests {
 
     #[test]
     fn test_phase() {
-        let mut circuit = Circuit::new();
+        let mut circuit = Circuit::new(1);
         circuit.add_gate(phase(std::f64::consts::PI / 2.0));
 
         let initial_state = vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)]; // |1⟩ state
@@ -91,32 +130,32 @@ mod tests {
         ));
     }
 
-    // #[test]
-    // fn test_hadamard_multi_qubit() {
-    //     let mut circuit = Circuit::new();
-    //     circuit.add_gate(hadamard(3));
-    //
-    //     let initial_state = vec![
-    //         Complex::new(1.0, 0.0),
-    //         Complex::new(0.0, 0.0),
-    //         Complex::new(0.0, 0.0),
-    //         Complex::new(0.0, 0.0),
-    //         Complex::new(0.0, 0.0),
-    //         Complex::new(0.0, 0.0),
-    //         Complex::new(0.0, 0.0),
-    //         Complex::new(0.0, 0.0),
-    //     ]; // |000⟩ state
-    //     let final_qubit = Simulator::run(&circuit, &initial_state);
-    //
-    //     let expected_amplitude = 1.0 / (8.0_f64).sqrt();
-    //     for state in final_qubit.state.iter() {
-    //         assert!(complex_approx_eq(
-    //             *state,
-    //             Complex::new(expected_amplitude, 0.0),
-    //             TOLERANCE
-    //         ));
-    //     }
-    // }
+    #[test]
+    fn test_hadamard_multi_qubit() {
+        let mut circuit = Circuit::new(3);
+        circuit.add_gate(hadamard(3));
+
+        let initial_state = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ]; // |000⟩ state
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+
+        let expected_amplitude = 1.0 / (8.0_f64).sqrt();
+        for state in final_qubit.state.iter() {
+            assert!(complex_approx_eq(
+                *state,
+                Complex::new(expected_amplitude, 0.0),
+                TOLERANCE
+            ));
+        }
+    }
 
     // #[test]
     // fn test_cnot() {
@@ -147,4 +186,5599 @@ mod tests {
     //         TOLERANCE
     //     ));
     // }
+
+    #[test]
+    fn test_cnot_flips_the_target_bit_of_every_basis_state_with_the_control_set() {
+        for (control, target, num_qubits) in [(0, 1, 2), (1, 0, 2), (0, 2, 3), (2, 1, 3)] {
+            let gate = cnot(control, target, num_qubits);
+            let size = 1 << num_qubits;
+            for column in 0..size {
+                let row = if (column >> control) & 1 == 1 {
+                    column ^ (1 << target)
+                } else {
+                    column
+                };
+                for (i, entry) in gate.matrix.iter().map(|r| r[column]).enumerate() {
+                    let expected = if i == row { 1.0 } else { 0.0 };
+                    assert_eq!(entry, Complex::new(expected, 0.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_gst_lite_amplitude_damping() {
+        let gamma: f64 = 0.2;
+        let damping = KrausChannel::new(vec![
+            vec![
+                vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+                vec![
+                    Complex::new(0.0, 0.0),
+                    Complex::new((1.0 - gamma).sqrt(), 0.0),
+                ],
+            ],
+            vec![
+                vec![Complex::new(0.0, 0.0), Complex::new(gamma.sqrt(), 0.0)],
+                vec![Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)],
+            ],
+        ]);
+        let identity = phase(0.0);
+
+        let estimates = gst_lite(&[GateSetEntry::new("idle", identity, damping)]);
+        let expected = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, (1.0 - gamma).sqrt(), 0.0, 0.0],
+            [0.0, 0.0, (1.0 - gamma).sqrt(), 0.0],
+            [gamma, 0.0, 0.0, 1.0 - gamma],
+        ];
+        for (row, expected_row) in estimates[0].estimated.iter().zip(expected.iter()) {
+            for (value, expected_value) in row.iter().zip(expected_row.iter()) {
+                assert!((value - expected_value).abs() < TOLERANCE);
+            }
+        }
+        assert!(estimates[0].process_fidelity < 1.0);
+    }
+
+    #[test]
+    fn test_measure_qubit_collapses_ghz_state() {
+        let h = 1.0 / (2.0_f64).sqrt();
+        let mut state = vec![Complex::new(0.0, 0.0); 8];
+        state[0] = Complex::new(h, 0.0);
+        state[7] = Complex::new(h, 0.0);
+        let mut qubit = Qubit::from_state(state);
+
+        let bit = qubit.measure_qubit(1);
+        let survivor = if bit == 1 { 7 } else { 0 };
+        for (index, amplitude) in qubit.state.iter().enumerate() {
+            let expected = if index == survivor { 1.0 } else { 0.0 };
+            assert!(complex_approx_eq(
+                *amplitude,
+                Complex::new(expected, 0.0),
+                TOLERANCE
+            ));
+        }
+        assert_eq!(qubit.measure_all(), vec![bit; 3]);
+    }
+
+    #[test]
+    fn test_channel_representation_round_trips() {
+        let gamma: f64 = 0.3;
+        let damping = KrausChannel::new(vec![
+            vec![
+                vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+                vec![
+                    Complex::new(0.0, 0.0),
+                    Complex::new((1.0 - gamma).sqrt(), 0.0),
+                ],
+            ],
+            vec![
+                vec![Complex::new(0.0, 0.0), Complex::new(gamma.sqrt(), 0.0)],
+                vec![Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)],
+            ],
+        ]);
+        let ptm = damping.to_ptm();
+
+        let via_choi = KrausChannel::from_choi(&damping.to_choi()).unwrap();
+        let via_chi = KrausChannel::from_chi(&damping.to_chi()).unwrap();
+        let via_ptm = KrausChannel::from_ptm(&ptm).unwrap();
+        for channel in [via_choi, via_chi, via_ptm] {
+            for (row, expected_row) in channel.to_ptm().iter().zip(&ptm) {
+                for (value, expected) in row.iter().zip(expected_row) {
+                    assert!((value - expected).abs() < 1e-8);
+                }
+            }
+        }
+
+        // An off-diagonal entry without its conjugate partner is rejected, not diagonalized
+        let mut choi = damping.to_choi();
+        choi[0][3] += Complex::new(0.0, 0.1);
+        assert!(matches!(
+            KrausChannel::from_choi(&choi),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        let mut chi = damping.to_chi();
+        chi[1][2] = Complex::new(0.2, 0.0);
+        chi[2][1] = Complex::new(-0.2, 0.0);
+        assert!(matches!(
+            KrausChannel::from_chi(&chi),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_transpose_map_is_not_completely_positive() {
+        // The transpose map flips the sign of ⟨Y⟩; it is positive but not completely positive.
+        let mut ptm = vec![vec![0.0; 4]; 4];
+        ptm[0][0] = 1.0;
+        ptm[1][1] = 1.0;
+        ptm[2][2] = -1.0;
+        ptm[3][3] = 1.0;
+
+        assert!(matches!(
+            KrausChannel::from_ptm(&ptm),
+            Err(QuantumError::NotCompletelyPositive { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sample_bell_state_counts() {
+        let h = 1.0 / (2.0_f64).sqrt();
+        let bell = vec![
+            Complex::new(h, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(h, 0.0),
+        ];
+
+        let counts = Simulator::sample(&Circuit::new(2), &bell, 2000);
+        assert_eq!(counts.shots(), 2000);
+        assert_eq!(counts.get("00") + counts.get("11"), 2000);
+        assert!(counts.get("00") > 800 && counts.get("11") > 800);
+    }
+
+    #[test]
+    fn test_channel_algebra_builds_valid_error_model() {
+        let overrotation = KrausChannel::from_gate(&phase(0.05));
+        let flip = KrausChannel::from_gate(&pauli_y());
+        let error_model =
+            (0.95 * KrausChannel::identity(2) + 0.05 * flip.clone()) * overrotation.clone();
+        assert!(error_model.validate().is_ok());
+
+        let explicit = overrotation.then(&KrausChannel::identity(2).mix(&flip, 0.95));
+        for (row, expected_row) in error_model.to_ptm().iter().zip(explicit.to_ptm()) {
+            for (value, expected) in row.iter().zip(expected_row) {
+                assert!((value - expected).abs() < TOLERANCE);
+            }
+        }
+
+        let two_qubit = error_model.clone() ^ error_model;
+        assert_eq!(two_qubit.dimension(), 4);
+        assert!(two_qubit.validate().is_ok());
+    }
+
+    #[test]
+    fn test_seeded_collapse_is_reproducible() {
+        let amplitude = Complex::new(0.5, 0.0);
+        let state = vec![amplitude; 4];
+
+        let mut outcomes = Vec::new();
+        for _ in 0..2 {
+            Simulator::set_seed(1234);
+            let mut qubit = Qubit::from_state(state.clone());
+            let bits: Vec<usize> = (0..2).map(|k| qubit.measure_qubit(k)).collect();
+            outcomes.push((bits, Simulator::sample(&Circuit::new(2), &state, 64)));
+        }
+        Simulator::clear_seed();
+
+        assert_eq!(outcomes[0], outcomes[1]);
+    }
+
+    #[test]
+    fn test_seeded_draws_inside_an_observer_are_reproducible() {
+        use quantum_simulator::hooks::SimulationEvent;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1).measure(0, 0).measure(1, 1);
+        let initial_state = Qubit::zero_state(2).state;
+
+        // The observer measures its own qubit while the run holds the seeded generator
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            Simulator::set_seed(1255);
+            let mut observed = Vec::new();
+            let result = Simulator::run_with_observer(&circuit, &initial_state, |event| {
+                if let SimulationEvent::GateApplied { .. } = event {
+                    observed.push(Qubit::plus_state(3).measure());
+                }
+            });
+            runs.push((result.clbits, observed));
+        }
+        Simulator::clear_seed();
+
+        assert_eq!(runs[0], runs[1]);
+        assert_eq!(runs[0].1.len(), 2);
+    }
+
+    #[test]
+    fn test_coherent_overrotation() {
+        let epsilon = 0.05;
+        let mut model = NoiseModel::new();
+        model.set_coherent_error(CoherentError::new(epsilon, 0.0));
+
+        let mut circuit = Circuit::new(1);
+        circuit.add_gate(pauli_x());
+        let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+        let final_qubit = Simulator::run_with_noise(&circuit, &initial_state, &model);
+        let expected = (std::f64::consts::PI * epsilon / 2.0).sin().powi(2);
+        assert!((final_qubit.state[0].norm_sqr() - expected).abs() < TOLERANCE);
+
+        // Doubling a CNOT (ε = 1) gives the identity.
+        let doubled = CoherentError::new(1.0, 0.0).perturb(&cnot(0, 1, 2));
+        for (i, row) in doubled.matrix.iter().enumerate() {
+            for (j, elem) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!(complex_approx_eq(*elem, Complex::new(expected, 0.0), 1e-9));
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_qubit_gate_on_target() {
+        let mut circuit = Circuit::new(3);
+        circuit.add_single_qubit_gate(pauli_x(), 2);
+        circuit.add_single_qubit_gate(pauli_z(), 2);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0); // |000⟩ state
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+
+        assert_eq!(final_qubit.state.len(), 8);
+        for (index, amplitude) in final_qubit.state.iter().enumerate() {
+            let expected = if index == 4 { -1.0 } else { 0.0 }; // -|100⟩
+            assert!(complex_approx_eq(
+                *amplitude,
+                Complex::new(expected, 0.0),
+                TOLERANCE
+            ));
+        }
+    }
+
+    #[test]
+    fn test_circuit_builder_bell_state() {
+        let mut circuit = Circuit::new(3);
+        circuit.h(0).cnot(0, 2);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0); // |000⟩ state
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+
+        let h = 1.0 / (2.0_f64).sqrt();
+        for (index, amplitude) in final_qubit.state.iter().enumerate() {
+            let expected = if index == 0 || index == 5 { h } else { 0.0 };
+            assert!(complex_approx_eq(
+                *amplitude,
+                Complex::new(expected, 0.0),
+                TOLERANCE
+            ));
+        }
+
+        let lowered = circuit.to_gates();
+        assert_eq!(lowered[1].matrix, cnot(0, 2, 3).matrix);
+        assert_eq!(
+            circuit.add_instruction(Instruction::new(Operation::Swap, vec![1, 1])),
+            Err(QuantumError::DuplicateQubit { qubit: 1 })
+        );
+    }
+
+    #[test]
+    fn test_extract_subsystem_after_uncomputation() {
+        // Compute x0 AND x1 into an ancilla, copy it out, then uncompute.
+        let mut circuit = Circuit::new(4);
+        circuit
+            .h(0)
+            .h(1)
+            .toffoli(0, 1, 2)
+            .cnot(2, 3)
+            .toffoli(0, 1, 2);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 16];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let final_state: StateVector = Simulator::run(&circuit, &initial_state);
+
+        let ancilla = final_state.extract_subsystem(&[2]).unwrap();
+        assert!((ancilla.state[0].norm() - 1.0).abs() < TOLERANCE);
+
+        // The output qubit is still entangled with the inputs.
+        assert!(matches!(
+            final_state.extract_subsystem(&[3]),
+            Err(QuantumError::EntangledSubsystem { .. })
+        ));
+    }
+
+    #[test]
+    fn test_try_run_rejects_invalid_inputs() {
+        let zero = Complex::new(0.0, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        assert_eq!(
+            Gate::try_new(vec![vec![one, zero, zero]; 3], None).unwrap_err(),
+            QuantumError::InvalidDimension { dimension: 3 }
+        );
+        assert!(matches!(
+            Gate::try_new(vec![vec![one, one], vec![zero, one]], Some(1e-10)),
+            Err(QuantumError::NotUnitary { .. })
+        ));
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+        let mut wrong_width = Qubit::new();
+        assert_eq!(
+            circuit.try_run(&mut wrong_width),
+            Err(QuantumError::DimensionMismatch {
+                expected: 4,
+                found: 2
+            })
+        );
+        assert_eq!(wrong_width.state, vec![one, zero]);
+
+        let mut register = Qubit::from_state(vec![one, zero, zero, zero]);
+        assert!(circuit.try_run(&mut register).is_ok());
+        assert!(complex_approx_eq(
+            register.state[3],
+            Complex::new(1.0 / 2.0_f64.sqrt(), 0.0),
+            TOLERANCE
+        ));
+    }
+
+    #[test]
+    fn test_verify_uncomputation_reports_checkpoint() {
+        let mut circuit = Circuit::new(4);
+        circuit.toffoli(0, 1, 2).cnot(2, 3).toffoli(0, 1, 2);
+        assert!(verify_uncomputation(&circuit, &[2], &[], 8, TOLERANCE).is_ok());
+
+        // The ancilla is only clean once the final Toffoli has run.
+        let failure = verify_uncomputation(&circuit, &[2], &[2], 8, TOLERANCE).unwrap_err();
+        assert_eq!(failure.checkpoint, 2);
+        assert_eq!(failure.ancilla, 2);
+        assert!(failure.population > TOLERANCE);
+    }
+
+    #[test]
+    fn test_qasm_round_trip_preserves_state() {
+        let source = r#"
+            OPENQASM 2.0;
+            include "qelib1.inc";
+            gate majority a, b, c { cx c, b; cx c, a; ccx a, b, c; }
+            qreg q[3];
+            creg c[3];
+            h q;
+            sdg q[1];
+            u2(0, pi) q[2]; // another Hadamard
+            majority q[0], q[1], q[2];
+            rx(-pi/4 + 0.1) q[0];
+            iswap q[1], q[2];
+            measure q -> c;
+        "#;
+        let circuit = qasm::parse(source).unwrap();
+        assert_eq!(circuit.num_qubits(), 3);
+        assert_eq!(circuit.num_clbits(), 3);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+
+        // The measurements collapse the state, so both runs draw from identically seeded rngs
+        let run = |circuit: &Circuit| {
+            let mut qubit = Qubit::from_state(initial_state.clone());
+            let clbits = circuit.run_with_rng(&mut qubit, &mut StdRng::seed_from_u64(5));
+            (qubit, clbits)
+        };
+        let (expected, expected_clbits) = run(&circuit);
+
+        for version in [QasmVersion::V2, QasmVersion::V3] {
+            let exported = qasm::to_qasm(&circuit, version).unwrap();
+            let reparsed = qasm::parse(&exported).unwrap();
+            assert_eq!(reparsed.num_clbits(), 3);
+
+            let (actual, clbits) = run(&reparsed);
+            assert_eq!(clbits, expected_clbits);
+            let overlap: Complex<f64> = expected
+                .state
+                .iter()
+                .zip(&actual.state)
+                .map(|(a, b)| a.conj() * b)
+                .sum();
+            assert!((overlap.norm() - 1.0).abs() < 1e-9);
+        }
+
+        let error = qasm::parse("OPENQASM 2.0;\nqreg q[1];\nfoo q[0];").unwrap_err();
+        assert!(matches!(error, QuantumError::Parse { line: 3, .. }));
+    }
+
+    #[cfg(feature = "exact")]
+    #[test]
+    fn test_exact_amplitudes_match_numerical_simulation() {
+        use quantum_simulator::exact::{run_exact, ExactAmplitude};
+
+        let mut circuit = Circuit::new(3);
+        circuit
+            .h(0)
+            .t(0)
+            .cnot(0, 1)
+            .h(2)
+            .s(2)
+            .cz(1, 2)
+            .iswap(0, 2)
+            .h(1)
+            .t(1);
+
+        let exact = run_exact(&circuit).unwrap();
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let numerical = Simulator::run(&circuit, &initial_state);
+        for (exact, numerical) in exact.iter().zip(&numerical.state) {
+            assert!(complex_approx_eq(exact.to_complex(), *numerical, TOLERANCE));
+        }
+
+        let total = exact.iter().fold(ExactAmplitude::zero(), |sum, amplitude| {
+            sum + amplitude.norm_sqr()
+        });
+        assert_eq!(total, ExactAmplitude::one());
+
+        circuit.rz(0, 0.3);
+        assert!(matches!(
+            run_exact(&circuit),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_probability_bounds_enclose_simulation() {
+        let mut circuit = Circuit::new(3);
+        circuit
+            .h(0)
+            .ry(1, 0.7)
+            .cnot(0, 2)
+            .rz(2, 1.1)
+            .toffoli(0, 1, 2)
+            .t(1)
+            .h(2);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+
+        let bounds = probability_bounds(&circuit, &initial_state);
+        for (bound, amplitude) in bounds.iter().zip(&final_qubit.state) {
+            assert!(bound.contains(amplitude.norm_sqr()));
+            assert!(bound.width() < 1e-13);
+        }
+        let total = bounds
+            .iter()
+            .fold(Interval::point(0.0), |sum, &bound| sum + bound);
+        assert!(total.contains(1.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trips_circuit_and_results() {
+        use quantum_simulator::counts::Counts;
+
+        let mut circuit = Circuit::new(2);
+        circuit
+            .h(0)
+            .rx(1, 0.25)
+            .cnot(0, 1)
+            .add_single_qubit_gate(pauli_y(), 1);
+        let json = serde_json::to_string(&circuit).unwrap();
+        let restored: Circuit = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, circuit);
+
+        let initial_state = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+        let final_qubit = Simulator::run(&restored, &initial_state);
+        let json = serde_json::to_string(&final_qubit).unwrap();
+        assert_eq!(serde_json::from_str::<Qubit>(&json).unwrap(), final_qubit);
+
+        let counts = final_qubit.sample(100);
+        let json = serde_json::to_string(&counts).unwrap();
+        assert_eq!(serde_json::from_str::<Counts>(&json).unwrap(), counts);
+    }
+
+    #[test]
+    fn test_trajectories_reproduce_depolarizing_channel() {
+        let mut circuit = Circuit::new(2);
+        circuit.x(0).cnot(0, 1);
+        let mut model = NoiseModel::new();
+        model.add_qubit_error(
+            Operation::Cnot,
+            &[0, 1],
+            KrausChannel::identity(2) ^ depolarizing(0.3),
+        );
+
+        let initial_state = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+        let mut rng = StdRng::seed_from_u64(11);
+        let counts = Simulator::sample_trajectories_with_rng(
+            &circuit,
+            &initial_state,
+            &model,
+            4000,
+            &mut rng,
+        );
+
+        // Depolarizing qubit 0 of |11⟩ flips it to |0⟩ with probability p/2.
+        let flipped = counts.get("10") as f64 / 4000.0;
+        assert!((flipped - 0.15).abs() < 0.03);
+        assert_eq!(counts.get("10") + counts.get("11"), 4000);
+
+        let phase_model = {
+            let mut model = NoiseModel::new();
+            model.add_error(phase_damping(0.5)).add_error(bit_flip(0.0));
+            model
+        };
+        let clean = Simulator::run_trajectory(&circuit, &initial_state, &phase_model);
+        assert!((clean.state[3].norm() - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_experiment_bundles_score_noisy_counts() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for experiment in [
+            experiments::bell_pair(),
+            experiments::ghz(5),
+            experiments::w(4),
+        ] {
+            let (counts, analysis) = experiment.run_with_rng(2000, &mut rng);
+            assert_eq!(counts.shots(), 2000);
+            assert!((analysis.figure_of_merit - 1.0).abs() < TOLERANCE);
+            assert!(analysis.total_variation_distance < 0.05);
+        }
+
+        // Bit flips on |+⟩ before the CNOT are invisible, so the parity correlation survives.
+        let bell = experiments::bell_pair();
+        let mut model = NoiseModel::new();
+        model.add_gate_error(Operation::H, bit_flip(0.5));
+        let initial_state = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+        let counts = Simulator::sample_trajectories_with_rng(
+            &bell.circuit,
+            &initial_state,
+            &model,
+            2000,
+            &mut rng,
+        );
+        let analysis = bell.analyze(&counts);
+        assert!((analysis.figure_of_merit - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_density_matrix_matches_noisy_channel() {
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+        let bell = Simulator::run_density(&circuit, &DensityMatrix::new(2));
+        assert!((bell.purity() - 1.0).abs() < TOLERANCE);
+        assert!((bell.trace() - 1.0).abs() < TOLERANCE);
+        assert!((bell.partial_trace(&[1]).purity() - 0.5).abs() < TOLERANCE);
+
+        let pure = Simulator::run(
+            &circuit,
+            &[
+                Complex::new(1.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, 0.0),
+            ],
+        );
+        assert!((bell.fidelity(&DensityMatrix::from_state(&pure)) - 1.0).abs() < 1e-9);
+
+        // Depolarizing one half of a Bell pair mixes in the identity with weight p.
+        let mut model = NoiseModel::new();
+        model.add_qubit_error(
+            Operation::Cnot,
+            &[0, 1],
+            KrausChannel::identity(2) ^ depolarizing(0.4),
+        );
+        let noisy = Simulator::run_density_with_noise(&circuit, &DensityMatrix::new(2), &model);
+        assert!((noisy.trace() - 1.0).abs() < TOLERANCE);
+        let expected_fidelity = 0.6 + 0.4 / 4.0;
+        assert!((noisy.fidelity(&bell) - expected_fidelity).abs() < 1e-9);
+        for (p, expected) in noisy.probabilities().iter().zip([0.4, 0.1, 0.1, 0.4]) {
+            assert!((p - expected).abs() < TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_recorded_run_logs_gates_and_noise_in_order() {
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+        let mut model = NoiseModel::new();
+        model.add_gate_error(Operation::Cnot, bit_flip(0.5));
+        let initial_state = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+
+        let mut rng = StdRng::seed_from_u64(17);
+        let result = Simulator::run_recorded_with_rng(&circuit, &initial_state, &model, &mut rng);
+        let kinds: Vec<(usize, bool)> = result
+            .events
+            .iter()
+            .map(|event| match event {
+                Event::Gate { instruction, .. } => (*instruction, true),
+                Event::Channel { instruction, .. } => (*instruction, false),
+                other => panic!("unexpected event {}", other),
+            })
+            .collect();
+        assert_eq!(kinds, vec![(0, true), (1, true), (1, false), (1, false)]);
+
+        let mut rng = StdRng::seed_from_u64(17);
+        let trajectory =
+            Simulator::run_trajectory_with_rng(&circuit, &initial_state, &model, &mut rng);
+        assert_eq!(result.state, trajectory);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&result.events).unwrap();
+            assert_eq!(
+                serde_json::from_str::<quantum_simulator::events::EventLog>(&json).unwrap(),
+                result.events
+            );
+        }
+    }
+
+    #[test]
+    fn test_replay_reproduces_a_rare_shot() {
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1).h(1);
+        let mut model = NoiseModel::new();
+        model.add_gate_error(Operation::Cnot, depolarizing(0.05));
+        let initial_state = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+
+        let mut rng = StdRng::seed_from_u64(23);
+        let rare = (0..1000)
+            .map(|_| Simulator::run_recorded_with_rng(&circuit, &initial_state, &model, &mut rng))
+            .find(|result| {
+                result
+                    .events
+                    .iter()
+                    .any(|event| matches!(event, Event::Channel { operator, .. } if *operator != 0))
+            })
+            .expect("a 5% error should occur within 1000 shots");
+
+        let replayed = Simulator::replay(&circuit, &initial_state, &model, &rare.events).unwrap();
+        assert_eq!(replayed.state, rare.state);
+
+        let mut truncated = EventLog::new();
+        for event in rare.events.iter().take(rare.events.len() - 1) {
+            truncated.push(event.clone());
+        }
+        assert_eq!(
+            Simulator::replay(&circuit, &initial_state, &model, &truncated),
+            Err(QuantumError::ReplayMismatch {
+                event: truncated.len()
+            })
+        );
+        assert_eq!(
+            Simulator::replay(&circuit, &initial_state, &NoiseModel::new(), &rare.events),
+            Err(QuantumError::ReplayMismatch { event: 2 })
+        );
+    }
+
+    #[test]
+    fn test_hooks_observe_gates_measurements_and_stop_early() {
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+        let initial_state = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+
+        let mut gates = 0;
+        let mut ones = 0;
+        let mut hooks = Hooks::new();
+        hooks
+            .on_gate_applied(|index, instruction, qubit| {
+                assert_eq!(instruction, &circuit.instructions()[index]);
+                assert!(
+                    (qubit.state.iter().map(|a| a.norm_sqr()).sum::<f64>() - 1.0).abs() < TOLERANCE
+                );
+                gates += 1;
+            })
+            .on_measurement(|qubits, outcome, _| {
+                assert_eq!(qubits, &[0, 1]);
+                assert!(outcome == 0 || outcome == 3);
+            })
+            .on_shot_complete(|_, outcome, _| {
+                if outcome == 3 {
+                    ones += 1;
+                }
+                if ones == 5 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+
+        let mut rng = StdRng::seed_from_u64(29);
+        let counts = Simulator::sample_with_hooks_with_rng(
+            &circuit,
+            &initial_state,
+            &NoiseModel::new(),
+            1000,
+            &mut hooks,
+            &mut rng,
+        );
+        drop(hooks);
+        assert_eq!(counts.get("11"), 5);
+        assert!(counts.shots() < 1000);
+        assert_eq!(gates, 2 * counts.shots());
+    }
+
+    #[test]
+    fn test_teleportation_with_mid_circuit_measurement() {
+        let theta = 1.1;
+        let mut circuit = Circuit::new(3);
+        circuit.ry(0, theta).h(1).cnot(1, 2);
+        circuit.cnot(0, 1).h(0).measure(0, 0).measure(1, 1);
+        circuit
+            .conditional(1, Operation::X, vec![2])
+            .conditional(0, Operation::Z, vec![2]);
+        assert_eq!(circuit.num_clbits(), 2);
+        assert!(!circuit.is_unitary());
+
+        let mut expected = Qubit::new();
+        Instruction::new(Operation::Ry(theta), vec![0]).apply(&mut expected);
+        let expected = DensityMatrix::from_state(&expected);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        for seed in 0..8 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let result = Simulator::run_recorded_with_rng(
+                &circuit,
+                &initial_state,
+                &NoiseModel::new(),
+                &mut rng,
+            );
+            let teleported = DensityMatrix::from_state(&result.state).partial_trace(&[2]);
+            assert!((teleported.fidelity(&expected) - 1.0).abs() < 1e-6);
+
+            let replayed =
+                Simulator::replay(&circuit, &initial_state, &NoiseModel::new(), &result.events)
+                    .unwrap();
+            assert_eq!(replayed.clbits, result.clbits);
+            assert_eq!(replayed.state, result.state);
+        }
+
+        // The ensemble over every measurement record teleports the state exactly
+        let rho = Simulator::run_density(&circuit, &DensityMatrix::new(3));
+        assert!((rho.trace() - 1.0).abs() < 1e-12);
+        assert!((rho.partial_trace(&[2]).fidelity(&expected) - 1.0).abs() < 1e-6);
+
+        for version in [QasmVersion::V2, QasmVersion::V3] {
+            let exported = qasm::to_qasm(&circuit, version).unwrap();
+            let reparsed = qasm::parse(&exported).unwrap();
+            let rho = Simulator::run_density(&reparsed, &DensityMatrix::new(3));
+            assert!((rho.partial_trace(&[2]).fidelity(&expected) - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_reset_returns_qubit_to_zero() {
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1).reset(0);
+
+        let mut qubit = Qubit::from_state(vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ]);
+        circuit.run_with_rng(&mut qubit, &mut StdRng::seed_from_u64(3));
+        assert!(qubit.state[1].norm() < TOLERANCE && qubit.state[3].norm() < TOLERANCE);
+
+        // Resetting one half of a Bell pair leaves the other half maximally mixed
+        let rho = Simulator::run_density(&circuit, &DensityMatrix::new(2));
+        assert!((rho.partial_trace(&[0]).matrix[0][0].re - 1.0).abs() < TOLERANCE);
+        assert!((rho.partial_trace(&[1]).purity() - 0.5).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_controlled_gates_match_builtin_constructors() {
+        assert_eq!(pauli_x().controlled(2, 0, 3).unwrap(), cnot(2, 0, 3));
+        assert_eq!(pauli_z().controlled(0, 1, 2).unwrap(), cz(0, 1, 2));
+        assert_eq!(
+            pauli_x().multi_controlled(&[2, 0], 1, 3).unwrap(),
+            toffoli(0, 2, 1, 3)
+        );
+
+        // A controlled rotation only rotates the target in the branch where the controls are set
+        let gate = ry(std::f64::consts::PI)
+            .multi_controlled(&[0, 1], 2, 3)
+            .unwrap();
+        let mut qubit = Qubit::from_state(
+            (0..8)
+                .map(|i| {
+                    Complex::new(if i == 3 || i == 1 { 1.0 } else { 0.0 }, 0.0) / 2.0_f64.sqrt()
+                })
+                .collect(),
+        );
+        gate.apply(&mut qubit);
+        assert!(complex_approx_eq(
+            qubit.state[1],
+            Complex::new(1.0 / 2.0_f64.sqrt(), 0.0),
+            TOLERANCE
+        ));
+        assert!(complex_approx_eq(
+            qubit.state[7],
+            Complex::new(1.0 / 2.0_f64.sqrt(), 0.0),
+            TOLERANCE
+        ));
+        assert!(qubit.state[3].norm() < TOLERANCE);
+
+        // A control on the target, a repeated control or a qubit outside the register is an error
+        assert_eq!(
+            pauli_x().controlled(1, 1, 2),
+            Err(QuantumError::DuplicateQubit { qubit: 1 })
+        );
+        assert_eq!(
+            pauli_x().multi_controlled(&[0, 0], 1, 2),
+            Err(QuantumError::DuplicateQubit { qubit: 0 })
+        );
+        assert!(matches!(
+            pauli_x().controlled(2, 0, 2),
+            Err(QuantumError::QubitOutOfRange { qubit: 2, .. })
+        ));
+        assert!(matches!(
+            cnot(0, 1, 2).controlled(2, 0, 3),
+            Err(QuantumError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_shot_campaign_stops_once_estimate_converges() {
+        let mut circuit = Circuit::new(2);
+        circuit.ry(0, 1.0).cnot(0, 1);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let model = NoiseModel::new();
+
+        // A Bell-like state has perfectly correlated parity, so its estimate converges at once
+        let parity = Convergence::parity(&[0, 1], 0.01).with_min_shots(20);
+        let mut rng = StdRng::seed_from_u64(11);
+        let (counts, estimate) = Simulator::sample_until_converged_with_rng(
+            &circuit,
+            &initial_state,
+            &model,
+            10_000,
+            &parity,
+            &mut rng,
+        );
+        assert_eq!(counts.shots(), 20);
+        assert_eq!(estimate.mean, 1.0);
+
+        let criterion = Convergence::probability(3, 0.01);
+        let (counts, estimate) = Simulator::sample_until_converged_with_rng(
+            &circuit,
+            &initial_state,
+            &model,
+            10_000,
+            &criterion,
+            &mut rng,
+        );
+        assert_eq!(counts.shots(), estimate.shots);
+        assert!(estimate.shots < 10_000);
+        assert!(estimate.standard_error < 0.01);
+        let expected = (0.5_f64).sin().powi(2);
+        assert!((estimate.mean - expected).abs() < 0.05);
+
+        // An unreachable tolerance runs the whole budget
+        let strict = Convergence::probability(3, 1e-9);
+        let (counts, _) = Simulator::sample_until_converged_with_rng(
+            &circuit,
+            &initial_state,
+            &model,
+            500,
+            &strict,
+            &mut rng,
+        );
+        assert_eq!(counts.shots(), 500);
+    }
+
+    #[test]
+    fn test_circuit_inverse_repeat_and_compose() {
+        let mut block = Circuit::new(3);
+        block
+            .h(0)
+            .s(1)
+            .t(2)
+            .rx(0, 0.4)
+            .iswap(1, 2)
+            .toffoli(0, 1, 2)
+            .add_single_qubit_gate(ry(0.9), 1)
+            .phase(2, 1.3);
+
+        let mut circuit = Circuit::new(3);
+        circuit.append(&block).append(&block.inverse());
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[5] = Complex::new(1.0, 0.0);
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+        for (actual, expected) in final_qubit.state.iter().zip(&initial_state) {
+            assert!(complex_approx_eq(*actual, *expected, TOLERANCE));
+        }
+
+        // Eight T gates are the identity, four are Z
+        let mut t = Circuit::new(1);
+        t.t(0);
+        let plus = vec![Complex::new(1.0, 0.0), Complex::new(1.0, 0.0)]
+            .into_iter()
+            .map(|a| a / 2.0_f64.sqrt())
+            .collect::<Vec<_>>();
+        let state = Simulator::run(&t.repeat(4), &plus).state;
+        assert!(complex_approx_eq(state[1], -plus[1], TOLERANCE));
+        assert!(t.repeat(0).instructions().is_empty());
+
+        let mut bell = Circuit::new(2);
+        bell.h(0).cnot(0, 1);
+        let mut pairs = Circuit::new(4);
+        pairs.compose(&bell, &[3, 1]);
+        assert_eq!(pairs.instructions()[1].qubits, vec![3, 1]);
+        let state = Simulator::run(&pairs, &{
+            let mut zero = vec![Complex::new(0.0, 0.0); 16];
+            zero[0] = Complex::new(1.0, 0.0);
+            zero
+        })
+        .state;
+        assert!(complex_approx_eq(state[0b1010], plus[1], TOLERANCE));
+    }
+
+    #[test]
+    fn test_importance_sampling_resolves_rare_parity_sector() {
+        // A weak rotation leaves the odd-parity sector with probability sin²(ε/2) ≈ 2.5e-5
+        let epsilon = 0.01;
+        let mut circuit = Circuit::new(3);
+        circuit.ry(0, epsilon).h(2);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+
+        let odd_parity = |index: usize| ((index & 0b11).count_ones() % 2) as f64;
+        let mut rng = StdRng::seed_from_u64(21);
+        let counts = final_qubit.sample_weighted_with_rng(2000, odd_parity, &mut rng);
+        assert_eq!(counts.shots(), 2000);
+        assert!(counts.counts().iter().all(|(bits, _)| bits.ends_with('1')));
+        let rare = (epsilon / 2.0).sin().powi(2);
+        assert!((counts.total_estimate() - rare).abs() < 1e-12);
+        assert!((counts.estimate("001") - rare / 2.0).abs() < 0.1 * rare);
+
+        // A soft bias keeps every outcome and still gives unbiased estimates
+        let counts = final_qubit.sample_weighted_with_rng(
+            20_000,
+            |index| if index & 1 == 1 { 1000.0 } else { 1.0 },
+            &mut rng,
+        );
+        assert!((counts.estimate("000") - (1.0 - rare) / 2.0).abs() < 0.05);
+        assert!((counts.estimate("101") - rare / 2.0).abs() < 0.2 * rare);
+        assert!(counts.effective_sample_size() < 20_000.0);
+    }
+
+    #[test]
+    fn test_truncated_run_tracks_discarded_probability() {
+        let mut circuit = Circuit::new(3);
+        circuit.h(0).cnot(0, 1).ry(2, 0.02).cnot(2, 0).rz(1, 0.3);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let exact = Simulator::run(&circuit, &initial_state);
+
+        // A zero threshold keeps everything
+        let result = Simulator::run_truncated(&circuit, &initial_state, 0.0);
+        assert_eq!(result.truncation_error, 0.0);
+        assert_eq!(result.state, exact);
+
+        let result = Simulator::run_truncated(&circuit, &initial_state, 0.05);
+        assert!(result.truncation_error > 0.0);
+        assert_eq!(
+            result.state.state.iter().filter(|a| a.norm() > 0.0).count(),
+            2
+        );
+        let overlap: Complex<f64> = exact
+            .state
+            .iter()
+            .zip(&result.state.state)
+            .map(|(a, b)| a.conj() * b)
+            .sum();
+        assert!(1.0 - overlap.norm_sqr() <= result.truncation_error + TOLERANCE);
+
+        // A threshold above every amplitude cannot renormalize, so the state stays finite
+        let mut spread = Qubit::plus_state(3);
+        assert_eq!(spread.truncate(0.5), 0.0);
+        assert_eq!(spread, Qubit::plus_state(3));
+        let result = Simulator::run_truncated(&circuit, &initial_state, 2.0);
+        assert_eq!(result.truncation_error, 0.0);
+        assert!(result
+            .state
+            .state
+            .iter()
+            .all(|a| a.re.is_finite() && a.im.is_finite()));
+        assert!(
+            (result.state.state.iter().map(|a| a.norm_sqr()).sum::<f64>() - 1.0).abs() < TOLERANCE
+        );
+    }
+
+    #[test]
+    fn test_qft_matches_discrete_fourier_transform() {
+        let num_qubits = 4;
+        let size = 1 << num_qubits;
+        for x in [0, 1, 6, 13] {
+            let mut initial_state = vec![Complex::new(0.0, 0.0); size];
+            initial_state[x] = Complex::new(1.0, 0.0);
+            let final_qubit = Simulator::run(&qft(num_qubits), &initial_state);
+            for (y, amplitude) in final_qubit.state.iter().enumerate() {
+                let angle = 2.0 * std::f64::consts::PI * (x * y) as f64 / size as f64;
+                let expected = Complex::from_polar(1.0 / (size as f64).sqrt(), angle);
+                assert!(complex_approx_eq(*amplitude, expected, TOLERANCE));
+            }
+
+            let round_trip = Simulator::run(&inverse_qft(num_qubits), &final_qubit.state);
+            assert!(complex_approx_eq(
+                round_trip.state[x],
+                Complex::new(1.0, 0.0),
+                TOLERANCE
+            ));
+        }
+    }
+
+    #[test]
+    fn test_phase_estimation_recovers_eigenphase() {
+        // A controlled phase on two target qubits has eigenstate |11⟩ with phase φ = 5/16
+        let phi = 5.0 / 16.0;
+        let unitary = phase(2.0 * std::f64::consts::PI * phi)
+            .controlled(0, 1, 2)
+            .unwrap();
+        let precision = 4;
+        let mut circuit = Circuit::new(precision + 2);
+        circuit
+            .x(precision)
+            .x(precision + 1)
+            .append(&phase_estimation(&unitary, precision));
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << (precision + 2)];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let final_qubit = Simulator::run(&circuit, &initial_state);
+        let expected = 0b11 << precision | 5;
+        assert!((final_qubit.state[expected].norm() - 1.0).abs() < 1e-9);
+
+        // A phase between grid points is still most likely rounded to the nearest one
+        let unitary = phase(2.0 * std::f64::consts::PI * 0.3);
+        let mut circuit = Circuit::new(precision + 1);
+        circuit
+            .x(precision)
+            .append(&phase_estimation(&unitary, precision));
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << (precision + 1)];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let probabilities: Vec<f64> = Simulator::run(&circuit, &initial_state)
+            .state
+            .iter()
+            .map(|a| a.norm_sqr())
+            .collect();
+        let most_likely = (0..probabilities.len())
+            .max_by(|&a, &b| probabilities[a].total_cmp(&probabilities[b]))
+            .unwrap();
+        assert_eq!(most_likely & 0b1111, 5); // 0.3 · 16 = 4.8
+    }
+
+    #[test]
+    fn test_grover_oracles_agree_and_track_success_probability() {
+        let num_qubits = 5;
+        let from_bitstrings = Oracle::from_bitstrings(num_qubits, &["00111", "10100"]);
+        let from_fn = Oracle::from_fn(num_qubits, |index| index == 7 || index == 20);
+        let from_gate = Oracle::from_gate(&from_fn.gate()).unwrap();
+        assert_eq!(from_bitstrings, from_fn);
+        assert_eq!(from_gate, from_fn);
+        assert!(Oracle::from_gate(&hadamard(1)).is_err());
+
+        let grover = Grover::new(from_fn);
+        assert_eq!(grover.iterations(), 3);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << num_qubits];
+        initial_state[0] = Complex::new(1.0, 0.0);
+
+        // The simulated success probability after each round matches the analytic curve
+        for (iterations, expected) in grover.success_probabilities().into_iter().enumerate() {
+            let circuit = grover.clone().with_iterations(iterations).circuit();
+            let state = Simulator::run(&circuit, &initial_state).state;
+            let success = state[7].norm_sqr() + state[20].norm_sqr();
+            assert!((success - expected).abs() < 1e-9);
+        }
+        assert!(grover.success_probability(grover.iterations()) > 0.9);
+
+        // Nothing to find means nothing to amplify
+        assert_eq!(Grover::new(Oracle::from_fn(3, |_| false)).iterations(), 0);
+    }
+
+    #[test]
+    fn test_sector_simulation_matches_full_register() {
+        let num_qubits = 6;
+        let mut circuit = Circuit::new(num_qubits);
+        circuit
+            .iswap(0, 1)
+            .add_controlled_gate(phase(0.7), 1, &[2])
+            .swap(2, 5)
+            .rz(3, 0.4)
+            .iswap(3, 4)
+            .cz(4, 5)
+            .iswap(1, 4)
+            .t(0);
+
+        let sector = Sector::new(num_qubits, 3);
+        assert_eq!(sector.dimension(), 20);
+        let mut initial = vec![Complex::new(0.0, 0.0); sector.dimension()];
+        initial[sector.index_of(0b000111).unwrap()] = Complex::new(0.6, 0.0);
+        initial[sector.index_of(0b101010).unwrap()] = Complex::new(0.0, 0.8);
+
+        let amplitudes = sector.run(&circuit, &initial).unwrap();
+        let full = Simulator::run(&circuit, &sector.embed(&initial));
+        let embedded = sector.embed(&amplitudes);
+        for (actual, expected) in embedded.iter().zip(&full.state) {
+            assert!(complex_approx_eq(*actual, *expected, TOLERANCE));
+        }
+
+        assert_eq!(Sector::new(4, 0).basis(), &[0]);
+        assert_eq!(Sector::new(4, 4).basis(), &[15]);
+        assert!(Sector::new(3, 4).basis().is_empty());
+        assert!(matches!(
+            sector.run(&circuit, &initial[1..]),
+            Err(QuantumError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_entanglement_metrics_of_partially_entangled_state() {
+        // cos(θ/2)|000⟩ + sin(θ/2)|011⟩ with a spectator qubit in |+⟩
+        let theta = 1.2;
+        let mut circuit = Circuit::new(3);
+        circuit.ry(0, theta).cnot(0, 1).h(2);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let state = Simulator::run(&circuit, &initial_state);
+
+        let reduced = reduced_density_matrix(&state, &[1, 0]);
+        let full = DensityMatrix::from_state(&state).partial_trace(&[1, 0]);
+        for (row, expected_row) in reduced.matrix.iter().zip(&full.matrix) {
+            for (entry, expected) in row.iter().zip(expected_row) {
+                assert!(complex_approx_eq(*entry, *expected, TOLERANCE));
+            }
+        }
+
+        let (c, s) = ((theta / 2.0).cos(), (theta / 2.0).sin());
+        let coefficients = schmidt_coefficients(&state, &[0]);
+        assert!((coefficients[0] - c.max(s)).abs() < 1e-9);
+        assert!((coefficients[1] - c.min(s)).abs() < 1e-9);
+        let entropy = -(c * c) * (c * c).log2() - (s * s) * (s * s).log2();
+        assert!((entanglement_entropy(&state, &[0]) - entropy).abs() < 1e-9);
+        assert!((entanglement_entropy(&state, &[1, 2]) - entropy).abs() < 1e-9);
+        assert!(entanglement_entropy(&state, &[2]).abs() < 1e-9);
+
+        assert!((concurrence(&reduced) - 2.0 * c * s).abs() < 1e-6);
+        assert!(concurrence(&reduced_density_matrix(&state, &[0, 2])) < 1e-6);
+
+        // Entangled qubits sit inside the Bloch sphere, the spectator on its surface
+        let (x, y, z) = bloch_vector(&state, 0);
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9 && (z - theta.cos()).abs() < 1e-9);
+        let (x, _, _) = bloch_vector(&state, 2);
+        assert!((x - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_remapped_circuit_matches_permuted_state() {
+        let num_qubits = 10;
+        let mut circuit = Circuit::new(num_qubits);
+        for qubit in 0..num_qubits {
+            circuit.ry(qubit, 0.3 + qubit as f64 * 0.2);
+        }
+        circuit
+            .cnot(0, 9)
+            .iswap(2, 8)
+            .toffoli(1, 4, 6)
+            .rz(5, 0.7)
+            .cz(3, 7);
+
+        let permutation = [7, 2, 9, 0, 4, 1, 8, 3, 6, 5];
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << num_qubits];
+        initial_state[0] = Complex::new(1.0, 0.0);
+
+        let mut expected = Simulator::run(&circuit, &initial_state);
+        expected.permute_qubits(&permutation).unwrap();
+        let remapped = circuit.remap_qubits(&permutation).unwrap();
+        let actual = Simulator::run(&remapped, &initial_state);
+        for (a, b) in actual.state.iter().zip(&expected.state) {
+            assert!(complex_approx_eq(*a, *b, TOLERANCE));
+        }
+
+        // Undoing the permutation restores the original labels
+        let mut inverse = [0; 10];
+        for (qubit, &target) in permutation.iter().enumerate() {
+            inverse[target] = qubit;
+        }
+        expected.permute_qubits(&inverse).unwrap();
+        assert_eq!(expected, Simulator::run(&circuit, &initial_state));
+
+        assert_eq!(
+            expected.permute_qubits(&[0, 0, 1, 2, 3, 4, 5, 6, 7, 8]),
+            Err(QuantumError::DuplicateQubit { qubit: 0 })
+        );
+        assert!(circuit.remap_qubits(&[0, 1]).is_err());
+    }
+
+    #[test]
+    fn test_light_cone_preserves_measured_marginals() {
+        let mut circuit = Circuit::new(7);
+        circuit
+            .h(0)
+            .ry(1, 0.4)
+            .cnot(0, 1)
+            .h(4)
+            .cnot(4, 5)
+            .cnot(5, 6)
+            .rx(2, 0.9)
+            .cnot(1, 2)
+            .cz(3, 6)
+            .t(2);
+        let cone = light_cone(&circuit, &[2]);
+        assert_eq!(cone.qubits, vec![0, 1, 2]);
+        assert_eq!(cone.circuit.num_qubits(), 3);
+        assert_eq!(cone.circuit.instructions().len(), 6);
+
+        let run = |circuit: &Circuit| {
+            let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << circuit.num_qubits()];
+            initial_state[0] = Complex::new(1.0, 0.0);
+            Simulator::run(circuit, &initial_state)
+        };
+        let full = reduced_density_matrix(&run(&circuit), &[2]);
+        let reduced = reduced_density_matrix(&run(&cone.circuit), &[cone.position(2).unwrap()]);
+        for (row, reduced_row) in full.matrix.iter().zip(&reduced.matrix) {
+            for (a, b) in row.iter().zip(reduced_row) {
+                assert!(complex_approx_eq(*a, *b, TOLERANCE));
+            }
+        }
+
+        // A conditional gate pulls in the qubit whose measurement feeds it
+        let mut feedback = Circuit::new(4);
+        feedback
+            .h(3)
+            .measure(3, 0)
+            .h(2)
+            .conditional(0, Operation::X, vec![0]);
+        let cone = light_cone(&feedback, &[0]);
+        assert_eq!(cone.qubits, vec![0, 3]);
+        assert_eq!(cone.circuit.instructions().len(), 3);
+    }
+
+    #[test]
+    fn test_expectation_value_matches_full_simulation() {
+        let mut circuit = Circuit::new(6);
+        circuit
+            .h(0)
+            .ry(1, 1.1)
+            .cnot(0, 1)
+            .rx(2, 0.3)
+            .cz(1, 2)
+            .h(4)
+            .cnot(4, 5)
+            .cnot(5, 3)
+            .t(1);
+        // Z ⊗ Z on qubits 1 and 2
+        let observable = Gate::new(
+            (0..4)
+                .map(|i: usize| {
+                    let mut row = vec![Complex::new(0.0, 0.0); 4];
+                    row[i] = Complex::new(1.0 - 2.0 * (i.count_ones() & 1) as f64, 0.0);
+                    row
+                })
+                .collect(),
+        );
+
+        let rho = Simulator::run_density(&circuit, &DensityMatrix::new(6)).partial_trace(&[1, 2]);
+        let expected: f64 = (0..4)
+            .map(|i| rho.matrix[i][i].re * observable.matrix[i][i].re)
+            .sum();
+        assert!((expectation_value(&circuit, &observable, &[1, 2]) - expected).abs() < 1e-10);
+
+        // Mid-circuit measurement inside the cone is handled exactly
+        let mut measured = Circuit::new(3);
+        measured
+            .h(0)
+            .measure(0, 0)
+            .h(2)
+            .conditional(0, Operation::X, vec![1]);
+        assert!(expectation_value(&measured, &pauli_z(), &[1]).abs() < 1e-12);
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn test_gpu_backend_matches_cpu() {
+        use quantum_simulator::backend::{Backend, CpuBackend};
+        use quantum_simulator::gpu::GpuBackend;
+
+        // Headless machines without GPU drivers have nothing to compare against
+        let mut gpu = match GpuBackend::new(5) {
+            Ok(gpu) => gpu,
+            Err(error) => {
+                assert!(matches!(error, QuantumError::BackendUnavailable { .. }));
+                return;
+            }
+        };
+
+        let mut circuit = Circuit::new(5);
+        circuit
+            .h(0)
+            .ry(1, 0.8)
+            .cnot(0, 3)
+            .t(3)
+            .iswap(4, 1)
+            .rx(2, 1.3)
+            .cz(2, 0)
+            .swap(1, 3);
+        let mut cpu = CpuBackend::new(5);
+        let expected = Simulator::run_on(&mut cpu, &circuit).unwrap();
+        let actual = Simulator::run_on(&mut gpu, &circuit).unwrap();
+        for (a, b) in actual.state.iter().zip(&expected.state) {
+            assert!(complex_approx_eq(*a, *b, 1e-5));
+        }
+        let zz = cz(0, 1, 2);
+        assert!((gpu.expectation(&zz, &[3, 1]) - cpu.expectation(&zz, &[3, 1])).abs() < 1e-5);
+
+        assert!(matches!(
+            gpu.apply_gate(&toffoli(0, 1, 2, 3), &[0, 1, 2]),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_schedule_layers_and_idle_spans() {
+        let mut circuit = Circuit::new(4);
+        circuit
+            .h(0)
+            .h(1)
+            .cnot(0, 1)
+            .x(3)
+            .measure(1, 0)
+            .conditional(0, Operation::X, vec![3])
+            .cz(2, 3);
+        let schedule = Schedule::new(&circuit);
+
+        assert_eq!(schedule.depth(), 5);
+        assert_eq!(
+            schedule.layers(),
+            &[vec![0, 1, 3], vec![2], vec![4], vec![5], vec![6]]
+        );
+        // The conditional waits for the measurement of its classical bit
+        assert_eq!(schedule.layer_of(5), schedule.layer_of(4) + 1);
+        assert_eq!(schedule.parallelism(), vec![3, 1, 1, 1, 1]);
+        assert_eq!(schedule.idle_spans(2), vec![0..4]);
+        assert_eq!(schedule.idle_spans(3), vec![1..3]);
+
+        let chart = schedule.to_string();
+        assert_eq!(chart.lines().count(), 5);
+        assert_eq!(chart.lines().nth(4), Some("q3 x .  . ifx cz"));
+    }
+
+    #[test]
+    fn test_prelude_covers_a_typical_program() {
+        use quantum_simulator::prelude::*;
+
+        let mut circuit = Circuit::new(2);
+        circuit.add_single_qubit_gate(pauli_x(), 0).cnot(0, 1);
+        let initial_state: Vec<Complex<f64>> = vec![
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+        let state: StateVector = Simulator::run(&circuit, &initial_state);
+        let counts: Counts = state.sample(10);
+        assert_eq!(counts.get("11"), 10);
+
+        let error: QuantumError = circuit
+            .add_instruction(Instruction::new(Operation::H, vec![2]))
+            .unwrap_err();
+        assert!(matches!(error, QuantumError::QubitOutOfRange { .. }));
+    }
+
+    #[test]
+    fn test_cpu_backend_runs_circuits_with_feedback() {
+        use quantum_simulator::backend::{Backend, CpuBackend};
+
+        // Teleport |1⟩ from qubit 0 to qubit 2 with classically controlled corrections
+        let mut circuit = Circuit::new(3);
+        circuit
+            .x(0)
+            .h(1)
+            .cnot(1, 2)
+            .cnot(0, 1)
+            .h(0)
+            .measure(0, 0)
+            .measure(1, 1)
+            .conditional(1, Operation::X, vec![2])
+            .conditional(0, Operation::Z, vec![2]);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..10 {
+            let mut backend = CpuBackend::new(3);
+            let clbits = backend.run_with_rng(&circuit, &mut rng).unwrap();
+            assert_eq!(clbits.len(), 2);
+            assert!((backend.expectation(&pauli_z(), &[2]) + 1.0).abs() < TOLERANCE);
+        }
+
+        let mut backend = CpuBackend::new(2);
+        assert_eq!(
+            backend.apply_gate(&cnot(0, 1, 2), &[0]),
+            Err(QuantumError::DimensionMismatch {
+                expected: 2,
+                found: 4
+            })
+        );
+        assert!(matches!(
+            backend.run(&circuit),
+            Err(QuantumError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_stabilizer_backend_matches_state_vector() {
+        use quantum_simulator::backend::{Backend, CpuBackend};
+        use quantum_simulator::stabilizer::StabilizerBackend;
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let num_qubits = 4;
+        for _ in 0..20 {
+            let mut circuit = Circuit::new(num_qubits);
+            for _ in 0..30 {
+                let a = rng.gen_range(0..num_qubits);
+                let b = (a + rng.gen_range(1..num_qubits)) % num_qubits;
+                match rng.gen_range(0..8) {
+                    0 => circuit.h(a),
+                    1 => circuit.s(a),
+                    2 => circuit.x(a),
+                    3 => circuit.y(a),
+                    4 => circuit.cnot(a, b),
+                    5 => circuit.cz(a, b),
+                    6 => circuit.swap(a, b),
+                    _ => circuit.iswap(a, b),
+                };
+            }
+            let mut stabilizer = StabilizerBackend::new(num_qubits);
+            let mut cpu = CpuBackend::new(num_qubits);
+            stabilizer.run(&circuit).unwrap();
+            cpu.run(&circuit).unwrap();
+
+            let observables = [
+                (pauli_z(), vec![0]),
+                (pauli_x(), vec![1]),
+                (pauli_y(), vec![2]),
+                (hadamard(1), vec![3]),
+                (cnot(0, 1, 2), vec![2, 0]),
+            ];
+            for (observable, targets) in &observables {
+                let expected = cpu.expectation(observable, targets);
+                let actual = stabilizer.expectation(observable, targets);
+                assert!(
+                    (expected - actual).abs() < 1e-9,
+                    "{} vs {}",
+                    expected,
+                    actual
+                );
+            }
+        }
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).t(0);
+        assert!(matches!(
+            StabilizerBackend::new(2).run(&circuit),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected_without_panicking() {
+        let nested = format!(
+            "qreg q[1]; rz({}1{}) q[0];",
+            "(".repeat(10_000),
+            ")".repeat(10_000)
+        );
+        assert!(matches!(
+            qasm::parse(&nested),
+            Err(QuantumError::Parse { .. })
+        ));
+        assert!(qasm::parse("qreg q[99999999999999999999]; qreg r[2];").is_err());
+        assert!(qasm::parse("qreg q[1]; rz(1/0) q[0];").is_err());
+
+        // Each gate calls the previous one twice, expanding to 2^21 applications.
+        let mut source = "qreg q[1]; gate g0 a { x a; }".to_string();
+        for k in 1..=21 {
+            source += &format!(" gate g{} a {{ g{} a; g{} a; }}", k, k - 1, k - 1);
+        }
+        source += " g21 q[0];";
+        assert!(matches!(
+            qasm::parse(&source),
+            Err(QuantumError::Parse { .. })
+        ));
+
+        let mut circuit = Circuit::new(1);
+        let measure = Instruction::new(Operation::Measure(usize::MAX), vec![0]);
+        assert!(circuit.add_instruction(measure).is_err());
+        assert!(Circuit::new(64).try_run(&mut Qubit::new()).is_err());
+    }
+
+    #[test]
+    fn test_fused_structured_gates_match_dense_simulation() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut circuit = Circuit::new(4);
+        for _ in 0..60 {
+            let a = rng.gen_range(0..4);
+            let b = (a + rng.gen_range(1..4)) % 4;
+            match rng.gen_range(0..8) {
+                0 => circuit.h(a),
+                1 => circuit.t(a),
+                2 => circuit.y(a),
+                3 => circuit.rx(a, rng.gen::<f64>()),
+                4 => circuit.cnot(a, b),
+                5 => circuit.iswap(a, b),
+                6 => circuit.add_controlled_gate(ry(rng.gen()), a, &[b]),
+                _ => circuit.rz(b, rng.gen::<f64>()),
+            };
+        }
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 16];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let mut dense = Qubit::from_state(initial_state.clone());
+        for gate in circuit.to_gates() {
+            gate.apply(&mut dense);
+        }
+        let mut fused = Qubit::from_state(initial_state);
+        circuit.run(&mut fused);
+        for (a, b) in dense.state.iter().zip(&fused.state) {
+            assert!(complex_approx_eq(*a, *b, TOLERANCE));
+        }
+
+        let kinds: Vec<GateKind> = circuit
+            .instructions()
+            .iter()
+            .map(|instruction| instruction.kind())
+            .collect();
+        assert!(fuse(kinds).len() < circuit.instructions().len());
+    }
+
+    #[test]
+    fn test_optimized_circuit_is_equivalent_and_smaller() {
+        let mut circuit = Circuit::new(3);
+        circuit
+            .h(0)
+            .cnot(0, 1)
+            .rz(2, 0.4)
+            .x(1)
+            .x(1)
+            .cnot(0, 1)
+            .rz(2, -0.4)
+            .h(0)
+            .ry(1, 0.3)
+            .ry(1, 0.2)
+            .t(2)
+            .measure(2, 0)
+            .t(2);
+
+        let (optimized, report) = circuit.optimized();
+        assert_eq!(optimized.instructions().len(), 4);
+        assert_eq!(optimized.instructions()[0].operation, Operation::Ry(0.5));
+        assert_eq!(report.gates_before, 12);
+        assert_eq!(report.gates_after, 3);
+        assert!(report.depth_after < report.depth_before);
+        assert_eq!(report.to_string(), "gates: 12 -> 3, depth: 7 -> 3");
+
+        let mut unitary = Circuit::new(3);
+        unitary
+            .h(0)
+            .cnot(0, 1)
+            .x(1)
+            .x(1)
+            .cnot(0, 1)
+            .h(0)
+            .ry(1, 0.3)
+            .ry(1, 0.2)
+            .s(2)
+            .cz(1, 2);
+        let (reduced, _) = unitary.optimized();
+        assert!(reduced.instructions().len() < unitary.instructions().len());
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let amplitudes: Vec<Complex<f64>> = (0..8)
+            .map(|_| Complex::new(rng.gen::<f64>() - 0.5, rng.gen::<f64>() - 0.5))
+            .collect();
+        let norm = amplitudes.iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt();
+        let initial_state: Vec<Complex<f64>> = amplitudes.iter().map(|a| a / norm).collect();
+        let mut expected = Qubit::from_state(initial_state.clone());
+        unitary.run(&mut expected);
+        let mut actual = Qubit::from_state(initial_state);
+        reduced.run(&mut actual);
+        for (a, b) in expected.state.iter().zip(&actual.state) {
+            assert!(complex_approx_eq(*a, *b, TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn test_lenient_mode_reports_recoverable_problems_as_warnings() {
+        // Qubit 1 is |1⟩ with probability 1e-20.
+        let tiny: f64 = 1e-10;
+        let state = vec![
+            Complex::new((1.0 - tiny * tiny).sqrt(), 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(tiny, 0.0),
+            Complex::new(0.0, 0.0),
+        ];
+        let mut strict = Qubit::from_state(state.clone());
+        assert!(matches!(
+            strict.postselect(1, 1, ExecutionMode::Strict),
+            Err(QuantumError::PostselectionFailed {
+                qubit: 1,
+                outcome: 1,
+                ..
+            })
+        ));
+        assert_eq!(strict.state, state);
+
+        let mut lenient = Qubit::from_state(state);
+        let execution = lenient.postselect(1, 1, ExecutionMode::Lenient).unwrap();
+        assert!(matches!(
+            execution.warnings[..],
+            [Warning::UnlikelyPostselection {
+                qubit: 1,
+                outcome: 1,
+                ..
+            }]
+        ));
+        assert!(complex_approx_eq(
+            lenient.state[2],
+            Complex::new(1.0, 0.0),
+            TOLERANCE
+        ));
+        assert!(lenient.postselect(0, 1, ExecutionMode::Lenient).is_err());
+
+        let mut circuit = Circuit::new(1);
+        circuit.h(0);
+        let drifted = vec![Complex::new(0.999, 0.0), Complex::new(0.0, 0.0)];
+        assert_eq!(
+            Simulator::try_run(&circuit, &drifted, ExecutionMode::Strict),
+            Err(QuantumError::NotNormalized { norm: 0.999 })
+        );
+        let execution = Simulator::try_run(&circuit, &drifted, ExecutionMode::Lenient).unwrap();
+        assert_eq!(
+            execution.warnings,
+            vec![Warning::Renormalized { norm: 0.999 }]
+        );
+        let h = 1.0 / 2.0_f64.sqrt();
+        assert!(complex_approx_eq(
+            execution.value.state[1],
+            Complex::new(h, 0.0),
+            TOLERANCE
+        ));
+
+        #[cfg(feature = "serde")]
+        {
+            // Deserialized circuits skip `add_instruction`, so the checked entry points must
+            // validate them before running.
+            let json = r#"{"num_qubits":1,"num_clbits":0,"instructions":[{"operation":"H","qubits":[3]}]}"#;
+            let circuit: Circuit = serde_json::from_str(json).unwrap();
+            let initial_state = vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)];
+            assert!(matches!(
+                Simulator::try_run(&circuit, &initial_state, ExecutionMode::Lenient),
+                Err(QuantumError::QubitOutOfRange {
+                    qubit: 3,
+                    num_qubits: 1
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_draw_renders_wires_boxes_controls_and_measurements() {
+        let mut circuit = Circuit::new(4);
+        circuit
+            .h(0)
+            .h(1)
+            .toffoli(0, 3, 2)
+            .rz(1, 0.5)
+            .swap(1, 2)
+            .measure(0, 1)
+            .reset(3)
+            .conditional(1, Operation::X, vec![2]);
+
+        let expected = [
+            "     ┌───┐         ┌──────┐",
+            "q0: ─┤ H ├───●─────┤ M→c1 ├─────────────────",
+            "     └───┘   │     └──────┘",
+            "     ┌───┐   │   ┌──────────┐",
+            "q1: ─┤ H ├───┼───┤ Rz(0.50) ├─×─────────────",
+            "     └───┘   │   └──────────┘ │",
+            "           ┌─┴─┐              │ ┌─────────┐",
+            "q2: ───────┤ X ├──────────────×─┤ X if c1 ├─",
+            "           └─┬─┘                └─────────┘",
+            "             │     ┌─────┐",
+            "q3: ─────────●─────┤ |0⟩ ├──────────────────",
+            "                   └─────┘",
+        ];
+        assert_eq!(circuit.draw(), expected.join("\n") + "\n");
+
+        let ascii = circuit.draw_ascii();
+        assert!(ascii.is_ascii());
+        assert!(ascii.contains("q2: -------| X |--------------x-| X if c1 |-"));
+        assert_eq!(Circuit::new(1).draw(), "q0: ─\n");
+    }
+
+    #[test]
+    fn test_circuits_share_gate_matrices_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Gate>();
+        assert_send_sync::<Circuit>();
+
+        let mut circuit = Circuit::new(3);
+        circuit.h(0);
+        circuit.add_gate(toffoli(0, 1, 2, 3));
+        let copy = circuit.clone();
+        match (
+            &circuit.instructions()[1].operation,
+            &copy.instructions()[1].operation,
+        ) {
+            (Operation::Unitary(a), Operation::Unitary(b)) => {
+                assert!(Arc::ptr_eq(&a.matrix, &b.matrix))
+            }
+            _ => panic!("expected unitary instructions"),
+        }
+
+        let shared = Arc::new(circuit);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[2] = Complex::new(1.0, 0.0);
+        let expected = Simulator::run(&shared, &initial_state).state;
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let circuit = Arc::clone(&shared);
+                let initial_state = initial_state.clone();
+                thread::spawn(move || Simulator::run(&circuit, &initial_state).state)
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_trace_shares_unchanged_chunks() {
+        let num_qubits = 12;
+        let mut circuit = Circuit::new(num_qubits);
+        circuit.h(11).h(10).z(11).s(10).x(0);
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 1 << num_qubits];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let mut trace = vec![Snapshot::capture(&initial_state)];
+        let mut hooks = Hooks::new();
+        hooks.on_gate_applied(|_, _, qubit| {
+            let next = trace.last().unwrap().capture_next(&qubit.state);
+            trace.push(next);
+        });
+        let result =
+            Simulator::run_with_hooks(&circuit, &initial_state, &NoiseModel::new(), &mut hooks);
+        drop(hooks);
+
+        // Four chunks of 1024 amplitudes, selected by qubits 10 and 11. H(11) rewrites chunks
+        // 0 and 2 and H(10) all four; Z(11) and S(10) touch only the chunks where their qubit is
+        // 1, and X(0) permutes amplitudes within every chunk.
+        let shared: Vec<usize> = trace
+            .windows(2)
+            .map(|pair| pair[1].shared_chunks(&pair[0]))
+            .collect();
+        assert_eq!(shared, vec![2, 0, 2, 2, 0]);
+        assert_eq!(trace.last().unwrap().to_qubit(), result.state);
+
+        // Resuming from an intermediate snapshot reproduces the rest of the run
+        let mut tail = Circuit::new(num_qubits);
+        tail.z(11).s(10).x(0);
+        let resumed = Simulator::run(&tail, &trace[2].to_qubit().state);
+        for (a, b) in resumed.state.iter().zip(&result.state.state) {
+            assert!((a - b).norm() < 1e-12);
+        }
+
+        let mut edited = trace[1].clone();
+        edited.set(0, Complex::new(0.0, 0.0));
+        assert_eq!(trace[1].get(0), Complex::new(1.0 / 2f64.sqrt(), 0.0));
+        assert_eq!(edited.shared_chunks(&trace[1]), 3);
+    }
+
+    #[test]
+    fn test_run_stepwise_records_every_instruction_and_labeled_snapshots() {
+        let mut circuit = Circuit::new(2);
+        circuit
+            .x(0)
+            .snapshot("flipped")
+            .cnot(0, 1)
+            .measure(1, 0)
+            .snapshot("measured");
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let mut rng = StdRng::seed_from_u64(5);
+        let trace = Simulator::run_stepwise_with_rng(&circuit, &initial_state, &mut rng);
+
+        let instructions: Vec<usize> = trace.steps().iter().map(|step| step.instruction).collect();
+        assert_eq!(instructions, vec![0, 1, 2, 3, 4]);
+        let populated: Vec<usize> = trace
+            .states()
+            .map(|state| (0..4).find(|&k| state.get(k).norm() > 0.5).unwrap())
+            .collect();
+        assert_eq!(populated, vec![0, 1, 1, 3, 3, 3]);
+        assert_eq!(
+            trace.snapshot("flipped").unwrap().get(1),
+            Complex::new(1.0, 0.0)
+        );
+        assert_eq!(trace.snapshot("measured"), Some(trace.final_state()));
+        assert_eq!(trace.snapshot("missing"), None);
+        assert_eq!(trace.clbits(), &[true]);
+
+        // Snapshots leave every other execution path untouched
+        let mut qubit = Qubit::from_state(initial_state.clone());
+        assert_eq!(circuit.run_with_rng(&mut qubit, &mut rng), vec![true]);
+        assert_eq!(qubit.state, trace.final_state().to_qubit().state);
+        assert_eq!(circuit.draw(), {
+            let mut plain = Circuit::new(2);
+            plain.x(0).cnot(0, 1).measure(1, 0);
+            plain.draw()
+        });
+        let qasm = qasm::to_qasm(&circuit, QasmVersion::V2).unwrap();
+        assert!(qasm.contains("// snapshot \"flipped\""));
+
+        // Snapshots are the identity when inverting and comparing unitary circuits
+        let mut labeled = Circuit::new(2);
+        labeled.h(0).snapshot("superposed").cnot(0, 1);
+        assert!(labeled.is_unitary());
+        let inverse = labeled.inverse();
+        assert_eq!(
+            inverse.instructions()[1].operation,
+            Operation::Snapshot("superposed".to_string())
+        );
+        let mut round_trip = labeled.clone();
+        round_trip.append(&inverse);
+        assert!(
+            quantum_simulator::analysis::circuit_equivalence(&round_trip, &Circuit::new(2))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_run_stepwise_trajectory_records_sampled_errors() {
+        let mut circuit = Circuit::new(2);
+        circuit.x(0).cnot(0, 1);
+
+        // A flip that always fires cancels the X and then flips both qubits of the CNOT
+        let mut noise_model = NoiseModel::new();
+        noise_model.add_error(bit_flip(1.0));
+
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 4];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let mut rng = StdRng::seed_from_u64(9);
+        let trace = Simulator::run_stepwise_trajectory_with_rng(
+            &circuit,
+            &initial_state,
+            &noise_model,
+            &mut rng,
+        );
+        let populated: Vec<usize> = trace
+            .states()
+            .map(|state| (0..4).find(|&k| state.get(k).norm() > 0.5).unwrap())
+            .collect();
+        assert_eq!(populated, vec![0, 0, 3]);
+
+        // Without errors the trajectory matches the noiseless trace
+        let noiseless = Simulator::run_stepwise_trajectory_with_rng(
+            &circuit,
+            &initial_state,
+            &NoiseModel::new(),
+            &mut rng,
+        );
+        assert_eq!(
+            noiseless,
+            Simulator::run_stepwise_with_rng(&circuit, &initial_state, &mut rng)
+        );
+    }
+
+    #[test]
+    fn test_state_preparation_constructors() {
+        let probabilities = |qubit: &Qubit| -> Vec<f64> {
+            qubit
+                .state
+                .iter()
+                .map(|a| (a.norm_sqr() * 1e9).round() / 1e9)
+                .collect()
+        };
+
+        assert_eq!(Qubit::zero_state(2), Qubit::from_bitstring("00").unwrap());
+        let mut basis = Qubit::from_bitstring("110").unwrap();
+        assert_eq!(basis.state[6], Complex::new(1.0, 0.0));
+        assert_eq!(
+            basis.measure_all_with_rng(&mut StdRng::seed_from_u64(1)),
+            vec![0, 1, 1]
+        );
+        assert!(matches!(
+            Qubit::from_bitstring("1a"),
+            Err(QuantumError::Parse { .. })
+        ));
+        // Bitstrings too long for a basis index are rejected rather than overflowing it
+        assert!(matches!(
+            Qubit::from_bitstring(&"1".repeat(64)),
+            Err(QuantumError::Parse { .. })
+        ));
+        assert!(matches!(
+            Qubit::from_bitstring(&"0".repeat(200)),
+            Err(QuantumError::Parse { .. })
+        ));
+
+        let weighted = Qubit::from_probabilities(&[1.0, 0.0, 2.0, 1.0]).unwrap();
+        assert_eq!(probabilities(&weighted), vec![0.25, 0.0, 0.5, 0.25]);
+        assert_eq!(
+            Qubit::from_probabilities(&[1.0, 0.0, 0.0]),
+            Err(QuantumError::InvalidDimension { dimension: 3 })
+        );
+        assert!(matches!(
+            Qubit::from_probabilities(&[0.0, f64::NAN]),
+            Err(QuantumError::InvalidProbability { index: 1, .. })
+        ));
+        assert_eq!(
+            Qubit::from_probabilities(&[0.0, 0.0]),
+            Err(QuantumError::NotNormalized { norm: 0.0 })
+        );
+
+        assert_eq!(probabilities(&Qubit::plus_state(2)), vec![0.25; 4]);
+        assert_eq!(
+            probabilities(&Qubit::ghz(3)),
+            vec![0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.5]
+        );
+        let w = probabilities(&Qubit::w_state(3));
+        let third = (1e9_f64 / 3.0).round() / 1e9;
+        assert_eq!(w, vec![0.0, third, third, 0.0, third, 0.0, 0.0, 0.0]);
+
+        // Random states are normalized, reproducible and differ between seeds
+        let random = Qubit::random_state(4, 7);
+        let norm: f64 = random.state.iter().map(|a| a.norm_sqr()).sum();
+        assert!((norm - 1.0).abs() < 1e-12);
+        assert_eq!(random, Qubit::random_state(4, 7));
+        assert_ne!(random, Qubit::random_state(4, 8));
+    }
+
+    #[test]
+    fn test_landscape_sweeps_and_renders_heatmap() {
+        let axis = Axis::new(0.0, std::f64::consts::PI, 5);
+        let line = sweep_1d(
+            &axis,
+            |theta| {
+                let mut circuit = Circuit::new(1);
+                circuit.ry(0, theta);
+                circuit
+            },
+            &pauli_z(),
+            &[0],
+        );
+        for point in line.points() {
+            assert!((point.value - point.x.cos()).abs() < 1e-12);
+            assert_eq!(point.y, None);
+        }
+        assert!((line.max().value - 1.0).abs() < 1e-12);
+
+        // ⟨Z⟩ on qubit 0 ignores the second parameter, so every row is the same
+        let grid = sweep_2d(
+            &axis,
+            &Axis::new(0.0, 1.0, 3),
+            |a, b| {
+                let mut circuit = Circuit::new(2);
+                circuit.ry(0, a).rx(1, b);
+                circuit
+            },
+            &pauli_z(),
+            &[0],
+        );
+        assert_eq!(grid.values.len(), 3);
+        assert!(grid.values.iter().all(|row| row == &line.values[0]));
+        assert!((grid.variance() - line.variance()).abs() < 1e-12);
+        assert_eq!(grid.to_svg().matches("<rect").count(), 15);
+
+        // A flat landscape has zero variance
+        let flat = sweep_1d(&axis, |_| Circuit::new(1), &pauli_z(), &[0]);
+        assert!(flat.variance() < 1e-24);
+    }
+
+    #[test]
+    fn test_variational_optimization_streams_iterations() {
+        // VQE on Z0 Z1 + 0.5 (X0 + X1), whose ground energy is -√2
+        let hamiltonian = Hamiltonian::parse("Z0*Z1 + 0.5 X0 + 0.5 X1").unwrap();
+        let ansatz = Ansatz::HardwareEfficient {
+            num_qubits: 2,
+            layers: 1,
+        };
+        let initial_state = Qubit::zero_state(2).state;
+        let energy = |params: &[f64]| {
+            hamiltonian.expectation(&Simulator::run(&ansatz.circuit(params), &initial_state))
+        };
+        let mut rows = vec![Iteration::csv_header(ansatz.num_parameters())];
+        let last =
+            GradientDescent::new(0.2, 500).minimize(energy, &[0.1, -0.2, 0.3, 0.4], |iteration| {
+                rows.push(iteration.to_csv_row())
+            });
+        assert!((last.energy + 2f64.sqrt()).abs() < 1e-4);
+        assert_eq!(rows.len(), last.step + 2);
+        assert!(rows[1].starts_with("0,"));
+        assert_eq!(rows[1].split(',').count(), 7);
+
+        // QAOA for MaxCut on a triangle, where cutting two of the three edges gives -1
+        let cost = Hamiltonian::parse("Z0 Z1 + Z1 Z2 + Z0 Z2").unwrap();
+        let qaoa = Ansatz::Qaoa {
+            hamiltonian: cost.clone(),
+            layers: 1,
+        };
+        let initial_state = Qubit::zero_state(3).state;
+        let last = GradientDescent::new(0.05, 300).minimize(
+            |params| cost.expectation(&Simulator::run(&qaoa.circuit(params), &initial_state)),
+            &[1.0, 0.4],
+            |_| {},
+        );
+        assert!((last.energy + 1.0).abs() < 1e-6);
+
+        assert!(matches!(
+            Hamiltonian::parse("Z0 Z0"),
+            Err(QuantumError::DuplicateQubit { qubit: 0 })
+        ));
+        assert!(Hamiltonian::parse("Z0 +").is_err());
+        use quantum_simulator::qubit::Basis;
+        use quantum_simulator::variational::PauliString;
+        assert!(matches!(
+            PauliString::parse("Z64"),
+            Err(QuantumError::Parse { .. })
+        ));
+        assert!(Hamiltonian::parse("X1000").is_err());
+        let edge = PauliString::parse("Z63").unwrap();
+        assert_eq!(edge.factor(63), Some(Basis::Z));
+        assert_eq!(edge.factor(200), None);
+        assert!(!Hamiltonian::parse("1e-3 X0").unwrap().is_diagonal());
+    }
+
+    #[test]
+    fn test_arbitrary_single_qubit_unitaries() {
+        let close = |a: &Gate, b: &Gate| {
+            a.matrix
+                .iter()
+                .flatten()
+                .zip(b.matrix.iter().flatten())
+                .all(|(x, y)| (x - y).norm() < 1e-12)
+        };
+
+        // U(θ, φ, λ) = e^{i(φ+λ)/2} RZ(φ) RY(θ) RZ(λ)
+        let (theta, phi, lambda) = (0.7, -1.3, 2.1);
+        let mut euler = rz(phi).matrix.as_ref().clone();
+        for gate in [ry(theta), rz(lambda)] {
+            euler = (0..2)
+                .map(|i| {
+                    (0..2)
+                        .map(|j| (0..2).map(|k| euler[i][k] * gate.matrix[k][j]).sum())
+                        .collect()
+                })
+                .collect();
+        }
+        let global = Complex::from_polar(1.0, (phi + lambda) / 2.0);
+        let euler = Gate::new(
+            euler
+                .into_iter()
+                .map(|row| row.into_iter().map(|entry| entry * global).collect())
+                .collect(),
+        );
+        assert!(close(&u3(theta, phi, lambda), &euler));
+
+        // Axis-angle rotations are unitary and reduce to the Pauli rotations on the axes
+        let gate = rotation_about_axis(0.3, -0.4, 1.2, 2.5);
+        assert!(Gate::from_unitary(gate.matrix.as_ref().clone()).is_ok());
+        assert!(close(&rotation_about_axis(0.0, 0.0, 3.0, 0.9), &rz(0.9)));
+        let mut qubit = Qubit::new();
+        rotation_about_axis(1.0, 0.0, 0.0, std::f64::consts::PI).apply(&mut qubit);
+        assert!((qubit.state[1].norm() - 1.0).abs() < 1e-12);
+
+        // Validation rejects non-unitary and malformed matrices
+        let one = Complex::new(1.0, 0.0);
+        assert!(matches!(
+            Gate::from_unitary(vec![vec![one, one], vec![one, -one]]),
+            Err(QuantumError::NotUnitary { .. })
+        ));
+        assert!(matches!(
+            Gate::from_unitary(vec![vec![one; 3]; 3]),
+            Err(QuantumError::InvalidDimension { dimension: 3 })
+        ));
+        let gate = Gate::from_unitary(u3(theta, phi, lambda).matrix.as_ref().clone()).unwrap();
+        assert!(close(&gate, &u3(theta, phi, lambda)));
+    }
+
+    #[test]
+    fn test_parametric_circuit_binding() {
+        let (theta, phi) = (Parameter::new("theta"), Parameter::new("phi"));
+        let mut ansatz = Circuit::new(2);
+        ansatz
+            .ry(0, theta.clone())
+            .cnot(0, 1)
+            .rz(1, phi.clone() * 0.5)
+            .rx(0, -theta)
+            .measure(1, 0)
+            .conditional(0, Operation::X, vec![0]);
+        assert_eq!(ansatz.parameters(), vec!["phi", "theta"]);
+        assert!(ansatz.draw_ascii().contains("Rz(0.5*phi)"));
+
+        // Binding gives the same circuit as building it with numbers, for every sweep point
+        for (a, b) in [(0.3, 1.1), (-0.7, 2.4)] {
+            let values = HashMap::from([("theta".to_string(), a), ("phi".to_string(), b)]);
+            let bound = ansatz.bind_parameters(&values).unwrap();
+            let mut expected = Circuit::new(2);
+            expected
+                .ry(0, a)
+                .cnot(0, 1)
+                .rz(1, 0.5 * b)
+                .rx(0, -a)
+                .measure(1, 0)
+                .conditional(0, Operation::X, vec![0]);
+            assert_eq!(bound, expected);
+            assert!(bound.parameters().is_empty());
+        }
+        assert_eq!(ansatz.parameters().len(), 2);
+
+        // Missing values are reported by name, and the inverse negates the parameter
+        let partial = HashMap::from([("theta".to_string(), 0.3)]);
+        assert!(matches!(
+            ansatz.bind_parameters(&partial),
+            Err(QuantumError::UnboundParameter { name }) if name == "phi"
+        ));
+        let mut rotation = Circuit::new(1);
+        rotation.rz(0, Parameter::new("t"));
+        let mut round_trip = rotation.clone();
+        round_trip.append(&rotation.inverse());
+        let bound = round_trip
+            .bind_parameters(&HashMap::from([("t".to_string(), 0.8)]))
+            .unwrap();
+        assert_eq!(bound.optimized().0.instructions().len(), 0);
+    }
+
+    #[test]
+    fn test_conformance_suite_on_every_backend() {
+        use quantum_simulator::backend::{Backend, CpuBackend};
+        use quantum_simulator::conformance::{self, Outcome};
+        use quantum_simulator::gates::Gate;
+        use quantum_simulator::stabilizer::StabilizerBackend;
+        use rand::RngCore;
+
+        let cpu = conformance::run(CpuBackend::new);
+        assert_eq!(cpu.backend, "cpu");
+        assert_eq!(cpu.results.len(), conformance::cases().len());
+        assert!(cpu
+            .results
+            .iter()
+            .all(|result| result.outcome == Outcome::Passed));
+
+        // The stabilizer backend has no amplitudes, so it is sampled, and rejects non-Clifford
+        // circuits without failing them
+        let stabilizer = conformance::run(StabilizerBackend::new);
+        assert!(stabilizer.passed(), "{}", stabilizer);
+        let outcome = |report: &conformance::Report, name: &str| {
+            report
+                .results
+                .iter()
+                .find(|result| result.name == name)
+                .unwrap()
+                .outcome
+                .clone()
+        };
+        assert_eq!(outcome(&stabilizer, "ghz_3"), Outcome::Passed);
+        assert_eq!(
+            outcome(&stabilizer, "conditional_correction"),
+            Outcome::Passed
+        );
+        assert!(matches!(
+            outcome(&stabilizer, "ry_third_turn"),
+            Outcome::Unsupported(_)
+        ));
+
+        // A third-party backend that applies two-qubit gates with their qubits swapped is caught
+        struct Swapped(CpuBackend);
+        impl Backend for Swapped {
+            type State = Qubit;
+            fn name(&self) -> &str {
+                "swapped"
+            }
+            fn num_qubits(&self) -> usize {
+                self.0.num_qubits()
+            }
+            fn apply_gate(&mut self, gate: &Gate, targets: &[usize]) -> Result<(), QuantumError> {
+                let reversed: Vec<usize> = targets.iter().rev().copied().collect();
+                self.0.apply_gate(gate, &reversed)
+            }
+            fn measure_with_rng(&mut self, qubit: usize, rng: &mut dyn RngCore) -> usize {
+                self.0.measure_with_rng(qubit, rng)
+            }
+            fn expectation(&self, observable: &Gate, targets: &[usize]) -> f64 {
+                self.0.expectation(observable, targets)
+            }
+            fn state(&self) -> Qubit {
+                self.0.state()
+            }
+        }
+        let swapped = conformance::run(|num_qubits| Swapped(CpuBackend::new(num_qubits)));
+        assert!(!swapped.passed());
+        let failed: Vec<&str> = swapped
+            .failures()
+            .map(|result| result.name.as_str())
+            .collect();
+        assert!(failed.contains(&"reversed_cnot"));
+        assert!(failed.contains(&"bell"));
+        assert!(!failed.contains(&"hadamard"));
+        assert!(swapped.to_string().starts_with("swapped: "));
+
+        #[cfg(feature = "gpu")]
+        if quantum_simulator::gpu::GpuBackend::new(1).is_ok() {
+            let gpu = conformance::run(|num_qubits| {
+                quantum_simulator::gpu::GpuBackend::new(num_qubits).unwrap()
+            });
+            assert!(gpu.passed(), "{}", gpu);
+        }
+    }
+
+    #[test]
+    fn test_parameter_shift_gradient_matches_finite_differences() {
+        let theta = Parameter::new("theta");
+        let gamma = Parameter::new("gamma");
+        let mut circuit = Circuit::new(2);
+        circuit
+            .ry(0, theta.clone())
+            .rx(1, gamma.clone() * 2.0)
+            .cnot(0, 1)
+            .rz(1, -theta)
+            .phase(0, gamma)
+            .h(0);
+        let hamiltonian = Hamiltonian::parse("Z0 Z1 + 0.5 X1 - 0.3 Y0 Z1").unwrap();
+        let params = HashMap::from([("gamma".to_string(), 0.4), ("theta".to_string(), -1.1)]);
+
+        let energy = |params: &HashMap<String, f64>| {
+            let bound = circuit.bind_parameters(params).unwrap();
+            let mut qubit = Qubit::zero_state(2);
+            bound.run(&mut qubit);
+            hamiltonian.expectation(&qubit)
+        };
+        let gradient = Simulator::gradient(&circuit, &hamiltonian, &params);
+        assert_eq!(circuit.parameters(), vec!["gamma", "theta"]);
+        assert_eq!(gradient.len(), 2);
+        for (name, derivative) in circuit.parameters().iter().zip(&gradient) {
+            let step = 1e-6;
+            let mut plus = params.clone();
+            *plus.get_mut(name).unwrap() += step;
+            let mut minus = params.clone();
+            *minus.get_mut(name).unwrap() -= step;
+            let estimate = (energy(&plus) - energy(&minus)) / (2.0 * step);
+            assert!(
+                (derivative - estimate).abs() < 1e-6,
+                "{}: {} vs {}",
+                name,
+                derivative,
+                estimate
+            );
+        }
+    }
+
+    #[test]
+    fn test_external_backends_normalize_counts() {
+        use quantum_simulator::external::{CommandBackend, ExternalBackend, HttpBackend};
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+
+        // A fake provider that answers with a fixed Qiskit-style histogram and echoes the request
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            let mut headers = vec![];
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                headers.push(line);
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let reply = r#"{"counts": {"0x0": 60, "0x3": 40}}"#;
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                reply.len(),
+                reply
+            )
+            .unwrap();
+            (headers, String::from_utf8(body).unwrap())
+        });
+
+        let backend = HttpBackend::new(&format!("http://127.0.0.1:{}/run", port))
+            .unwrap()
+            .header("Authorization", "Bearer secret");
+        let counts = backend.submit(&circuit, 100).unwrap();
+        assert_eq!(counts.get("00"), 60);
+        assert_eq!(counts.get("11"), 40);
+        assert_eq!(counts.shots(), 100);
+
+        let (headers, body) = server.join().unwrap();
+        assert!(headers[0].starts_with("POST /run HTTP/1.1"));
+        assert!(headers
+            .iter()
+            .any(|line| line.starts_with("Authorization: Bearer secret")));
+        let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(request["shots"], 100);
+        // Circuits without a classical register are measured on every qubit before submission
+        let submitted = qasm::parse(request["qasm"].as_str().unwrap()).unwrap();
+        assert_eq!(submitted.num_clbits(), 2);
+
+        #[cfg(unix)]
+        {
+            let echo = CommandBackend::new("sh")
+                .arg("-c")
+                .arg(r#"grep -q 'cx q\[0\], q\[1\]' && echo '{"01 1": {shots}}'"#);
+            let mut measured = Circuit::new(3);
+            measured.h(0).cnot(0, 1).measure(0, 2);
+            let counts = echo.submit(&measured, 25).unwrap();
+            assert_eq!(counts.get("011"), 25);
+
+            let failing = CommandBackend::new("sh")
+                .arg("-c")
+                .arg("echo broken >&2; exit 3");
+            match failing.submit(&circuit, 1) {
+                Err(QuantumError::BackendUnavailable { reason }) => {
+                    assert!(reason.contains("broken"))
+                }
+                other => panic!("expected an unavailable backend, got {:?}", other),
+            }
+
+            // A program that fills its output pipe before reading a source larger than the input
+            // pipe finishes instead of deadlocking
+            let chatty = CommandBackend::new("sh").arg("-c").arg(
+                r#"head -c 200000 /dev/zero | tr '\000' ' '; echo '{"00": {shots}}'; cat > /dev/null"#,
+            );
+            let mut long = Circuit::new(2);
+            for _ in 0..20000 {
+                long.h(0);
+            }
+            assert_eq!(chatty.submit(&long, 7).unwrap().get("00"), 7);
+        }
+        assert!(matches!(
+            CommandBackend::new("/nonexistent/simulator").submit(&circuit, 1),
+            Err(QuantumError::BackendUnavailable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_vqe_finds_singlet_with_every_optimizer() {
+        use quantum_simulator::algorithms::vqe::{vqe, NelderMead, Optimizer, Spsa};
+        use quantum_simulator::variational::PauliString;
+
+        // X0 X1 + Y0 Y1 + Z0 Z1 has the singlet as its ground state, with energy -3
+        let hamiltonian = Hamiltonian::from_terms(
+            ["X0 X1", "Y0 Y1", "Z0 Z1"].map(|term| (1.0, PauliString::parse(term).unwrap())),
+        );
+        let mut ansatz = Circuit::new(2);
+        ansatz
+            .ry(0, Parameter::new("a"))
+            .ry(1, Parameter::new("b"))
+            .cnot(0, 1);
+        let optimizers: [(&dyn Optimizer, f64); 3] = [
+            (&GradientDescent::new(0.2, 500), 1e-6),
+            (&NelderMead::new(500), 1e-6),
+            (&Spsa::new(300).with_seed(7), 1e-2),
+        ];
+        for (optimizer, tolerance) in optimizers {
+            let result = vqe(&hamiltonian, &ansatz, optimizer, &[0.4, 2.0]).unwrap();
+            assert!(
+                (result.energy + 3.0).abs() < tolerance,
+                "energy {}",
+                result.energy
+            );
+            assert_eq!(result.history[0].step, 0);
+            let best = result
+                .history
+                .iter()
+                .map(|step| step.energy)
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(best, result.energy);
+
+            let bound = ansatz.bind_parameters(&result.parameters).unwrap();
+            let mut state = Qubit::zero_state(2);
+            bound.run(&mut state);
+            assert!((hamiltonian.expectation(&state) - result.energy).abs() < 1e-12);
+        }
+
+        // Gradient descent reports the same iterations through either entry point
+        let direct: Vec<Iteration> = {
+            let mut history = vec![];
+            let energy = |params: &[f64]| {
+                let bound = ansatz
+                    .bind_parameters(&HashMap::from([
+                        ("a".to_string(), params[0]),
+                        ("b".to_string(), params[1]),
+                    ]))
+                    .unwrap();
+                let mut state = Qubit::zero_state(2);
+                bound.run(&mut state);
+                hamiltonian.expectation(&state)
+            };
+            GradientDescent::new(0.2, 20).minimize(energy, &[0.4, 2.0], |iteration| {
+                history.push(iteration.clone())
+            });
+            history
+        };
+        let driven = vqe(
+            &hamiltonian,
+            &ansatz,
+            &GradientDescent::new(0.2, 20),
+            &[0.4, 2.0],
+        )
+        .unwrap()
+        .history;
+        assert_eq!(direct.len(), driven.len());
+        for (a, b) in direct.iter().zip(&driven) {
+            assert_eq!(a.step, b.step);
+            assert!((a.energy - b.energy).abs() < 1e-8);
+            assert!((a.gradient_norm - b.gradient_norm).abs() < 1e-6);
+        }
+        let simplex = vqe(&hamiltonian, &ansatz, &NelderMead::new(5), &[0.4, 2.0]).unwrap();
+        assert!(simplex
+            .history
+            .iter()
+            .all(|step| step.gradient_norm.is_nan()));
+
+        assert!(matches!(
+            vqe(&hamiltonian, &ansatz, &NelderMead::new(10), &[0.0]),
+            Err(QuantumError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+        let wide = Hamiltonian::parse("Z2").unwrap();
+        assert!(matches!(
+            vqe(&wide, &ansatz, &NelderMead::new(10), &[0.0, 0.0]),
+            Err(QuantumError::QubitOutOfRange {
+                qubit: 2,
+                num_qubits: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_job_queue_retries_polls_and_rate_limits() {
+        use quantum_simulator::counts::Counts;
+        use quantum_simulator::external::{
+            ExternalBackend, HttpBackend, Job, JobQueue, PendingJob, ProviderProfile,
+        };
+        use std::cell::{Cell, RefCell};
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+        use std::time::Instant;
+
+        // Fails its first `failures` submissions, then queues jobs that finish on the third poll
+        struct Flaky {
+            failures: usize,
+            error: QuantumError,
+            starts: RefCell<Vec<Instant>>,
+            polls: Cell<usize>,
+        }
+        impl ExternalBackend for Flaky {
+            fn name(&self) -> &str {
+                "flaky"
+            }
+            fn submit(&self, _: &Circuit, _: usize) -> Result<Counts, QuantumError> {
+                unreachable!("the queue starts jobs")
+            }
+            fn start(&self, _: &Circuit, shots: usize) -> Result<Job, QuantumError> {
+                self.starts.borrow_mut().push(Instant::now());
+                if self.starts.borrow().len() <= self.failures {
+                    return Err(self.error.clone());
+                }
+                self.polls.set(0);
+                Ok(Job::Pending(PendingJob {
+                    id: shots.to_string(),
+                    width: 1,
+                }))
+            }
+            fn poll(&self, job: &PendingJob) -> Result<Job, QuantumError> {
+                self.polls.set(self.polls.get() + 1);
+                if self.polls.get() < 3 {
+                    return Ok(Job::Pending(job.clone()));
+                }
+                let mut counts = Counts::new();
+                counts.record_many(1, job.width, job.id.parse().unwrap());
+                Ok(Job::Done(counts))
+            }
+        }
+        let flaky = |failures, error| Flaky {
+            failures,
+            error,
+            starts: RefCell::new(vec![]),
+            polls: Cell::new(0),
+        };
+        let unavailable = QuantumError::BackendUnavailable {
+            reason: "busy".to_string(),
+        };
+        let profile = ProviderProfile {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            min_interval: Duration::from_millis(15),
+            poll_interval: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+        };
+        let circuit = Circuit::new(1);
+
+        let mut queue = JobQueue::new(flaky(2, unavailable.clone()), profile);
+        queue.push(circuit.clone(), 10).push(circuit.clone(), 20);
+        assert_eq!(queue.len(), 2);
+        let results = queue.run();
+        assert!(queue.is_empty());
+        assert_eq!(results[0].as_ref().unwrap().get("1"), 10);
+        assert_eq!(results[1].as_ref().unwrap().get("1"), 20);
+        let starts = queue.backend().starts.borrow();
+        assert_eq!(starts.len(), 4);
+        assert!(starts
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] >= profile.min_interval));
+
+        // Transient failures stop after the retries run out; other errors are not retried
+        let mut queue = JobQueue::new(flaky(usize::MAX, unavailable), profile);
+        assert!(matches!(
+            queue.push(circuit.clone(), 1).run()[0],
+            Err(QuantumError::BackendUnavailable { .. })
+        ));
+        assert_eq!(queue.backend().starts.borrow().len(), 3);
+        let parse_error = QuantumError::Parse {
+            line: 1,
+            message: "bad reply".to_string(),
+        };
+        let mut queue = JobQueue::new(flaky(usize::MAX, parse_error.clone()), profile);
+        assert_eq!(queue.push(circuit.clone(), 1).run(), vec![Err(parse_error)]);
+        assert_eq!(queue.backend().starts.borrow().len(), 1);
+
+        // A provider answering with a job ID is polled at <path>/<id>
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let mut requests = vec![];
+            for reply in [
+                r#"{"job_id": "abc", "status": "QUEUED"}"#,
+                r#"{"status": "running"}"#,
+                r#"{"status": "completed", "counts": {"11": 7}}"#,
+            ] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                reader.read_exact(&mut vec![0; length]).unwrap();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    reply.len(),
+                    reply
+                )
+                .unwrap();
+                requests.push(request_line);
+            }
+            requests
+        });
+        let backend = HttpBackend::new(&format!("http://127.0.0.1:{}/jobs", port)).unwrap();
+        let mut measured = Circuit::new(2);
+        measured.h(0).cnot(0, 1);
+        let results = JobQueue::new(backend, profile).push(measured, 7).run();
+        assert_eq!(results[0].as_ref().unwrap().get("11"), 7);
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /jobs "));
+        assert!(requests[1].starts_with("GET /jobs/abc "));
+        assert!(requests[2].starts_with("GET /jobs/abc "));
+
+        // Out-of-range durations and fractional retry counts are parse errors, not panics
+        for source in [
+            "[p]\ntimeout = 1e30",
+            "[p]\nmin_interval = -1",
+            "[p]\nmax_retries = 2.5",
+            "[p]\nmax_retries = -1",
+        ] {
+            assert!(
+                matches!(
+                    ProviderProfile::parse_profiles(source),
+                    Err(QuantumError::Parse { .. })
+                ),
+                "{}",
+                source
+            );
+        }
+        let profiles =
+            ProviderProfile::parse_profiles("[p]\nmax_retries = 2\ntimeout = 2").unwrap();
+        assert_eq!(profiles["p"].max_retries, 2);
+        assert_eq!(profiles["p"].timeout, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_qaoa_approximates_max_cut() {
+        use quantum_simulator::algorithms::qaoa::{parameter_values, MaxCut};
+        use quantum_simulator::algorithms::vqe::{NelderMead, Optimizer};
+
+        // A 4-cycle is bipartite, so its maximum cut takes every edge
+        let ring =
+            MaxCut::new(4, vec![(0, 1, 1.0), (1, 2, 1.0), (2, 3, 1.0), (3, 0, 1.0)]).unwrap();
+        assert_eq!(ring.max_cut().1, 4.0);
+        assert_eq!(ring.cut_value(0b0101), 4.0);
+
+        // The parametric circuit and the cost operator agree with the direct expectation
+        let (gammas, betas) = ([0.3, 0.7], [0.2, 0.4]);
+        let circuit = ring
+            .circuit(2)
+            .bind_parameters(&parameter_values(&gammas, &betas))
+            .unwrap();
+        let mut state = Qubit::zero_state(4);
+        circuit.run(&mut state);
+        assert!(
+            (ring.cost_hamiltonian().expectation(&state) - ring.expectation(&gammas, &betas)).abs()
+                < 1e-12
+        );
+        let expected_cut: f64 = (0..16)
+            .map(|assignment| state.state[assignment].norm_sqr() * ring.cut_value(assignment))
+            .sum();
+        assert!((expected_cut - ring.expectation(&gammas, &betas)).abs() < 1e-12);
+
+        // Depth 1 reaches the known 3/4 approximation ratio on a ring
+        let best = NelderMead::new(300).minimize(
+            &|angles: &[f64]| -ring.expectation(&angles[..1], &angles[1..]),
+            &[0.5, 0.3],
+            &mut |_| {},
+        );
+        assert!((-best.energy - 3.0).abs() < 1e-6, "{}", best.energy);
+
+        Simulator::set_seed(1289);
+        let counts = ring.sample(&best.params[..1], &best.params[1..], 200);
+        Simulator::clear_seed();
+        let (bitstring, value) = ring.best_cut(&counts).unwrap();
+        assert_eq!(value, 4.0);
+        assert!(bitstring == "0101" || bitstring == "1010");
+    }
+
+    #[test]
+    fn test_experiment_report_combines_run_results() {
+        use quantum_simulator::noise::{depolarizing, NoiseModel};
+        use quantum_simulator::report::{ExperimentReport, ReportFormat};
+        use std::collections::BTreeMap;
+        use std::path::Path;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+        let mut state = Qubit::zero_state(2);
+        circuit.run(&mut state);
+        let counts = state.sample(200);
+        let ideal = BTreeMap::from([("00".to_string(), 0.5), ("11".to_string(), 0.5)]);
+        let mut noise = NoiseModel::new();
+        noise.add_error(depolarizing(0.01));
+
+        let report = ExperimentReport::new("Bell <pair>", &circuit)
+            .noise(&noise)
+            .counts(&counts)
+            .reference(ideal)
+            .metric("state fidelity", 1.0)
+            .timing("sampling", Duration::from_micros(1500));
+
+        let html = report.render(ReportFormat::from_path(Path::new("run.html")));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>Bell &lt;pair&gt;</h1>"));
+        assert!(html.contains("<svg"));
+        assert!(html.contains("1.500 ms"));
+        assert!(html.contains("Kraus operators"));
+
+        let markdown = report.render(ReportFormat::from_path(Path::new("run.md")));
+        assert!(markdown.contains("| multi-qubit gates | 1 |"));
+        assert!(markdown.contains("| state fidelity | 1.000000 |"));
+        // Only 00 and 11 are ever sampled, so the measured distribution is close to the ideal one
+        let fidelity_line = markdown
+            .lines()
+            .find(|line| line.starts_with("| Hellinger fidelity"))
+            .unwrap();
+        let fidelity: f64 = fidelity_line
+            .split('|')
+            .nth(2)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        assert!(fidelity > 0.98, "{}", fidelity);
+    }
+
+    #[test]
+    fn test_verify_circuit_against_full_matrices() {
+        use quantum_simulator::gates::u3;
+        use quantum_simulator::verify::verify_circuit;
+
+        let mut circuit = Circuit::new(4);
+        circuit
+            .h(0)
+            .cnot(0, 3)
+            .ry(1, 0.3)
+            .rz(1, 0.2)
+            .cz(3, 1)
+            .iswap(2, 0);
+        circuit
+            .add_instruction(Instruction::new(
+                Operation::Unitary(u3(0.4, 1.1, -0.6).multi_controlled(&[0, 1], 2, 3).unwrap()),
+                vec![3, 0, 1],
+            ))
+            .unwrap();
+        circuit.snapshot("middle").toffoli(2, 3, 0).phase(2, 0.9);
+
+        let report = verify_circuit(&circuit, 10, 1e-12).unwrap();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.states, 10);
+        assert!(report.max_error < 1e-12);
+        assert!(report.to_string().starts_with("10 of 10 states"));
+
+        circuit.measure(0, 0);
+        assert!(matches!(
+            verify_circuit(&circuit, 1, 1e-12),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        let mut parametric = Circuit::new(1);
+        parametric.rx(0, Parameter::new("theta"));
+        assert!(matches!(
+            verify_circuit(&parametric, 1, 1e-12),
+            Err(QuantumError::UnboundParameter { .. })
+        ));
+    }
+
+    #[test]
+    fn test_fidelity_and_distance_utilities() {
+        use quantum_simulator::analysis::{gate_fidelity, state_fidelity, trace_distance};
+        use quantum_simulator::density::DensityMatrix;
+        use quantum_simulator::noise::{depolarizing, NoiseModel};
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1).t(1).h(1);
+
+        // An optimized circuit computes the same state and the same unitary
+        let (optimized, _) = circuit.optimized();
+        let mut ideal = Qubit::zero_state(2);
+        circuit.run(&mut ideal);
+        let mut rewritten = Qubit::zero_state(2);
+        optimized.run(&mut rewritten);
+        assert!((state_fidelity(&ideal, &rewritten) - 1.0).abs() < 1e-9);
+        let unitary = |circuit: &Circuit| {
+            let columns: Vec<Vec<Complex<f64>>> = (0..4)
+                .map(|basis| {
+                    let mut state = vec![Complex::new(0.0, 0.0); 4];
+                    state[basis] = Complex::new(1.0, 0.0);
+                    let mut qubit = Qubit::from_state(state);
+                    circuit.run(&mut qubit);
+                    qubit.state
+                })
+                .collect();
+            quantum_simulator::gates::Gate::new(
+                (0..4)
+                    .map(|row| columns.iter().map(|column| column[row]).collect())
+                    .collect(),
+            )
+        };
+        assert!((gate_fidelity(&unitary(&circuit), &unitary(&optimized)) - 1.0).abs() < 1e-9);
+        let mut tilted = circuit.clone();
+        tilted.rz(0, 0.2);
+        assert!(gate_fidelity(&unitary(&circuit), &unitary(&tilted)) < 1.0 - 1e-3);
+
+        // Noise moves the output away from the ideal state, more so the stronger it is
+        let pure = DensityMatrix::from_state(&ideal);
+        let distance = |p: f64| {
+            let mut model = NoiseModel::new();
+            model.add_error(depolarizing(p));
+            trace_distance(
+                &pure,
+                &Simulator::run_density_with_noise(&circuit, &DensityMatrix::new(2), &model),
+            )
+        };
+        assert!(distance(0.0) < 1e-9);
+        assert!(0.0 < distance(0.01) && distance(0.01) < distance(0.1));
+        assert!(distance(0.1) <= 1.0);
+    }
+
+    #[test]
+    fn test_tomography_reconstructs_noisy_states() {
+        use quantum_simulator::analysis::trace_distance;
+        use quantum_simulator::density::DensityMatrix;
+        use quantum_simulator::noise::{depolarizing, NoiseModel};
+        use quantum_simulator::tomography::{collect_counts_with_rng, reconstruct, Estimator};
+
+        let mut circuit = Circuit::new(3);
+        circuit.h(0).cnot(0, 2).ry(1, 0.9).rz(2, 0.4);
+        let mut model = NoiseModel::new();
+        model.add_error(depolarizing(0.05));
+        let mut rng = StdRng::seed_from_u64(1291);
+
+        // The reconstruction of the sampled noisy state matches the exact noisy density matrix
+        let exact = Simulator::run_density_with_noise(&circuit, &DensityMatrix::new(3), &model);
+        for qubits in [vec![1], vec![2, 0]] {
+            let counts =
+                collect_counts_with_rng(&circuit, &qubits, &model, 3000, &mut rng).unwrap();
+            let expected = exact.partial_trace(&qubits);
+            for estimator in [Estimator::LinearInversion, Estimator::MaximumLikelihood] {
+                let rho = reconstruct(&counts, estimator).unwrap();
+                assert!((rho.trace() - 1.0).abs() < 1e-9);
+                // The basis rotations are noisy too, so allow for their extra depolarization
+                assert!(
+                    trace_distance(&rho, &expected) < 0.1,
+                    "{:?} {:?}",
+                    qubits,
+                    estimator
+                );
+            }
+        }
+
+        // Maximum likelihood turns a noisy estimate of a pure state into a physical one
+        let mut pure = Circuit::new(2);
+        pure.h(0).cnot(0, 1);
+        let counts =
+            collect_counts_with_rng(&pure, &[0, 1], &NoiseModel::new(), 200, &mut rng).unwrap();
+        let rho = reconstruct(&counts, Estimator::MaximumLikelihood).unwrap();
+        let mut bell = Qubit::zero_state(2);
+        pure.run(&mut bell);
+        assert!(rho.purity() <= 1.0 + 1e-9);
+        assert!(rho.fidelity(&DensityMatrix::from_state(&bell)) > 0.9);
+
+        let mut partial = counts.clone();
+        partial.retain(|setting, _| setting.starts_with('Z'));
+        assert!(matches!(
+            reconstruct(&partial, Estimator::LinearInversion),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compact_qubits_preserves_measurement_statistics() {
+        use quantum_simulator::analysis::{compact_qubits, qubit_lifetimes};
+        use quantum_simulator::noise::NoiseModel;
+
+        // A chain that hands a qubit on, measures it and moves on, with one qubit reset and reused
+        let mut circuit = Circuit::new(5);
+        circuit.ry(0, 1.0).cnot(0, 1).measure(0, 0);
+        circuit.ry(1, 0.6).cnot(1, 2).measure(1, 1);
+        circuit.h(2).cnot(2, 3).measure(2, 2);
+        circuit.reset(3).x(3).measure(3, 3);
+        circuit.h(4).measure(4, 4);
+
+        let lifetimes = qubit_lifetimes(&circuit);
+        assert_eq!(lifetimes[0].unwrap().last, 2);
+        assert_eq!(lifetimes[3].unwrap().first, 7);
+
+        let compacted = compact_qubits(&circuit);
+        assert_eq!(compacted.circuit.num_qubits(), 2);
+        assert!(compacted.qubits.iter().all(|qubit| qubit.is_some()));
+
+        let sample = |circuit: &Circuit, seed: u64| {
+            let initial_state = Qubit::zero_state(circuit.num_qubits()).state;
+            let mut rng = StdRng::seed_from_u64(seed);
+            Simulator::sample_trajectories_with_rng(
+                circuit,
+                &initial_state,
+                &NoiseModel::new(),
+                4000,
+                &mut rng,
+            )
+            .frequencies()
+        };
+        let original = sample(&circuit, 1);
+        let rewritten = sample(&compacted.circuit, 2);
+        for outcome in original.keys().chain(rewritten.keys()) {
+            let p = original.get(outcome).copied().unwrap_or(0.0);
+            let q = rewritten.get(outcome).copied().unwrap_or(0.0);
+            assert!((p - q).abs() < 0.04, "{}: {} vs {}", outcome, p, q);
+        }
+    }
+
+    #[test]
+    fn test_compressor_shortens_deep_two_qubit_circuit() {
+        use quantum_simulator::compression::Compressor;
+
+        // A long, redundant machine-generated circuit on two qubits
+        let mut rng = StdRng::seed_from_u64(1292);
+        let mut circuit = Circuit::new(2);
+        for _ in 0..15 {
+            let qubit = rng.gen_range(0..2);
+            circuit
+                .rx(qubit, rng.gen_range(-1.0..1.0))
+                .rz(1 - qubit, rng.gen_range(-1.0..1.0))
+                .cnot(qubit, 1 - qubit)
+                .h(qubit);
+        }
+
+        let compression = Compressor::new(0.999)
+            .with_seed(3)
+            .compress(&circuit)
+            .unwrap();
+        assert!(compression.layers.is_some());
+        assert!(compression.fidelity >= 0.999, "{}", compression.fidelity);
+        assert!(compression.circuit.instructions().len() < circuit.instructions().len() / 2);
+
+        // Measurements have no unitary to approximate
+        circuit.measure(0, 0);
+        assert!(matches!(
+            Compressor::new(0.999).compress(&circuit),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_randomized_benchmarking_recovers_depolarizing_rate() {
+        use quantum_simulator::backend::Backend;
+        use quantum_simulator::benchmarking::{
+            random_clifford_circuit, random_universal_circuit, randomized_benchmarking,
+        };
+        use quantum_simulator::noise::{depolarizing, NoiseModel};
+        use quantum_simulator::stabilizer::StabilizerBackend;
+
+        // Depolarizing each H and S with probability p shrinks the Bloch vector by 1 - p per gate
+        let p = 0.004;
+        let mut model = NoiseModel::new();
+        model.add_error(depolarizing(p));
+        let rb = randomized_benchmarking(&model, &[1, 10, 25, 50, 100, 200], 30, 1292);
+        assert!(rb.survival.windows(2).all(|pair| pair[1] < pair[0]));
+        assert!((rb.offset - 0.5).abs() < 0.05, "{}", rb.offset);
+        let expected = rb.gates_per_clifford * p / 2.0;
+        assert!(
+            (rb.error_per_clifford - expected).abs() < 0.2 * expected,
+            "{} vs {}",
+            rb.error_per_clifford,
+            expected
+        );
+
+        // Random circuits are reproducible, and the Clifford ones run on the stabilizer backend
+        let clifford = random_clifford_circuit(40, 30, 5);
+        assert_eq!(clifford, random_clifford_circuit(40, 30, 5));
+        assert_ne!(clifford, random_clifford_circuit(40, 30, 6));
+        assert!(StabilizerBackend::new(40).run(&clifford).is_ok());
+        let universal = random_universal_circuit(5, 12, 5);
+        let mut state = Qubit::zero_state(5);
+        universal.run(&mut state);
+        let norm: f64 = state.state.iter().map(|a| a.norm_sqr()).sum();
+        assert!((norm - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_measurement_in_arbitrary_bases() {
+        use quantum_simulator::qubit::Basis;
+
+        let mut rng = StdRng::seed_from_u64(11);
+
+        // |+i⟩ is certain in the Y basis and random in X
+        let mut plus_i = Qubit::zero_state(1);
+        let mut circuit = Circuit::new(1);
+        circuit.h(0).s(0);
+        circuit.run(&mut plus_i);
+        let prepared = plus_i.clone();
+        for _ in 0..20 {
+            assert_eq!(plus_i.measure_in_basis_with_rng(0, Basis::Y, &mut rng), 0);
+        }
+        assert!((plus_i.state[1] - prepared.state[1]).norm() < 1e-12);
+        let ones: usize = (0..400)
+            .map(|_| {
+                prepared
+                    .clone()
+                    .measure_in_basis_with_rng(0, Basis::X, &mut rng)
+            })
+            .sum();
+        assert!((150..250).contains(&ones));
+
+        // A Bell pair's Y outcomes are anticorrelated in a circuit
+        let mut bell = Circuit::new(2);
+        bell.h(0)
+            .cnot(0, 1)
+            .measure_in_basis(0, 0, Basis::Y)
+            .measure_in_basis(1, 1, Basis::Y);
+        for _ in 0..20 {
+            let bits = bell.run_with_rng(&mut Qubit::zero_state(2), &mut rng);
+            assert_ne!(bits[0], bits[1]);
+        }
+
+        // Measuring Y leaves an eigenstate and estimating it from shots approaches ⟨Y⟩
+        let mut state = Qubit::zero_state(1);
+        let mut tilt = Circuit::new(1);
+        tilt.rx(0, 0.6);
+        tilt.run(&mut state);
+        let expected = -0.6_f64.sin();
+        let estimate = state
+            .estimate_observable_with_rng(&pauli_y(), &[0], 20_000, &mut rng)
+            .unwrap();
+        assert!((estimate - expected).abs() < 0.03);
+        let value = state
+            .measure_observable_with_rng(&pauli_y(), &[0], &mut rng)
+            .unwrap();
+        assert!((value.abs() - 1.0).abs() < 1e-12);
+        let after = state
+            .estimate_observable_with_rng(&pauli_y(), &[0], 100, &mut rng)
+            .unwrap();
+        assert!((after - value).abs() < 1e-12);
+
+        assert!(matches!(
+            state.measure_observable(&pauli_y(), &[1]),
+            Err(QuantumError::QubitOutOfRange { .. })
+        ));
+        let not_hermitian = Gate::new(vec![
+            vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+            vec![Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)],
+        ]);
+        assert!(matches!(
+            state.estimate_observable(&not_hermitian, &[0], 10),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_noise_adaptive_transpilation_beats_naive_placement() {
+        use quantum_simulator::transpile::{compare_placements, predicted_fidelity, Calibration};
+
+        // A ring of five qubits whose couplings around qubits 0 and 1 are poor
+        let calibration = Calibration::parse(
+            "gate_error = [0.01, 0.01, 0.001, 0.001, 0.001]\n\
+         readout_error = [0.1, 0.1, 0.01, 0.01, 0.01]\n\
+         couplings = [[0, 1, 0.2], [1, 2, 0.15], [2, 3, 0.01], [3, 4, 0.01], [4, 0, 0.1]]",
+        )
+        .unwrap();
+        let mut ghz = Circuit::new(3);
+        ghz.h(0).cnot(0, 1).cnot(1, 2).cz(0, 2);
+        for qubit in 0..3 {
+            ghz.measure(qubit, qubit);
+        }
+
+        let comparison = compare_placements(&ghz, &calibration).unwrap();
+        assert!(comparison.improvement() > 0.2, "{}", comparison);
+        let mut placed = comparison.adaptive.initial_layout.clone();
+        placed.sort();
+        assert_eq!(placed, vec![2, 3, 4]);
+
+        // Both routings respect the coupling map and keep the measured distribution
+        for transpilation in [&comparison.adaptive, &comparison.naive] {
+            assert!(
+                (predicted_fidelity(&transpilation.circuit, &calibration).unwrap()
+                    - transpilation.fidelity)
+                    .abs()
+                    < 1e-12
+            );
+            let mut state = Qubit::zero_state(5);
+            let mut rng = StdRng::seed_from_u64(3);
+            for _ in 0..20 {
+                let bits = transpilation.circuit.run_with_rng(&mut state, &mut rng);
+                assert!(bits == vec![false; 3] || bits == vec![true; 3]);
+                state = Qubit::zero_state(5);
+            }
+        }
+
+        let mut toffoli = Circuit::new(3);
+        toffoli.toffoli(0, 1, 2);
+        assert!(matches!(
+            compare_placements(&toffoli, &calibration),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_chsh_violation_from_exact_and_sampled_correlations() {
+        use quantum_simulator::algorithms::{chsh, chsh_value};
+        use quantum_simulator::analysis::{
+            correlation, correlation_from_counts, sample_correlation_with_rng,
+        };
+        use quantum_simulator::noise::NoiseModel;
+
+        let mut bell = Qubit::zero_state(2);
+        Circuit::new(2).h(0).cnot(0, 1).run(&mut bell);
+        let exact = chsh_value(|a, b| correlation(&bell, (0, 1), a, b));
+        assert!((exact - 2.0 * 2.0_f64.sqrt()).abs() < 1e-12);
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let sampled =
+            chsh_value(|a, b| sample_correlation_with_rng(&bell, (0, 1), a, b, 4000, &mut rng));
+        assert!((sampled - exact).abs() < 0.15);
+
+        let initial_state = Qubit::zero_state(2).state;
+        let from_circuit = chsh_value(|a, b| {
+            let counts = Simulator::sample_trajectories_with_rng(
+                &chsh(a, b),
+                &initial_state,
+                &NoiseModel::new(),
+                4000,
+                &mut rng,
+            );
+            correlation_from_counts(&counts, (0, 1))
+        });
+        assert!(from_circuit > 2.5);
+
+        // A product state stays within the classical bound
+        let product = Qubit::zero_state(2);
+        assert!(chsh_value(|a, b| correlation(&product, (0, 1), a, b)).abs() <= 2.0 + 1e-12);
+    }
+
+    #[test]
+    fn test_qubit_ordering_conversions_round_trip() {
+        use quantum_simulator::ordering::QubitOrdering;
+
+        let big = QubitOrdering::BigEndian;
+        for index in 0..16 {
+            assert_eq!(big.convert_index(big.convert_index(index, 4), 4), index);
+            let bitstring = big.bitstring(index, 4);
+            assert_eq!(big.parse_bitstring(&bitstring).unwrap(), index);
+            // Big-endian bitstrings read qubit 0 first
+            assert_eq!(bitstring.starts_with('1'), index & 1 == 1);
+        }
+
+        // A state in big-endian order runs the same circuit as its little-endian counterpart
+        let mut circuit = Circuit::new(3);
+        circuit.h(0).cnot(0, 2).ry(1, 0.4);
+        let initial = Qubit::random_state(3, 9);
+        let little = Simulator::run(&circuit, &initial.state);
+        let ordered = Simulator::run_ordered(&circuit, &big.convert_state(&initial.state), big);
+        for (index, amplitude) in ordered.state.iter().enumerate() {
+            assert!((amplitude - little.state[big.convert_index(index, 3)]).norm() < 1e-12);
+        }
+
+        let counts = little.sample_with_rng(500, &mut StdRng::seed_from_u64(2));
+        let converted = big.convert_counts(&counts);
+        assert_eq!(converted.shots(), 500);
+        for (bitstring, count) in counts.iter() {
+            let reversed: String = bitstring.chars().rev().collect();
+            assert_eq!(converted.get(&reversed), count);
+        }
+        assert_eq!(QubitOrdering::LittleEndian.convert_counts(&counts), counts);
+    }
+
+    #[test]
+    fn test_cycle_benchmarking_pauli_fidelities() {
+        use quantum_simulator::benchmarking::cycle_benchmarking;
+        use quantum_simulator::noise::{depolarizing, NoiseModel};
+        use quantum_simulator::variational::PauliString;
+
+        let mut cycle = Circuit::new(2);
+        cycle.h(0).cnot(0, 1);
+
+        // Without noise every Pauli fidelity is one
+        let ideal = cycle_benchmarking(&cycle, &NoiseModel::new(), &[1, 2, 4], 2, 5).unwrap();
+        assert_eq!(ideal.decays.len(), 15);
+        assert!(ideal.decays.iter().all(|decay| decay.fidelity == 1.0));
+        assert_eq!(ideal.process_infidelity(), 0.0);
+
+        // Depolarizing noise after every gate shrinks every Pauli fidelity
+        let mut model = NoiseModel::new();
+        model.add_error(depolarizing(0.03));
+        let cb = cycle_benchmarking(&cycle, &model, &[1, 2, 4, 8], 3, 5).unwrap();
+        for decay in &cb.decays {
+            assert!(decay.fidelity > 0.8 && decay.fidelity < 1.0);
+            assert!((decay.amplitude - 1.0).abs() < 0.1);
+        }
+        assert!(cb.process_infidelity() > 0.01 && cb.process_infidelity() < 0.2);
+        assert!(cb.fidelity_of(&PauliString::parse("Z0").unwrap()).is_some());
+        assert!(cb.fidelity_of(&PauliString::parse("X2").unwrap()).is_none());
+
+        // T is not a Clifford, and wide cycles are refused
+        let mut t = Circuit::new(1);
+        t.t(0);
+        assert!(matches!(
+            cycle_benchmarking(&t, &model, &[1, 2], 1, 0),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            cycle_benchmarking(&Circuit::new(4), &model, &[1, 2], 1, 0),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_result_export_json_and_csv() {
+        use quantum_simulator::report::ResultExport;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+        let mut state = Qubit::zero_state(2);
+        circuit.run(&mut state);
+        let counts = state.sample_with_rng(200, &mut StdRng::seed_from_u64(4));
+        let export = ResultExport::new(&state).counts(&counts);
+
+        let dir = std::env::temp_dir().join("quantum_simulator_export_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("bell.json");
+        let csv_path = dir.join("bell.csv");
+        export.save(&json_path).unwrap();
+        export.save(&csv_path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json["num_qubits"], 2);
+        assert_eq!(json["amplitudes"].as_array().unwrap().len(), 4);
+        assert!((json["probabilities"]["11"].as_f64().unwrap() - 0.5).abs() < 1e-12);
+        assert!(json["bloch_vectors"][1][2].as_f64().unwrap().abs() < 1e-12);
+        let shots: u64 = json["counts"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|count| count.as_u64().unwrap())
+            .sum();
+        assert_eq!(shots, 200);
+
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        let p00: f64 = csv
+            .lines()
+            .find_map(|line| line.strip_prefix("probability,00,"))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((p00 - 0.5).abs() < 1e-12);
+        assert!(csv.contains("bloch_z,0,"));
+        assert_eq!(
+            csv.lines()
+                .filter(|line| line.starts_with("count,"))
+                .count(),
+            counts.iter().count()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_classical_shadow_fidelity_estimation() {
+        use quantum_simulator::counts::Counts;
+        use quantum_simulator::density::DensityMatrix;
+        use quantum_simulator::noise::{depolarizing, NoiseModel};
+        use quantum_simulator::qubit::Basis;
+        use quantum_simulator::shadows::ClassicalShadow;
+        use quantum_simulator::variational::PauliString;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1);
+        let target = Simulator::run(&circuit, &Qubit::zero_state(2).state);
+        let mut rng = StdRng::seed_from_u64(12);
+
+        // A perfect preparation has fidelity one and the Bell stabilizers at one
+        let shadow = ClassicalShadow::collect_with_rng(&target, 400, 5, &mut rng);
+        assert_eq!(shadow.num_settings(), 400);
+        let fidelity = shadow.fidelity(&target).unwrap();
+        assert!((fidelity.value - 1.0).abs() < 4.0 * fidelity.standard_error);
+        assert!(fidelity.standard_error < 0.1);
+        let xx = shadow.expectation(&PauliString::parse("X0 X1").unwrap());
+        assert!((xx.value - 1.0).abs() < 4.0 * xx.standard_error);
+
+        // A depolarized preparation is told apart from the target
+        let mut noise = NoiseModel::new();
+        noise.add_error(depolarizing(0.1));
+        let noisy = Simulator::run_density_with_noise(&circuit, &DensityMatrix::new(2), &noise);
+        let exact = noisy.fidelity(&DensityMatrix::from_state(&target));
+        let shadow = ClassicalShadow::collect_density_with_rng(&noisy, 400, 5, &mut rng);
+        let estimate = shadow.fidelity(&target).unwrap();
+        assert!((estimate.value - exact).abs() < 4.0 * estimate.standard_error);
+        assert!(estimate.value < 0.95);
+
+        // Counts from an external setting are added as one more setting
+        let mut external = ClassicalShadow::new(2);
+        let mut counts = Counts::new();
+        counts.record_many(0b00, 2, 3);
+        counts.record_many(0b11, 2, 2);
+        external.add_counts(&[Basis::Z, Basis::Z], &counts).unwrap();
+        assert_eq!(external.num_settings(), 1);
+        assert_eq!(external.snapshots().len(), 5);
+        assert_eq!(
+            external
+                .expectation(&PauliString::parse("Z0 Z1").unwrap())
+                .value,
+            9.0
+        );
+        assert!(matches!(
+            external.add_counts(&[Basis::Z], &counts),
+            Err(QuantumError::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            external.fidelity(&Qubit::zero_state(3)),
+            Err(QuantumError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cost_estimates_scale_with_width_and_depth() {
+        use quantum_simulator::cost::{estimate_all, estimate_cost, DeviceProfile, Engine};
+        use quantum_simulator::parameter::Parameter;
+
+        let device = DeviceProfile::desktop();
+        let layer = |num_qubits: usize, layers: usize| {
+            let mut circuit = Circuit::new(num_qubits);
+            for _ in 0..layers {
+                for qubit in 0..num_qubits {
+                    circuit.h(qubit);
+                }
+            }
+            circuit.measure(0, 0);
+            circuit
+        };
+
+        // Memory doubles per qubit for state vectors and quadruples for density matrices
+        let (small, large) = (layer(8, 1), layer(9, 1));
+        for (engine, factor) in [(Engine::StateVector, 2.0), (Engine::DensityMatrix, 4.0)] {
+            let a = estimate_cost(&small, engine, &device);
+            let b = estimate_cost(&large, engine, &device);
+            assert_eq!(b.memory_bytes, factor * a.memory_bytes);
+            assert!(b.flops > a.flops && b.seconds > a.seconds);
+        }
+
+        // Gate work grows linearly with depth, and energy follows time
+        let shallow = estimate_cost(&layer(6, 2), Engine::StateVector, &device);
+        let deep = estimate_cost(&layer(6, 4), Engine::StateVector, &device);
+        assert!(deep.flops > 1.9 * shallow.flops && deep.flops < 2.1 * shallow.flops);
+        assert!((deep.joules - deep.seconds * device.watts).abs() < 1e-15);
+
+        // Wide Clifford circuits only fit as a tableau
+        let wide = estimate_all(&layer(40, 1), &device);
+        assert_eq!(wide.len(), 4);
+        assert!(!wide[0].fits_in_memory);
+        assert!(wide[2].is_feasible(1.0));
+
+        // Unbound rotations cannot be checked for the tableau
+        let mut parametric = Circuit::new(2);
+        parametric.rx(0, Parameter::new("theta"));
+        let estimate = estimate_cost(&parametric, Engine::Stabilizer, &device);
+        assert!(estimate.unsupported.is_some());
+        assert!(estimate.to_string().contains("unsupported"));
+        assert!(estimate_cost(&parametric, Engine::StateVector, &device).is_feasible(1.0));
+    }
+
+    #[test]
+    fn test_run_circuit_json_facade() {
+        use quantum_simulator::wasm::run_circuit_json;
+
+        let bell = r#"{
+        "qasm": "OPENQASM 2.0; include \"qelib1.inc\"; qreg q[2]; h q[0]; cx q[0], q[1];",
+        "shots": 500,
+        "seed": 11
+    }"#;
+        let response: serde_json::Value = serde_json::from_str(&run_circuit_json(bell)).unwrap();
+        assert_eq!(response["num_qubits"], 2);
+        let counts = response["counts"].as_object().unwrap();
+        let total: u64 = counts.values().map(|count| count.as_u64().unwrap()).sum();
+        assert_eq!(total, 500);
+        assert!(counts
+            .keys()
+            .all(|bitstring| bitstring == "00" || bitstring == "11"));
+        // Seeded requests are reproducible
+        assert_eq!(run_circuit_json(bell), run_circuit_json(bell));
+
+        let noisy = r#"{
+        "qasm": "OPENQASM 2.0; include \"qelib1.inc\"; qreg q[2]; h q[0]; cx q[0], q[1];",
+        "shots": 500,
+        "seed": 11,
+        "depolarizing": 0.3
+    }"#;
+        let response: serde_json::Value = serde_json::from_str(&run_circuit_json(noisy)).unwrap();
+        let counts = response["counts"].as_object().unwrap();
+        assert!(counts.contains_key("01") || counts.contains_key("10"));
+
+        for request in [
+            "not json",
+            r#"{"shots": 10}"#,
+            r#"{"qasm": "qreg q[1]; bogus q[0];"}"#,
+            r#"{"qasm": "OPENQASM 2.0; qreg q[1];", "shots": -1}"#,
+            r#"{"qasm": "OPENQASM 2.0; qreg q[1];", "depolarizing": 2.0}"#,
+            r#"{"qasm": "OPENQASM 2.0; qreg q[40];"}"#,
+            r#"{"qasm": "OPENQASM 2.0; qreg q[64];"}"#,
+        ] {
+            let response: serde_json::Value =
+                serde_json::from_str(&run_circuit_json(request)).unwrap();
+            assert!(response["error"].is_string(), "{request}");
+        }
+    }
+
+    #[test]
+    fn test_fixed_register_matches_state_vector() {
+        use quantum_simulator::fixed::{self, FixedRegister};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut register = FixedRegister::<8>::new();
+        register.apply(&fixed::H, 0);
+        register.cnot(0, 1);
+        register.apply(&fixed::rx(0.7), 2);
+        register.apply(&fixed::T, 1);
+        register.apply_controlled(&fixed::ry(-1.3), 2, 0);
+        register.swap(0, 2);
+        register.apply(&fixed::rz(2.9), 1);
+        register.apply(&fixed::phase(12.0), 2);
+
+        let mut circuit = Circuit::new(3);
+        circuit.add_single_qubit_gate(hadamard(1), 0);
+        circuit.add_controlled_gate(pauli_x(), 0, &[1]);
+        circuit.add_single_qubit_gate(quantum_simulator::gates::rx(0.7), 2);
+        circuit.add_single_qubit_gate(quantum_simulator::gates::t(), 1);
+        circuit.add_controlled_gate(ry(-1.3), 2, &[0]);
+        circuit.add_gate(quantum_simulator::gates::swap(0, 2, 3));
+        circuit.add_single_qubit_gate(rz(2.9), 1);
+        circuit.add_single_qubit_gate(phase(12.0), 2);
+        let expected = Simulator::run(&circuit, &Qubit::zero_state(3).state);
+
+        let actual = Qubit::from(register);
+        for (a, b) in actual.state.iter().zip(&expected.state) {
+            assert!((a - b).norm() < 1e-12, "{a} != {b}");
+        }
+
+        // Seeded measurements are reproducible and collapse the register
+        let measure = |seed| {
+            let mut copy = register;
+            let mut rng = StdRng::seed_from_u64(seed);
+            let outcomes: Vec<usize> = (0..3)
+                .map(|qubit| copy.measure_qubit_with_rng(qubit, &mut rng))
+                .collect();
+            (outcomes, copy)
+        };
+        let (outcomes, collapsed) = measure(5);
+        assert_eq!(measure(5).0, outcomes);
+        let index = outcomes
+            .iter()
+            .enumerate()
+            .map(|(k, &bit)| bit << k)
+            .sum::<usize>();
+        assert!((collapsed.probability(index) - 1.0).abs() < 1e-12);
+
+        let mut rng = StdRng::seed_from_u64(9);
+        let bell = {
+            let mut bell = FixedRegister::<4>::new();
+            bell.apply(&fixed::H, 0);
+            bell.cnot(0, 1);
+            bell
+        };
+        for _ in 0..100 {
+            assert!(matches!(bell.sample_with_rng(&mut rng), 0b00 | 0b11));
+        }
+    }
+
+    #[test]
+    fn test_f32_backend_tracks_double_precision() {
+        use quantum_simulator::backend::{Backend, CpuBackend};
+        use quantum_simulator::precision::{F32Backend, StateVector32};
+
+        let mut circuit = Circuit::new(4);
+        for layer in 0..20 {
+            for qubit in 0..4 {
+                circuit.add_single_qubit_gate(ry(0.3 + 0.1 * (layer + qubit) as f64), qubit);
+                circuit.add_single_qubit_gate(rz(0.7 * qubit as f64 - 0.2), qubit);
+            }
+            for qubit in 0..3 {
+                circuit.cnot(qubit, qubit + 1);
+            }
+        }
+
+        let mut single = F32Backend::new(4);
+        let mut double = CpuBackend::new(4);
+        single.run(&circuit).unwrap();
+        double.run(&circuit).unwrap();
+
+        let widened = single.amplitudes().unwrap();
+        let reference = double.amplitudes().unwrap();
+        let max_error = widened
+            .iter()
+            .zip(&reference)
+            .map(|(a, b)| (a - b).norm())
+            .fold(0.0, f64::max);
+        assert!(max_error < 1e-5, "{max_error}");
+        assert!(max_error > 0.0);
+        assert!(single.state().norm_error() < 1e-5);
+        assert_eq!(
+            single.state().memory_bytes() * 2,
+            reference.len() * std::mem::size_of::<Complex<f64>>()
+        );
+        let zz = single.expectation(&pauli_z(), &[3]);
+        assert!((zz - double.expectation(&pauli_z(), &[3])).abs() < 1e-5);
+
+        // Round trips through double precision are lossless in that direction
+        let state = single.state();
+        assert_eq!(StateVector32::from_qubit(&state.to_qubit()), state);
+        assert!(single.apply_gate(&pauli_z(), &[4]).is_err());
+
+        // Measuring a qubit outside the register panics in both precisions
+        let measured = std::panic::catch_unwind(|| {
+            state
+                .clone()
+                .measure_qubit_with_rng(4, &mut StdRng::seed_from_u64(1))
+        });
+        assert!(measured.is_err());
+        let measured = std::panic::catch_unwind(|| {
+            state
+                .to_qubit()
+                .measure_qubit_with_rng(4, &mut StdRng::seed_from_u64(1))
+        });
+        assert!(measured.is_err());
+    }
+
+    #[test]
+    fn test_debugger_breakpoints_and_time_travel() {
+        use quantum_simulator::debugger::{Breakpoint, Comparison, Debugger, Stop};
+
+        assert_eq!(
+            "#3".parse::<Breakpoint>().unwrap(),
+            Breakpoint::Instruction(3)
+        );
+        assert_eq!("q1".parse::<Breakpoint>().unwrap(), Breakpoint::Qubit(1));
+        assert_eq!(
+            "P(|11⟩) >= 0.25".parse::<Breakpoint>().unwrap(),
+            Breakpoint::Probability {
+                bitstring: "11".to_string(),
+                comparison: Comparison::GreaterOrEqual,
+                threshold: 0.25,
+            }
+        );
+        for invalid in ["", "q", "P(12) > 0.1", "P(11) = 0.1", "P(11 > 0.1", "step"] {
+            assert!(invalid.parse::<Breakpoint>().is_err(), "{invalid}");
+        }
+
+        let mut circuit = Circuit::new(3);
+        circuit.h(0).cnot(0, 1).measure(0, 0).x(2).cnot(1, 2);
+        let initial_state = Qubit::zero_state(3).state;
+        let mut debugger = Debugger::new(&circuit, &initial_state, &NoiseModel::new(), 4);
+        assert_eq!(debugger.len(), 5);
+
+        // Stepping past the end or the start is a no-op
+        debugger.goto(99);
+        assert_eq!(debugger.position(), 5);
+        assert!(!debugger.step());
+        debugger.goto(0);
+        assert!(!debugger.step_back());
+
+        let qubit_two = debugger.add_breakpoint(Breakpoint::Qubit(2)).unwrap();
+        let entangled = debugger
+            .add_breakpoint("P(011) > 0.4".parse().unwrap())
+            .unwrap();
+        // A bitstring of the wrong width could never fire, so it is rejected up front
+        assert_eq!(
+            debugger.add_breakpoint("P(11) > 0.4".parse().unwrap()),
+            Err(QuantumError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(debugger.breakpoints().len(), 2);
+        assert_eq!(debugger.resume(), Stop::Breakpoint(entangled));
+        assert_eq!(debugger.position(), 2);
+        assert_eq!(debugger.events().count(), 2);
+
+        // The measurement collapses the pair; whichever outcome was recorded is kept when travelling
+        assert_eq!(debugger.resume(), Stop::Breakpoint(qubit_two));
+        assert_eq!(debugger.position(), 3);
+        assert_eq!(debugger.next_instruction().unwrap().qubits, vec![2]);
+        let outcome = debugger.state().get(0b011).norm_sqr();
+        assert!(outcome < 1e-12 || (outcome - 1.0).abs() < 1e-12);
+        debugger.goto(5);
+        debugger.goto(3);
+        assert_eq!(debugger.state().get(0b011).norm_sqr(), outcome);
+
+        assert_eq!(debugger.reverse(), Stop::Breakpoint(entangled));
+        assert_eq!(debugger.position(), 2);
+        assert_eq!(
+            debugger.remove_breakpoint(entangled),
+            Some("P(|011⟩) > 0.4".parse().unwrap())
+        );
+        assert_eq!(debugger.reverse(), Stop::Boundary);
+        assert_eq!(debugger.position(), 0);
+        assert_eq!(debugger.events().count(), 0);
+        assert_eq!(debugger.event_log().len(), 5);
+
+        // The same seed records the same run
+        let replay = Debugger::new(&circuit, &initial_state, &NoiseModel::new(), 4);
+        assert_eq!(replay.event_log(), debugger.event_log());
+    }
+
+    #[test]
+    fn test_circuit_assertions_checked_during_simulation() {
+        let amplitude = Complex::new(std::f64::consts::FRAC_1_SQRT_2, 0.0);
+        let zero = Complex::new(0.0, 0.0);
+        let mut bell = Circuit::new(2);
+        bell.h(0)
+            .cnot(0, 1)
+            .assert_state(&[amplitude, zero, zero, amplitude], 1e-9)
+            .assert_prob("11", 0.5, 1e-9)
+            .assert_entangled(0, 1)
+            .measure(0, 0)
+            .measure(1, 1);
+        assert!(bell.try_run(&mut Qubit::zero_state(2)).is_ok());
+        let zero_state = Qubit::zero_state(2).state;
+        let counts = Simulator::sample(&bell, &zero_state, 64);
+        assert_eq!(counts.shots(), 64);
+
+        let qasm = qasm::to_qasm(&bell, QasmVersion::V2).unwrap();
+        assert!(!qasm.contains("assert"));
+        assert_eq!(qasm::parse(&qasm).unwrap().instructions().len(), 4);
+
+        let mut product = Circuit::new(2);
+        product.h(0).h(1).assert_entangled(0, 1);
+        assert!(matches!(
+            product.try_run(&mut Qubit::zero_state(2)),
+            Err(QuantumError::AssertionFailed { instruction: 2, .. })
+        ));
+        let panicked = std::panic::catch_unwind(|| Simulator::run(&product, &zero_state));
+        assert!(panicked.is_err());
+
+        // Assertions are metadata that inversion and equivalence checks pass through
+        let mut checked = Circuit::new(2);
+        checked.h(0).cnot(0, 1).assert_entangled(0, 1);
+        assert!(checked.is_unitary());
+        let inverse = checked.inverse();
+        assert_eq!(
+            inverse.instructions()[0].operation,
+            checked.instructions()[2].operation
+        );
+        let mut plain = Circuit::new(2);
+        plain.h(0).cnot(0, 1);
+        assert!(quantum_simulator::analysis::circuit_equivalence(&checked, &plain).unwrap());
+    }
+
+    #[test]
+    fn test_simulator_stats_scale_with_register_width() {
+        use quantum_simulator::backend::Backend;
+        use quantum_simulator::precision::F32Backend;
+
+        Simulator::enable_stats();
+        for num_qubits in [2, 4, 8, 12, 16] {
+            let mut ghz = Circuit::new(num_qubits);
+            ghz.h(0);
+            for qubit in 1..num_qubits {
+                ghz.cnot(0, qubit);
+            }
+
+            Simulator::reset_stats();
+            Simulator::run(&ghz, &Qubit::zero_state(num_qubits).state);
+            let stats = Simulator::stats();
+            assert!(stats.gates >= 1 && stats.gates <= num_qubits as u64);
+            assert_eq!(stats.peak_memory_bytes, 16 << num_qubits);
+
+            Simulator::reset_stats();
+            F32Backend::new(num_qubits).run(&ghz).unwrap();
+            assert_eq!(Simulator::stats().gates, num_qubits as u64);
+            assert_eq!(Simulator::stats().peak_memory_bytes, 8 << num_qubits);
+
+            if num_qubits <= 4 {
+                Simulator::reset_stats();
+                Simulator::run_density(&ghz, &DensityMatrix::new(num_qubits));
+                assert_eq!(Simulator::stats().peak_memory_bytes, 16 << (2 * num_qubits));
+            }
+        }
+
+        // Collection is per thread and off until enabled
+        Simulator::disable_stats();
+        Simulator::reset_stats();
+        Simulator::run(
+            &Circuit::preset("ghz-5").unwrap(),
+            &Qubit::zero_state(5).state,
+        );
+        assert_eq!(Simulator::stats().gates, 0);
+        let other_thread = std::thread::spawn(|| {
+            Simulator::run(
+                &Circuit::preset("bell").unwrap(),
+                &Qubit::zero_state(2).state,
+            );
+            Simulator::stats().gates
+        });
+        assert_eq!(other_thread.join().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_gate_tensor_products() {
+        use quantum_simulator::gates::{kron, Gate};
+
+        let composed = kron(&[hadamard(1), Gate::identity(1), pauli_x()]).unwrap();
+        let mut circuit = Circuit::new(3);
+        circuit.x(0).h(2);
+        let mut expected = Qubit::zero_state(3);
+        circuit.run(&mut expected);
+        let mut actual = Qubit::zero_state(3);
+        composed.apply(&mut actual);
+        for (a, b) in actual.state.iter().zip(&expected.state) {
+            assert!((a - b).norm() < 1e-12);
+        }
+
+        assert_eq!(
+            kron(&[hadamard(1), hadamard(1), hadamard(1)]).unwrap(),
+            hadamard(3)
+        );
+        assert_eq!(kron(&[]).unwrap(), Gate::identity(0));
+        assert_eq!(
+            pauli_x().tensor(&Gate::identity(2)).unwrap(),
+            pauli_x().expand(2, 3)
+        );
+        assert!(matches!(
+            Gate::new(vec![vec![Complex::new(1.0, 0.0); 2]; 3]).tensor(&pauli_x()),
+            Err(QuantumError::InvalidDimension { dimension: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_circuit_diff_reports_optimizer_changes() {
+        use quantum_simulator::diff::Change;
+
+        let mut original = Circuit::new(2);
+        original
+            .h(0)
+            .h(0)
+            .rz(1, 0.25)
+            .rz(1, 0.5)
+            .cnot(0, 1)
+            .measure(0, 0);
+        let (optimized, _) = original.optimized();
+        let diff = original.diff(&optimized);
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::Removed {
+                    index: 0,
+                    instruction: Instruction::new(Operation::H, vec![0]),
+                },
+                Change::Removed {
+                    index: 1,
+                    instruction: Instruction::new(Operation::H, vec![0]),
+                },
+                Change::Changed {
+                    old_index: 2,
+                    new_index: 0,
+                    old: Instruction::new(Operation::Rz(0.25), vec![1]),
+                    new: Instruction::new(Operation::Rz(0.75), vec![1]),
+                },
+                Change::Removed {
+                    index: 3,
+                    instruction: Instruction::new(Operation::Rz(0.5), vec![1]),
+                },
+            ]
+        );
+        assert_eq!(diff.to_string().lines().count(), 4);
+        assert!(diff.to_string().starts_with("- #0 H on [0]"));
+
+        let mut wider = Circuit::new(3);
+        wider.h(0);
+        let widened = Circuit::new(2).diff(&wider);
+        assert_eq!(widened.num_qubits, (2, 3));
+        assert_eq!(widened.to_string(), "qubits: 2 -> 3\n+ #0 H on [0]");
+        assert_eq!(optimized.diff(&optimized).to_string(), "no differences");
+    }
+
+    #[test]
+    fn test_hadamard_on_single_register_qubit() {
+        use quantum_simulator::gates::{hadamard_all, hadamard_on, kron, Gate, GateKind};
+
+        let gate = hadamard_on(2, 4);
+        assert_eq!(gate.targets(), &[2]);
+        let expected = kron(&[Gate::identity(1), hadamard(1), Gate::identity(2)]).unwrap();
+        for column in 0..16 {
+            let mut state = Qubit::from_state(
+                (0..16)
+                    .map(|row| Complex::new(if row == column { 1.0 } else { 0.0 }, 0.0))
+                    .collect(),
+            );
+            gate.apply(&mut state);
+            for (amplitude, expected_row) in state.state.iter().zip(expected.matrix.iter()) {
+                assert!((amplitude - expected_row[column]).norm() < 1e-12);
+            }
+        }
+
+        let mut circuit = Circuit::new(4);
+        circuit.h(2);
+        let mut expected_state = Qubit::zero_state(4);
+        circuit.run(&mut expected_state);
+        let mut state = Qubit::zero_state(4);
+        gate.apply(&mut state);
+        assert_eq!(state, expected_state);
+
+        // A 20-qubit register keeps the 2×2 matrix, not a 2²⁰ × 2²⁰ one
+        let wide = hadamard_on(19, 20);
+        assert!(matches!(wide, GateKind::SingleQubit { target: 19, .. }));
+        assert_eq!(wide.to_gate().matrix.len(), 2);
+        let mut register = Qubit::zero_state(20);
+        wide.apply(&mut register);
+        assert!((register.state[1 << 19].norm_sqr() - 0.5).abs() < 1e-12);
+
+        assert_eq!(hadamard_all(3), hadamard(3));
+        assert_eq!(hadamard_all(3), kron(&vec![hadamard(1); 3]).unwrap());
+        assert!(std::panic::catch_unwind(|| hadamard_on(4, 4)).is_err());
+    }
+
+    #[test]
+    fn test_gate_activity_heatmap() {
+        use quantum_simulator::analysis::gate_activity;
+
+        let mut circuit = Circuit::new(4);
+        circuit
+            .h(0)
+            .cnot(0, 1)
+            .cnot(0, 2)
+            .cnot(0, 3)
+            .toffoli(0, 1, 2)
+            .measure(3, 0)
+            .reset(3);
+        let activity = gate_activity(&circuit);
+        assert_eq!(activity.gates, vec![5, 2, 2, 1]);
+        assert_eq!(activity.interactions[0], vec![0, 2, 2, 1]);
+        assert_eq!(activity.interactions[1][2], 1);
+        assert_eq!(activity.matrix()[0][0], 5);
+        assert_eq!(activity.max(), 5);
+
+        let csv = activity.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "qubit,q0,q1,q2,q3");
+        assert_eq!(lines[1], "q0,5,2,2,1");
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn test_state_gallery_exports_canonical_states() {
+        use quantum_simulator::gallery::Gallery;
+        use quantum_simulator::qubit::{BellState, Qubit};
+
+        for which in BellState::ALL {
+            let bell = Qubit::bell_state(which);
+            let norm: f64 = bell.state.iter().map(|a| a.norm_sqr()).sum();
+            assert!((norm - 1.0).abs() < 1e-12);
+        }
+        assert_eq!(
+            Qubit::bell_state(BellState::PsiPlus).to_dirac(),
+            "0.7071|01⟩ + 0.7071|10⟩"
+        );
+        let dicke = Qubit::dicke(4, 2);
+        let support = dicke.state.iter().filter(|a| a.norm_sqr() > 0.0).count();
+        assert_eq!(support, 6);
+
+        let gallery = Gallery::canonical(3, 11);
+        let slugs: Vec<&str> = gallery.entries.iter().map(|e| e.slug.as_str()).collect();
+        assert_eq!(
+            slugs,
+            [
+                "bell-phi-plus",
+                "bell-phi-minus",
+                "bell-psi-plus",
+                "bell-psi-minus",
+                "ghz-3",
+                "w-3",
+                "dicke-3-2",
+                "random-3"
+            ]
+        );
+
+        let dir = std::env::temp_dir().join(format!("gallery-test-{}", std::process::id()));
+        gallery.write(&dir).unwrap();
+        for slug in &slugs {
+            let svg = std::fs::read_to_string(dir.join(format!("{}.svg", slug))).unwrap();
+            assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        }
+        let presets: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("gallery.json")).unwrap())
+                .unwrap();
+        assert_eq!(presets.as_array().unwrap().len(), 8);
+        assert_eq!(presets[4]["amplitudes"].as_array().unwrap().len(), 8);
+        assert!(std::fs::read_to_string(dir.join("index.md"))
+            .unwrap()
+            .contains("![GHZ](ghz-3.svg)"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_global_phase_comparisons() {
+        use quantum_simulator::analysis::{approx_eq_up_to_global_phase, circuit_equivalence};
+        use std::f64::consts::PI;
+
+        // Rz(θ) and the phase gate P(θ) differ by the global phase e^{-iθ/2}
+        let mut rz = Circuit::new(2);
+        rz.h(0).cnot(0, 1).rz(1, PI / 4.0);
+        let mut phase = Circuit::new(2);
+        phase.h(0).cnot(0, 1).t(1);
+        assert!(circuit_equivalence(&rz, &phase).unwrap());
+
+        let mut a = Qubit::zero_state(2);
+        rz.run(&mut a);
+        let mut b = Qubit::zero_state(2);
+        phase.run(&mut b);
+        assert!((a.state[0] - b.state[0]).norm() > 0.1);
+        assert!(approx_eq_up_to_global_phase(&a, &b, 1e-12));
+        a.canonicalize();
+        b.canonicalize();
+        assert!(a.global_phase().abs() < 1e-12);
+        assert!(a
+            .state
+            .iter()
+            .zip(&b.state)
+            .all(|(x, y)| (x - y).norm() < 1e-12));
+
+        // A relative phase is physical
+        let mut relative = Circuit::new(2);
+        relative.h(0).cnot(0, 1).rz(1, PI / 3.0);
+        assert!(!circuit_equivalence(&rz, &relative).unwrap());
+        assert!(!circuit_equivalence(&rz, &Circuit::new(3)).unwrap());
+
+        let mut measured = Circuit::new(2);
+        measured.measure(0, 0);
+        assert!(circuit_equivalence(&rz, &measured).is_err());
+    }
+
+    #[test]
+    fn test_preset_circuit_library() {
+        use quantum_simulator::algorithms::qft;
+        use quantum_simulator::analysis::circuit_equivalence;
+        use quantum_simulator::presets::PRESETS;
+
+        for preset in PRESETS {
+            let circuit = Circuit::preset(preset.name).unwrap();
+            assert_eq!(circuit, preset.circuit());
+            assert!(circuit.num_qubits() >= 2);
+        }
+
+        let ghz = Circuit::preset("ghz-5").unwrap();
+        let mut register = Qubit::zero_state(5);
+        ghz.run(&mut register);
+        assert!((register.state[0].norm_sqr() - 0.5).abs() < 1e-12);
+        assert!((register.state[0b11111].norm_sqr() - 0.5).abs() < 1e-12);
+
+        assert!(circuit_equivalence(&Circuit::preset("qft-4").unwrap(), &qft(4)).unwrap());
+        let counts = Simulator::sample(
+            &Circuit::preset("grover-3").unwrap(),
+            &Qubit::zero_state(3).state,
+            200,
+        );
+        assert_eq!(counts.most_frequent().unwrap().0, "111");
+
+        // Teleportation delivers Ry(π/2)|0⟩ = |+⟩ to qubit 2 whatever the measurements give
+        let teleportation = Circuit::preset("teleportation").unwrap();
+        for _ in 0..8 {
+            let mut register = Qubit::zero_state(3);
+            teleportation.run(&mut register);
+            let (x, _, z) = quantum_simulator::analysis::bloch_vector(&register, 2);
+            assert!((x - 1.0).abs() < 1e-9 && z.abs() < 1e-9);
+        }
+
+        for name in ["ghz-1", "qft-17", "grover", "shor-15", "bell-2"] {
+            assert!(Circuit::preset(name).is_err(), "{}", name);
+        }
+
+        // Grover's dense oracle and diffusion keep it narrower than the other families
+        use quantum_simulator::presets::{MAX_GROVER_PRESET_QUBITS, MAX_PRESET_QUBITS};
+        let widest = format!("grover-{}", MAX_GROVER_PRESET_QUBITS);
+        assert_eq!(
+            Circuit::preset(&widest).unwrap().num_qubits(),
+            MAX_GROVER_PRESET_QUBITS
+        );
+        assert!(matches!(
+            Circuit::preset(&format!("grover-{}", MAX_GROVER_PRESET_QUBITS + 1)),
+            Err(QuantumError::Parse { .. })
+        ));
+        assert!(Circuit::preset(&format!("ghz-{}", MAX_PRESET_QUBITS)).is_ok());
+    }
+
+    #[test]
+    fn test_circuit_to_unitary_matches_gate_constructions() {
+        use quantum_simulator::gates::{cz, toffoli, Gate};
+
+        // The textbook Toffoli decomposition into H, T, T† and CNOTs
+        let tdg = |circuit: &mut Circuit, qubit: usize| {
+            circuit.rz(qubit, -std::f64::consts::FRAC_PI_4);
+        };
+        let mut decomposed = Circuit::new(3);
+        decomposed.h(2).cnot(1, 2);
+        tdg(&mut decomposed, 2);
+        decomposed.cnot(0, 2).t(2).cnot(1, 2);
+        tdg(&mut decomposed, 2);
+        decomposed.cnot(0, 2).t(1).t(2).h(2).cnot(0, 1).t(0);
+        tdg(&mut decomposed, 1);
+        decomposed.cnot(0, 1);
+
+        let matrix = decomposed.to_unitary(3);
+        let gate = Gate::from_unitary(matrix.clone()).unwrap();
+        let expected = toffoli(0, 1, 2, 3);
+        let fidelity = quantum_simulator::analysis::gate_fidelity(&gate, &expected);
+        assert!((fidelity - 1.0).abs() < 1e-12);
+
+        // Applying the matrix agrees with running the circuit
+        let mut register = Qubit::random_state(3, 5);
+        let input = register.state.clone();
+        decomposed.run(&mut register);
+        for (row, amplitude) in matrix.iter().zip(&register.state) {
+            let product: num_complex::Complex<f64> =
+                row.iter().zip(&input).map(|(u, a)| u * a).sum();
+            assert!((product - amplitude).norm() < 1e-12);
+        }
+
+        let mut hch = Circuit::new(2);
+        hch.h(1).cnot(0, 1).h(1);
+        let wide = hch.to_unitary(3);
+        let expected = cz(0, 1, 3);
+        for (row, expected_row) in wide.iter().zip(expected.matrix.iter()) {
+            for (u, v) in row.iter().zip(expected_row) {
+                assert!((u - v).norm() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_explain_mode_describes_each_step() {
+        use quantum_simulator::explain::explain_run;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1).z(1).measure(0, 0);
+        let explanations = explain_run(&circuit, &Qubit::zero_state(2).state);
+        assert_eq!(explanations.len(), 4);
+
+        assert_eq!(explanations[0].gate, "h");
+        assert_eq!(explanations[0].qubits, vec![0]);
+        assert_eq!(explanations[0].dirac, "0.7071|00⟩ + 0.7071|01⟩");
+        let bitstrings: Vec<&str> = explanations[0]
+            .changes
+            .iter()
+            .map(|change| change.bitstring.as_str())
+            .collect();
+        assert_eq!(bitstrings, vec!["00", "01"]);
+
+        assert_eq!(explanations[1].qubits, vec![0, 1]);
+        assert_eq!(explanations[1].dirac, "0.7071|00⟩ + 0.7071|11⟩");
+        assert!(explanations[2].only_phases_changed());
+        assert!(explanations[2]
+            .to_string()
+            .contains("probabilities unchanged"));
+
+        // The measurement collapses onto one branch
+        assert_eq!(explanations[3].gate, "m → c0");
+        assert_eq!(explanations[3].changes.len(), 2);
+        let collapsed = &explanations[3].changes;
+        assert!(collapsed
+            .iter()
+            .any(|change| (change.after - 1.0).abs() < 1e-12));
+        assert_eq!(explanations[3].to_json_value()["instruction"], 3);
+    }
+
+    #[test]
+    fn test_moments_depth_and_barriers() {
+        let mut circuit = Circuit::new(3);
+        circuit.h(0).h(1).cnot(0, 1).x(2);
+        assert_eq!(circuit.depth(), 2);
+        assert_eq!(circuit.gate_count(), 4);
+        let moments = circuit.moments();
+        assert_eq!(moments[0].len(), 3);
+        for moment in &moments {
+            let mut qubits: Vec<usize> = moment
+                .iter()
+                .flat_map(|instruction| instruction.qubits.clone())
+                .collect();
+            let count = qubits.len();
+            qubits.sort_unstable();
+            qubits.dedup();
+            assert_eq!(qubits.len(), count);
+        }
+
+        // The barrier holds the Z on qubit 2 back until the CNOT has run
+        let mut fenced = circuit.clone();
+        fenced.barrier(&[]).z(2);
+        let mut unfenced = circuit.clone();
+        unfenced.z(2);
+        assert_eq!(unfenced.depth(), 2);
+        assert_eq!(fenced.depth(), 3);
+        assert_eq!(fenced.gate_count(), 5);
+        let counts = fenced.count_ops();
+        assert_eq!(counts["h"], 2);
+        assert_eq!(counts["barrier"], 1);
+        assert_eq!(counts.values().sum::<usize>(), fenced.instructions().len());
+
+        // Barriers leave the state unchanged on every backend
+        fenced.measure(2, 0);
+        let initial = Qubit::zero_state(3).state;
+        let direct = Simulator::run(&fenced, &initial);
+        assert!((direct.state.iter().map(|a| a.norm_sqr()).sum::<f64>() - 1.0).abs() < 1e-12);
+        let rho =
+            Simulator::run_density(&fenced, &DensityMatrix::from_state(&Qubit::zero_state(3)));
+        assert!((rho.trace() - 1.0).abs() < 1e-12);
+        let mut noise = NoiseModel::new();
+        noise.add_error(depolarizing(0.01));
+        Simulator::run_with_noise(&fenced, &initial, &noise);
+
+        // The optimizer does not cancel gates across a barrier
+        let mut pair = Circuit::new(1);
+        pair.x(0).barrier(&[0]).x(0);
+        assert_eq!(pair.optimized().0.gate_count(), 2);
+
+        // Barriers round-trip through OpenQASM and show in diagrams
+        let source = qasm::to_qasm(&fenced, QasmVersion::V2).unwrap();
+        assert!(source.contains("barrier q[0], q[1], q[2];"));
+        assert_eq!(
+            qasm::parse(&source).unwrap().instructions(),
+            fenced.instructions()
+        );
+        assert!(fenced.draw().contains('░'));
+
+        // Imported barriers are the identity when inverting and comparing circuits
+        let imported = qasm::parse(
+            "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[3];\nh q[0];\nbarrier q;\ncx q[0], q[2];\n",
+        )
+        .unwrap();
+        assert!(imported.is_unitary());
+        let mut round_trip = imported.clone();
+        round_trip.append(&imported.inverse());
+        assert!(
+            quantum_simulator::analysis::circuit_equivalence(&round_trip, &Circuit::new(3))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_idle_decoherence_depends_on_schedule() {
+        use quantum_simulator::noise::{thermal_relaxation, Decoherence};
+
+        let mut decoherence = Decoherence::new(&[50.0; 3], &[40.0; 3], 1.0).unwrap();
+        decoherence.set_duration(Operation::Cnot, 4.0).unwrap();
+        // Durations are validated like the default one, and a rejected one changes nothing
+        for invalid in [-1.0, f64::NAN] {
+            assert!(matches!(
+                decoherence.set_duration(Operation::Cnot, invalid),
+                Err(QuantumError::Unsupported { .. })
+            ));
+        }
+        assert_eq!(decoherence.duration(&Operation::Cnot), 4.0);
+        let mut model = NoiseModel::new();
+        model.set_decoherence(decoherence.clone());
+
+        // A GHZ state prepared as a chain of CNOTs; qubit 2 idles while the first CNOT runs
+        let mut ghz = Circuit::new(3);
+        ghz.h(0).cnot(0, 1).cnot(1, 2);
+        assert_eq!(decoherence.circuit_duration(&ghz), 9.0);
+        let idle = model.idle_errors(&ghz);
+        assert_eq!(idle.len(), 4);
+        assert_eq!(idle[1].len(), 1);
+        let waits: Vec<Vec<usize>> = idle[2].iter().map(|(_, qubits)| qubits.clone()).collect();
+        assert_eq!(waits, vec![vec![2]]);
+        let expected = thermal_relaxation(50.0, 40.0, 5.0);
+        assert!((idle[2][0].0.process_fidelity() - expected.process_fidelity()).abs() < 1e-12);
+
+        let ideal = DensityMatrix::from_state(&Simulator::run(&ghz, &Qubit::zero_state(3).state));
+        let noisy = Simulator::run_density_with_noise(&ghz, &DensityMatrix::new(3), &model);
+        let fidelity = noisy.fidelity(&ideal);
+        assert!(fidelity < 1.0 - 1e-3);
+
+        // A barrier behind two gates on qubit 2 leaves qubit 0 waiting in |+⟩
+        let mut delayed = Circuit::new(3);
+        delayed.x(2).x(2).h(0).barrier(&[]).cnot(0, 1).cnot(1, 2);
+        assert_eq!(decoherence.circuit_duration(&delayed), 10.0);
+        let late = Simulator::run_density_with_noise(&delayed, &DensityMatrix::new(3), &model);
+        assert!(late.fidelity(&ideal) < fidelity);
+
+        // Trajectories reproduce the density matrix on average and replay their idle noise
+        let initial = Qubit::zero_state(3).state;
+        let mut rng = StdRng::seed_from_u64(11);
+        let shots = 4000;
+        let mut excited = 0.0;
+        for _ in 0..shots {
+            let result = Simulator::run_recorded_with_rng(&ghz, &initial, &model, &mut rng);
+            excited += result.state.state[0b111].norm_sqr() / shots as f64;
+            let replayed = Simulator::replay(&ghz, &initial, &model, &result.events).unwrap();
+            assert_eq!(replayed, result);
+        }
+        assert!((excited - noisy.probabilities()[0b111]).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_soak_checks_invariants_across_backends() {
+        let soak = Soak::new(Duration::from_secs(60))
+            .with_max_iterations(12)
+            .with_max_qubits(4)
+            .with_seed(5);
+        let report = soak.run();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.iterations, 12);
+        // Every circuit is checked on the CPU, single-precision and density-matrix backends
+        assert!(report.checks >= 12 * 5);
+
+        let mut seen = 0;
+        let again = soak.with_max_iterations(3).run_with(|progress| {
+            seen += 1;
+            assert_eq!(progress.iterations, seen);
+        });
+        assert_eq!(again.iterations, 3);
+
+        let json = report.to_json_value();
+        assert_eq!(json["passed"], true);
+        assert_eq!(json["iterations"], 12);
+        assert!(report.to_string().starts_with("passed: 12 circuits"));
+
+        // Widths beyond the library bound are clamped rather than allocating 4ⁿ density matrices
+        use quantum_simulator::soak::MAX_SOAK_QUBITS;
+        let wide = Soak::new(Duration::from_secs(60)).with_max_qubits(64);
+        assert_eq!(wide.max_qubits, MAX_SOAK_QUBITS);
+        assert_eq!(Soak::new(Duration::ZERO).with_max_qubits(0).max_qubits, 1);
+        let mut unbounded = soak.with_max_iterations(2);
+        unbounded.max_qubits = 1 << 20;
+        assert!(unbounded.run().passed());
+    }
+
+    #[test]
+    fn test_multi_controlled_gates_and_ancilla_decomposition() {
+        use std::f64::consts::PI;
+
+        // The constructors agree with the fixed-arity gates
+        assert_eq!(mcx(&[2, 0], 1, 3), toffoli(0, 2, 1, 3));
+        let controlled_phase = mcp(PI, &[0], 1, 2);
+        for (row, cz_row) in controlled_phase
+            .matrix
+            .iter()
+            .zip(cz(0, 1, 2).matrix.iter())
+        {
+            for (a, b) in row.iter().zip(cz_row) {
+                assert!((a - b).norm() < 1e-12);
+            }
+        }
+        assert_eq!(
+            mcx(&[0, 1, 2], 3, 4),
+            pauli_x().multi_controlled(&[0, 1, 2], 3, 4).unwrap()
+        );
+
+        // Three and four controls: the decomposition flips the target exactly when every control
+        // is set, on every input, and returns the ancillas to |0⟩
+        for num_controls in 3..=4 {
+            let controls: Vec<usize> = (0..num_controls).collect();
+            let target = num_controls;
+            let ancillas: Vec<usize> = (num_controls + 1..2 * num_controls - 1).collect();
+            let num_qubits = 2 * num_controls - 1;
+            let mut decomposed = Circuit::new(num_qubits);
+            decomposed.mcx_with_ancillas(&controls, target, &ancillas);
+            assert!(decomposed
+                .instructions()
+                .iter()
+                .all(|instruction| instruction.qubits.len() <= 2));
+            assert_eq!(decomposed.count_ops()["cx"], 6 * (2 * num_controls - 3));
+
+            let mut direct = Circuit::new(num_qubits);
+            direct.mcx(&controls, target);
+            for input in 0..1 << (num_controls + 1) {
+                let mut state = vec![Complex::new(0.0, 0.0); 1 << num_qubits];
+                state[input] = Complex::new(1.0, 0.0);
+                let expected = Simulator::run(&direct, &state);
+                let actual = Simulator::run(&decomposed, &state);
+                for (a, b) in expected.state.iter().zip(&actual.state) {
+                    assert!((a - b).norm() < 1e-9, "input {:b}", input);
+                }
+            }
+        }
+
+        // A Grover oracle marking |101⟩ written with mcp matches Oracle::from_bitstrings
+        let mut oracle = Circuit::new(3);
+        oracle.x(1).mcp(PI, &[0, 1], 2).x(1);
+        let expected = Oracle::from_bitstrings(3, &["101"]).gate();
+        let actual = oracle.to_unitary(3);
+        for (row, expected_row) in actual.iter().zip(expected.matrix.iter()) {
+            for (a, b) in row.iter().zip(expected_row) {
+                assert!((a - b).norm() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arithmetic_circuits() {
+        use quantum_simulator::algorithms::arithmetic::{
+            controlled_modular_multiplier, modular_exponentiation, qft_adder, ripple_carry_adder,
+        };
+
+        let basis_state = |num_qubits: usize, index: usize| {
+            let mut state = vec![Complex::new(0.0, 0.0); 1 << num_qubits];
+            state[index] = Complex::new(1.0, 0.0);
+            state
+        };
+        let lands_on = |qubit: &Qubit, index: usize| (qubit.state[index].norm() - 1.0).abs() < 1e-6;
+
+        // Both adders on every pair of 2-bit and 3-bit inputs
+        let ripple = ripple_carry_adder(2);
+        for a in 0..4 {
+            for b in 0..4 {
+                let final_qubit = Simulator::run(&ripple, &basis_state(6, a | b << 2));
+                let expected = a | ((a + b) % 4) << 2 | ((a + b) / 4) << 5;
+                assert!(lands_on(&final_qubit, expected), "{} + {}", a, b);
+            }
+        }
+        let draper = qft_adder(3);
+        for a in 0..8 {
+            for b in 0..8 {
+                let final_qubit = Simulator::run(&draper, &basis_state(6, a | b << 3));
+                assert!(
+                    lands_on(&final_qubit, a | ((a + b) % 8) << 3),
+                    "{} + {}",
+                    a,
+                    b
+                );
+            }
+        }
+
+        // Multiplication by 7 modulo 15 for every x, controlled, with the work qubits cleared
+        let multiplier = controlled_modular_multiplier(7, 15).unwrap();
+        for x in 0..15 {
+            let controlled = Simulator::run(&multiplier, &basis_state(11, 1 | x << 1));
+            assert!(lands_on(&controlled, 1 | (7 * x % 15) << 1), "7·{}", x);
+            let idle = Simulator::run(&multiplier, &basis_state(11, x << 1));
+            assert!(lands_on(&idle, x << 1));
+        }
+        assert!(matches!(
+            controlled_modular_multiplier(5, 15),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        assert!(modular_exponentiation(2, 1, 3).is_err());
+
+        // 2^e mod 15 for every two-bit exponent
+        let modexp = modular_exponentiation(2, 15, 2).unwrap();
+        assert_eq!(modexp.num_qubits(), 12);
+        for e in 0..4 {
+            let final_qubit = Simulator::run(&modexp, &basis_state(12, e | 1 << 2));
+            assert!(lands_on(&final_qubit, e | ((1 << e) % 15) << 2), "2^{}", e);
+        }
+    }
+
+    #[test]
+    fn test_shor_factors_small_numbers() {
+        use quantum_simulator::algorithms::{order_finding, shor};
+
+        // 7 has order 4 modulo 15, so the eight phase bits read s/4 exactly
+        let circuit = order_finding(7, 15).unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..2 {
+            let mut qubit = Qubit::zero_state(circuit.num_qubits());
+            let bits = circuit.run_with_rng(&mut qubit, &mut rng);
+            assert!(bits[..6].iter().all(|&bit| !bit), "{:?}", bits);
+        }
+
+        // Seeds whose first base is coprime to the modulus, so the factors come from its order
+        for (modulus, seed, factors) in [(15, 0, (3, 5)), (15, 1, (3, 5)), (21, 7, (3, 7))] {
+            let factorization = shor(modulus, seed).unwrap();
+            assert_eq!(factorization.factors, factors);
+            assert_eq!(factorization.runs, 1);
+            let base = factorization.base.unwrap();
+            let order = factorization.order.unwrap();
+            assert_eq!((0..order).fold(1, |power, _| power * base % modulus), 1);
+        }
+        // A base sharing a factor with the modulus needs no circuit
+        let factorization = shor(15, 2).unwrap();
+        assert_eq!((factorization.factors, factorization.runs), ((3, 5), 0));
+
+        assert_eq!(shor(9, 0).unwrap().factors, (3, 3));
+        assert!(matches!(shor(17, 0), Err(QuantumError::Unsupported { .. })));
+        assert!(shor(1 << 9, 0).is_err());
+        // 7 × 13 needs a 17-qubit circuit per run, past the supported width
+        assert!(matches!(shor(91, 0), Err(QuantumError::Unsupported { .. })));
+    }
+
+    #[test]
+    fn test_teleportation_and_superdense_coding_protocols() {
+        use quantum_simulator::algorithms::{superdense_coding, teleportation};
+
+        let protocol = teleportation();
+        let mut rng = StdRng::seed_from_u64(11);
+        for seed in 0..6 {
+            let input = Qubit::random_state(1, seed);
+            let fidelity = protocol.verify_with_rng(&input, &mut rng).unwrap();
+            assert!((fidelity - 1.0).abs() < 1e-9);
+        }
+        // Every measurement outcome occurs, each followed by its correction
+        let mut outcomes = HashMap::new();
+        for _ in 0..200 {
+            let mut qubit = Qubit::zero_state(3);
+            *outcomes
+                .entry(protocol.circuit().run_with_rng(&mut qubit, &mut rng))
+                .or_insert(0) += 1;
+        }
+        assert_eq!(outcomes.len(), 4);
+        assert!(matches!(
+            protocol.verify(&Qubit::zero_state(2)),
+            Err(QuantumError::DimensionMismatch { .. })
+        ));
+
+        for message in 0..4 {
+            let protocol = superdense_coding(message);
+            assert_eq!(protocol.message(), message);
+            assert!((0..10).all(|_| protocol.verify_with_rng(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn test_import_quirk_and_cirq_circuits() {
+        use quantum_simulator::gates::{swap, t};
+        use quantum_simulator::interop::{from_cirq_json, from_quirk, parse};
+        use std::f64::consts::PI;
+
+        // Quirk: an initial |+⟩, an anti-control, formula and fractional gates and a Fredkin
+        let quirk = r#"{"init":["+"],"cols":[
+        ["◦","X"],
+        [{"id":"Rzft","arg":"-π/3"},"Y^½"],
+        [1,"•","Swap","Swap"],
+        ["Z^-½","Bloch"]]}"#;
+        let imported = from_quirk(quirk).unwrap();
+        assert_eq!(imported.num_qubits(), 4);
+        assert_eq!(imported.num_clbits(), 0);
+        let mut expected = Circuit::new(4);
+        expected
+            .h(0)
+            .x(0)
+            .cnot(0, 1)
+            .x(0)
+            .rz(0, -PI / 3.0)
+            .ry(1, PI / 2.0);
+        expected
+            .add_instruction(Instruction::new(
+                Operation::Unitary(swap(0, 1, 2).with_control()),
+                vec![1, 2, 3],
+            ))
+            .unwrap();
+        expected.phase(0, -PI / 2.0);
+        let mut a = Qubit::zero_state(4);
+        imported.run(&mut a);
+        let mut b = Qubit::zero_state(4);
+        expected.run(&mut b);
+        // Y^½ is Ry(π/2) up to a global phase
+        let overlap: Complex<f64> = a
+            .state
+            .iter()
+            .zip(&b.state)
+            .map(|(x, y)| x.conj() * y)
+            .sum();
+        assert!((overlap.norm() - 1.0).abs() < 1e-9);
+
+        // The same circuit from a percent-encoded link, and clear errors for unsupported gates
+        let link = format!(
+            "https://algassert.com/quirk#circuit={}",
+            quirk
+                .bytes()
+                .map(|byte| format!("%{:02X}", byte))
+                .collect::<String>()
+        );
+        assert_eq!(
+            from_quirk(&link).unwrap().instructions(),
+            imported.instructions()
+        );
+        assert!(matches!(
+            from_quirk(r#"{"cols":[["QFT3"]]}"#),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            from_quirk(r#"{"cols":[[{"id":"Rxft","arg":"sin(t)"}]]}"#),
+            Err(QuantumError::Unsupported { .. })
+        ));
+
+        // Cirq: grid qubits numbered in sorted order, an anti-controlled T and √iSWAP
+        let q00 = r#"{"cirq_type": "GridQubit", "row": 0, "col": 0}"#;
+        let q01 = r#"{"cirq_type": "GridQubit", "row": 0, "col": 1}"#;
+        let operation = |gate: &str, qubits: &[&str]| {
+            format!(
+                r#"{{"cirq_type": "GateOperation", "gate": {}, "qubits": [{}]}}"#,
+                gate,
+                qubits.join(", ")
+            )
+        };
+        let circuit = |operations: &[String]| {
+            let moments: Vec<String> = operations
+                .iter()
+                .map(|op| format!(r#"{{"cirq_type": "Moment", "operations": [{}]}}"#, op))
+                .collect();
+            format!(
+                r#"{{"cirq_type": "Circuit", "moments": [{}]}}"#,
+                moments.join(", ")
+            )
+        };
+        let json = circuit(&[
+            operation(
+                r#"{"cirq_type": "XPowGate", "exponent": 0.5, "global_shift": -0.5}"#,
+                &[q01],
+            ),
+            operation(
+                r#"{"cirq_type": "ControlledGate", "num_controls": 1, "control_values": [[0]],
+                "sub_gate": {"cirq_type": "ZPowGate", "exponent": 0.25, "global_shift": 0.0}}"#,
+                &[q01, q00],
+            ),
+            operation(
+                r#"{"cirq_type": "ISwapPowGate", "exponent": 0.5, "global_shift": 0.0}"#,
+                &[q00, q01],
+            ),
+            operation(
+                r#"{"cirq_type": "MeasurementGate", "num_qubits": 2, "key": "m", "invert_mask": []}"#,
+                &[q00, q01],
+            ),
+        ]);
+        let imported = parse(&json).unwrap();
+        let instructions = imported.instructions();
+        assert_eq!(imported.num_clbits(), 2);
+        assert_eq!(
+            instructions[0],
+            Instruction::new(Operation::Rx(PI / 2.0), vec![1])
+        );
+        assert_eq!(instructions[2].qubits, vec![1, 0]);
+        assert_eq!(instructions[2].operation.gate(), t().with_control());
+        let Operation::Unitary(root_iswap) = &instructions[4].operation else {
+            panic!("expected √iSWAP as a unitary");
+        };
+        let squared = &root_iswap.matrix;
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        assert!((squared[1][1] - Complex::new(h, 0.0)).norm() < 1e-12);
+        assert!((squared[1][2] - Complex::new(0.0, h)).norm() < 1e-12);
+        assert_eq!(instructions[6].operation, Operation::Measure(1));
+
+        let symbolic = circuit(&[operation(
+            r#"{"cirq_type": "ZPowGate", "exponent": {"cirq_type": "sympy.Symbol", "name": "t"}}"#,
+            &[q00],
+        )]);
+        assert!(matches!(
+            from_cirq_json(&symbolic),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        let phased = circuit(&[operation(
+            r#"{"cirq_type": "PhasedXPowGate", "exponent": 1.0, "phase_exponent": 0.5}"#,
+            &[q00],
+        )]);
+        assert!(matches!(
+            from_cirq_json(&phased),
+            Err(QuantumError::Unsupported { .. })
+        ));
+    }
+
+    #[cfg(feature = "chunked")]
+    #[test]
+    fn test_out_of_core_state_matches_in_memory() {
+        use quantum_simulator::backend::{Backend, CpuBackend};
+        use quantum_simulator::benchmarking::random_universal_circuit;
+        use quantum_simulator::chunked::{
+            ChunkedBackend, ChunkedStateVector, DEFAULT_MEMORY_BUDGET, MIN_MEMORY_BUDGET,
+        };
+
+        // 64 KiB chunks: qubits 12 and 13 select the chunk, so gates on them span groups of chunks
+        let mut circuit = random_universal_circuit(14, 6, 3);
+        circuit.toffoli(12, 13, 0).toffoli(0, 12, 13).swap(1, 13);
+        circuit.measure(13, 0).measure(2, 1);
+
+        let state = ChunkedStateVector::zero_state(14, 512 << 10).unwrap();
+        assert_eq!(state.num_chunks(), 4);
+        let path = state.path().to_path_buf();
+        let mut chunked = ChunkedBackend::from_state(state);
+        let mut cpu = CpuBackend::new(14);
+        let chunked_clbits = chunked
+            .run_with_rng(&circuit, &mut StdRng::seed_from_u64(5))
+            .unwrap();
+        let cpu_clbits = cpu
+            .run_with_rng(&circuit, &mut StdRng::seed_from_u64(5))
+            .unwrap();
+        assert_eq!(chunked_clbits, cpu_clbits);
+        let expected = cpu.state();
+        for (a, b) in chunked.state().state.iter().zip(&expected.state) {
+            assert!((a - b).norm() < 1e-10);
+        }
+        let zz = pauli_z().tensor(&pauli_z()).unwrap();
+        assert!(
+            (chunked.expectation(&zz, &[3, 12]) - cpu.expectation(&zz, &[3, 12])).abs() < 1e-10
+        );
+
+        // The state file only lives as long as the state
+        assert!(path.exists());
+        drop(chunked);
+        assert!(!path.exists());
+
+        // A copied state round-trips, and the simulator's budget is per thread
+        let random = Qubit::random_state(13, 2);
+        let copy = ChunkedStateVector::from_qubit(&random, 512 << 10).unwrap();
+        assert_eq!(copy.to_qubit().unwrap(), random);
+        assert_eq!(Simulator::memory_budget(), DEFAULT_MEMORY_BUDGET);
+        Simulator::set_memory_budget(1 << 20);
+        assert_eq!(Simulator::memory_budget(), 1 << 20);
+        Simulator::clear_memory_budget();
+        assert_eq!(Simulator::memory_budget(), DEFAULT_MEMORY_BUDGET);
+
+        // Budgets too small for the smallest chunks are rejected rather than ignored
+        assert!(matches!(
+            ChunkedStateVector::zero_state(14, MIN_MEMORY_BUDGET - 1),
+            Err(QuantumError::Unsupported { .. })
+        ));
+
+        // Four targets above the chunk boundary would map sixteen chunks, twice the budget
+        let mut wide = ChunkedStateVector::zero_state(16, MIN_MEMORY_BUDGET).unwrap();
+        assert_eq!(wide.num_chunks(), 16);
+        let four = Gate::identity(4);
+        assert!(matches!(
+            wide.apply_gate(&four, &[12, 13, 14, 15]),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        wide.apply_gate(&four, &[0, 13, 14, 15]).unwrap();
+        assert_eq!(wide.to_qubit().unwrap().state[0], Complex::new(1.0, 0.0));
+
+        // Runs report errors from measurements instead of panicking
+        let mut backend = ChunkedBackend::new(13, MIN_MEMORY_BUDGET).unwrap();
+        assert_eq!(
+            backend.try_measure_with_rng(0, &mut StdRng::seed_from_u64(1)),
+            Ok(0)
+        );
+        assert_eq!(backend.try_expectation(&pauli_z(), &[12]), Ok(1.0));
+    }
+
+    #[test]
+    fn test_simd_kernels_match_scalar() {
+        use quantum_simulator::benchmarking::random_universal_circuit;
+        use quantum_simulator::simd::{self, InstructionSet};
+
+        // CNOTs in both directions across qubit 0, the case the pair layout treats specially
+        let mut circuit = random_universal_circuit(9, 8, 17);
+        circuit
+            .h(0)
+            .ry(5, 0.4)
+            .cnot(0, 4)
+            .cnot(4, 0)
+            .cnot(3, 7)
+            .cnot(8, 1);
+        let initial_state = Qubit::random_state(9, 4).state;
+
+        let vectorized = Simulator::run(&circuit, &initial_state);
+        simd::set_enabled(false);
+        assert_eq!(simd::active(), InstructionSet::Scalar);
+        let scalar = Simulator::run(&circuit, &initial_state);
+        simd::set_enabled(true);
+
+        for (a, b) in vectorized.state.iter().zip(&scalar.state) {
+            assert!((a - b).norm() < 1e-12);
+        }
+        let mut expected = Qubit::from_state(initial_state);
+        for instruction in circuit.instructions() {
+            // The dense path, which bypasses the kernels
+            instruction
+                .operation
+                .gate()
+                .apply_to(&mut expected, &instruction.qubits);
+        }
+        for (a, b) in vectorized.state.iter().zip(&expected.state) {
+            assert!((a - b).norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_observer_sees_gates_measurements_and_snapshots() {
+        // X on qubit 0 makes the measurement certain, so the conditional X always fires while
+        // the one on the untouched clbit 1 never does
+        let mut circuit = Circuit::with_clbits(3, 2);
+        circuit
+            .x(0)
+            .h(2)
+            .barrier(&[0, 1, 2])
+            .measure(0, 0)
+            .conditional(0, Operation::X, vec![1])
+            .conditional(1, Operation::X, vec![2])
+            .snapshot("copied")
+            .reset(0);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+
+        let mut log = vec![];
+        let mut rng = StdRng::seed_from_u64(31);
+        let result = Simulator::run_with_observer_with_rng(
+            &circuit,
+            &initial_state,
+            |event| {
+                let norm: f64 = event.state().state.iter().map(|a| a.norm_sqr()).sum();
+                assert!((norm - 1.0).abs() < TOLERANCE);
+                let entry = match event {
+                    SimulationEvent::GateApplied {
+                        operation, qubits, ..
+                    } => format!("{} {:?}", operation.name(), qubits),
+                    SimulationEvent::MeasurementTaken {
+                        qubit,
+                        clbit,
+                        outcome,
+                        ..
+                    } => format!("measure {} {:?} {}", qubit, clbit, outcome),
+                    SimulationEvent::StateSnapshot { label, state, .. } => {
+                        // |011⟩ and |111⟩: qubits 0 and 1 set, qubit 2 in superposition
+                        assert!((state.state[3].norm_sqr() - 0.5).abs() < TOLERANCE);
+                        assert!((state.state[7].norm_sqr() - 0.5).abs() < TOLERANCE);
+                        label.to_string()
+                    }
+                };
+                log.push((event.instruction(), entry));
+            },
+            &mut rng,
+        );
+
+        let expected = [
+            (0, "x [0]"),
+            (1, "h [2]"),
+            (3, "measure 0 Some(0) 1"),
+            (4, "x [1]"),
+            (6, "copied"),
+            (7, "measure 0 None 1"),
+        ];
+        let expected: Vec<_> = expected.iter().map(|(i, e)| (*i, e.to_string())).collect();
+        assert_eq!(log, expected);
+        assert_eq!(result.clbits, vec![true, false]);
+        assert!((result.state.state[2].norm_sqr() - 0.5).abs() < TOLERANCE);
+        assert!((result.state.state[6].norm_sqr() - 0.5).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn test_trotter_circuits_converge_to_exact_evolution() {
+        // A Heisenberg-like chain with a field, so X, Y and Z terms all fail to commute
+        let hamiltonian =
+            Hamiltonian::parse("X0 X1 + Y0 Y1 + Z0 Z1 + Y1 Y2 - 0.4 Z1 X2 + 0.8 X0 + 1.5").unwrap();
+        let mut preparation = Circuit::new(3);
+        preparation.h(0).ry(1, 0.7).cnot(0, 2);
+        let initial = Simulator::run(&preparation, &Qubit::zero_state(3).state);
+        let time = 1.3;
+        let exact = hamiltonian.evolve(&initial, time);
+        let infidelity = |steps, order| {
+            let circuit = hamiltonian.trotter_circuit_with_order(time, steps, order);
+            let state = Simulator::run(&circuit, &initial.state);
+            let overlap: Complex<f64> = exact
+                .state
+                .iter()
+                .zip(&state.state)
+                .map(|(a, b)| a.conj() * b)
+                .sum();
+            1.0 - overlap.norm_sqr()
+        };
+
+        // Infidelity falls as the square of the Trotter error: steps² for first order and
+        // steps⁴ for second
+        let first = infidelity(20, TrotterOrder::First) / infidelity(40, TrotterOrder::First);
+        assert!((3.5..4.5).contains(&first), "first-order ratio {}", first);
+        let second = infidelity(20, TrotterOrder::Second) / infidelity(40, TrotterOrder::Second);
+        assert!(
+            (14.0..18.0).contains(&second),
+            "second-order ratio {}",
+            second
+        );
+        assert!(infidelity(40, TrotterOrder::Second) < 1e-6);
+        assert_eq!(
+            hamiltonian.trotter_circuit(time, 3),
+            hamiltonian.trotter_circuit_with_order(time, 3, TrotterOrder::First)
+        );
+
+        // The constant term is kept by the exact evolution as a global phase
+        let phase = Hamiltonian::parse("1.5")
+            .unwrap()
+            .evolve(&Qubit::zero_state(1), time);
+        assert!((phase.state[0] - Complex::from_polar(1.0, -1.5 * time)).norm() < TOLERANCE);
+        assert_eq!(hamiltonian.evolution(time).matrix.len(), 8);
+    }
+
+    #[test]
+    fn test_gate_library_flows_through_qasm_drawer_and_transpiler() {
+        use quantum_simulator::transpile::{transpile, Calibration, Placement};
+
+        // √Y by its matrix, and a three-qubit GHZ preparation that uses it
+        let sy = Gate::from_unitary(vec![
+            vec![Complex::new(0.5, 0.5), Complex::new(-0.5, -0.5)],
+            vec![Complex::new(0.5, 0.5), Complex::new(0.5, 0.5)],
+        ])
+        .unwrap();
+        let mut library = GateLibrary::new();
+        library.define_matrix("sy", sy).unwrap();
+        let mut ghz = Circuit::new(3);
+        ghz.add_instruction(Instruction::new(library.operation("sy").unwrap(), vec![0]))
+            .unwrap();
+        ghz.cnot(0, 1).cnot(1, 2);
+        library.define_decomposition("ghz", ghz).unwrap();
+        assert_eq!(library.names().collect::<Vec<_>>(), ["ghz", "sy"]);
+        assert!(library
+            .define_decomposition("measured", {
+                let mut measured = Circuit::new(1);
+                measured.measure(0, 0);
+                measured
+            })
+            .is_err());
+
+        Simulator::set_gate_library(library.clone());
+        let mut circuit = Circuit::with_clbits(3, 3);
+        circuit.custom("ghz", &[2, 1, 0]).measure(0, 0);
+        let mut initial_state = vec![Complex::new(0.0, 0.0); 8];
+        initial_state[0] = Complex::new(1.0, 0.0);
+        let state = Simulator::run(&circuit, &initial_state);
+        let ones = state.state[0].norm_sqr() + state.state[7].norm_sqr();
+        assert!((ones - 1.0).abs() < TOLERANCE);
+        assert!(circuit.draw().contains("ghz"));
+
+        // Exported by name with its definitions, and read back as the same custom gate
+        let source = qasm::to_qasm(&circuit, QasmVersion::V2).unwrap();
+        let sy_definition = source.find("gate sy a0 {").unwrap();
+        let ghz_definition = source
+            .find("gate ghz a0, a1, a2 { sy a0; cx a0, a1; cx a1, a2; }")
+            .unwrap();
+        assert!(sy_definition < ghz_definition);
+        assert!(source.contains("ghz q[2], q[1], q[0];"));
+        let parsed = qasm::parse(&source).unwrap();
+        assert_eq!(parsed.instructions()[0], circuit.instructions()[0]);
+
+        // The transpiler routes the expansion on a line, where the gate itself spans 3 qubits
+        let mut calibration = Calibration::new(3);
+        calibration
+            .add_coupling(0, 1, 0.01)
+            .add_coupling(1, 2, 0.01);
+        let routed = transpile(&circuit, &calibration, Placement::Naive).unwrap();
+        assert_eq!(routed.circuit.instructions()[0].operation.name(), "sy");
+        assert_eq!(routed.swaps, 0);
+
+        // The inverse keeps the name as `ghz_dg` and exports the inverted decomposition
+        let mut forward = Circuit::new(3);
+        forward.custom("ghz", &[2, 1, 0]);
+        let inverse = forward.inverse();
+        assert_eq!(inverse.instructions()[0].operation.name(), "ghz_dg");
+        assert!(inverse.draw().contains("ghz_dg"));
+        assert_eq!(inverse.inverse().instructions()[0].operation.name(), "ghz");
+        let expanded = library.expand(&inverse).unwrap();
+        assert_eq!(expanded.instructions()[0].operation, Operation::Cnot);
+        assert_eq!(expanded.instructions()[2].operation.name(), "sy_dg");
+        let source = qasm::to_qasm(&inverse, QasmVersion::V2).unwrap();
+        assert!(source.contains("gate ghz_dg a0, a1, a2 { cx a1, a2; cx a0, a1; sy_dg a0; }"));
+        let mut round_trip = forward.clone();
+        round_trip.append(&qasm::parse(&source).unwrap());
+        assert!(
+            quantum_simulator::analysis::circuit_equivalence(&round_trip, &Circuit::new(3))
+                .unwrap()
+        );
+
+        // Without the library the definitions are expanded inline and routing fails
+        Simulator::clear_gate_library();
+        assert!(Simulator::gate_library().names().next().is_none());
+        let inline = qasm::parse(&source).unwrap();
+        assert_eq!(inline.instructions()[1].operation, Operation::Cnot);
+        assert!(transpile(&circuit, &calibration, Placement::Naive).is_err());
+    }
+
+    #[test]
+    fn test_two_qubit_decomposition_uses_at_most_three_cnots() {
+        use num_complex::Complex;
+        use quantum_simulator::benchmarking::random_universal_circuit;
+        use quantum_simulator::decompose::{single_qubit, two_qubit};
+        use quantum_simulator::gates::{cnot, iswap, kron, rx, swap, Gate};
+        use quantum_simulator::optimize::{LowerUnitaries, PassManager};
+
+        // |tr(U†V)| / d is 1 exactly when V is U up to a global phase
+        let overlap = |u: &[Vec<Complex<f64>>], v: &[Vec<Complex<f64>>]| {
+            let trace: Complex<f64> = (0..u.len())
+                .flat_map(|i| (0..u.len()).map(move |j| (i, j)))
+                .map(|(i, j)| u[i][j].conj() * v[i][j])
+                .sum();
+            trace.norm() / u.len() as f64
+        };
+        let cnots = |circuit: &Circuit| {
+            circuit
+                .instructions()
+                .iter()
+                .filter(|i| i.operation == Operation::Cnot)
+                .count()
+        };
+
+        for seed in 0..20 {
+            let gate = Gate::new(random_universal_circuit(2, 6, seed).to_unitary(2));
+            let circuit = two_qubit(&gate).unwrap();
+            assert!(cnots(&circuit) <= 3);
+            assert!((overlap(&gate.matrix, &circuit.to_unitary(2)) - 1.0).abs() < 1e-9);
+
+            let single = Gate::new(random_universal_circuit(1, 4, seed).to_unitary(1));
+            let circuit = single_qubit(&single).unwrap();
+            assert!(circuit.instructions().len() <= 3);
+            assert!((overlap(&single.matrix, &circuit.to_unitary(1)) - 1.0).abs() < 1e-9);
+        }
+
+        // Special gates need fewer CNOTs: none for local gates, one for CNOT-like gates and two
+        // when the interaction has only two terms
+        let local = kron(&[rx(0.3), rx(1.1)]).unwrap();
+        let cases = [
+            (local, 0),
+            (cnot(1, 0, 2), 1),
+            (iswap(0, 1, 2), 2),
+            (swap(0, 1, 2), 3),
+        ];
+        for (gate, expected) in cases {
+            let circuit = two_qubit(&gate).unwrap();
+            assert_eq!(cnots(&circuit), expected);
+            assert!((overlap(&gate.matrix, &circuit.to_unitary(2)) - 1.0).abs() < 1e-9);
+        }
+        assert!(two_qubit(&rx(0.2)).is_err());
+
+        // The pass lowers every one- and two-qubit matrix gate of a circuit
+        let mut circuit = Circuit::new(3);
+        let gate = Gate::new(random_universal_circuit(2, 6, 42).to_unitary(2));
+        circuit.h(0);
+        circuit
+            .add_instruction(Instruction::new(Operation::Unitary(gate), vec![2, 0]))
+            .unwrap();
+        circuit
+            .add_instruction(Instruction::new(Operation::Unitary(rx(0.7)), vec![1]))
+            .unwrap();
+        let mut manager = PassManager::new();
+        manager.add_pass(LowerUnitaries);
+        let (lowered, _) = manager.run(&circuit);
+        assert!(!lowered
+            .instructions()
+            .iter()
+            .any(|i| matches!(i.operation, Operation::Unitary(_))));
+        assert!((overlap(&circuit.to_unitary(3), &lowered.to_unitary(3)) - 1.0).abs() < 1e-9);
+
+        // A matrix that is not unitary is copied through rather than synthesized
+        let mut scaled = Circuit::new(1);
+        let half = Gate::new(vec![
+            vec![Complex::new(0.5, 0.0), Complex::new(0.0, 0.0)],
+            vec![Complex::new(0.0, 0.0), Complex::new(0.5, 0.0)],
+        ]);
+        scaled
+            .add_instruction(Instruction::new(Operation::Unitary(half), vec![0]))
+            .unwrap();
+        assert_eq!(manager.run(&scaled).0, scaled);
+    }
+
+    #[test]
+    fn test_classical_shadows_estimate_circuit_observables() {
+        use quantum_simulator::analysis::classical_shadows;
+        use quantum_simulator::benchmarking::random_universal_circuit;
+        use quantum_simulator::variational::{Hamiltonian, PauliString};
+
+        let circuit = random_universal_circuit(4, 5, 21);
+        let state = Simulator::run(&circuit, &Qubit::zero_state(4).state);
+        let shadow = classical_shadows(&circuit, 3000, 4);
+        assert_eq!(shadow.snapshots().len(), 3000);
+        assert_eq!(shadow, classical_shadows(&circuit, 3000, 4));
+
+        // The 4σ intervals of low-weight Paulis cover the exact expectations
+        let paulis = ["Z0", "X1", "Y3", "Z0 Z1", "X2 Y3", "X0 Z2", "Z1 Z2 Z3"];
+        let covered = paulis
+            .iter()
+            .filter(|&&pauli| {
+                let pauli = PauliString::parse(pauli).unwrap();
+                let interval = shadow.expectation(&pauli).confidence_interval(4.0);
+                let exact = pauli.expectation(&state);
+                interval.lo <= exact && exact <= interval.hi
+            })
+            .count();
+        assert_eq!(covered, paulis.len());
+
+        // A weighted sum is estimated from the same snapshots
+        let hamiltonian = Hamiltonian::parse("0.5 Z0 Z1 - 1.5 X2 + Y3 Z0 + 0.25").unwrap();
+        let energy = shadow.observable(&hamiltonian);
+        let exact = hamiltonian.expectation(&state);
+        assert!((energy.value - exact).abs() < 4.0 * energy.standard_error);
+        assert!(energy.standard_error < 0.2);
+        let interval = energy.confidence_interval(0.0);
+        assert_eq!((interval.lo, interval.hi), (energy.value, energy.value));
+    }
+
+    #[test]
+    fn test_state_queries_read_probabilities_without_raw_amplitudes() {
+        let mut circuit = Circuit::new(4);
+        circuit.h(0).cnot(0, 3).ry(1, 0.8).x(2);
+        let state = Simulator::run(&circuit, &Qubit::zero_state(4).state);
+
+        // The probabilities agree with the amplitudes they are read from
+        let probabilities = state.probabilities();
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        for (index, probability) in probabilities.iter().enumerate() {
+            let amplitude = state.amplitude_of(index).unwrap();
+            assert!((amplitude.norm_sqr() - probability).abs() < 1e-15);
+        }
+        assert_eq!(state.amplitude_of(16), None);
+
+        // Bitstrings are written qubit 3 first, like the keys of counts
+        let cos2 = 0.4_f64.cos().powi(2);
+        assert!((state.prob_of("0100").unwrap() - 0.5 * cos2).abs() < 1e-12);
+        assert!((state.prob_of("1111").unwrap() - 0.5 * (1.0 - cos2)).abs() < 1e-12);
+        assert_eq!(state.prob_of("0000").unwrap(), 0.0);
+        assert!(matches!(
+            state.prob_of("01x0"),
+            Err(QuantumError::Parse { .. })
+        ));
+        assert!(matches!(
+            state.prob_of("010"),
+            Err(QuantumError::DimensionMismatch { .. })
+        ));
+
+        // The two equally likely |0100⟩ and |1101⟩ lead, then the less likely pair
+        let top = state.top_k_states(3);
+        let indices: Vec<usize> = top.iter().map(|&(index, _)| index).collect();
+        assert_eq!(indices, vec![0b0100, 0b1101, 0b0110]);
+        assert!(top.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(state.top_k_states(100).len(), 16);
+        assert!(state.top_k_states(0).is_empty());
+
+        // Qubits 0 and 3 are perfectly correlated and qubit 2 is always one
+        let marginal = state.marginal_probabilities(&[0, 3]).unwrap();
+        assert!((marginal[0b00] - 0.5).abs() < 1e-12 && (marginal[0b11] - 0.5).abs() < 1e-12);
+        assert_eq!(marginal[0b01] + marginal[0b10], 0.0);
+        assert!((state.marginal_probabilities(&[2]).unwrap()[1] - 1.0).abs() < 1e-12);
+        let nothing = state.marginal_probabilities(&[]).unwrap();
+        assert!(nothing.len() == 1 && (nothing[0] - 1.0).abs() < 1e-12);
+        assert!(matches!(
+            state.marginal_probabilities(&[1, 1]),
+            Err(QuantumError::DuplicateQubit { qubit: 1 })
+        ));
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn test_python_bindings_reject_invalid_qubits() {
+        use pyo3::prelude::*;
+        use quantum_simulator::python::quantum_simulator as module;
+
+        pyo3::append_to_inittab!(module);
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let qs = py.import_bound("quantum_simulator").unwrap();
+            let raises = |code: &str| {
+                let locals = pyo3::types::PyDict::new_bound(py);
+                locals.set_item("qs", &qs).unwrap();
+                py.run_bound(code, None, Some(&locals))
+                    .unwrap_err()
+                    .is_instance_of::<pyo3::exceptions::PyValueError>(py)
+            };
+            assert!(raises("qs.cnot(1, 1, 2)"));
+            assert!(raises("qs.cz(0, 2, 2)"));
+            assert!(raises("qs.swap(0, 1, 40)"));
+            assert!(raises("qs.Circuit(64)"));
+            assert!(raises("qs.Circuit(2).cnot(0, 0)"));
+
+            let matrix: Vec<Vec<num_complex::Complex<f64>>> = qs
+                .getattr("cnot")
+                .unwrap()
+                .call1((1, 0, 2))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(matrix, cnot(1, 0, 2).matrix.as_ref().clone());
+        });
+    }
+
+    #[test]
+    fn test_leakage_channel_reaches_leak_seep_equilibrium() {
+        use quantum_simulator::noise::{leakage, LeakagePreset};
+
+        // Start in |1⟩⟨1|: one application moves the leakage probability into |2⟩
+        let zero = Complex::new(0.0, 0.0);
+        let mut rho = vec![vec![zero; 3]; 3];
+        rho[1][1] = Complex::new(1.0, 0.0);
+        let channel = leakage(0.1, 0.2);
+        assert_eq!(channel.dimension(), 3);
+        assert!(channel.validate().is_ok());
+
+        let once = channel.apply(&rho);
+        assert!((once[1][1].re - 0.9).abs() < 1e-12);
+        assert!((once[2][2].re - 0.1).abs() < 1e-12);
+        assert_eq!(once[0][0], zero);
+
+        // Leakage and seepage balance once |2⟩ holds leakage / (leakage + seepage) of the population
+        for _ in 0..200 {
+            rho = channel.apply(&rho);
+        }
+        let trace: f64 = (0..3).map(|level| rho[level][level].re).sum();
+        assert!((trace - 1.0).abs() < 1e-12);
+        assert!((rho[2][2].re - 1.0 / 3.0).abs() < 1e-9);
+        assert!((rho[1][1].re - 2.0 / 3.0).abs() < 1e-9);
+
+        // A coherence between |0⟩ and |1⟩ decays with the no-jump amplitude of |1⟩
+        let half = Complex::new(0.5, 0.0);
+        let plus = vec![
+            vec![half, half, zero],
+            vec![half, half, zero],
+            vec![zero; 3],
+        ];
+        let decohered = channel.apply(&plus);
+        assert!((decohered[0][1].re - 0.5 * 0.9f64.sqrt()).abs() < 1e-12);
+        assert!((decohered[0][0].re - 0.5).abs() < 1e-12);
+
+        // CZ gates leak orders of magnitude more than single-qubit gates, and idle windows
+        // are where leaked population seeps back
+        let presets = [
+            LeakagePreset::TransmonSingleQubitGate,
+            LeakagePreset::TransmonCzGate,
+            LeakagePreset::TransmonIdle,
+        ];
+        for preset in presets {
+            assert!(preset.channel().validate().is_ok());
+        }
+        let (single_leak, single_seep) = LeakagePreset::TransmonSingleQubitGate.rates();
+        let (cz_leak, _) = LeakagePreset::TransmonCzGate.rates();
+        let (_, idle_seep) = LeakagePreset::TransmonIdle.rates();
+        assert!(cz_leak > 100.0 * single_leak);
+        assert!(idle_seep > single_seep);
+    }
+
+    #[test]
+    fn test_bloch_vector_tracks_each_qubit_of_a_product_state() {
+        let (theta, phi) = (1.1, 0.4);
+        let mut circuit = Circuit::new(3);
+        circuit.ry(0, theta).rz(0, phi).x(1).h(2).s(2);
+        let mut state = Qubit::zero_state(3);
+        circuit.run(&mut state);
+
+        // Each qubit lands on its own point of the sphere surface, addressed by index
+        let (x, y, z) = bloch_vector(&state, 0);
+        assert!((x - theta.sin() * phi.cos()).abs() < 1e-9);
+        assert!((y - theta.sin() * phi.sin()).abs() < 1e-9);
+        assert!((z - theta.cos()).abs() < 1e-9);
+        let (x, y, z) = bloch_vector(&state, 1);
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9 && (z + 1.0).abs() < 1e-9);
+        let (x, y, z) = bloch_vector(&state, 2);
+        assert!(x.abs() < 1e-9 && (y - 1.0).abs() < 1e-9 && z.abs() < 1e-9);
+
+        // Entangling q1 with q2 pulls both towards the centre and leaves q0 untouched
+        let mut entangler = Circuit::new(3);
+        entangler.cnot(2, 1);
+        entangler.run(&mut state);
+        let (x, y, z) = bloch_vector(&state, 1);
+        assert!((x * x + y * y + z * z).sqrt() < 1e-9);
+        let (x, _, z) = bloch_vector(&state, 0);
+        assert!((z - theta.cos()).abs() < 1e-9 && (x - theta.sin() * phi.cos()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_example_algorithm_pipelines_hold_with_a_seeded_rng() {
+        use quantum_simulator::algorithms::teleportation;
+        use std::f64::consts::PI;
+
+        let mut rng = StdRng::seed_from_u64(1275);
+
+        // bell_state: only correlated outcomes and one bit of entanglement
+        let mut bell = Circuit::new(2);
+        bell.h(0).cnot(0, 1);
+        let state = Simulator::run(&bell, &Qubit::zero_state(2).state);
+        let counts = state.sample_with_rng(1000, &mut rng);
+        assert_eq!(counts.get("00") + counts.get("11"), 1000);
+        assert!(counts.get("00") > 400 && counts.get("11") > 400);
+        assert!((entanglement_entropy(&state, &[0]) - 1.0).abs() < 1e-9);
+
+        // grover: the marked item dominates after the optimal number of iterations
+        let grover = Grover::new(Oracle::from_bitstrings(4, &["1011"]));
+        let counts = Simulator::sample_with_rng(
+            &grover.circuit(),
+            &Qubit::zero_state(4).state,
+            500,
+            &mut rng,
+        );
+        assert_eq!(counts.most_frequent().map(|(best, _)| best), Some("1011"));
+        assert!(counts.get("1011") > 450);
+
+        // qft: a basis state spreads uniformly and the inverse restores it
+        let input = Qubit::from_bitstring("101").unwrap().state;
+        let transformed = Simulator::run(&qft(3), &input);
+        assert!(transformed
+            .state
+            .iter()
+            .all(|amplitude| (amplitude.norm_sqr() - 1.0 / 8.0).abs() < 1e-12));
+        let mut round_trip = qft(3);
+        round_trip.append(&inverse_qft(3));
+        let restored = Simulator::run(&round_trip, &input);
+        assert!((restored.state[0b101].norm() - 1.0).abs() < 1e-12);
+
+        // qft: phase estimation reads an exactly representable phase of 3/8 as 6/16
+        let precision = 4;
+        let mut estimation = Circuit::new(precision + 1);
+        estimation
+            .x(precision)
+            .append(&phase_estimation(&phase(2.0 * PI * 3.0 / 8.0), precision));
+        let estimated = Simulator::run(&estimation, &Qubit::zero_state(precision + 1).state);
+        for _ in 0..20 {
+            assert_eq!(estimated.measure_with_rng(&mut rng) & 0b1111, 6);
+        }
+
+        // teleportation: q2 receives the prepared Bloch vector whatever q0 and q1 measure
+        let (theta, phi) = (1.1, 0.4);
+        let mut prepare = Circuit::new(1);
+        prepare.ry(0, theta).rz(0, phi);
+        let protocol = teleportation();
+        let mut circuit = Circuit::new(3);
+        circuit.append(&prepare).append(protocol.circuit());
+        let mut outcomes = HashMap::new();
+        for _ in 0..40 {
+            let mut qubit = Qubit::zero_state(3);
+            *outcomes
+                .entry(circuit.run_with_rng(&mut qubit, &mut rng))
+                .or_insert(0) += 1;
+            let (x, y, z) = bloch_vector(&qubit, 2);
+            assert!((x - theta.sin() * phi.cos()).abs() < 1e-9);
+            assert!((y - theta.sin() * phi.sin()).abs() < 1e-9);
+            assert!((z - theta.cos()).abs() < 1e-9);
+        }
+        assert_eq!(outcomes.len(), 4);
+
+        // noisy_vqe: the ansatz reaches the ZZ minimum, and depolarizing noise lifts it
+        let mut ansatz = Circuit::new(2);
+        ansatz.ry(0, PI).ry(1, 0.0).cnot(0, 1).ry(1, PI);
+        let zz = |rho: &DensityMatrix| {
+            rho.matrix[0][0].re - rho.matrix[1][1].re - rho.matrix[2][2].re + rho.matrix[3][3].re
+        };
+        let ideal =
+            Simulator::run_density_with_noise(&ansatz, &DensityMatrix::new(2), &NoiseModel::new());
+        let mut model = NoiseModel::new();
+        model.add_error(depolarizing(0.02));
+        let noisy = Simulator::run_density_with_noise(&ansatz, &DensityMatrix::new(2), &model);
+        assert!((zz(&ideal) + 1.0).abs() < 1e-9);
+        assert!(zz(&noisy) > zz(&ideal));
+    }
+
+    #[test]
+    fn test_bloch_trajectory_follows_each_gate() {
+        use quantum_simulator::debugger::Debugger;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).s(0).cnot(0, 1);
+        let initial_state = Qubit::zero_state(2).state;
+        let debugger = Debugger::new(&circuit, &initial_state, &NoiseModel::new(), 0);
+        let trajectory: Vec<Vec<(f64, f64, f64)>> = (0..=debugger.len())
+            .map(|position| {
+                let qubit = debugger.state_at(position).to_qubit();
+                (0..2).map(|index| bloch_vector(&qubit, index)).collect()
+            })
+            .collect();
+
+        // One point per gate plus the start: q0 goes +z → +x → +y, then both shrink to the centre
+        assert_eq!(trajectory.len(), circuit.instructions().len() + 1);
+        let close = |(x, y, z): (f64, f64, f64), expected: (f64, f64, f64)| {
+            (x - expected.0).abs() < 1e-9
+                && (y - expected.1).abs() < 1e-9
+                && (z - expected.2).abs() < 1e-9
+        };
+        assert!(close(trajectory[0][0], (0.0, 0.0, 1.0)));
+        assert!(close(trajectory[1][0], (1.0, 0.0, 0.0)));
+        assert!(close(trajectory[2][0], (0.0, 1.0, 0.0)));
+        assert!(close(trajectory[3][0], (0.0, 0.0, 0.0)));
+        assert!(close(trajectory[3][1], (0.0, 0.0, 0.0)));
+
+        // q1 stays at |0⟩ until the CNOT reaches it
+        assert!(trajectory[..3]
+            .iter()
+            .all(|points| close(points[1], (0.0, 0.0, 1.0))));
+    }
+
+    #[test]
+    fn test_debugger_recorded_on_a_worker_thread_matches_the_same_seed() {
+        use quantum_simulator::debugger::Debugger;
+        use quantum_simulator::explain::explain;
+
+        let mut circuit = Circuit::new(2);
+        circuit.h(0).cnot(0, 1).measure(0, 0).rx(1, 0.3);
+        let initial_state = Qubit::zero_state(2).state;
+        let mut noise_model = NoiseModel::new();
+        noise_model.add_error(depolarizing(0.3));
+
+        // The visualizer records its trajectory off the main thread; the seed alone decides it
+        let worker = {
+            let (circuit, initial_state, noise_model) =
+                (circuit.clone(), initial_state.clone(), noise_model.clone());
+            thread::spawn(move || {
+                let debugger = Debugger::new(&circuit, &initial_state, &noise_model, 12);
+                let explanations: Vec<String> = explain(&circuit, debugger.trace())
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                (debugger, explanations)
+            })
+        };
+        let (recorded, explanations) = worker.join().unwrap();
+        let local = Debugger::new(&circuit, &initial_state, &noise_model, 12);
+        assert_eq!(recorded.trace(), local.trace());
+        assert_eq!(recorded.len(), circuit.instructions().len());
+        assert_eq!(explanations.len(), circuit.instructions().len());
+        assert!(explanations[1].contains("cx on q0, q1"));
+
+        // Every recorded position converts back into a normalized state for the spheres
+        for position in 0..=recorded.len() {
+            let probabilities = recorded.state_at(position).to_qubit().probabilities();
+            assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_measuring_a_live_state_collapses_its_bloch_vectors() {
+        let mut circuit = Circuit::new(3);
+        circuit.h(0).cnot(0, 1).ry(2, 0.8);
+        let mut rng = StdRng::seed_from_u64(1281);
+
+        for _ in 0..10 {
+            let mut live = Simulator::run(&circuit, &Qubit::zero_state(3).state);
+
+            // Measuring one half of the Bell pair sends both halves to the same pole and
+            // leaves the unentangled q2 where it was
+            let outcome = live.measure_qubit_with_rng(0, &mut rng);
+            let pole = if outcome == 0 { 1.0 } else { -1.0 };
+            for index in 0..2 {
+                let (x, y, z) = bloch_vector(&live, index);
+                assert!(x.abs() < 1e-9 && y.abs() < 1e-9 && (z - pole).abs() < 1e-9);
+            }
+            let (x, _, z) = bloch_vector(&live, 2);
+            assert!((x - 0.8f64.sin()).abs() < 1e-9 && (z - 0.8f64.cos()).abs() < 1e-9);
+
+            // Measuring everything reads as a bitstring with q0 rightmost and leaves that
+            // basis state with certainty, so a repeat gives the same answer
+            let bits: String = live
+                .measure_all_with_rng(&mut rng)
+                .iter()
+                .rev()
+                .map(|bit| bit.to_string())
+                .collect();
+            assert!(bits.ends_with(&format!("{}{}", outcome, outcome)));
+            let index = usize::from_str_radix(&bits, 2).unwrap();
+            assert!((live.probabilities()[index] - 1.0).abs() < 1e-9);
+            assert_eq!(live.measure_all_with_rng(&mut rng).len(), 3);
+            assert!((live.probabilities()[index] - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_sampled_counts_line_up_with_probability_bars() {
+        let num_qubits = 3;
+        let mut circuit = Circuit::new(num_qubits);
+        circuit.ry(0, 1.0).x(2).h(1);
+        let live = Simulator::run(&circuit, &Qubit::zero_state(num_qubits).state);
+        let probabilities = live.probabilities();
+        let counts = live.sample_with_rng(4096, &mut StdRng::seed_from_u64(1281));
+        assert_eq!(counts.shots(), 4096);
+
+        // Bar `index` reads its count under the bitstring of the same index, q0 rightmost
+        let frequencies: Vec<f64> = (0..1 << num_qubits)
+            .map(|index| {
+                let bitstring = format!("{:0width$b}", index, width = num_qubits);
+                counts.get(&bitstring) as f64 / counts.shots() as f64
+            })
+            .collect();
+        assert!((frequencies.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        for (index, (&frequency, &probability)) in
+            frequencies.iter().zip(&probabilities).enumerate()
+        {
+            assert!(
+                (frequency - probability).abs() < 0.03,
+                "bar {}: sampled {} against {}",
+                index,
+                frequency,
+                probability
+            );
+            if probability == 0.0 {
+                assert_eq!(frequency, 0.0);
+            }
+        }
+
+        // q2 is always one, so only the upper half of the bars is ever filled
+        assert!(frequencies[..4].iter().all(|&frequency| frequency == 0.0));
+        let (best, _) = counts.most_frequent().unwrap();
+        assert!(best.starts_with('1') && best.ends_with('0'));
+    }
+
+    #[test]
+    fn test_circuit_built_slot_by_slot_matches_the_fluent_circuit() {
+        use quantum_simulator::analysis::circuit_equivalence;
+        use quantum_simulator::presets::PRESETS;
+
+        // A grid of time steps by qubits, as the editor stores it: a gate, the control of a
+        // CNOT naming its target, or nothing (CNOT targets are drawn but add no instruction)
+        enum Slot {
+            Empty,
+            Gate(Operation),
+            Control(usize),
+        }
+        let build = |grid: &[Vec<Slot>]| {
+            let mut circuit = Circuit::new(3);
+            for step in grid {
+                for (qubit, slot) in step.iter().enumerate() {
+                    match slot {
+                        Slot::Gate(operation) => circuit
+                            .add_instruction(Instruction::new(operation.clone(), vec![qubit]))
+                            .unwrap(),
+                        Slot::Control(target) => {
+                            circuit.cnot(qubit, *target);
+                        }
+                        Slot::Empty => {}
+                    }
+                }
+            }
+            circuit
+        };
+        let mut grid = vec![
+            vec![
+                Slot::Gate(Operation::H),
+                Slot::Gate(Operation::Ry(0.4)),
+                Slot::Gate(Operation::X),
+            ],
+            vec![Slot::Control(1), Slot::Empty, Slot::Gate(Operation::T)],
+            vec![
+                Slot::Empty,
+                Slot::Gate(Operation::Rz(0.7)),
+                Slot::Control(0),
+            ],
+        ];
+        let built = build(&grid);
+        assert_eq!(built.instructions().len(), 7);
+
+        // The grid reads column by column, which reorders only gates on different qubits
+        let mut fluent = Circuit::new(3);
+        fluent
+            .x(2)
+            .t(2)
+            .h(0)
+            .ry(1, 0.4)
+            .cnot(0, 1)
+            .rz(1, 0.7)
+            .cnot(2, 0);
+        assert!(circuit_equivalence(&built, &fluent).unwrap());
+
+        // Clearing a slot is an edit the simulation sees
+        grid[2][1] = Slot::Empty;
+        let edited = build(&grid);
+        assert_eq!(edited.instructions().len(), 6);
+        assert!(!circuit_equivalence(&edited, &fluent).unwrap());
+
+        // A slot off the register is refused rather than silently widening the circuit
+        assert!(matches!(
+            built
+                .clone()
+                .add_instruction(Instruction::new(Operation::H, vec![3])),
+            Err(QuantumError::QubitOutOfRange { .. })
+        ));
+
+        // Every preset the editor's menu offers builds
+        for preset in PRESETS {
+            let circuit = Circuit::preset(preset.name).unwrap();
+            assert!(circuit.num_qubits() >= 2, "{}", preset.name);
+            assert!(!circuit.instructions().is_empty(), "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn test_run_subcommand_reports_seeded_counts_as_json() {
+        use std::process::Command;
+
+        let dir = std::env::temp_dir().join(format!("run-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("bell.qasm");
+        std::fs::write(
+            &source,
+            "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\ncreg c[2];\n\
+             h q[0];\ncx q[0],q[1];\nmeasure q -> c;\n",
+        )
+        .unwrap();
+        let run = |extra: &[&str]| {
+            Command::new(env!("CARGO_BIN_EXE_quantum_simulator"))
+                .arg("run")
+                .arg(&source)
+                .args(["--shots", "200", "--seed", "3", "--output", "json"])
+                .args(extra)
+                .output()
+                .unwrap()
+        };
+
+        let output = run(&[]);
+        assert!(output.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(report["qubits"], 2);
+        assert_eq!(report["shots"], 200);
+        assert_eq!(report["seed"], 3);
+
+        // A Bell pair only ever reads 00 or 11, and the final state collapsed onto one of them
+        let counts = report["counts"].as_object().unwrap();
+        assert!(counts
+            .keys()
+            .all(|bitstring| bitstring == "00" || bitstring == "11"));
+        let total: u64 = counts.values().map(|count| count.as_u64().unwrap()).sum();
+        assert_eq!(total, 200);
+        let populated: Vec<usize> = report["state"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, amplitude)| amplitude[0].as_f64().unwrap().abs() > 1e-9)
+            .map(|(index, _)| index)
+            .collect();
+        assert!(populated == [0b00] || populated == [0b11]);
+
+        // Explained runs add one step object per instruction
+        let explained = run(&["--explain"]);
+        assert!(explained.status.success());
+        let report: serde_json::Value = serde_json::from_slice(&explained.stdout).unwrap();
+        let steps = report["steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 4);
+        assert!(steps.iter().all(serde_json::Value::is_object));
+
+        // The seed makes the run reproducible, also when written to a file
+        let results = dir.join("results.json");
+        assert!(run(&["--out", results.to_str().unwrap()]).status.success());
+        assert_eq!(std::fs::read(&results).unwrap(), output.stdout);
+
+        let missing = Command::new(env!("CARGO_BIN_EXE_quantum_simulator"))
+            .arg("run")
+            .arg(dir.join("missing.qasm"))
+            .output()
+            .unwrap();
+        assert_eq!(missing.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&missing.stderr).starts_with("error: cannot read"));
+
+        // A register too wide for a state vector is refused before anything is allocated
+        let wide = dir.join("wide.qasm");
+        std::fs::write(&wide, "OPENQASM 2.0;\nqreg q[40];\n").unwrap();
+        let refused = Command::new(env!("CARGO_BIN_EXE_quantum_simulator"))
+            .arg("run")
+            .arg(&wide)
+            .output()
+            .unwrap();
+        assert_eq!(refused.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&refused.stderr).contains("40 qubits"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lesson_targets_reach_each_lessons_goal() {
+        // Each step's target is OpenQASM appended to the targets before it, after a header
+        // declaring the lesson's register
+        let final_state = |text: &str| {
+            let lesson: toml::Table = text.parse().unwrap();
+            let num_qubits = lesson["qubits"].as_integer().unwrap() as usize;
+            assert!(lesson["title"]
+                .as_str()
+                .is_some_and(|title| !title.is_empty()));
+            let mut source = format!(
+                "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[{}];\n",
+                num_qubits
+            );
+            let mut previous = 0;
+            for step in lesson["steps"].as_array().unwrap() {
+                assert!(step["instruction"]
+                    .as_str()
+                    .is_some_and(|text| !text.is_empty()));
+                source.push_str(step["target"].as_str().unwrap());
+                source.push('\n');
+                let target = qasm::parse(&source).unwrap();
+                assert!(target.instructions().len() > previous);
+                previous = target.instructions().len();
+            }
+            let circuit = qasm::parse(&source).unwrap();
+            Simulator::run(&circuit, &Qubit::zero_state(num_qubits).state)
+        };
+
+        // bell: measuring always gives equal outcomes
+        let bell = final_state(include_str!("../lessons/bell.toml"));
+        let probabilities = bell.probabilities();
+        assert!((probabilities[0b00] - 0.5).abs() < 1e-9);
+        assert!((probabilities[0b11] - 0.5).abs() < 1e-9);
+        assert!((entanglement_entropy(&bell, &[0]) - 1.0).abs() < 1e-9);
+
+        // teleportation: q2 ends in Ry(π/2)|0⟩, the state q0 started in
+        let teleported = final_state(include_str!("../lessons/teleportation.toml"));
+        let (x, y, z) = bloch_vector(&teleported, 2);
+        assert!((x - 1.0).abs() < 1e-9 && y.abs() < 1e-9 && z.abs() < 1e-9);
+
+        // grover: one iteration finds |11⟩ with certainty
+        let found = final_state(include_str!("../lessons/grover.toml"));
+        assert!((found.probabilities()[0b11] - 1.0).abs() < 1e-9);
+    }
 }
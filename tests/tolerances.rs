@@ -0,0 +1,86 @@
+// Tolerances are process-wide, so the test that changes them runs in its own binary instead of
+// alongside the integration tests that rely on the defaults.
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex;
+    use quantum_simulator::circuit::Circuit;
+    use quantum_simulator::error::QuantumError;
+    use quantum_simulator::gates::Gate;
+    use quantum_simulator::noise::KrausChannel;
+    use quantum_simulator::qubit::Qubit;
+    use quantum_simulator::simulator::Simulator;
+    use quantum_simulator::tolerance::Tolerances;
+    use std::f64::consts::FRAC_1_SQRT_2;
+    use std::thread;
+
+    #[test]
+    fn test_tolerances_apply_globally() {
+        assert_eq!(Simulator::tolerances(), Tolerances::default());
+
+        // A state off unit norm by 1e-6 is rejected by default and accepted once relaxed
+        let drift = 1.0 + 1e-6;
+        let mut circuit = Circuit::new(1);
+        circuit.h(0);
+        let drifted = Qubit::from_state(vec![Complex::new(drift, 0.0), Complex::new(0.0, 0.0)]);
+        assert!(circuit.try_run(&mut drifted.clone()).is_err());
+
+        // An angle of 1e-10 is too large to drop by default but not under relaxed tolerances
+        let mut tiny = Circuit::new(1);
+        tiny.rz(0, 1e-10).x(0);
+        assert_eq!(tiny.optimized().0.gate_count(), 2);
+
+        Simulator::set_tolerances(Tolerances::default().scaled(1e3).unwrap()).unwrap();
+        assert_eq!(Simulator::tolerances().normalization, 1e-5);
+        assert!(circuit.try_run(&mut drifted.clone()).is_ok());
+        assert_eq!(tiny.optimized().0.gate_count(), 1);
+
+        // Tightened tolerances reject what the defaults accept
+        Simulator::set_tolerances(Tolerances::default().scaled(1e-6).unwrap()).unwrap();
+        let noisy = KrausChannel::new(vec![vec![
+            vec![Complex::new(1.0 + 1e-10, 0.0), Complex::new(0.0, 0.0)],
+            vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+        ]]);
+        assert!(noisy.validate().is_err());
+        Simulator::clear_tolerances();
+        assert!(noisy.validate().is_ok());
+        assert_eq!(Simulator::tolerances(), Tolerances::default());
+
+        // Negative or NaN thresholds are rejected and leave the tolerances in effect unchanged
+        assert!(Tolerances::default().scaled(f64::NAN).is_err());
+        let negative = Tolerances {
+            equality: -1e-9,
+            ..Tolerances::default()
+        };
+        assert!(matches!(
+            Simulator::set_tolerances(negative),
+            Err(QuantumError::Unsupported { .. })
+        ));
+        assert_eq!(Simulator::tolerances(), Tolerances::default());
+
+        // Dirac notation drops amplitudes below the equality tolerance in effect
+        let nearly_zero = Qubit::from_state(vec![Complex::new(1.0, 0.0), Complex::new(1e-7, 0.0)]);
+        assert!(nearly_zero.to_dirac().contains("|1⟩"));
+        Simulator::set_tolerances(Tolerances::default().scaled(1e3).unwrap()).unwrap();
+        assert!(!nearly_zero.to_dirac().contains("|1⟩"));
+
+        // Other threads see the tolerances in effect, and the defaults once they are cleared
+        let worker = thread::spawn(move || circuit.try_run(&mut drifted.clone()).is_ok());
+        assert!(worker.join().unwrap());
+        Simulator::clear_tolerances();
+        assert_eq!(
+            thread::spawn(Simulator::tolerances).join().unwrap(),
+            Tolerances::default()
+        );
+
+        // Explicit tolerances apply regardless of those in effect
+        assert!(noisy
+            .validate_with(&Tolerances::default().scaled(1e-6).unwrap())
+            .is_err());
+        // A Hadamard with entries rounded to four digits is unitary only to about 1e-4
+        let h = Complex::new((FRAC_1_SQRT_2 * 1e4).round() / 1e4, 0.0);
+        let rounded = vec![vec![h, h], vec![h, -h]];
+        assert!(Gate::from_unitary(rounded.clone()).is_err());
+        let relaxed = Tolerances::default().scaled(1e7).unwrap();
+        assert!(Gate::try_new(rounded, Some(relaxed.unitarity)).is_ok());
+    }
+}